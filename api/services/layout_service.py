@@ -11,6 +11,19 @@ from . import get_layout_client, LayoutOptions
 logger = logging.getLogger(__name__)
 
 
+def _link_with_polyline(link_id: str, row) -> Dict[str, Any]:
+    """Связь для клиента; маршрут из свойств path_x/path_y связи (воркер укладки) — в polyline"""
+    link: Dict[str, Any] = {
+        "id": link_id,
+        "source_id": str(row[0]),
+        "target_id": str(row[1]),
+    }
+    path_x, path_y = row[2], row[3]
+    if path_x and path_y and len(path_x) == len(path_y):
+        link["polyline"] = [[float(x), float(y)] for x, y in zip(path_x, path_y)]
+    return link
+
+
 class LayoutService:
     """Сервис для работы с укладкой графов"""
     
@@ -306,18 +319,14 @@ class LayoutService:
             # Запрос всех связей
             links_query = """
             MATCH (s:Article)-[r:BIBLIOGRAPHIC_LINK]->(t:Article)
-            RETURN s.uid as source_id, t.uid as target_id
+            RETURN s.uid as source_id, t.uid as target_id, r.path_x as path_x, r.path_y as path_y
             """
             links_result, _ = db.cypher_query(links_query)
             
             links: list[dict] = []
             for row in links_result:
                 link_id = f"{row[0]}-{row[1]}"  # Генерируем ID из source и target
-                links.append({
-                    "id": link_id,
-                    "source_id": str(row[0]),
-                    "target_id": str(row[1]),
-                })
+                links.append(_link_with_polyline(link_id, row))
 
             logger.info(f"Loaded {len(blocks)} blocks and {len(links)} links")
 
@@ -428,19 +437,13 @@ class LayoutService:
             links_query = """
             MATCH (s:Article)-[r:BIBLIOGRAPHIC_LINK]->(t:Article)
             WHERE s.uid IN $ids OR t.uid IN $ids
-            RETURN s.uid as source_id, t.uid as target_id
+            RETURN s.uid as source_id, t.uid as target_id, r.path_x as path_x, r.path_y as path_y
             """
             links_result, _ = db.cypher_query(links_query, {"ids": list(selected_ids)})
             links_for_layout: list[dict] = []
             for row in links_result:
                 link_id = f"{row[0]}-{row[1]}"  # Генерируем ID из source и target
-                links_for_layout.append(
-                    {
-                        "id": link_id,
-                        "source_id": str(row[0]),
-                        "target_id": str(row[1]),
-                    }
-                )
+                links_for_layout.append(_link_with_polyline(link_id, row))
 
             # Загружаем целевые статьи, которые не попали в текущую страницу, но имеют связи
             target_ids = set()
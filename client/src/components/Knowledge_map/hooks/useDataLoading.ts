@@ -151,8 +151,6 @@ const parsePolylineCandidate = (candidate: unknown): PolylinePoint[] | undefined
 
 const extractPolylineFromMetadata = (
   metadata: Record<string, unknown> | undefined,
-  sourceId: string,
-  targetId: string,
 ): PolylinePoint[] | undefined => {
   if (!metadata) {
     return undefined;
@@ -160,63 +158,7 @@ const extractPolylineFromMetadata = (
 
   const directKeys = ['polyline', 'poly_line', 'points', 'path', 'edge_path', 'edgePath'];
   for (const key of directKeys) {
-    const directCandidate = metadata[key];
-    const parsed = parsePolylineCandidate(directCandidate);
-    if (parsed) {
-      return parsed;
-    }
-  }
-
-  const keyVariants = [
-    `${sourceId}->${targetId}`,
-    `${sourceId}:${targetId}`,
-    `${sourceId}|${targetId}`,
-    `${sourceId},${targetId}`,
-  ];
-  const mapKeys = ['edge_paths', 'edgePaths', 'paths', 'polyline_map'];
-
-  for (const key of mapKeys) {
-    if (!(key in metadata)) {
-      continue;
-    }
-    let mapCandidate = metadata[key];
-    if (typeof mapCandidate === 'string') {
-      try {
-        mapCandidate = JSON.parse(mapCandidate);
-      } catch {
-        continue;
-      }
-    }
-
-    if (Array.isArray(mapCandidate)) {
-      const parsed = parsePolylineCandidate(mapCandidate);
-      if (parsed) {
-        return parsed;
-      }
-    } else if (mapCandidate && typeof mapCandidate === 'object') {
-      const mapObject = mapCandidate as Record<string, unknown>;
-      for (const variantKey of keyVariants) {
-        const candidate = mapObject[variantKey];
-        const parsed = parsePolylineCandidate(candidate);
-        if (parsed) {
-          return parsed;
-        }
-      }
-
-      const nested = mapObject[sourceId];
-      if (nested && typeof nested === 'object') {
-        const nestedMap = nested as Record<string, unknown>;
-        const parsed = parsePolylineCandidate(nestedMap[targetId]);
-        if (parsed) {
-          return parsed;
-        }
-      }
-    }
-  }
-
-  if ('parameters' in metadata && metadata.parameters && typeof metadata.parameters === 'object') {
-    const nested = metadata.parameters as Record<string, unknown>;
-    const parsed = extractPolylineFromMetadata(nested, sourceId, targetId);
+    const parsed = parsePolylineCandidate(metadata[key]);
     if (parsed) {
       return parsed;
     }
@@ -225,21 +167,26 @@ const extractPolylineFromMetadata = (
   return undefined;
 };
 
+// Маршрут связи воркер укладки хранит в свойствах связи path_x/path_y,
+// API отдаёт его полем polyline
 const extractPolylineFromLink = (
   apiLink: api.Link,
   metadata: Record<string, unknown> | undefined,
 ): PolylinePoint[] | undefined => {
-  const fromMetadata = extractPolylineFromMetadata(metadata, apiLink.source_id, apiLink.target_id);
-  if (fromMetadata) {
-    return fromMetadata;
-  }
-
-  const direct = parsePolylineCandidate((apiLink as unknown as Record<string, unknown>).polyline);
+  const direct = parsePolylineCandidate(apiLink.polyline);
   if (direct) {
     return direct;
   }
 
-  return undefined;
+  const { path_x: xs, path_y: ys } = apiLink;
+  if (Array.isArray(xs) && Array.isArray(ys) && xs.length === ys.length) {
+    const parsed = parsePolylineCandidate(xs.map((x, index) => [x, ys[index]]));
+    if (parsed) {
+      return parsed;
+    }
+  }
+
+  return extractPolylineFromMetadata(metadata);
 };
 
 const convertApiLinkToLinkData = (apiLink: api.Link): LinkData => {
@@ -253,37 +200,7 @@ const convertApiLinkToLinkData = (apiLink: api.Link): LinkData => {
 
   const parsedMetadata = normalizeMetadata(apiLink.metadata);
   const polyline = extractPolylineFromLink(apiLink, parsedMetadata);
-  const metadataCopy = parsedMetadata ? { ...parsedMetadata } : undefined;
-  if (metadataCopy && 'edge_paths' in metadataCopy) {
-    delete (metadataCopy as Record<string, unknown>).edge_paths;
-  }
-  if (metadataCopy && 'parameters' in metadataCopy) {
-    const paramsValue = metadataCopy.parameters;
-    if (typeof paramsValue === 'string') {
-      try {
-        const parsedParams = JSON.parse(paramsValue);
-        if (parsedParams && typeof parsedParams === 'object') {
-          const paramsCopy = { ...(parsedParams as Record<string, unknown>) };
-          if ('edge_paths' in paramsCopy) {
-            delete paramsCopy.edge_paths;
-          }
-          metadataCopy.parameters = Object.keys(paramsCopy).length > 0 ? paramsCopy : undefined;
-        }
-      } catch {
-        // ignore parsing errors
-      }
-    } else if (paramsValue && typeof paramsValue === 'object') {
-      const paramsCopy = { ...(paramsValue as Record<string, unknown>) };
-      if ('edge_paths' in paramsCopy) {
-        delete paramsCopy.edge_paths;
-      }
-      metadataCopy.parameters = Object.keys(paramsCopy).length > 0 ? paramsCopy : undefined;
-    }
-    if (metadataCopy.parameters === undefined) {
-      delete metadataCopy.parameters;
-    }
-  }
-  const cleanedMetadata = metadataCopy && Object.keys(metadataCopy).length > 0 ? metadataCopy : undefined;
+  const cleanedMetadata = parsedMetadata && Object.keys(parsedMetadata).length > 0 ? parsedMetadata : undefined;
 
   return {
     id: linkId,
@@ -41,6 +41,8 @@ export interface Link {
   target_id: string;
     metadata?: Record<string, any>;
     polyline?: unknown;
+    path_x?: number[];
+    path_y?: number[];
 }
 
 export interface Level {
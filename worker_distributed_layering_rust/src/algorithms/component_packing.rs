@@ -0,0 +1,462 @@
+/*!
+# Укладка по компонентам связности
+
+Несвязные компоненты графа укладываются независимо (каждая своим экземпляром
+`OptimalVertexPlacer`, опционально параллельно) и затем упаковываются
+слева направо с настраиваемым зазором.
+
+Компоненты упорядочиваются по убыванию размера, при равенстве — по
+наименьшему ID вершины, поэтому результат детерминирован. При упаковке
+сдвигаются и координата `x`, и номер слоя: пара (layer, level) остаётся
+уникальной для всей укладки.
+
+Для тайлового рендеринга порядок по размеру неудобен: стоит компоненте
+вырасти, и все соседние сдвигаются. Поэтому вместо упаковки подряд можно
+задать сетку слотов фиксированного размера (слоты раздаются по возрастанию
+якоря компоненты) и/или явные начала отдельных компонент. Якорь компоненты —
+её наименьший ID вершины, он не меняется при росте компоненты.
+
+Компонента с вершинами, закреплёнными в заданных координатах
+(`PlacementConfig::anchors`), не сдвигается вовсе, иначе закреплённые вершины
+ушли бы со своих мест.
+*/
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::data_structures::{Graph, GraphBuilder};
+use crate::generated::LayoutOptions;
+use crate::neo4j::VertexPosition;
+use super::vertex_placement::{OptimalVertexPlacer, PlacementStats};
+
+/// Маршруты связей: (source, target) -> точки ломаной
+pub type EdgePaths = HashMap<(String, String), Vec<(f32, f32)>>;
+
+/// Параметры укладки по компонентам
+#[derive(Debug, Clone)]
+pub struct ComponentPackingOptions {
+    /// Укладывать компоненты независимо
+    pub enabled: bool,
+
+    /// Горизонтальный зазор между компонентами (пиксели)
+    pub gap: f32,
+
+    /// Укладывать компоненты параллельно
+    pub parallel: bool,
+
+    /// Размещение компонент: подряд или по сетке слотов
+    pub slots: ComponentSlots,
+
+    /// Явные начала (x, y) компонент по якорю; имеют приоритет над `slots`
+    pub origins: HashMap<String, (f32, f32)>,
+
+    /// Размер слота (ширина, высота) для сетки из запроса, в котором размер
+    /// не задан: незаданное поле proto приходит нулём
+    pub cell_size: (f32, f32),
+}
+
+impl Default for ComponentPackingOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gap: 200.0,
+            parallel: true,
+            slots: ComponentSlots::Sequential,
+            origins: HashMap::new(),
+            cell_size: (4000.0, 4000.0),
+        }
+    }
+}
+
+impl ComponentPackingOptions {
+    /// Параметры с учётом запроса: сетка и начала из запроса заменяют настроенные
+    ///
+    /// Размер слота 0 (или меньше) означает «не задан» и берётся из `cell_size`.
+    pub fn with_request(&self, options: &LayoutOptions) -> Self {
+        let mut packing = self.clone();
+        if options.component_grid_columns > 0 {
+            let or_default = |size: f32, default: f32| if size > 0.0 { size } else { default };
+            packing.slots = ComponentSlots::Grid {
+                columns: options.component_grid_columns as usize,
+                cell_width: or_default(options.component_cell_width, self.cell_size.0),
+                cell_height: or_default(options.component_cell_height, self.cell_size.1),
+            };
+        }
+        packing.origins.extend(
+            options
+                .component_origins
+                .iter()
+                .map(|(anchor, origin)| (anchor.clone(), (origin.x, origin.y))),
+        );
+        packing
+    }
+}
+
+/// Размещение упакованных компонент
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ComponentSlots {
+    /// Слева направо по убыванию размера, с зазором `gap`
+    #[default]
+    Sequential,
+
+    /// Сетка слотов `cell_width` x `cell_height` по `columns` в ряд;
+    /// компонента i (по возрастанию якоря) занимает слот (i % columns, i / columns)
+    Grid {
+        columns: usize,
+        cell_width: f32,
+        cell_height: f32,
+    },
+}
+
+impl ComponentSlots {
+    /// Начало слота с номером `index` (None для `Sequential`)
+    fn origin(&self, index: usize) -> Option<(f32, f32)> {
+        match *self {
+            ComponentSlots::Sequential => None,
+            ComponentSlots::Grid { columns, cell_width, cell_height } => {
+                let columns = columns.max(1);
+                let column = (index % columns) as f32;
+                let row = (index / columns) as f32;
+                Some((column * cell_width, row * cell_height))
+            }
+        }
+    }
+}
+
+/// Результат укладки одной компоненты
+#[derive(Debug, Clone)]
+pub struct ComponentLayout {
+    /// Наименьший ID вершины компоненты
+    pub anchor: String,
+    pub positions: Vec<VertexPosition>,
+    pub edge_paths: EdgePaths,
+    pub stats: PlacementStats,
+}
+
+/// Разбиение графа на подграфы по компонентам слабой связности
+///
+/// Атрибуты вершин переходят в подграфы.
+/// Возвращает подграфы в детерминированном порядке: по убыванию числа вершин,
+/// затем по наименьшему ID вершины.
+pub fn split_components(graph: &mut Graph) -> Result<Vec<Graph>> {
+    let components: Vec<Vec<usize>> = graph.get_connected_components().to_vec();
+
+    let mut keyed: Vec<(usize, String, Graph)> = Vec::with_capacity(components.len());
+    for component in components {
+        // Self-loops и параллельные связи переходят в компоненту как есть
+        let mut builder = GraphBuilder::new().allow_self_loops(true).allow_parallel_edges(true);
+        let mut min_id: Option<&String> = None;
+
+        for &idx in &component {
+            let Some(vertex_id) = graph.vertex_id(idx) else { continue };
+            if min_id.map_or(true, |current| vertex_id < current) {
+                min_id = Some(vertex_id);
+            }
+            builder.add_vertex(vertex_id.clone());
+            if let Some(vertex) = graph.vertex_handle(vertex_id) {
+                for (key, value) in graph.vertex_attributes(vertex) {
+                    builder.set_attribute(vertex_id, key, value.clone());
+                }
+            }
+
+            for weight in graph.multi_edge_weights(vertex_id, vertex_id) {
+                builder.add_edge(vertex_id.clone(), vertex_id.clone(), weight)?;
+            }
+            if let Some(targets) = graph.get_outgoing_edges(vertex_id) {
+                for target in targets {
+                    for weight in graph.multi_edge_weights(vertex_id, target) {
+                        builder.add_edge(vertex_id.clone(), target.clone(), weight)?;
+                    }
+                }
+            }
+        }
+
+        let min_id = min_id.cloned().unwrap_or_default();
+        keyed.push((component.len(), min_id, builder.build()?));
+    }
+
+    keyed.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    Ok(keyed.into_iter().map(|(_, _, subgraph)| subgraph).collect())
+}
+
+/// Независимая укладка каждой компоненты
+///
+/// Каждой компоненте достаётся копия `placer` с его настройками. При `parallel`
+/// компоненты обрабатываются отдельными задачами tokio (кроме детерминированного
+/// планировщика, см. `scheduler`).
+pub async fn layout_components(
+    components: Vec<Graph>,
+    placer: &OptimalVertexPlacer,
+    parallel: bool,
+) -> Result<Vec<ComponentLayout>> {
+    let mut layouts = Vec::with_capacity(components.len());
+
+    if parallel && components.len() > 1 && !super::scheduler::ENABLED {
+        let mut handles = Vec::with_capacity(components.len());
+        for component in components {
+            let mut component_placer = placer.clone();
+            handles.push(tokio::spawn(async move {
+                let (positions, edge_paths) = component_placer
+                    .place_vertices(&component, &[])
+                    .await?;
+                Ok::<_, anyhow::Error>(ComponentLayout {
+                    anchor: anchor(&component),
+                    positions,
+                    edge_paths,
+                    stats: component_placer.get_stats().clone(),
+                })
+            }));
+        }
+        for handle in handles {
+            layouts.push(handle.await??);
+        }
+    } else {
+        for component in components {
+            let mut component_placer = placer.clone();
+            let (positions, edge_paths) = component_placer
+                .place_vertices(&component, &[])
+                .await?;
+            layouts.push(ComponentLayout {
+                anchor: anchor(&component),
+                positions,
+                edge_paths,
+                stats: component_placer.get_stats().clone(),
+            });
+        }
+    }
+
+    Ok(layouts)
+}
+
+/// Якорь компоненты: наименьший ID вершины
+fn anchor(component: &Graph) -> String {
+    component.vertices().min().cloned().unwrap_or_default()
+}
+
+/// Упаковка уложенных компонент
+///
+/// При `ComponentSlots::Sequential` компонента i сдвигается по `x` на суммарную
+/// ширину предыдущих компонент плюс `gap`. При сетке левый верхний угол
+/// компоненты ставится в начало её слота, а компонента из `origins` — в заданную
+/// точку. Слои в любом случае сдвигаются на суммарное количество слоёв
+/// предыдущих компонент. Маршруты связей сдвигаются вместе с вершинами.
+pub fn pack_components(
+    mut layouts: Vec<ComponentLayout>,
+    options: &ComponentPackingOptions,
+    block_width: f32,
+    block_height: f32,
+) -> ComponentLayout {
+    let mut positions = Vec::new();
+    let mut edge_paths = EdgePaths::new();
+    let mut stats = PlacementStats::new();
+
+    // Слоты сетки не должны зависеть от размеров компонент
+    if options.slots != ComponentSlots::Sequential {
+        layouts.sort_by(|a, b| a.anchor.cmp(&b.anchor));
+    }
+
+    let mut x_offset = 0.0f32;
+    let mut layer_offset = 0i32;
+    let mut slot_index = 0usize;
+    let mut overflowing = 0usize;
+    let mut total_width = 0.0f32;
+    let mut total_height = 0.0f32;
+
+    for layout in layouts {
+        if layout.positions.is_empty() {
+            continue;
+        }
+
+        let min_x = layout.positions.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let max_x = layout.positions.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = layout.positions.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_y = layout.positions.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        let min_layer = layout.positions.iter().map(|p| p.layer).min().unwrap_or(0);
+        let max_layer = layout.positions.iter().map(|p| p.layer).max().unwrap_or(0);
+
+        let (dx, dy, sequential) = match options.origins.get(&layout.anchor) {
+            _ if layout.stats.anchored_vertices > 0 => (0.0, 0.0, false),
+            Some(&(x, y)) => (x - min_x, y - min_y, false),
+            None => match options.slots.origin(slot_index) {
+                Some((x, y)) => {
+                    slot_index += 1;
+                    if let ComponentSlots::Grid { cell_width, cell_height, .. } = options.slots {
+                        if max_x - min_x + block_width > cell_width || max_y - min_y + block_height > cell_height {
+                            overflowing += 1;
+                        }
+                    }
+                    (x - min_x, y - min_y, false)
+                }
+                None => (x_offset - min_x, 0.0, true),
+            },
+        };
+        let dlayer = layer_offset - min_layer;
+
+        for mut position in layout.positions {
+            position.x += dx;
+            position.y += dy;
+            position.layer += dlayer;
+            positions.push(position);
+        }
+        for (edge, points) in layout.edge_paths {
+            let shifted = points.into_iter().map(|(x, y)| (x + dx, y + dy)).collect();
+            edge_paths.insert(edge, shifted);
+        }
+
+        stats.vertices_placed += layout.stats.vertices_placed;
+        stats.layers_used += layout.stats.layers_used;
+        stats.max_vertices_in_layer = stats.max_vertices_in_layer.max(layout.stats.max_vertices_in_layer);
+        stats.crossings_before += layout.stats.crossings_before;
+        stats.crossings_after += layout.stats.crossings_after;
+        stats.overlaps_repaired += layout.stats.overlaps_repaired;
+        stats.anchored_vertices += layout.stats.anchored_vertices;
+        stats.approximate_layers += layout.stats.approximate_layers;
+        stats.edges_detoured += layout.stats.edges_detoured;
+        stats.channel_shifts += layout.stats.channel_shifts;
+        stats.path_points_removed += layout.stats.path_points_removed;
+        // Верхняя оценка: параллельные компоненты держат свои арены одновременно
+        stats.arena_peak_bytes += layout.stats.arena_peak_bytes;
+
+        if sequential {
+            x_offset += (max_x - min_x) + block_width + options.gap;
+        }
+        layer_offset += max_layer - min_layer + 1;
+        total_width = total_width.max(max_x + dx + block_width);
+        total_height = total_height.max(max_y + dy + block_height);
+    }
+
+    if overflowing > 0 {
+        tracing::warn!("⚠️ {} компонент не помещаются в слоты сетки и перекрывают соседние", overflowing);
+    }
+
+    if stats.layers_used > 0 {
+        stats.avg_vertices_per_layer = stats.vertices_placed as f32 / stats.layers_used as f32;
+    }
+    stats.total_width = total_width;
+    stats.total_height = total_height;
+
+    ComponentLayout {
+        positions,
+        edge_paths,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, layer: i32, level: i32) -> VertexPosition {
+        VertexPosition {
+            article_id: id.to_string(),
+            layer,
+            level,
+            x: layer as f32 * 240.0,
+            y: level as f32 * 130.0,
+        }
+    }
+
+    fn layout(positions: Vec<VertexPosition>) -> ComponentLayout {
+        let mut stats = PlacementStats::new();
+        stats.vertices_placed = positions.len();
+        stats.layers_used = positions.iter().map(|p| p.layer).max().unwrap_or(0) as usize + 1;
+        ComponentLayout {
+            anchor: positions.iter().map(|p| p.article_id.clone()).min().unwrap_or_default(),
+            positions,
+            edge_paths: EdgePaths::new(),
+            stats,
+        }
+    }
+
+    #[test]
+    fn test_split_components_orders_by_size() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("X".to_string(), "Y".to_string(), 1.0)?;
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0)?;
+        let mut graph = builder.build()?;
+
+        let components = split_components(&mut graph)?;
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].vertex_count(), 3);
+        assert_eq!(components[0].edge_count(), 2);
+        assert!(components[1].contains_edge("X", "Y"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_component_with_anchors_is_not_moved() {
+        let first = layout(vec![position("A", 0, 0), position("B", 1, 0), position("C", 1, 1)]);
+        let mut second = layout(vec![position("X", 0, 0), position("Y", 1, 0)]);
+        second.stats.anchored_vertices = 1;
+
+        let packed = pack_components(vec![first, second], &ComponentPackingOptions::default(), 160.0, 80.0);
+        let find = |id: &str| packed.positions.iter().find(|p| p.article_id == id).unwrap();
+
+        // Координаты закреплённой компоненты не меняются, слои по-прежнему уникальны
+        assert_eq!((find("X").x, find("Y").x), (0.0, 240.0));
+        assert_eq!(find("X").layer, 2);
+        assert_eq!(packed.stats.anchored_vertices, 1);
+    }
+
+    #[test]
+    fn test_pack_components_does_not_overlap() {
+        let first = layout(vec![position("A", 0, 0), position("B", 1, 0), position("C", 1, 1)]);
+        let second = layout(vec![position("X", 0, 0), position("Y", 1, 0)]);
+
+        let packed = pack_components(vec![first, second], &ComponentPackingOptions::default(), 160.0, 80.0);
+        let x_of = |id: &str| packed.positions.iter().find(|p| p.article_id == id).unwrap();
+
+        // Вторая компонента начинается после первой (240 + 160) с зазором 200
+        assert_eq!(x_of("X").x, 600.0);
+        assert_eq!(x_of("X").layer, 2);
+        assert_eq!(x_of("Y").layer, 3);
+        assert_eq!(packed.stats.vertices_placed, 5);
+        assert_eq!(packed.stats.layers_used, 4);
+    }
+
+    #[test]
+    fn test_grid_slots_do_not_depend_on_component_size() {
+        let options = ComponentPackingOptions {
+            slots: ComponentSlots::Grid { columns: 2, cell_width: 1000.0, cell_height: 500.0 },
+            origins: HashMap::from([("P".to_string(), (5000.0, 0.0))]),
+            ..ComponentPackingOptions::default()
+        };
+        let small = || layout(vec![position("A", 0, 0)]);
+        let large = || layout(vec![position("M", 0, 0), position("N", 1, 0), position("O", 2, 1)]);
+        let pinned = || layout(vec![position("P", 0, 0), position("Q", 1, 0)]);
+
+        // Порядок (и размеры) на входе меняются, слоты — нет
+        for layouts in [vec![large(), small(), pinned()], vec![pinned(), small(), large()]] {
+            let packed = pack_components(layouts, &options, 160.0, 80.0);
+            let at = |id: &str| {
+                let p = packed.positions.iter().find(|p| p.article_id == id).unwrap();
+                (p.x, p.y)
+            };
+            assert_eq!(at("A"), (0.0, 0.0));
+            assert_eq!(at("M"), (1000.0, 0.0));
+            assert_eq!(at("O"), (1480.0, 130.0));
+            assert_eq!(at("P"), (5000.0, 0.0));
+            assert_eq!(packed.stats.total_width, 5400.0);
+        }
+    }
+
+    #[test]
+    fn test_request_grid_without_cell_size_uses_configured_size() {
+        let configured = ComponentPackingOptions {
+            cell_size: (1200.0, 600.0),
+            ..ComponentPackingOptions::default()
+        };
+        let request = LayoutOptions {
+            component_grid_columns: 3,
+            component_cell_height: 900.0,
+            ..LayoutOptions::default()
+        };
+
+        let packing = configured.with_request(&request);
+        assert_eq!(
+            packing.slots,
+            ComponentSlots::Grid { columns: 3, cell_width: 1200.0, cell_height: 900.0 }
+        );
+    }
+}
@@ -0,0 +1,413 @@
+/*!
+# Разрыв циклов перед назначением слоёв
+
+Реальные данные цитирования из Neo4j содержат циклы (взаимные ссылки,
+ошибки метаданных), а топологическая сортировка требует DAG.
+
+## Алгоритм
+
+1. **SCC**: Итеративный алгоритм Тарьяна находит сильно связные компоненты — O(V + E)
+2. **Feedback arc set**: Внутри каждой нетривиальной компоненты жадный алгоритм
+   Eades–Lin–Smyth строит порядок вершин, минимизирующий число обратных связей — O(E log V)
+3. **Разрыв**: Обратные связи разворачиваются (или удаляются) — граф становится ацикличным
+
+Связи между разными компонентами никогда не образуют цикл и не затрагиваются.
+*/
+
+use crate::neo4j::GraphEdge;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Способ разрыва обратных связей
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleBreakStrategy {
+    /// Развернуть связь (сохраняет связность графа)
+    Reverse,
+
+    /// Удалить связь
+    Remove,
+}
+
+impl Default for CycleBreakStrategy {
+    fn default() -> Self {
+        CycleBreakStrategy::Reverse
+    }
+}
+
+/// Результат разрыва циклов
+#[derive(Debug, Clone)]
+pub struct CycleBreakResult {
+    /// Связи ацикличного графа
+    pub edges: Vec<GraphEdge>,
+
+    /// Разорванные связи в исходном направлении (source, target)
+    pub broken_edges: Vec<(String, String)>,
+
+    /// Количество нетривиальных сильно связных компонент (с циклами)
+    pub cyclic_components: usize,
+}
+
+impl CycleBreakResult {
+    /// Были ли в графе циклы
+    pub fn had_cycles(&self) -> bool {
+        !self.broken_edges.is_empty()
+    }
+}
+
+/// Сильно связные компоненты (итеративный алгоритм Тарьяна)
+///
+/// Возвращает компоненты в обратном топологическом порядке конденсации.
+pub fn strongly_connected_components(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = adjacency.len();
+    let mut index = vec![usize::MAX; n];
+    let mut lowlink = vec![0usize; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0usize;
+
+    for root in 0..n {
+        if index[root] != usize::MAX {
+            continue;
+        }
+
+        // Стек вызовов: (вершина, позиция следующего соседа)
+        let mut call_stack = vec![(root, 0usize)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (v, ref mut next_neighbor)) = call_stack.last_mut() {
+            if *next_neighbor < adjacency[v].len() {
+                let w = adjacency[v][*next_neighbor];
+                *next_neighbor += 1;
+
+                if index[w] == usize::MAX {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+
+            if lowlink[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Порядок вершин компоненты по жадному алгоритму Eades–Lin–Smyth
+///
+/// `edges` — связи внутри компоненты в локальных индексах `0..n`.
+/// Связи, идущие в полученном порядке «назад», образуют feedback arc set.
+pub fn greedy_feedback_order(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut out_adj = vec![Vec::new(); n];
+    let mut in_adj = vec![Vec::new(); n];
+    let mut out_deg = vec![0i64; n];
+    let mut in_deg = vec![0i64; n];
+    for &(u, v) in edges {
+        out_adj[u].push(v);
+        in_adj[v].push(u);
+        out_deg[u] += 1;
+        in_deg[v] += 1;
+    }
+
+    let mut removed = vec![false; n];
+    let mut left = Vec::with_capacity(n);
+    let mut right = Vec::with_capacity(n);
+    let mut sinks: Vec<usize> = (0..n).filter(|&v| out_deg[v] == 0).collect();
+    let mut sources: Vec<usize> = (0..n).filter(|&v| in_deg[v] == 0 && out_deg[v] > 0).collect();
+    let mut heap: BinaryHeap<(i64, Reverse<usize>)> =
+        (0..n).map(|v| (out_deg[v] - in_deg[v], Reverse(v))).collect();
+    let mut remaining = n;
+
+    while remaining > 0 {
+        let v = if let Some(v) = sinks.pop() {
+            if removed[v] {
+                continue;
+            }
+            right.push(v);
+            v
+        } else if let Some(v) = sources.pop() {
+            if removed[v] {
+                continue;
+            }
+            left.push(v);
+            v
+        } else {
+            match heap.pop() {
+                Some((delta, Reverse(v))) => {
+                    if removed[v] || delta != out_deg[v] - in_deg[v] {
+                        continue;
+                    }
+                    left.push(v);
+                    v
+                }
+                None => break,
+            }
+        };
+
+        removed[v] = true;
+        remaining -= 1;
+
+        for &w in &out_adj[v] {
+            if removed[w] {
+                continue;
+            }
+            in_deg[w] -= 1;
+            if in_deg[w] == 0 && out_deg[w] > 0 {
+                sources.push(w);
+            }
+            heap.push((out_deg[w] - in_deg[w], Reverse(w)));
+        }
+        for &w in &in_adj[v] {
+            if removed[w] {
+                continue;
+            }
+            out_deg[w] -= 1;
+            if out_deg[w] == 0 {
+                sinks.push(w);
+            }
+            heap.push((out_deg[w] - in_deg[w], Reverse(w)));
+        }
+    }
+
+    right.reverse();
+    left.extend(right);
+    left
+}
+
+/// Индекс вершины (новые вершины получают следующий свободный индекс)
+fn intern_vertex<'a>(
+    id: &'a str,
+    vertex_index: &mut HashMap<&'a str, usize>,
+    adjacency: &mut Vec<Vec<usize>>,
+) -> usize {
+    if let Some(&index) = vertex_index.get(id) {
+        return index;
+    }
+    let index = adjacency.len();
+    vertex_index.insert(id, index);
+    adjacency.push(Vec::new());
+    index
+}
+
+/// Разрыв циклов в наборе связей
+///
+/// Пустые идентификаторы и self-loops пропускаются без изменений —
+/// они отфильтровываются при построении графа.
+pub fn break_cycles(edges: Vec<GraphEdge>, strategy: CycleBreakStrategy) -> CycleBreakResult {
+    // Индексация вершин в порядке первого появления (детерминированно)
+    let mut vertex_index: HashMap<&str, usize> = HashMap::new();
+    let mut adjacency: Vec<Vec<usize>> = Vec::new();
+    let mut edge_pairs: Vec<Option<(usize, usize)>> = Vec::with_capacity(edges.len());
+
+    for edge in &edges {
+        if edge.source_id.trim().is_empty()
+            || edge.target_id.trim().is_empty()
+            || edge.source_id == edge.target_id
+        {
+            edge_pairs.push(None);
+            continue;
+        }
+        let u = intern_vertex(&edge.source_id, &mut vertex_index, &mut adjacency);
+        let v = intern_vertex(&edge.target_id, &mut vertex_index, &mut adjacency);
+        adjacency[u].push(v);
+        edge_pairs.push(Some((u, v)));
+    }
+
+    drop(vertex_index);
+
+    let components = strongly_connected_components(&adjacency);
+
+    // Компонента и локальный индекс каждой вершины
+    let mut component_of = vec![0usize; adjacency.len()];
+    let mut local_index = vec![0usize; adjacency.len()];
+    for (c, component) in components.iter().enumerate() {
+        for (i, &v) in component.iter().enumerate() {
+            component_of[v] = c;
+            local_index[v] = i;
+        }
+    }
+
+    // Порядок вершин внутри каждой нетривиальной компоненты
+    let mut component_edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); components.len()];
+    for &(u, v) in edge_pairs.iter().flatten() {
+        if component_of[u] == component_of[v] {
+            component_edges[component_of[u]].push((local_index[u], local_index[v]));
+        }
+    }
+
+    let mut position = vec![0usize; adjacency.len()];
+    let mut cyclic_components = 0;
+    for (c, component) in components.iter().enumerate() {
+        if component.len() < 2 {
+            continue;
+        }
+        cyclic_components += 1;
+        let order = greedy_feedback_order(component.len(), &component_edges[c]);
+        for (pos, &local) in order.iter().enumerate() {
+            position[component[local]] = pos;
+        }
+    }
+
+    if cyclic_components == 0 {
+        return CycleBreakResult {
+            edges,
+            broken_edges: Vec::new(),
+            cyclic_components: 0,
+        };
+    }
+
+    // Разрыв обратных связей
+    let existing: HashSet<(usize, usize)> = edge_pairs.iter().flatten().copied().collect();
+    let mut broken_edges = Vec::new();
+    let mut result_edges = Vec::with_capacity(edges.len());
+    let mut added_reversed = HashSet::new();
+
+    for (edge, pair) in edges.into_iter().zip(edge_pairs) {
+        let Some((u, v)) = pair else {
+            result_edges.push(edge);
+            continue;
+        };
+        let is_back_edge = component_of[u] == component_of[v] && position[u] > position[v];
+        if !is_back_edge {
+            result_edges.push(edge);
+            continue;
+        }
+
+        broken_edges.push((edge.source_id.clone(), edge.target_id.clone()));
+        if strategy == CycleBreakStrategy::Reverse
+            && !existing.contains(&(v, u))
+            && added_reversed.insert((v, u))
+        {
+            result_edges.push(GraphEdge {
+                source_id: edge.target_id,
+                target_id: edge.source_id,
+                weight: edge.weight,
+                edge_type: edge.edge_type,
+                source_cluster: edge.target_cluster,
+                target_cluster: edge.source_cluster,
+                source_year: edge.target_year,
+                target_year: edge.source_year,
+            });
+        }
+    }
+
+    CycleBreakResult {
+        edges: result_edges,
+        broken_edges,
+        cyclic_components,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str) -> GraphEdge {
+        GraphEdge::new(source, target, 1.0)
+    }
+
+    fn is_acyclic(edges: &[GraphEdge]) -> bool {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut adjacency: Vec<Vec<usize>> = Vec::new();
+        for e in edges {
+            for id in [e.source_id.as_str(), e.target_id.as_str()] {
+                if !index.contains_key(id) {
+                    index.insert(id, adjacency.len());
+                    adjacency.push(Vec::new());
+                }
+            }
+            adjacency[index[e.source_id.as_str()]].push(index[e.target_id.as_str()]);
+        }
+        strongly_connected_components(&adjacency)
+            .iter()
+            .all(|c| c.len() == 1)
+    }
+
+    #[test]
+    fn test_scc() {
+        // 0 -> 1 -> 2 -> 0, 2 -> 3
+        let adjacency = vec![vec![1], vec![2], vec![0, 3], vec![]];
+        let mut components = strongly_connected_components(&adjacency);
+        components.iter_mut().for_each(|c| c.sort());
+        assert_eq!(components.len(), 2);
+        assert!(components.contains(&vec![0, 1, 2]));
+        assert!(components.contains(&vec![3]));
+    }
+
+    #[test]
+    fn test_dag_is_untouched() {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("A", "C")];
+        let result = break_cycles(edges, CycleBreakStrategy::Reverse);
+        assert!(!result.had_cycles());
+        assert_eq!(result.edges.len(), 3);
+        assert_eq!(result.cyclic_components, 0);
+    }
+
+    #[test]
+    fn test_simple_cycle_breaks_one_edge() {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("C", "A"), edge("C", "D")];
+        let result = break_cycles(edges, CycleBreakStrategy::Reverse);
+        assert_eq!(result.cyclic_components, 1);
+        assert_eq!(result.broken_edges.len(), 1);
+        assert_eq!(result.edges.len(), 4);
+        assert!(is_acyclic(&result.edges));
+    }
+
+    #[test]
+    fn test_mutual_citation_removed() {
+        // A <-> B: разворот дал бы дубликат, поэтому связь просто убирается
+        let edges = vec![edge("A", "B"), edge("B", "A")];
+        let result = break_cycles(edges, CycleBreakStrategy::Reverse);
+        assert_eq!(result.broken_edges.len(), 1);
+        assert_eq!(result.edges.len(), 1);
+        assert!(is_acyclic(&result.edges));
+    }
+
+    #[test]
+    fn test_remove_strategy() {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("C", "A")];
+        let result = break_cycles(edges, CycleBreakStrategy::Remove);
+        assert_eq!(result.broken_edges.len(), 1);
+        assert_eq!(result.edges.len(), 2);
+        assert!(is_acyclic(&result.edges));
+    }
+
+    #[test]
+    fn test_nested_cycles() {
+        let edges = vec![
+            edge("A", "B"), edge("B", "C"), edge("C", "A"),
+            edge("C", "D"), edge("D", "E"), edge("E", "C"),
+            edge("E", "F"),
+        ];
+        let result = break_cycles(edges, CycleBreakStrategy::Reverse);
+        assert_eq!(result.cyclic_components, 1);
+        assert!(!result.broken_edges.is_empty());
+        assert!(is_acyclic(&result.edges));
+    }
+}
@@ -0,0 +1,315 @@
+/*!
+# Обнаружение вырожденных укладок
+
+Некоторые графы (широкие «звёзды» цитирования, почти плоские DAG) при
+выбранной стратегии разбиения на слои дают вырожденный результат: почти все
+вершины попадают в пару слоёв, и укладка превращается в одну бесконечную
+колонку. Здесь собраны проверка такого результата и выбор альтернативной
+стратегии для повторного прогона.
+
+Проверка срабатывает, только если граф достаточно велик (`min_vertices`) и
+глубже `top_layers` слоёв: у мелкого графа глубины 2 все вершины неизбежно
+лежат в двух слоях, и повторный прогон ничего не даст.
+
+Движок укладки (gRPC) повторяет размещение целиком; батчевая укладка
+(`auto-layout`) пересчитывает только слои (`relayer_degenerate`) по графу,
+собранному из накопленных связей.
+*/
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::vertex_placement::{assign_layers_coffman_graham, LayeringStrategy};
+use crate::data_structures::Graph;
+
+/// Пороги вырожденности укладки
+#[derive(Debug, Clone)]
+pub struct DegenerateThresholds {
+    /// Минимальное число вершин, при котором выполняется проверка
+    pub min_vertices: usize,
+
+    /// Сколько самых заполненных слоёв учитывается в `top_layers_share`
+    pub top_layers: usize,
+
+    /// Доля вершин в `top_layers` самых заполненных слоях
+    pub top_layers_share: f64,
+
+    /// Доля вершин в одном слое
+    pub single_layer_share: f64,
+}
+
+impl Default for DegenerateThresholds {
+    fn default() -> Self {
+        Self {
+            min_vertices: 50,
+            top_layers: 3,
+            top_layers_share: 0.9,
+            single_layer_share: 0.5,
+        }
+    }
+}
+
+/// Причина, по которой укладка признана вырожденной
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DegenerateReason {
+    /// Один слой содержит слишком большую долю вершин
+    SingleLayer { layer: i32, share: f64 },
+
+    /// Несколько самых заполненных слоёв содержат почти все вершины
+    TopLayers { layers: usize, share: f64 },
+}
+
+impl fmt::Display for DegenerateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DegenerateReason::SingleLayer { layer, share } => {
+                write!(f, "слой {} содержит {:.0}% вершин", layer, share * 100.0)
+            }
+            DegenerateReason::TopLayers { layers, share } => {
+                write!(f, "{} слоя содержат {:.0}% вершин", layers, share * 100.0)
+            }
+        }
+    }
+}
+
+/// Запись о повторном прогоне с другой стратегией
+#[derive(Debug, Clone, Serialize)]
+pub struct LayeringFallback {
+    /// Почему исходная укладка признана вырожденной
+    pub reason: DegenerateReason,
+
+    /// Исходная стратегия
+    pub from: String,
+
+    /// Стратегия повторного прогона
+    pub to: String,
+
+    /// Принят ли результат повторного прогона
+    pub accepted: bool,
+}
+
+/// Распределение вершин по слоям
+#[derive(Debug, Clone, Default)]
+pub struct LayerDistribution {
+    counts: HashMap<i32, usize>,
+    total: usize,
+}
+
+impl LayerDistribution {
+    /// Подсчёт вершин по номерам слоёв
+    pub fn from_layers(layers: impl IntoIterator<Item = i32>) -> Self {
+        let mut counts: HashMap<i32, usize> = HashMap::new();
+        let mut total = 0;
+        for layer in layers {
+            *counts.entry(layer).or_insert(0) += 1;
+            total += 1;
+        }
+        Self { counts, total }
+    }
+
+    /// Число вершин
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Число непустых слоёв
+    pub fn layer_count(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Самый заполненный слой и его доля (при равенстве — меньший номер)
+    pub fn max_layer_share(&self) -> Option<(i32, f64)> {
+        if self.total == 0 {
+            return None;
+        }
+        self.counts
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(&layer, &count)| (layer, count as f64 / self.total as f64))
+    }
+
+    /// Доля вершин в `k` самых заполненных слоях
+    pub fn top_share(&self, k: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let mut sizes: Vec<usize> = self.counts.values().copied().collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.iter().take(k).sum::<usize>() as f64 / self.total as f64
+    }
+}
+
+/// Проверка укладки на вырожденность
+///
+/// `depth` — длина самого длинного пути в вершинах: если граф не глубже
+/// `top_layers`, плотные слои неизбежны и укладка вырожденной не считается.
+pub fn detect_degenerate(
+    distribution: &LayerDistribution,
+    depth: usize,
+    thresholds: &DegenerateThresholds,
+) -> Option<DegenerateReason> {
+    if distribution.total() < thresholds.min_vertices || depth <= thresholds.top_layers {
+        return None;
+    }
+
+    if let Some((layer, share)) = distribution.max_layer_share() {
+        if share > thresholds.single_layer_share {
+            return Some(DegenerateReason::SingleLayer { layer, share });
+        }
+    }
+
+    let layers = thresholds.top_layers.min(distribution.layer_count());
+    let share = distribution.top_share(thresholds.top_layers);
+    if share > thresholds.top_layers_share {
+        return Some(DegenerateReason::TopLayers { layers, share });
+    }
+
+    None
+}
+
+/// Альтернативная стратегия и ограничение ширины слоя для повторного прогона
+///
+/// Стратегии без ограничения ширины заменяются на Coffman–Graham с шириной
+/// `ceil(sqrt(V))`; Coffman–Graham — на longest path. Слои по году
+/// публикации задаются данными, вместо них тоже используется Coffman–Graham.
+pub fn fallback_strategy(current: LayeringStrategy, vertex_count: usize) -> (LayeringStrategy, usize) {
+    match current {
+        LayeringStrategy::Bfs | LayeringStrategy::NetworkSimplex | LayeringStrategy::ByProperty => {
+            let width = ((vertex_count as f64).sqrt().ceil() as usize).max(1);
+            (LayeringStrategy::CoffmanGraham, width)
+        }
+        LayeringStrategy::CoffmanGraham => (LayeringStrategy::Bfs, 0),
+    }
+}
+
+/// Повторное разбиение на слои для батчевой укладки
+///
+/// Батчевая укладка назначает слои самым длинным путём, как `Bfs`, поэтому
+/// замена выбирается `fallback_strategy(Bfs, …)`: Coffman–Graham с шириной
+/// `ceil(sqrt(V))`. `layers` — исходные слои по ID вершин; вершины, которых нет
+/// в `graph`, сохраняют свой слой. Новые слои возвращаются, только если самый
+/// плотный слой стал меньше.
+pub fn relayer_degenerate(
+    graph: &Graph,
+    layers: &HashMap<String, i32>,
+    distribution: &LayerDistribution,
+    reason: DegenerateReason,
+) -> (Option<HashMap<String, i32>>, LayeringFallback) {
+    let (strategy, width) = fallback_strategy(LayeringStrategy::Bfs, graph.vertex_count());
+    let relayered = assign_layers_coffman_graham(graph, width).map(|layer_map| {
+        let mut relayered = layers.clone();
+        for (vertex, layer) in layer_map {
+            relayered.insert(graph.vertex_uid(vertex).to_string(), layer);
+        }
+        relayered
+    });
+
+    let original_share = distribution.max_layer_share().map_or(1.0, |(_, share)| share);
+    let accepted = relayered.as_ref().map_or(false, |relayered| {
+        let retry_share = LayerDistribution::from_layers(relayered.values().copied())
+            .max_layer_share()
+            .map_or(1.0, |(_, share)| share);
+        retry_share < original_share
+    });
+    let fallback = LayeringFallback {
+        reason,
+        from: format!("{:?}", LayeringStrategy::Bfs),
+        to: format!("{:?}", strategy),
+        accepted,
+    };
+    (relayered.filter(|_| accepted), fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distribution(sizes: &[usize]) -> LayerDistribution {
+        LayerDistribution::from_layers(
+            sizes
+                .iter()
+                .enumerate()
+                .flat_map(|(layer, &size)| std::iter::repeat(layer as i32).take(size)),
+        )
+    }
+
+    #[test]
+    fn test_single_dense_layer_is_degenerate() {
+        let layers = distribution(&[1, 60, 10, 10, 9, 10]);
+        let reason = detect_degenerate(&layers, 6, &DegenerateThresholds::default());
+        match reason {
+            Some(DegenerateReason::SingleLayer { layer, share }) => {
+                assert_eq!(layer, 1);
+                assert!(share > 0.5);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_top_layers_share_is_degenerate() {
+        let layers = distribution(&[40, 30, 25, 2, 2, 1]);
+        let reason = detect_degenerate(&layers, 6, &DegenerateThresholds::default());
+        assert!(matches!(reason, Some(DegenerateReason::TopLayers { layers: 3, .. })));
+    }
+
+    #[test]
+    fn test_balanced_and_shallow_layouts_are_accepted() {
+        let thresholds = DegenerateThresholds::default();
+        assert_eq!(detect_degenerate(&distribution(&[20; 10]), 10, &thresholds), None);
+        // Звезда: глубина 2, плотный слой неизбежен
+        assert_eq!(detect_degenerate(&distribution(&[1, 99]), 2, &thresholds), None);
+        // Маленький граф не проверяется
+        assert_eq!(detect_degenerate(&distribution(&[1, 8, 1, 1]), 4, &thresholds), None);
+    }
+
+    #[test]
+    fn test_fallback_strategy() {
+        assert_eq!(
+            fallback_strategy(LayeringStrategy::Bfs, 100),
+            (LayeringStrategy::CoffmanGraham, 10)
+        );
+        assert_eq!(
+            fallback_strategy(LayeringStrategy::NetworkSimplex, 101),
+            (LayeringStrategy::CoffmanGraham, 11)
+        );
+        assert_eq!(fallback_strategy(LayeringStrategy::CoffmanGraham, 100), (LayeringStrategy::Bfs, 0));
+    }
+
+    #[test]
+    fn test_relayer_degenerate_spreads_wide_layer() {
+        // Корень, 99 листьев и цепочка глубины 4: 99 вершин в слое 1
+        let mut builder = crate::data_structures::GraphBuilder::new().deterministic(true);
+        for i in 0..99 {
+            builder.add_edge("root".to_string(), format!("leaf{:02}", i), 1.0).unwrap();
+        }
+        builder.add_edge("leaf00".to_string(), "deep1".to_string(), 1.0).unwrap();
+        builder.add_edge("deep1".to_string(), "deep2".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let mut layers: HashMap<String, i32> = graph
+            .handles()
+            .map(|v| (graph.vertex_uid(v).to_string(), 1))
+            .collect();
+        layers.insert("root".to_string(), 0);
+        layers.insert("deep1".to_string(), 2);
+        layers.insert("deep2".to_string(), 3);
+        layers.insert("isolated".to_string(), 0);
+        let distribution = LayerDistribution::from_layers(layers.values().copied());
+        let reason = detect_degenerate(&distribution, 4, &DegenerateThresholds::default()).unwrap();
+
+        let (relayered, fallback) = relayer_degenerate(&graph, &layers, &distribution, reason);
+        assert!(fallback.accepted);
+        assert_eq!(fallback.to, "CoffmanGraham");
+        let relayered = relayered.unwrap();
+        assert_eq!(relayered.len(), layers.len());
+        assert_eq!(relayered["isolated"], 0);
+        let widest = LayerDistribution::from_layers(relayered.values().copied())
+            .max_layer_share()
+            .unwrap()
+            .1;
+        assert!(widest <= 0.11);
+    }
+}
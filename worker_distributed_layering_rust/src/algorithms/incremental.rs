@@ -0,0 +1,333 @@
+/*!
+# Инкрементальная укладка
+
+Еженедельно в граф добавляются десятки тысяч статей, и полная переукладка
+графа на 10M связей ради них избыточна. `IncrementalLayoutEngine` хранит
+состояние слоёв (`GlobalLayerState`), принимает изменения графа (добавленные и
+удалённые связи) и пересчитывает слои только для затронутых вершин через
+распространение «грязных» вершин.
+
+Исходное состояние — предыдущий `LayoutResult` или сохранённые в Neo4j
+`layer`/`level`. Без загрузки всего графа (`from_positions`, режим
+`--incremental`) сохранённые слои служат нижними границами, а связи
+догружаются только вокруг изменений. Результат применения изменений — только
+новые и сдвинутые позиции, а также вершины, оставшиеся без связей.
+
+Позиции остальных вершин не меняются: вершина, сменившая слой, или новая
+вершина занимает следующий свободный уровень в конце своего слоя.
+Освободившиеся уровни не переиспользуются — для уплотнения нужна полная укладка.
+*/
+
+use anyhow::Result;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+use crate::neo4j::{GraphEdge, Neo4jClient, VertexPosition};
+use super::vertex_placement::{GlobalLayerState, PlacementConfig};
+use super::LayoutResult;
+
+/// Изменения графа
+#[derive(Debug, Clone, Default)]
+pub struct GraphDelta {
+    /// Добавленные связи
+    pub added: Vec<GraphEdge>,
+
+    /// Удалённые связи
+    pub removed: Vec<GraphEdge>,
+}
+
+/// Результат применения изменений
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalUpdate {
+    /// Новые вершины и вершины, сменившие слой
+    pub changed: Vec<VertexPosition>,
+
+    /// Вершины, оставшиеся без связей и удалённые из укладки
+    pub removed: Vec<String>,
+
+    /// Добавленные связи, отклонённые из-за образования цикла
+    pub rejected: Vec<(String, String)>,
+}
+
+/// Инкрементальная укладка поверх сохранённого состояния слоёв
+pub struct IncrementalLayoutEngine {
+    /// Слои и структура графа
+    state: GlobalLayerState,
+
+    /// Текущие позиции всех вершин
+    positions: HashMap<String, VertexPosition>,
+
+    /// Следующий свободный уровень каждого слоя
+    next_level: HashMap<i32, i32>,
+
+    /// Размеры блоков и отступы
+    config: PlacementConfig,
+}
+
+impl IncrementalLayoutEngine {
+    /// Создание из связей графа и предыдущих позиций
+    ///
+    /// Слои вершин из `previous` принимаются как есть; слои остальных вершин
+    /// вычисляются, и эти вершины получают новые позиции.
+    pub fn new(edges: &[GraphEdge], previous: &[VertexPosition], config: PlacementConfig) -> Result<Self> {
+        let mut state = GlobalLayerState::new();
+        state.add_edges_batch(&edge_pairs(edges))?;
+
+        let known: HashMap<String, VertexPosition> = previous
+            .iter()
+            .filter(|p| state.contains_vertex(&p.article_id))
+            .map(|p| (p.article_id.clone(), p.clone()))
+            .collect();
+        let layers: HashMap<String, i32> = known.iter().map(|(id, p)| (id.clone(), p.layer)).collect();
+
+        state.seed_layers(&layers);
+        state.enable_change_tracking();
+        state.propagate_until_convergence()?;
+
+        let mut engine = Self {
+            next_level: next_levels(known.values()),
+            state,
+            positions: known,
+            config,
+        };
+
+        // Вершины без сохранённой позиции получают место сразу
+        let placed = engine.place_changed();
+        if !placed.is_empty() {
+            info!("📍 Размещено {} вершин без сохранённой позиции", placed.len());
+        }
+
+        Ok(engine)
+    }
+
+    /// Создание только из сохранённых позиций, без связей графа
+    ///
+    /// Сохранённые слои становятся нижними границами, поэтому связи,
+    /// добавленные через `apply_delta`, сдвигают вершины только вправо.
+    /// Структура графа состоит лишь из применённых связей: цикл через
+    /// не загруженные связи не обнаруживается, а удаление связей не сдвигает
+    /// вершины влево.
+    pub fn from_positions(previous: &[VertexPosition], config: PlacementConfig) -> Result<Self> {
+        let mut state = GlobalLayerState::new();
+        state.set_layer_lower_bounds(previous.iter().map(|p| (p.article_id.clone(), p.layer)).collect())?;
+        state.enable_change_tracking();
+
+        let positions: HashMap<String, VertexPosition> = previous
+            .iter()
+            .map(|p| (p.article_id.clone(), p.clone()))
+            .collect();
+
+        Ok(Self {
+            next_level: next_levels(positions.values()),
+            state,
+            positions,
+            config,
+        })
+    }
+
+    /// Создание из результата предыдущей укладки
+    pub fn from_result(edges: &[GraphEdge], previous: &LayoutResult, config: PlacementConfig) -> Result<Self> {
+        Self::new(edges, &previous.positions, config)
+    }
+
+    /// Создание из графа и укладки, сохранённых в Neo4j
+    pub async fn from_neo4j(client: &Neo4jClient, config: PlacementConfig) -> Result<Self> {
+        let edges = client.load_graph_edges().await?;
+        let positions = client.load_layout_positions().await?;
+        Self::new(&edges, &positions, config)
+    }
+
+    /// Применение изменений графа
+    ///
+    /// Сначала удаляются связи, затем добавляются новые. Связь, замыкающая
+    /// цикл, отклоняется. Возвращаются только изменившиеся позиции.
+    pub fn apply_delta(&mut self, delta: &GraphDelta) -> Result<IncrementalUpdate> {
+        let removed = self.state.remove_edges_batch(&edge_pairs(&delta.removed))?;
+        for vertex in &removed {
+            self.positions.remove(vertex);
+        }
+
+        let mut rejected = Vec::new();
+        for (source, target) in edge_pairs(&delta.added) {
+            if self.state.would_create_cycle(&source, &target)? {
+                rejected.push((source, target));
+                continue;
+            }
+            self.state.add_edges_batch(&[(source, target)])?;
+        }
+        if !rejected.is_empty() {
+            warn!("⚠️ Отклонено {} связей, замыкающих цикл", rejected.len());
+        }
+
+        self.state.propagate_until_convergence()?;
+        let changed = self.place_changed();
+
+        info!(
+            "🔁 Инкрементальная укладка: +{} / -{} связей, изменено {} позиций, удалено {} вершин",
+            delta.added.len() - rejected.len(),
+            delta.removed.len(),
+            changed.len(),
+            removed.len()
+        );
+
+        Ok(IncrementalUpdate {
+            changed,
+            removed,
+            rejected,
+        })
+    }
+
+    /// Текущие позиции всех вершин
+    pub fn positions(&self) -> impl Iterator<Item = &VertexPosition> {
+        self.positions.values()
+    }
+
+    /// Состояние слоёв
+    pub fn state(&self) -> &GlobalLayerState {
+        &self.state
+    }
+
+    /// Размещение новых вершин и вершин, сменивших слой
+    fn place_changed(&mut self) -> Vec<VertexPosition> {
+        let mut changed: Vec<String> = self.state.take_changed_vertices().into_iter().collect();
+        changed.sort();
+
+        let mut placed = Vec::with_capacity(changed.len());
+        for vertex in changed {
+            let Some(layer) = self.state.layer_of(&vertex) else {
+                continue;
+            };
+            if self.positions.get(&vertex).map_or(false, |p| p.layer == layer) {
+                continue;
+            }
+
+            let next = self.next_level.entry(layer).or_insert(0);
+            let level = *next;
+            *next += 1;
+
+            let position = VertexPosition {
+                article_id: vertex.clone(),
+                layer,
+                level,
+                x: layer as f32 * (self.config.block_width + self.config.horizontal_gap),
+                y: level as f32 * (self.config.block_height + self.config.vertical_gap),
+            };
+            self.positions.insert(vertex, position.clone());
+            placed.push(position);
+        }
+
+        placed
+    }
+}
+
+/// Следующий свободный уровень каждого слоя
+fn next_levels<'a>(positions: impl Iterator<Item = &'a VertexPosition>) -> HashMap<i32, i32> {
+    let mut next_level = HashMap::new();
+    for position in positions {
+        let next = next_level.entry(position.layer).or_insert(0);
+        *next = (*next).max(position.level + 1);
+    }
+    next_level
+}
+
+fn edge_pairs(edges: &[GraphEdge]) -> Vec<(String, String)> {
+    edges
+        .iter()
+        .map(|e| (e.source_id.clone(), e.target_id.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str) -> GraphEdge {
+        GraphEdge::new(source, target, 1.0)
+    }
+
+    fn position(id: &str, layer: i32, level: i32) -> VertexPosition {
+        VertexPosition {
+            article_id: id.to_string(),
+            layer,
+            level,
+            x: layer as f32 * 240.0,
+            y: level as f32 * 130.0,
+        }
+    }
+
+    fn engine() -> IncrementalLayoutEngine {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("A", "D")];
+        let previous = vec![position("A", 0, 0), position("B", 1, 0), position("D", 1, 1), position("C", 2, 0)];
+        IncrementalLayoutEngine::new(&edges, &previous, PlacementConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_new_article_gets_only_changed_positions() {
+        let mut engine = engine();
+
+        // Новая статья N цитирует C
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![edge("C", "N")], removed: vec![] })
+            .unwrap();
+
+        assert_eq!(update.changed.len(), 1);
+        assert_eq!(update.changed[0].article_id, "N");
+        assert_eq!(update.changed[0].layer, 3);
+        assert_eq!(update.changed[0].level, 0);
+    }
+
+    #[test]
+    fn test_relayered_vertex_moves_to_free_level() {
+        let mut engine = engine();
+
+        // D теперь цитирует C, поэтому переходит в слой 3
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![edge("C", "D")], removed: vec![] })
+            .unwrap();
+
+        assert_eq!(update.changed.len(), 1);
+        assert_eq!(update.changed[0].article_id, "D");
+        assert_eq!(update.changed[0].layer, 3);
+
+        // Удаление A -> B: B становится источником
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![], removed: vec![edge("A", "B")] })
+            .unwrap();
+        let b = update.changed.iter().find(|p| p.article_id == "B").unwrap();
+        assert_eq!(b.layer, 0);
+        assert_eq!(b.level, 1);
+    }
+
+    #[test]
+    fn test_saved_layers_are_lower_bounds() {
+        let previous = vec![position("A", 0, 0), position("B", 1, 0), position("D", 1, 1), position("C", 2, 0)];
+        let mut engine = IncrementalLayoutEngine::from_positions(&previous, PlacementConfig::default()).unwrap();
+
+        // Уже уложенная связь ничего не сдвигает
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![edge("A", "B")], removed: vec![] })
+            .unwrap();
+        assert!(update.changed.is_empty());
+
+        // D теперь цитирует C: D уходит в слой 3, B остаётся в слое 1
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![edge("C", "D")], removed: vec![] })
+            .unwrap();
+        assert_eq!(update.changed.len(), 1);
+        assert_eq!(update.changed[0].article_id, "D");
+        assert_eq!(update.changed[0].layer, 3);
+        assert_eq!(update.changed[0].level, 0);
+        assert_eq!(engine.positions().count(), 4);
+    }
+
+    #[test]
+    fn test_cycle_edges_are_rejected() {
+        let mut engine = engine();
+        let update = engine
+            .apply_delta(&GraphDelta { added: vec![edge("C", "A")], removed: vec![] })
+            .unwrap();
+
+        assert_eq!(update.rejected, vec![("C".to_string(), "A".to_string())]);
+        assert!(update.changed.is_empty());
+    }
+}
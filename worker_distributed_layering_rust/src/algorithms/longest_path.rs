@@ -10,7 +10,8 @@
 
 */
 
-use crate::data_structures::Graph;
+use crate::data_structures::{Graph, VertexId};
+use super::simd::{self, SimdPath};
 use anyhow::Result;
 use rayon::prelude::*;
 use std::collections::HashMap;
@@ -18,8 +19,8 @@ use std::collections::HashMap;
 /// SIMD-оптимизированный поиск longest path
 #[derive(Debug)]
 pub struct SIMDLongestPath {
-    /// Включены ли SIMD оптимизации
-    simd_enabled: bool,
+    /// Реализация SIMD-ядер (скалярная, если SIMD выключен или не поддерживается CPU)
+    simd_path: SimdPath,
     
     /// Кеш результатов
     path_cache: HashMap<String, Vec<String>>,
@@ -29,36 +30,57 @@ impl SIMDLongestPath {
     /// Создание нового экземпляра
     pub fn new(simd_enabled: bool) -> Result<Self> {
         Ok(Self {
-            simd_enabled,
+            simd_path: SimdPath::select(simd_enabled),
             path_cache: HashMap::new(),
         })
     }
     
+    /// Выбранная реализация SIMD-ядер
+    pub fn simd_path(&self) -> SimdPath {
+        self.simd_path
+    }
+    
     /// SIMD-оптимизированный поиск longest path
     pub async fn find_simd(
         &self,
         graph: &Graph,
-        topo_order: &[String],
-    ) -> Result<Vec<String>> {
-        if self.simd_enabled {
+        topo_order: &[VertexId],
+    ) -> Result<Vec<VertexId>> {
+        Ok(self.find_simd_with_path(graph, topo_order).await?.0)
+    }
+    
+    /// Поиск longest path и реализация SIMD-ядер, которая фактически выполнилась
+    /// (скалярная, если SIMD выключен или данных меньше одного вектора)
+    pub async fn find_simd_with_path(
+        &self,
+        graph: &Graph,
+        topo_order: &[VertexId],
+    ) -> Result<(Vec<VertexId>, SimdPath)> {
+        if self.simd_path.is_vectorized() {
             self.find_longest_path_simd_optimized(graph, topo_order).await
         } else {
-            self.find_longest_path_standard(graph, topo_order).await
+            Ok((self.find_longest_path_standard(graph, topo_order).await?, SimdPath::Scalar))
         }
     }
     
+    /// Позиции вершин в топологическом порядке по индексу handle
+    /// (`usize::MAX` — вершина в порядок не входит)
+    fn topo_positions(graph: &Graph, topo_order: &[VertexId]) -> Vec<usize> {
+        let mut positions = vec![usize::MAX; graph.vertex_count()];
+        for (idx, vertex) in topo_order.iter().enumerate() {
+            positions[vertex.index()] = idx;
+        }
+        positions
+    }
+    
     /// SIMD-оптимизированная версия
     async fn find_longest_path_simd_optimized(
         &self,
         graph: &Graph,
-        topo_order: &[String],
-    ) -> Result<Vec<String>> {
-        // Создание маппинга вершин на индексы
-        let vertex_to_idx: HashMap<_, _> = topo_order
-            .iter()
-            .enumerate()
-            .map(|(idx, vertex)| (vertex, idx))
-            .collect();
+        topo_order: &[VertexId],
+    ) -> Result<(Vec<VertexId>, SimdPath)> {
+        // Маппинг handles на позиции в порядке
+        let vertex_to_idx = Self::topo_positions(graph, topo_order);
         
         let n = topo_order.len();
         let mut distances = vec![-1.0f32; n]; // Используем f32 для SIMD
@@ -68,76 +90,73 @@ impl SIMDLongestPath {
         distances.par_iter_mut().for_each(|d| *d = 0.0);
         
         // Параллельная обработка в топологическом порядке
-        for (current_idx, current_vertex) in topo_order.iter().enumerate() {
-            if let Some(outgoing) = graph.get_outgoing_edges(current_vertex) {
-                let outgoing_list: Vec<_> = outgoing.collect();
+        for (current_idx, &current_vertex) in topo_order.iter().enumerate() {
+            for target_vertex in graph.successors(current_vertex) {
+                let target_idx = vertex_to_idx[target_vertex.index()];
+                if target_idx == usize::MAX {
+                    continue;
+                }
+                let new_distance = distances[current_idx] + 1.0;
                 
-                // Обновление дистанций
-                for &target_vertex in &outgoing_list {
-                    if let Some(&target_idx) = vertex_to_idx.get(target_vertex) {
-                        let new_distance = distances[current_idx] + 1.0;
-                        
-                        // Простое обновление максимального расстояния
-                        if new_distance > distances[target_idx] {
-                            distances[target_idx] = new_distance;
-                            predecessors[target_idx] = Some(current_idx);
-                        }
-                    }
+                // Простое обновление максимального расстояния
+                if new_distance > distances[target_idx] {
+                    distances[target_idx] = new_distance;
+                    predecessors[target_idx] = Some(current_idx);
                 }
             }
         }
         
-        // Поиск вершины с максимальной дистанцией
-        let (max_idx, _) = distances
+        // Поиск вершины с максимальной дистанцией (при равенстве — последней)
+        let Some(max_distance) = simd::max_f32(self.simd_path, &distances) else {
+            return Ok((Vec::new(), SimdPath::Scalar));
+        };
+        let max_idx = distances
             .iter()
-            .enumerate()
-            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .unwrap();
+            .rposition(|&d| d == max_distance)
+            .unwrap_or(0);
         
         // Восстановление пути
         let path = self.reconstruct_path(max_idx, &predecessors, topo_order);
         
-        Ok(path)
+        Ok((path, self.simd_path.for_len(distances.len())))
     }
     
     /// Стандартная версия без SIMD
     async fn find_longest_path_standard(
         &self,
         graph: &Graph,
-        topo_order: &[String],
-    ) -> Result<Vec<String>> {
-        let vertex_to_idx: HashMap<_, _> = topo_order
-            .iter()
-            .enumerate()
-            .map(|(idx, vertex)| (vertex, idx))
-            .collect();
+        topo_order: &[VertexId],
+    ) -> Result<Vec<VertexId>> {
+        let vertex_to_idx = Self::topo_positions(graph, topo_order);
         
         let n = topo_order.len();
         let mut distances = vec![0i32; n];
         let mut predecessors = vec![None::<usize>; n];
         
         // Обработка в топологическом порядке
-        for (current_idx, current_vertex) in topo_order.iter().enumerate() {
-            if let Some(outgoing) = graph.get_outgoing_edges(current_vertex) {
-                for target_vertex in outgoing {
-                    if let Some(&target_idx) = vertex_to_idx.get(target_vertex) {
-                        let new_distance = distances[current_idx] + 1;
-                        
-                        if new_distance > distances[target_idx] {
-                            distances[target_idx] = new_distance;
-                            predecessors[target_idx] = Some(current_idx);
-                        }
-                    }
+        for (current_idx, &current_vertex) in topo_order.iter().enumerate() {
+            for target_vertex in graph.successors(current_vertex) {
+                let target_idx = vertex_to_idx[target_vertex.index()];
+                if target_idx == usize::MAX {
+                    continue;
+                }
+                let new_distance = distances[current_idx] + 1;
+                
+                if new_distance > distances[target_idx] {
+                    distances[target_idx] = new_distance;
+                    predecessors[target_idx] = Some(current_idx);
                 }
             }
         }
         
         // Поиск вершины с максимальной дистанцией
-        let (max_idx, _) = distances
+        let Some((max_idx, _)) = distances
             .iter()
             .enumerate()
             .max_by_key(|(_, &d)| d)
-            .unwrap();
+        else {
+            return Ok(Vec::new());
+        };
         
         // Восстановление пути
         let path = self.reconstruct_path(max_idx, &predecessors, topo_order);
@@ -150,13 +169,13 @@ impl SIMDLongestPath {
         &self,
         end_idx: usize,
         predecessors: &[Option<usize>],
-        topo_order: &[String],
-    ) -> Vec<String> {
+        topo_order: &[VertexId],
+    ) -> Vec<VertexId> {
         let mut path = Vec::new();
         let mut current_idx = Some(end_idx);
         
         while let Some(idx) = current_idx {
-            path.push(topo_order[idx].clone());
+            path.push(topo_order[idx]);
             current_idx = predecessors[idx];
         }
         
@@ -165,19 +184,19 @@ impl SIMDLongestPath {
     }
     
     /// Валидация пути
-    pub fn validate_path(&self, graph: &Graph, path: &[String]) -> Result<()> {
+    pub fn validate_path(&self, graph: &Graph, path: &[VertexId]) -> Result<()> {
         if path.is_empty() {
             return Ok(());
         }
         
         for window in path.windows(2) {
-            let source = &window[0];
-            let target = &window[1];
+            let source = window[0];
+            let target = window[1];
             
-            if !graph.contains_edge(source, target) {
+            if !graph.successors(source).any(|v| v == target) {
                 return Err(anyhow::anyhow!(
                     "Некорректный путь: отсутствует связь {} -> {}",
-                    source, target
+                    graph.vertex_uid(source), graph.vertex_uid(target)
                 ));
             }
         }
@@ -189,20 +208,21 @@ impl SIMDLongestPath {
     pub async fn find_all_longest_paths(
         &self,
         graph: &Graph,
-        topo_order: &[String],
-    ) -> Result<Vec<Vec<String>>> {
+        topo_order: &[VertexId],
+    ) -> Result<Vec<Vec<VertexId>>> {
         // TODO: Реализация поиска всех longest paths
         let single_path = self.find_simd(graph, topo_order).await?;
         Ok(vec![single_path])
     }
     
-    /// Статистика longest path
-    pub fn get_path_statistics(&self, path: &[String]) -> PathStatistics {
+    /// Статистика longest path (UID вершин восстанавливаются по графу)
+    pub fn get_path_statistics(&self, graph: &Graph, path: &[VertexId]) -> PathStatistics {
+        let vertices: Vec<String> = path.iter().map(|&v| graph.vertex_uid(v).to_string()).collect();
         PathStatistics {
             length: path.len(),
-            start_vertex: path.first().cloned().unwrap_or_default(),
-            end_vertex: path.last().cloned().unwrap_or_default(),
-            vertices: path.to_vec(),
+            start_vertex: vertices.first().cloned().unwrap_or_default(),
+            end_vertex: vertices.last().cloned().unwrap_or_default(),
+            vertices,
         }
     }
 }
@@ -221,6 +241,10 @@ mod tests {
     use super::*;
     use crate::data_structures::GraphBuilder;
     
+    fn handles(graph: &Graph, ids: &[&str]) -> Vec<VertexId> {
+        ids.iter().map(|id| graph.vertex_handle(id).unwrap()).collect()
+    }
+    
     #[tokio::test]
     async fn test_longest_path_simple() -> Result<()> {
         // Создание простого DAG: A -> B -> C -> D
@@ -230,13 +254,13 @@ mod tests {
         builder.add_edge("C".to_string(), "D".to_string(), 1.0)?;
         let graph = builder.build()?;
         
-        let topo_order = vec!["A".to_string(), "B".to_string(), "C".to_string(), "D".to_string()];
+        let topo_order = handles(&graph, &["A", "B", "C", "D"]);
         
         let finder = SIMDLongestPath::new(true)?;
         let longest_path = finder.find_simd(&graph, &topo_order).await?;
         
         assert_eq!(longest_path.len(), 4);
-        assert_eq!(longest_path, vec!["A", "B", "C", "D"]);
+        assert_eq!(longest_path, topo_order);
         
         finder.validate_path(&graph, &longest_path)?;
         
@@ -264,10 +288,7 @@ mod tests {
         let graph = builder.build()?;
         
         // Примерный топологический порядок
-        let topo_order = vec![
-            "A".to_string(), "B".to_string(), "C".to_string(), "F".to_string(),
-            "D".to_string(), "G".to_string(), "E".to_string(), "H".to_string(), "I".to_string()
-        ];
+        let topo_order = handles(&graph, &["A", "B", "C", "F", "D", "G", "E", "H", "I"]);
         
         let finder = SIMDLongestPath::new(true)?;
         let longest_path = finder.find_simd(&graph, &topo_order).await?;
@@ -276,9 +297,25 @@ mod tests {
         assert!(longest_path.len() >= 4);
         finder.validate_path(&graph, &longest_path)?;
         
-        let stats = finder.get_path_statistics(&longest_path);
+        let stats = finder.get_path_statistics(&graph, &longest_path);
         assert_eq!(stats.start_vertex, "A");
         
         Ok(())
     }
+    
+    #[tokio::test]
+    async fn test_reports_scalar_path_when_simd_disabled_or_input_short() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        let graph = builder.build()?;
+        let topo_order = handles(&graph, &["A", "B"]);
+        
+        let (_, path) = SIMDLongestPath::new(false)?.find_simd_with_path(&graph, &topo_order).await?;
+        assert_eq!(path, SimdPath::Scalar);
+        // Две вершины короче любого вектора: работает скалярный хвост ядра
+        let (_, path) = SIMDLongestPath::new(true)?.find_simd_with_path(&graph, &topo_order).await?;
+        assert_eq!(path, SimdPath::Scalar);
+        
+        Ok(())
+    }
 }
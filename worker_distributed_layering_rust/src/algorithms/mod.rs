@@ -23,13 +23,25 @@
 ```
 */
 
-pub mod topological_sort;
-pub mod longest_path;
+pub(crate) mod topological_sort;
+pub(crate) mod longest_path;
+pub(crate) mod simd;
+pub mod cycle_breaking;
+pub mod network_simplex;
+pub mod incremental;
+pub mod component_packing;
+pub mod degenerate;
+pub mod time_slicing;
+pub mod transitive_reduction;
+pub mod phases;
+pub mod scheduler;
+pub mod validation;
 pub mod vertex_placement;
-pub mod memory_optimized;
-pub mod parallel_processing;
+pub(crate) mod memory_optimized;
+pub(crate) mod parallel_processing;
 
 use crate::generated::{LayoutOptions, LayoutStatistics};
+use crate::messages::Msg;
 use crate::neo4j::{GraphEdge, VertexPosition};
 use anyhow::Result;
 use std::collections::HashMap;
@@ -60,6 +72,19 @@ pub struct LayoutResult {
     /// Статистика выполнения
     pub statistics: LayoutStatistics,
     
+    /// Типизированные результаты этапов
+    pub phases: phases::LayoutPhases,
+    
+    /// Маршруты связей, упорядоченные по (источник, цель)
+    pub edge_paths: Vec<crate::neo4j::EdgePath>,
+    
+    /// Сетка занятых позиций (слой → битовая карта уровней) для поиска
+    /// свободного места рядом с вершиной, например под аннотации
+    pub occupancy: vertex_placement::OccupiedPositions,
+    
+    /// Отчёт валидации входных связей
+    pub validation: validation::EdgeValidationReport,
+    
     /// Метаданные алгоритма
     pub metadata: AlgorithmMetadata,
 }
@@ -78,6 +103,9 @@ pub struct AlgorithmMetadata {
     
     /// Параметры алгоритма
     pub parameters: HashMap<String, String>,
+    
+    /// Пространственный индекс итоговых позиций (если включён)
+    pub spatial_index: Option<crate::data_structures::SpatialIndex>,
 }
 
 /// Статистика алгоритма
@@ -111,6 +139,39 @@ pub struct HighPerformanceLayoutEngine {
     /// Менеджер памяти
     memory_manager: memory_optimized::MemoryManager,
     
+    /// Способ разрыва циклов
+    cycle_break_strategy: cycle_breaking::CycleBreakStrategy,
+    
+    /// Укладка несвязных компонент
+    component_packing: component_packing::ComponentPackingOptions,
+    
+    /// Пороги вырожденной укладки (None — без повторного прогона)
+    degenerate_thresholds: Option<degenerate::DegenerateThresholds>,
+    
+    /// Подробность диагностики валидации входных связей
+    validation_verbosity: validation::ValidationVerbosity,
+    
+    /// Сторона ячейки пространственного индекса (None — индекс не строится)
+    spatial_index_cell_size: Option<f32>,
+    
+    /// Точки привязки подписей вокруг блоков (None — не вычисляются)
+    annotation_anchors: Option<vertex_placement::AnnotationOptions>,
+    
+    /// Связывание входящих связей вершин с большой полустепенью захода (None — выключено)
+    edge_bundling: Option<vertex_placement::EdgeBundlingOptions>,
+    
+    /// Транзитивная редукция после разрыва циклов (None — выключена)
+    transitive_reduction: Option<transitive_reduction::TransitiveReductionOptions>,
+    
+    /// Сохранять self-loops при построении графа (иначе отбрасываются)
+    allow_self_loops: bool,
+    
+    /// Сохранять параллельные связи при построении графа (иначе дубликаты отбрасываются)
+    allow_parallel_edges: bool,
+    
+    /// Каталог CSR-файлов для очень больших графов (None — граф всегда в куче)
+    graph_mmap_dir: Option<std::path::PathBuf>,
+    
     /// Статистика
     stats: AlgorithmStats,
 }
@@ -133,11 +194,43 @@ impl HighPerformanceLayoutEngine {
             block_height: options.block_height,
             horizontal_gap: options.horizontal_gap,
             vertical_gap: options.vertical_gap,
+            layering: match crate::generated::LayeringStrategy::try_from(options.layering_strategy) {
+                Ok(crate::generated::LayeringStrategy::LayeringCoffmanGraham) => {
+                    vertex_placement::LayeringStrategy::CoffmanGraham
+                }
+                Ok(crate::generated::LayeringStrategy::LayeringNetworkSimplex) => {
+                    vertex_placement::LayeringStrategy::NetworkSimplex
+                }
+                Ok(crate::generated::LayeringStrategy::LayeringByProperty) => {
+                    vertex_placement::LayeringStrategy::ByProperty
+                }
+                _ => vertex_placement::LayeringStrategy::Bfs,
+            },
+            max_layer_width: options.max_layer_width as usize,
+            level_packing: match crate::generated::LevelPacking::try_from(options.level_packing) {
+                Ok(crate::generated::LevelPacking::Interval) => vertex_placement::LevelPacking::Interval,
+                _ => vertex_placement::LevelPacking::OnePerLevel,
+            },
+            vertex_sizes: options
+                .vertex_sizes
+                .iter()
+                .filter(|(_, size)| size.width > 0.0 && size.height > 0.0)
+                .map(|(vertex_id, size)| (vertex_id.clone(), (size.width, size.height)))
+                .collect(),
+            anchors: options
+                .vertex_anchors
+                .iter()
+                .map(|(vertex_id, anchor)| (vertex_id.clone(), (anchor.x, anchor.y)))
+                .collect(),
+            ..vertex_placement::PlacementConfig::default()
         };
 
         let opt_options = vertex_placement::OptimizationOptions {
             compact_layout: options.optimize_layout,
             max_iterations: 10,
+            minimize_crossings: options.optimize_layout,
+            deterministic: options.deterministic,
+            ..vertex_placement::OptimizationOptions::default()
         };
 
         let edge_options = vertex_placement::EdgeRoutingOptions::default();
@@ -157,6 +250,17 @@ impl HighPerformanceLayoutEngine {
             longest_path_finder,
             vertex_placer,
             memory_manager,
+            cycle_break_strategy: cycle_breaking::CycleBreakStrategy::default(),
+            component_packing: component_packing::ComponentPackingOptions::default(),
+            degenerate_thresholds: Some(degenerate::DegenerateThresholds::default()),
+            validation_verbosity: validation::ValidationVerbosity::default(),
+            spatial_index_cell_size: None,
+            annotation_anchors: None,
+            edge_bundling: None,
+            transitive_reduction: None,
+            allow_self_loops: false,
+            allow_parallel_edges: false,
+            graph_mmap_dir: None,
             stats: AlgorithmStats {
                 component_times: HashMap::new(),
                 memory_usage: HashMap::new(),
@@ -166,123 +270,312 @@ impl HighPerformanceLayoutEngine {
         })
     }
     
-    /// Валидация и фильтрация входных данных
-    fn validate_edges(&self, edges: &[GraphEdge]) -> Result<()> {
-        use tracing::info;
-        if edges.is_empty() {
-            return Err(anyhow::anyhow!("Граф не может быть пустым"));
-        }
-        
-        // Анализ данных для диагностики
-        let empty_source = edges.iter().filter(|e| e.source_id.trim().is_empty()).count();
-        let empty_target = edges.iter().filter(|e| e.target_id.trim().is_empty()).count();
-        let self_loops = edges.iter().filter(|e| e.source_id == e.target_id && !e.source_id.trim().is_empty()).count();
-        
-        info!("🔍 Диагностика данных:");
-        info!("   - Связей с пустым source_id: {}", empty_source);
-        info!("   - Связей с пустым target_id: {}", empty_target);
-        info!("   - Self-loops: {}", self_loops);
-        
-        // Показать первые несколько примеров данных
-        info!("📝 Первые 5 связей:");
-        for (i, edge) in edges.iter().take(5).enumerate() {
-            info!("   {}. '{}' -> '{}' (вес: {})", i+1, edge.source_id, edge.target_id, edge.weight);
-        }
-        
-        // Подсчёт валидных связей (исключаем пустые и self-loops)
-        let valid_edges: Vec<_> = edges.iter()
-            .filter(|e| !e.source_id.trim().is_empty() && !e.target_id.trim().is_empty())
-            .filter(|e| e.source_id != e.target_id)
-            .collect();
-            
-        if valid_edges.is_empty() {
-            return Err(anyhow::anyhow!("Нет валидных связей после фильтрации"));
-        }
-        
-        // Проверка на дубликаты среди валидных связей
-        let mut edge_set = std::collections::HashSet::new();
-        let mut duplicate_count = 0;
-        for edge in &valid_edges {
-            let edge_key = (&edge.source_id, &edge.target_id);
-            if !edge_set.insert(edge_key) {
-                duplicate_count += 1;
+    /// Установка способа разрыва циклов
+    pub fn set_cycle_break_strategy(&mut self, strategy: cycle_breaking::CycleBreakStrategy) {
+        self.cycle_break_strategy = strategy;
+    }
+    
+    /// Установка транзитивной редукции связей (None — без редукции)
+    pub fn set_transitive_reduction(&mut self, options: Option<transitive_reduction::TransitiveReductionOptions>) {
+        self.transitive_reduction = options;
+    }
+    
+    /// Мультиграф: сохранение self-loops и параллельных связей (маршруты для каждой)
+    pub fn set_multigraph(&mut self, allow_self_loops: bool, allow_parallel_edges: bool) {
+        self.allow_self_loops = allow_self_loops;
+        self.allow_parallel_edges = allow_parallel_edges;
+    }
+    
+    /// Хранение связей графов от `DEFAULT_MMAP_THRESHOLD` связей в mmap-файле в `dir`
+    pub fn set_graph_memory_mapping(&mut self, dir: Option<std::path::PathBuf>) {
+        self.graph_mmap_dir = dir;
+    }
+    
+    /// Установка параметров укладки несвязных компонент
+    pub fn set_component_packing(&mut self, options: component_packing::ComponentPackingOptions) {
+        self.component_packing = options;
+    }
+    
+    /// Установка правила упорядочивания вершин внутри слоя
+    pub fn set_tie_break(&mut self, tie_break: vertex_placement::TieBreak) {
+        self.vertex_placer.get_config_mut().tie_break = tie_break;
+    }
+    
+    /// Установка формы размещения вершин в слое
+    pub fn set_placement_mode(&mut self, mode: vertex_placement::PlacementMode) {
+        self.vertex_placer.get_config_mut().placement_mode = mode;
+    }
+    
+    /// Установка распределения вершин слоя по уровням
+    pub fn set_level_packing(&mut self, level_packing: vertex_placement::LevelPacking) {
+        self.vertex_placer.get_config_mut().level_packing = level_packing;
+    }
+    
+    /// Установка порогов вырожденной укладки (None отключает повторный прогон)
+    pub fn set_degenerate_fallback(&mut self, thresholds: Option<degenerate::DegenerateThresholds>) {
+        self.degenerate_thresholds = thresholds;
+    }
+    
+    /// Установка вертикальной балансировки слоёв (None — слои выровнены по верху)
+    pub fn set_layer_balance(&mut self, balance: Option<vertex_placement::LayerBalance>) {
+        let opt_options = self.vertex_placer.get_opt_options_mut();
+        opt_options.balance_layers = balance.is_some();
+        opt_options.balance_mode = balance.unwrap_or_default();
+    }
+    
+    /// Упорядочивание вершин слоя по центроиду соседей при начальном размещении
+    pub fn set_centroid_ordering(&mut self, enabled: bool) {
+        self.vertex_placer.get_opt_options_mut().centroid_ordering = enabled;
+    }
+    
+    /// Установка закреплённых слоёв и нижних границ слоёв вершин по ID
+    ///
+    /// Пока они заданы, слои назначаются BFS-релаксацией с их учётом, а
+    /// стратегия слоёв и ограничение ширины слоя не применяются.
+    pub fn set_layer_constraints(
+        &mut self,
+        layer_constraints: HashMap<String, i32>,
+        layer_lower_bounds: HashMap<String, i32>,
+    ) {
+        let config = self.vertex_placer.get_config_mut();
+        config.layer_constraints = layer_constraints;
+        config.layer_lower_bounds = layer_lower_bounds;
+    }
+    
+    /// Порог приближённого упорядочивания огромных слоёв (0 — всегда точно)
+    pub fn set_approximate_layer_threshold(&mut self, threshold: usize) {
+        self.vertex_placer.get_opt_options_mut().approximate_ordering = (threshold > 0).then(|| {
+            vertex_placement::ApproximateOrdering {
+                layer_threshold: threshold,
+                ..vertex_placement::ApproximateOrdering::default()
             }
+        });
+    }
+    
+    /// Установка подробности диагностики валидации входных связей
+    pub fn set_validation_verbosity(&mut self, verbosity: validation::ValidationVerbosity) {
+        self.validation_verbosity = verbosity;
+    }
+    
+    /// Разбиение длинных связей на цепочки dummy-вершин (не больше `max_dummy_vertices`)
+    pub fn set_dummy_vertices(&mut self, enabled: bool, max_dummy_vertices: usize) {
+        let config = self.vertex_placer.get_config_mut();
+        config.dummy_vertices = enabled;
+        config.max_dummy_vertices = max_dummy_vertices;
+    }
+    
+    /// Обход блоков связями, пересекающими промежуточные слои
+    pub fn set_avoid_vertices(&mut self, enabled: bool) {
+        self.vertex_placer.get_edge_options_mut().avoid_vertices = enabled;
+    }
+    
+    /// Установка канальной трассировки: связи в одном зазоре слоя получают
+    /// дорожки не ближе `spacing` друг к другу (None или 0 — без дорожек)
+    pub fn set_edge_channels(&mut self, spacing: Option<f32>) {
+        self.vertex_placer.get_edge_options_mut().channel_spacing =
+            spacing.filter(|&spacing| spacing > 0.0);
+    }
+    
+    /// Установка точек привязки подписей (None — не вычислять)
+    pub fn set_annotation_anchors(&mut self, options: Option<vertex_placement::AnnotationOptions>) {
+        self.annotation_anchors = options;
+    }
+    
+    /// Установка связывания входящих связей в общие стволы (None — без связывания)
+    pub fn set_edge_bundling(&mut self, options: Option<vertex_placement::EdgeBundlingOptions>) {
+        self.edge_bundling = options.filter(|options| options.min_fan_in > 0);
+    }
+    
+    /// Распределение концов связей по левой и правой сторонам блоков (портам)
+    pub fn set_edge_ports(&mut self, enabled: bool) {
+        self.vertex_placer.get_edge_options_mut().ports = enabled;
+    }
+    
+    /// Установка упрощения маршрутов по Дугласу–Пекеру (None или 0 — без упрощения)
+    pub fn set_path_simplification(&mut self, tolerance: Option<f32>) {
+        self.vertex_placer.get_edge_options_mut().simplify_tolerance =
+            tolerance.filter(|&tolerance| tolerance > 0.0);
+    }
+    
+    /// Установка сглаживания маршрутов кривыми Безье (None или 0 — ломаные)
+    pub fn set_edge_smoothing(&mut self, smoothing: Option<f32>) {
+        self.vertex_placer.get_edge_options_mut().smoothing =
+            smoothing.filter(|&smoothing| smoothing > 0.0).map(|smoothing| smoothing.min(1.0));
+    }
+    
+    /// Установка пространственного индекса по итоговым позициям (None — не строить)
+    pub fn set_spatial_index(&mut self, cell_size: Option<f32>) {
+        self.spatial_index_cell_size = cell_size.filter(|&size| size > 0.0);
+    }
+    
+    /// Размещение вершин: целиком или по компонентам с последующей упаковкой
+    async fn place_graph(
+        &mut self,
+        graph: &mut crate::data_structures::Graph,
+        topo_order: &[crate::data_structures::VertexId],
+        pack_components: bool,
+        options: &LayoutOptions,
+    ) -> Result<(Vec<VertexPosition>, component_packing::EdgePaths, vertex_placement::PlacementStats)> {
+        if pack_components {
+            let components = component_packing::split_components(graph)?;
+            let layouts = component_packing::layout_components(
+                components,
+                &self.vertex_placer,
+                self.component_packing.parallel,
+            ).await?;
+            let packing = self.component_packing.with_request(options);
+            let packed = component_packing::pack_components(
+                layouts,
+                &packing,
+                options.block_width,
+                options.block_height,
+            );
+            Ok((packed.positions, packed.edge_paths, packed.stats))
+        } else {
+            let (positions, edge_paths) = self.vertex_placer.place_vertices(
+                graph,
+                topo_order,
+            ).await?;
+            Ok((positions, edge_paths, self.vertex_placer.get_stats().clone()))
         }
-        
-        info!("📊 Статистика валидации связей:");
-        info!("   - Всего связей: {}", edges.len());
-        info!("   - Валидных связей: {}", valid_edges.len());
-        info!("   - Уникальных связей: {}", edge_set.len());
-        
-        if valid_edges.len() < edges.len() {
-            info!("⚠️ Отфильтровано {} невалидных связей", edges.len() - valid_edges.len());
-        }
-        
-        if duplicate_count > 0 {
-            info!("⚠️ Найдено {} дублирующих связей", duplicate_count);
-        }
-        
-        Ok(())
+    }
+    
+    /// Валидация входных данных за один проход (дубликаты считает GraphBuilder)
+    fn validate_edges(&self, edges: &[GraphEdge]) -> Result<validation::EdgeValidationReport> {
+        validation::validate_edges(edges, self.validation_verbosity)
     }
     
     /// Построение графа из связей
-    fn build_graph(&self, edges: &[GraphEdge]) -> Result<crate::data_structures::Graph> {
-        use crate::data_structures::GraphBuilder;
-        use std::collections::HashSet;
+    fn build_graph(
+        &self,
+        edges: &[GraphEdge],
+        deterministic: bool,
+    ) -> Result<(crate::data_structures::Graph, crate::data_structures::IngestStats)> {
+        use crate::data_structures::{GraphBuilder, DEFAULT_MMAP_THRESHOLD};
         use tracing::info;
 
-        let mut builder = GraphBuilder::new();
-        let mut unique_edges = HashSet::new();
-        let mut added_count = 0;
+        crate::failure_injection::check(
+            crate::failure_injection::FaultPoint::Allocation,
+            &format!("граф из {} связей", edges.len()),
+        )?;
+        let builder = GraphBuilder::new()
+            .deterministic(deterministic)
+            .allow_self_loops(self.allow_self_loops)
+            .allow_parallel_edges(self.allow_parallel_edges)
+            .memory_mapped(self.graph_mmap_dir.clone());
 
-        // Добавление связей с фильтрацией и дедупликацией
-        for edge in edges {
-            // Пропускаем пустые и self-loop связи
-            if edge.source_id.trim().is_empty() || edge.target_id.trim().is_empty() {
-                continue;
-            }
-            if edge.source_id == edge.target_id {
-                continue;
-            }
+        // Граф от порога mmap раскладывается прямо в CSR-файл за два прохода по
+        // связям, без копии строк и списков смежности в куче
+        let (graph, stats) = if self.graph_mmap_dir.is_some() && edges.len() >= DEFAULT_MMAP_THRESHOLD {
+            builder.build_mmap_streamed(|| {
+                Ok(edges
+                    .iter()
+                    .map(|edge| Ok((edge.source_id.as_str(), edge.target_id.as_str(), edge.weight))))
+            })?
+        } else {
+            let builder = builder.sharded();
 
-            // Пропускаем дубликаты
-            let edge_key = (&edge.source_id, &edge.target_id);
-            if !unique_edges.insert(edge_key) {
-                continue;
-            }
+            // Пустые ID, self-loops и дубликаты (если они не разрешены) отсеивает и подсчитывает строитель;
+            // связи принимаются параллельно, позиция во входных данных сохраняет их порядок
+            scheduler::install(|| {
+                use rayon::prelude::*;
+                edges.par_iter().enumerate().for_each(|(position, edge)| {
+                    // УНИФИЦИРОВАННАЯ СЕМАНТИКА SOURCE/TARGET:
+                    //
+                    // SOURCE (left, слева):
+                    // - Старая cited статья из reference list
+                    // - Получает НИЗКИЕ слои (0, 1, 2...) - слева на графе
+                    // - Это наиболее цитируемые статьи
+                    //
+                    // TARGET (right, справа):
+                    // - Новая citing статья
+                    // - Получает ВЫСОКИЕ слои - справа на графе
+                    // - Это статьи которые цитируют SOURCE
+                    //
+                    // Направление: SOURCE -> TARGET (старая -> новая)
+                    //
+                    // В Neo4j хранится ТАК ЖЕ: SOURCE -> TARGET (cited -> citing)
+                    // НЕ разворачиваем! Используем как есть.
+                    //
+                    // BFS корректно работает с этим направлением:
+                    // - Вершины без входящих рёбер (старые, SOURCE) получают слой 0
+                    // - Вершины, цитирующие их (новые, TARGET) получают более высокие слои
+                    //
+                    builder.ingest_edge_at(
+                        position,
+                        &edge.source_id,  // SOURCE: cited reference (старая статья)
+                        &edge.target_id,  // TARGET: citing article (новая статья)
+                        edge.weight,
+                    );
+                });
+            });
 
-            // УНИФИЦИРОВАННАЯ СЕМАНТИКА SOURCE/TARGET:
-            //
-            // SOURCE (left, слева):
-            // - Старая cited статья из reference list
-            // - Получает НИЗКИЕ слои (0, 1, 2...) - слева на графе
-            // - Это наиболее цитируемые статьи
-            //
-            // TARGET (right, справа):
-            // - Новая citing статья
-            // - Получает ВЫСОКИЕ слои - справа на графе
-            // - Это статьи которые цитируют SOURCE
-            //
-            // Направление: SOURCE -> TARGET (старая -> новая)
-            //
-            // В Neo4j хранится ТАК ЖЕ: SOURCE -> TARGET (cited -> citing)
-            // НЕ разворачиваем! Используем как есть.
-            //
-            // BFS корректно работает с этим направлением:
-            // - Вершины без входящих рёбер (старые, SOURCE) получают слой 0
-            // - Вершины, цитирующие их (новые, TARGET) получают более высокие слои
-            //
-            builder.add_edge(
-                edge.source_id.clone(),  // SOURCE: cited reference (старая статья)
-                edge.target_id.clone(),  // TARGET: citing article (новая статья)
-                edge.weight,
-            )?;
-            added_count += 1;
+            builder.build_with_stats()?
+        };
+
+        info!("{}", Msg::IngestStatsHeader);
+        info!("{}", Msg::IngestEdgesSeen { count: stats.edges_seen });
+        info!("{}", Msg::IngestInvalidIds { count: stats.invalid_ids });
+        info!("{}", Msg::IngestSelfLoops { count: stats.self_loops });
+        info!("{}", Msg::IngestDuplicates { count: stats.duplicates });
+        if stats.self_loops_kept + stats.parallel_edges > 0 {
+            info!("{}", Msg::IngestMultigraph {
+                self_loops: stats.self_loops_kept,
+                parallel: stats.parallel_edges,
+            });
+        }
+        if stats.dropped() > 0 {
+            info!("{}", Msg::IngestDropped { count: stats.dropped() });
+        }
+        info!("{}", Msg::IngestEdgesAdded { count: stats.edges_added });
+        if graph.is_mmap() {
+            info!("{}", Msg::GraphMapped { megabytes: graph.mapped_bytes() / (1024 * 1024) });
         }
 
-        info!("🏗️ Добавлено {} уникальных связей в граф (SOURCE->TARGET, cited->citing)", added_count);
+        Ok((graph, stats))
+    }
+}
+
+/// Связи окрестности `seed_ids` радиуса `depth` (см. `Graph::neighborhood`)
+///
+/// Неизвестные seed-вершины пропускаются; ошибка, если не найдена ни одна.
+fn neighborhood_edges(edges: Vec<GraphEdge>, seed_ids: &[String], depth: usize) -> Result<Vec<GraphEdge>> {
+    use crate::data_structures::GraphBuilder;
+    
+    let mut builder = GraphBuilder::new().csr_threshold(usize::MAX);
+    for edge in &edges {
+        builder.ingest_edge(&edge.source_id, &edge.target_id, edge.weight);
+    }
+    let graph = builder.build()?;
+    let seeds: Vec<_> = seed_ids.iter().filter_map(|id| graph.vertex_handle(id)).collect();
+    if seeds.is_empty() {
+        return Err(anyhow::anyhow!("{}", Msg::SeedVerticesNotFound { seeds: &seed_ids }));
+    }
+    
+    let subgraph = graph.neighborhood(&seeds, depth);
+    Ok(edges
+        .into_iter()
+        .filter(|edge| {
+            subgraph.graph.contains_vertex(&edge.source_id) && subgraph.graph.contains_vertex(&edge.target_id)
+        })
+        .collect())
+}
 
-        builder.build()
+/// Форма графа задачи для `LayoutStatistics`
+fn graph_shape_metrics(stats: &crate::data_structures::GraphStatistics) -> crate::generated::GraphShapeMetrics {
+    let degrees = |distribution: &crate::data_structures::DegreeDistribution| crate::generated::DegreeDistribution {
+        p50: distribution.p50 as i32,
+        p90: distribution.p90 as i32,
+        p99: distribution.p99 as i32,
+        max: distribution.max as i32,
+    };
+    crate::generated::GraphShapeMetrics {
+        in_degree: Some(degrees(&stats.in_degree)),
+        out_degree: Some(degrees(&stats.out_degree)),
+        largest_component: stats.largest_component as i64,
+        sources: stats.sources as i64,
+        sinks: stats.sinks as i64,
+        approximate_diameter: stats.approximate_diameter as i32,
     }
 }
 
@@ -294,80 +587,328 @@ impl LayoutAlgorithm for HighPerformanceLayoutEngine {
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<LayoutResult>> + Send + 'a>> {
         Box::pin(async move {
         use std::time::Instant;
-        use tracing::info;
+        use tracing::{info, warn};
         
         let start_time = Instant::now();
         
-        info!("=== ШАГ 0: ИНИЦИАЛИЗАЦИЯ УКЛАДКИ ===");
-        info!("📊 Входные данные: {} связей", edges.len());
+        info!("{}", Msg::LayoutInitHeader);
+        info!("{}", Msg::InputEdges { count: edges.len() });
         
         // 1. Валидация входных данных
-        info!("🔍 Валидация входных данных...");
-        self.validate_edges(&edges)?;
-        info!("✅ Валидация успешна");
+        info!("{}", Msg::ValidatingInput);
+        let mut validation_report = self.validate_edges(&edges)?;
+        info!("{}", Msg::InputValid);
+        
+        // Только окрестность seed-вершин, если она запрошена
+        let edges = if options.seed_ids.is_empty() {
+            edges
+        } else {
+            let total = edges.len();
+            let edges = neighborhood_edges(edges, &options.seed_ids, options.neighborhood_depth as usize)?;
+            info!("{}", Msg::SeedNeighborhood {
+                seeds: options.seed_ids.len(),
+                depth: options.neighborhood_depth,
+                kept: edges.len(),
+                total,
+            });
+            edges
+        };
+        
+        // Кластеры собираются до разрыва циклов: удалённая связь может быть единственной у вершины
+        let clusters = vertex_placement::clusters_from_edges(&edges);
+        if !clusters.is_empty() {
+            info!("{}", Msg::ClusteredVertices { count: clusters.len() });
+        }
+        let years = vertex_placement::years_from_edges(&edges);
+        if !years.is_empty() {
+            info!("{}", Msg::VerticesWithYear { count: years.len() });
+        }
+        
+        // 2. Разрыв циклов (реальные данные цитирования не всегда ацикличны)
+        info!("{}", Msg::BreakingCycles);
+        let input_edge_count = edges.len();
+        let cycle_result = cycle_breaking::break_cycles(edges, self.cycle_break_strategy);
+        if cycle_result.had_cycles() {
+            info!("{}", Msg::CyclesBroken {
+                components: cycle_result.cyclic_components,
+                edges: cycle_result.broken_edges.len(),
+                strategy: &self.cycle_break_strategy,
+            });
+        } else {
+            info!("{}", Msg::NoCycles);
+        }
+        let mut layout_phases = phases::LayoutPhases {
+            cycle_breaking: phases::CycleBreakingPhase::from_result(input_edge_count, &cycle_result),
+            ..phases::LayoutPhases::default()
+        };
+        let edges = match self.transitive_reduction {
+            Some(reduction_options) => {
+                info!("{}", Msg::ReducingTransitively);
+                let reduction = transitive_reduction::reduce(cycle_result.edges, reduction_options);
+                info!("{}", Msg::TransitiveEdgesRemoved {
+                    count: reduction.stats.removed_edges,
+                    exact: reduction.stats.exact,
+                });
+                validation_report.transitive_reduction = Some(reduction.stats);
+                reduction.edges
+            }
+            None => cycle_result.edges,
+        };
+        
+        let tie_break = self.vertex_placer.get_config().tie_break;
+        if tie_break == vertex_placement::TieBreak::InputOrder {
+            let keys = vertex_placement::input_order_keys(
+                edges.iter().flat_map(|e| [&e.source_id, &e.target_id]),
+            );
+            self.vertex_placer.set_tie_break_keys(keys);
+        }
         
-        // 2. Построение графа
-        info!("=== ШАГ 1: ПОСТРОЕНИЕ ГРАФА ===");
-        info!("🏗️ Построение внутреннего представления графа...");
-        let graph = self.build_graph(&edges)?;
-        info!("✅ Граф построен: {} вершин, {} связей", graph.vertex_count(), graph.edge_count());
+        // 3. Построение графа
+        info!("{}", Msg::GraphBuildHeader);
+        info!("{}", Msg::BuildingGraph);
+        // Опции приходят с каждым запросом, движок общий
+        self.vertex_placer.get_opt_options_mut().deterministic = options.deterministic;
+        let (mut graph, ingest_stats) = self.build_graph(&edges, options.deterministic)?;
+        // Кластеры и годы — атрибуты вершин, их читают назначение слоёв и размещение
+        vertex_placement::set_vertex_clusters(&mut graph, &clusters);
+        vertex_placement::set_vertex_years(&mut graph, &years);
+        layout_phases.ingest = ingest_stats;
+        let component_count = graph.component_count();
+        let graph_stats = graph.get_statistics();
+        info!("{}", Msg::GraphBuilt {
+            vertices: graph.vertex_count(),
+            edges: graph.edge_count(),
+            components: component_count,
+        });
+        let percentiles = |d: &crate::data_structures::DegreeDistribution| [d.p50, d.p90, d.p99, d.max];
+        info!("{}", Msg::DegreeStats {
+            out_degree: percentiles(&graph_stats.out_degree),
+            in_degree: percentiles(&graph_stats.in_degree),
+            sources: graph_stats.sources,
+            sinks: graph_stats.sinks,
+            largest_component: graph_stats.largest_component,
+            diameter: graph_stats.approximate_diameter,
+        });
         
-        // 3. Топологическая сортировка с параллелизмом
-        info!("=== ШАГ 2: ТОПОЛОГИЧЕСКАЯ СОРТИРОВКА ===");
-        info!("🔄 Выполнение параллельной топологической сортировки...");
+        // 4. Топологическая сортировка с параллелизмом
+        info!("{}", Msg::TopoSortHeader);
+        info!("{}", Msg::TopoSorting);
         let topo_start = Instant::now();
         let topo_order = self.topo_sorter.compute_parallel(&graph).await?;
         let topo_time = topo_start.elapsed().as_millis() as u64;
-        info!("✅ Топологическая сортировка завершена за {} мс", topo_time);
-        info!("📊 Упорядочено {} вершин", topo_order.order.len());
+        info!("{}", Msg::TopoSortDone { ms: topo_time });
+        info!("{}", Msg::VerticesOrdered { count: topo_order.order.len() });
+        layout_phases.topo_sort = phases::TopoSortPhase::from_result(topo_time, &topo_order);
         
-        // 4. Поиск longest path с SIMD оптимизацией
-        info!("=== ШАГ 3: ПОИСК САМОГО ДЛИННОГО ПУТИ ===");
-        info!("🛤️ Поиск самого длинного пути с SIMD оптимизацией...");
+        // 5. Поиск longest path с SIMD оптимизацией
+        info!("{}", Msg::LongestPathHeader);
+        info!("{}", Msg::FindingLongestPath);
         let lp_start = Instant::now();
-        let longest_path = self.longest_path_finder.find_simd(&graph, &topo_order.order).await?;
+        let (longest_path, simd_path) = self.longest_path_finder.find_simd_with_path(&graph, &topo_order.order).await?;
         let lp_time = lp_start.elapsed().as_millis() as u64;
-        info!("✅ Самый длинный путь найден за {} мс", lp_time);
-        info!("📏 Длина самого длинного пути: {} вершин", longest_path.len());
+        info!("{}", Msg::LongestPathFound { ms: lp_time });
+        info!("{}", Msg::LongestPathLength { vertices: longest_path.len() });
+        layout_phases.layering.time_ms = lp_time;
+        layout_phases.layering.longest_path_length = longest_path.len();
         
-        // 5. Размещение вершин с оптимизацией пространства
-        info!("=== ШАГ 4: РАЗМЕЩЕНИЕ ВЕРШИН ===");
-        info!("📍 Размещение вершин с оптимизацией пространства...");
+        // 6. Размещение вершин с оптимизацией пространства
+        info!("{}", Msg::PlacementHeader);
+        info!("{}", Msg::PlacingWithOptimization);
         let placement_start = Instant::now();
-        let (positions, edge_paths) = self.vertex_placer.place_vertices(
+        let pack_components = self.component_packing.enabled && component_count > 1;
+        if pack_components {
+            info!("{}", Msg::PackingComponents {
+                count: component_count,
+                parallel: self.component_packing.parallel,
+                gap: self.component_packing.gap,
+            });
+        }
+        let (mut positions, mut edge_paths, mut placement_stats) = self
+            .place_graph(&mut graph, &topo_order.order, pack_components, options)
+            .await?;
+        
+        // Проверка на вырожденность и повторный прогон с другой стратегией слоёв
+        // (при закреплённых слоях стратегия не применяется, повтор бесполезен)
+        let mut layering_fallback = None;
+        let pinned = {
+            let config = self.vertex_placer.get_config();
+            !config.layer_constraints.is_empty() || !config.layer_lower_bounds.is_empty()
+        };
+        if let Some(thresholds) = self.degenerate_thresholds.clone().filter(|_| !pinned) {
+            let distribution = degenerate::LayerDistribution::from_layers(
+                positions
+                    .iter()
+                    .filter(|p| graph.contains_vertex(&p.article_id))
+                    .map(|p| p.layer),
+            );
+            if let Some(reason) = degenerate::detect_degenerate(&distribution, longest_path.len(), &thresholds) {
+                let original = self.vertex_placer.get_config().clone();
+                let (strategy, width) = degenerate::fallback_strategy(original.layering, graph.vertex_count());
+                warn!("{}", Msg::DegenerateLayoutRetry { reason: &reason, strategy: &strategy, width });
+                {
+                    let config = self.vertex_placer.get_config_mut();
+                    config.layering = strategy;
+                    config.max_layer_width = width;
+                }
+                let retry = self
+                    .place_graph(&mut graph, &topo_order.order, pack_components, options)
+                    .await;
+                *self.vertex_placer.get_config_mut() = original.clone();
+                let (retry_positions, retry_paths, retry_stats) = retry?;
+                
+                let retry_share = degenerate::LayerDistribution::from_layers(
+                    retry_positions
+                        .iter()
+                        .filter(|p| graph.contains_vertex(&p.article_id))
+                        .map(|p| p.layer),
+                )
+                .max_layer_share()
+                .map_or(1.0, |(_, share)| share);
+                let original_share = distribution.max_layer_share().map_or(1.0, |(_, share)| share);
+                let accepted = retry_share < original_share;
+                if accepted {
+                    info!("{}", Msg::LayeringFallbackAccepted {
+                        strategy: &strategy,
+                        from_percent: original_share as f32 * 100.0,
+                        to_percent: retry_share as f32 * 100.0,
+                    });
+                    positions = retry_positions;
+                    edge_paths = retry_paths;
+                    placement_stats = retry_stats;
+                } else {
+                    warn!("{}", Msg::LayeringFallbackRejected);
+                }
+                layering_fallback = Some(degenerate::LayeringFallback {
+                    reason,
+                    from: format!("{:?}", original.layering),
+                    to: format!("{:?}", strategy),
+                    accepted,
+                });
+            }
+        }
+        let placement_time = placement_start.elapsed().as_millis() as u64;
+        info!("{}", Msg::PlacementDone { ms: placement_time });
+        info!("{}", Msg::VerticesPlacedTotal { count: positions.len() });
+        layout_phases.apply_placement_stats(
+            &placement_stats,
+            placement_time,
+            positions.iter().map(|p| p.level).max().unwrap_or(0),
+        );
+        self.stats
+            .memory_usage
+            .insert("placement_arena".to_string(), placement_stats.arena_peak_bytes as u64);
+        let edge_bundles = match self.edge_bundling {
+            Some(ref bundling) => vertex_placement::bundle_edges(
+                &mut edge_paths,
+                &positions,
+                self.vertex_placer.get_config(),
+                bundling,
+            ),
+            None => HashMap::new(),
+        };
+        let layers: HashMap<&str, i32> = positions.iter().map(|p| (p.article_id.as_str(), p.layer)).collect();
+        let mut routing_report = vertex_placement::get_edge_statistics(&edge_paths, |id| layers.get(id).copied());
+        
+        // Точное число пересечений итоговой укладки (метрика качества): длинные
+        // связи учитываются в каждом промежуточном слое по своим маршрутам
+        let crossings = vertex_placement::routed_crossings(
+            positions.iter().map(|p| (p.article_id.as_str(), p.layer, p.x, p.y)),
             &graph,
-            &longest_path,
+            &edge_paths,
+            self.vertex_placer.get_config().block_width,
+        );
+        routing_report.set_crossings(&crossings.per_edge);
+        let edge_crossings = crossings.total;
+        layout_phases.routing = phases::RoutingPhase::from_paths(&edge_paths);
+        layout_phases.routing.edges_detoured = placement_stats.edges_detoured;
+        layout_phases.routing.channel_shifts = placement_stats.channel_shifts;
+        layout_phases.routing.points_removed = placement_stats.path_points_removed;
+        layout_phases.routing.edges_bundled = edge_bundles.len();
+        layout_phases.layering.components = phases::ComponentDepth::top_components(
+            &mut graph,
             &topo_order.order,
-        ).await?;
-        let placement_time = placement_start.elapsed().as_millis() as u64;
-        info!("✅ Размещение вершин завершено за {} мс", placement_time);
-        info!("📌 Размещено {} вершин", positions.len());
+            &positions,
+            phases::TOP_COMPONENTS,
+        );
+        if component_count > 1 {
+            if let Some(deepest) = layout_phases.layering.components.first() {
+                info!("{}", Msg::DeepestComponent {
+                    representative: &deepest.representative,
+                    vertices: deepest.vertices,
+                    path: deepest.longest_path_length,
+                    layers: deepest.max_layer + 1,
+                });
+            }
+        }
         
         let total_time = start_time.elapsed().as_millis() as u64;
 
-        let edge_paths_payload = if edge_paths.is_empty() {
-            None
+        // Порядок маршрутов не зависит от хеширования
+        let smoothing = self.vertex_placer.get_edge_options().smoothing;
+        let ports = self.vertex_placer.get_edge_options().ports;
+        // Смещение порта — от вертикального центра блока до конца маршрута
+        let centers: HashMap<&str, f32> = if ports {
+            let placement_config = self.vertex_placer.get_config();
+            positions
+                .iter()
+                .map(|p| (p.article_id.as_str(), p.y + placement_config.vertex_size(&p.article_id).1 / 2.0))
+                .collect()
         } else {
-            let mut map = HashMap::new();
-            for ((src, dst), points) in &edge_paths {
-                let key = format!("{}->{}", src, dst);
-                let value: Vec<[f32; 2]> = points.iter().map(|(x, y)| [*x, *y]).collect();
-                map.insert(key, value);
-            }
-            Some(serde_json::to_string(&map)?)
+            HashMap::new()
+        };
+        let port_offset = |vertex_id: &str, end: Option<&(f32, f32)>| match (centers.get(vertex_id), end) {
+            (Some(center), Some(&(_, y))) => y - center,
+            _ => 0.0,
         };
+        let mut result_edge_paths: Vec<crate::neo4j::EdgePath> = Vec::with_capacity(edge_paths.len());
+        for (edge, points) in &edge_paths {
+            // Параллельные связи (мультиграф) расходятся веером внутри меньшего из двух блоков
+            let count = graph.edge_multiplicity(&edge.0, &edge.1).max(1);
+            let copies = if count > 1 {
+                let placement_config = self.vertex_placer.get_config();
+                let height = placement_config.vertex_size(&edge.0).1.min(placement_config.vertex_size(&edge.1).1);
+                vertex_placement::parallel_edge_paths(points, count, height / (2.0 * count as f32))
+            } else {
+                vec![points.clone()]
+            };
+            // Копии параллельных связей — сдвиг исходного маршрута, метрики у них общие
+            let route = routing_report.get(&edge.0, &edge.1);
+            for (edge_index, points) in copies.into_iter().enumerate() {
+                result_edge_paths.push(crate::neo4j::EdgePath {
+                    source_id: edge.0.clone(),
+                    target_id: edge.1.clone(),
+                    bundle_id: edge_bundles.get(edge).cloned(),
+                    control_points: smoothing
+                        .map(|smoothing| vertex_placement::bezier_control_points(&points, smoothing))
+                        .unwrap_or_default(),
+                    source_port: port_offset(&edge.0, points.first()),
+                    target_port: port_offset(&edge.1, points.last()),
+                    edge_index,
+                    length: route.map_or(0.0, |r| r.length),
+                    bends: route.map_or(0, |r| r.bends),
+                    layer_span: route.map_or(0, |r| r.layer_span),
+                    crossings: route.map_or(0, |r| r.crossings),
+                    points,
+                });
+            }
+        }
+        result_edge_paths.sort_by(|a, b| {
+            (&a.source_id, &a.target_id, a.edge_index).cmp(&(&b.source_id, &b.target_id, b.edge_index))
+        });
         
-        info!("=== ШАГ 5: ФИНАЛИЗАЦИЯ ===");
-        info!("📊 Создание статистики и метаданных...");
+        info!("{}", Msg::FinalizationHeader);
+        info!("{}", Msg::BuildingStatistics);
+        
+        info!("{}", Msg::EdgeCrossings { count: edge_crossings });
         
         // Создание статистики
         let statistics = LayoutStatistics {
             processing_time_ms: total_time as i64,
             vertices_processed: graph.vertex_count() as i64,
-            edges_processed: edges.len() as i64,
+            edges_processed: input_edge_count as i64,
             iterations_completed: 1,
-            memory_used_bytes: self.memory_manager.get_memory_usage() as i64,
-            connected_components: 1, // Упрощенная версия
+            memory_used_bytes: (self.memory_manager.get_memory_usage() + placement_stats.arena_peak_bytes) as i64,
+            connected_components: component_count as i32,
             longest_path_length: longest_path.len() as i32,
             vertices_per_second: (graph.vertex_count() as f32 / total_time as f32 * 1000.0),
             algorithm_metrics: Some(crate::generated::AlgorithmMetrics {
@@ -375,24 +916,92 @@ impl LayoutAlgorithm for HighPerformanceLayoutEngine {
                 topo_sort_time_ms: topo_time as i64,
                 longest_path_time_ms: lp_time as i64,
                 placement_time_ms: placement_time as i64,
-                layers_used: self.vertex_placer.get_stats().layers_used as i32,
+                layers_used: placement_stats.layers_used as i32,
                 max_level: positions.iter().map(|p| p.level).max().unwrap_or(0),
-                space_efficiency: if self.vertex_placer.get_stats().vertices_placed > 0 {
-                    self.vertex_placer.get_stats().vertices_placed as f32 /
-                    (self.vertex_placer.get_stats().layers_used * self.vertex_placer.get_stats().vertices_placed / self.vertex_placer.get_stats().layers_used) as f32
+                space_efficiency: if placement_stats.vertices_placed > 0 {
+                    placement_stats.vertices_placed as f32 /
+                    (placement_stats.layers_used * placement_stats.vertices_placed / placement_stats.layers_used) as f32
                 } else {
                     0.0
                 },
             }),
+            edge_crossings: edge_crossings as i64,
+            routing: Some(crate::generated::EdgeRoutingMetrics {
+                edges_routed: routing_report.edges.len() as i64,
+                total_length: routing_report.total_length,
+                average_length: routing_report.average_length(),
+                max_length: routing_report.max_length,
+                total_bends: routing_report.total_bends as i64,
+                max_bends: routing_report.max_bends as i32,
+                max_layer_span: routing_report.max_layer_span,
+                max_crossings: routing_report.max_crossings as i64,
+            }),
+            graph_shape: Some(graph_shape_metrics(&graph_stats)),
         };
         
-        // Метаданные
+        // Метаданные (SIMD отмечается, только если векторное ядро действительно выполнилось)
+        let mut optimizations_used = vec![
+            "Parallel Processing".to_string(),
+            "Memory Optimization".to_string(),
+        ];
+        if simd_path.is_vectorized() {
+            optimizations_used.insert(0, "SIMD".to_string());
+        }
+        if cycle_result.had_cycles() {
+            optimizations_used.push("Cycle Breaking".to_string());
+        }
+        if validation_report.transitive_reduction.map_or(false, |stats| stats.removed_edges > 0) {
+            optimizations_used.push("Transitive Reduction".to_string());
+        }
+        if pack_components {
+            optimizations_used.push("Component Packing".to_string());
+        }
+        if !clusters.is_empty() {
+            optimizations_used.push("Cluster Grouping".to_string());
+        }
+        if layering_fallback.as_ref().map_or(false, |f| f.accepted) {
+            optimizations_used.push("Layering Fallback".to_string());
+        }
+        if placement_stats.approximate_layers > 0 {
+            optimizations_used.push("Approximate Ordering".to_string());
+        }
+        if !edge_bundles.is_empty() {
+            optimizations_used.push("Edge Bundling".to_string());
+        }
+        if ports {
+            optimizations_used.push("Edge Ports".to_string());
+        }
+        if placement_stats.channel_shifts > 0 {
+            optimizations_used.push("Channel Routing".to_string());
+        }
+        if placement_stats.path_points_removed > 0 {
+            optimizations_used.push("Path Simplification".to_string());
+        }
+        if smoothing.is_some() {
+            optimizations_used.push("Edge Smoothing".to_string());
+        }
+        let spatial_index = match self.spatial_index_cell_size {
+            Some(cell_size) => {
+                let placement_config = self.vertex_placer.get_config();
+                let index = crate::data_structures::SpatialIndex::from_rects(
+                    cell_size,
+                    positions.iter().map(|p| {
+                        let (width, height) = placement_config
+                            .vertex_sizes
+                            .get(&p.article_id)
+                            .copied()
+                            .unwrap_or((placement_config.block_width, placement_config.block_height));
+                        (p.article_id.clone(), p.x, p.y, width, height)
+                    }),
+                )?;
+                info!("{}", Msg::SpatialIndexBuilt { cells: index.cell_count(), cell_size });
+                optimizations_used.push("Spatial Index".to_string());
+                Some(index)
+            }
+            None => None,
+        };
         let metadata = AlgorithmMetadata {
-            optimizations_used: vec![
-                "SIMD".to_string(),
-                "Parallel Processing".to_string(),
-                "Memory Optimization".to_string(),
-            ],
+            optimizations_used,
             complexity: "O((V + E) / P + V log V)".to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             parameters: {
@@ -400,25 +1009,84 @@ impl LayoutAlgorithm for HighPerformanceLayoutEngine {
                 params.insert("chunk_size".to_string(), options.chunk_size.to_string());
                 params.insert("max_workers".to_string(), options.max_workers.to_string());
                 params.insert("simd_enabled".to_string(), options.enable_simd.to_string());
-                if let Some(ref payload) = edge_paths_payload {
-                    params.insert("edge_paths".to_string(), payload.clone());
+                params.insert("simd_path".to_string(), simd_path.name().to_string());
+                params.insert("tie_break".to_string(), format!("{:?}", tie_break));
+                params.insert("layering".to_string(), format!("{:?}", self.vertex_placer.get_config().layering));
+                params.insert("placement_mode".to_string(), format!("{:?}", self.vertex_placer.get_config().placement_mode));
+                params.insert("level_packing".to_string(), format!("{:?}", self.vertex_placer.get_config().level_packing));
+                params.insert("max_layer_width".to_string(), options.max_layer_width.to_string());
+                params.insert("deterministic".to_string(), options.deterministic.to_string());
+                if let Some(smoothing) = smoothing {
+                    params.insert("edge_smoothing".to_string(), smoothing.to_string());
+                }
+                if !options.seed_ids.is_empty() {
+                    params.insert("seed_ids".to_string(), options.seed_ids.join(","));
+                    params.insert("neighborhood_depth".to_string(), options.neighborhood_depth.to_string());
+                }
+                params.insert("phase_results".to_string(), serde_json::to_string(&layout_phases)?);
+                if let Some(ref fallback) = layering_fallback {
+                    params.insert("layering_fallback".to_string(), serde_json::to_string(fallback)?);
+                }
+                if !clusters.is_empty() {
+                    let bounds = vertex_placement::cluster_bounds(&positions, &clusters, self.vertex_placer.get_config());
+                    params.insert("cluster_bounds".to_string(), serde_json::to_string(&bounds)?);
+                }
+                if let Some(ref annotation) = self.annotation_anchors {
+                    let anchors = vertex_placement::annotation_anchors(
+                        &positions,
+                        &edge_paths,
+                        self.vertex_placer.get_config(),
+                        annotation,
+                    );
+                    params.insert("annotation_anchors".to_string(), serde_json::to_string(&anchors)?);
+                }
+                if let Some(ref index) = spatial_index {
+                    params.insert("spatial_index".to_string(), serde_json::to_string(index)?);
+                }
+                if placement_stats.approximate_layers > 0 {
+                    let approximate = self.vertex_placer.get_opt_options().approximate_ordering.unwrap_or_default();
+                    params.insert("approximate_layers".to_string(), placement_stats.approximate_layers.to_string());
+                    params.insert("approximate_layer_threshold".to_string(), approximate.layer_threshold.to_string());
+                }
+                params.insert("cyclic_components".to_string(), cycle_result.cyclic_components.to_string());
+                params.insert("cycles_broken".to_string(), cycle_result.broken_edges.len().to_string());
+                if let Some(stats) = validation_report.transitive_reduction {
+                    params.insert("transitive_edges_removed".to_string(), stats.removed_edges.to_string());
+                }
+                if cycle_result.had_cycles() {
+                    let broken: Vec<[&str; 2]> = cycle_result
+                        .broken_edges
+                        .iter()
+                        .map(|(s, t)| [s.as_str(), t.as_str()])
+                        .collect();
+                    params.insert("broken_edges".to_string(), serde_json::to_string(&broken)?);
                 }
                 params
             },
+            spatial_index,
         };
         
+        let occupancy = vertex_placement::OccupiedPositions::from_slots(
+            positions.iter().map(|p| (p.layer, p.level)),
+        );
         let result = LayoutResult {
             positions,
             statistics,
+            phases: layout_phases,
+            edge_paths: result_edge_paths,
+            occupancy,
+            validation: validation_report,
             metadata,
         };
         
-        info!("=== УКЛАДКА УСПЕШНО ЗАВЕРШЕНА ===");
-        info!("⏱️ Общее время: {} мс", total_time);
-        info!("📈 Обработано вершин: {}", graph.vertex_count());
-        info!("🔗 Обработано связей: {}", edges.len());
-        info!("⚡ Скорость: {:.1} вершин/сек", (graph.vertex_count() as f32 / total_time as f32 * 1000.0));
-        info!("=== ВСЕ ЭТАПЫ ЗАВЕРШЕНЫ ===");
+        info!("{}", Msg::LayoutCompleteHeader);
+        info!("{}", Msg::LayoutTotalTime { ms: total_time });
+        info!("{}", Msg::VerticesProcessed { count: graph.vertex_count() });
+        info!("{}", Msg::EdgesProcessed { count: input_edge_count });
+        info!("{}", Msg::LayoutThroughput {
+            per_second: graph.vertex_count() as f32 / total_time as f32 * 1000.0,
+        });
+        info!("{}", Msg::AllStepsDone);
         
         Ok(result)
         })
@@ -429,6 +1097,6 @@ impl LayoutAlgorithm for HighPerformanceLayoutEngine {
     }
     
     fn validate_input(&self, edges: &[GraphEdge]) -> Result<()> {
-        self.validate_edges(edges)
+        self.validate_edges(edges).map(|_| ())
     }
 }
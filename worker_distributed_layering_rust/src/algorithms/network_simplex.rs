@@ -0,0 +1,599 @@
+/*!
+# Назначение слоёв методом network simplex
+
+Слои выбираются так, чтобы минимизировать суммарную взвешенную длину связей
+`Σ w(u,v) · (layer(v) − layer(u))` при ограничении `layer(v) − layer(u) ≥ 1`
+(Gansner et al., «A Technique for Drawing Directed Graphs», 1993).
+
+BFS/longest path прижимает все вершины к источникам, из-за чего связи от
+старых статей к новым тянутся через десятки слоёв. Network simplex сдвигает
+вершины так, чтобы связи были как можно короче, — это даёт более компактную
+и читаемую укладку для публикации ценой большего времени счёта.
+
+Алгоритм (по компонентам слабой связности):
+1. начальные слои — longest path от источников (допустимое решение);
+2. допустимое остовное дерево из «натянутых» связей (slack = 0);
+3. вычисление cut values по дереву (нумерация low/lim);
+4. пока есть связь дерева с отрицательным cut value — заменить её
+   недревесной связью с минимальным slack. Обмен обновляет состояние
+   локально: слои сдвигаются только у отрезанного поддерева, cut values
+   поправляются только на пути дерева между концами новой связи, нумерация
+   low/lim пересчитывается только в поддереве их общего предка.
+
+Слой 0 — источники, как и в остальных стратегиях.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use tracing::{info, warn};
+
+use crate::algorithms::vertex_placement::LayerMap;
+use crate::data_structures::{Graph, VertexId};
+
+/// Лимит итераций обмена связей на компоненту по умолчанию
+pub const DEFAULT_MAX_ITERATIONS: usize = 10_000;
+
+/// Компонента графа с локальной нумерацией вершин
+struct Component {
+    /// Связи (tail, head, вес), кратные связи объединены
+    edges: Vec<(usize, usize, f64)>,
+
+    /// Инцидентные связи каждой вершины
+    incident: Vec<Vec<usize>>,
+}
+
+/// Состояние network simplex для одной компоненты
+struct Simplex<'a> {
+    component: &'a Component,
+    rank: Vec<i64>,
+    in_tree: Vec<bool>,
+    cut_value: Vec<f64>,
+    /// Связи дерева, инцидентные каждой вершине
+    tree_adj: Vec<Vec<usize>>,
+    parent_edge: Vec<Option<usize>>,
+    low: Vec<usize>,
+    lim: Vec<usize>,
+}
+
+impl<'a> Simplex<'a> {
+    fn new(component: &'a Component, rank: Vec<i64>) -> Self {
+        let n = component.incident.len();
+        let m = component.edges.len();
+        Self {
+            component,
+            rank,
+            in_tree: vec![false; m],
+            cut_value: vec![0.0; m],
+            tree_adj: vec![Vec::new(); n],
+            parent_edge: vec![None; n],
+            low: vec![0; n],
+            lim: vec![0; n],
+        }
+    }
+
+    fn slack(&self, edge: usize) -> i64 {
+        let (tail, head, _) = self.component.edges[edge];
+        self.rank[head] - self.rank[tail] - 1
+    }
+
+    /// Вершина на другом конце связи `edge`
+    fn other_end(&self, edge: usize, v: usize) -> usize {
+        let (tail, head, _) = self.component.edges[edge];
+        if tail == v { head } else { tail }
+    }
+
+    /// Построение допустимого остовного дерева из натянутых связей
+    fn feasible_tree(&mut self) {
+        let n = self.component.incident.len();
+        let mut in_tree_node = vec![false; n];
+        in_tree_node[0] = true;
+        let mut tree_nodes = vec![0usize];
+
+        loop {
+            // Наращиваем дерево натянутыми связями
+            let mut stack = tree_nodes.clone();
+            while let Some(v) = stack.pop() {
+                for &edge in &self.component.incident[v] {
+                    let (tail, head, _) = self.component.edges[edge];
+                    let other = if tail == v { head } else { tail };
+                    if !in_tree_node[other] && self.slack(edge) == 0 {
+                        in_tree_node[other] = true;
+                        self.in_tree[edge] = true;
+                        tree_nodes.push(other);
+                        stack.push(other);
+                    }
+                }
+            }
+
+            if tree_nodes.len() == n {
+                return;
+            }
+
+            // Связь с одним концом в дереве и минимальным slack
+            let mut best: Option<(i64, usize)> = None;
+            for (edge, &(tail, head, _)) in self.component.edges.iter().enumerate() {
+                if in_tree_node[tail] != in_tree_node[head] {
+                    let slack = self.slack(edge);
+                    if best.map_or(true, |(s, _)| slack < s) {
+                        best = Some((slack, edge));
+                    }
+                }
+            }
+            let Some((slack, edge)) = best else {
+                return;
+            };
+
+            // Сдвигаем дерево, чтобы связь стала натянутой
+            let tail = self.component.edges[edge].0;
+            let delta = if in_tree_node[tail] { slack } else { -slack };
+            for &v in &tree_nodes {
+                self.rank[v] += delta;
+            }
+        }
+    }
+
+    /// Списки связей дерева и нумерация low/lim от вершины 0
+    ///
+    /// Возвращает вершины в порядке обратного обхода (postorder).
+    fn init_tree(&mut self) -> Vec<usize> {
+        for (edge, &(tail, head, _)) in self.component.edges.iter().enumerate() {
+            if self.in_tree[edge] {
+                self.tree_adj[tail].push(edge);
+                self.tree_adj[head].push(edge);
+            }
+        }
+        self.parent_edge[0] = None;
+        self.dfs_range(0, 1)
+    }
+
+    /// Нумерация low/lim и родительские связи поддерева `root`
+    ///
+    /// Связь `root` с его родителем не меняется; lim вершин поддерева идут
+    /// подряд начиная с `low`. Возвращает вершины поддерева в postorder.
+    fn dfs_range(&mut self, root: usize, low: usize) -> Vec<usize> {
+        let mut postorder = Vec::new();
+        let mut next_lim = low;
+        // (вершина, индекс следующей связи дерева)
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        self.low[root] = next_lim;
+
+        while let Some(top) = stack.last_mut() {
+            let v = top.0;
+            if let Some(&edge) = self.tree_adj[v].get(top.1) {
+                top.1 += 1;
+                if Some(edge) == self.parent_edge[v] {
+                    continue;
+                }
+                let child = self.other_end(edge, v);
+                self.parent_edge[child] = Some(edge);
+                self.low[child] = next_lim;
+                stack.push((child, 0));
+            } else {
+                self.lim[v] = next_lim;
+                next_lim += 1;
+                postorder.push(v);
+                stack.pop();
+            }
+        }
+        postorder
+    }
+
+    /// Cut value связи дерева между `child` и его родителем
+    fn calc_cut_value(&self, child: usize) -> f64 {
+        let parent_edge = self.parent_edge[child].expect("child has a parent edge");
+        let (tail, _, weight) = self.component.edges[parent_edge];
+        let child_is_tail = tail == child;
+
+        let mut cut = weight;
+        for &edge in &self.component.incident[child] {
+            if edge == parent_edge {
+                continue;
+            }
+            let (edge_tail, _, edge_weight) = self.component.edges[edge];
+            let is_out = edge_tail == child;
+            let points_to_head = is_out == child_is_tail;
+
+            cut += if points_to_head { edge_weight } else { -edge_weight };
+            if self.in_tree[edge] {
+                let other_cut = self.cut_value[edge];
+                cut += if points_to_head { -other_cut } else { other_cut };
+            }
+        }
+        cut
+    }
+
+    fn init_cut_values(&mut self, postorder: &[usize]) {
+        for &v in postorder {
+            if let Some(edge) = self.parent_edge[v] {
+                let cut = self.calc_cut_value(v);
+                self.cut_value[edge] = cut;
+            }
+        }
+    }
+
+    fn is_descendant(&self, v: usize, root: usize) -> bool {
+        self.low[root] <= self.lim[v] && self.lim[v] <= self.lim[root]
+    }
+
+    /// Связь дерева с отрицательным cut value
+    fn leave_edge(&self) -> Option<usize> {
+        (0..self.component.edges.len()).find(|&edge| self.in_tree[edge] && self.cut_value[edge] < -1e-9)
+    }
+
+    /// Недревесная связь с минимальным slack, пересекающая разрез `leaving`
+    fn enter_edge(&self, leaving: usize) -> Option<usize> {
+        let (tail, head, _) = self.component.edges[leaving];
+        let (subtree, flip) = if self.lim[tail] > self.lim[head] {
+            (head, true)
+        } else {
+            (tail, false)
+        };
+
+        let mut best: Option<(i64, usize)> = None;
+        for (edge, &(edge_tail, edge_head, _)) in self.component.edges.iter().enumerate() {
+            if self.in_tree[edge] {
+                continue;
+            }
+            if flip == self.is_descendant(edge_tail, subtree)
+                && flip != self.is_descendant(edge_head, subtree)
+            {
+                let slack = self.slack(edge);
+                if best.map_or(true, |(s, _)| slack < s) {
+                    best = Some((slack, edge));
+                }
+            }
+        }
+        best.map(|(_, edge)| edge)
+    }
+
+    /// Сдвиг слоёв всех вершин поддерева `root`
+    fn shift_subtree(&mut self, root: usize, delta: i64) {
+        let mut stack = vec![root];
+        while let Some(v) = stack.pop() {
+            self.rank[v] += delta;
+            for &edge in &self.tree_adj[v] {
+                if Some(edge) != self.parent_edge[v] {
+                    stack.push(self.other_end(edge, v));
+                }
+            }
+        }
+    }
+
+    /// Поправка cut values на пути дерева от `from` вверх до общего предка с `to`
+    ///
+    /// При обмене cut value связи пути меняется на `cut` (cut value уходящей
+    /// связи), если путь от хвоста входящей связи к её голове проходит связь
+    /// по направлению, и на `−cut`, если против. `from_tail` — начат ли подъём
+    /// от хвоста входящей связи. Возвращает общего предка.
+    fn update_path_cut_values(&mut self, from: usize, to: usize, cut: f64, from_tail: bool) -> usize {
+        let mut v = from;
+        while !self.is_descendant(to, v) {
+            let edge = self.parent_edge[v].expect("vertex below the common ancestor has a parent edge");
+            let tail = self.component.edges[edge].0;
+            // От хвоста путь идёт к родителю, от головы — от родителя к v
+            let forward = (tail == v) == from_tail;
+            self.cut_value[edge] += if forward { cut } else { -cut };
+            v = self.other_end(edge, v);
+        }
+        v
+    }
+
+    /// Замена связи дерева `leaving` на `entering`
+    ///
+    /// Слои сдвигаются только у отрезанного поддерева, cut values меняются
+    /// только на пути между концами `entering`, нумерация low/lim — только
+    /// в поддереве их общего предка.
+    fn exchange(&mut self, leaving: usize, entering: usize) {
+        let (tail, head, _) = self.component.edges[leaving];
+        let subtree = if self.lim[tail] < self.lim[head] { tail } else { head };
+
+        // Входящая связь идёт из головной части разреза в хвостовую;
+        // сдвиг отрезанной части делает её натянутой
+        let delta = self.slack(entering);
+        if delta != 0 {
+            self.shift_subtree(subtree, if subtree == head { delta } else { -delta });
+        }
+
+        let (entering_tail, entering_head, _) = self.component.edges[entering];
+        let cut = self.cut_value[leaving];
+        let ancestor = self.update_path_cut_values(entering_tail, entering_head, cut, true);
+        self.update_path_cut_values(entering_head, entering_tail, cut, false);
+        self.cut_value[entering] = -cut;
+        self.cut_value[leaving] = 0.0;
+
+        self.in_tree[leaving] = false;
+        self.in_tree[entering] = true;
+        for v in [tail, head] {
+            self.tree_adj[v].retain(|&edge| edge != leaving);
+        }
+        self.tree_adj[entering_tail].push(entering);
+        self.tree_adj[entering_head].push(entering);
+        self.dfs_range(ancestor, self.low[ancestor]);
+    }
+
+    /// Основной цикл; возвращает количество выполненных обменов
+    fn run(&mut self, max_iterations: usize) -> usize {
+        self.feasible_tree();
+        let postorder = self.init_tree();
+        self.init_cut_values(&postorder);
+
+        let mut iterations = 0;
+        while iterations < max_iterations {
+            let Some(leaving) = self.leave_edge() else {
+                break;
+            };
+            let Some(entering) = self.enter_edge(leaving) else {
+                break;
+            };
+
+            self.exchange(leaving, entering);
+            iterations += 1;
+        }
+
+        if iterations == max_iterations {
+            warn!("Network simplex: достигнут лимит {} итераций, решение может быть неоптимальным", max_iterations);
+        }
+        iterations
+    }
+}
+
+/// Начальные слои: longest path от источников (алгоритм Кана)
+///
+/// Возвращает `None`, если в компоненте есть цикл.
+fn longest_path_ranks(component: &Component) -> Option<Vec<i64>> {
+    let n = component.incident.len();
+    let mut indegree = vec![0usize; n];
+    for &(_, head, _) in &component.edges {
+        indegree[head] += 1;
+    }
+
+    let mut rank = vec![0i64; n];
+    let mut queue: VecDeque<usize> = (0..n).filter(|&v| indegree[v] == 0).collect();
+    let mut processed = 0;
+
+    while let Some(v) = queue.pop_front() {
+        processed += 1;
+        for &edge in &component.incident[v] {
+            let (tail, head, _) = component.edges[edge];
+            if tail != v {
+                continue;
+            }
+            rank[head] = rank[head].max(rank[v] + 1);
+            indegree[head] -= 1;
+            if indegree[head] == 0 {
+                queue.push_back(head);
+            }
+        }
+    }
+
+    (processed == n).then_some(rank)
+}
+
+/// Суммарная взвешенная длина связей при данных слоях
+pub fn total_edge_length(graph: &Graph, layer_map: &LayerMap) -> f64 {
+    let mut total = 0.0;
+    for vertex in graph.handles() {
+        let Some(&source_layer) = layer_map.get(&vertex) else { continue };
+        for target in graph.successors(vertex) {
+            if let Some(&target_layer) = layer_map.get(&target) {
+                let weight = graph.edge_weight(vertex, target).unwrap_or(1.0) as f64;
+                total += weight * (target_layer - source_layer).abs() as f64;
+            }
+        }
+    }
+    total
+}
+
+/// Назначение слоёв методом network simplex
+///
+/// Каждая компонента слабой связности решается отдельно и нормализуется так,
+/// чтобы её минимальный слой был 0. Неположительные веса связей заменяются на 1.
+/// Возвращает `None`, если граф содержит цикл (циклы должны быть разорваны заранее).
+pub fn assign_layers_network_simplex(graph: &Graph, max_iterations: usize) -> Option<LayerMap> {
+    // Нумерация вершин в порядке UID — результат не зависит от порядка загрузки
+    let mut ids: Vec<VertexId> = graph.handles().collect();
+    ids.sort_by_key(|&vertex| graph.vertex_uid(vertex));
+    let mut index = vec![0usize; ids.len()];
+    for (i, vertex) in ids.iter().enumerate() {
+        index[vertex.index()] = i;
+    }
+
+    // Объединение кратных связей
+    let mut weights: HashMap<(usize, usize), f64> = HashMap::new();
+    for (i, &vertex) in ids.iter().enumerate() {
+        for target in graph.successors(vertex) {
+            let j = index[target.index()];
+            let weight = graph.edge_weight(vertex, target).unwrap_or(1.0) as f64;
+            *weights.entry((i, j)).or_insert(0.0) += if weight > 0.0 { weight } else { 1.0 };
+        }
+    }
+    let mut global_edges: Vec<(usize, usize, f64)> = weights.into_iter().map(|((u, v), w)| (u, v, w)).collect();
+    global_edges.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+    let n = ids.len();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (edge, &(tail, head, _)) in global_edges.iter().enumerate() {
+        adjacency[tail].push(edge);
+        adjacency[head].push(edge);
+    }
+
+    let mut layers = LayerMap::with_capacity(n);
+    let mut local = vec![usize::MAX; n];
+    let mut total_iterations = 0;
+    let mut components = 0;
+
+    for start in 0..n {
+        if local[start] != usize::MAX {
+            continue;
+        }
+        components += 1;
+
+        // Вершины компоненты (BFS по связям в обе стороны)
+        let mut members = vec![start];
+        local[start] = 0;
+        let mut cursor = 0;
+        while cursor < members.len() {
+            let v = members[cursor];
+            cursor += 1;
+            for &edge in &adjacency[v] {
+                let (tail, head, _) = global_edges[edge];
+                let other = if tail == v { head } else { tail };
+                if local[other] == usize::MAX {
+                    local[other] = members.len();
+                    members.push(other);
+                }
+            }
+        }
+
+        let mut component = Component {
+            edges: Vec::new(),
+            incident: vec![Vec::new(); members.len()],
+        };
+        for &v in &members {
+            for &edge in &adjacency[v] {
+                let (tail, head, weight) = global_edges[edge];
+                if tail == v {
+                    let local_edge = component.edges.len();
+                    component.edges.push((local[tail], local[head], weight));
+                    component.incident[local[tail]].push(local_edge);
+                    component.incident[local[head]].push(local_edge);
+                }
+            }
+        }
+
+        let initial = longest_path_ranks(&component)?;
+        let mut simplex = Simplex::new(&component, initial);
+        total_iterations += simplex.run(max_iterations);
+
+        let min_rank = simplex.rank.iter().copied().min().unwrap_or(0);
+        for (local_index, &v) in members.iter().enumerate() {
+            layers.insert(ids[v], (simplex.rank[local_index] - min_rank) as i32);
+        }
+    }
+
+    info!(
+        "Network simplex: {} компонент, {} обменов связей",
+        components, total_iterations
+    );
+
+    Some(layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::GraphBuilder;
+
+    fn graph(edges: &[(&str, &str)]) -> Graph {
+        let mut builder = GraphBuilder::new();
+        for &(source, target) in edges {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    fn assert_edges_point_forward(layers: &LayerMap, graph: &Graph) {
+        for vertex in graph.handles() {
+            for target in graph.successors(vertex) {
+                assert!(
+                    layers[&vertex] < layers[&target],
+                    "{} -> {}",
+                    graph.vertex_uid(vertex),
+                    graph.vertex_uid(target)
+                );
+            }
+        }
+    }
+
+    fn layer_of(layers: &LayerMap, graph: &Graph, vertex_id: &str) -> i32 {
+        layers[&graph.vertex_handle(vertex_id).unwrap()]
+    }
+
+    #[test]
+    fn test_pulls_late_source_towards_target() {
+        // Longest path ставит X в слой 0, и связь X -> D тянется через 3 слоя
+        let g = graph(&[("A", "B"), ("B", "C"), ("C", "D"), ("X", "D")]);
+        let layers = assign_layers_network_simplex(&g, DEFAULT_MAX_ITERATIONS).unwrap();
+
+        assert_edges_point_forward(&layers, &g);
+        assert_eq!(layer_of(&layers, &g, "A"), 0);
+        assert_eq!(layer_of(&layers, &g, "D"), 3);
+        assert_eq!(layer_of(&layers, &g, "X"), 2);
+        assert_eq!(total_edge_length(&g, &layers), 4.0);
+    }
+
+    #[test]
+    fn test_not_worse_than_longest_path() {
+        let g = graph(&[
+            ("A", "B"), ("A", "C"), ("B", "D"), ("C", "D"), ("C", "E"),
+            ("D", "F"), ("E", "F"), ("A", "E"), ("G", "F"), ("G", "H"), ("B", "F"),
+        ]);
+        let layers = assign_layers_network_simplex(&g, DEFAULT_MAX_ITERATIONS).unwrap();
+        assert_edges_point_forward(&layers, &g);
+
+        let mut longest = LayerMap::new();
+        for _ in 0..g.vertex_count() {
+            for vertex in g.handles() {
+                let layer = g
+                    .predecessors(vertex)
+                    .map(|p| longest.get(&p).copied().unwrap_or(0) + 1)
+                    .max()
+                    .unwrap_or(0);
+                longest.insert(vertex, layer);
+            }
+        }
+
+        assert!(total_edge_length(&g, &layers) <= total_edge_length(&g, &longest));
+    }
+
+    #[test]
+    fn test_incremental_exchange_matches_full_recompute() {
+        // Псевдослучайный DAG (связи от меньшего номера к большему) с разными весами
+        let n = 40;
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut edges = Vec::new();
+        for head in 1..n {
+            for _ in 0..2 {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+                let tail = (state >> 33) as usize % head;
+                if !edges.iter().any(|&(t, h, _)| (t, h) == (tail, head)) {
+                    edges.push((tail, head, 1.0 + (state >> 60) as f64));
+                }
+            }
+        }
+        let mut component = Component { edges: Vec::new(), incident: vec![Vec::new(); n] };
+        for (edge, &(tail, head, weight)) in edges.iter().enumerate() {
+            component.edges.push((tail, head, weight));
+            component.incident[tail].push(edge);
+            component.incident[head].push(edge);
+        }
+
+        let mut simplex = Simplex::new(&component, longest_path_ranks(&component).unwrap());
+        simplex.feasible_tree();
+        let postorder = simplex.init_tree();
+        simplex.init_cut_values(&postorder);
+
+        let mut exchanges = 0;
+        while let Some(leaving) = simplex.leave_edge() {
+            let entering = simplex.enter_edge(leaving).unwrap();
+            simplex.exchange(leaving, entering);
+            exchanges += 1;
+
+            let mut full = Simplex::new(&component, simplex.rank.clone());
+            full.in_tree = simplex.in_tree.clone();
+            let postorder = full.init_tree();
+            full.init_cut_values(&postorder);
+            for edge in (0..component.edges.len()).filter(|&edge| simplex.in_tree[edge]) {
+                assert_eq!(simplex.slack(edge), 0);
+                assert!((simplex.cut_value[edge] - full.cut_value[edge]).abs() < 1e-9);
+            }
+        }
+        assert!(exchanges > 0);
+        assert!((0..component.edges.len()).all(|edge| simplex.slack(edge) >= 0));
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let g = graph(&[("A", "B"), ("B", "A")]);
+        assert!(assign_layers_network_simplex(&g, DEFAULT_MAX_ITERATIONS).is_none());
+    }
+}
@@ -51,14 +51,16 @@ impl ParallelGraphProcessor {
     {
         self.active_tasks.store(items.len(), Ordering::Relaxed);
         
-        let results: Result<Vec<_>> = items
-            .par_iter()
-            .map(|item| {
-                let result = processor(item);
-                self.active_tasks.fetch_sub(1, Ordering::Relaxed);
-                result
-            })
-            .collect();
+        let results: Result<Vec<_>> = super::scheduler::install(|| {
+            items
+                .par_iter()
+                .map(|item| {
+                    let result = processor(item);
+                    self.active_tasks.fetch_sub(1, Ordering::Relaxed);
+                    result
+                })
+                .collect()
+        });
         
         results
     }
@@ -0,0 +1,409 @@
+/*!
+# Типизированные результаты этапов укладки
+
+Каждый этап конвейера (разрыв циклов, топологическая сортировка, назначение слоёв,
+размещение, маршрутизация связей) возвращает собственную структуру статистики.
+Все они собираются в `LayoutPhases` внутри `LayoutResult`, поэтому данные,
+которые раньше существовали только в строках `info!`, можно проверять в тестах
+и экспортировать (см. параметр `phase_results` в метаданных алгоритма).
+*/
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::data_structures::{Graph, IngestStats, VertexId};
+use crate::neo4j::VertexPosition;
+use super::cycle_breaking::CycleBreakResult;
+use super::topological_sort::TopoSortResult;
+use super::vertex_placement::PlacementStats;
+
+/// Результат этапа разрыва циклов
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CycleBreakingPhase {
+    /// Количество связей на входе
+    pub input_edges: usize,
+
+    /// Количество сильно связных компонент с циклами
+    pub cyclic_components: usize,
+
+    /// Количество развёрнутых/удалённых связей
+    pub broken_edges: usize,
+}
+
+impl CycleBreakingPhase {
+    pub fn from_result(input_edges: usize, result: &CycleBreakResult) -> Self {
+        Self {
+            input_edges,
+            cyclic_components: result.cyclic_components,
+            broken_edges: result.broken_edges.len(),
+        }
+    }
+}
+
+/// Результат этапа топологической сортировки
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopoSortPhase {
+    /// Время выполнения (мс)
+    pub time_ms: u64,
+
+    /// Количество упорядоченных вершин
+    pub vertices_ordered: usize,
+
+    /// Количество уровней топологического порядка
+    pub level_count: usize,
+
+    /// Количество обработанных батчей
+    pub batches_processed: usize,
+}
+
+impl TopoSortPhase {
+    pub(crate) fn from_result(time_ms: u64, result: &TopoSortResult) -> Self {
+        Self {
+            time_ms,
+            vertices_ordered: result.order.len(),
+            level_count: result.level_count,
+            batches_processed: result.stats.batches_processed,
+        }
+    }
+}
+
+/// Результат этапа назначения слоёв (longest path + BFS слои)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayeringPhase {
+    /// Время поиска longest path (мс)
+    pub time_ms: u64,
+
+    /// Длина самого длинного пути (вершин)
+    pub longest_path_length: usize,
+
+    /// Количество использованных слоёв
+    pub layers_used: usize,
+
+    /// Максимальное количество вершин в одном слое
+    pub max_vertices_in_layer: usize,
+
+    /// Среднее количество вершин на слой
+    pub avg_vertices_per_layer: f32,
+
+    /// Самые глубокие компоненты связности (не больше `TOP_COMPONENTS`)
+    #[serde(default)]
+    pub components: Vec<ComponentDepth>,
+}
+
+/// Сколько самых глубоких компонент попадает в статистику
+pub const TOP_COMPONENTS: usize = 10;
+
+/// Глубина одной компоненты связности
+///
+/// Неожиданно глубокая маленькая компонента обычно указывает на ошибку в данных
+/// (цепочка ошибочных ссылок, перепутанное направление цитирования).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentDepth {
+    /// Наименьший ID вершины компоненты (для поиска компоненты в базе)
+    pub representative: String,
+
+    /// Количество вершин компоненты
+    pub vertices: usize,
+
+    /// Длина самого длинного пути внутри компоненты (вершин)
+    pub longest_path_length: usize,
+
+    /// Наибольший номер слоя вершин компоненты
+    pub max_layer: i32,
+}
+
+impl ComponentDepth {
+    /// Глубина компонент графа после укладки, самые глубокие первыми
+    ///
+    /// `topo_order` — топологический порядок вершин ациклического графа.
+    pub fn top_components(
+        graph: &mut Graph,
+        topo_order: &[VertexId],
+        positions: &[VertexPosition],
+        limit: usize,
+    ) -> Vec<ComponentDepth> {
+        let components = graph.get_connected_components().to_vec();
+
+        // Номер компоненты по индексу handle вершины
+        let mut component_of = vec![0usize; graph.vertex_count()];
+        let mut depths: Vec<ComponentDepth> = Vec::with_capacity(components.len());
+        for (index, members) in components.iter().enumerate() {
+            let mut representative: Option<&String> = None;
+            for &idx in members {
+                if let Some(vertex_id) = graph.vertex_id(idx) {
+                    component_of[idx] = index;
+                    if representative.map_or(true, |r| vertex_id < r) {
+                        representative = Some(vertex_id);
+                    }
+                }
+            }
+            depths.push(ComponentDepth {
+                representative: representative.cloned().unwrap_or_default(),
+                vertices: members.len(),
+                ..ComponentDepth::default()
+            });
+        }
+
+        // Длина самого длинного пути, заканчивающегося в вершине (в топологическом порядке)
+        let mut path_length = vec![0usize; graph.vertex_count()];
+        for &vertex in topo_order {
+            let length = graph
+                .predecessors(vertex)
+                .map(|source| path_length[source.index()])
+                .max()
+                .map_or(1, |longest| longest + 1);
+            path_length[vertex.index()] = length;
+            let depth = &mut depths[component_of[vertex.index()]];
+            depth.longest_path_length = depth.longest_path_length.max(length);
+        }
+
+        for position in positions {
+            // Фиктивные вершины длинных связей в граф не входят
+            if let Some(vertex) = graph.vertex_handle(&position.article_id) {
+                let depth = &mut depths[component_of[vertex.index()]];
+                depth.max_layer = depth.max_layer.max(position.layer);
+            }
+        }
+
+        depths.sort_by(|a, b| {
+            b.longest_path_length
+                .cmp(&a.longest_path_length)
+                .then(b.vertices.cmp(&a.vertices))
+                .then_with(|| a.representative.cmp(&b.representative))
+        });
+        depths.truncate(limit);
+        depths
+    }
+}
+
+/// Результат этапа размещения вершин
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlacementPhase {
+    /// Время выполнения (мс)
+    pub time_ms: u64,
+
+    /// Количество размещённых вершин
+    pub vertices_placed: usize,
+
+    /// Максимальный уровень
+    pub max_level: i32,
+
+    /// Ширина укладки (пиксели)
+    pub total_width: f32,
+
+    /// Высота укладки (пиксели)
+    pub total_height: f32,
+
+    /// Пересечения связей до минимизации
+    pub crossings_before: usize,
+
+    /// Пересечения связей после минимизации
+    pub crossings_after: usize,
+
+    /// Вершин, оказавшихся в занятой ячейке (слой, уровень) и перенесённых на свободный уровень
+    #[serde(default)]
+    pub overlaps_repaired: usize,
+
+    /// Вершин, оставленных в заданных вручную координатах
+    #[serde(default)]
+    pub anchored_vertices: usize,
+
+    /// Слоёв, упорядоченных приближённо (слишком большие для точной минимизации пересечений)
+    #[serde(default)]
+    pub approximate_layers: usize,
+
+    /// Пиковый объём арены временных данных размещения (байты)
+    #[serde(default)]
+    pub arena_peak_bytes: usize,
+}
+
+/// Результат этапа маршрутизации связей
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoutingPhase {
+    /// Количество связей с построенным маршрутом
+    pub edges_routed: usize,
+
+    /// Суммарное количество точек во всех маршрутах
+    pub total_points: usize,
+
+    /// Суммарное количество изломов (смен направления, см. `count_bends`)
+    pub total_bends: usize,
+
+    /// Связей, обведённых вокруг блоков (avoid_vertices)
+    #[serde(default)]
+    pub edges_detoured: usize,
+
+    /// Пересечений слоёв, перенесённых на отдельную дорожку зазора (канальная трассировка)
+    #[serde(default)]
+    pub channel_shifts: usize,
+
+    /// Связей, объединённых в общие стволы (edge bundling)
+    #[serde(default)]
+    pub edges_bundled: usize,
+
+    /// Точек, удалённых упрощением маршрутов (Douglas–Peucker)
+    #[serde(default)]
+    pub points_removed: usize,
+}
+
+impl RoutingPhase {
+    pub fn from_paths(edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>) -> Self {
+        let total_points: usize = edge_paths.values().map(|points| points.len()).sum();
+        let total_bends = edge_paths
+            .values()
+            .map(|points| crate::algorithms::vertex_placement::count_bends(points))
+            .sum();
+
+        Self {
+            edges_routed: edge_paths.len(),
+            total_points,
+            total_bends,
+            edges_detoured: 0,
+            channel_shifts: 0,
+            edges_bundled: 0,
+            points_removed: 0,
+        }
+    }
+}
+
+/// Результаты всех этапов укладки
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutPhases {
+    pub cycle_breaking: CycleBreakingPhase,
+
+    /// Приём связей при построении графа
+    #[serde(default)]
+    pub ingest: IngestStats,
+    pub topo_sort: TopoSortPhase,
+    pub layering: LayeringPhase,
+    pub placement: PlacementPhase,
+    pub routing: RoutingPhase,
+}
+
+impl LayoutPhases {
+    /// Заполнение этапов назначения слоёв и размещения из статистики размещения
+    pub fn apply_placement_stats(
+        &mut self,
+        stats: &PlacementStats,
+        placement_time_ms: u64,
+        max_level: i32,
+    ) {
+        self.layering.layers_used = stats.layers_used;
+        self.layering.max_vertices_in_layer = stats.max_vertices_in_layer;
+        self.layering.avg_vertices_per_layer = stats.avg_vertices_per_layer;
+
+        self.placement = PlacementPhase {
+            time_ms: placement_time_ms,
+            vertices_placed: stats.vertices_placed,
+            max_level,
+            total_width: stats.total_width,
+            total_height: stats.total_height,
+            crossings_before: stats.crossings_before,
+            crossings_after: stats.crossings_after,
+            overlaps_repaired: stats.overlaps_repaired,
+            anchored_vertices: stats.anchored_vertices,
+            approximate_layers: stats.approximate_layers,
+            arena_peak_bytes: stats.arena_peak_bytes,
+        };
+    }
+
+    /// Суммарное время измеренных этапов (мс)
+    pub fn total_time_ms(&self) -> u64 {
+        self.topo_sort.time_ms + self.layering.time_ms + self.placement.time_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routing_phase_from_paths() {
+        let mut paths = HashMap::new();
+        paths.insert(("A".to_string(), "B".to_string()), vec![(0.0, 0.0), (10.0, 0.0)]);
+        paths.insert(
+            ("A".to_string(), "C".to_string()),
+            vec![(0.0, 0.0), (5.0, 0.0), (5.0, 10.0), (10.0, 10.0)],
+        );
+
+        let routing = RoutingPhase::from_paths(&paths);
+        assert_eq!(routing.edges_routed, 2);
+        assert_eq!(routing.total_points, 6);
+        assert_eq!(routing.total_bends, 2);
+    }
+
+    #[test]
+    fn test_apply_placement_stats() {
+        let stats = PlacementStats {
+            vertices_placed: 10,
+            layers_used: 3,
+            avg_vertices_per_layer: 3.33,
+            max_vertices_in_layer: 5,
+            total_width: 720.0,
+            total_height: 650.0,
+            crossings_before: 7,
+            crossings_after: 2,
+            overlaps_repaired: 1,
+            anchored_vertices: 2,
+            approximate_layers: 1,
+            edges_detoured: 0,
+            channel_shifts: 0,
+            path_points_removed: 0,
+            arena_peak_bytes: 4096,
+        };
+
+        let mut phases = LayoutPhases::default();
+        phases.topo_sort.time_ms = 4;
+        phases.layering.time_ms = 6;
+        phases.apply_placement_stats(&stats, 10, 4);
+
+        assert_eq!(phases.layering.layers_used, 3);
+        assert_eq!(phases.placement.vertices_placed, 10);
+        assert_eq!(phases.placement.crossings_after, 2);
+        assert_eq!(phases.placement.overlaps_repaired, 1);
+        assert_eq!(phases.placement.anchored_vertices, 2);
+        assert_eq!(phases.placement.approximate_layers, 1);
+        assert_eq!(phases.placement.arena_peak_bytes, 4096);
+        assert_eq!(phases.total_time_ms(), 20);
+
+        let json = serde_json::to_string(&phases).unwrap();
+        let restored: LayoutPhases = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, phases);
+    }
+
+    #[test]
+    fn test_top_components_by_depth() {
+        use crate::data_structures::GraphBuilder;
+
+        let mut builder = GraphBuilder::new();
+        for (source, target) in [("X", "Y"), ("A", "B"), ("B", "C"), ("C", "D"), ("P", "Q"), ("P", "R")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0).unwrap();
+        }
+        let mut graph = builder.build().unwrap();
+        let order: Vec<VertexId> = ["A", "P", "X", "B", "Q", "R", "Y", "C", "D"]
+            .iter()
+            .map(|id| graph.vertex_handle(id).unwrap())
+            .collect();
+        let positions: Vec<VertexPosition> = [("A", 0), ("B", 1), ("C", 2), ("D", 3), ("X", 0), ("Y", 1)]
+            .iter()
+            .map(|&(id, layer)| VertexPosition {
+                article_id: id.to_string(),
+                layer,
+                level: 0,
+                x: 0.0,
+                y: 0.0,
+            })
+            .collect();
+
+        let depths = ComponentDepth::top_components(&mut graph, &order, &positions, 2);
+
+        assert_eq!(depths.len(), 2);
+        assert_eq!(depths[0].representative, "A");
+        assert_eq!(depths[0].vertices, 4);
+        assert_eq!(depths[0].longest_path_length, 4);
+        assert_eq!(depths[0].max_layer, 3);
+        // При равной глубине первой идёт компонента с большим числом вершин
+        assert_eq!(depths[1].representative, "P");
+        assert_eq!(depths[1].longest_path_length, 2);
+    }
+}
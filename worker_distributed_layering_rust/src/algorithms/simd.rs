@@ -0,0 +1,171 @@
+/*!
+# Определение возможностей CPU и выбор SIMD-ядра
+
+Поддержка AVX2 (x86_64) или NEON (aarch64) проверяется один раз во время
+выполнения, и ядра выбирают реализацию по результату. Флаг `enable_simd` только
+разрешает SIMD: если процессор нужных инструкций не поддерживает, используется
+скалярный путь, и в метаданных ответа SIMD не отмечается. Данные короче одного
+вектора ядра тоже обрабатывают скалярно, и такой запуск считается скалярным.
+*/
+
+use std::sync::OnceLock;
+
+/// Реализация, выбранная для SIMD-ядер
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdPath {
+    /// Скалярный код (SIMD выключен или не поддерживается)
+    Scalar,
+    /// AVX2, 8 значений f32 за инструкцию
+    Avx2,
+    /// NEON, 4 значения f32 за инструкцию
+    Neon,
+}
+
+impl SimdPath {
+    /// Лучший путь, поддерживаемый текущим CPU (определяется один раз)
+    pub fn detect() -> Self {
+        static PATH: OnceLock<SimdPath> = OnceLock::new();
+        *PATH.get_or_init(detect_uncached)
+    }
+
+    /// Путь с учётом настройки: при `enabled == false` всегда скалярный
+    pub fn select(enabled: bool) -> Self {
+        if enabled {
+            Self::detect()
+        } else {
+            SimdPath::Scalar
+        }
+    }
+
+    /// Используются ли векторные инструкции
+    pub fn is_vectorized(self) -> bool {
+        self != SimdPath::Scalar
+    }
+
+    /// Число значений f32 в одном векторе (1 для скалярного пути)
+    pub fn lanes(self) -> usize {
+        match self {
+            SimdPath::Scalar => 1,
+            SimdPath::Avx2 => 8,
+            SimdPath::Neon => 4,
+        }
+    }
+
+    /// Путь, которым ядро фактически обработает `len` значений: если данных
+    /// меньше одного вектора, работает только скалярный хвост
+    pub fn for_len(self, len: usize) -> Self {
+        if len < self.lanes() {
+            SimdPath::Scalar
+        } else {
+            self
+        }
+    }
+
+    /// Название для метаданных и логов
+    pub fn name(self) -> &'static str {
+        match self {
+            SimdPath::Scalar => "scalar",
+            SimdPath::Avx2 => "avx2",
+            SimdPath::Neon => "neon",
+        }
+    }
+}
+
+fn detect_uncached() -> SimdPath {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("avx2") {
+            return SimdPath::Avx2;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return SimdPath::Neon;
+        }
+    }
+    SimdPath::Scalar
+}
+
+/// Максимум среди значений (None для пустого среза)
+///
+/// NaN во входных данных не ожидается: векторные и скалярная реализации
+/// обрабатывают его по-разному.
+pub fn max_f32(path: SimdPath, values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    let max = match path.for_len(values.len()) {
+        // SAFETY: путь Avx2 выбирается только после проверки поддержки AVX2
+        #[cfg(target_arch = "x86_64")]
+        SimdPath::Avx2 => unsafe { max_f32_avx2(values) },
+        // SAFETY: путь Neon выбирается только после проверки поддержки NEON
+        #[cfg(target_arch = "aarch64")]
+        SimdPath::Neon => unsafe { max_f32_neon(values) },
+        _ => max_f32_scalar(values),
+    };
+    Some(max)
+}
+
+fn max_f32_scalar(values: &[f32]) -> f32 {
+    values.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn max_f32_avx2(values: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let chunks = values.chunks_exact(8);
+    let tail = chunks.remainder();
+    let mut acc = _mm256_set1_ps(f32::NEG_INFINITY);
+    for chunk in chunks {
+        acc = _mm256_max_ps(acc, _mm256_loadu_ps(chunk.as_ptr()));
+    }
+    let mut lanes = [0.0f32; 8];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), acc);
+    max_f32_scalar(&lanes).max(max_f32_scalar(tail))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn max_f32_neon(values: &[f32]) -> f32 {
+    use std::arch::aarch64::*;
+
+    let chunks = values.chunks_exact(4);
+    let tail = chunks.remainder();
+    let mut acc = vdupq_n_f32(f32::NEG_INFINITY);
+    for chunk in chunks {
+        acc = vmaxq_f32(acc, vld1q_f32(chunk.as_ptr()));
+    }
+    vmaxvq_f32(acc).max(max_f32_scalar(tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_simd_is_scalar() {
+        assert_eq!(SimdPath::select(false), SimdPath::Scalar);
+        assert!(!SimdPath::Scalar.is_vectorized());
+    }
+
+    #[test]
+    fn test_short_input_runs_scalar() {
+        assert_eq!(SimdPath::Avx2.for_len(7), SimdPath::Scalar);
+        assert_eq!(SimdPath::Avx2.for_len(8), SimdPath::Avx2);
+        assert_eq!(SimdPath::Neon.for_len(3), SimdPath::Scalar);
+        assert_eq!(SimdPath::Scalar.for_len(100), SimdPath::Scalar);
+    }
+
+    #[test]
+    fn test_max_matches_scalar_on_detected_path() {
+        let path = SimdPath::detect();
+        for len in [1usize, 3, 4, 7, 8, 9, 17, 100] {
+            let values: Vec<f32> = (0..len).map(|i| ((i * 37) % 23) as f32 - 5.0).collect();
+            assert_eq!(max_f32(path, &values), Some(max_f32_scalar(&values)), "len {}", len);
+        }
+        assert_eq!(max_f32(path, &[]), None);
+    }
+}
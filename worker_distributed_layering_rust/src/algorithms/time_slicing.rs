@@ -0,0 +1,252 @@
+/*!
+# Укладка по временным окнам
+
+Режим для анимированного представления эволюции карты знаний: граф делится
+по времени публикации на окна (например, по 5 лет), каждое окно укладывается
+отдельно, а вершины, присутствующие в нескольких окнах, выравниваются —
+их порядок внутри слоя наследуется от предыдущего окна.
+
+Время связи — максимум времён её концов (связь появляется вместе с более
+новой, цитирующей статьёй). Связи, у которых время неизвестно для обоих
+концов, пропускаются.
+
+В кумулятивном режиме окно `i` содержит все связи до конца окна, поэтому
+кадры анимации показывают рост графа; иначе окна не пересекаются.
+*/
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+use crate::data_structures::GraphBuilder;
+use crate::neo4j::{GraphEdge, VertexPosition};
+use super::cycle_breaking::{self, CycleBreakStrategy};
+use super::vertex_placement::{set_vertex_years, years_from_edges, OptimalVertexPlacer, TieBreak, TieBreakKeys};
+
+/// Параметры разбиения на временные окна
+#[derive(Debug, Clone)]
+pub struct TimeSliceOptions {
+    /// Ширина окна (в единицах времени, обычно годах)
+    pub window_size: i32,
+
+    /// Окно содержит все связи с начала времени до конца окна
+    pub cumulative: bool,
+}
+
+impl Default for TimeSliceOptions {
+    fn default() -> Self {
+        Self {
+            window_size: 5,
+            cumulative: true,
+        }
+    }
+}
+
+/// Временное окно `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start: i32,
+    pub end: i32,
+}
+
+/// Укладка одного временного окна
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSlice {
+    /// Окно
+    pub window: TimeWindow,
+
+    /// Позиции вершин окна
+    pub positions: Vec<VertexPosition>,
+
+    /// Количество связей в окне
+    pub edge_count: usize,
+
+    /// Вершины, которых не было в предыдущем окне
+    pub new_vertices: usize,
+
+    /// Вершины, общие с предыдущим окном
+    pub shared_vertices: usize,
+
+    /// Среднее смещение общих вершин относительно предыдущего окна (пиксели)
+    pub mean_shift: f32,
+}
+
+/// Разбиение связей на временные окна
+///
+/// Окна выровнены по `window_size` и идут подряд от самого раннего до самого
+/// позднего времени; пустые некумулятивные окна пропускаются.
+pub fn partition_edges(
+    edges: &[GraphEdge],
+    vertex_times: &HashMap<String, i32>,
+    options: &TimeSliceOptions,
+) -> Vec<(TimeWindow, Vec<GraphEdge>)> {
+    let window_size = options.window_size.max(1);
+
+    let mut buckets: BTreeMap<i32, Vec<GraphEdge>> = BTreeMap::new();
+    let mut skipped = 0usize;
+    for edge in edges {
+        let time = vertex_times
+            .get(&edge.source_id)
+            .copied()
+            .max(vertex_times.get(&edge.target_id).copied());
+        match time {
+            Some(time) => {
+                let start = time.div_euclid(window_size) * window_size;
+                buckets.entry(start).or_default().push(edge.clone());
+            }
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        info!("⏳ Пропущено {} связей без времени публикации", skipped);
+    }
+
+    let (Some(&first), Some(&last)) = (buckets.keys().next(), buckets.keys().next_back()) else {
+        return Vec::new();
+    };
+
+    let mut windows = Vec::new();
+    let mut accumulated: Vec<GraphEdge> = Vec::new();
+    let mut start = first;
+    while start <= last {
+        let window = TimeWindow { start, end: start + window_size };
+        let bucket = buckets.remove(&start).unwrap_or_default();
+
+        if options.cumulative {
+            accumulated.extend(bucket);
+            windows.push((window, accumulated.clone()));
+        } else if !bucket.is_empty() {
+            windows.push((window, bucket));
+        }
+
+        start += window_size;
+    }
+
+    windows
+}
+
+/// Укладка всех временных окон
+///
+/// Каждое окно укладывается копией `placer`. Порядок внутри слоя задаётся через
+/// `TieBreak::InputOrder`: сначала общие с предыдущим окном вершины в порядке
+/// их прежних уровней, затем новые вершины по ID. Годы публикации для
+/// `LayeringStrategy::ByProperty` становятся атрибутами вершин графа окна.
+pub async fn layout_time_slices(
+    edges: &[GraphEdge],
+    vertex_times: &HashMap<String, i32>,
+    options: &TimeSliceOptions,
+    placer: &OptimalVertexPlacer,
+) -> Result<Vec<TimeSlice>> {
+    let windows = partition_edges(edges, vertex_times, options);
+    info!("🕰️ Укладка {} временных окон (ширина {}, кумулятивно: {})",
+          windows.len(), options.window_size, options.cumulative);
+
+    let mut slices: Vec<TimeSlice> = Vec::with_capacity(windows.len());
+    let mut previous: HashMap<String, VertexPosition> = HashMap::new();
+
+    for (window, window_edges) in windows {
+        let edge_count = window_edges.len();
+        let years = years_from_edges(&window_edges);
+        let acyclic = cycle_breaking::break_cycles(window_edges, CycleBreakStrategy::default()).edges;
+
+        let mut builder = GraphBuilder::new();
+        for edge in &acyclic {
+            if edge.source_id.trim().is_empty() || edge.target_id.trim().is_empty() {
+                continue;
+            }
+            if edge.source_id != edge.target_id {
+                builder.add_edge(edge.source_id.clone(), edge.target_id.clone(), edge.weight)?;
+            }
+        }
+        let mut graph = builder.build()?;
+        set_vertex_years(&mut graph, &years);
+
+        let keys: TieBreakKeys = previous
+            .iter()
+            .map(|(id, pos)| (id.clone(), pos.level as i64))
+            .collect();
+
+        let mut window_placer = placer.clone();
+        window_placer.get_config_mut().tie_break = TieBreak::InputOrder;
+        window_placer.set_tie_break_keys(keys);
+        let (positions, _) = window_placer.place_vertices(&graph, &[]).await?;
+
+        let mut shared_vertices = 0usize;
+        let mut total_shift = 0.0f32;
+        for position in &positions {
+            if let Some(old) = previous.get(&position.article_id) {
+                shared_vertices += 1;
+                total_shift += ((position.x - old.x).powi(2) + (position.y - old.y).powi(2)).sqrt();
+            }
+        }
+        let mean_shift = if shared_vertices > 0 { total_shift / shared_vertices as f32 } else { 0.0 };
+
+        info!(
+            "   - окно [{}, {}): {} связей, {} вершин ({} новых, среднее смещение {:.1} px)",
+            window.start,
+            window.end,
+            edge_count,
+            positions.len(),
+            positions.len() - shared_vertices,
+            mean_shift
+        );
+
+        previous = positions.iter().map(|p| (p.article_id.clone(), p.clone())).collect();
+        slices.push(TimeSlice {
+            window,
+            new_vertices: positions.len() - shared_vertices,
+            shared_vertices,
+            mean_shift,
+            edge_count,
+            positions,
+        });
+    }
+
+    Ok(slices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str) -> GraphEdge {
+        GraphEdge::new(source, target, 1.0)
+    }
+
+    fn times() -> HashMap<String, i32> {
+        [("A", 1990), ("B", 1992), ("C", 2001), ("D", 2003)]
+            .into_iter()
+            .map(|(id, year)| (id.to_string(), year))
+            .collect()
+    }
+
+    #[test]
+    fn test_partition_cumulative_and_disjoint() {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("A", "D"), edge("X", "Y")];
+        let options = TimeSliceOptions { window_size: 5, cumulative: true };
+
+        let windows = partition_edges(&edges, &times(), &options);
+        let sizes: Vec<(i32, usize)> = windows.iter().map(|(w, e)| (w.start, e.len())).collect();
+        assert_eq!(sizes, vec![(1990, 1), (1995, 1), (2000, 3)]);
+
+        let options = TimeSliceOptions { window_size: 5, cumulative: false };
+        let windows = partition_edges(&edges, &times(), &options);
+        let sizes: Vec<(i32, usize)> = windows.iter().map(|(w, e)| (w.start, e.len())).collect();
+        assert_eq!(sizes, vec![(1990, 1), (2000, 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_shared_vertices_are_tracked() -> Result<()> {
+        let edges = vec![edge("A", "B"), edge("B", "C"), edge("A", "D")];
+        let options = TimeSliceOptions { window_size: 10, cumulative: true };
+
+        let slices = layout_time_slices(&edges, &times(), &options, &OptimalVertexPlacer::new()).await?;
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[0].new_vertices, 2);
+        assert_eq!(slices[1].shared_vertices, 2);
+        assert_eq!(slices[1].new_vertices, 2);
+        Ok(())
+    }
+}
@@ -17,13 +17,13 @@
 
 */
 
-use crate::data_structures::Graph;
+use crate::data_structures::{Graph, VertexId};
+use super::scheduler;
 use anyhow::Result;
 use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use hashbrown::HashMap;
 
 /// Параллельный алгоритм топологической сортировки
 #[derive(Debug)]
@@ -58,13 +58,16 @@ pub struct TopoSortStats {
 }
 
 /// Результат топологической сортировки
+///
+/// Вершины представлены handles графа; UID нужны только на выходе из
+/// конвейера (`uids`).
 #[derive(Debug, Clone)]
 pub struct TopoSortResult {
     /// Топологический порядок вершин
-    pub order: Vec<String>,
+    pub order: Vec<VertexId>,
     
-    /// Маппинг ID вершины -> позиция в порядке
-    pub position_map: HashMap<String, usize>,
+    /// Позиция вершины в порядке, по индексу handle
+    pub positions: Vec<usize>,
     
     /// Статистика выполнения
     pub stats: TopoSortStats,
@@ -73,6 +76,18 @@ pub struct TopoSortResult {
     pub level_count: usize,
 }
 
+impl TopoSortResult {
+    /// Позиция вершины в топологическом порядке
+    pub fn position(&self, vertex: VertexId) -> usize {
+        self.positions[vertex.index()]
+    }
+    
+    /// UID вершин в топологическом порядке
+    pub fn uids(&self, graph: &Graph) -> Vec<String> {
+        self.order.iter().map(|&v| graph.vertex_uid(v).to_string()).collect()
+    }
+}
+
 impl ParallelTopoSort {
     /// Создание нового экземпляра алгоритма
     pub fn new(worker_count: usize, batch_size: usize) -> Result<Self> {
@@ -108,11 +123,10 @@ impl ParallelTopoSort {
         let algo_time = algo_start.elapsed().as_millis() as u64;
         
         // 3. Создание маппинга позиций
-        let position_map: HashMap<String, usize> = order
-            .iter()
-            .enumerate()
-            .map(|(pos, vertex_id)| (vertex_id.clone(), pos))
-            .collect();
+        let mut positions = vec![0; graph.vertex_count()];
+        for (pos, vertex) in order.iter().enumerate() {
+            positions[vertex.index()] = pos;
+        }
         
         // 4. Расчет эффективности параллелизма
         let total_time = start_time.elapsed().as_millis() as u64;
@@ -130,38 +144,32 @@ impl ParallelTopoSort {
         
         Ok(TopoSortResult {
             order,
-            position_map,
+            positions,
             stats,
             level_count,
         })
     }
     
-    /// SIMD-оптимизированный подсчет входящих степеней
-    fn compute_in_degrees_simd(&self, graph: &Graph) -> Result<HashMap<String, AtomicUsize>> {
-        let vertex_ids: Vec<_> = graph.vertices().collect();
-        let in_degrees: HashMap<String, AtomicUsize> = vertex_ids
-            .iter()
-            .map(|id| ((*id).clone(), AtomicUsize::new(0)))
-            .collect();
+    /// SIMD-оптимизированный подсчет входящих степеней (по индексу handle)
+    fn compute_in_degrees_simd(&self, graph: &Graph) -> Result<Vec<AtomicUsize>> {
+        let vertices: Vec<VertexId> = graph.handles().collect();
+        let in_degrees: Vec<AtomicUsize> = vertices.iter().map(|_| AtomicUsize::new(0)).collect();
         
         // Параллельный подсчет входящих степеней
         let in_degrees_ref = &in_degrees;
         
-        vertex_ids
-            .par_chunks(self.batch_size)
-            .for_each(|chunk| {
-                for vertex_id in chunk {
-                    // Получаем исходящие связи для текущей вершины
-                    if let Some(outgoing) = graph.get_outgoing_edges(vertex_id) {
-                        for target_id in outgoing {
-                            // Атомарно увеличиваем входящую степень целевой вершины
-                            if let Some(target_degree) = in_degrees_ref.get(target_id) {
-                                target_degree.fetch_add(1, Ordering::Relaxed);
-                            }
+        scheduler::install(|| {
+            vertices
+                .par_chunks(self.batch_size)
+                .for_each(|chunk| {
+                    for &vertex in chunk {
+                        // Атомарно увеличиваем входящую степень целевых вершин
+                        for target in graph.successors(vertex) {
+                            in_degrees_ref[target.index()].fetch_add(1, Ordering::Relaxed);
                         }
                     }
-                }
-            });
+                });
+        });
         
         Ok(in_degrees)
     }
@@ -170,17 +178,17 @@ impl ParallelTopoSort {
     async fn kahn_parallel(
         &self,
         graph: &Graph,
-        in_degrees: HashMap<String, AtomicUsize>,
-    ) -> Result<(Vec<String>, usize, BatchStats)> {
+        in_degrees: Vec<AtomicUsize>,
+    ) -> Result<(Vec<VertexId>, usize, BatchStats)> {
         let mut result = Vec::with_capacity(graph.vertex_count());
         let mut queue = VecDeque::new();
         let mut level_count = 0;
         let mut batch_stats = BatchStats::default();
         
         // Инициализация очереди вершинами с нулевой входящей степенью
-        for (vertex_id, degree) in &in_degrees {
-            if degree.load(Ordering::Relaxed) == 0 {
-                queue.push_back(vertex_id.clone());
+        for vertex in graph.handles() {
+            if in_degrees[vertex.index()].load(Ordering::Relaxed) == 0 {
+                queue.push_back(vertex);
             }
         }
         
@@ -190,7 +198,8 @@ impl ParallelTopoSort {
             level_count += 1;
             
             // Обрабатываем текущий уровень батчами
-            let current_level: Vec<_> = queue.drain(..).collect();
+            let mut current_level: Vec<_> = queue.drain(..).collect();
+            scheduler::order_by(&mut current_level, |&vertex| graph.vertex_uid(vertex));
             
             // Параллельная обработка вершин текущего уровня
             let next_vertices = self.process_level_parallel(
@@ -224,43 +233,40 @@ impl ParallelTopoSort {
     /// Параллельная обработка уровня вершин
     async fn process_level_parallel(
         &self,
-        vertices: &[String],
+        vertices: &[VertexId],
         graph: &Graph,
-        in_degrees: &HashMap<String, AtomicUsize>,
-    ) -> Result<Vec<String>> {
+        in_degrees: &[AtomicUsize],
+    ) -> Result<Vec<VertexId>> {
         use std::sync::Mutex;
         
         let next_vertices = Arc::new(Mutex::new(Vec::new()));
         
         // Разбиваем вершины на батчи и обрабатываем параллельно
-        vertices
-            .par_chunks(self.batch_size)
-            .for_each(|chunk| {
-                let mut local_next = Vec::new();
+        scheduler::install(|| {
+            vertices
+                .par_chunks(self.batch_size)
+                .for_each(|chunk| {
+                    let mut local_next = Vec::new();
                 
-                for vertex_id in chunk {
-                    // Получаем исходящие связи
-                    if let Some(outgoing) = graph.get_outgoing_edges(vertex_id) {
-                        for target_id in outgoing {
+                    for &vertex in chunk {
+                        for target in graph.successors(vertex) {
                             // Атомарно уменьшаем входящую степень
-                            if let Some(target_degree) = in_degrees.get(target_id) {
-                                let new_degree = target_degree.fetch_sub(1, Ordering::Relaxed) - 1;
-                                
-                                // Если степень стала 0, добавляем в следующий уровень
-                                if new_degree == 0 {
-                                    local_next.push(target_id.clone());
-                                }
+                            let new_degree = in_degrees[target.index()].fetch_sub(1, Ordering::Relaxed) - 1;
+                            
+                            // Если степень стала 0, добавляем в следующий уровень
+                            if new_degree == 0 {
+                                local_next.push(target);
                             }
                         }
                     }
-                }
                 
-                // Объединяем локальные результаты
-                if !local_next.is_empty() {
-                    let mut global_next = next_vertices.lock().unwrap();
-                    global_next.extend(local_next);
-                }
-            });
+                    // Объединяем локальные результаты
+                    if !local_next.is_empty() {
+                        let mut global_next = next_vertices.lock().unwrap();
+                        global_next.extend(local_next);
+                    }
+                });
+        });
         
         let result = next_vertices.lock().unwrap().clone();
         Ok(result)
@@ -272,7 +278,7 @@ impl ParallelTopoSort {
     }
     
     /// Валидация результата топологической сортировки
-    pub fn validate_result(&self, graph: &Graph, order: &[String]) -> Result<()> {
+    pub fn validate_result(&self, graph: &Graph, order: &[VertexId]) -> Result<()> {
         // Проверка количества вершин
         if order.len() != graph.vertex_count() {
             return Err(anyhow::anyhow!(
@@ -283,26 +289,23 @@ impl ParallelTopoSort {
         }
         
         // Создание маппинга позиций
-        let position_map: HashMap<_, _> = order
-            .iter()
-            .enumerate()
-            .map(|(pos, vertex_id)| (vertex_id, pos))
-            .collect();
+        let mut positions = vec![usize::MAX; graph.vertex_count()];
+        for (pos, vertex) in order.iter().enumerate() {
+            positions[vertex.index()] = pos;
+        }
         
         // Проверка топологического порядка
-        for vertex_id in order {
-            if let Some(outgoing) = graph.get_outgoing_edges(vertex_id) {
-                let source_pos = position_map[vertex_id];
+        for &vertex in order {
+            let source_pos = positions[vertex.index()];
+            
+            for target in graph.successors(vertex) {
+                let target_pos = positions[target.index()];
                 
-                for target_id in outgoing {
-                    let target_pos = position_map[target_id];
-                    
-                    if source_pos >= target_pos {
-                        return Err(anyhow::anyhow!(
-                            "Нарушен топологический порядок: {} (pos {}) -> {} (pos {})",
-                            vertex_id, source_pos, target_id, target_pos
-                        ));
-                    }
+                if source_pos >= target_pos {
+                    return Err(anyhow::anyhow!(
+                        "Нарушен топологический порядок: {} (pos {}) -> {} (pos {})",
+                        graph.vertex_uid(vertex), source_pos, graph.vertex_uid(target), target_pos
+                    ));
                 }
             }
         }
@@ -347,9 +350,8 @@ mod tests {
         sorter.validate_result(&graph, &result.order)?;
         
         // Проверка порядка
-        let pos_a = result.position_map["A"];
-        let pos_b = result.position_map["B"];
-        let pos_c = result.position_map["C"];
+        let pos = |id: &str| result.position(graph.vertex_handle(id).unwrap());
+        let (pos_a, pos_b, pos_c) = (pos("A"), pos("B"), pos("C"));
         
         assert!(pos_a < pos_b);
         assert!(pos_b < pos_c);
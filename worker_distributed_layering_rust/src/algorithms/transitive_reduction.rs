@@ -0,0 +1,338 @@
+/*!
+# Транзитивная редукция перед построением графа
+
+Связь `A -> C` избыточна, если `C` достижима из `A` другим путём
+(`A -> B -> C`): порядок слоёв она не меняет, но тянется через весь рисунок
+и добавляет пересечения. В плотных сетях цитирования таких связей много —
+статья цитирует и обзор, и всё, что цитирует сам обзор.
+
+## Режимы
+
+1. **Точный** (до `exact_max_vertices` вершин): вершины обходятся в обратном
+   топологическом порядке, для каждой хранится битовое множество достижимых
+   вершин. Прямые потомки вершины перебираются по возрастанию топологического
+   индекса: потомок, уже попавший в множество, достижим через другого потомка —
+   связь к нему избыточна. O(V · E / 64) времени и V² / 8 байт памяти
+2. **Эвристический** (большие графы): от прямых потомков каждой вершины идёт
+   поиск в ширину глубиной до `max_hops` связей и не более чем `max_visits`
+   вершин. Найденный так прямой потомок — избыточная связь. Находит не все
+   избыточные связи, но ничего лишнего не удаляет
+
+Удаление любого набора избыточных связей DAG сохраняет достижимость, поэтому
+эвристика безопасна. Редукция выполняется после разрыва циклов; если граф
+всё же содержит цикл, связи возвращаются без изменений.
+*/
+
+use crate::neo4j::GraphEdge;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Параметры транзитивной редукции
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitiveReductionOptions {
+    /// Граф до этого числа вершин редуцируется точно, больший — эвристикой
+    pub exact_max_vertices: usize,
+
+    /// Максимальная длина обходного пути в эвристике (связей)
+    pub max_hops: usize,
+
+    /// Максимум вершин, просматриваемых эвристикой от одной вершины
+    pub max_visits: usize,
+}
+
+impl Default for TransitiveReductionOptions {
+    fn default() -> Self {
+        Self {
+            exact_max_vertices: 10_000,
+            max_hops: 3,
+            max_visits: 4096,
+        }
+    }
+}
+
+/// Итог транзитивной редукции для отчёта валидации
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TransitiveReductionStats {
+    /// Удалено избыточных связей (включая их дубликаты)
+    pub removed_edges: usize,
+
+    /// Редукция точная (иначе — эвристика, часть избыточных связей может остаться)
+    pub exact: bool,
+}
+
+/// Результат транзитивной редукции
+#[derive(Debug, Clone)]
+pub struct TransitiveReductionResult {
+    /// Связи без избыточных
+    pub edges: Vec<GraphEdge>,
+
+    /// Итог для отчёта
+    pub stats: TransitiveReductionStats,
+}
+
+/// Транзитивная редукция связей ацикличного графа
+///
+/// Связи с пустым ID и self-loops не рассматриваются и остаются как есть —
+/// их отбрасывает `GraphBuilder`.
+pub fn reduce(edges: Vec<GraphEdge>, options: TransitiveReductionOptions) -> TransitiveReductionResult {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut adjacency: Vec<Vec<usize>> = Vec::new();
+    for edge in &edges {
+        if !is_reducible(edge) {
+            continue;
+        }
+        let u = vertex_index(&mut index, &mut adjacency, edge.source_id.as_str());
+        let v = vertex_index(&mut index, &mut adjacency, edge.target_id.as_str());
+        adjacency[u].push(v);
+    }
+    for successors in &mut adjacency {
+        successors.sort_unstable();
+        successors.dedup();
+    }
+
+    let exact = adjacency.len() <= options.exact_max_vertices;
+    let redundant = if exact {
+        match redundant_exact(&adjacency) {
+            Some(redundant) => redundant,
+            None => return unchanged(edges, exact),
+        }
+    } else {
+        redundant_bounded(&adjacency, options.max_hops, options.max_visits)
+    };
+    if redundant.is_empty() {
+        return unchanged(edges, exact);
+    }
+
+    let keep: Vec<bool> = edges
+        .iter()
+        .map(|edge| {
+            !is_reducible(edge)
+                || !redundant.contains(&(index[edge.source_id.as_str()], index[edge.target_id.as_str()]))
+        })
+        .collect();
+    let removed_edges = keep.iter().filter(|&&keep| !keep).count();
+    let edges = edges
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(edge, keep)| keep.then_some(edge))
+        .collect();
+
+    TransitiveReductionResult {
+        edges,
+        stats: TransitiveReductionStats { removed_edges, exact },
+    }
+}
+
+fn vertex_index<'a>(
+    index: &mut HashMap<&'a str, usize>,
+    adjacency: &mut Vec<Vec<usize>>,
+    id: &'a str,
+) -> usize {
+    *index.entry(id).or_insert_with(|| {
+        adjacency.push(Vec::new());
+        adjacency.len() - 1
+    })
+}
+
+fn is_reducible(edge: &GraphEdge) -> bool {
+    !edge.source_id.trim().is_empty()
+        && !edge.target_id.trim().is_empty()
+        && edge.source_id != edge.target_id
+}
+
+fn unchanged(edges: Vec<GraphEdge>, exact: bool) -> TransitiveReductionResult {
+    TransitiveReductionResult {
+        edges,
+        stats: TransitiveReductionStats { removed_edges: 0, exact },
+    }
+}
+
+/// Топологический порядок (Кан); None — в графе есть цикл
+fn topological_order(adjacency: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let n = adjacency.len();
+    let mut in_degree = vec![0usize; n];
+    for successors in adjacency {
+        for &v in successors {
+            in_degree[v] += 1;
+        }
+    }
+    let mut order: Vec<usize> = (0..n).filter(|&v| in_degree[v] == 0).collect();
+    let mut head = 0;
+    while head < order.len() {
+        let u = order[head];
+        head += 1;
+        for &v in &adjacency[u] {
+            in_degree[v] -= 1;
+            if in_degree[v] == 0 {
+                order.push(v);
+            }
+        }
+    }
+    (order.len() == n).then_some(order)
+}
+
+/// Все избыточные связи по битовым множествам достижимости
+pub fn redundant_exact(adjacency: &[Vec<usize>]) -> Option<HashSet<(usize, usize)>> {
+    let order = topological_order(adjacency)?;
+    let n = adjacency.len();
+    let mut position = vec![0usize; n];
+    for (i, &v) in order.iter().enumerate() {
+        position[v] = i;
+    }
+
+    let words = n.div_ceil(64);
+    let mut reach = vec![0u64; n * words];
+    let mut redundant = HashSet::new();
+    let mut successors = Vec::new();
+    for &u in order.iter().rev() {
+        successors.clear();
+        successors.extend_from_slice(&adjacency[u]);
+        successors.sort_unstable_by_key(|&v| position[v]);
+
+        let (before, rest) = reach.split_at_mut(u * words);
+        let (reach_u, after) = rest.split_at_mut(words);
+        for &v in &successors {
+            if reach_u[v / 64] & (1 << (v % 64)) != 0 {
+                redundant.insert((u, v));
+                continue;
+            }
+            reach_u[v / 64] |= 1 << (v % 64);
+            let reach_v = if v < u {
+                &before[v * words..(v + 1) * words]
+            } else {
+                &after[(v - u - 1) * words..(v - u) * words]
+            };
+            for (word, &bits) in reach_u.iter_mut().zip(reach_v) {
+                *word |= bits;
+            }
+        }
+    }
+    Some(redundant)
+}
+
+/// Избыточные связи, подтверждённые обходными путями длиной до `max_hops`
+pub fn redundant_bounded(
+    adjacency: &[Vec<usize>],
+    max_hops: usize,
+    max_visits: usize,
+) -> HashSet<(usize, usize)> {
+    let n = adjacency.len();
+    // Метки хранят номер вершины, для которой они выставлены: без очистки между вершинами
+    let mut direct = vec![usize::MAX; n];
+    let mut seen = vec![usize::MAX; n];
+    let mut redundant = HashSet::new();
+    let mut frontier = Vec::new();
+    let mut next = Vec::new();
+
+    for u in 0..n {
+        if adjacency[u].len() < 2 {
+            continue;
+        }
+        for &v in &adjacency[u] {
+            direct[v] = u;
+            seen[v] = u;
+        }
+        frontier.clear();
+        frontier.extend_from_slice(&adjacency[u]);
+        let mut visits = frontier.len();
+
+        'search: for _ in 1..max_hops {
+            next.clear();
+            for &w in &frontier {
+                for &x in &adjacency[w] {
+                    if direct[x] == u {
+                        redundant.insert((u, x));
+                        // Повторно не учитывается
+                        direct[x] = usize::MAX;
+                    }
+                    if seen[x] != u {
+                        seen[x] = u;
+                        next.push(x);
+                        visits += 1;
+                        if visits >= max_visits {
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            std::mem::swap(&mut frontier, &mut next);
+        }
+    }
+    redundant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str) -> GraphEdge {
+        GraphEdge::new(source, target, 1.0)
+    }
+
+    fn pairs(edges: &[GraphEdge]) -> Vec<(&str, &str)> {
+        let mut pairs: Vec<_> = edges
+            .iter()
+            .map(|e| (e.source_id.as_str(), e.target_id.as_str()))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn test_exact_removes_shortcuts() {
+        // A -> B -> C -> D, ярлыки A -> C (дважды), A -> D, B -> D
+        let edges = vec![
+            edge("A", "B"),
+            edge("B", "C"),
+            edge("C", "D"),
+            edge("A", "C"),
+            edge("A", "C"),
+            edge("A", "D"),
+            edge("B", "D"),
+            edge("A", "A"),
+        ];
+        let result = reduce(edges, TransitiveReductionOptions::default());
+        assert!(result.stats.exact);
+        assert_eq!(result.stats.removed_edges, 4);
+        assert_eq!(pairs(&result.edges), vec![("A", "A"), ("A", "B"), ("B", "C"), ("C", "D")]);
+    }
+
+    #[test]
+    fn test_heuristic_is_bounded_by_hops() {
+        // Ярлык A -> C обходится путём из 2 связей, A -> E — из 3 (A -> C -> D -> E)
+        let edges = vec![
+            edge("A", "B"),
+            edge("B", "C"),
+            edge("C", "D"),
+            edge("D", "E"),
+            edge("A", "C"),
+            edge("A", "E"),
+        ];
+        let options = TransitiveReductionOptions {
+            exact_max_vertices: 0,
+            max_hops: 2,
+            ..TransitiveReductionOptions::default()
+        };
+        let result = reduce(edges.clone(), options);
+        assert!(!result.stats.exact);
+        assert_eq!(result.stats.removed_edges, 1);
+        assert!(!pairs(&result.edges).contains(&("A", "C")));
+
+        let exact = reduce(edges, TransitiveReductionOptions::default());
+        assert_eq!(exact.stats.removed_edges, 2);
+    }
+
+    #[test]
+    fn test_cycle_and_reduced_graph_are_untouched() {
+        let cyclic = vec![edge("A", "B"), edge("B", "A"), edge("A", "C"), edge("B", "C")];
+        let result = reduce(cyclic, TransitiveReductionOptions::default());
+        assert_eq!(result.stats.removed_edges, 0);
+        assert_eq!(result.edges.len(), 4);
+
+        let tree = vec![edge("A", "B"), edge("A", "C"), edge("B", "D")];
+        assert_eq!(reduce(tree, TransitiveReductionOptions::default()).edges.len(), 3);
+    }
+}
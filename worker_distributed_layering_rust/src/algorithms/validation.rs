@@ -0,0 +1,191 @@
+/*!
+# Валидация входных связей
+
+Один проход по связям с ограниченной памятью: считаются связи с пустым ID,
+self-loops и связи с нечисловым весом, а примеры сохраняются только в пределах
+заданного числа. Дубликаты здесь не ищутся — для этого нужен индекс всех
+связей, а их всё равно подсчитывает `GraphBuilder` при построении графа.
+
+Подробность логов настраивается: на входе в 50M связей построчные примеры
+не нужны, а в режиме `Quiet` проход останавливается на первой валидной связи.
+*/
+
+use anyhow::Result;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::algorithms::transitive_reduction::TransitiveReductionStats;
+use crate::data_structures::GraphBuilder;
+use crate::messages::Msg;
+use crate::neo4j::GraphEdge;
+
+/// Подробность диагностики валидации
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationVerbosity {
+    /// Без логов; проверяется только наличие хотя бы одной валидной связи
+    Quiet,
+
+    /// Одна строка со счётчиками
+    Summary,
+
+    /// Счётчики и до `samples` первых связей и первых невалидных связей
+    Detailed { samples: usize },
+}
+
+impl Default for ValidationVerbosity {
+    fn default() -> Self {
+        ValidationVerbosity::Summary
+    }
+}
+
+/// Итог валидации связей
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EdgeValidationReport {
+    /// Просмотрено связей (в режиме `Quiet` — до первой валидной)
+    pub edges_seen: usize,
+
+    /// Связей, которые примет `GraphBuilder`
+    pub valid_edges: usize,
+
+    /// Связей с пустым ID
+    pub invalid_ids: usize,
+
+    /// Self-loops
+    pub self_loops: usize,
+
+    /// Связей с весом NaN или бесконечностью
+    pub non_finite_weights: usize,
+
+    /// Первые связи (только в режиме `Detailed`)
+    pub samples: Vec<String>,
+
+    /// Первые невалидные связи (только в режиме `Detailed`)
+    pub invalid_samples: Vec<String>,
+
+    /// Итог транзитивной редукции (None — редукция выключена)
+    pub transitive_reduction: Option<TransitiveReductionStats>,
+}
+
+fn describe(edge: &GraphEdge) -> String {
+    format!("'{}' -> '{}' (вес: {})", edge.source_id, edge.target_id, edge.weight)
+}
+
+/// Валидация связей за один проход
+///
+/// Ошибка, если связей нет или ни одна из них не валидна.
+pub fn validate_edges<'a>(
+    edges: impl IntoIterator<Item = &'a GraphEdge>,
+    verbosity: ValidationVerbosity,
+) -> Result<EdgeValidationReport> {
+    let samples = match verbosity {
+        ValidationVerbosity::Detailed { samples } => samples,
+        _ => 0,
+    };
+    let mut report = EdgeValidationReport::default();
+
+    for edge in edges {
+        report.edges_seen += 1;
+        if report.samples.len() < samples {
+            report.samples.push(describe(edge));
+        }
+        if !edge.weight.is_finite() {
+            report.non_finite_weights += 1;
+        }
+
+        if GraphBuilder::accepts(&edge.source_id, &edge.target_id) {
+            report.valid_edges += 1;
+            if verbosity == ValidationVerbosity::Quiet {
+                break;
+            }
+            continue;
+        }
+
+        if edge.source_id == edge.target_id && !edge.source_id.trim().is_empty() {
+            report.self_loops += 1;
+        } else {
+            report.invalid_ids += 1;
+        }
+        if report.invalid_samples.len() < samples {
+            report.invalid_samples.push(describe(edge));
+        }
+    }
+
+    if report.edges_seen == 0 {
+        return Err(anyhow::anyhow!("{}", Msg::EmptyGraph));
+    }
+    if report.valid_edges == 0 {
+        return Err(anyhow::anyhow!("{}", Msg::NoValidEdges));
+    }
+
+    if verbosity != ValidationVerbosity::Quiet {
+        info!("{}", Msg::EdgeValidationSummary {
+            seen: report.edges_seen,
+            valid: report.valid_edges,
+            invalid_ids: report.invalid_ids,
+            self_loops: report.self_loops,
+            non_finite: report.non_finite_weights,
+        });
+    }
+    if !report.samples.is_empty() {
+        info!("{}", Msg::FirstEdges { count: report.samples.len() });
+        for (i, sample) in report.samples.iter().enumerate() {
+            info!("   {}. {}", i + 1, sample);
+        }
+    }
+    if !report.invalid_samples.is_empty() {
+        warn!("{}", Msg::FirstInvalidEdges { count: report.invalid_samples.len() });
+        for (i, sample) in report.invalid_samples.iter().enumerate() {
+            warn!("   {}. {}", i + 1, sample);
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str, weight: f32) -> GraphEdge {
+        GraphEdge::new(source, target, weight)
+    }
+
+    #[test]
+    fn test_single_pass_counts_and_bounded_samples() {
+        let edges = vec![
+            edge("A", "B", 1.0),
+            edge("", "B", 1.0),
+            edge("C", "C", 1.0),
+            edge("B", "C", f32::NAN),
+            edge("D", " ", 1.0),
+        ];
+
+        let report = validate_edges(&edges, ValidationVerbosity::Detailed { samples: 1 }).unwrap();
+        assert_eq!(report.edges_seen, 5);
+        assert_eq!(report.valid_edges, 2);
+        assert_eq!(report.invalid_ids, 2);
+        assert_eq!(report.self_loops, 1);
+        assert_eq!(report.non_finite_weights, 1);
+        assert_eq!(report.samples.len(), 1);
+        assert_eq!(report.invalid_samples.len(), 1);
+
+        let summary = validate_edges(&edges, ValidationVerbosity::Summary).unwrap();
+        assert!(summary.samples.is_empty() && summary.invalid_samples.is_empty());
+        assert_eq!(summary.valid_edges, 2);
+    }
+
+    #[test]
+    fn test_quiet_stops_at_first_valid_edge() {
+        let edges = vec![edge("", "B", 1.0), edge("A", "B", 1.0), edge("B", "C", 1.0)];
+        let report = validate_edges(&edges, ValidationVerbosity::Quiet).unwrap();
+        assert_eq!(report.edges_seen, 2);
+        assert_eq!(report.valid_edges, 1);
+    }
+
+    #[test]
+    fn test_empty_and_all_invalid_inputs_fail() {
+        assert!(validate_edges(&[], ValidationVerbosity::Summary).is_err());
+        let edges = vec![edge("A", "A", 1.0), edge("", "B", 1.0)];
+        assert!(validate_edges(&edges, ValidationVerbosity::Quiet).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+/// Anchor points for external annotations
+///
+/// A labeling service places callouts next to vertex blocks. For every vertex
+/// this pass offers candidate points in the margin above, below and to the
+/// right of its block and keeps only those no routed edge passes close to, so
+/// a callout attached there does not cover an edge. The left side is skipped:
+/// incoming edges enter blocks from the left.
+///
+/// Edge segments are bucketed into a uniform grid, so each candidate is only
+/// tested against the segments near it.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use super::placement::PlacementConfig;
+
+/// Side of the block an anchor belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorSide {
+    Top,
+    Bottom,
+    Right,
+}
+
+/// A free point for attaching an annotation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationAnchor {
+    pub side: AnchorSide,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Options for `annotation_anchors`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnnotationOptions {
+    /// Distance from the block edge to the anchor row/column (pixels)
+    pub margin: f32,
+
+    /// Candidate points per side, spread evenly along it
+    pub points_per_side: usize,
+
+    /// Minimum distance between an anchor and any edge segment (pixels)
+    pub clearance: f32,
+}
+
+impl Default for AnnotationOptions {
+    fn default() -> Self {
+        Self {
+            margin: 12.0,
+            points_per_side: 3,
+            clearance: 6.0,
+        }
+    }
+}
+
+type Segment = ((f32, f32), (f32, f32));
+
+/// Free annotation anchors per vertex, in top, bottom, right order
+///
+/// Vertices are keyed by ID in a `BTreeMap`, so the serialized result does not
+/// depend on hashing. Vertices whose candidates are all blocked map to an
+/// empty list.
+pub fn annotation_anchors(
+    positions: &[crate::neo4j::VertexPosition],
+    edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>,
+    config: &PlacementConfig,
+    options: &AnnotationOptions,
+) -> BTreeMap<String, Vec<AnnotationAnchor>> {
+    let segments: Vec<Segment> = edge_paths
+        .values()
+        .flat_map(|points| points.windows(2).map(|w| (w[0], w[1])))
+        .collect();
+    let cell = (config.block_width + config.horizontal_gap)
+        .max(config.block_height + config.vertical_gap)
+        .max(1.0);
+    let grid = SegmentGrid::new(&segments, cell, options.clearance);
+
+    let mut anchors = BTreeMap::new();
+    for pos in positions {
+        let (width, height) = config
+            .vertex_sizes
+            .get(&pos.article_id)
+            .copied()
+            .unwrap_or((config.block_width, config.block_height));
+
+        let free: Vec<AnnotationAnchor> = candidates(pos.x, pos.y, width, height, options)
+            .filter(|anchor| !grid.is_near_segment(&segments, anchor.x, anchor.y, options.clearance))
+            .collect();
+        anchors.insert(pos.article_id.clone(), free);
+    }
+    anchors
+}
+
+/// Evenly spread candidate points around one block
+fn candidates(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    options: &AnnotationOptions,
+) -> impl Iterator<Item = AnnotationAnchor> {
+    let n = options.points_per_side.max(1);
+    let margin = options.margin;
+    let along = move |start: f32, length: f32, i: usize| start + length * (i as f32 + 0.5) / n as f32;
+
+    let top = (0..n).map(move |i| AnnotationAnchor { side: AnchorSide::Top, x: along(x, width, i), y: y - margin });
+    let bottom = (0..n).map(move |i| AnnotationAnchor {
+        side: AnchorSide::Bottom,
+        x: along(x, width, i),
+        y: y + height + margin,
+    });
+    let right = (0..n).map(move |i| AnnotationAnchor {
+        side: AnchorSide::Right,
+        x: x + width + margin,
+        y: along(y, height, i),
+    });
+    top.chain(bottom).chain(right)
+}
+
+/// Edge segments bucketed by the grid cells their (padded) bounding boxes touch
+struct SegmentGrid {
+    cell: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SegmentGrid {
+    fn new(segments: &[Segment], cell: f32, padding: f32) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (idx, &((x1, y1), (x2, y2))) in segments.iter().enumerate() {
+            let (col_min, row_min) = cell_of(cell, x1.min(x2) - padding, y1.min(y2) - padding);
+            let (col_max, row_max) = cell_of(cell, x1.max(x2) + padding, y1.max(y2) + padding);
+            for col in col_min..=col_max {
+                for row in row_min..=row_max {
+                    cells.entry((col, row)).or_default().push(idx);
+                }
+            }
+        }
+        Self { cell, cells }
+    }
+
+    fn is_near_segment(&self, segments: &[Segment], x: f32, y: f32, clearance: f32) -> bool {
+        self.cells
+            .get(&cell_of(self.cell, x, y))
+            .into_iter()
+            .flatten()
+            .any(|&idx| point_segment_distance((x, y), segments[idx]) < clearance)
+    }
+}
+
+fn cell_of(cell: f32, x: f32, y: f32) -> (i32, i32) {
+    ((x / cell).floor() as i32, (y / cell).floor() as i32)
+}
+
+fn point_segment_distance((px, py): (f32, f32), ((x1, y1), (x2, y2)): Segment) -> f32 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((px - x1) * dx + (py - y1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (x1 + t * dx, y1 + t * dy);
+    ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, x: f32, y: f32) -> crate::neo4j::VertexPosition {
+        crate::neo4j::VertexPosition {
+            article_id: id.to_string(),
+            layer: 0,
+            level: 0,
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn test_anchors_skip_points_on_edges() {
+        let config = PlacementConfig::default();
+        let options = AnnotationOptions {
+            points_per_side: 1,
+            ..AnnotationOptions::default()
+        };
+        let positions = vec![position("A", 0.0, 0.0)];
+
+        let free = annotation_anchors(&positions, &HashMap::new(), &config, &options);
+        let sides: Vec<AnchorSide> = free["A"].iter().map(|a| a.side).collect();
+        assert_eq!(sides, vec![AnchorSide::Top, AnchorSide::Bottom, AnchorSide::Right]);
+
+        // An edge leaving the middle of the right side blocks the right anchor
+        let mid_y = config.block_height / 2.0;
+        let mut edge_paths = HashMap::new();
+        edge_paths.insert(
+            ("A".to_string(), "B".to_string()),
+            vec![(config.block_width, mid_y), (config.block_width + 200.0, mid_y)],
+        );
+        let free = annotation_anchors(&positions, &edge_paths, &config, &options);
+        let sides: Vec<AnchorSide> = free["A"].iter().map(|a| a.side).collect();
+        assert_eq!(sides, vec![AnchorSide::Top, AnchorSide::Bottom]);
+        assert_eq!(free["A"][0].y, -options.margin);
+    }
+}
@@ -0,0 +1,121 @@
+/// Bump arena for the temporary data of one placement run
+///
+/// Layering, crossing minimization and edge routing build many short-lived
+/// tables: in-degree maps, sort keys, crossing counts, per-layer block
+/// intervals. They are allocated from one `bumpalo::Bump` owned by the placer:
+/// an allocation is a pointer bump, nothing is freed individually, and the
+/// whole arena is released at once when the run ends. The bytes reserved by the
+/// arena at that point are the peak of all temporary data and are reported as
+/// `PlacementStats::arena_peak_bytes`.
+///
+/// The placer lives as long as the engine that owns it (for the gRPC service,
+/// the whole process), so `reset` keeps at most `MAX_RETAINED_BYTES` for the
+/// next run: after one huge request the arena goes back to the system allocator
+/// instead of pinning its peak for good.
+///
+/// Results (layer maps, positions, edge paths) are never allocated in the arena.
+/// `Bump` is not `Sync`, so it sits behind a mutex that is only locked inside
+/// synchronous steps (`LayoutArena::scope`), never across an `.await`.
+
+use bumpalo::Bump;
+use std::sync::{Mutex, MutexGuard};
+
+/// Vector allocated in the arena
+pub type ArenaVec<'a, T> = bumpalo::collections::Vec<'a, T>;
+
+/// Hash map allocated in the arena
+pub type ArenaMap<'a, K, V> = hashbrown::HashMap<K, V, hashbrown::hash_map::DefaultHashBuilder, &'a Bump>;
+
+/// Largest chunk kept by `LayoutArena::reset` for the next run
+pub const MAX_RETAINED_BYTES: usize = 16 * 1024 * 1024;
+
+/// Arena for the temporary allocations of one placement run
+#[derive(Debug, Default)]
+pub struct LayoutArena {
+    bump: Mutex<Bump>,
+}
+
+impl LayoutArena {
+    /// Create an empty arena
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a synchronous step with the arena
+    ///
+    /// Allocations made in `f` stay reserved until `reset`.
+    pub fn scope<R>(&self, f: impl FnOnce(&Bump) -> R) -> R {
+        f(&self.lock())
+    }
+
+    /// Bytes currently reserved by the arena
+    pub fn allocated_bytes(&self) -> usize {
+        self.lock().allocated_bytes()
+    }
+
+    /// Release all temporary allocations
+    ///
+    /// The largest chunk is kept, so the next run of a similar size does not
+    /// go back to the system allocator, unless it exceeds `MAX_RETAINED_BYTES`:
+    /// then the arena is freed entirely.
+    pub fn reset(&self) {
+        let mut bump = self.lock();
+        bump.reset();
+        if bump.allocated_bytes() > MAX_RETAINED_BYTES {
+            *bump = Bump::new();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Bump> {
+        // A panic inside a step leaves only dead temporaries behind
+        self.bump.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Copies start with an empty arena: temporaries are never shared between runs
+impl Clone for LayoutArena {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_allocations_are_released_by_reset() {
+        let arena = LayoutArena::new();
+
+        let sum = arena.scope(|bump| {
+            let mut values: ArenaVec<u64> = ArenaVec::with_capacity_in(64 * 1024, bump);
+            values.extend(0..64 * 1024);
+            let mut counts: ArenaMap<u64, usize> = ArenaMap::new_in(bump);
+            for &value in &values {
+                *counts.entry(value % 7).or_insert(0) += 1;
+            }
+            assert_eq!(counts.len(), 7);
+            values.iter().sum::<u64>()
+        });
+        assert_eq!(sum, (0..64 * 1024u64).sum::<u64>());
+
+        let peak = arena.allocated_bytes();
+        assert!(peak >= 64 * 1024 * std::mem::size_of::<u64>());
+        assert_eq!(arena.clone().allocated_bytes(), 0);
+
+        arena.reset();
+        assert!(arena.allocated_bytes() <= peak);
+    }
+
+    #[test]
+    fn test_reset_frees_chunks_above_retained_limit() {
+        let arena = LayoutArena::new();
+        arena.scope(|bump| {
+            bump.alloc_slice_fill_copy(MAX_RETAINED_BYTES + 1, 0u8);
+        });
+        assert!(arena.allocated_bytes() > MAX_RETAINED_BYTES);
+
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), 0);
+    }
+}
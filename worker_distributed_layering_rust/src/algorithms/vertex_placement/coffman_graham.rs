@@ -0,0 +1,345 @@
+/// Width-bounded layer assignment
+///
+/// BFS layering puts every vertex at its longest-path distance from the sources,
+/// so wide citation graphs get layers with tens of thousands of vertices. This
+/// module provides two ways to bound the layer width:
+///
+/// - `assign_layers_coffman_graham`: classic Coffman–Graham. Vertices are labelled
+///   in topological order (ties broken by the lexicographically smallest decreasing
+///   sequence of predecessor labels), then packed into layers of at most
+///   `max_width` vertices, filling layers from the sinks towards the sources.
+/// - `limit_layer_width`: post-pass over an existing layering that moves the
+///   overflow of a full layer into the following layers, keeping every edge
+///   pointed at a strictly higher layer.
+///
+/// Both keep the module convention: layer 0 holds the sources.
+
+use std::collections::BinaryHeap;
+use std::cmp::Reverse;
+use crate::data_structures::{Graph, VertexId};
+use super::layer_assignment::LayerMap;
+
+/// Graph with vertices renumbered in ID order (for reproducibility)
+struct IndexedDag {
+    ids: Vec<VertexId>,
+    preds: Vec<Vec<usize>>,
+    succs: Vec<Vec<usize>>,
+}
+
+impl IndexedDag {
+    fn from_graph(graph: &Graph) -> Self {
+        let mut ids: Vec<VertexId> = graph.handles().collect();
+        ids.sort_by_key(|&vertex| graph.vertex_uid(vertex));
+
+        // Position of every handle in `ids`
+        let mut index = vec![0usize; ids.len()];
+        for (i, vertex) in ids.iter().enumerate() {
+            index[vertex.index()] = i;
+        }
+
+        let mut preds = vec![Vec::new(); ids.len()];
+        let mut succs = vec![Vec::new(); ids.len()];
+        for (i, &vertex) in ids.iter().enumerate() {
+            for target in graph.successors(vertex) {
+                let j = index[target.index()];
+                succs[i].push(j);
+                preds[j].push(i);
+            }
+        }
+
+        Self { ids, preds, succs }
+    }
+}
+
+/// Coffman–Graham labels (1-based, topological)
+///
+/// A vertex becomes ready once all its predecessors are labelled; the ready vertex
+/// with the lexicographically smallest decreasing sequence of predecessor labels
+/// receives the next label. Returns `None` if the graph has a cycle.
+fn coffman_graham_labels(dag: &IndexedDag) -> Option<Vec<usize>> {
+    let n = dag.ids.len();
+    let mut labels = vec![0usize; n];
+    let mut pending: Vec<usize> = dag.preds.iter().map(Vec::len).collect();
+    let mut ready: BinaryHeap<Reverse<(Vec<usize>, usize)>> = BinaryHeap::new();
+
+    for v in 0..n {
+        if pending[v] == 0 {
+            ready.push(Reverse((Vec::new(), v)));
+        }
+    }
+
+    let mut next_label = 1;
+    while let Some(Reverse((_, v))) = ready.pop() {
+        labels[v] = next_label;
+        next_label += 1;
+
+        for &s in &dag.succs[v] {
+            pending[s] -= 1;
+            if pending[s] == 0 {
+                let mut sequence: Vec<usize> = dag.preds[s].iter().map(|&p| labels[p]).collect();
+                sequence.sort_unstable_by(|a, b| b.cmp(a));
+                ready.push(Reverse((sequence, s)));
+            }
+        }
+    }
+
+    (next_label == n + 1).then_some(labels)
+}
+
+/// Assign layers with the Coffman–Graham algorithm
+///
+/// Every layer receives at most `max_width` vertices (0 = unlimited). Layers are
+/// filled from the sinks: the unplaced vertex with the highest label whose
+/// successors all lie in already completed layers goes next.
+///
+/// Returns `None` if the graph has a cycle (cycles must be broken beforehand).
+pub fn assign_layers_coffman_graham(graph: &Graph, max_width: usize) -> Option<LayerMap> {
+    let dag = IndexedDag::from_graph(graph);
+    let labels = coffman_graham_labels(&dag)?;
+    let width = if max_width == 0 { usize::MAX } else { max_width };
+    let n = dag.ids.len();
+
+    // depth 0 is the last layer (sinks); converted to source-based layers at the end
+    let mut depth = vec![0usize; n];
+    let mut max_successor_depth: Vec<Option<usize>> = vec![None; n];
+    let mut pending: Vec<usize> = dag.succs.iter().map(Vec::len).collect();
+
+    let mut ready: BinaryHeap<(usize, usize)> = BinaryHeap::new();
+    for v in 0..n {
+        if pending[v] == 0 {
+            ready.push((labels[v], v));
+        }
+    }
+
+    let mut deferred: Vec<(usize, usize)> = Vec::new();
+    let mut current = 0usize;
+    let mut filled = 0usize;
+    let mut max_depth = 0usize;
+
+    loop {
+        let Some((label, v)) = ready.pop() else {
+            if deferred.is_empty() {
+                break;
+            }
+            // Every remaining candidate has a successor in the current layer
+            current += 1;
+            filled = 0;
+            ready.extend(deferred.drain(..));
+            continue;
+        };
+
+        if max_successor_depth[v] == Some(current) {
+            deferred.push((label, v));
+            continue;
+        }
+
+        depth[v] = current;
+        max_depth = max_depth.max(current);
+        filled += 1;
+
+        for &p in &dag.preds[v] {
+            max_successor_depth[p] = Some(current);
+            pending[p] -= 1;
+            if pending[p] == 0 {
+                ready.push((labels[p], p));
+            }
+        }
+
+        if filled >= width {
+            current += 1;
+            filled = 0;
+            ready.extend(deferred.drain(..));
+        }
+    }
+
+    Some(
+        dag.ids
+            .into_iter()
+            .zip(depth)
+            .map(|(id, d)| (id, (max_depth - d) as i32))
+            .collect(),
+    )
+}
+
+/// Free slots per layer with "next non-full layer" lookup (union-find style)
+struct LayerSlots {
+    width: usize,
+    occupancy: Vec<usize>,
+    next: Vec<usize>,
+}
+
+impl LayerSlots {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            occupancy: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    fn ensure(&mut self, layer: usize) {
+        while self.next.len() <= layer {
+            self.next.push(self.next.len());
+            self.occupancy.push(0);
+        }
+    }
+
+    /// First non-full layer at or after `layer`
+    fn find(&mut self, layer: usize) -> usize {
+        self.ensure(layer);
+        let mut root = layer;
+        while self.next[root] != root {
+            root = self.next[root];
+            self.ensure(root);
+        }
+
+        let mut cursor = layer;
+        while self.next[cursor] != root {
+            let following = self.next[cursor];
+            self.next[cursor] = root;
+            cursor = following;
+        }
+
+        root
+    }
+
+    /// Take a slot in the first non-full layer at or after `min_layer`
+    fn take(&mut self, min_layer: usize) -> usize {
+        let layer = self.find(min_layer);
+        self.occupancy[layer] += 1;
+        if self.occupancy[layer] >= self.width {
+            self.ensure(layer + 1);
+            self.next[layer] = layer + 1;
+        }
+        layer
+    }
+}
+
+/// Redistribute an existing layering so that no layer exceeds `max_width`
+///
+/// Vertices are processed by (layer, ID). Each vertex keeps its layer if there
+/// is room and it is still above all its (already moved) predecessors; otherwise
+/// it goes to the first non-full layer after that. `max_width == 0` returns
+/// the layering unchanged.
+pub fn limit_layer_width(
+    layer_map: &LayerMap,
+    graph: &Graph,
+    max_width: usize,
+) -> LayerMap {
+    if max_width == 0 {
+        return layer_map.clone();
+    }
+
+    let mut order: Vec<(VertexId, i32)> = layer_map.iter().map(|(&vertex, &layer)| (vertex, layer)).collect();
+    order.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| graph.vertex_uid(a.0).cmp(graph.vertex_uid(b.0))));
+
+    let mut slots = LayerSlots::new(max_width);
+    let mut result = LayerMap::with_capacity(layer_map.len());
+    let mut moved = 0usize;
+
+    for (vertex, layer) in order {
+        let mut min_layer = layer.max(0) as usize;
+        for pred in graph.predecessors(vertex) {
+            if let Some(&pred_layer) = result.get(&pred) {
+                min_layer = min_layer.max(pred_layer as usize + 1);
+            }
+        }
+
+        let new_layer = slots.take(min_layer) as i32;
+        if new_layer != layer {
+            moved += 1;
+        }
+        result.insert(vertex, new_layer);
+    }
+
+    if moved > 0 {
+        tracing::info!(
+            "Layer width limit {}: moved {} vertices, layers {} -> {}",
+            max_width,
+            moved,
+            layer_map.values().max().map_or(0, |&l| l + 1),
+            result.values().max().map_or(0, |&l| l + 1)
+        );
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::data_structures::GraphBuilder;
+
+    fn star(leaves: usize) -> Graph {
+        let mut builder = GraphBuilder::new();
+        for i in 0..leaves {
+            builder.add_edge("root".to_string(), format!("leaf{:02}", i), 1.0).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    fn max_layer_size(layers: &LayerMap) -> usize {
+        let mut counts: HashMap<i32, usize> = HashMap::new();
+        for &layer in layers.values() {
+            *counts.entry(layer).or_insert(0) += 1;
+        }
+        counts.values().copied().max().unwrap_or(0)
+    }
+
+    fn assert_edges_point_forward(layers: &LayerMap, graph: &Graph) {
+        for vertex in graph.handles() {
+            for target in graph.successors(vertex) {
+                assert!(
+                    layers[&vertex] < layers[&target],
+                    "{} -> {}",
+                    graph.vertex_uid(vertex),
+                    graph.vertex_uid(target)
+                );
+            }
+        }
+    }
+
+    fn layer_of(layers: &LayerMap, graph: &Graph, vertex_id: &str) -> i32 {
+        layers[&graph.vertex_handle(vertex_id).unwrap()]
+    }
+
+    #[test]
+    fn test_coffman_graham_respects_width() {
+        let graph = star(10);
+        let layers = assign_layers_coffman_graham(&graph, 3).unwrap();
+
+        assert_eq!(layers.len(), 11);
+        assert_eq!(layer_of(&layers, &graph, "root"), 0);
+        assert!(max_layer_size(&layers) <= 3);
+        assert_edges_point_forward(&layers, &graph);
+    }
+
+    #[test]
+    fn test_coffman_graham_unlimited_width_on_chain() {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let layers = assign_layers_coffman_graham(&graph, 0).unwrap();
+        assert_eq!(layer_of(&layers, &graph, "A"), 0);
+        assert_eq!(layer_of(&layers, &graph, "B"), 1);
+        assert_eq!(layer_of(&layers, &graph, "C"), 2);
+    }
+
+    #[test]
+    fn test_limit_layer_width_moves_overflow() {
+        let graph = star(5);
+        let layers: LayerMap = graph
+            .handles()
+            .map(|vertex| (vertex, if graph.vertex_uid(vertex) == "root" { 0 } else { 1 }))
+            .collect();
+
+        let limited = limit_layer_width(&layers, &graph, 2);
+        assert!(max_layer_size(&limited) <= 2);
+        assert_eq!(layer_of(&limited, &graph, "leaf00"), 1);
+        assert_eq!(layer_of(&limited, &graph, "leaf04"), 3);
+        assert_edges_point_forward(&limited, &graph);
+    }
+}
@@ -0,0 +1,353 @@
+/// Dummy vertices for long edges
+///
+/// An edge spanning more than one layer is split into a chain of dummy vertices,
+/// one per intermediate layer. The dummies take part in level assignment and
+/// crossing minimization like real vertices, so a long edge gets its own free
+/// slot in every layer it passes and is ordered together with its neighbours
+/// instead of being drawn straight through unrelated blocks.
+///
+/// After placement the dummies are stripped: their slots become the waypoints
+/// of the original edge, and only real vertices are returned.
+///
+/// Dummy IDs have the form `__dummy__<source>::<target>::<step>`, where `step`
+/// counts intermediate layers from the source starting at 1. Backward edges
+/// (from a higher to a lower layer) get chains as well, running towards the
+/// lower layers.
+///
+/// The number of dummies grows with the total layer span of all edges, so the
+/// placer counts them first (`count_dummy_vertices`) and falls back to regular
+/// edge routing above `PlacementConfig::max_dummy_vertices`.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use crate::data_structures::{Graph, GraphBuilder, VertexId};
+use super::layer_assignment::LayerMap;
+use super::edge_routing::{compute_self_loop_path, spread_ports, EdgeRoutingOptions, PortOffsets};
+use super::placement::{PlacementConfig, VertexPosition};
+
+/// Prefix of every dummy vertex ID
+pub const DUMMY_PREFIX: &str = "__dummy__";
+
+/// Dummy vertices of each long edge, ordered from source to target
+pub type DummyChains = HashMap<(String, String), Vec<String>>;
+
+/// Graph and layering with long edges replaced by dummy chains
+#[derive(Debug)]
+pub struct DummyExpansion {
+    /// Graph where every long edge is replaced by its chain
+    pub graph: Graph,
+
+    /// Layers of real and dummy vertices, keyed by handles of `graph`
+    pub layer_map: LayerMap,
+
+    /// Chains by original edge
+    pub chains: DummyChains,
+}
+
+/// ID of the `step`-th dummy vertex of the edge `source -> target`
+pub fn dummy_id(source: &str, target: &str, step: usize) -> String {
+    format!("{}{}::{}::{}", DUMMY_PREFIX, source, target, step)
+}
+
+/// Whether the vertex ID names a dummy vertex
+pub fn is_dummy(vertex_id: &str) -> bool {
+    vertex_id.starts_with(DUMMY_PREFIX)
+}
+
+/// Layer span of an edge that needs a dummy chain (more than one layer in
+/// either direction), as the source layer and the signed span
+fn long_span(layer_map: &LayerMap, source: VertexId, target: VertexId) -> Option<(i32, i32)> {
+    match (layer_map.get(&source), layer_map.get(&target)) {
+        (Some(&from), Some(&to)) if (to - from).abs() > 1 => Some((from, to - from)),
+        _ => None,
+    }
+}
+
+/// Number of dummy vertices `insert_dummy_vertices` would create
+pub fn count_dummy_vertices(graph: &Graph, layer_map: &LayerMap) -> usize {
+    graph
+        .handles()
+        .flat_map(|source| graph.successors(source).map(move |target| (source, target)))
+        .filter_map(|(source, target)| long_span(layer_map, source, target))
+        .map(|(_, span)| span.unsigned_abs() as usize - 1)
+        .sum()
+}
+
+/// Split every edge spanning more than one layer into a chain of dummy vertices
+///
+/// Edges within one layer or to an adjacent layer are kept as they are; long
+/// backward edges get a chain towards the lower layers. Chain edges inherit the
+/// weight of the original edge. The expanded graph is built deterministically,
+/// so the vertex order does not depend on hashing. Its handles differ from
+/// those of `graph`, so the layers are re-keyed for the expanded graph.
+pub fn insert_dummy_vertices(graph: &Graph, layer_map: &LayerMap) -> Result<DummyExpansion> {
+    let mut builder = GraphBuilder::new().deterministic(true);
+    let mut dummy_layers: HashMap<String, i32> = HashMap::new();
+    let mut chains = DummyChains::new();
+
+    for source in graph.handles() {
+        let source_id = graph.vertex_uid(source);
+        builder.add_vertex(source_id.to_string());
+
+        for target in graph.successors(source) {
+            let target_id = graph.vertex_uid(target);
+            let weight = graph.edge_weight(source, target).unwrap_or(1.0);
+
+            match long_span(layer_map, source, target) {
+                Some((from, span)) => {
+                    let direction = span.signum();
+                    let chain: Vec<String> = (1..span.unsigned_abs() as usize)
+                        .map(|step| dummy_id(source_id, target_id, step))
+                        .collect();
+                    let mut previous = source_id.to_string();
+                    for (step, dummy) in chain.iter().enumerate() {
+                        dummy_layers.insert(dummy.clone(), from + direction * (step as i32 + 1));
+                        builder.add_edge(previous, dummy.clone(), weight)?;
+                        previous = dummy.clone();
+                    }
+                    builder.add_edge(previous, target_id.to_string(), weight)?;
+                    chains.insert((source_id.to_string(), target_id.to_string()), chain);
+                }
+                None => builder.add_edge(source_id.to_string(), target_id.to_string(), weight)?,
+            }
+        }
+    }
+
+    let dummy_count: usize = chains.values().map(Vec::len).sum();
+    tracing::info!(
+        "Inserted {} dummy vertices for {} long edges",
+        dummy_count,
+        chains.len()
+    );
+
+    let expanded = builder.build()?;
+    let expanded_layers: LayerMap = expanded
+        .handles()
+        .filter_map(|vertex| {
+            let vertex_id = expanded.vertex_uid(vertex);
+            let layer = match graph.vertex_handle(vertex_id) {
+                Some(original) => layer_map.get(&original),
+                None => dummy_layers.get(vertex_id),
+            };
+            Some((vertex, *layer?))
+        })
+        .collect();
+
+    Ok(DummyExpansion {
+        graph: expanded,
+        layer_map: expanded_layers,
+        chains,
+    })
+}
+
+/// Compute paths of the original edges, passing through their dummy slots
+///
+/// Every path starts at the right side of the source block, crosses each dummy
+/// slot horizontally at its vertical centre (two waypoints per slot, the same
+/// form as the avoiding paths of `edge_routing`) and ends at the left side of
+/// the target.
+/// With `options.ports` the endpoints are spread over the block sides like in
+/// `edge_routing`. Self-loops get the same loop at the right side of their
+/// block as there.
+pub fn route_through_dummies(
+    positions: &[VertexPosition],
+    graph: &Graph,
+    chains: &DummyChains,
+    config: &PlacementConfig,
+    options: &EdgeRoutingOptions,
+) -> HashMap<(String, String), Vec<(f32, f32)>> {
+    let pos_map: HashMap<&str, &VertexPosition> = positions
+        .iter()
+        .map(|p| (p.vertex_id.as_str(), p))
+        .collect();
+    let ports = if options.ports {
+        dummy_ports(&pos_map, graph, chains, config)
+    } else {
+        HashMap::new()
+    };
+
+    let mut edge_paths = HashMap::new();
+    for source in graph.vertices() {
+        let Some(source_pos) = pos_map.get(source.as_str()) else { continue };
+        for target in graph.get_outgoing_edges(source).into_iter().flatten() {
+            let Some(target_pos) = pos_map.get(target.as_str()) else { continue };
+
+            let (source_width, source_height) = config.vertex_size(source);
+            let (_, target_height) = config.vertex_size(target);
+            let key = (source.clone(), target.clone());
+            let port = ports.get(&key).copied().unwrap_or_default();
+            let mut path = vec![(source_pos.x + source_width, source_pos.y + source_height / 2.0 + port.source)];
+            if let Some(chain) = chains.get(&key) {
+                let forward = target_pos.layer > source_pos.layer;
+                for d in chain.iter().filter_map(|dummy| pos_map.get(dummy.as_str())) {
+                    let y = d.y + config.block_height / 2.0;
+                    let (left, right) = (d.x, d.x + config.block_width);
+                    if forward {
+                        path.extend([(left, y), (right, y)]);
+                    } else {
+                        path.extend([(right, y), (left, y)]);
+                    }
+                }
+            }
+            path.push((target_pos.x, target_pos.y + target_height / 2.0 + port.target));
+            edge_paths.insert(key, path);
+        }
+    }
+
+    for vertex_id in graph.self_loop_vertices() {
+        if let Some(pos) = pos_map.get(vertex_id.as_str()) {
+            edge_paths.insert((vertex_id.clone(), vertex_id.clone()), compute_self_loop_path(pos, config));
+        }
+    }
+
+    edge_paths
+}
+
+/// Port offsets of the original edges for `route_through_dummies`
+///
+/// Like `edge_routing::assign_ports`, but a side of a block is ordered by the
+/// first slot its edges pass: the nearest dummy of a chain instead of the far
+/// end, so the ports follow the order of the chains next to the block.
+fn dummy_ports(
+    pos_map: &HashMap<&str, &VertexPosition>,
+    graph: &Graph,
+    chains: &DummyChains,
+    config: &PlacementConfig,
+) -> HashMap<(String, String), PortOffsets> {
+    let center_y = |vertex_id: &str| {
+        pos_map
+            .get(vertex_id)
+            .map(|p| p.y + config.vertex_size(vertex_id).1 / 2.0)
+    };
+    let mut ports: HashMap<(String, String), PortOffsets> = HashMap::new();
+    let mut side: Vec<(f32, &str)> = Vec::new();
+
+    for vertex in graph.vertices() {
+        let (_, height) = config.vertex_size(vertex);
+
+        side.clear();
+        for target in graph.get_outgoing_edges(vertex).into_iter().flatten() {
+            let next = chains
+                .get(&(vertex.clone(), target.clone()))
+                .and_then(|chain| chain.first())
+                .unwrap_or(target);
+            side.extend(center_y(next.as_str()).map(|y| (y, target.as_str())));
+        }
+        spread_ports(&mut side, height);
+        for &(offset, target) in &side {
+            ports.entry((vertex.clone(), target.to_string())).or_default().source = offset;
+        }
+
+        side.clear();
+        for source in graph.get_incoming_edges(vertex).into_iter().flatten() {
+            let previous = chains
+                .get(&(source.clone(), vertex.clone()))
+                .and_then(|chain| chain.last())
+                .unwrap_or(source);
+            side.extend(center_y(previous.as_str()).map(|y| (y, source.as_str())));
+        }
+        spread_ports(&mut side, height);
+        for &(offset, source) in &side {
+            ports.entry((source.to_string(), vertex.clone())).or_default().target = offset;
+        }
+    }
+    ports
+}
+
+/// Remove dummy vertices from the positions; returns how many were removed
+pub fn strip_dummy_vertices(positions: &mut Vec<VertexPosition>) -> usize {
+    let before = positions.len();
+    positions.retain(|p| !is_dummy(&p.vertex_id));
+    before - positions.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::placement::{place_graph_vertices, TieBreakKeys};
+
+    fn layers_of(graph: &Graph, layers: &[(&str, i32)]) -> LayerMap {
+        layers
+            .iter()
+            .map(|&(vertex_id, layer)| (graph.vertex_handle(vertex_id).unwrap(), layer))
+            .collect()
+    }
+
+    fn layer_of(expansion: &DummyExpansion, vertex_id: &str) -> i32 {
+        expansion.layer_map[&expansion.graph.vertex_handle(vertex_id).unwrap()]
+    }
+
+    #[test]
+    fn test_long_edge_gets_dummy_slots() -> Result<()> {
+        // A -> B -> C -> D and the long edge A -> D
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0)?;
+        builder.add_edge("C".to_string(), "D".to_string(), 1.0)?;
+        builder.add_edge("A".to_string(), "D".to_string(), 2.0)?;
+        let graph = builder.build()?;
+        let layers = layers_of(&graph, &[("A", 0), ("B", 1), ("C", 2), ("D", 3)]);
+
+        let expansion = insert_dummy_vertices(&graph, &layers)?;
+        let chain = &expansion.chains[&("A".to_string(), "D".to_string())];
+        assert_eq!(chain, &vec![dummy_id("A", "D", 1), dummy_id("A", "D", 2)]);
+        assert!(!expansion.graph.contains_edge("A", "D"));
+        assert!(expansion.graph.contains_edge(&chain[1], "D"));
+        assert_eq!(expansion.graph.get_edge_weight("A", &chain[0]), Some(2.0));
+        assert_eq!(layer_of(&expansion, &chain[1]), 2);
+        assert_eq!(layer_of(&expansion, "D"), 3);
+
+        let config = PlacementConfig::default();
+        let mut positions = place_graph_vertices(&expansion.layer_map, &expansion.graph, &TieBreakKeys::new(), &config);
+
+        // Dummies never share a slot with a real vertex
+        let mut slots = std::collections::HashSet::new();
+        assert!(positions.iter().all(|p| slots.insert((p.layer, p.level))));
+
+        let options = EdgeRoutingOptions::default();
+        let paths = route_through_dummies(&positions, &graph, &expansion.chains, &config, &options);
+        assert_eq!(paths[&("A".to_string(), "D".to_string())].len(), 6);
+        assert_eq!(paths[&("A".to_string(), "B".to_string())].len(), 2);
+
+        // Ports: the two edges of A leave its right side at different heights
+        let options = EdgeRoutingOptions { ports: true, ..EdgeRoutingOptions::default() };
+        let paths = route_through_dummies(&positions, &graph, &expansion.chains, &config, &options);
+        let to_b = paths[&("A".to_string(), "B".to_string())][0];
+        let to_d = paths[&("A".to_string(), "D".to_string())][0];
+        assert_eq!(to_b.0, to_d.0);
+        assert_ne!(to_b.1, to_d.1);
+
+        // Self-loops are kept out of the expansion but still get their loop
+        let mut builder = GraphBuilder::new().allow_self_loops(true);
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "B".to_string(), 1.0)?;
+        let looped = builder.build()?;
+        let loops = route_through_dummies(&positions, &looped, &DummyChains::new(), &config, &options);
+        assert_eq!(loops[&("B".to_string(), "B".to_string())].len(), 4);
+
+        assert_eq!(strip_dummy_vertices(&mut positions), 2);
+        assert_eq!(positions.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_backward_long_edge_chain_runs_downwards() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("D".to_string(), "A".to_string(), 1.0)?;
+        let graph = builder.build()?;
+        let layers = layers_of(&graph, &[("A", 0), ("B", 1), ("D", 3)]);
+
+        assert_eq!(count_dummy_vertices(&graph, &layers), 2);
+        let expansion = insert_dummy_vertices(&graph, &layers)?;
+        let chain = &expansion.chains[&("D".to_string(), "A".to_string())];
+        assert_eq!(layer_of(&expansion, &chain[0]), 2);
+        assert_eq!(layer_of(&expansion, &chain[1]), 1);
+        assert!(expansion.graph.contains_edge(&chain[1], "A"));
+
+        // Same expansion, same vertex order
+        let again = insert_dummy_vertices(&graph, &layers)?;
+        assert!(expansion.graph.vertices().eq(again.graph.vertices()));
+        Ok(())
+    }
+}
@@ -4,10 +4,57 @@
 /// that span multiple layers. For edges within the same layer or adjacent layers,
 /// simple straight lines can be used. For edges spanning multiple layers,
 /// we compute intermediate waypoints.
+///
+/// With `avoid_vertices` every edge spanning more than one layer crosses each
+/// intermediate layer column horizontally at a free height: the height of the
+/// straight line if no block rectangle there covers it, otherwise the nearest
+/// edge of the covering block, padded into the vertical gap. Block rectangles
+/// use the actual vertex sizes and are kept as sorted, merged y-intervals per
+/// layer column (`LayerIntervals`). Between columns the path only runs through
+/// the horizontal gaps, so it never cuts through an unrelated block.
+///
+/// With `channel_spacing` set as well, `allocate_channels` gives every edge
+/// crossing the same free gap of a layer column its own horizontal track, so
+/// edges bent to the same block boundary do not share waypoints. Paths routed
+/// through dummy vertices get their tracks from `allocate_dummy_channels`.
+///
+/// `bundle_edges` is an optional pass over the finished paths that merges the
+/// incoming edges of high-fan-in vertices into shared trunk segments; the
+/// routed waypoints through intermediate layers are kept.
+///
+/// With `ports` set, the endpoints of a vertex are spread over its block
+/// instead of all meeting at the vertical center: outgoing edges leave the right
+/// edge and incoming edges enter the left edge at evenly spaced ports, ordered
+/// by the y coordinate of the neighbor, so edges of one vertex do not cross at
+/// the block. Paths through dummy vertices use the same spreading, ordered by
+/// the nearest dummy slot (see `dummy_vertices::route_through_dummies`).
+///
+/// Self-loops (`Graph::self_loop_vertices`) get a small loop at the right side
+/// of their block, keyed by (vertex_id, vertex_id), with dummy vertices as well.
+/// Parallel edges share the path of their pair; `parallel_edge_paths` fans it
+/// out into one copy per edge.
+///
+/// With `simplify_tolerance` set, `simplify_edge_paths` drops waypoints that
+/// deviate from the simplified line by at most the tolerance (Douglas–Peucker):
+/// an edge across hundreds of layers keeps its bends instead of one waypoint
+/// per layer. The simplified path stays within the tolerance of the routed one,
+/// so with `avoid_vertices` it should stay below the `vertical_gap / 4` padding.
+///
+/// With `smoothing` set, `bezier_control_points` turns a finished polyline into
+/// a smooth curve: two cubic Bezier control points per segment, so clients draw
+/// the same curve instead of deriving their own.
+///
+/// `get_edge_statistics` measures the finished paths (`EdgeRoutingReport`):
+/// length, bends (direction changes, see `count_bends`) and layer span per
+/// edge, plus totals such as the wire length. Per-edge crossings come from
+/// `optimization::routed_crossings` and are added with `set_crossings`.
 
 use anyhow::Result;
-use std::collections::HashMap;
+use bumpalo::Bump;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use crate::data_structures::Graph;
+use super::arena::{ArenaMap, ArenaVec, LayoutArena};
 use super::placement::{VertexPosition, PlacementConfig};
 
 /// Layout options for edge routing
@@ -19,8 +66,26 @@ pub struct EdgeRoutingOptions {
     /// Minimum number of layers to span before using polylines
     pub polyline_threshold: i32,
 
-    /// Whether to route edges around vertices
+    /// Whether to route edges around vertices (applies to every edge spanning
+    /// more than one layer, regardless of `polyline_threshold`); not needed with
+    /// `PlacementConfig::dummy_vertices`, where long edges own their slots
     pub avoid_vertices: bool,
+
+    /// Spread edge endpoints along the left/right block edges (see `assign_ports`)
+    pub ports: bool,
+
+    /// Minimum distance between the tracks of edges crossing one gap of a layer
+    /// column (see `allocate_channels`, with `avoid_vertices` or dummy
+    /// vertices); None lets edges share a height
+    pub channel_spacing: Option<f32>,
+
+    /// Douglas–Peucker tolerance in pixels for dropping redundant waypoints
+    /// (see `simplify_edge_paths`); None keeps every waypoint
+    pub simplify_tolerance: Option<f32>,
+
+    /// Spline mode: tension of the Bezier curves through the waypoints
+    /// (see `bezier_control_points`); None keeps plain polylines
+    pub smoothing: Option<f32>,
 }
 
 impl Default for EdgeRoutingOptions {
@@ -29,10 +94,30 @@ impl Default for EdgeRoutingOptions {
             use_polylines: true,
             polyline_threshold: 2,
             avoid_vertices: false,
+            ports: false,
+            channel_spacing: None,
+            simplify_tolerance: None,
+            smoothing: None,
         }
     }
 }
 
+/// Counters of the vertex-avoiding routing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoutingStats {
+    /// Edges that had to bend around at least one block
+    pub edges_detoured: usize,
+
+    /// Layer columns where an edge left its straight line
+    pub detours: usize,
+
+    /// Layer crossings moved off their height onto a separate track
+    pub channel_shifts: usize,
+
+    /// Waypoints dropped by path simplification
+    pub points_removed: usize,
+}
+
 /// Compute edge paths (polylines) for all edges in the graph
 ///
 /// Returns a HashMap mapping (source_id, target_id) -> Vec of (x, y) waypoints
@@ -42,30 +127,77 @@ pub fn compute_edge_paths(
     config: &PlacementConfig,
     options: &EdgeRoutingOptions,
 ) -> Result<HashMap<(String, String), Vec<(f32, f32)>>> {
+    compute_edge_paths_with_stats(positions, graph, config, options).map(|(paths, _)| paths)
+}
+
+/// Compute edge paths together with the detour counters of `avoid_vertices`
+pub fn compute_edge_paths_with_stats(
+    positions: &[VertexPosition],
+    graph: &Graph,
+    config: &PlacementConfig,
+    options: &EdgeRoutingOptions,
+) -> Result<(HashMap<(String, String), Vec<(f32, f32)>>, RoutingStats)> {
+    compute_edge_paths_in(&LayoutArena::new(), positions, graph, config, options)
+}
+
+/// `compute_edge_paths_with_stats` with the position lookup and block
+/// intervals allocated in `arena` (the paths themselves are regular vectors)
+pub fn compute_edge_paths_in(
+    arena: &LayoutArena,
+    positions: &[VertexPosition],
+    graph: &Graph,
+    config: &PlacementConfig,
+    options: &EdgeRoutingOptions,
+) -> Result<(HashMap<(String, String), Vec<(f32, f32)>>, RoutingStats)> {
+    arena.scope(|bump| route_edges(bump, positions, graph, config, options))
+}
+
+fn route_edges(
+    bump: &Bump,
+    positions: &[VertexPosition],
+    graph: &Graph,
+    config: &PlacementConfig,
+    options: &EdgeRoutingOptions,
+) -> Result<(HashMap<(String, String), Vec<(f32, f32)>>, RoutingStats)> {
     tracing::info!("Computing edge paths...");
 
     let mut edge_paths = HashMap::new();
 
     // Build position lookup map
-    let pos_map: HashMap<&str, &VertexPosition> = positions
-        .iter()
-        .map(|p| (p.vertex_id.as_str(), p))
-        .collect();
+    let mut pos_map: ArenaMap<&str, &VertexPosition> = ArenaMap::with_capacity_in(positions.len(), bump);
+    pos_map.extend(positions.iter().map(|p| (p.vertex_id.as_str(), p)));
+
+    // Block rectangles from the actual coordinates, which compaction may change
+    let occupied = options
+        .avoid_vertices
+        .then(|| LayerIntervals::new_in(bump, positions, config));
+
+    let ports = options.ports.then(|| assign_ports(bump, positions, &pos_map, graph, config));
 
     let mut edges_processed = 0;
     let mut polylines_created = 0;
+    let mut stats = RoutingStats::default();
 
     // Process each edge
     for pos in positions {
         if let Some(outgoing) = graph.get_outgoing_edges(&pos.vertex_id) {
             for target_id in outgoing {
                 if let Some(target_pos) = pos_map.get(target_id.as_str()) {
-                    let path = compute_single_edge_path(
-                        pos,
-                        target_pos,
-                        config,
-                        options,
-                    )?;
+                    let port = ports
+                        .as_ref()
+                        .and_then(|ports| ports.get(&(pos.vertex_id.as_str(), target_id.as_str())).copied())
+                        .unwrap_or_default();
+                    let path = match occupied.as_ref() {
+                        Some(occupied) if (target_pos.layer - pos.layer).abs() > 1 => {
+                            let (path, detoured) = compute_avoiding_path(pos, target_pos, config, occupied, port);
+                            if detoured > 0 {
+                                stats.edges_detoured += 1;
+                                stats.detours += detoured;
+                            }
+                            path
+                        }
+                        _ => compute_single_edge_path(pos, target_pos, config, options, port)?,
+                    };
 
                     if path.len() > 2 {
                         polylines_created += 1;
@@ -82,13 +214,94 @@ pub fn compute_edge_paths(
         }
     }
 
+    if let (Some(occupied), Some(spacing)) = (occupied.as_ref(), options.channel_spacing) {
+        stats.channel_shifts = allocate_channels(bump, &mut edge_paths, &pos_map, occupied, spacing);
+    }
+
+    for vertex_id in graph.self_loop_vertices() {
+        if let Some(pos) = pos_map.get(vertex_id.as_str()) {
+            edge_paths.insert((vertex_id.clone(), vertex_id.clone()), compute_self_loop_path(pos, config));
+            edges_processed += 1;
+        }
+    }
+
     tracing::info!(
         "Edge path computation complete: {} edges processed, {} polylines created",
         edges_processed,
         polylines_created
     );
+    if options.avoid_vertices {
+        tracing::info!(
+            "Edge routing: {} edges detoured around blocks in {} layer columns",
+            stats.edges_detoured,
+            stats.detours
+        );
+        if options.channel_spacing.is_some() {
+            tracing::info!("Edge routing: {} layer crossings moved to separate tracks", stats.channel_shifts);
+        }
+    }
+
+    Ok((edge_paths, stats))
+}
+
+/// Offsets of the edge endpoints from the vertical block centers
+///
+/// `source` applies to the right edge of the source block, `target` to the
+/// left edge of the target block; both are zero without port assignment.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct PortOffsets {
+    pub(super) source: f32,
+    pub(super) target: f32,
+}
+
+/// Assign ports to the edges of every vertex
+///
+/// The `k` edges on one side of a block get ports at `height * i / (k + 1)`
+/// from its top, ordered by the center y of the neighbor (ties by ID), so a
+/// single edge keeps the vertical center. Returns the offsets keyed by
+/// (source_id, target_id).
+fn assign_ports<'a>(
+    bump: &'a Bump,
+    positions: &'a [VertexPosition],
+    pos_map: &ArenaMap<&str, &VertexPosition>,
+    graph: &'a Graph,
+    config: &PlacementConfig,
+) -> ArenaMap<'a, (&'a str, &'a str), PortOffsets> {
+    let center_y = |p: &VertexPosition| p.y + config.vertex_size(&p.vertex_id).1 / 2.0;
+    let mut ports: ArenaMap<(&str, &str), PortOffsets> = ArenaMap::new_in(bump);
+    let mut side: ArenaVec<(f32, &str)> = ArenaVec::new_in(bump);
+
+    for pos in positions {
+        let (_, height) = config.vertex_size(&pos.vertex_id);
+
+        side.clear();
+        if let Some(outgoing) = graph.get_outgoing_edges(&pos.vertex_id) {
+            side.extend(outgoing.filter_map(|t| pos_map.get(t.as_str()).map(|p| (center_y(p), t.as_str()))));
+        }
+        spread_ports(&mut side, height);
+        for &(offset, target) in side.iter() {
+            ports.entry((pos.vertex_id.as_str(), target)).or_default().source = offset;
+        }
+
+        side.clear();
+        if let Some(incoming) = graph.get_incoming_edges(&pos.vertex_id) {
+            side.extend(incoming.filter_map(|s| pos_map.get(s.as_str()).map(|p| (center_y(p), s.as_str()))));
+        }
+        spread_ports(&mut side, height);
+        for &(offset, source) in side.iter() {
+            ports.entry((source, pos.vertex_id.as_str())).or_default().target = offset;
+        }
+    }
+    ports
+}
 
-    Ok(edge_paths)
+/// Sort one side of a block by neighbor y and replace the keys with port offsets
+pub(super) fn spread_ports(side: &mut [(f32, &str)], height: f32) {
+    side.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+    let step = height / (side.len() + 1) as f32;
+    for (i, port) in side.iter_mut().enumerate() {
+        port.0 = step * (i + 1) as f32 - height / 2.0;
+    }
 }
 
 /// Compute the path for a single edge
@@ -100,19 +313,22 @@ fn compute_single_edge_path(
     target: &VertexPosition,
     config: &PlacementConfig,
     options: &EdgeRoutingOptions,
+    port: PortOffsets,
 ) -> Result<Vec<(f32, f32)>> {
     let layer_span = (target.layer - source.layer).abs();
 
     // For short edges or if polylines are disabled, use straight line
     if !options.use_polylines || layer_span < options.polyline_threshold {
+        let (source_width, source_height) = config.vertex_size(&source.vertex_id);
+        let (_, target_height) = config.vertex_size(&target.vertex_id);
         return Ok(vec![
-            (source.x + config.block_width, source.y + config.block_height / 2.0),
-            (target.x, target.y + config.block_height / 2.0),
+            (source.x + source_width, source.y + source_height / 2.0 + port.source),
+            (target.x, target.y + target_height / 2.0 + port.target),
         ]);
     }
 
     // For long edges, compute polyline with intermediate waypoints
-    compute_polyline(source, target, config)
+    compute_polyline(source, target, config, port)
 }
 
 /// Compute a polyline with intermediate waypoints
@@ -120,12 +336,15 @@ fn compute_polyline(
     source: &VertexPosition,
     target: &VertexPosition,
     config: &PlacementConfig,
+    port: PortOffsets,
 ) -> Result<Vec<(f32, f32)>> {
     let mut waypoints = Vec::new();
+    let (source_width, source_height) = config.vertex_size(&source.vertex_id);
+    let (_, target_height) = config.vertex_size(&target.vertex_id);
 
-    // Start point (right edge of source block, middle)
-    let start_x = source.x + config.block_width;
-    let start_y = source.y + config.block_height / 2.0;
+    // Start point (right edge of source block, at its port)
+    let start_x = source.x + source_width;
+    let start_y = source.y + source_height / 2.0 + port.source;
     waypoints.push((start_x, start_y));
 
     // Calculate number of intermediate layers
@@ -135,7 +354,7 @@ fn compute_polyline(
     // Add intermediate waypoints
     if num_intermediates > 0 {
         let x_step = (target.x - start_x) / (num_intermediates + 1) as f32;
-        let y_step = (target.y + config.block_height / 2.0 - start_y) / (num_intermediates + 1) as f32;
+        let y_step = (target.y + target_height / 2.0 + port.target - start_y) / (num_intermediates + 1) as f32;
 
         for i in 1..=num_intermediates {
             let waypoint_x = start_x + x_step * i as f32;
@@ -144,14 +363,337 @@ fn compute_polyline(
         }
     }
 
-    // End point (left edge of target block, middle)
+    // End point (left edge of target block, at its port)
     let end_x = target.x;
-    let end_y = target.y + config.block_height / 2.0;
+    let end_y = target.y + target_height / 2.0 + port.target;
     waypoints.push((end_x, end_y));
 
     Ok(waypoints)
 }
 
+/// Small rectangular loop at the right side of a block
+///
+/// Leaves the right edge a quarter of the block height above the center, runs
+/// a quarter of the horizontal gap to the right and comes back a quarter below
+/// the center, so it stays in the gap before the next layer.
+pub(super) fn compute_self_loop_path(pos: &VertexPosition, config: &PlacementConfig) -> Vec<(f32, f32)> {
+    let (width, height) = config.vertex_size(&pos.vertex_id);
+    let right = pos.x + width;
+    let center = pos.y + height / 2.0;
+    let (reach, half) = (config.horizontal_gap / 4.0, height / 4.0);
+    vec![
+        (right, center - half),
+        (right + reach, center - half),
+        (right + reach, center + half),
+        (right, center + half),
+    ]
+}
+
+/// Copies of a path for `count` parallel edges of one pair
+///
+/// Every waypoint of copy `i` is moved down by `(i - (count - 1) / 2) * spacing`,
+/// so the copies are centered on the original path. With `spacing` below
+/// `height / (2 * count)` the endpoints stay on the block edges.
+pub fn parallel_edge_paths(path: &[(f32, f32)], count: usize, spacing: f32) -> Vec<Vec<(f32, f32)>> {
+    let middle = count.saturating_sub(1) as f32 / 2.0;
+    (0..count)
+        .map(|i| {
+            let shift = (i as f32 - middle) * spacing;
+            path.iter().map(|&(x, y)| (x, y + shift)).collect()
+        })
+        .collect()
+}
+
+/// Occupied y-intervals of every layer column
+///
+/// Each block contributes `[y - pad, y + height + pad]`, where the padding is a
+/// quarter of the vertical gap, so a path at an interval boundary runs through
+/// the gap between blocks with some clearance. Overlapping intervals are merged,
+/// and the lookup is a binary search.
+struct LayerIntervals<'a> {
+    columns: ArenaMap<'a, i32, LayerColumn<'a>>,
+}
+
+struct LayerColumn<'a> {
+    /// Leftmost block x in the column
+    left: f32,
+
+    /// Rightmost block edge in the column
+    right: f32,
+
+    /// Sorted, disjoint occupied intervals (top, bottom)
+    intervals: ArenaVec<'a, (f32, f32)>,
+}
+
+impl<'a> LayerIntervals<'a> {
+    fn new_in(bump: &'a Bump, positions: &[VertexPosition], config: &PlacementConfig) -> Self {
+        let pad = config.vertical_gap / 4.0;
+
+        // Exact column sizes first, so no interval list is reallocated in the arena
+        let mut sizes: ArenaMap<i32, usize> = ArenaMap::new_in(bump);
+        for p in positions {
+            *sizes.entry(p.layer).or_insert(0) += 1;
+        }
+        let mut columns: ArenaMap<i32, LayerColumn> = ArenaMap::with_capacity_in(sizes.len(), bump);
+        for p in positions {
+            let (width, height) = config.vertex_size(&p.vertex_id);
+            let column = columns.entry(p.layer).or_insert_with(|| LayerColumn {
+                left: p.x,
+                right: p.x + width,
+                intervals: ArenaVec::with_capacity_in(sizes[&p.layer], bump),
+            });
+            column.left = column.left.min(p.x);
+            column.right = column.right.max(p.x + width);
+            column.intervals.push((p.y - pad, p.y + height + pad));
+        }
+
+        // Merge overlapping intervals in place
+        for column in columns.values_mut() {
+            let intervals = &mut column.intervals;
+            intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut merged = 0;
+            for i in 0..intervals.len() {
+                let (top, bottom) = intervals[i];
+                if merged > 0 && top <= intervals[merged - 1].1 {
+                    intervals[merged - 1].1 = intervals[merged - 1].1.max(bottom);
+                } else {
+                    intervals[merged] = (top, bottom);
+                    merged += 1;
+                }
+            }
+            intervals.truncate(merged);
+        }
+        Self { columns }
+    }
+
+    /// Horizontal extent of a layer column (computed from the grid if it is empty)
+    fn extent(&self, layer: i32, config: &PlacementConfig) -> (f32, f32) {
+        match self.columns.get(&layer) {
+            Some(column) => (column.left, column.right),
+            None => {
+                let left = layer as f32 * (config.block_width + config.horizontal_gap);
+                (left, left + config.block_width)
+            }
+        }
+    }
+
+    /// Nearest y to `wanted` not covered by a block of the layer
+    ///
+    /// Ties between the two boundaries of a covering interval go downwards.
+    fn free_y(&self, layer: i32, wanted: f32) -> f32 {
+        let Some(column) = self.columns.get(&layer) else {
+            return wanted;
+        };
+        // First interval whose bottom is not above `wanted`
+        let idx = column.intervals.partition_point(|&(_, bottom)| bottom < wanted);
+        match column.intervals.get(idx) {
+            Some(&(top, bottom)) if top < wanted && wanted < bottom => {
+                if wanted - top < bottom - wanted {
+                    top
+                } else {
+                    bottom
+                }
+            }
+            _ => wanted,
+        }
+    }
+
+    /// Free gap of the layer around the free height `y`
+    ///
+    /// The bounds are the neighboring interval boundaries (infinite above the
+    /// first and below the last block); a `y` on a boundary belongs to the gap
+    /// on the outer side of that boundary.
+    fn gap(&self, layer: i32, y: f32) -> (f32, f32) {
+        let Some(column) = self.columns.get(&layer) else {
+            return (f32::NEG_INFINITY, f32::INFINITY);
+        };
+        let intervals = &column.intervals;
+        let above = intervals.partition_point(|&(_, bottom)| bottom <= y);
+        let below = intervals.partition_point(|&(top, _)| top < y);
+        let top = if above > 0 { intervals[above - 1].1 } else { f32::NEG_INFINITY };
+        let bottom = intervals.get(below).map_or(f32::INFINITY, |&(top, _)| top);
+        (top, bottom)
+    }
+}
+
+/// One crossing of a layer column by an avoiding path
+struct ChannelClaim {
+    layer: i32,
+
+    /// Top of the free gap the crossing runs through
+    gap_top: f32,
+
+    /// Bottom of the gap
+    gap_bottom: f32,
+
+    /// Height of the straight line from source to target in this layer
+    wanted: f32,
+
+    /// Index into the routed paths
+    route: usize,
+
+    /// Index of the first of the two waypoints in the column
+    waypoint: usize,
+}
+
+/// Spread the layer crossings that share a gap over distinct tracks
+///
+/// Channel routing over the avoiding paths: every crossing of a layer column
+/// belongs to the free gap between two blocks it runs through. The crossings
+/// of one gap are ordered by the height of their straight lines (ties by edge
+/// key) and pushed apart to at least `spacing`, staying as close to their
+/// heights as possible; a gap too narrow for that spreads its tracks evenly
+/// between its bounds. The order of the edges inside a gap is kept, so no
+/// crossings are added. Returns the number of crossings that moved.
+fn allocate_channels(
+    bump: &Bump,
+    edge_paths: &mut HashMap<(String, String), Vec<(f32, f32)>>,
+    pos_map: &ArenaMap<&str, &VertexPosition>,
+    occupied: &LayerIntervals,
+    spacing: f32,
+) -> usize {
+    let mut routes: ArenaVec<(&(String, String), &mut Vec<(f32, f32)>)> = ArenaVec::new_in(bump);
+    let mut claims: ArenaVec<ChannelClaim> = ArenaVec::new_in(bump);
+    for (key, path) in edge_paths.iter_mut() {
+        let (Some(source), Some(target)) = (pos_map.get(key.0.as_str()), pos_map.get(key.1.as_str())) else {
+            continue;
+        };
+        let span = (target.layer - source.layer).abs();
+        // Only avoiding paths: two waypoints per intermediate layer
+        if span < 2 || path.len() != 2 * span as usize {
+            continue;
+        }
+        let step = if target.layer > source.layer { 1 } else { -1 };
+        let (start, end) = (path[0], path[path.len() - 1]);
+        for i in 1..span {
+            let layer = source.layer + step * i;
+            let waypoint = 2 * i as usize - 1;
+            let (gap_top, gap_bottom) = occupied.gap(layer, path[waypoint].1);
+            claims.push(ChannelClaim {
+                layer,
+                gap_top,
+                gap_bottom,
+                wanted: start.1 + (end.1 - start.1) * i as f32 / span as f32,
+                route: routes.len(),
+                waypoint,
+            });
+        }
+        routes.push((key, path));
+    }
+
+    claims.sort_by(|a, b| {
+        a.layer
+            .cmp(&b.layer)
+            .then_with(|| a.gap_top.total_cmp(&b.gap_top))
+            .then_with(|| a.wanted.total_cmp(&b.wanted))
+            .then_with(|| routes[a.route].0.cmp(routes[b.route].0))
+    });
+
+    let mut shifted = 0;
+    let mut tracks: ArenaVec<f32> = ArenaVec::new_in(bump);
+    let mut first = 0;
+    while first < claims.len() {
+        let (layer, gap_top) = (claims[first].layer, claims[first].gap_top);
+        let last = first + claims[first..].partition_point(|c| c.layer == layer && c.gap_top == gap_top);
+        let channel = &claims[first..last];
+        first = last;
+        if channel.len() < 2 {
+            continue;
+        }
+
+        let (top, bottom) = (channel[0].gap_top, channel[0].gap_bottom);
+        let step = spacing.min((bottom - top) / (channel.len() - 1) as f32);
+        // Downwards from the current heights, then back up from the bottom
+        tracks.clear();
+        for claim in channel {
+            let y = routes[claim.route].1[claim.waypoint].1;
+            let floor = tracks.last().map_or(top, |&previous| previous + step);
+            tracks.push(y.max(floor));
+        }
+        let mut ceiling = bottom;
+        for track in tracks.iter_mut().rev() {
+            *track = track.min(ceiling);
+            ceiling = *track - step;
+        }
+
+        for (claim, &track) in channel.iter().zip(tracks.iter()) {
+            let path = &mut routes[claim.route].1;
+            if path[claim.waypoint].1 != track {
+                path[claim.waypoint].1 = track;
+                path[claim.waypoint + 1].1 = track;
+                shifted += 1;
+            }
+        }
+    }
+    shifted
+}
+
+/// `allocate_channels` over the paths of `route_through_dummies`
+///
+/// `positions` must not contain dummy vertices any more: the free gaps of a
+/// layer column come from the real blocks, and the crossings of the dummy
+/// slots in one gap are spread like the avoiding paths. Returns the number of
+/// crossings that moved.
+pub(super) fn allocate_dummy_channels(
+    arena: &LayoutArena,
+    edge_paths: &mut HashMap<(String, String), Vec<(f32, f32)>>,
+    positions: &[VertexPosition],
+    config: &PlacementConfig,
+    spacing: f32,
+) -> usize {
+    arena.scope(|bump| {
+        let mut pos_map: ArenaMap<&str, &VertexPosition> = ArenaMap::with_capacity_in(positions.len(), bump);
+        pos_map.extend(positions.iter().map(|p| (p.vertex_id.as_str(), p)));
+        let occupied = LayerIntervals::new_in(bump, positions, config);
+        allocate_channels(bump, edge_paths, &pos_map, &occupied, spacing)
+    })
+}
+
+/// Compute a polyline that crosses every intermediate layer at a free height
+///
+/// Returns the waypoints and the number of layers where the straight line was
+/// blocked and the path had to bend around a block.
+fn compute_avoiding_path(
+    source: &VertexPosition,
+    target: &VertexPosition,
+    config: &PlacementConfig,
+    occupied: &LayerIntervals,
+    port: PortOffsets,
+) -> (Vec<(f32, f32)>, usize) {
+    let (source_width, source_height) = config.vertex_size(&source.vertex_id);
+    let (_, target_height) = config.vertex_size(&target.vertex_id);
+    let start = (source.x + source_width, source.y + source_height / 2.0 + port.source);
+    let end = (target.x, target.y + target_height / 2.0 + port.target);
+
+    let step = if target.layer > source.layer { 1 } else { -1 };
+    let span = (target.layer - source.layer).abs();
+
+    let mut waypoints = vec![start];
+    let mut detours = 0;
+    for i in 1..span {
+        let layer = source.layer + step * i;
+        let t = i as f32 / span as f32;
+        let wanted_y = start.1 + (end.1 - start.1) * t;
+
+        let y = occupied.free_y(layer, wanted_y);
+        if y != wanted_y {
+            detours += 1;
+        }
+
+        let (left, right) = occupied.extent(layer, config);
+        if step > 0 {
+            waypoints.push((left, y));
+            waypoints.push((right, y));
+        } else {
+            waypoints.push((right, y));
+            waypoints.push((left, y));
+        }
+    }
+    waypoints.push(end);
+
+    (waypoints, detours)
+}
+
 /// Compute orthogonal edge routing (manhattan-style)
 ///
 /// This creates edges that follow horizontal and vertical lines,
@@ -180,6 +722,212 @@ fn compute_orthogonal_path(
     Ok(waypoints)
 }
 
+/// Simplify every path in place; returns the number of dropped waypoints
+pub fn simplify_edge_paths(
+    edge_paths: &mut HashMap<(String, String), Vec<(f32, f32)>>,
+    tolerance: f32,
+) -> usize {
+    let mut removed = 0;
+    for path in edge_paths.values_mut() {
+        let before = path.len();
+        *path = simplify_path(path, tolerance);
+        removed += before - path.len();
+    }
+    tracing::info!("Path simplification: {} waypoints removed (tolerance {})", removed, tolerance);
+    removed
+}
+
+/// Douglas–Peucker simplification of one polyline
+///
+/// The end points are always kept. A waypoint is kept when it lies farther than
+/// `tolerance` from the segment between the kept points around it, so collinear
+/// runs collapse to their ends. Iterative, so paths of any length are safe.
+pub fn simplify_path(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, distance)) = farthest {
+            if distance > tolerance {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&point, keep)| keep.then_some(point))
+        .collect()
+}
+
+/// Distance from `point` to the segment `start`–`end`
+fn segment_distance(point: (f32, f32), start: (f32, f32), end: (f32, f32)) -> f32 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (x, y) = (start.0 + t * dx, start.1 + t * dy);
+    ((point.0 - x).powi(2) + (point.1 - y).powi(2)).sqrt()
+}
+
+/// Options for `bundle_edges`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeBundlingOptions {
+    /// Minimum number of incoming edges for a vertex to get a bundle
+    pub min_fan_in: usize,
+}
+
+impl Default for EdgeBundlingOptions {
+    fn default() -> Self {
+        Self { min_fan_in: 8 }
+    }
+}
+
+/// Bundle the incoming edges of high-fan-in vertices into shared trunks
+///
+/// Hierarchical bundling over the computed waypoints. All incoming edges of a
+/// target with at least `min_fan_in` of them meet at a junction in the
+/// horizontal gap before the target and share the trunk from there on. Edges
+/// from the same source layer first meet at a branch point in the gap after
+/// that layer, centered on their start points. Between the branch point (or
+/// the start) and the junction every edge keeps its routed waypoints, so the
+/// bundled paths pass intermediate layers where routing put them; only edges
+/// without such waypoints (the branch and the junction lie in the same gap or
+/// in neighbouring gaps) share the branch up to the junction. The new segments
+/// all run inside horizontal gaps and cross no block.
+///
+/// Returns the bundle ID of every bundled edge, keyed by (source_id,
+/// target_id). The bundle ID is the target vertex ID.
+pub fn bundle_edges(
+    edge_paths: &mut HashMap<(String, String), Vec<(f32, f32)>>,
+    positions: &[crate::neo4j::VertexPosition],
+    config: &PlacementConfig,
+    options: &EdgeBundlingOptions,
+) -> HashMap<(String, String), String> {
+    let pos_map: HashMap<&str, &crate::neo4j::VertexPosition> = positions
+        .iter()
+        .map(|p| (p.article_id.as_str(), p))
+        .collect();
+
+    // BTreeMap: bundles are built in a stable order
+    let mut incoming: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for edge in edge_paths.keys() {
+        incoming.entry(edge.1.clone()).or_default().push(edge.clone());
+    }
+
+    let mut bundles = HashMap::new();
+    for (target, mut edges) in incoming {
+        if edges.len() < options.min_fan_in.max(2) {
+            continue;
+        }
+        let Some(target_pos) = pos_map.get(target.as_str()) else {
+            continue;
+        };
+        let (_, target_height) = config.vertex_size(&target);
+        let junction = (
+            target_pos.x - config.horizontal_gap / 2.0,
+            target_pos.y + target_height / 2.0,
+        );
+
+        // Only forward edges that start before the junction can join the trunk
+        edges.sort();
+        let mut branches: BTreeMap<i32, Vec<((String, String), (f32, f32))>> = BTreeMap::new();
+        for edge in edges {
+            let (Some(source_pos), Some(&start)) = (
+                pos_map.get(edge.0.as_str()),
+                edge_paths.get(&edge).and_then(|path| path.first()),
+            ) else {
+                continue;
+            };
+            if source_pos.layer < target_pos.layer && start.0 < junction.0 {
+                branches.entry(source_pos.layer).or_default().push((edge, start));
+            }
+        }
+        if branches.values().map(Vec::len).sum::<usize>() < options.min_fan_in.max(2) {
+            continue;
+        }
+
+        for members in branches.values() {
+            let column_right = members
+                .iter()
+                .map(|((source, _), _)| pos_map[source.as_str()].x + config.vertex_size(source).0)
+                .fold(f32::MIN, f32::max);
+            let branch_x = column_right + config.horizontal_gap / 2.0;
+            let branch_y = members.iter().map(|(_, start)| start.1).sum::<f32>() / members.len() as f32;
+            let branch = (members.len() > 1 && branch_x < junction.0).then_some((branch_x, branch_y));
+
+            for (edge, start) in members {
+                let Some(path) = edge_paths.get_mut(edge) else {
+                    continue;
+                };
+                let end = path[path.len() - 1];
+                // Waypoints through the layers between the branch and the junction stay
+                let after = branch.map_or(start.0, |(x, _)| x);
+                let inner: Vec<(f32, f32)> = path[1..path.len() - 1]
+                    .iter()
+                    .copied()
+                    .filter(|&(x, _)| x > after && x < junction.0)
+                    .collect();
+                path.clear();
+                path.push(*start);
+                path.extend(branch);
+                path.extend(inner);
+                path.push(junction);
+                path.push(end);
+                bundles.insert(edge.clone(), target.clone());
+            }
+        }
+    }
+
+    tracing::info!(
+        "Edge bundling: {} edges merged into {} bundles",
+        bundles.len(),
+        bundles.values().collect::<std::collections::BTreeSet<_>>().len()
+    );
+    bundles
+}
+
+/// Cubic Bezier control points of a smooth curve through the waypoints
+///
+/// Returns two control points per segment `points[i] → points[i + 1]`, derived
+/// from a Catmull–Rom spline: the tangent at a waypoint is parallel to the line
+/// between its neighbors (the end points are their own outer neighbors), and
+/// `smoothing` in [0, 1] scales the tangents. 0 gives control points on the
+/// segments (the polyline itself), 1 the uniform Catmull–Rom curve. The curve
+/// passes through every waypoint, so ports and bundle junctions stay in place.
+pub fn bezier_control_points(points: &[(f32, f32)], smoothing: f32) -> Vec<(f32, f32)> {
+    let k = smoothing.clamp(0.0, 1.0);
+    let mut controls = Vec::with_capacity(points.len().saturating_sub(1) * 2);
+    for i in 0..points.len().saturating_sub(1) {
+        let (start, end) = (points[i], points[i + 1]);
+        let before = points[i.saturating_sub(1)];
+        let after = points.get(i + 2).copied().unwrap_or(end);
+        controls.push((
+            start.0 + (end.0 - before.0) * k / 6.0,
+            start.1 + (end.1 - before.1) * k / 6.0,
+        ));
+        controls.push((
+            end.0 - (after.0 - start.0) * k / 6.0,
+            end.1 - (after.1 - start.1) * k / 6.0,
+        ));
+    }
+    controls
+}
+
 /// Calculate edge length (useful for optimization)
 pub fn calculate_edge_length(path: &[(f32, f32)]) -> f32 {
     let mut total_length = 0.0;
@@ -197,19 +945,171 @@ pub fn calculate_edge_length(path: &[(f32, f32)]) -> f32 {
     total_length
 }
 
+/// Routing metrics of one edge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeRouteMetrics {
+    pub source_id: String,
+    pub target_id: String,
+
+    /// Length of the polyline in pixels
+    pub length: f32,
+
+    /// Direction changes along the polyline (see `count_bends`)
+    pub bends: usize,
+
+    /// Layers between source and target (0 for self-loops and same-layer edges)
+    pub layer_span: i32,
+
+    /// Crossings with other edges (see `EdgeRoutingReport::set_crossings`)
+    #[serde(default)]
+    pub crossings: usize,
+}
+
+/// Per-edge routing metrics with aggregates over all edges
+///
+/// Built from the finished paths by `get_edge_statistics`, so two routing
+/// configurations of the same layout can be compared by wire length and bends.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EdgeRoutingReport {
+    /// Metrics of every routed edge, sorted by (source_id, target_id)
+    pub edges: Vec<EdgeRouteMetrics>,
+
+    /// Total wire length in pixels
+    pub total_length: f32,
+
+    /// Length of the longest path
+    pub max_length: f32,
+
+    /// Bends over all paths
+    pub total_bends: usize,
+
+    /// Most bends of a single path
+    pub max_bends: usize,
+
+    /// Largest layer span of an edge
+    pub max_layer_span: i32,
+
+    /// Most crossings of a single edge
+    #[serde(default)]
+    pub max_crossings: usize,
+}
+
+impl EdgeRoutingReport {
+    /// Average path length (0 without edges)
+    pub fn average_length(&self) -> f32 {
+        if self.edges.is_empty() {
+            0.0
+        } else {
+            self.total_length / self.edges.len() as f32
+        }
+    }
+
+    /// Metrics of one edge
+    pub fn get(&self, source_id: &str, target_id: &str) -> Option<&EdgeRouteMetrics> {
+        self.edges
+            .binary_search_by(|e| (e.source_id.as_str(), e.target_id.as_str()).cmp(&(source_id, target_id)))
+            .ok()
+            .map(|i| &self.edges[i])
+    }
+
+    /// Per-edge crossing counts keyed by (source_id, target_id); edges
+    /// without an entry get 0
+    pub fn set_crossings(&mut self, per_edge: &HashMap<(String, String), usize>) {
+        self.max_crossings = 0;
+        for edge in &mut self.edges {
+            edge.crossings = per_edge
+                .get(&(edge.source_id.clone(), edge.target_id.clone()))
+                .copied()
+                .unwrap_or(0);
+            self.max_crossings = self.max_crossings.max(edge.crossings);
+        }
+    }
+}
+
+/// Sine of the smallest angle counted as a bend (about 0.5°)
+const BEND_SINE: f32 = 0.01;
+
+/// Direction changes along a polyline
+///
+/// A waypoint is a bend when the segments before and after it are not
+/// collinear: the sine of the angle between them exceeds `BEND_SINE`, or the
+/// path turns back. Zero-length segments are skipped, so waypoints on a
+/// straight run (e.g. the two points of a layer crossing in line with the
+/// rest of the path) are not bends.
+pub fn count_bends(path: &[(f32, f32)]) -> usize {
+    let mut bends = 0;
+    let mut previous: Option<(f32, f32)> = None;
+    for segment in path.windows(2) {
+        let direction = (segment[1].0 - segment[0].0, segment[1].1 - segment[0].1);
+        let length = direction.0.hypot(direction.1);
+        if length == 0.0 {
+            continue;
+        }
+        let direction = (direction.0 / length, direction.1 / length);
+        if let Some(before) = previous {
+            let sine = before.0 * direction.1 - before.1 * direction.0;
+            let cosine = before.0 * direction.0 + before.1 * direction.1;
+            if sine.abs() > BEND_SINE || cosine < 0.0 {
+                bends += 1;
+            }
+        }
+        previous = Some(direction);
+    }
+    bends
+}
+
 /// Get statistics about edge paths
-pub fn get_edge_statistics(edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>) {
-    let total_edges = edge_paths.len();
-    let polylines = edge_paths.values().filter(|path| path.len() > 2).count();
-    let straight_lines = total_edges - polylines;
+///
+/// `layer_of` gives the layer of a vertex; edges with an unknown end get a
+/// layer span of 0. The aggregates are logged as well.
+pub fn get_edge_statistics(
+    edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>,
+    layer_of: impl Fn(&str) -> Option<i32>,
+) -> EdgeRoutingReport {
+    let mut report = EdgeRoutingReport::default();
+    report.edges.reserve(edge_paths.len());
+    for ((source_id, target_id), path) in edge_paths {
+        let layer_span = match (layer_of(source_id), layer_of(target_id)) {
+            (Some(source), Some(target)) => (target - source).abs(),
+            _ => 0,
+        };
+        let metrics = EdgeRouteMetrics {
+            source_id: source_id.clone(),
+            target_id: target_id.clone(),
+            length: calculate_edge_length(path),
+            bends: count_bends(path),
+            layer_span,
+            crossings: 0,
+        };
+        report.total_length += metrics.length;
+        report.max_length = report.max_length.max(metrics.length);
+        report.total_bends += metrics.bends;
+        report.max_bends = report.max_bends.max(metrics.bends);
+        report.max_layer_span = report.max_layer_span.max(layer_span);
+        report.edges.push(metrics);
+    }
+    report
+        .edges
+        .sort_by(|a, b| (&a.source_id, &a.target_id).cmp(&(&b.source_id, &b.target_id)));
 
-    let avg_waypoints = edge_paths.values().map(|p| p.len()).sum::<usize>() as f32 / total_edges as f32;
+    let total_edges = report.edges.len();
+    let polylines = report.edges.iter().filter(|e| e.bends > 0).count();
+    let avg_waypoints = edge_paths.values().map(|p| p.len()).sum::<usize>() as f32 / total_edges.max(1) as f32;
 
     tracing::info!("Edge Path Statistics:");
     tracing::info!("  Total edges: {}", total_edges);
-    tracing::info!("  Straight lines: {}", straight_lines);
+    tracing::info!("  Straight lines: {}", total_edges - polylines);
     tracing::info!("  Polylines: {}", polylines);
     tracing::info!("  Average waypoints per edge: {:.2}", avg_waypoints);
+    tracing::info!(
+        "  Wire length: {:.0} px total, {:.1} px average, {:.1} px max",
+        report.total_length,
+        report.average_length(),
+        report.max_length
+    );
+    tracing::info!("  Bends: {} total, {} max; max layer span {}", report.total_bends, report.max_bends, report.max_layer_span);
+
+    report
 }
 
 #[cfg(test)]
@@ -237,7 +1137,7 @@ mod tests {
             level: 0,
         };
 
-        let path = compute_single_edge_path(&source, &target, &config, &options).unwrap();
+        let path = compute_single_edge_path(&source, &target, &config, &options, PortOffsets::default()).unwrap();
 
         assert_eq!(path.len(), 2); // Start and end points only
         assert_eq!(path[0].0, 160.0); // Source right edge
@@ -265,11 +1165,388 @@ mod tests {
             level: 0,
         };
 
-        let path = compute_single_edge_path(&source, &target, &config, &options).unwrap();
+        let path = compute_single_edge_path(&source, &target, &config, &options, PortOffsets::default()).unwrap();
 
         assert!(path.len() > 2); // Should have intermediate waypoints
     }
 
+    #[test]
+    fn test_avoiding_path_detours_around_blocks() {
+        let config = PlacementConfig::default();
+        let row_height = config.block_height + config.vertical_gap;
+        let column_width = config.block_width + config.horizontal_gap;
+
+        let position = |id: &str, layer: i32, level: i32| VertexPosition {
+            vertex_id: id.to_string(),
+            x: layer as f32 * column_width,
+            y: level as f32 * row_height,
+            layer,
+            level,
+        };
+
+        // A -> D spans two intermediate layers; B blocks the straight line in layer 1
+        let positions = vec![
+            position("A", 0, 0),
+            position("B", 1, 0),
+            position("C", 2, 1),
+            position("D", 3, 0),
+        ];
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        builder.add_edge("A".to_string(), "D".to_string(), 1.0).unwrap();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let options = EdgeRoutingOptions {
+            avoid_vertices: true,
+            ..EdgeRoutingOptions::default()
+        };
+        let (paths, stats) = compute_edge_paths_with_stats(&positions, &graph, &config, &options).unwrap();
+
+        let path = &paths[&("A".to_string(), "D".to_string())];
+        let center = config.block_height / 2.0;
+        let below_b = config.block_height + config.vertical_gap / 4.0;
+        assert_eq!(path.len(), 6);
+        // Layer 1 is blocked by B at the straight line: the path bends below it
+        assert_eq!(path[1], (column_width, below_b));
+        assert_eq!(path[2], (column_width + config.block_width, below_b));
+        // In layer 2 the straight line is free
+        assert_eq!(path[3], (2.0 * column_width, center));
+        assert_eq!(stats, RoutingStats { edges_detoured: 1, detours: 1, channel_shifts: 0, points_removed: 0 });
+
+        // Adjacent layers keep the straight line
+        assert_eq!(paths[&("A".to_string(), "B".to_string())].len(), 2);
+
+        // No waypoint lies inside a block
+        for &(x, y) in path {
+            for p in &positions {
+                let inside = x > p.x && x < p.x + config.block_width
+                    && y > p.y && y < p.y + config.block_height;
+                assert!(!inside, "waypoint ({}, {}) inside block {}", x, y, p.vertex_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_avoiding_path_uses_vertex_sizes() {
+        let mut config = PlacementConfig::default();
+        let column_width = config.block_width + config.horizontal_gap;
+        // B is three rows tall: a uniform grid would only see its first row
+        config.vertex_sizes.insert("B".to_string(), (config.block_width, 3.0 * config.block_height));
+
+        let positions = vec![
+            VertexPosition { vertex_id: "A".to_string(), x: 0.0, y: 200.0, layer: 0, level: 0 },
+            VertexPosition { vertex_id: "B".to_string(), x: column_width, y: 0.0, layer: 1, level: 0 },
+            VertexPosition { vertex_id: "C".to_string(), x: 2.0 * column_width, y: 200.0, layer: 2, level: 0 },
+        ];
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        builder.add_edge("A".to_string(), "C".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let options = EdgeRoutingOptions { avoid_vertices: true, ..EdgeRoutingOptions::default() };
+        let (paths, stats) = compute_edge_paths_with_stats(&positions, &graph, &config, &options).unwrap();
+
+        let path = &paths[&("A".to_string(), "C".to_string())];
+        let bottom_of_b = 3.0 * config.block_height + config.vertical_gap / 4.0;
+        assert_eq!(path[1].1, bottom_of_b);
+        assert_eq!(stats.edges_detoured, 1);
+    }
+
+    #[test]
+    fn test_channels_separate_shared_gap() {
+        let config = PlacementConfig::default();
+        let column_width = config.block_width + config.horizontal_gap;
+        let position = |id: &str, layer: i32, y: f32| VertexPosition {
+            vertex_id: id.to_string(),
+            x: layer as f32 * column_width,
+            y,
+            layer,
+            level: 0,
+        };
+
+        // X blocks both straight lines in layer 1; both bend to its bottom boundary
+        let positions = vec![
+            position("A", 0, 0.0),
+            position("B", 0, 130.0),
+            position("X", 1, 0.0),
+            position("C", 2, 0.0),
+            position("D", 2, -100.0),
+        ];
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        builder.add_edge("A".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "D".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let below_x = config.block_height + config.vertical_gap / 4.0;
+        let options = EdgeRoutingOptions { avoid_vertices: true, ..EdgeRoutingOptions::default() };
+        let (shared, _) = compute_edge_paths_with_stats(&positions, &graph, &config, &options).unwrap();
+        assert_eq!(shared[&("A".to_string(), "C".to_string())][1].1, below_x);
+        assert_eq!(shared[&("B".to_string(), "D".to_string())][1].1, below_x);
+
+        let options = EdgeRoutingOptions { channel_spacing: Some(10.0), ..options };
+        let (paths, stats) = compute_edge_paths_with_stats(&positions, &graph, &config, &options).unwrap();
+        let upper = &paths[&("A".to_string(), "C".to_string())];
+        let lower = &paths[&("B".to_string(), "D".to_string())];
+        // A -> C wants the higher line and keeps the boundary; B -> D gets the next track
+        assert_eq!((upper[1].1, upper[2].1), (below_x, below_x));
+        assert_eq!((lower[1].1, lower[2].1), (below_x + 10.0, below_x + 10.0));
+        assert_eq!(stats.channel_shifts, 1);
+    }
+
+    #[test]
+    fn test_dummy_channels_spread_close_slots() {
+        let config = PlacementConfig::default();
+        let column_width = config.block_width + config.horizontal_gap;
+        let position = |id: &str, layer: i32, y: f32| VertexPosition {
+            vertex_id: id.to_string(),
+            x: layer as f32 * column_width,
+            y,
+            layer,
+            level: 0,
+        };
+        // Dummies already stripped: layer 1 has no real block, one free gap
+        let positions = vec![
+            position("A", 0, 0.0),
+            position("B", 0, 130.0),
+            position("C", 2, 0.0),
+            position("D", 2, 260.0),
+        ];
+        let crossing = |y: f32| vec![(column_width, y), (column_width + config.block_width, y)];
+        let mut paths = HashMap::new();
+        let mut upper = vec![(config.block_width, 40.0)];
+        upper.extend(crossing(40.0));
+        upper.push((2.0 * column_width, 40.0));
+        let mut lower = vec![(config.block_width, 170.0)];
+        lower.extend(crossing(45.0));
+        lower.push((2.0 * column_width, 300.0));
+        paths.insert(("A".to_string(), "C".to_string()), upper);
+        paths.insert(("B".to_string(), "D".to_string()), lower);
+
+        let shifted = allocate_dummy_channels(&LayoutArena::new(), &mut paths, &positions, &config, 10.0);
+        assert_eq!(shifted, 1);
+        assert_eq!(paths[&("A".to_string(), "C".to_string())][1].1, 40.0);
+        assert_eq!(paths[&("B".to_string(), "D".to_string())][1].1, 50.0);
+        assert_eq!(paths[&("B".to_string(), "D".to_string())][2].1, 50.0);
+    }
+
+    #[test]
+    fn test_ports_follow_neighbor_order() {
+        let config = PlacementConfig::default();
+        let row_height = config.block_height + config.vertical_gap;
+        let column_width = config.block_width + config.horizontal_gap;
+        let position = |id: &str, layer: i32, level: i32| VertexPosition {
+            vertex_id: id.to_string(),
+            x: layer as f32 * column_width,
+            y: level as f32 * row_height,
+            layer,
+            level,
+        };
+
+        // A fans out to B above and C below; B also receives D from the row above A
+        let positions = vec![position("D", 0, 0), position("A", 0, 1), position("B", 1, 0), position("C", 1, 2)];
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        for (source, target) in [("A", "B"), ("A", "C"), ("D", "B")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0).unwrap();
+        }
+        let graph = builder.build().unwrap();
+
+        let options = EdgeRoutingOptions { ports: true, ..EdgeRoutingOptions::default() };
+        let paths = compute_edge_paths(&positions, &graph, &config, &options).unwrap();
+        let path = |source: &str, target: &str| paths[&(source.to_string(), target.to_string())].clone();
+        let sixth = config.block_height / 6.0;
+        let center = |level: i32| level as f32 * row_height + config.block_height / 2.0;
+        let assert_close = |actual: f32, expected: f32| {
+            assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+        };
+
+        // Outgoing ports of A: upper one towards B, lower one towards C
+        assert_close(path("A", "B")[0].1, center(1) - sixth);
+        assert_close(path("A", "C")[0].1, center(1) + sixth);
+        // Incoming ports of B: D from above first
+        assert_close(path("D", "B").last().unwrap().1, center(0) - sixth);
+        assert_close(path("A", "B").last().unwrap().1, center(0) + sixth);
+        // Single edges stay centered
+        assert_close(path("D", "B")[0].1, center(0));
+        assert_close(path("A", "C").last().unwrap().1, center(2));
+    }
+
+    #[test]
+    fn test_self_loop_and_parallel_paths() {
+        let config = PlacementConfig::default();
+        let positions = vec![
+            VertexPosition { vertex_id: "A".to_string(), x: 0.0, y: 0.0, layer: 0, level: 0 },
+            VertexPosition { vertex_id: "B".to_string(), x: 240.0, y: 0.0, layer: 1, level: 0 },
+        ];
+        let mut builder = crate::data_structures::GraphBuilder::new()
+            .allow_self_loops(true)
+            .allow_parallel_edges(true);
+        for (source, target) in [("A", "B"), ("A", "B"), ("B", "B")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0).unwrap();
+        }
+        let graph = builder.build().unwrap();
+
+        let paths = compute_edge_paths(&positions, &graph, &config, &EdgeRoutingOptions::default()).unwrap();
+        assert_eq!(paths.len(), 2);
+
+        // The loop starts and ends on the right edge of B and stays in the gap
+        let center = config.block_height / 2.0;
+        let right = 240.0 + config.block_width;
+        let quarter = config.block_height / 4.0;
+        let loop_path = &paths[&("B".to_string(), "B".to_string())];
+        assert_eq!(loop_path.first(), Some(&(right, center - quarter)));
+        assert_eq!(loop_path.last(), Some(&(right, center + quarter)));
+        assert!(loop_path.iter().all(|&(x, _)| x >= right && x < right + config.horizontal_gap));
+
+        let copies = parallel_edge_paths(&paths[&("A".to_string(), "B".to_string())], 2, 10.0);
+        assert_eq!(copies.len(), 2);
+        assert_eq!(copies[0][0], (config.block_width, center - 5.0));
+        assert_eq!(copies[1][1], (240.0, center + 5.0));
+    }
+
+    #[test]
+    fn test_simplify_path() {
+        // A straight run across 200 layers collapses to its ends
+        let straight: Vec<(f32, f32)> = (0..=200)
+            .map(|i| (i as f32 * 240.0, 40.0 + i as f32 * 0.5))
+            .collect();
+        assert_eq!(simplify_path(&straight, 0.5), vec![straight[0], straight[200]]);
+
+        // A detour farther than the tolerance survives, a smaller wiggle does not
+        let path = vec![(0.0, 0.0), (10.0, 0.2), (20.0, 0.0), (30.0, 25.0), (40.0, 0.0)];
+        assert_eq!(simplify_path(&path, 1.0), vec![(0.0, 0.0), (20.0, 0.0), (30.0, 25.0), (40.0, 0.0)]);
+        assert_eq!(simplify_path(&path[..2], 1.0), path[..2].to_vec());
+
+        let mut paths = HashMap::new();
+        paths.insert(("A".to_string(), "B".to_string()), straight);
+        paths.insert(("B".to_string(), "C".to_string()), path);
+        assert_eq!(simplify_edge_paths(&mut paths, 1.0), 199 + 1);
+    }
+
+    #[test]
+    fn test_bundle_edges_share_trunk() {
+        let config = PlacementConfig::default();
+        let row_height = config.block_height + config.vertical_gap;
+        let column_width = config.block_width + config.horizontal_gap;
+        let position = |id: &str, layer: i32, level: i32| crate::neo4j::VertexPosition {
+            article_id: id.to_string(),
+            layer,
+            level,
+            x: layer as f32 * column_width,
+            y: level as f32 * row_height,
+        };
+        let center = config.block_height / 2.0;
+        let straight = |from: &crate::neo4j::VertexPosition, to: &crate::neo4j::VertexPosition| {
+            vec![(from.x + config.block_width, from.y + center), (to.x, to.y + center)]
+        };
+
+        // T has fan-in 3 (two sources in layer 0, one in layer 2); U has fan-in 1
+        let positions = vec![
+            position("S1", 0, 0),
+            position("S2", 0, 2),
+            position("S3", 2, 1),
+            position("T", 3, 1),
+            position("U", 1, 0),
+        ];
+        let mut edge_paths = HashMap::new();
+        for (source, target) in [(0, 3), (1, 3), (2, 3), (0, 4)] {
+            edge_paths.insert(
+                (positions[source].article_id.clone(), positions[target].article_id.clone()),
+                straight(&positions[source], &positions[target]),
+            );
+        }
+        let untouched = edge_paths[&("S1".to_string(), "U".to_string())].clone();
+
+        let bundles = bundle_edges(&mut edge_paths, &positions, &config, &EdgeBundlingOptions { min_fan_in: 3 });
+
+        assert_eq!(bundles.len(), 3);
+        assert!(bundles.values().all(|id| id == "T"));
+        assert!(!bundles.contains_key(&("S1".to_string(), "U".to_string())));
+        assert_eq!(edge_paths[&("S1".to_string(), "U".to_string())], untouched);
+
+        let junction = (3.0 * column_width - config.horizontal_gap / 2.0, row_height + center);
+        let end = (3.0 * column_width, row_height + center);
+        let s1 = &edge_paths[&("S1".to_string(), "T".to_string())];
+        let s2 = &edge_paths[&("S2".to_string(), "T".to_string())];
+        let s3 = &edge_paths[&("S3".to_string(), "T".to_string())];
+
+        // Layer 0 sources share a branch point centered between them
+        let branch = (config.block_width + config.horizontal_gap / 2.0, (center + 2.0 * row_height + center) / 2.0);
+        assert_eq!(s1[1..], [branch, junction, end]);
+        assert_eq!(s2[1..], [branch, junction, end]);
+        // A lone source goes straight to the junction
+        assert_eq!(s3[1..], [junction, end]);
+
+        // A routed path keeps its crossing of layers 1 and 2 below the blocks there
+        let detour = vec![
+            (config.block_width, center),
+            (column_width, 3.0 * row_height),
+            (column_width + config.block_width, 3.0 * row_height),
+            (2.0 * column_width, 3.0 * row_height),
+            (2.0 * column_width + config.block_width, 3.0 * row_height),
+            (3.0 * column_width, row_height + center),
+        ];
+        edge_paths.insert(("S1".to_string(), "T".to_string()), detour.clone());
+        bundle_edges(&mut edge_paths, &positions, &config, &EdgeBundlingOptions { min_fan_in: 3 });
+        let s1 = &edge_paths[&("S1".to_string(), "T".to_string())];
+        assert_eq!(s1[1], branch);
+        assert_eq!(s1[2..6], detour[1..5]);
+        assert_eq!(s1[6..], [junction, end]);
+    }
+
+    #[test]
+    fn test_bezier_control_points() {
+        let points = vec![(0.0, 0.0), (60.0, 0.0), (120.0, 60.0)];
+
+        let flat = bezier_control_points(&points, 0.0);
+        assert_eq!(flat, vec![(0.0, 0.0), (60.0, 0.0), (60.0, 0.0), (120.0, 60.0)]);
+
+        let smooth = bezier_control_points(&points, 1.0);
+        assert_eq!(smooth.len(), 4);
+        // The tangent at the middle waypoint is shared by both segments
+        let incoming = (points[1].0 - smooth[1].0, points[1].1 - smooth[1].1);
+        let outgoing = (smooth[2].0 - points[1].0, smooth[2].1 - points[1].1);
+        assert_eq!(incoming, outgoing);
+        assert_eq!(incoming, (20.0, 10.0));
+
+        assert!(bezier_control_points(&points[..1], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_edge_routing_report() {
+        let mut paths = HashMap::new();
+        paths.insert(("A".to_string(), "B".to_string()), vec![(0.0, 0.0), (3.0, 4.0)]);
+        paths.insert(("A".to_string(), "C".to_string()), vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (20.0, 10.0)]);
+        let layers: HashMap<&str, i32> = [("A", 0), ("B", 1), ("C", 3)].into_iter().collect();
+
+        let report = get_edge_statistics(&paths, |id| layers.get(id).copied());
+
+        assert_eq!(report.edges.len(), 2);
+        assert_eq!(report.edges[0].target_id, "B");
+        assert_eq!(report.total_length, 35.0);
+        assert_eq!(report.max_length, 30.0);
+        assert_eq!(report.average_length(), 17.5);
+        assert_eq!((report.total_bends, report.max_bends, report.max_layer_span), (2, 2, 3));
+
+        let long = report.get("A", "C").unwrap();
+        assert_eq!((long.length, long.bends, long.layer_span), (30.0, 2, 3));
+        assert!(report.get("B", "C").is_none());
+        assert_eq!(get_edge_statistics(&HashMap::new(), |_| None), EdgeRoutingReport::default());
+
+        let mut report = report;
+        report.set_crossings(&[(("A".to_string(), "C".to_string()), 3)].into_iter().collect());
+        assert_eq!((report.get("A", "B").unwrap().crossings, report.get("A", "C").unwrap().crossings), (0, 3));
+        assert_eq!(report.max_crossings, 3);
+    }
+
+    #[test]
+    fn test_count_bends_ignores_collinear_waypoints() {
+        // A layer crossing in line with the path adds waypoints but no bends
+        assert_eq!(count_bends(&[(0.0, 0.0), (10.0, 5.0), (20.0, 10.0), (30.0, 15.0)]), 0);
+        assert_eq!(count_bends(&[(0.0, 0.0), (10.0, 0.0), (10.0, 0.0), (20.0, 10.0)]), 1);
+        // Turning back is a bend as well
+        assert_eq!(count_bends(&[(0.0, 0.0), (10.0, 0.0), (5.0, 0.0)]), 1);
+        assert_eq!(count_bends(&[(0.0, 0.0), (1.0, 1.0)]), 0);
+    }
+
     #[test]
     fn test_edge_length_calculation() {
         let path = vec![(0.0, 0.0), (3.0, 4.0)]; // 3-4-5 triangle
@@ -0,0 +1,232 @@
+/// Adjacency storage for `GlobalLayerState`
+///
+/// Vertices are interned to dense `u32` indices by the layer state; this module
+/// only stores edges between those indices. Two backends are available:
+///
+/// - `Memory` keeps one compact `Vec<u32>` of successors and one of predecessors
+///   per vertex (8 bytes per edge instead of two `String` copies in hash sets);
+/// - `Disk` spills the adjacency into a temporary sled database, so only the
+///   per-vertex arrays stay in RAM. It is selected by `MemoryStrategy::Streaming`
+///   for graphs whose edges do not fit into memory.
+///
+/// Disk keys are `(vertex, neighbor)` as big-endian bytes, so a prefix scan by
+/// vertex returns its neighbors.
+
+use std::path::Path;
+use anyhow::{Context, Result};
+
+/// Edge storage backend
+#[derive(Debug)]
+pub enum EdgeStore {
+    Memory(MemoryEdges),
+    Disk(DiskEdges),
+}
+
+/// In-memory adjacency vectors indexed by vertex
+#[derive(Debug, Default)]
+pub struct MemoryEdges {
+    outgoing: Vec<Vec<u32>>,
+    incoming: Vec<Vec<u32>>,
+}
+
+/// Adjacency spilled into a temporary sled database
+#[derive(Debug)]
+pub struct DiskEdges {
+    _db: sled::Db,
+    outgoing: sled::Tree,
+    incoming: sled::Tree,
+}
+
+impl EdgeStore {
+    /// In-memory store
+    pub fn memory() -> Self {
+        EdgeStore::Memory(MemoryEdges::default())
+    }
+
+    /// Disk-backed store in a fresh directory under `dir` (removed on drop)
+    pub fn disk(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("cannot create spill directory {}", dir.display()))?;
+        let path = dir.join(format!("layer_state_{}", uuid::Uuid::new_v4()));
+        let db = sled::Config::new()
+            .path(&path)
+            .temporary(true)
+            .open()
+            .with_context(|| format!("cannot open edge store at {}", path.display()))?;
+        let outgoing = db.open_tree("outgoing")?;
+        let incoming = db.open_tree("incoming")?;
+        Ok(EdgeStore::Disk(DiskEdges { _db: db, outgoing, incoming }))
+    }
+
+    /// Whether edges are spilled to disk
+    pub fn is_disk(&self) -> bool {
+        matches!(self, EdgeStore::Disk(_))
+    }
+
+    /// Add `source -> target`; returns false if the edge already exists
+    pub fn insert(&mut self, source: u32, target: u32) -> Result<bool> {
+        match self {
+            EdgeStore::Memory(edges) => {
+                let needed = source.max(target) as usize + 1;
+                if edges.outgoing.len() < needed {
+                    edges.outgoing.resize_with(needed, Vec::new);
+                    edges.incoming.resize_with(needed, Vec::new);
+                }
+                let targets = &mut edges.outgoing[source as usize];
+                if targets.contains(&target) {
+                    return Ok(false);
+                }
+                targets.push(target);
+                edges.incoming[target as usize].push(source);
+                Ok(true)
+            }
+            EdgeStore::Disk(edges) => {
+                let previous = edges.outgoing.insert(edge_key(source, target), &[])?;
+                if previous.is_some() {
+                    return Ok(false);
+                }
+                edges.incoming.insert(edge_key(target, source), &[])?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Remove `source -> target`; returns false if there was no such edge
+    pub fn remove(&mut self, source: u32, target: u32) -> Result<bool> {
+        match self {
+            EdgeStore::Memory(edges) => {
+                let removed = edges
+                    .outgoing
+                    .get_mut(source as usize)
+                    .map_or(false, |targets| swap_remove_item(targets, target));
+                if removed {
+                    swap_remove_item(&mut edges.incoming[target as usize], source);
+                }
+                Ok(removed)
+            }
+            EdgeStore::Disk(edges) => {
+                if edges.outgoing.remove(edge_key(source, target))?.is_none() {
+                    return Ok(false);
+                }
+                edges.incoming.remove(edge_key(target, source))?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Successors of a vertex
+    pub fn outgoing(&self, vertex: u32) -> Result<Vec<u32>> {
+        match self {
+            EdgeStore::Memory(edges) => Ok(neighbors(&edges.outgoing, vertex)),
+            EdgeStore::Disk(edges) => scan_neighbors(&edges.outgoing, vertex),
+        }
+    }
+
+    /// Predecessors of a vertex
+    pub fn incoming(&self, vertex: u32) -> Result<Vec<u32>> {
+        match self {
+            EdgeStore::Memory(edges) => Ok(neighbors(&edges.incoming, vertex)),
+            EdgeStore::Disk(edges) => scan_neighbors(&edges.incoming, vertex),
+        }
+    }
+
+    /// Visit every edge as `(source, target)`
+    pub fn for_each_edge(&self, mut visit: impl FnMut(u32, u32)) -> Result<()> {
+        match self {
+            EdgeStore::Memory(edges) => {
+                for (source, targets) in edges.outgoing.iter().enumerate() {
+                    for &target in targets {
+                        visit(source as u32, target);
+                    }
+                }
+            }
+            EdgeStore::Disk(edges) => {
+                for entry in edges.outgoing.iter() {
+                    let (key, _) = entry?;
+                    let (source, target) = split_key(&key);
+                    visit(source, target);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn neighbors(adjacency: &[Vec<u32>], vertex: u32) -> Vec<u32> {
+    adjacency.get(vertex as usize).cloned().unwrap_or_default()
+}
+
+fn swap_remove_item(items: &mut Vec<u32>, item: u32) -> bool {
+    match items.iter().position(|&x| x == item) {
+        Some(pos) => {
+            items.swap_remove(pos);
+            true
+        }
+        None => false,
+    }
+}
+
+fn edge_key(vertex: u32, neighbor: u32) -> [u8; 8] {
+    let mut key = [0u8; 8];
+    key[..4].copy_from_slice(&vertex.to_be_bytes());
+    key[4..].copy_from_slice(&neighbor.to_be_bytes());
+    key
+}
+
+fn split_key(key: &[u8]) -> (u32, u32) {
+    let vertex = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+    let neighbor = u32::from_be_bytes([key[4], key[5], key[6], key[7]]);
+    (vertex, neighbor)
+}
+
+fn scan_neighbors(tree: &sled::Tree, vertex: u32) -> Result<Vec<u32>> {
+    let mut result = Vec::new();
+    for entry in tree.scan_prefix(vertex.to_be_bytes()) {
+        let (key, _) = entry?;
+        result.push(split_key(&key).1);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(store: &mut EdgeStore) {
+        assert!(store.insert(0, 1).unwrap());
+        assert!(store.insert(0, 2).unwrap());
+        assert!(store.insert(2, 1).unwrap());
+        assert!(!store.insert(0, 1).unwrap());
+
+        let mut successors = store.outgoing(0).unwrap();
+        successors.sort();
+        assert_eq!(successors, vec![1, 2]);
+        let mut predecessors = store.incoming(1).unwrap();
+        predecessors.sort();
+        assert_eq!(predecessors, vec![0, 2]);
+
+        assert!(store.remove(0, 1).unwrap());
+        assert!(!store.remove(0, 1).unwrap());
+        assert_eq!(store.incoming(1).unwrap(), vec![2]);
+
+        let mut count = 0;
+        store.for_each_edge(|_, _| count += 1).unwrap();
+        assert_eq!(count, 2);
+        assert!(store.outgoing(7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&mut EdgeStore::memory());
+    }
+
+    #[test]
+    fn test_disk_store() {
+        let dir = std::env::temp_dir().join(format!("edge-store-{}", uuid::Uuid::new_v4()));
+        let mut store = EdgeStore::disk(&dir).unwrap();
+        assert!(store.is_disk());
+        exercise(&mut store);
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
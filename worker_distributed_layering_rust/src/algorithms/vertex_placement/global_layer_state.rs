@@ -9,46 +9,340 @@
 /// 2. Maintain global state of vertex layers across all batches
 /// 3. Iteratively update layers as new edges are discovered
 /// 4. Use topological ordering principles: layer[target] = max(layer[source] + 1)
-
-use std::collections::{HashMap, HashSet, VecDeque};
+///
+/// Memory layout: vertex IDs are interned once into dense `u32` indices; layers,
+/// degrees and flags are plain vectors indexed by them, and the adjacency lives in
+/// an `EdgeStore` (compact vectors in RAM, or a temporary sled database on disk
+/// for `MemoryStrategy::Streaming`).
+///
+/// Cycles: on a cyclic graph the propagation never converges, since every round
+/// trip around a cycle raises its layers again. Propagation stops with an error
+/// once a layer exceeds any value reachable in a DAG; `detect_cycles` then reports
+/// the strongly connected components (Tarjan on the accumulated adjacency) and
+/// `break_cycles` removes or reverses a feedback arc set. Between batches
+/// `propagate_with_cycle_breaking` searches only the vertices the diverged
+/// propagation raised: every cycle closed by new edges lies among them, so a
+/// batch costs as much as the region it touched, not the whole graph.
+///
+/// Layer constraints: vertices pinned with `set_layer_constraints` keep their
+/// layer through propagation and seeding; their successors are propagated from
+/// the pinned layer. `constraint_violations` lists pins that ended up at or
+/// below one of their predecessors.
+///
+/// Layer lower bounds: layers known from a previous run or from manual curation
+/// can be set with `set_layer_lower_bounds`. Unlike pins they are only a floor:
+/// a vertex gets at least its known layer and moves further right when its
+/// predecessors require it, so an existing layering is extended rather than
+/// recomputed from zero.
+///
+/// Diagnostics: every propagation records frontier sizes per round, the longest
+/// chain of updates and vertices updated suspiciously often. A vertex updated
+/// `cycle_check_after` times is checked for lying on a cycle; if it does,
+/// propagation stops early instead of raising layers up to the divergence bound.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
 use anyhow::Result;
-use tracing::{info, debug};
+use tokio_stream::{Stream, StreamExt};
+use tracing::{info, debug, warn};
+use crate::algorithms::cycle_breaking::{
+    greedy_feedback_order, strongly_connected_components, CycleBreakStrategy,
+};
+use crate::data_structures::{Graph, GraphBuilder};
+use super::edge_store::EdgeStore;
+use super::layer_assignment::LayerConstraintViolation;
+use super::placement::{TieBreak, TieBreakKeys};
 
 /// Global state for layer assignment across multiple batches
 #[derive(Debug)]
 pub struct GlobalLayerState {
-    /// Maps vertex_id -> current assigned layer
-    vertex_layers: HashMap<String, i32>,
+    /// Interned vertex IDs; the index is the vertex number, in order of first appearance
+    vertex_ids: Vec<Arc<str>>,
+
+    /// Maps vertex_id -> vertex number
+    vertex_index: HashMap<Arc<str>, u32>,
 
-    /// Maps vertex_id -> set of outgoing edges (target vertices)
-    outgoing_edges: HashMap<String, HashSet<String>>,
+    /// Current assigned layer per vertex number
+    layers: Vec<i32>,
 
-    /// Maps vertex_id -> set of incoming edges (source vertices)
-    incoming_edges: HashMap<String, HashSet<String>>,
+    /// Whether the vertex is part of the graph (dropped vertices keep their number)
+    present: Vec<bool>,
+
+    /// Outgoing / incoming edge counts per vertex number
+    out_degree: Vec<u32>,
+    in_degree: Vec<u32>,
+
+    /// Edges between vertex numbers
+    edges: EdgeStore,
 
     /// Vertices that need layer recalculation (dirty set)
-    dirty_vertices: HashSet<String>,
+    dirty_vertices: HashSet<u32>,
+
+    /// Vertices created or re-layered since the last `take_changed_vertices`
+    /// (only collected when change tracking is enabled)
+    changed_vertices: Option<HashSet<u32>>,
 
     /// Statistics
     max_layer: i32,
     total_vertices: usize,
     total_edges: usize,
     update_iterations: usize,
+
+    /// Set when the last propagation diverged on a cycle
+    diverged: bool,
+
+    /// Vertices re-layered since the start of the last `propagate_until_convergence`
+    raised_vertices: HashSet<u32>,
+
+    /// Result of the last `compress_layers`
+    compression: Option<LayerCompression>,
+
+    /// Pinned layers by vertex ID (may name vertices that are not loaded yet)
+    layer_constraints: HashMap<String, i32>,
+    max_pinned_layer: i32,
+
+    /// Minimal layers by vertex ID (may name vertices that are not loaded yet)
+    layer_lower_bounds: HashMap<String, i32>,
+    max_lower_bound: i32,
+
+    /// Early-stop settings and diagnostics of the last propagation
+    propagation_limits: PropagationLimits,
+    diagnostics: PropagationDiagnostics,
+}
+
+/// Early-stop settings of layer propagation
+#[derive(Debug, Clone)]
+pub struct PropagationLimits {
+    /// Vertices updated more than this many times are reported as hot
+    pub hot_threshold: usize,
+
+    /// Updates of one vertex after which it is checked for lying on a cycle;
+    /// the threshold doubles after every negative check (0 disables the check)
+    pub cycle_check_after: usize,
+}
+
+impl Default for PropagationLimits {
+    fn default() -> Self {
+        Self {
+            hot_threshold: 8,
+            cycle_check_after: 32,
+        }
+    }
+}
+
+/// What happened during layer propagation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropagationDiagnostics {
+    /// Number of vertices processed in each propagation round
+    pub frontier_sizes: Vec<usize>,
+
+    /// Longest chain of updates, each caused by the previous one
+    pub longest_update_chain: usize,
+
+    /// Vertices updated more than `hot_threshold` times, most updated first
+    pub hot_vertices: Vec<(String, usize)>,
+
+    /// Vertex found on a cycle, at which propagation stopped early
+    pub stopped_at: Option<String>,
+
+    /// Vertices left dirty after an early stop or divergence
+    pub pending_vertices: usize,
+}
+
+/// Result of a layer compression pass
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayerCompression {
+    /// Layer span before compression (max - min + 1, including empty layers)
+    pub layers_before: usize,
+
+    /// Number of layers after compression
+    pub layers_after: usize,
+
+    /// Non-empty layers that were merged into a neighbour
+    pub merged_layers: usize,
+
+    /// Old layer -> new layer
+    pub mapping: BTreeMap<i32, i32>,
+}
+
+/// Cycles in the accumulated graph
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CycleReport {
+    /// Members of every strongly connected component with a cycle
+    /// (largest first, members sorted by ID)
+    pub components: Vec<Vec<String>>,
+
+    /// One shortest cycle per component, starting at its smallest member
+    /// (at most `max_cycles` of them)
+    pub cycles: Vec<Vec<String>>,
 }
 
 impl GlobalLayerState {
-    /// Create a new empty global layer state
+    /// Create a new empty global layer state with in-memory adjacency
     pub fn new() -> Self {
         info!("🌍 Initializing Global Layer State for iterative batch processing");
+        Self::with_edge_store(EdgeStore::memory())
+    }
+
+    /// Create a new empty global layer state that spills adjacency to `dir`
+    pub fn with_disk_spill(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        info!("🌍 Initializing Global Layer State with disk-backed edges in {}", dir.display());
+        Ok(Self::with_edge_store(EdgeStore::disk(dir)?))
+    }
+
+    fn with_edge_store(edges: EdgeStore) -> Self {
         Self {
-            vertex_layers: HashMap::new(),
-            outgoing_edges: HashMap::new(),
-            incoming_edges: HashMap::new(),
+            vertex_ids: Vec::new(),
+            vertex_index: HashMap::new(),
+            layers: Vec::new(),
+            present: Vec::new(),
+            out_degree: Vec::new(),
+            in_degree: Vec::new(),
+            edges,
             dirty_vertices: HashSet::new(),
+            changed_vertices: None,
             max_layer: 0,
             total_vertices: 0,
             total_edges: 0,
             update_iterations: 0,
+            diverged: false,
+            raised_vertices: HashSet::new(),
+            compression: None,
+            layer_constraints: HashMap::new(),
+            max_pinned_layer: 0,
+            layer_lower_bounds: HashMap::new(),
+            max_lower_bound: 0,
+            propagation_limits: PropagationLimits::default(),
+            diagnostics: PropagationDiagnostics::default(),
+        }
+    }
+
+    /// Set early-stop settings of layer propagation
+    pub fn set_propagation_limits(&mut self, limits: PropagationLimits) {
+        self.propagation_limits = limits;
+    }
+
+    /// Diagnostics of the last `propagate_until_convergence`
+    /// (or of all `propagate_layers` calls since then)
+    pub fn propagation_diagnostics(&self) -> &PropagationDiagnostics {
+        &self.diagnostics
+    }
+
+    /// Pin vertices to fixed layers
+    ///
+    /// Replaces previous constraints. Vertices already in the graph whose pin
+    /// changed are marked dirty; the next propagation applies the new layers.
+    pub fn set_layer_constraints(&mut self, constraints: HashMap<String, i32>) -> Result<()> {
+        if let Some((vertex_id, layer)) = constraints.iter().find(|(_, &layer)| layer < 0) {
+            return Err(anyhow::anyhow!("Vertex {} is pinned to negative layer {}", vertex_id, layer));
+        }
+
+        let previous = std::mem::replace(&mut self.layer_constraints, constraints);
+        for vertex_id in previous.keys().chain(self.layer_constraints.keys()) {
+            if let Some(v) = self.index_of(vertex_id) {
+                self.dirty_vertices.insert(v);
+            }
+        }
+        self.max_pinned_layer = self.layer_constraints.values().copied().max().unwrap_or(0);
+        info!("📌 {} layer constraints set", self.layer_constraints.len());
+        Ok(())
+    }
+
+    /// Set minimal layers, e.g. layers of a previous run
+    ///
+    /// Replaces previous bounds. Propagation assigns
+    /// `max(bound, max(predecessor layers) + 1)`, so bounded vertices can still
+    /// move to higher layers. Pins take precedence over bounds.
+    pub fn set_layer_lower_bounds(&mut self, bounds: HashMap<String, i32>) -> Result<()> {
+        if let Some((vertex_id, layer)) = bounds.iter().find(|(_, &layer)| layer < 0) {
+            return Err(anyhow::anyhow!("Vertex {} has negative layer lower bound {}", vertex_id, layer));
+        }
+
+        let previous = std::mem::replace(&mut self.layer_lower_bounds, bounds);
+        for vertex_id in previous.keys().chain(self.layer_lower_bounds.keys()) {
+            if let Some(v) = self.index_of(vertex_id) {
+                self.dirty_vertices.insert(v);
+            }
+        }
+        self.max_lower_bound = self.layer_lower_bounds.values().copied().max().unwrap_or(0);
+        info!("🧱 {} layer lower bounds set", self.layer_lower_bounds.len());
+        Ok(())
+    }
+
+    /// Minimal layer of a vertex number
+    fn lower_bound(&self, v: u32) -> Option<i32> {
+        if self.layer_lower_bounds.is_empty() {
+            return None;
+        }
+        self.layer_lower_bounds.get(&*self.vertex_ids[v as usize]).copied()
+    }
+
+    /// Pinned layer of a vertex number
+    fn pinned_layer(&self, v: u32) -> Option<i32> {
+        if self.layer_constraints.is_empty() {
+            return None;
+        }
+        self.layer_constraints.get(&*self.vertex_ids[v as usize]).copied()
+    }
+
+    /// Whether the adjacency is spilled to disk
+    pub fn is_disk_backed(&self) -> bool {
+        self.edges.is_disk()
+    }
+
+    /// Number of vertices in the graph
+    pub fn vertex_count(&self) -> usize {
+        self.total_vertices
+    }
+
+    /// Whether the vertex is part of the graph
+    pub fn contains_vertex(&self, vertex_id: &str) -> bool {
+        self.index_of(vertex_id).is_some()
+    }
+
+    /// Current layer of a vertex
+    pub fn layer_of(&self, vertex_id: &str) -> Option<i32> {
+        self.index_of(vertex_id).map(|v| self.layers[v as usize])
+    }
+
+    /// Number of a vertex that is part of the graph
+    fn index_of(&self, vertex_id: &str) -> Option<u32> {
+        self.vertex_index
+            .get(vertex_id)
+            .copied()
+            .filter(|&v| self.present[v as usize])
+    }
+
+    /// Number of a vertex, interning it and adding it to the graph if needed
+    ///
+    /// Returns the number and whether the vertex is new to the graph.
+    fn intern(&mut self, vertex_id: &str) -> Result<(u32, bool)> {
+        if let Some(&v) = self.vertex_index.get(vertex_id) {
+            let is_new = !self.present[v as usize];
+            if is_new {
+                self.present[v as usize] = true;
+                self.layers[v as usize] = 0;
+            }
+            return Ok((v, is_new));
+        }
+
+        let v = u32::try_from(self.vertex_ids.len())
+            .map_err(|_| anyhow::anyhow!("Too many vertices for u32 vertex numbers"))?;
+        let id: Arc<str> = Arc::from(vertex_id);
+        self.vertex_ids.push(id.clone());
+        self.vertex_index.insert(id, v);
+        self.layers.push(0);
+        self.present.push(true);
+        self.out_degree.push(0);
+        self.in_degree.push(0);
+        Ok((v, true))
+    }
+
+    fn mark_changed(&mut self, v: u32) {
+        if let Some(changed) = self.changed_vertices.as_mut() {
+            changed.insert(v);
         }
     }
 
@@ -61,49 +355,86 @@ impl GlobalLayerState {
     pub fn add_edges_batch(&mut self, edges: &[(String, String)]) -> Result<()> {
         debug!("📥 Adding batch of {} edges to global state", edges.len());
 
-        let mut new_vertices = 0;
-        let mut new_edges = 0;
+        let mut counts = (0, 0);
+        for (source, target) in edges {
+            self.add_edge(source, target, &mut counts)?;
+        }
+
+        debug!("✅ Batch added: {} new vertices, {} new edges, {} dirty vertices",
+               counts.0, counts.1, self.dirty_vertices.len());
+
+        Ok(())
+    }
 
+    /// Add edges from an iterator without collecting them first
+    ///
+    /// Same semantics as `add_edges_batch`. Returns the number of edges consumed
+    /// from the iterator (including skipped invalid ones).
+    pub fn add_edges_from(&mut self, edges: impl IntoIterator<Item = (String, String)>) -> Result<usize> {
+        let mut counts = (0, 0);
+        let mut consumed = 0;
         for (source, target) in edges {
-            // Skip invalid edges
-            if source.trim().is_empty() || target.trim().is_empty() || source == target {
-                continue;
-            }
+            self.add_edge(&source, &target, &mut counts)?;
+            consumed += 1;
+        }
 
-            // Track if vertices are new
-            let source_is_new = !self.vertex_layers.contains_key(source);
-            let target_is_new = !self.vertex_layers.contains_key(target);
+        debug!("✅ Edges added: {} consumed, {} new vertices, {} new edges, {} dirty vertices",
+               consumed, counts.0, counts.1, self.dirty_vertices.len());
 
-            if source_is_new {
-                self.vertex_layers.insert(source.clone(), 0);
-                new_vertices += 1;
-            }
+        Ok(consumed)
+    }
 
-            if target_is_new {
-                self.vertex_layers.insert(target.clone(), 0);
-                new_vertices += 1;
-            }
+    /// Add edges from an async stream as they arrive
+    ///
+    /// Streaming front-end of `add_edges_from`: each edge is interned as soon as
+    /// the stream yields it, so a producer (e.g. a Neo4j result cursor) never has
+    /// to buffer a batch. Returns the number of edges consumed.
+    pub async fn add_edges_from_stream<S>(&mut self, edges: S) -> Result<usize>
+    where
+        S: Stream<Item = (String, String)>,
+    {
+        tokio::pin!(edges);
+
+        let mut counts = (0, 0);
+        let mut consumed = 0;
+        while let Some((source, target)) = edges.next().await {
+            self.add_edge(&source, &target, &mut counts)?;
+            consumed += 1;
+        }
 
-            // Add outgoing edge from source
-            let outgoing = self.outgoing_edges.entry(source.clone()).or_insert_with(HashSet::new);
-            if outgoing.insert(target.clone()) {
-                new_edges += 1;
-            }
+        debug!("✅ Stream drained: {} consumed, {} new vertices, {} new edges, {} dirty vertices",
+               consumed, counts.0, counts.1, self.dirty_vertices.len());
 
-            // Add incoming edge to target
-            let incoming = self.incoming_edges.entry(target.clone()).or_insert_with(HashSet::new);
-            incoming.insert(source.clone());
+        Ok(consumed)
+    }
 
-            // Mark target as dirty (its layer may need updating)
-            self.dirty_vertices.insert(target.clone());
+    /// Add a single edge; `counts` accumulates (new vertices, new edges)
+    fn add_edge(&mut self, source: &str, target: &str, counts: &mut (usize, usize)) -> Result<()> {
+        // Skip invalid edges
+        if source.trim().is_empty() || target.trim().is_empty() || source == target {
+            return Ok(());
         }
 
-        self.total_vertices = self.vertex_layers.len();
-        self.total_edges += new_edges;
+        let (s, source_is_new) = self.intern(source)?;
+        let (t, target_is_new) = self.intern(target)?;
+        for (v, is_new) in [(s, source_is_new), (t, target_is_new)] {
+            if is_new {
+                self.total_vertices += 1;
+                self.mark_changed(v);
+                counts.0 += 1;
+                // A pinned or bounded source would otherwise never leave layer 0
+                if self.pinned_layer(v).is_some() || self.lower_bound(v).is_some() {
+                    self.dirty_vertices.insert(v);
+                }
+            }
+        }
 
-        debug!("✅ Batch added: {} new vertices, {} new edges, {} dirty vertices",
-               new_vertices, new_edges, self.dirty_vertices.len());
+        if self.insert_edge(s, t)? {
+            counts.1 += 1;
+        }
 
+        // Mark target as dirty (its layer may need updating)
+        self.dirty_vertices.insert(t);
         Ok(())
     }
 
@@ -113,6 +444,9 @@ impl GlobalLayerState {
     /// - For each vertex, layer = max(all predecessor layers) + 1
     /// - Continue until no more updates are needed (convergence)
     ///
+    /// Fails if the graph contains a cycle (see `break_cycles`); the pending
+    /// vertices stay dirty, so propagation can resume after the cycles are broken.
+    ///
     /// Returns: number of vertices whose layers were updated
     pub fn propagate_layers(&mut self) -> Result<usize> {
         self.update_iterations += 1;
@@ -126,57 +460,140 @@ impl GlobalLayerState {
         }
 
         let mut updated_count = 0;
-        let mut queue = VecDeque::new();
-
-        // Initialize queue with dirty vertices
-        for vertex in self.dirty_vertices.drain() {
-            queue.push_back(vertex);
-        }
+        let mut update_counts: HashMap<u32, usize> = HashMap::new();
+        let mut check_at = self.propagation_limits.cycle_check_after;
+
+        // In a DAG no layer can exceed the current maximum (or the highest pin
+        // or lower bound) by more than the vertex count
+        let layer_bound = self
+            .max_layer
+            .max(self.max_pinned_layer)
+            .max(self.max_lower_bound)
+            .saturating_add(self.total_vertices as i32);
+
+        // Rounds of dirty vertices; each entry carries the length of the update
+        // chain that queued it
+        let mut frontier: Vec<(u32, usize)> = self.dirty_vertices.drain().map(|v| (v, 0)).collect();
+
+        while !frontier.is_empty() {
+            self.diagnostics.frontier_sizes.push(frontier.len());
+            let mut next = Vec::new();
+
+            for (i, &(v, chain)) in frontier.iter().enumerate() {
+                if !self.present[v as usize] {
+                    continue;
+                }
 
-        // Process queue until empty
-        while let Some(vertex) = queue.pop_front() {
-            // Calculate new layer based on predecessors
-            let new_layer = if let Some(incoming) = self.incoming_edges.get(&vertex) {
-                if incoming.is_empty() {
-                    // No incoming edges = source vertex = layer 0
-                    0
+                // Layer = max(predecessor layers) + 1; no incoming edges = source vertex = layer 0
+                let new_layer = if let Some(pinned) = self.pinned_layer(v) {
+                    pinned
                 } else {
-                    // Layer = max(predecessor layers) + 1
-                    incoming.iter()
-                        .filter_map(|pred| self.vertex_layers.get(pred))
-                        .max()
-                        .map(|&max_pred_layer| max_pred_layer + 1)
-                        .unwrap_or(0)
+                    let from_predecessors = if self.in_degree[v as usize] == 0 {
+                        0
+                    } else {
+                        self.edges
+                            .incoming(v)?
+                            .into_iter()
+                            .map(|pred| self.layers[pred as usize])
+                            .max()
+                            .map_or(0, |max_pred_layer| max_pred_layer + 1)
+                    };
+                    from_predecessors.max(self.lower_bound(v).unwrap_or(0))
+                };
+
+                if new_layer > layer_bound {
+                    self.stop_propagation(&frontier[i..], &next, &update_counts);
+                    return Err(anyhow::anyhow!(
+                        "Layer propagation diverged at vertex {}: the graph contains a cycle",
+                        self.vertex_ids[v as usize]
+                    ));
                 }
-            } else {
-                // No incoming edges = source vertex = layer 0
-                0
-            };
-
-            // Get current layer
-            let current_layer = *self.vertex_layers.get(&vertex).unwrap_or(&0);
 
-            // Update if layer changed
-            if new_layer != current_layer {
-                self.vertex_layers.insert(vertex.clone(), new_layer);
-                self.max_layer = self.max_layer.max(new_layer);
-                updated_count += 1;
-
-                // Mark all successors as dirty
-                if let Some(outgoing) = self.outgoing_edges.get(&vertex) {
-                    for successor in outgoing {
-                        queue.push_back(successor.clone());
+                // Update if layer changed
+                if new_layer != self.layers[v as usize] {
+                    self.layers[v as usize] = new_layer;
+                    self.max_layer = self.max_layer.max(new_layer);
+                    updated_count += 1;
+                    self.mark_changed(v);
+                    self.raised_vertices.insert(v);
+                    self.diagnostics.longest_update_chain =
+                        self.diagnostics.longest_update_chain.max(chain + 1);
+
+                    // A vertex updated over and over is checked for lying on a cycle
+                    let count = update_counts.entry(v).or_insert(0);
+                    *count += 1;
+                    let count = *count;
+                    if check_at > 0 && count >= check_at {
+                        if self.lies_on_cycle(v)? {
+                            self.stop_propagation(&frontier[i..], &next, &update_counts);
+                            self.diagnostics.stopped_at = Some(self.vertex_ids[v as usize].to_string());
+                            return Err(anyhow::anyhow!(
+                                "Layer propagation stopped early at vertex {} (updated {} times): the graph contains a cycle",
+                                self.vertex_ids[v as usize],
+                                count
+                            ));
+                        }
+                        check_at = check_at.saturating_mul(2);
                     }
+
+                    // Mark all successors as dirty
+                    next.extend(self.edges.outgoing(v)?.into_iter().map(|w| (w, chain + 1)));
                 }
             }
+
+            frontier = next;
         }
 
+        self.record_hot_vertices(&update_counts);
+
         debug!("✅ Layer propagation complete: {} vertices updated, max layer = {}",
                updated_count, self.max_layer);
 
         Ok(updated_count)
     }
 
+    /// Keep the unprocessed vertices dirty after a divergence or an early stop
+    fn stop_propagation(
+        &mut self,
+        remaining: &[(u32, usize)],
+        next: &[(u32, usize)],
+        update_counts: &HashMap<u32, usize>,
+    ) {
+        self.diverged = true;
+        self.dirty_vertices.extend(remaining.iter().chain(next).map(|&(v, _)| v));
+        self.diagnostics.pending_vertices = self.dirty_vertices.len();
+        self.record_hot_vertices(update_counts);
+    }
+
+    /// Merge vertices updated more than `hot_threshold` times into the diagnostics
+    fn record_hot_vertices(&mut self, update_counts: &HashMap<u32, usize>) {
+        let threshold = self.propagation_limits.hot_threshold;
+        let mut hot: HashMap<String, usize> = self.diagnostics.hot_vertices.drain(..).collect();
+        for (&v, &count) in update_counts {
+            if count > threshold {
+                *hot.entry(self.vertex_ids[v as usize].to_string()).or_insert(0) += count;
+            }
+        }
+        let mut hot: Vec<(String, usize)> = hot.into_iter().collect();
+        hot.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.diagnostics.hot_vertices = hot;
+    }
+
+    /// Whether `v` can reach itself along outgoing edges
+    fn lies_on_cycle(&self, v: u32) -> Result<bool> {
+        let mut visited = HashSet::new();
+        let mut stack = self.edges.outgoing(v)?;
+        while let Some(w) = stack.pop() {
+            if w == v {
+                return Ok(true);
+            }
+            if visited.insert(w) {
+                stack.extend(self.edges.outgoing(w)?);
+            }
+        }
+        Ok(false)
+    }
+
     /// Run layer propagation until convergence
     ///
     /// Continues propagating until no more updates occur
@@ -184,6 +601,8 @@ impl GlobalLayerState {
     /// Returns: total number of updates across all iterations
     pub fn propagate_until_convergence(&mut self) -> Result<usize> {
         info!("🔄 Starting layer propagation until convergence...");
+        self.diagnostics = PropagationDiagnostics::default();
+        self.raised_vertices.clear();
 
         let mut total_updates = 0;
         let mut iteration = 0;
@@ -196,6 +615,10 @@ impl GlobalLayerState {
 
             if updates == 0 {
                 info!("✅ Convergence reached after {} iterations, {} total updates", iteration, total_updates);
+                info!("📊 {} propagation rounds, longest update chain {}, {} hot vertices",
+                      self.diagnostics.frontier_sizes.len(),
+                      self.diagnostics.longest_update_chain,
+                      self.diagnostics.hot_vertices.len());
                 break;
             }
 
@@ -212,16 +635,503 @@ impl GlobalLayerState {
         Ok(total_updates)
     }
 
+    /// Run layer propagation, breaking cycles if it diverges
+    ///
+    /// A cycle that makes propagation diverge keeps raising its members, so the
+    /// cycles are searched among the raised vertices only. After
+    /// `MAX_LOCAL_CYCLE_ROUNDS` rounds, or when the raised vertices close no
+    /// cycle, the whole graph is searched instead.
+    ///
+    /// Returns: total number of updates and the edges that were broken
+    pub fn propagate_with_cycle_breaking(
+        &mut self,
+        strategy: CycleBreakStrategy,
+    ) -> Result<(usize, Vec<(String, String)>)> {
+        const MAX_LOCAL_CYCLE_ROUNDS: usize = 8;
+
+        self.diverged = false;
+        let mut broken = Vec::new();
+        let mut rounds = 0;
+        loop {
+            let error = match self.propagate_until_convergence() {
+                Ok(updates) => {
+                    if !broken.is_empty() {
+                        // Layers raised while diverging are gone now
+                        self.max_layer = self.present_layers().max().unwrap_or(0);
+                    }
+                    return Ok((updates, broken));
+                }
+                Err(e) if self.diverged => e,
+                Err(e) => return Err(e),
+            };
+            self.diverged = false;
+            rounds += 1;
+
+            warn!("⚠️ {}, breaking cycles among {} raised vertices", error, self.raised_vertices.len());
+            let mut round = if rounds <= MAX_LOCAL_CYCLE_ROUNDS {
+                self.break_raised_cycles(strategy)?
+            } else {
+                Vec::new()
+            };
+            if round.is_empty() {
+                round = self.break_cycles(strategy)?;
+            }
+            if round.is_empty() {
+                return Err(error);
+            }
+            broken.append(&mut round);
+        }
+    }
+
+    /// Adjacency of all vertex numbers (materialized in RAM even for disk-backed edges)
+    fn adjacency(&self) -> Result<Vec<Vec<usize>>> {
+        let mut adjacency = vec![Vec::new(); self.vertex_ids.len()];
+        self.edges.for_each_edge(|s, t| adjacency[s as usize].push(t as usize))?;
+        Ok(adjacency)
+    }
+
+    /// Strongly connected components with at least two vertices
+    fn cyclic_components(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        strongly_connected_components(adjacency)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .collect()
+    }
+
+    /// Find the cycles of the accumulated graph
+    pub fn detect_cycles(&self, max_cycles: usize) -> Result<CycleReport> {
+        let adjacency = self.adjacency()?;
+        let mut components = Self::cyclic_components(&adjacency);
+        for component in components.iter_mut() {
+            component.sort_by(|&a, &b| self.vertex_ids[a].cmp(&self.vertex_ids[b]));
+        }
+        components.sort_by(|a, b| {
+            b.len()
+                .cmp(&a.len())
+                .then_with(|| self.vertex_ids[a[0]].cmp(&self.vertex_ids[b[0]]))
+        });
+
+        let name = |v: usize| self.vertex_ids[v].to_string();
+        let cycles = components
+            .iter()
+            .take(max_cycles)
+            .filter_map(|component| shortest_cycle(&adjacency, component))
+            .map(|cycle| cycle.into_iter().map(name).collect())
+            .collect();
+
+        Ok(CycleReport {
+            components: components
+                .into_iter()
+                .map(|component| component.into_iter().map(name).collect())
+                .collect(),
+            cycles,
+        })
+    }
+
+    /// Remove a feedback arc set so that the graph becomes acyclic
+    ///
+    /// Inside every cyclic component the Eades–Lin–Smyth order is computed
+    /// (as in `cycle_breaking::break_cycles`) and the edges pointing backwards
+    /// in that order are removed or reversed. Members of the components are
+    /// marked dirty, so the next propagation recomputes their layers.
+    ///
+    /// Returns: the broken edges in their original direction
+    pub fn break_cycles(&mut self, strategy: CycleBreakStrategy) -> Result<Vec<(String, String)>> {
+        let adjacency = self.adjacency()?;
+        let vertices: Vec<u32> = (0..self.vertex_ids.len() as u32).collect();
+        self.break_cycles_among(&vertices, &adjacency, strategy)
+    }
+
+    /// Break the cycles among the vertices raised by the last propagation
+    ///
+    /// Only the edges between raised vertices are read, so the cost follows the
+    /// region the last batch touched rather than the accumulated graph.
+    fn break_raised_cycles(&mut self, strategy: CycleBreakStrategy) -> Result<Vec<(String, String)>> {
+        let mut vertices: Vec<u32> = self.raised_vertices.iter().copied().collect();
+        vertices.sort_unstable();
+        let local: HashMap<u32, usize> = vertices.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+        let adjacency = vertices
+            .iter()
+            .map(|&v| {
+                Ok(self
+                    .edges
+                    .outgoing(v)?
+                    .into_iter()
+                    .filter_map(|w| local.get(&w).copied())
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<usize>>>>()?;
+        self.break_cycles_among(&vertices, &adjacency, strategy)
+    }
+
+    /// Break the cycles of the subgraph `adjacency` over `vertices`
+    /// (adjacency lists hold positions in `vertices`)
+    fn break_cycles_among(
+        &mut self,
+        vertices: &[u32],
+        adjacency: &[Vec<usize>],
+        strategy: CycleBreakStrategy,
+    ) -> Result<Vec<(String, String)>> {
+        let components = Self::cyclic_components(adjacency);
+        if components.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut broken = Vec::new();
+        for component in &components {
+            let local: HashMap<usize, usize> =
+                component.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+            let mut component_edges = Vec::new();
+            for (i, &u) in component.iter().enumerate() {
+                for w in &adjacency[u] {
+                    if let Some(&j) = local.get(w) {
+                        component_edges.push((i, j));
+                    }
+                }
+            }
+
+            let order = greedy_feedback_order(component.len(), &component_edges);
+            let mut position = vec![0usize; component.len()];
+            for (pos, &i) in order.iter().enumerate() {
+                position[i] = pos;
+            }
+
+            for &(i, j) in &component_edges {
+                if position[i] <= position[j] {
+                    continue;
+                }
+                let (s, t) = (vertices[component[i]], vertices[component[j]]);
+                self.remove_edge(s, t)?;
+                if strategy == CycleBreakStrategy::Reverse {
+                    self.insert_edge(t, s)?;
+                }
+                broken.push((self.vertex_ids[s as usize].to_string(), self.vertex_ids[t as usize].to_string()));
+            }
+
+            self.dirty_vertices.extend(component.iter().map(|&v| vertices[v]));
+        }
+
+        warn!("✂️ Broke {} edges in {} cyclic components ({:?})",
+              broken.len(), components.len(), strategy);
+
+        Ok(broken)
+    }
+
+    fn insert_edge(&mut self, s: u32, t: u32) -> Result<bool> {
+        let inserted = self.edges.insert(s, t)?;
+        if inserted {
+            self.out_degree[s as usize] += 1;
+            self.in_degree[t as usize] += 1;
+            self.total_edges += 1;
+        }
+        Ok(inserted)
+    }
+
+    fn remove_edge(&mut self, s: u32, t: u32) -> Result<bool> {
+        let removed = self.edges.remove(s, t)?;
+        if removed {
+            self.out_degree[s as usize] -= 1;
+            self.in_degree[t as usize] -= 1;
+            self.total_edges = self.total_edges.saturating_sub(1);
+        }
+        Ok(removed)
+    }
+
+    /// Remap layers to consecutive integers, merging sparse layers where possible
+    ///
+    /// Empty layers are dropped. A layer with fewer than `min_layer_size` vertices
+    /// (or one next to such a layer) is merged into the following layer group
+    /// when no edge connects the two, so every edge still goes to a higher layer.
+    /// With a pure longest-path layering adjacent layers are always connected;
+    /// merges mostly apply to seeded or incrementally updated layers.
+    ///
+    /// Call after the last propagation: propagating again recomputes the layers.
+    /// Skipped while layer constraints or lower bounds are set, since it would
+    /// move pinned vertices or drop vertices below their bound.
+    pub fn compress_layers(&mut self, min_layer_size: usize) -> Result<LayerCompression> {
+        if !self.layer_constraints.is_empty() {
+            warn!("⚠️ Layer compression skipped: {} vertices are pinned", self.layer_constraints.len());
+            return Ok(LayerCompression::default());
+        }
+        if !self.layer_lower_bounds.is_empty() {
+            warn!("⚠️ Layer compression skipped: {} vertices have layer lower bounds",
+                  self.layer_lower_bounds.len());
+            return Ok(LayerCompression::default());
+        }
+
+        let mut members: BTreeMap<i32, Vec<u32>> = BTreeMap::new();
+        for v in self.present_vertices() {
+            members.entry(self.layers[v]).or_default().push(v as u32);
+        }
+        let (Some(&min_layer), Some(&max_layer)) = (members.keys().next(), members.keys().next_back()) else {
+            return Ok(LayerCompression::default());
+        };
+
+        // Groups are built from the last layer backwards; group 0 is the rightmost
+        let mut group_of: BTreeMap<i32, usize> = BTreeMap::new();
+        let mut group_sizes: Vec<usize> = Vec::new();
+        for (&layer, vertices) in members.iter().rev() {
+            let merge = match group_sizes.last() {
+                Some(&group_size) if vertices.len() < min_layer_size || group_size < min_layer_size => {
+                    !self.connected_to_group(vertices, group_sizes.len() - 1, &group_of)?
+                }
+                _ => false,
+            };
+            if merge {
+                *group_sizes.last_mut().unwrap() += vertices.len();
+            } else {
+                group_sizes.push(vertices.len());
+            }
+            group_of.insert(layer, group_sizes.len() - 1);
+        }
+
+        let last_group = group_sizes.len() - 1;
+        let mapping: BTreeMap<i32, i32> = group_of
+            .into_iter()
+            .map(|(layer, group)| (layer, (last_group - group) as i32))
+            .collect();
+        for (layer, vertices) in &members {
+            for &v in vertices {
+                self.layers[v as usize] = mapping[layer];
+            }
+        }
+        self.max_layer = last_group as i32;
+
+        let compression = LayerCompression {
+            layers_before: (max_layer - min_layer + 1) as usize,
+            layers_after: group_sizes.len(),
+            merged_layers: members.len() - group_sizes.len(),
+            mapping,
+        };
+        info!("🗜️ Compressed layers: {} -> {} ({} sparse layers merged)",
+              compression.layers_before, compression.layers_after, compression.merged_layers);
+        self.compression = Some(compression.clone());
+
+        Ok(compression)
+    }
+
+    /// Whether any vertex of `vertices` has an edge to a layer already in `group`
+    fn connected_to_group(
+        &self,
+        vertices: &[u32],
+        group: usize,
+        group_of: &BTreeMap<i32, usize>,
+    ) -> Result<bool> {
+        let in_group = |u: u32| group_of.get(&self.layers[u as usize]) == Some(&group);
+        for &v in vertices {
+            if self.edges.outgoing(v)?.into_iter().any(in_group)
+                || self.edges.incoming(v)?.into_iter().any(in_group)
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Remove a batch of edges from the global state
+    ///
+    /// Targets of removed edges are marked dirty (their layer may drop).
+    /// Vertices left without any edges are dropped from the state.
+    ///
+    /// Returns: vertices that were dropped
+    pub fn remove_edges_batch(&mut self, edges: &[(String, String)]) -> Result<Vec<String>> {
+        debug!("📤 Removing batch of {} edges from global state", edges.len());
+
+        let mut touched = Vec::new();
+        for (source, target) in edges {
+            let (Some(s), Some(t)) = (self.index_of(source), self.index_of(target)) else {
+                continue;
+            };
+            if !self.remove_edge(s, t)? {
+                continue;
+            }
+            self.dirty_vertices.insert(t);
+            touched.push(s);
+            touched.push(t);
+        }
+
+        let mut dropped = Vec::new();
+        for v in touched {
+            let idx = v as usize;
+            if self.present[idx] && self.out_degree[idx] == 0 && self.in_degree[idx] == 0 {
+                self.present[idx] = false;
+                self.total_vertices -= 1;
+                self.dirty_vertices.remove(&v);
+                if let Some(changed) = self.changed_vertices.as_mut() {
+                    changed.remove(&v);
+                }
+                dropped.push(self.vertex_ids[idx].to_string());
+            }
+        }
+
+        Ok(dropped)
+    }
+
+    /// Seed layers from a previous layout instead of recomputing them
+    ///
+    /// Known vertices take the given layer; the dirty set is replaced by the
+    /// vertices missing from `layers`, so the next propagation only touches those.
+    /// Pinned vertices take their pinned layer instead.
+    pub fn seed_layers(&mut self, layers: &HashMap<String, i32>) {
+        self.dirty_vertices.clear();
+        for v in 0..self.vertex_ids.len() {
+            if !self.present[v] {
+                continue;
+            }
+            let seeded = self
+                .pinned_layer(v as u32)
+                .or_else(|| layers.get(&*self.vertex_ids[v]).copied());
+            match seeded {
+                Some(seeded) => self.layers[v] = seeded,
+                None => {
+                    self.dirty_vertices.insert(v as u32);
+                }
+            }
+        }
+        self.max_layer = self.present_layers().max().unwrap_or(0);
+        info!("🌱 Seeded {} layers, {} vertices left to compute",
+              self.total_vertices - self.dirty_vertices.len(), self.dirty_vertices.len());
+    }
+
+    /// Check whether the edge `source -> target` would close a cycle
+    ///
+    /// True if `source` is reachable from `target`. New vertices have no edges,
+    /// so edges from newly added articles are checked in O(1).
+    pub fn would_create_cycle(&self, source: &str, target: &str) -> Result<bool> {
+        if source == target {
+            return Ok(true);
+        }
+        let (Some(s), Some(t)) = (self.index_of(source), self.index_of(target)) else {
+            return Ok(false);
+        };
+        if self.in_degree[s as usize] == 0 {
+            return Ok(false);
+        }
+
+        let mut visited: HashSet<u32> = HashSet::new();
+        let mut stack = vec![t];
+        while let Some(v) = stack.pop() {
+            if v == s {
+                return Ok(true);
+            }
+            if !visited.insert(v) {
+                continue;
+            }
+            stack.extend(self.edges.outgoing(v)?);
+        }
+        Ok(false)
+    }
+
+    /// Pinned vertices whose predecessors are not all in lower layers
+    ///
+    /// Sorted by vertex and predecessor ID.
+    pub fn constraint_violations(&self) -> Result<Vec<LayerConstraintViolation>> {
+        let mut violations = Vec::new();
+        for (vertex_id, &pinned_layer) in &self.layer_constraints {
+            let Some(v) = self.index_of(vertex_id) else {
+                continue;
+            };
+            for pred in self.edges.incoming(v)? {
+                let predecessor_layer = self.layers[pred as usize];
+                if predecessor_layer >= pinned_layer {
+                    violations.push(LayerConstraintViolation {
+                        vertex: vertex_id.clone(),
+                        pinned_layer,
+                        predecessor: self.vertex_ids[pred as usize].to_string(),
+                        predecessor_layer,
+                    });
+                }
+            }
+        }
+        violations.sort_by(|a, b| a.vertex.cmp(&b.vertex).then_with(|| a.predecessor.cmp(&b.predecessor)));
+        Ok(violations)
+    }
+
+    /// Start collecting created and re-layered vertices (see `take_changed_vertices`)
+    pub fn enable_change_tracking(&mut self) {
+        self.changed_vertices.get_or_insert_with(HashSet::new);
+    }
+
+    /// Vertices created or re-layered since the previous call
+    pub fn take_changed_vertices(&mut self) -> HashSet<String> {
+        self.changed_vertices
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| self.vertex_ids[v as usize].to_string())
+            .collect()
+    }
+
     /// Get the final layer assignments
-    pub fn get_layer_map(&self) -> &HashMap<String, i32> {
-        &self.vertex_layers
+    ///
+    /// Builds a fresh map; use `layer_of` for single lookups.
+    pub fn get_layer_map(&self) -> HashMap<String, i32> {
+        self.present_vertices()
+            .map(|v| (self.vertex_ids[v].to_string(), self.layers[v]))
+            .collect()
+    }
+
+    /// Whether layer pins or lower bounds are set
+    pub fn has_layer_constraints(&self) -> bool {
+        !self.layer_constraints.is_empty() || !self.layer_lower_bounds.is_empty()
+    }
+
+    /// Build an in-memory graph of the accumulated edges
+    ///
+    /// Reads the whole edge store, so it is meant for one-off whole-graph
+    /// passes after loading (e.g. re-layering a degenerate result).
+    pub fn build_graph(&self) -> Result<Graph> {
+        let mut builder = GraphBuilder::new().deterministic(true);
+        let mut added = Ok(());
+        self.edges.for_each_edge(|s, t| {
+            if added.is_ok() {
+                added = builder.add_edge(
+                    self.vertex_ids[s as usize].to_string(),
+                    self.vertex_ids[t as usize].to_string(),
+                    1.0,
+                );
+            }
+        })?;
+        added?;
+        builder.build()
+    }
+
+    fn present_vertices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.vertex_ids.len()).filter(move |&v| self.present[v])
+    }
+
+    fn present_layers(&self) -> impl Iterator<Item = i32> + '_ {
+        self.present_vertices().map(move |v| self.layers[v])
+    }
+
+    /// Sort keys for ordering vertices within a layer
+    ///
+    /// Returns an empty map for `TieBreak::VertexId`, which needs no keys.
+    pub fn tie_break_keys(&self, tie_break: TieBreak) -> TieBreakKeys {
+        match tie_break {
+            TieBreak::VertexId => TieBreakKeys::new(),
+            // Vertex numbers follow the order of first appearance
+            TieBreak::InputOrder => self
+                .present_vertices()
+                .enumerate()
+                .map(|(order, v)| (self.vertex_ids[v].to_string(), order as i64))
+                .collect(),
+            TieBreak::Degree => self
+                .present_vertices()
+                .map(|v| {
+                    let degree = self.out_degree[v] as i64 + self.in_degree[v] as i64;
+                    (self.vertex_ids[v].to_string(), -degree)
+                })
+                .collect(),
+        }
     }
 
     /// Get statistics about the current state
     pub fn get_statistics(&self) -> LayerStatistics {
         let mut layer_distribution: HashMap<i32, usize> = HashMap::new();
 
-        for &layer in self.vertex_layers.values() {
+        for layer in self.present_layers() {
             *layer_distribution.entry(layer).or_insert(0) += 1;
         }
 
@@ -232,6 +1142,7 @@ impl GlobalLayerState {
             unique_layers: layer_distribution.len(),
             layer_distribution,
             update_iterations: self.update_iterations,
+            compression: self.compression.clone(),
         }
     }
 
@@ -245,6 +1156,10 @@ impl GlobalLayerState {
         info!("📏 Max layer: {}", stats.max_layer);
         info!("🔢 Unique layers: {}", stats.unique_layers);
         info!("🔄 Update iterations: {}", stats.update_iterations);
+        if let Some(ref compression) = stats.compression {
+            info!("🗜️ Layer compression: {} -> {} layers, {} merged",
+                  compression.layers_before, compression.layers_after, compression.merged_layers);
+        }
 
         if !stats.layer_distribution.is_empty() {
             info!("📈 Layer distribution (first 20 layers):");
@@ -268,37 +1183,64 @@ impl GlobalLayerState {
     /// Checks that all edges go from lower to higher layers
     ///
     /// Returns: number of invalid edges found
-    pub fn validate_layers(&self) -> usize {
+    pub fn validate_layers(&self) -> Result<usize> {
         let mut invalid_count = 0;
         let max_errors_to_log = 10;
 
-        for (source, targets) in &self.outgoing_edges {
-            let source_layer = self.vertex_layers.get(source).unwrap_or(&0);
-
-            for target in targets {
-                let target_layer = self.vertex_layers.get(target).unwrap_or(&0);
+        self.edges.for_each_edge(|s, t| {
+            let source_layer = self.layers[s as usize];
+            let target_layer = self.layers[t as usize];
 
-                if source_layer >= target_layer {
-                    if invalid_count < max_errors_to_log {
-                        debug!("⚠️ Invalid edge: {} (layer {}) -> {} (layer {})",
-                              source, source_layer, target, target_layer);
-                    }
-                    invalid_count += 1;
+            if source_layer >= target_layer {
+                if invalid_count < max_errors_to_log {
+                    debug!("⚠️ Invalid edge: {} (layer {}) -> {} (layer {})",
+                          self.vertex_ids[s as usize], source_layer,
+                          self.vertex_ids[t as usize], target_layer);
                 }
+                invalid_count += 1;
             }
-        }
+        })?;
 
         if invalid_count > 0 {
             if invalid_count > max_errors_to_log {
                 debug!("... and {} more invalid edges", invalid_count - max_errors_to_log);
             }
-            info!("⚠️ Found {} invalid edges (may indicate cycles)", invalid_count);
+            info!("⚠️ Found {} invalid edges (may indicate cycles, see detect_cycles)", invalid_count);
         } else {
             info!("✅ All edges respect layer ordering");
         }
 
-        invalid_count
+        Ok(invalid_count)
+    }
+}
+
+/// Shortest cycle through the first member of a strongly connected component
+fn shortest_cycle(adjacency: &[Vec<usize>], component: &[usize]) -> Option<Vec<usize>> {
+    let members: HashSet<usize> = component.iter().copied().collect();
+    let start = *component.first()?;
+
+    // BFS from the start vertex until an edge leads back to it
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(u) = queue.pop_front() {
+        for &w in &adjacency[u] {
+            if w == start {
+                let mut cycle = vec![u];
+                let mut v = u;
+                while v != start {
+                    v = parent[&v];
+                    cycle.push(v);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if members.contains(&w) && !parent.contains_key(&w) {
+                parent.insert(w, u);
+                queue.push_back(w);
+            }
+        }
     }
+    None
 }
 
 impl Default for GlobalLayerState {
@@ -316,6 +1258,9 @@ pub struct LayerStatistics {
     pub unique_layers: usize,
     pub layer_distribution: HashMap<i32, usize>,
     pub update_iterations: usize,
+
+    /// Layer remapping of the last `compress_layers`, if any
+    pub compression: Option<LayerCompression>,
 }
 
 #[cfg(test)]
@@ -395,6 +1340,337 @@ mod tests {
         assert_eq!(state.max_layer, 4);
     }
 
+    #[tokio::test]
+    async fn test_iterator_and_stream_match_batch() {
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("A".to_string(), "C".to_string()),
+            ("C".to_string(), "C".to_string()),
+        ];
+
+        let mut batch = GlobalLayerState::new();
+        batch.add_edges_batch(&edges).unwrap();
+        batch.propagate_until_convergence().unwrap();
+
+        let mut from_iter = GlobalLayerState::new();
+        assert_eq!(from_iter.add_edges_from(edges.clone()).unwrap(), 4);
+        from_iter.propagate_until_convergence().unwrap();
+
+        let mut from_stream = GlobalLayerState::new();
+        let consumed = from_stream
+            .add_edges_from_stream(tokio_stream::iter(edges))
+            .await
+            .unwrap();
+        assert_eq!(consumed, 4);
+        from_stream.propagate_until_convergence().unwrap();
+
+        assert_eq!(from_iter.get_layer_map(), batch.get_layer_map());
+        assert_eq!(from_stream.get_layer_map(), batch.get_layer_map());
+        assert_eq!(batch.layer_of("C"), Some(2));
+    }
+
+    #[test]
+    fn test_remove_edges_lowers_layers() {
+        let mut state = GlobalLayerState::new();
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("C".to_string(), "D".to_string()),
+            ("X".to_string(), "D".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+        state.propagate_until_convergence().unwrap();
+        state.enable_change_tracking();
+
+        let dropped = state
+            .remove_edges_batch(&[("C".to_string(), "D".to_string())])
+            .unwrap();
+        state.propagate_until_convergence().unwrap();
+
+        assert!(dropped.is_empty());
+        assert_eq!(state.get_layer_map().get("D"), Some(&1));
+        let changed = state.take_changed_vertices();
+        assert_eq!(changed.len(), 1);
+        assert!(changed.contains("D"));
+
+        let dropped = state
+            .remove_edges_batch(&[("X".to_string(), "D".to_string())])
+            .unwrap();
+        assert_eq!(dropped.len(), 2);
+        assert!(!state.contains_vertex("X"));
+        assert_eq!(state.vertex_count(), 3);
+
+        // A dropped vertex can come back with a fresh layer
+        state.add_edges_batch(&[("B".to_string(), "X".to_string())]).unwrap();
+        state.propagate_until_convergence().unwrap();
+        assert_eq!(state.layer_of("X"), Some(2));
+    }
+
+    #[test]
+    fn test_disk_spill_matches_memory() {
+        let dir = std::env::temp_dir().join(format!("layer-state-{}", uuid::Uuid::new_v4()));
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("A".to_string(), "C".to_string()),
+            ("B".to_string(), "D".to_string()),
+            ("C".to_string(), "D".to_string()),
+            ("D".to_string(), "E".to_string()),
+        ];
+
+        let mut memory = GlobalLayerState::new();
+        memory.add_edges_batch(&edges).unwrap();
+        memory.propagate_until_convergence().unwrap();
+
+        let mut disk = GlobalLayerState::with_disk_spill(&dir).unwrap();
+        assert!(disk.is_disk_backed());
+        disk.add_edges_batch(&edges[..2]).unwrap();
+        disk.propagate_until_convergence().unwrap();
+        disk.add_edges_batch(&edges[2..]).unwrap();
+        disk.propagate_until_convergence().unwrap();
+
+        assert_eq!(disk.get_layer_map(), memory.get_layer_map());
+        assert_eq!(disk.validate_layers().unwrap(), 0);
+        assert_eq!(disk.get_statistics().total_edges, 5);
+
+        drop(disk);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_would_create_cycle() {
+        let mut state = GlobalLayerState::new();
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+
+        assert!(state.would_create_cycle("C", "A").unwrap());
+        assert!(!state.would_create_cycle("A", "C").unwrap());
+        assert!(!state.would_create_cycle("NEW", "A").unwrap());
+    }
+
+    #[test]
+    fn test_cycles_are_detected_and_broken() {
+        let mut state = GlobalLayerState::new();
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("C".to_string(), "A".to_string()),
+            ("C".to_string(), "D".to_string()),
+            ("X".to_string(), "Y".to_string()),
+            ("Y".to_string(), "X".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+
+        assert!(state.propagate_until_convergence().is_err());
+
+        let report = state.detect_cycles(10).unwrap();
+        assert_eq!(report.components, vec![vec!["A", "B", "C"], vec!["X", "Y"]]);
+        assert_eq!(report.cycles[0], vec!["A", "B", "C"]);
+        assert_eq!(report.cycles[1], vec!["X", "Y"]);
+
+        let (_, broken) = state
+            .propagate_with_cycle_breaking(CycleBreakStrategy::Remove)
+            .unwrap();
+        assert_eq!(broken.len(), 2);
+        assert!(state.detect_cycles(10).unwrap().components.is_empty());
+        assert_eq!(state.validate_layers().unwrap(), 0);
+        assert_eq!(state.get_statistics().total_edges, 4);
+        let layers = state.get_layer_map();
+        assert!(layers["D"] >= 1);
+        assert_eq!(state.get_statistics().max_layer, *layers.values().max().unwrap());
+    }
+
+    #[test]
+    fn test_cycles_closed_by_later_batches_are_broken() {
+        let mut state = GlobalLayerState::new();
+        let chain: Vec<(String, String)> = (0..50)
+            .map(|i| (format!("V{}", i), format!("V{}", i + 1)))
+            .collect();
+        state.add_edges_batch(&chain).unwrap();
+        let (_, broken) = state
+            .propagate_with_cycle_breaking(CycleBreakStrategy::Reverse)
+            .unwrap();
+        assert!(broken.is_empty());
+
+        for (from, to) in [("V30", "V20"), ("V45", "V40")] {
+            state.add_edges_batch(&[(from.to_string(), to.to_string())]).unwrap();
+            let (_, broken) = state
+                .propagate_with_cycle_breaking(CycleBreakStrategy::Reverse)
+                .unwrap();
+            assert_eq!(broken.len(), 1);
+            assert_eq!(state.validate_layers().unwrap(), 0);
+        }
+        assert!(state.detect_cycles(10).unwrap().components.is_empty());
+        assert_eq!(state.get_statistics().total_edges, 52);
+    }
+
+    #[test]
+    fn test_propagation_stops_early_on_cycle() {
+        // A long chain makes the divergence bound large; the cycle P <-> Q is
+        // caught by the update-count check long before reaching it
+        let mut edges: Vec<(String, String)> = (0..200)
+            .map(|i| (format!("V{}", i), format!("V{}", i + 1)))
+            .collect();
+        edges.push(("V0".to_string(), "P".to_string()));
+        edges.push(("P".to_string(), "Q".to_string()));
+        edges.push(("Q".to_string(), "P".to_string()));
+
+        let mut state = GlobalLayerState::new();
+        state.add_edges_batch(&edges).unwrap();
+        let error = state.propagate_until_convergence().unwrap_err();
+        assert!(error.to_string().contains("stopped early"));
+
+        let diagnostics = state.propagation_diagnostics();
+        let stopped_at = diagnostics.stopped_at.as_deref().unwrap();
+        assert!(stopped_at == "P" || stopped_at == "Q");
+        assert!(state.layer_of(stopped_at).unwrap() < 100);
+        assert!(diagnostics.pending_vertices > 0);
+        let hot: Vec<&str> = diagnostics.hot_vertices.iter().map(|(v, _)| v.as_str()).collect();
+        assert!(hot.contains(&"P") && hot.contains(&"Q"));
+
+        let (_, broken) = state
+            .propagate_with_cycle_breaking(CycleBreakStrategy::Remove)
+            .unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(state.validate_layers().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_propagation_diagnostics_on_dag() {
+        let mut state = GlobalLayerState::new();
+        state.set_propagation_limits(PropagationLimits { hot_threshold: 0, cycle_check_after: 1 });
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("A".to_string(), "C".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+        state.propagate_until_convergence().unwrap();
+
+        let diagnostics = state.propagation_diagnostics();
+        assert_eq!(diagnostics.stopped_at, None);
+        assert_eq!(diagnostics.pending_vertices, 0);
+        assert!(!diagnostics.frontier_sizes.is_empty());
+        assert!(diagnostics.longest_update_chain >= 1);
+        // Every re-layered vertex exceeds a zero threshold
+        let hot: Vec<&str> = diagnostics.hot_vertices.iter().map(|(v, _)| v.as_str()).collect();
+        assert!(hot.contains(&"B") && hot.contains(&"C"));
+        assert_eq!(state.layer_of("C"), Some(2));
+    }
+
+    #[test]
+    fn test_compress_layers() {
+        let mut state = GlobalLayerState::new();
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("P".to_string(), "X".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+        let seeded = HashMap::from([
+            ("A".to_string(), 0),
+            ("P".to_string(), 0),
+            ("B".to_string(), 2),
+            ("X".to_string(), 5),
+        ]);
+        state.seed_layers(&seeded);
+
+        // Only gaps are removed without a size threshold
+        let compression = state.compress_layers(0).unwrap();
+        assert_eq!(compression.layers_before, 6);
+        assert_eq!(compression.layers_after, 3);
+        assert_eq!(compression.mapping, BTreeMap::from([(0, 0), (2, 1), (5, 2)]));
+        assert_eq!(state.layer_of("X"), Some(2));
+
+        // B and X are not connected, so their single-vertex layers merge
+        let compression = state.compress_layers(2).unwrap();
+        assert_eq!(compression.layers_after, 2);
+        assert_eq!(compression.merged_layers, 1);
+        assert_eq!(state.layer_of("B"), Some(1));
+        assert_eq!(state.layer_of("X"), Some(1));
+        assert_eq!(state.validate_layers().unwrap(), 0);
+        assert_eq!(state.get_statistics().compression, Some(compression));
+    }
+
+    #[test]
+    fn test_layer_constraints() {
+        let mut state = GlobalLayerState::new();
+        state
+            .set_layer_constraints(HashMap::from([("A".to_string(), 3), ("C".to_string(), 1)]))
+            .unwrap();
+
+        // A -> B -> C -> D: A is pinned above C, so B -> C violates C's pin
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("C".to_string(), "D".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+        state.propagate_until_convergence().unwrap();
+
+        assert_eq!(state.layer_of("A"), Some(3));
+        assert_eq!(state.layer_of("B"), Some(4));
+        assert_eq!(state.layer_of("C"), Some(1));
+        assert_eq!(state.layer_of("D"), Some(2));
+        let violations = state.constraint_violations().unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].vertex, "C");
+        assert_eq!(violations[0].predecessor, "B");
+        assert_eq!(violations[0].predecessor_layer, 4);
+
+        // Moving the pin above B resolves the violation and shifts D
+        state
+            .set_layer_constraints(HashMap::from([("A".to_string(), 3), ("C".to_string(), 6)]))
+            .unwrap();
+        state.propagate_until_convergence().unwrap();
+        assert_eq!(state.layer_of("D"), Some(7));
+        assert!(state.constraint_violations().unwrap().is_empty());
+        assert_eq!(state.validate_layers().unwrap(), 0);
+
+        assert!(state.set_layer_constraints(HashMap::from([("A".to_string(), -1)])).is_err());
+    }
+
+    #[test]
+    fn test_layer_lower_bounds_extend_existing_layering() {
+        let mut state = GlobalLayerState::new();
+        // Known layers of a previous run; E is new
+        state
+            .set_layer_lower_bounds(HashMap::from([
+                ("A".to_string(), 2),
+                ("B".to_string(), 5),
+                ("C".to_string(), 1),
+            ]))
+            .unwrap();
+
+        // A -> B -> C, C -> E: C's bound is below what B requires
+        let edges = vec![
+            ("A".to_string(), "B".to_string()),
+            ("B".to_string(), "C".to_string()),
+            ("C".to_string(), "E".to_string()),
+        ];
+        state.add_edges_batch(&edges).unwrap();
+        state.propagate_until_convergence().unwrap();
+
+        assert_eq!(state.layer_of("A"), Some(2));
+        assert_eq!(state.layer_of("B"), Some(5));
+        assert_eq!(state.layer_of("C"), Some(6));
+        assert_eq!(state.layer_of("E"), Some(7));
+        assert_eq!(state.validate_layers().unwrap(), 0);
+
+        // Bounds are floors, compression would break them
+        assert_eq!(state.compress_layers(1).unwrap(), LayerCompression::default());
+
+        // Clearing the bounds recomputes from zero
+        state.set_layer_lower_bounds(HashMap::new()).unwrap();
+        state.propagate_until_convergence().unwrap();
+        assert_eq!(state.layer_of("A"), Some(0));
+        assert_eq!(state.layer_of("E"), Some(3));
+
+        assert!(state.set_layer_lower_bounds(HashMap::from([("A".to_string(), -1)])).is_err());
+    }
+
     #[test]
     fn test_validation() {
         let mut state = GlobalLayerState::new();
@@ -407,7 +1683,7 @@ mod tests {
         state.add_edges_batch(&edges).unwrap();
         state.propagate_until_convergence().unwrap();
 
-        let invalid_count = state.validate_layers();
+        let invalid_count = state.validate_layers().unwrap();
         assert_eq!(invalid_count, 0); // Should be valid
     }
 }
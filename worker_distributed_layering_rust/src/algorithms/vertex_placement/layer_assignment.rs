@@ -12,10 +12,253 @@
 /// - Higher layers = newer articles (that cite articles in lower layers)
 /// - Result: old articles on the left (low layers), new articles on the right (high layers)
 /// - Edges visually flow right-to-left (new citing old)
+///
+/// For built graphs the default is `assign_layers_kahn`: one pass over a Kahn
+/// topological order, O(V + E). The BFS relaxation (`assign_layers_bfs`) pushes a
+/// vertex again on every improvement, which is exponential on dense DAGs in the
+/// worst case; it remains as the fallback for graphs that still contain a cycle.
+/// The streaming `GlobalLayerState` keeps its own incremental propagation.
+///
+/// Layers of a built graph are keyed by vertex handle (`LayerMap`); pins and
+/// lower bounds come from configuration and previous runs and are keyed by UID.
+///
+/// Layer constraints pin selected vertices (e.g. seminal papers) to a fixed layer.
+/// Pinned vertices keep their layer, everything downstream is propagated from it;
+/// a pin below one of its predecessors cannot be satisfied and is reported as a
+/// `LayerConstraintViolation` instead of being moved.
+///
+/// Layer lower bounds (e.g. layers of a previous run) are floors rather than pins:
+/// `assign_layers_bfs_with_lower_bounds` starts every bounded vertex at its known
+/// layer and only moves it further right when a predecessor requires it.
 
 use anyhow::Result;
 use std::collections::{HashMap, VecDeque};
-use crate::data_structures::Graph;
+use crate::data_structures::{Graph, VertexId};
+use super::arena::{ArenaVec, LayoutArena};
+use super::coffman_graham;
+use super::property_layering;
+
+/// Layer assignment strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayeringStrategy {
+    /// Longest distance from the sources (Kahn-order DP, BFS relaxation on cycles)
+    Bfs,
+
+    /// Coffman–Graham: bounded layer width at the cost of extra layers
+    CoffmanGraham,
+
+    /// Network simplex: minimal total edge length (slower, for publication layouts)
+    NetworkSimplex,
+
+    /// Publication year (`neo4j.year_property`), longest path within a year
+    ByProperty,
+}
+
+impl Default for LayeringStrategy {
+    fn default() -> Self {
+        LayeringStrategy::Bfs
+    }
+}
+
+/// Layers of the vertices of a built graph, keyed by handle
+///
+/// Handles are only meaningful for the graph that was layered; UIDs are
+/// resolved once the positions are produced.
+pub type LayerMap = HashMap<VertexId, i32>;
+
+/// A pinned vertex whose predecessor is not in a lower layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerConstraintViolation {
+    /// Pinned vertex
+    pub vertex: String,
+
+    /// Layer the vertex is pinned to
+    pub pinned_layer: i32,
+
+    /// Predecessor at or above the pinned layer
+    pub predecessor: String,
+
+    /// Layer of the predecessor
+    pub predecessor_layer: i32,
+}
+
+/// Assign layers with the given strategy and optional width limit
+///
+/// `max_layer_width == 0` means unlimited. With `Bfs` and `NetworkSimplex` the
+/// limit is enforced by pushing overflow into extra layers (`limit_layer_width`).
+/// Coffman–Graham and network simplex fall back to BFS if the graph still
+/// contains a cycle. `ByProperty` layers by the `ATTR_YEAR` vertex attribute
+/// and falls back to BFS when no vertex has a year.
+pub async fn assign_layers(
+    graph: &Graph,
+    strategy: LayeringStrategy,
+    max_layer_width: usize,
+) -> Result<LayerMap> {
+    assign_layers_in(&LayoutArena::new(), graph, strategy, max_layer_width, &[]).await
+}
+
+/// `assign_layers` with the temporary tables allocated in `arena`
+///
+/// A non-empty `topo_order` (e.g. from the topological sort phase) replaces the
+/// Kahn pass of the longest-path layering; an order that does not fit `graph`
+/// is ignored.
+pub async fn assign_layers_in(
+    arena: &LayoutArena,
+    graph: &Graph,
+    strategy: LayeringStrategy,
+    max_layer_width: usize,
+    topo_order: &[VertexId],
+) -> Result<LayerMap> {
+    if strategy == LayeringStrategy::CoffmanGraham {
+        tracing::info!(
+            "Assigning layers with Coffman-Graham (max layer width: {})...",
+            max_layer_width
+        );
+        if let Some(layer_map) = coffman_graham::assign_layers_coffman_graham(graph, max_layer_width) {
+            return Ok(layer_map);
+        }
+        tracing::warn!("Coffman-Graham layering requires an acyclic graph, falling back to BFS");
+    }
+
+    if strategy == LayeringStrategy::NetworkSimplex {
+        tracing::info!("Assigning layers with network simplex...");
+        if let Some(layer_map) = crate::algorithms::network_simplex::assign_layers_network_simplex(
+            graph,
+            crate::algorithms::network_simplex::DEFAULT_MAX_ITERATIONS,
+        ) {
+            return Ok(coffman_graham::limit_layer_width(&layer_map, graph, max_layer_width));
+        }
+        tracing::warn!("Network simplex layering requires an acyclic graph, falling back to BFS");
+    }
+
+    let ordered = if topo_order.is_empty() {
+        None
+    } else {
+        let layer_map = assign_layers_in_order(arena, graph, topo_order);
+        if layer_map.is_none() {
+            tracing::warn!("Topological order does not match the graph, recomputing it");
+        }
+        layer_map
+    };
+    let mut layer_map = match ordered.or_else(|| assign_layers_kahn_in(arena, graph)) {
+        Some(layer_map) => layer_map,
+        None => {
+            tracing::warn!("Kahn layering requires an acyclic graph, falling back to BFS relaxation");
+            assign_layers_bfs(graph, &HashMap::new()).await?
+        }
+    };
+
+    if strategy == LayeringStrategy::ByProperty {
+        let years = property_layering::year_count(graph);
+        if years == 0 {
+            tracing::warn!("Year layering requested but no publication years are known, using BFS layers");
+        } else {
+            tracing::info!("Assigning layers by publication year ({} vertices with a year)...", years);
+            layer_map = property_layering::assign_layers_by_property(graph, &layer_map);
+        }
+    }
+
+    Ok(coffman_graham::limit_layer_width(&layer_map, graph, max_layer_width))
+}
+
+/// Assign layers by dynamic programming over a Kahn topological order
+///
+/// Every vertex is visited once, after all of its predecessors:
+/// layer[v] = max(layer[predecessors]) + 1, sources get layer 0.
+///
+/// Returns `None` if the graph contains a cycle (some vertices never reach in-degree 0).
+pub fn assign_layers_kahn(graph: &Graph) -> Option<LayerMap> {
+    assign_layers_kahn_in(&LayoutArena::new(), graph)
+}
+
+/// `assign_layers_kahn` with the in-degree table and queue allocated in `arena`
+pub fn assign_layers_kahn_in(arena: &LayoutArena, graph: &Graph) -> Option<LayerMap> {
+    let layer_map = arena.scope(|bump| {
+        let vertex_count = graph.vertex_count();
+        let mut remaining: ArenaVec<usize> = ArenaVec::with_capacity_in(vertex_count, bump);
+        remaining.extend(graph.handles().map(|vertex| graph.predecessors(vertex).len()));
+        let mut pending: ArenaVec<i32> = ArenaVec::with_capacity_in(vertex_count, bump);
+        pending.resize(vertex_count, 0);
+        let mut layer_map = LayerMap::with_capacity(vertex_count);
+        // Every vertex is queued at most once, so a vector with a read cursor is enough
+        let mut queue: ArenaVec<VertexId> = ArenaVec::with_capacity_in(vertex_count, bump);
+        queue.extend(graph.handles().filter(|vertex| remaining[vertex.index()] == 0));
+        let mut head = 0;
+
+        while let Some(&vertex) = queue.get(head) {
+            head += 1;
+            let layer = pending[vertex.index()];
+            layer_map.insert(vertex, layer);
+
+            for target in graph.successors(vertex) {
+                let target_layer = &mut pending[target.index()];
+                *target_layer = (*target_layer).max(layer + 1);
+
+                let in_degree = &mut remaining[target.index()];
+                *in_degree -= 1;
+                if *in_degree == 0 {
+                    queue.push(target);
+                }
+            }
+        }
+        layer_map
+    });
+
+    if layer_map.len() < graph.vertex_count() {
+        return None;
+    }
+
+    tracing::info!(
+        "Kahn layer assignment complete: {} vertices, max layer: {}",
+        layer_map.len(),
+        layer_map.values().copied().max().unwrap_or(0)
+    );
+
+    Some(layer_map)
+}
+
+/// Assign layers by dynamic programming over a given topological order
+///
+/// Same layers as `assign_layers_kahn`, without its in-degree table and queue.
+/// Returns `None` if `topo_order` is not a topological order of `graph`: it
+/// misses or repeats a vertex, holds a handle of another graph, or lists a
+/// vertex before one of its predecessors.
+pub fn assign_layers_in_order(arena: &LayoutArena, graph: &Graph, topo_order: &[VertexId]) -> Option<LayerMap> {
+    let vertex_count = graph.vertex_count();
+    if topo_order.len() != vertex_count {
+        return None;
+    }
+
+    let layer_map = arena.scope(|bump| {
+        // -1 marks vertices that are not visited yet
+        let mut layers: ArenaVec<i32> = ArenaVec::with_capacity_in(vertex_count, bump);
+        layers.resize(vertex_count, -1);
+
+        for &vertex in topo_order {
+            if vertex.index() >= vertex_count || layers[vertex.index()] >= 0 {
+                return None;
+            }
+            let mut layer = 0;
+            for predecessor in graph.predecessors(vertex) {
+                let predecessor_layer = layers[predecessor.index()];
+                if predecessor_layer < 0 {
+                    return None;
+                }
+                layer = layer.max(predecessor_layer + 1);
+            }
+            layers[vertex.index()] = layer;
+        }
+        Some(topo_order.iter().map(|&vertex| (vertex, layers[vertex.index()])).collect::<LayerMap>())
+    })?;
+
+    tracing::info!(
+        "Layer assignment over the topological order complete: {} vertices, max layer: {}",
+        layer_map.len(),
+        layer_map.values().copied().max().unwrap_or(0)
+    );
+
+    Some(layer_map)
+}
 
 /// Assign layers to all vertices in the graph using BFS from source nodes
 ///
@@ -26,26 +269,67 @@ use crate::data_structures::Graph;
 ///    layer[target] = max(layer[all predecessors]) + 1
 /// 4. Continue until all connected vertices are assigned
 ///
-/// Returns: HashMap mapping vertex_id -> layer_number
-pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
-    let mut layer_map = HashMap::new();
+/// Vertices in `layer_constraints` start at their pinned layer and are never
+/// relaxed; violated pins are logged (see `find_constraint_violations`).
+/// Pins for vertices outside the graph are ignored, negative pins are an error.
+///
+/// Returns: layer of every reached vertex by handle
+pub async fn assign_layers_bfs(
+    graph: &Graph,
+    layer_constraints: &HashMap<String, i32>,
+) -> Result<LayerMap> {
+    assign_layers_bfs_with_lower_bounds(graph, layer_constraints, &HashMap::new()).await
+}
+
+/// `assign_layers_bfs` that extends a partial precomputed layering
+///
+/// Vertices in `lower_bounds` get at least the given layer:
+/// layer[v] = max(lower_bound[v], max(layer[predecessors]) + 1).
+/// Pins in `layer_constraints` take precedence over bounds. Bounds for vertices
+/// outside the graph are ignored, negative bounds are an error.
+pub async fn assign_layers_bfs_with_lower_bounds(
+    graph: &Graph,
+    layer_constraints: &HashMap<String, i32>,
+    lower_bounds: &HashMap<String, i32>,
+) -> Result<LayerMap> {
+    let mut layer_map = LayerMap::new();
     let mut queue = VecDeque::new();
 
+    if let Some((vertex_id, layer)) = layer_constraints.iter().find(|(_, &layer)| layer < 0) {
+        return Err(anyhow::anyhow!("Vertex {} is pinned to negative layer {}", vertex_id, layer));
+    }
+    if let Some((vertex_id, layer)) = lower_bounds.iter().find(|(_, &layer)| layer < 0) {
+        return Err(anyhow::anyhow!("Vertex {} has negative layer lower bound {}", vertex_id, layer));
+    }
+    // Pins and bounds are given by UID; only those of vertices in the graph apply
+    let by_handle = |layers: &HashMap<String, i32>| -> LayerMap {
+        layers
+            .iter()
+            .filter_map(|(vertex_id, &layer)| Some((graph.vertex_handle(vertex_id)?, layer)))
+            .collect()
+    };
+    let pins = by_handle(layer_constraints);
+    let bounds = by_handle(lower_bounds);
+    let floor = |vertex: VertexId| bounds.get(&vertex).copied().unwrap_or(0);
+
     // Step 1: Find all source nodes (nodes without incoming edges)
     tracing::info!("Finding source nodes for BFS layer assignment...");
 
-    let vertices: Vec<String> = graph.vertices().map(|v| v.clone()).collect();
+    let vertex_count = graph.vertex_count();
     let mut source_count = 0;
 
-    for vertex_id in &vertices {
-        let has_incoming = graph
-            .get_incoming_edges(vertex_id)
-            .map_or(false, |mut incoming| incoming.next().is_some());
+    for vertex in graph.handles() {
+        if let Some(&pinned) = pins.get(&vertex) {
+            layer_map.insert(vertex, pinned);
+            queue.push_back((vertex, pinned));
+            continue;
+        }
 
-        if !has_incoming {
-            // This is a source node - assign layer 0
-            layer_map.insert(vertex_id.clone(), 0);
-            queue.push_back((vertex_id.clone(), 0));
+        if graph.predecessors(vertex).next().is_none() {
+            // This is a source node - assign layer 0 (or its lower bound)
+            let layer = floor(vertex);
+            layer_map.insert(vertex, layer);
+            queue.push_back((vertex, layer));
             source_count += 1;
         }
     }
@@ -53,11 +337,26 @@ pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
     tracing::info!(
         "Found {} source nodes ({}% of {} total vertices)",
         source_count,
-        (source_count as f32 / vertices.len() as f32 * 100.0),
-        vertices.len()
+        (source_count as f32 / vertex_count as f32 * 100.0),
+        vertex_count
     );
 
-    if source_count == 0 {
+    if !lower_bounds.is_empty() {
+        tracing::info!(
+            "Extending a partial layering: {} layer lower bounds",
+            lower_bounds.len()
+        );
+    }
+
+    if !pins.is_empty() {
+        tracing::info!(
+            "{} of {} layer constraints apply to vertices of the graph",
+            pins.len(),
+            layer_constraints.len()
+        );
+    }
+
+    if queue.is_empty() {
         tracing::warn!("No source nodes found! This may indicate a cyclic graph or incorrect edge direction");
         return Ok(layer_map);
     }
@@ -65,11 +364,11 @@ pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
     // Step 2: BFS traversal to assign layers
     tracing::info!("Starting BFS traversal to assign layers...");
 
-    let mut max_layer = 0;
+    let mut max_layer = layer_map.values().copied().max().unwrap_or(0);
     let mut processed = 0;
     let log_interval = 10000;
 
-    while let Some((vertex_id, current_layer)) = queue.pop_front() {
+    while let Some((vertex, current_layer)) = queue.pop_front() {
         processed += 1;
 
         if processed % log_interval == 0 {
@@ -82,20 +381,23 @@ pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
         }
 
         // Process all outgoing edges from this vertex
-        if let Some(outgoing) = graph.get_outgoing_edges(&vertex_id) {
-            for target_id in outgoing {
-                let new_layer = current_layer + 1;
-                max_layer = max_layer.max(new_layer);
-
-                // Update layer if we found a longer path to this vertex
-                let should_update = layer_map
-                    .get(target_id)
-                    .map_or(true, |&existing_layer| new_layer > existing_layer);
-
-                if should_update {
-                    layer_map.insert(target_id.clone(), new_layer);
-                    queue.push_back((target_id.clone(), new_layer));
-                }
+        for target in graph.successors(vertex) {
+            // Pinned vertices keep their layer
+            if pins.contains_key(&target) {
+                continue;
+            }
+
+            let new_layer = (current_layer + 1).max(floor(target));
+            max_layer = max_layer.max(new_layer);
+
+            // Update layer if we found a longer path to this vertex
+            let should_update = layer_map
+                .get(&target)
+                .map_or(true, |&existing_layer| new_layer > existing_layer);
+
+            if should_update {
+                layer_map.insert(target, new_layer);
+                queue.push_back((target, new_layer));
             }
         }
     }
@@ -103,7 +405,7 @@ pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
     tracing::info!(
         "BFS layer assignment complete: {} vertices assigned ({}%), max layer: {}",
         layer_map.len(),
-        (layer_map.len() as f32 / vertices.len() as f32 * 100.0),
+        (layer_map.len() as f32 / vertex_count as f32 * 100.0),
         max_layer
     );
 
@@ -120,35 +422,75 @@ pub async fn assign_layers_bfs(graph: &Graph) -> Result<HashMap<String, i32>> {
         tracing::info!("Layer assignment validation passed - all edges respect layer ordering (citing articles in higher layers -> cited in lower layers)");
     }
 
+    let violations = find_constraint_violations(&layer_map, graph, layer_constraints);
+    if !violations.is_empty() {
+        tracing::warn!("{} layer constraint violations", violations.len());
+        for violation in violations.iter().take(10) {
+            tracing::warn!(
+                "Pinned {} (layer {}) has predecessor {} in layer {}",
+                violation.vertex,
+                violation.pinned_layer,
+                violation.predecessor,
+                violation.predecessor_layer
+            );
+        }
+    }
+
     Ok(layer_map)
 }
 
+/// Pinned vertices whose predecessors are not all in lower layers
+///
+/// Sorted by vertex and predecessor ID.
+pub fn find_constraint_violations(
+    layer_map: &LayerMap,
+    graph: &Graph,
+    layer_constraints: &HashMap<String, i32>,
+) -> Vec<LayerConstraintViolation> {
+    let mut violations = Vec::new();
+    for (vertex_id, &pinned_layer) in layer_constraints {
+        let Some(vertex) = graph.vertex_handle(vertex_id) else { continue };
+        for predecessor in graph.predecessors(vertex) {
+            if let Some(&predecessor_layer) = layer_map.get(&predecessor) {
+                if predecessor_layer >= pinned_layer {
+                    violations.push(LayerConstraintViolation {
+                        vertex: vertex_id.clone(),
+                        pinned_layer,
+                        predecessor: graph.vertex_uid(predecessor).to_string(),
+                        predecessor_layer,
+                    });
+                }
+            }
+        }
+    }
+    violations.sort_by(|a, b| a.vertex.cmp(&b.vertex).then_with(|| a.predecessor.cmp(&b.predecessor)));
+    violations
+}
+
 /// Validate layer assignments by checking that all edges go from lower to higher layers
 ///
 /// Returns: Number of validation errors found
 fn validate_layer_assignments(
-    layer_map: &HashMap<String, i32>,
+    layer_map: &LayerMap,
     graph: &Graph,
 ) -> Result<usize> {
     let mut errors = 0;
     let max_errors_to_log = 10;
 
-    for (vertex_id, &source_layer) in layer_map.iter() {
-        if let Some(outgoing) = graph.get_outgoing_edges(vertex_id) {
-            for target_id in outgoing {
-                if let Some(&target_layer) = layer_map.get(target_id) {
-                    if source_layer >= target_layer {
-                        if errors < max_errors_to_log {
-                            tracing::warn!(
-                                "Layer validation error: {} (layer {}) -> {} (layer {})",
-                                vertex_id,
-                                source_layer,
-                                target_id,
-                                target_layer
-                            );
-                        }
-                        errors += 1;
+    for (&vertex, &source_layer) in layer_map.iter() {
+        for target in graph.successors(vertex) {
+            if let Some(&target_layer) = layer_map.get(&target) {
+                if source_layer >= target_layer {
+                    if errors < max_errors_to_log {
+                        tracing::warn!(
+                            "Layer validation error: {} (layer {}) -> {} (layer {})",
+                            graph.vertex_uid(vertex),
+                            source_layer,
+                            graph.vertex_uid(target),
+                            target_layer
+                        );
                     }
+                    errors += 1;
                 }
             }
         }
@@ -161,65 +503,92 @@ fn validate_layer_assignments(
     Ok(errors)
 }
 
-/// Fix same-layer edges by reversing them to make graph acyclic
+/// Result of `fix_same_layer_edges`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SameLayerFix {
+    /// Edges reversed to point from the smaller to the larger vertex ID
+    pub reversed_edges: usize,
+
+    /// Vertices moved to a higher layer
+    pub relayered_vertices: usize,
+}
+
+/// Fix same-layer edges by reversing and re-layering them
 ///
 /// When two vertices are in the same layer (source.layer == target.layer),
 /// we need to decide which direction the edge should go. We use a simple
 /// heuristic: compare vertex IDs lexicographically and always direct edges
-/// from "smaller" to "larger" ID to ensure consistency.
+/// from "smaller" to "larger" ID to ensure consistency. The edge is reversed
+/// in `graph`, then targets are pushed to higher layers until every edge that
+/// does not point backwards goes from a lower to a higher layer.
 ///
-/// Returns: Number of edges reversed
+/// Edges pointing to a lower layer are left as they are and still show up in
+/// layer validation. Reversing edges keeps the vertex handles, so `layer_map`
+/// stays valid for `graph`.
 pub fn fix_same_layer_edges(
-    layer_map: &HashMap<String, i32>,
-    graph: &mut crate::data_structures::Graph,
-) -> Result<usize> {
+    layer_map: &mut LayerMap,
+    graph: &mut Graph,
+) -> Result<SameLayerFix> {
     tracing::info!("Fixing same-layer edges to ensure acyclic graph...");
 
     let mut edges_to_reverse = Vec::new();
 
     // Find all same-layer edges
-    for (vertex_id, &source_layer) in layer_map.iter() {
-        if let Some(outgoing) = graph.get_outgoing_edges(vertex_id) {
-            for target_id in outgoing {
-                if let Some(&target_layer) = layer_map.get(target_id) {
-                    if source_layer == target_layer {
-                        // Same layer - decide direction based on lexicographic order
-                        if vertex_id > target_id {
-                            // Reverse this edge: delete vertex_id -> target_id, add target_id -> vertex_id
-                            edges_to_reverse.push((vertex_id.clone(), target_id.clone()));
-                        }
-                    }
-                }
+    for (&vertex, &source_layer) in layer_map.iter() {
+        for target in graph.successors(vertex) {
+            if layer_map.get(&target) == Some(&source_layer) && graph.vertex_uid(vertex) > graph.vertex_uid(target) {
+                edges_to_reverse.push((graph.vertex_uid(vertex).to_string(), graph.vertex_uid(target).to_string()));
             }
         }
     }
+    edges_to_reverse.sort();
+
+    let mut fix = SameLayerFix::default();
+    for (source, target) in &edges_to_reverse {
+        if graph.reverse_edge(source, target)? {
+            fix.reversed_edges += 1;
+        }
+    }
 
-    let reversed_count = edges_to_reverse.len();
+    // Forward edges are ordered by (layer, id), so this order is topological for them
+    let graph: &Graph = graph;
+    let original: LayerMap = layer_map.clone();
+    let key = |vertex: VertexId| (original[&vertex], graph.vertex_uid(vertex));
+    let mut order: Vec<VertexId> = original.keys().copied().collect();
+    order.sort_by(|&a, &b| key(a).cmp(&key(b)));
+
+    for &vertex in &order {
+        let required = graph
+            .predecessors(vertex)
+            .filter(|pred| original.contains_key(pred) && key(*pred) < key(vertex))
+            .map(|pred| layer_map[&pred] + 1)
+            .max();
+        if let Some(required) = required {
+            let layer = layer_map.get_mut(&vertex).expect("vertex from layer map");
+            if required > *layer {
+                *layer = required;
+                fix.relayered_vertices += 1;
+            }
+        }
+    }
 
-    if reversed_count > 0 {
+    if fix.reversed_edges > 0 || fix.relayered_vertices > 0 {
         tracing::info!(
-            "Found {} same-layer edges to reverse for consistency",
-            reversed_count
-        );
-
-        // Note: Since Graph doesn't have methods to modify edges directly,
-        // we would need to rebuild the graph or add mutation methods.
-        // For now, we just report the count.
-        tracing::warn!(
-            "Edge reversal requires graph mutation methods - recording {} edges that need reversal",
-            reversed_count
+            "Reversed {} same-layer edges, moved {} vertices to higher layers",
+            fix.reversed_edges,
+            fix.relayered_vertices
         );
     } else {
         tracing::info!("No same-layer edges found - graph is properly structured");
     }
 
-    Ok(reversed_count)
+    Ok(fix)
 }
 
 /// Get distribution of vertices across layers
 ///
 /// Returns: HashMap mapping layer_number -> count of vertices in that layer
-pub fn get_layer_distribution(layer_map: &HashMap<String, i32>) -> HashMap<i32, usize> {
+pub fn get_layer_distribution(layer_map: &LayerMap) -> HashMap<i32, usize> {
     let mut distribution = HashMap::new();
 
     for &layer in layer_map.values() {
@@ -230,7 +599,7 @@ pub fn get_layer_distribution(layer_map: &HashMap<String, i32>) -> HashMap<i32,
 }
 
 /// Log statistics about layer distribution
-pub fn log_layer_statistics(layer_map: &HashMap<String, i32>) {
+pub fn log_layer_statistics(layer_map: &LayerMap) {
     if layer_map.is_empty() {
         tracing::warn!("No layers assigned!");
         return;
@@ -265,6 +634,11 @@ mod tests {
     use super::*;
     use crate::data_structures::GraphBuilder;
 
+    /// Layer of the vertex `vertex_id`, if it has one
+    fn layer_of(layers: &LayerMap, graph: &Graph, vertex_id: &str) -> Option<i32> {
+        layers.get(&graph.vertex_handle(vertex_id)?).copied()
+    }
+
     #[tokio::test]
     async fn test_simple_chain() {
         // Create a simple chain: A -> B -> C
@@ -273,11 +647,11 @@ mod tests {
         builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
         let graph = builder.build().unwrap();
 
-        let layers = assign_layers_bfs(&graph).await.unwrap();
+        let layers = assign_layers_bfs(&graph, &HashMap::new()).await.unwrap();
 
-        assert_eq!(layers.get("A"), Some(&0));
-        assert_eq!(layers.get("B"), Some(&1));
-        assert_eq!(layers.get("C"), Some(&2));
+        assert_eq!(layer_of(&layers, &graph, "A"), Some(0));
+        assert_eq!(layer_of(&layers, &graph, "B"), Some(1));
+        assert_eq!(layer_of(&layers, &graph, "C"), Some(2));
     }
 
     #[tokio::test]
@@ -290,11 +664,153 @@ mod tests {
         builder.add_edge("C".to_string(), "D".to_string(), 1.0).unwrap();
         let graph = builder.build().unwrap();
 
-        let layers = assign_layers_bfs(&graph).await.unwrap();
+        let layers = assign_layers_bfs(&graph, &HashMap::new()).await.unwrap();
+
+        assert_eq!(layer_of(&layers, &graph, "A"), Some(0));
+        assert_eq!(layer_of(&layers, &graph, "B"), Some(1));
+        assert_eq!(layer_of(&layers, &graph, "C"), Some(1));
+        assert_eq!(layer_of(&layers, &graph, "D"), Some(2)); // Max of predecessors + 1
+    }
+
+    #[test]
+    fn test_fix_same_layer_edges_gives_layered_dag() {
+        // B -> A and C -> B in layer 1 form a chain inside the layer; D -> C crosses it
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("R".to_string(), "A".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "A".to_string(), 2.0).unwrap();
+        builder.add_edge("C".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("A".to_string(), "D".to_string(), 1.0).unwrap();
+        builder.add_edge("C".to_string(), "D".to_string(), 1.0).unwrap();
+        let mut graph = builder.build().unwrap();
+
+        let [r, a, b, c, d] = ["R", "A", "B", "C", "D"].map(|v| graph.vertex_handle(v).unwrap());
+        let mut layers = LayerMap::from([(r, 0), (a, 1), (b, 1), (c, 1), (d, 2)]);
+        assert_eq!(validate_layer_assignments(&layers, &graph).unwrap(), 2);
+
+        let fix = fix_same_layer_edges(&mut layers, &mut graph).unwrap();
+
+        assert_eq!(fix.reversed_edges, 2);
+        assert!(graph.contains_edge("A", "B"));
+        assert!(graph.contains_edge("B", "C"));
+        assert_eq!(graph.get_edge_weight("A", "B"), Some(2.0));
+        assert_eq!(graph.edge_count(), 5);
+        assert_eq!((layers[&a], layers[&b], layers[&c], layers[&d]), (1, 2, 3, 4));
+        assert_eq!(fix.relayered_vertices, 3);
+        assert!(graph.is_dag());
+        assert_eq!(validate_layer_assignments(&layers, &graph).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_kahn_matches_bfs_on_dense_dag() {
+        // Every vertex points to all later ones: BFS re-enqueues heavily, Kahn visits each once
+        let mut builder = GraphBuilder::new();
+        let ids: Vec<String> = (0..12).map(|i| format!("V{:02}", i)).collect();
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                builder.add_edge(ids[i].clone(), ids[j].clone(), 1.0).unwrap();
+            }
+        }
+        let graph = builder.build().unwrap();
+
+        let kahn = assign_layers_kahn(&graph).unwrap();
+        let bfs = assign_layers_bfs(&graph, &HashMap::new()).await.unwrap();
+
+        assert_eq!(kahn, bfs);
+        assert_eq!(layer_of(&kahn, &graph, "V11"), Some(11));
+
+        // A precomputed topological order gives the same layers
+        let mut order: Vec<VertexId> = graph.handles().collect();
+        order.sort_by_key(|&vertex| graph.vertex_uid(vertex));
+        let arena = LayoutArena::new();
+        assert_eq!(assign_layers_in_order(&arena, &graph, &order), Some(kahn));
+
+        // An order listing a vertex before its predecessor is rejected
+        order.swap(0, 1);
+        assert!(assign_layers_in_order(&arena, &graph, &order).is_none());
+        assert!(assign_layers_in_order(&arena, &graph, &order[1..]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_layer_constraints() {
+        // A -> B -> C -> D, X -> C; A pinned to layer 2, C pinned to layer 1
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("C".to_string(), "D".to_string(), 1.0).unwrap();
+        builder.add_edge("X".to_string(), "C".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let constraints = HashMap::from([
+            ("A".to_string(), 2),
+            ("C".to_string(), 1),
+            ("MISSING".to_string(), 0),
+        ]);
+        let layers = assign_layers_bfs(&graph, &constraints).await.unwrap();
+
+        assert_eq!(layer_of(&layers, &graph, "A"), Some(2));
+        assert_eq!(layer_of(&layers, &graph, "B"), Some(3));
+        assert_eq!(layer_of(&layers, &graph, "C"), Some(1));
+        assert_eq!(layer_of(&layers, &graph, "D"), Some(2));
+        assert_eq!(layer_of(&layers, &graph, "X"), Some(0));
+        assert_eq!(layers.len(), 5);
+
+        let violations = find_constraint_violations(&layers, &graph, &constraints);
+        assert_eq!(
+            violations,
+            vec![LayerConstraintViolation {
+                vertex: "C".to_string(),
+                pinned_layer: 1,
+                predecessor: "B".to_string(),
+                predecessor_layer: 3,
+            }]
+        );
+
+        let negative = HashMap::from([("A".to_string(), -1)]);
+        assert!(assign_layers_bfs(&graph, &negative).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lower_bounds_extend_partial_layering() {
+        // A -> B -> C, X -> C; B and C were layered before, X is new
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("X".to_string(), "C".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
+
+        let known = HashMap::from([
+            ("A".to_string(), 1),
+            ("B".to_string(), 4),
+            ("C".to_string(), 2),
+        ]);
+        let layers = assign_layers_bfs_with_lower_bounds(&graph, &HashMap::new(), &known)
+            .await
+            .unwrap();
+
+        assert_eq!(layer_of(&layers, &graph, "A"), Some(1));
+        assert_eq!(layer_of(&layers, &graph, "B"), Some(4));
+        assert_eq!(layer_of(&layers, &graph, "C"), Some(5));
+        assert_eq!(layer_of(&layers, &graph, "X"), Some(0));
+        assert_eq!(validate_layer_assignments(&layers, &graph).unwrap(), 0);
+
+        // Pins win over bounds
+        let pins = HashMap::from([("B".to_string(), 2)]);
+        let layers = assign_layers_bfs_with_lower_bounds(&graph, &pins, &known).await.unwrap();
+        assert_eq!(layer_of(&layers, &graph, "B"), Some(2));
+        assert_eq!(layer_of(&layers, &graph, "C"), Some(3));
+
+        let negative = HashMap::from([("A".to_string(), -1)]);
+        assert!(assign_layers_bfs_with_lower_bounds(&graph, &HashMap::new(), &negative).await.is_err());
+    }
+
+    #[test]
+    fn test_kahn_rejects_cycle() {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("C".to_string(), "B".to_string(), 1.0).unwrap();
+        let graph = builder.build().unwrap();
 
-        assert_eq!(layers.get("A"), Some(&0));
-        assert_eq!(layers.get("B"), Some(&1));
-        assert_eq!(layers.get("C"), Some(&1));
-        assert_eq!(layers.get("D"), Some(&2)); // Max of predecessors + 1
+        assert!(assign_layers_kahn(&graph).is_none());
     }
 }
@@ -2,33 +2,85 @@
 ///
 /// This module coordinates the entire vertex placement process:
 /// 1. Layer assignment using BFS algorithm
-/// 2. Vertex placement within layers
-/// 3. Optional layout optimization
-/// 4. Edge routing and polyline computation
+/// 2. Dummy vertices for edges spanning several layers
+/// 3. Vertex placement within layers
+/// 4. Optional layout optimization
+/// 5. Edge routing and polyline computation
+///
+/// Temporary data of layering, ordering and routing is allocated in a
+/// per-run bump arena (`LayoutArena`), released when the run ends.
+///
+/// `refine_region` re-optimizes a selected part of an existing layout while the
+/// rest stays fixed.
 ///
 /// This refactored version fixes the critical bug where only 3 layers were created
 /// instead of hundreds, by replacing the longest_path approach with BFS-based layer assignment.
 
 mod stats;
+mod arena;
 mod layer_assignment;
+mod coffman_graham;
 mod placement;
+mod clustering;
+mod annotation;
 mod optimization;
 mod edge_routing;
+mod dummy_vertices;
 mod global_layer_state;
+mod edge_store;
+mod property_layering;
+mod region;
 
 // Re-export public types
 pub use stats::PlacementStats;
-pub use placement::{VertexPosition, PlacementConfig, OccupiedPositions, place_all_vertices};
-pub use optimization::{OptimizationOptions, count_edge_crossings};
-pub use edge_routing::{EdgeRoutingOptions, calculate_edge_length, get_edge_statistics};
-pub use global_layer_state::{GlobalLayerState, LayerStatistics};
+pub use arena::{ArenaMap, ArenaVec, LayoutArena};
+pub use layer_assignment::{
+    LayerMap, LayeringStrategy, LayerConstraintViolation, SameLayerFix, assign_layers, assign_layers_bfs_with_lower_bounds, assign_layers_in, assign_layers_kahn,
+    assign_layers_kahn_in, assign_layers_in_order,
+    find_constraint_violations,
+    fix_same_layer_edges,
+};
+pub use coffman_graham::{assign_layers_coffman_graham, limit_layer_width};
+pub use placement::{
+    VertexPosition, PlacementConfig, PlacementMode, LevelPacking, TieBreak, TieBreakKeys, OccupiedPositions,
+    place_all_vertices, place_all_vertices_with_widths, place_all_vertices_ordered, apply_vertex_anchors,
+    place_all_vertices_centroid, place_graph_vertices, calculate_neighbor_centroid, apply_vertex_sizes,
+    calculate_layout_dimensions, centroid_sort_layers, place_vertices_in_layer_grid,
+    degree_keys, input_order_keys, order_layer_members,
+};
+pub use annotation::{AnchorSide, AnnotationAnchor, AnnotationOptions, annotation_anchors};
+pub use clustering::{
+    ClusterBounds, VertexClusters, cluster_bounds, clusters_from_edges, clusters_from_graph,
+    group_layers_by_cluster, set_vertex_clusters,
+};
+pub use optimization::{
+    OptimizationOptions, ApproximateOrdering, CrossingHeuristic, CrossingReduction, LayerBalance,
+    assert_no_overlaps, balance_layers, count_crossings, count_edge_crossings, count_overlaps,
+    count_routed_crossings, minimize_edge_crossings, minimize_edge_crossings_in,
+    minimize_edge_crossings_with, repair_overlaps, routed_crossings, RoutedCrossings,
+};
+pub use edge_routing::{
+    EdgeBundlingOptions, EdgeRouteMetrics, EdgeRoutingOptions, EdgeRoutingReport, RoutingStats,
+    bezier_control_points, bundle_edges, calculate_edge_length, count_bends, compute_edge_paths_in, get_edge_statistics, parallel_edge_paths,
+    simplify_edge_paths, simplify_path,
+};
+pub use dummy_vertices::{
+    DUMMY_PREFIX, DummyChains, DummyExpansion, count_dummy_vertices, dummy_id, insert_dummy_vertices,
+    is_dummy, route_through_dummies, strip_dummy_vertices,
+};
+pub use property_layering::{VertexYears, assign_layers_by_property, set_vertex_years, year_count, years_from_edges};
+pub use region::RegionRefinement;
+pub use global_layer_state::{
+    CycleReport, GlobalLayerState, LayerCompression, LayerStatistics, PropagationDiagnostics,
+    PropagationLimits,
+};
 
 use anyhow::Result;
 use std::collections::HashMap;
-use crate::data_structures::Graph;
+use crate::data_structures::{Graph, VertexId};
 
 /// Main vertex placer orchestrator
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OptimalVertexPlacer {
     /// Configuration for vertex placement
     config: PlacementConfig,
@@ -41,6 +93,12 @@ pub struct OptimalVertexPlacer {
 
     /// Statistics about the placement
     stats: PlacementStats,
+
+    /// Sort keys for `TieBreak::InputOrder` (degree keys are computed from the graph)
+    tie_break_keys: TieBreakKeys,
+
+    /// Temporary allocations of the current run
+    arena: LayoutArena,
 }
 
 impl OptimalVertexPlacer {
@@ -51,6 +109,8 @@ impl OptimalVertexPlacer {
             opt_options: OptimizationOptions::default(),
             edge_options: EdgeRoutingOptions::default(),
             stats: PlacementStats::new(),
+            tie_break_keys: TieBreakKeys::new(),
+            arena: LayoutArena::new(),
         }
     }
 
@@ -65,6 +125,8 @@ impl OptimalVertexPlacer {
             opt_options,
             edge_options,
             stats: PlacementStats::new(),
+            tie_break_keys: TieBreakKeys::new(),
+            arena: LayoutArena::new(),
         }
     }
 
@@ -73,27 +135,54 @@ impl OptimalVertexPlacer {
     /// This is the new BFS-based algorithm that fixes the 3-layer bug.
     ///
     /// Process:
-    /// 1. Assign layers using BFS from source nodes
+    /// 1. Assign layers using BFS from source nodes, insert dummy vertices
     /// 2. Place vertices within their assigned layers
     /// 3. Optionally optimize the layout
-    /// 4. Compute edge paths (polylines)
+    /// 4. Compute edge paths (polylines), strip dummy vertices
     /// 5. Update statistics
     ///
-    /// Note: The unused parameters (_longest_path, _topo_order) are kept for
-    /// backward compatibility with existing code that calls this method.
+    /// Publication years (`LayeringStrategy::ByProperty`) and clusters (layers
+    /// grouped by cluster) come from the vertex attributes of `graph`.
+    ///
+    /// `topo_order` is a topological order of `graph` computed beforehand (e.g. by
+    /// the topological sort phase); the longest-path layering then reuses it
+    /// instead of running its own Kahn pass. Pass an empty slice if there is none.
+    /// Layers, dummy expansion and layer ordering work on vertex handles; UIDs
+    /// are only resolved for the returned positions.
     pub async fn place_vertices(
         &mut self,
         graph: &Graph,
-        _longest_path: &[String], // No longer used, kept for compatibility
-        _topo_order: &[String],   // No longer used, kept for compatibility
+        topo_order: &[VertexId],
     ) -> Result<(Vec<crate::neo4j::VertexPosition>, HashMap<(String, String), Vec<(f32, f32)>>)> {
         self.reset_state();
 
         tracing::info!("=== Starting BFS-based vertex placement (FIXED algorithm) ===");
 
-        // Step 1: Assign layers using BFS (FIXED: replaces longest_path approach)
-        tracing::info!("Step 1/5: Assigning layers using BFS from source nodes...");
-        let layer_map = layer_assignment::assign_layers_bfs(graph).await?;
+        // Step 1: Assign layers (BFS by default, Coffman-Graham for bounded width).
+        // Pins and lower bounds only hold with BFS relaxation, and the width
+        // limit would move pinned vertices, so both are skipped then
+        let layer_map = if self.config.layer_constraints.is_empty() && self.config.layer_lower_bounds.is_empty() {
+            tracing::info!("Step 1/5: Assigning layers ({:?})...", self.config.layering);
+            layer_assignment::assign_layers_in(
+                &self.arena,
+                graph,
+                self.config.layering,
+                self.config.max_layer_width,
+                topo_order,
+            ).await?
+        } else {
+            tracing::info!(
+                "Step 1/5: Assigning layers with {} pins and {} lower bounds (BFS, {:?} ignored)...",
+                self.config.layer_constraints.len(),
+                self.config.layer_lower_bounds.len(),
+                self.config.layering
+            );
+            layer_assignment::assign_layers_bfs_with_lower_bounds(
+                graph,
+                &self.config.layer_constraints,
+                &self.config.layer_lower_bounds,
+            ).await?
+        };
 
         if layer_map.is_empty() {
             tracing::warn!("No vertices were assigned layers!");
@@ -103,32 +192,148 @@ impl OptimalVertexPlacer {
         // Log layer statistics
         layer_assignment::log_layer_statistics(&layer_map);
 
+        // Long edges become chains of dummy vertices that are placed and
+        // reordered like real ones
+        let expansion = if self.config.dummy_vertices {
+            let needed = dummy_vertices::count_dummy_vertices(graph, &layer_map);
+            if needed > self.config.max_dummy_vertices {
+                tracing::warn!(
+                    "{} dummy vertices needed, limit is {}; routing edges without them",
+                    needed,
+                    self.config.max_dummy_vertices
+                );
+                None
+            } else {
+                Some(dummy_vertices::insert_dummy_vertices(graph, &layer_map)?)
+            }
+        } else {
+            None
+        };
+        let (layout_graph, layout_layers) = match &expansion {
+            Some(expansion) => (&expansion.graph, &expansion.layer_map),
+            None => (graph, &layer_map),
+        };
+
         // Step 2: Place vertices at (x, y) coordinates based on their layers
         tracing::info!("Step 2/5: Placing vertices at coordinates...");
-        let mut positions = placement::place_all_vertices(&layer_map, &self.config);
+        let degree_tie_keys;
+        let tie_keys = match self.config.tie_break {
+            TieBreak::Degree => {
+                degree_tie_keys = placement::degree_keys(graph);
+                &degree_tie_keys
+            }
+            TieBreak::VertexId | TieBreak::InputOrder => &self.tie_break_keys,
+        };
+        let mut positions = if self.opt_options.centroid_ordering {
+            placement::place_all_vertices_centroid(layout_layers, layout_graph, tie_keys, &self.config)
+        } else {
+            placement::place_graph_vertices(layout_layers, layout_graph, tie_keys, &self.config)
+        };
+
+        if self.config.placement_mode == PlacementMode::CentroidSorted {
+            placement::centroid_sort_layers(&mut positions, layout_graph);
+        }
 
         // Step 3: Optional optimization
+        let mut crossing_reduction = None;
+        if self.opt_options.minimize_crossings {
+            tracing::info!("Step 3/5: Minimizing edge crossings...");
+            crossing_reduction = Some(optimization::minimize_edge_crossings_in(
+                &self.arena,
+                &mut positions,
+                layout_graph,
+                self.opt_options.crossing_heuristic,
+                self.opt_options.max_iterations,
+                self.opt_options.deterministic,
+                self.opt_options.approximate_ordering.as_ref(),
+            ));
+        }
+
+        let clusters = clustering::clusters_from_graph(graph);
+        if !clusters.is_empty() {
+            tracing::info!("Step 3/5: Grouping layers by cluster...");
+            clustering::group_layers_by_cluster(&mut positions, &clusters);
+        }
+
         if self.opt_options.compact_layout {
             tracing::info!("Step 3/5: Optimizing layout...");
-            optimization::optimize_placement(&mut positions, graph, &self.opt_options).await?;
+            optimization::optimize_placement(&mut positions, layout_graph, &self.opt_options).await?;
         } else {
             tracing::info!("Step 3/5: Skipping optimization (disabled)");
         }
 
+        // Reordering passes must not stack blocks on each other; move the blocks
+        // that actually collide before sizes and edge paths are derived
+        let overlaps_repaired = optimization::repair_overlaps(&mut positions, &self.config);
+        optimization::assert_no_overlaps(&positions, &self.config)?;
+
+        // Reordering moves vertices between slots of the uniform grid;
+        // restore offsets for the actual block sizes
+        if !self.config.vertex_sizes.is_empty() {
+            placement::apply_vertex_sizes(&mut positions, &self.config);
+        }
+
+        if self.opt_options.balance_layers {
+            tracing::info!("Step 3/5: Balancing layers ({:?})...", self.opt_options.balance_mode);
+            optimization::balance_layers(&mut positions, layout_graph, self.opt_options.balance_mode);
+        }
+
+        // Manually positioned vertices win over everything computed above
+        let anchored_vertices = placement::apply_vertex_anchors(&mut positions, &self.config);
+
         // Step 4: Compute edge paths (polylines)
         tracing::info!("Step 4/5: Computing edge paths...");
-        let edge_paths = edge_routing::compute_edge_paths(
-            &positions,
-            graph,
-            &self.config,
-            &self.edge_options,
-        )?;
-
-        edge_routing::get_edge_statistics(&edge_paths);
+        let (mut edge_paths, mut routing_stats) = match &expansion {
+            Some(expansion) => {
+                let mut edge_paths = dummy_vertices::route_through_dummies(
+                    &positions,
+                    graph,
+                    &expansion.chains,
+                    &self.config,
+                    &self.edge_options,
+                );
+                dummy_vertices::strip_dummy_vertices(&mut positions);
+                // Crossings of the dummy slots share the free gaps between real blocks
+                let mut stats = edge_routing::RoutingStats::default();
+                if let Some(spacing) = self.edge_options.channel_spacing {
+                    stats.channel_shifts = edge_routing::allocate_dummy_channels(
+                        &self.arena,
+                        &mut edge_paths,
+                        &positions,
+                        &self.config,
+                        spacing,
+                    );
+                }
+                (edge_paths, stats)
+            }
+            None => edge_routing::compute_edge_paths_in(
+                &self.arena,
+                &positions,
+                graph,
+                &self.config,
+                &self.edge_options,
+            )?,
+        };
+
+        if let Some(tolerance) = self.edge_options.simplify_tolerance {
+            routing_stats.points_removed = edge_routing::simplify_edge_paths(&mut edge_paths, tolerance);
+        }
 
         // Step 5: Update statistics
         tracing::info!("Step 5/5: Updating statistics...");
         self.update_stats(&positions);
+        if let Some(reduction) = crossing_reduction {
+            self.stats.crossings_before = reduction.crossings_before;
+            self.stats.crossings_after = reduction.crossings_after;
+            self.stats.approximate_layers = reduction.approximate_layers;
+        }
+        self.stats.overlaps_repaired = overlaps_repaired;
+        self.stats.anchored_vertices = anchored_vertices;
+        self.stats.edges_detoured = routing_stats.edges_detoured;
+        self.stats.channel_shifts = routing_stats.channel_shifts;
+        self.stats.path_points_removed = routing_stats.points_removed;
+        self.stats.arena_peak_bytes = self.arena.allocated_bytes();
+        self.arena.reset();
 
         let (width, height) = placement::calculate_layout_dimensions(&positions, &self.config);
 
@@ -137,6 +342,14 @@ impl OptimalVertexPlacer {
         tracing::info!("  Vertices placed: {}", self.stats.vertices_placed);
         tracing::info!("  Layers used: {}", self.stats.layers_used);
         tracing::info!("  Avg vertices/layer: {:.2}", self.stats.avg_vertices_per_layer);
+        tracing::info!(
+            "  Edge crossings: {} -> {}",
+            self.stats.crossings_before,
+            self.stats.crossings_after
+        );
+        if self.stats.overlaps_repaired > 0 {
+            tracing::warn!("  Overlapping vertices repaired: {}", self.stats.overlaps_repaired);
+        }
         tracing::info!("  Layout dimensions: {:.0} x {:.0} px", width, height);
 
         // Convert internal VertexPosition to neo4j::VertexPosition
@@ -154,9 +367,32 @@ impl OptimalVertexPlacer {
         Ok((neo4j_positions, edge_paths))
     }
 
+    /// Re-optimize only `vertex_ids` of an existing layout
+    ///
+    /// Levels and y coordinates of the selected vertices are recomputed from
+    /// their neighbors using the configured crossing heuristic; all other
+    /// vertices are fixed obstacles and keep their positions.
+    pub fn refine_region(
+        &self,
+        vertex_ids: &[String],
+        positions: &mut [crate::neo4j::VertexPosition],
+        graph: &Graph,
+    ) -> Result<RegionRefinement> {
+        region::refine_region(
+            vertex_ids,
+            positions,
+            graph,
+            &self.config,
+            self.opt_options.crossing_heuristic,
+            self.opt_options.max_iterations,
+        )
+    }
+
     /// Reset internal state before a new placement
     fn reset_state(&mut self) {
         self.stats.reset();
+        // Leftovers of a run that failed half-way
+        self.arena.reset();
     }
 
     /// Update placement statistics after placing vertices
@@ -192,21 +428,41 @@ impl OptimalVertexPlacer {
         self.stats.total_height = height;
     }
 
+    /// Set sort keys used by `TieBreak::InputOrder` (see `input_order_keys`)
+    pub fn set_tie_break_keys(&mut self, keys: TieBreakKeys) {
+        self.tie_break_keys = keys;
+    }
+
     /// Get current placement statistics
     pub fn get_stats(&self) -> &PlacementStats {
         &self.stats
     }
 
+    /// Get placement configuration
+    pub fn get_config(&self) -> &PlacementConfig {
+        &self.config
+    }
+
     /// Get mutable reference to configuration (for testing/adjustment)
     pub fn get_config_mut(&mut self) -> &mut PlacementConfig {
         &mut self.config
     }
 
+    /// Get optimization options
+    pub fn get_opt_options(&self) -> &OptimizationOptions {
+        &self.opt_options
+    }
+
     /// Get mutable reference to optimization options (for testing/adjustment)
     pub fn get_opt_options_mut(&mut self) -> &mut OptimizationOptions {
         &mut self.opt_options
     }
 
+    /// Get edge routing options
+    pub fn get_edge_options(&self) -> &EdgeRoutingOptions {
+        &self.edge_options
+    }
+
     /// Get mutable reference to edge routing options (for testing/adjustment)
     pub fn get_edge_options_mut(&mut self) -> &mut EdgeRoutingOptions {
         &mut self.edge_options
@@ -232,12 +488,8 @@ mod tests {
 
         let mut placer = OptimalVertexPlacer::new();
 
-        // Empty vectors for backward compatibility
-        let longest_path = vec![];
-        let topo_order = vec![];
-
         let (positions, _edge_paths) = placer
-            .place_vertices(&graph, &longest_path, &topo_order)
+            .place_vertices(&graph, &[])
             .await
             .unwrap();
 
@@ -258,6 +510,9 @@ mod tests {
         let stats = placer.get_stats();
         assert_eq!(stats.vertices_placed, 3);
         assert_eq!(stats.layers_used, 3);
+        // Layering and routing temporaries came from the arena, which is released afterwards
+        assert!(stats.arena_peak_bytes > 0);
+        assert!(placer.arena.allocated_bytes() <= stats.arena_peak_bytes);
     }
 
     #[tokio::test]
@@ -271,8 +526,13 @@ mod tests {
 
         let mut placer = OptimalVertexPlacer::new();
 
+        // A topological order from an earlier phase gives the same layers
+        let topo_order: Vec<VertexId> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|vertex_id| graph.vertex_handle(vertex_id).unwrap())
+            .collect();
         let (positions, _edge_paths) = placer
-            .place_vertices(&graph, &vec![], &vec![])
+            .place_vertices(&graph, &topo_order)
             .await
             .unwrap();
 
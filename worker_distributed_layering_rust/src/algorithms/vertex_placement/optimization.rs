@@ -1,13 +1,19 @@
 /// Layout optimization algorithms
 ///
 /// This module provides algorithms for optimizing the layout after initial placement:
+/// - Crossing minimization (Sugiyama barycenter/median sweeps)
 /// - Layout compaction
+/// - Vertical balancing of layers
+/// - Overlap detection and repair
 /// - General optimization passes
 
 use anyhow::Result;
-use std::collections::HashMap;
+use bumpalo::Bump;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
 use crate::data_structures::Graph;
-use super::placement::VertexPosition;
+use super::arena::{ArenaMap, ArenaVec, LayoutArena};
+use super::placement::{OccupiedPositions, PlacementConfig, VertexPosition};
 
 /// Options for layout optimization
 #[derive(Debug, Clone)]
@@ -16,7 +22,32 @@ pub struct OptimizationOptions {
     pub compact_layout: bool,
 
     /// Maximum number of optimization iterations
+    /// (also bounds the number of down-up crossing minimization sweeps)
     pub max_iterations: usize,
+
+    /// Whether to reorder vertices within layers to reduce edge crossings
+    pub minimize_crossings: bool,
+
+    /// Ordering heuristic used by the crossing minimization sweeps
+    pub crossing_heuristic: CrossingHeuristic,
+
+    /// Order each layer by the mean y of its neighbors in earlier layers
+    /// already during initial placement (see `place_all_vertices_centroid`)
+    pub centroid_ordering: bool,
+
+    /// Make the result independent of graph adjacency order
+    /// (neighbor lists are sorted before computing ordering keys)
+    pub deterministic: bool,
+
+    /// Shift layers vertically instead of leaving them top-aligned
+    pub balance_layers: bool,
+
+    /// Target of the vertical shift when `balance_layers` is set
+    pub balance_mode: LayerBalance,
+
+    /// Approximate ordering for layers above a size threshold
+    /// (None — every layer is sorted exactly)
+    pub approximate_ordering: Option<ApproximateOrdering>,
 }
 
 impl Default for OptimizationOptions {
@@ -24,10 +55,546 @@ impl Default for OptimizationOptions {
         Self {
             compact_layout: true,
             max_iterations: 10,
+            minimize_crossings: true,
+            crossing_heuristic: CrossingHeuristic::Barycenter,
+            centroid_ordering: false,
+            deterministic: false,
+            balance_layers: false,
+            balance_mode: LayerBalance::Midline,
+            approximate_ordering: Some(ApproximateOrdering::default()),
+        }
+    }
+}
+
+/// Approximate crossing minimization for huge layers
+///
+/// Layers with more than `layer_threshold` vertices are not sorted exactly:
+/// the barycenter of each vertex is taken over at most `sample` of its
+/// neighbors, and vertices are distributed into `buckets` equal key ranges
+/// with a stable counting sort, so a sweep over such a layer is linear.
+/// Vertices in the same bucket keep their relative order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApproximateOrdering {
+    /// Layers larger than this are ordered approximately
+    pub layer_threshold: usize,
+
+    /// Number of key buckets per layer
+    pub buckets: usize,
+
+    /// Maximum number of neighbors sampled per vertex
+    pub sample: usize,
+}
+
+impl Default for ApproximateOrdering {
+    fn default() -> Self {
+        Self {
+            layer_threshold: 100_000,
+            buckets: 4096,
+            sample: 32,
         }
     }
 }
 
+/// Where `balance_layers` moves each layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerBalance {
+    /// Center the layer on the horizontal midline of the drawing
+    Midline,
+
+    /// Move the layer towards the mean y of its vertices' neighbors;
+    /// layers without neighbors fall back to the midline
+    Barycenter,
+}
+
+impl Default for LayerBalance {
+    fn default() -> Self {
+        LayerBalance::Midline
+    }
+}
+
+/// Heuristic for computing a vertex's ordering key from its neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossingHeuristic {
+    /// Mean position of neighbors in the fixed layers
+    Barycenter,
+
+    /// Median position of neighbors in the fixed layers
+    Median,
+}
+
+impl Default for CrossingHeuristic {
+    fn default() -> Self {
+        CrossingHeuristic::Barycenter
+    }
+}
+
+/// Outcome of the crossing minimization pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrossingReduction {
+    /// Crossings before reordering
+    pub crossings_before: usize,
+
+    /// Crossings after reordering
+    pub crossings_after: usize,
+
+    /// Number of down-up sweeps performed
+    pub iterations: usize,
+
+    /// Layers ordered approximately (see `ApproximateOrdering`)
+    pub approximate_layers: usize,
+}
+
+/// Reorder vertices within layers to reduce edge crossings
+///
+/// Classic Sugiyama layer-by-layer sweep: a downward pass orders each layer by
+/// the barycenter (or median) of its predecessors' positions, an upward pass
+/// does the same using successors. Sweeps repeat up to `max_iterations` times
+/// and stop as soon as a sweep does not reduce the crossing count; the best
+/// ordering seen is kept.
+///
+/// Only the order inside each layer changes: the existing (level, x, y) slots of
+/// a layer are reassigned to its vertices in the new order, so the geometry
+/// produced by the placement step (including interval packing) is preserved.
+///
+/// With `deterministic` the neighbor lists are sorted, so the result depends only
+/// on the input positions and the edge set, not on the order edges were added.
+///
+/// Layers above the default `ApproximateOrdering` threshold are ordered
+/// approximately; see `minimize_edge_crossings_with`.
+pub fn minimize_edge_crossings(
+    positions: &mut [VertexPosition],
+    graph: &Graph,
+    heuristic: CrossingHeuristic,
+    max_iterations: usize,
+    deterministic: bool,
+) -> CrossingReduction {
+    minimize_edge_crossings_with(
+        positions,
+        graph,
+        heuristic,
+        max_iterations,
+        deterministic,
+        Some(&ApproximateOrdering::default()),
+    )
+}
+
+/// Crossing minimization with an explicit approximation policy for huge layers
+///
+/// With `approximate` set, layers larger than its threshold are reordered by
+/// the sampled, bucketed barycenter (`heuristic` is ignored for them); the
+/// number of such layers is reported in `CrossingReduction::approximate_layers`.
+pub fn minimize_edge_crossings_with(
+    positions: &mut [VertexPosition],
+    graph: &Graph,
+    heuristic: CrossingHeuristic,
+    max_iterations: usize,
+    deterministic: bool,
+    approximate: Option<&ApproximateOrdering>,
+) -> CrossingReduction {
+    minimize_edge_crossings_in(
+        &LayoutArena::new(),
+        positions,
+        graph,
+        heuristic,
+        max_iterations,
+        deterministic,
+        approximate,
+    )
+}
+
+/// `minimize_edge_crossings_with` with neighbor lists and sweep buffers in `arena`
+///
+/// All sweeps share the same key and crossing-count buffers, which only
+/// grow while the largest layer or layer pair is seen.
+pub fn minimize_edge_crossings_in(
+    arena: &LayoutArena,
+    positions: &mut [VertexPosition],
+    graph: &Graph,
+    heuristic: CrossingHeuristic,
+    max_iterations: usize,
+    deterministic: bool,
+    approximate: Option<&ApproximateOrdering>,
+) -> CrossingReduction {
+    arena.scope(|bump| {
+        minimize_crossings_in_bump(bump, positions, graph, heuristic, max_iterations, deterministic, approximate)
+    })
+}
+
+fn minimize_crossings_in_bump(
+    bump: &Bump,
+    positions: &mut [VertexPosition],
+    graph: &Graph,
+    heuristic: CrossingHeuristic,
+    max_iterations: usize,
+    deterministic: bool,
+    approximate: Option<&ApproximateOrdering>,
+) -> CrossingReduction {
+    let n = positions.len();
+    if n == 0 {
+        return CrossingReduction::default();
+    }
+
+    // Layers ordered by current (level, x)
+    let mut layers: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (idx, pos) in positions.iter().enumerate() {
+        layers.entry(pos.layer).or_default().push(idx);
+    }
+    for members in layers.values_mut() {
+        members.sort_by(|&a, &b| {
+            (positions[a].level, positions[a].x)
+                .partial_cmp(&(positions[b].level, positions[b].x))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    // Slots (level, x, y) of each layer in their original order
+    let slots: BTreeMap<i32, ArenaVec<(i32, f32, f32)>> = layers
+        .iter()
+        .map(|(&layer, members)| {
+            let layer_slots = ArenaVec::from_iter_in(
+                members
+                    .iter()
+                    .map(|&i| (positions[i].level, positions[i].x, positions[i].y)),
+                bump,
+            );
+            (layer, layer_slots)
+        })
+        .collect();
+
+    // Edges between different layers as (lower, upper) position indices
+    let mut index_of: ArenaMap<&str, usize> = ArenaMap::with_capacity_in(n, bump);
+    index_of.extend(positions.iter().enumerate().map(|(i, p)| (p.vertex_id.as_str(), i)));
+    let mut layered_edges: ArenaVec<(usize, usize)> = ArenaVec::new_in(bump);
+    for (u, pos) in positions.iter().enumerate() {
+        for target in graph.get_outgoing_edges(&pos.vertex_id).into_iter().flatten() {
+            if let Some(&v) = index_of.get(target.as_str()) {
+                if positions[u].layer < positions[v].layer {
+                    layered_edges.push((u, v));
+                } else if positions[u].layer > positions[v].layer {
+                    layered_edges.push((v, u));
+                }
+            }
+        }
+    }
+    drop(index_of);
+
+    // Neighbors in lower (preds) and higher (succs) layers, by position index;
+    // sized up front so no list is reallocated in the arena
+    let mut in_degree: ArenaVec<usize> = ArenaVec::from_iter_in(std::iter::repeat(0).take(n), bump);
+    let mut out_degree = in_degree.clone();
+    for &(u, v) in &layered_edges {
+        out_degree[u] += 1;
+        in_degree[v] += 1;
+    }
+    let mut preds: ArenaVec<ArenaVec<usize>> =
+        ArenaVec::from_iter_in(in_degree.iter().map(|&d| ArenaVec::with_capacity_in(d, bump)), bump);
+    let mut succs: ArenaVec<ArenaVec<usize>> =
+        ArenaVec::from_iter_in(out_degree.iter().map(|&d| ArenaVec::with_capacity_in(d, bump)), bump);
+    for &(u, v) in &layered_edges {
+        succs[u].push(v);
+        preds[v].push(u);
+    }
+    if deterministic {
+        for neighbors in preds.iter_mut().chain(succs.iter_mut()) {
+            neighbors.sort_unstable();
+        }
+    }
+    let layer_of: ArenaVec<i32> = ArenaVec::from_iter_in(positions.iter().map(|p| p.layer), bump);
+
+    let mut order_pos: ArenaVec<usize> = ArenaVec::from_iter_in(std::iter::repeat(0).take(n), bump);
+    let update_order = |layers: &BTreeMap<i32, Vec<usize>>, order_pos: &mut [usize]| {
+        for members in layers.values() {
+            for (i, &v) in members.iter().enumerate() {
+                order_pos[v] = i;
+            }
+        }
+    };
+    update_order(&layers, &mut order_pos);
+
+    let mut scratch = SweepScratch::new_in(bump);
+    let crossings_before = count_layer_crossings_into(&succs[..], &layer_of, &order_pos, &mut scratch);
+    let mut best = crossings_before;
+    let mut best_layers = layers.clone();
+    let mut iterations = 0;
+
+    let layer_keys: ArenaVec<i32> = ArenaVec::from_iter_in(layers.keys().copied(), bump);
+    let is_huge = |members: &Vec<usize>| {
+        approximate.map_or(false, |a| members.len() > a.layer_threshold)
+    };
+    let approximate_layers = layers.values().filter(|members| is_huge(members)).count();
+    if approximate_layers > 0 {
+        tracing::warn!(
+            "Crossing minimization: {} layers exceed {} vertices, ordering them approximately",
+            approximate_layers,
+            approximate.map_or(0, |a| a.layer_threshold)
+        );
+    }
+    let reorder = |members: &mut [usize],
+                   neighbors: &[ArenaVec<usize>],
+                   order_pos: &mut [usize],
+                   scratch: &mut SweepScratch<'_>| {
+        match approximate {
+            Some(approximate) if members.len() > approximate.layer_threshold => {
+                reorder_layer_approximate(members, neighbors, order_pos, approximate, scratch)
+            }
+            _ => reorder_layer(members, neighbors, order_pos, heuristic, scratch),
+        }
+    };
+    for _ in 0..max_iterations {
+        if best == 0 {
+            break;
+        }
+        iterations += 1;
+
+        // Down sweep: order by predecessors
+        for &layer in layer_keys.iter().skip(1) {
+            reorder(layers.get_mut(&layer).unwrap(), &preds, &mut order_pos, &mut scratch);
+        }
+        // Up sweep: order by successors
+        for &layer in layer_keys.iter().rev().skip(1) {
+            reorder(layers.get_mut(&layer).unwrap(), &succs, &mut order_pos, &mut scratch);
+        }
+
+        let crossings = count_layer_crossings_into(&succs[..], &layer_of, &order_pos, &mut scratch);
+        if crossings < best {
+            best = crossings;
+            best_layers.clone_from(&layers);
+        } else {
+            break;
+        }
+    }
+
+    // Assign original slots in the new order
+    for (layer, members) in &best_layers {
+        let layer_slots = &slots[layer];
+        for (slot, &v) in layer_slots.iter().zip(members.iter()) {
+            positions[v].level = slot.0;
+            positions[v].x = slot.1;
+            positions[v].y = slot.2;
+        }
+    }
+
+    tracing::info!(
+        "Crossing minimization: {} -> {} crossings in {} sweeps",
+        crossings_before,
+        best,
+        iterations
+    );
+
+    CrossingReduction {
+        crossings_before,
+        crossings_after: best,
+        iterations,
+        approximate_layers,
+    }
+}
+
+/// Buffers shared by all sweeps of one crossing minimization run
+struct SweepScratch<'a> {
+    /// (ordering key, vertex) of the layer being reordered
+    keyed: ArenaVec<'a, (f64, usize)>,
+
+    /// Positions of one vertex's fixed neighbors
+    neighbor_positions: ArenaVec<'a, f64>,
+
+    /// Bucket starts of the approximate counting sort
+    bucket_starts: ArenaVec<'a, usize>,
+
+    /// Output of the approximate counting sort
+    sorted: ArenaVec<'a, usize>,
+
+    /// (source layer, target layer, source order, target order) of every edge
+    edges: ArenaVec<'a, (i32, i32, usize, usize)>,
+
+    /// Target orders of one layer pair
+    targets: ArenaVec<'a, usize>,
+
+    /// Fenwick tree of `count_inversions`
+    fenwick: ArenaVec<'a, usize>,
+}
+
+impl<'a> SweepScratch<'a> {
+    fn new_in(bump: &'a Bump) -> Self {
+        Self {
+            keyed: ArenaVec::new_in(bump),
+            neighbor_positions: ArenaVec::new_in(bump),
+            bucket_starts: ArenaVec::new_in(bump),
+            sorted: ArenaVec::new_in(bump),
+            edges: ArenaVec::new_in(bump),
+            targets: ArenaVec::new_in(bump),
+            fenwick: ArenaVec::new_in(bump),
+        }
+    }
+}
+
+/// Stable-sort one layer by the heuristic key of its fixed neighbors
+fn reorder_layer(
+    members: &mut [usize],
+    neighbors: &[ArenaVec<usize>],
+    order_pos: &mut [usize],
+    heuristic: CrossingHeuristic,
+    scratch: &mut SweepScratch,
+) {
+    let SweepScratch { keyed, neighbor_positions, .. } = scratch;
+    keyed.clear();
+    for &v in members.iter() {
+        neighbor_positions.clear();
+        neighbor_positions.extend(neighbors[v].iter().map(|&u| order_pos[u] as f64));
+        let key = if neighbor_positions.is_empty() {
+            // Vertices without fixed neighbors keep their current slot
+            order_pos[v] as f64
+        } else {
+            match heuristic {
+                CrossingHeuristic::Barycenter => {
+                    neighbor_positions.iter().sum::<f64>() / neighbor_positions.len() as f64
+                }
+                CrossingHeuristic::Median => {
+                    neighbor_positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                    let mid = neighbor_positions.len() / 2;
+                    if neighbor_positions.len() % 2 == 1 {
+                        neighbor_positions[mid]
+                    } else {
+                        (neighbor_positions[mid - 1] + neighbor_positions[mid]) / 2.0
+                    }
+                }
+            }
+        };
+        keyed.push((key, v));
+    }
+
+    keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (i, &(_, v)) in keyed.iter().enumerate() {
+        members[i] = v;
+        order_pos[v] = i;
+    }
+}
+
+/// Linear-time reordering of a huge layer by sampled, bucketed barycenter
+fn reorder_layer_approximate(
+    members: &mut [usize],
+    neighbors: &[ArenaVec<usize>],
+    order_pos: &mut [usize],
+    approximate: &ApproximateOrdering,
+    scratch: &mut SweepScratch,
+) {
+    let SweepScratch { keyed, bucket_starts, sorted, .. } = scratch;
+    let sample = approximate.sample.max(1);
+    keyed.clear();
+    keyed.extend(members.iter().map(|&v| {
+        let all = &neighbors[v];
+        if all.is_empty() {
+            return (order_pos[v] as f64, v);
+        }
+        // Evenly strided sample, so hubs cost no more than `sample` lookups
+        let step = all.len().div_ceil(sample);
+        let (sum, count) = all
+            .iter()
+            .step_by(step)
+            .fold((0.0, 0usize), |(sum, count), &u| (sum + order_pos[u] as f64, count + 1));
+        (sum / count as f64, v)
+    }));
+
+    let (min, max) = keyed
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), &(k, _)| (min.min(k), max.max(k)));
+    let buckets = approximate.buckets.max(1);
+    let scale = if max > min { (buckets - 1) as f64 / (max - min) } else { 0.0 };
+    let bucket_of = |key: f64| ((key - min) * scale) as usize;
+
+    // Stable counting sort by bucket
+    bucket_starts.clear();
+    bucket_starts.resize(buckets + 1, 0);
+    for &(key, _) in keyed.iter() {
+        bucket_starts[bucket_of(key) + 1] += 1;
+    }
+    for b in 0..buckets {
+        bucket_starts[b + 1] += bucket_starts[b];
+    }
+    sorted.clear();
+    sorted.resize(members.len(), 0);
+    for &(key, v) in keyed.iter() {
+        let bucket = bucket_of(key);
+        sorted[bucket_starts[bucket]] = v;
+        bucket_starts[bucket] += 1;
+    }
+
+    for (i, &v) in sorted.iter().enumerate() {
+        order_pos[v] = i;
+    }
+    members.copy_from_slice(sorted);
+}
+
+/// Count crossings between edges that connect the same pair of layers
+///
+/// Edges are sorted by (source layer, target layer, source order, target order);
+/// within a group of the same layer pair two edges cross when their target
+/// orders are inverted. Inversions are counted with a Fenwick tree, so the
+/// whole count is O(E log E). Edges sharing an endpoint never count as a
+/// crossing. This is the exact count used by the crossing minimization sweeps
+/// and, through `count_crossings`, by the layout statistics.
+pub fn count_layer_crossings(succs: &[Vec<usize>], layer_of: &[i32], order_pos: &[usize]) -> usize {
+    let bump = Bump::new();
+    count_layer_crossings_into(succs, layer_of, order_pos, &mut SweepScratch::new_in(&bump))
+}
+
+fn count_layer_crossings_into<N: Deref<Target = [usize]>>(
+    succs: &[N],
+    layer_of: &[i32],
+    order_pos: &[usize],
+    scratch: &mut SweepScratch,
+) -> usize {
+    let SweepScratch { edges, targets, fenwick, .. } = scratch;
+    edges.clear();
+    for (u, neighbors) in succs.iter().enumerate() {
+        for &v in neighbors.iter() {
+            edges.push((layer_of[u], layer_of[v], order_pos[u], order_pos[v]));
+        }
+    }
+    edges.sort_unstable();
+
+    let mut crossings = 0;
+    let mut start = 0;
+    while start < edges.len() {
+        let layer_pair = (edges[start].0, edges[start].1);
+        let end = start + edges[start..].iter().take_while(|e| (e.0, e.1) == layer_pair).count();
+        targets.clear();
+        targets.extend(edges[start..end].iter().map(|&(_, _, _, target)| target));
+        let max_target = targets.iter().copied().max().unwrap_or(0);
+        fenwick.clear();
+        fenwick.resize(max_target + 2, 0);
+        crossings += count_inversions(targets, fenwick);
+        start = end;
+    }
+    crossings
+}
+
+/// Number of pairs i < j with values[i] > values[j]
+///
+/// Every value counts the earlier values greater than itself in a Fenwick
+/// tree indexed by value. `tree` must be zeroed and longer than the largest
+/// value by at least 2 (index 0 is unused).
+fn count_inversions(values: &[usize], tree: &mut [usize]) -> usize {
+    let mut inversions = 0;
+    for (seen, &value) in values.iter().enumerate() {
+        // Earlier values not greater than `value`
+        let mut not_greater = 0;
+        let mut i = value + 1;
+        while i > 0 {
+            not_greater += tree[i];
+            i &= i - 1;
+        }
+        inversions += seen - not_greater;
+
+        let mut i = value + 1;
+        while i < tree.len() {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+    inversions
+}
+
 /// Run optimization passes on the layout
 pub async fn optimize_placement(
     positions: &mut Vec<VertexPosition>,
@@ -101,72 +668,716 @@ async fn compact_layout(positions: &mut Vec<VertexPosition>) -> Result<bool> {
     Ok(improved)
 }
 
+/// Shift every layer vertically as a whole
+///
+/// Short layers are otherwise top-aligned, which leaves a jagged outline.
+/// Levels and the spacing inside a layer are kept; only a per-layer offset is
+/// added to y. Layers are processed from left to right, so with
+/// `LayerBalance::Barycenter` a layer follows the already shifted layers on
+/// its left. Every layer stays within the original vertical extent of the
+/// drawing.
+///
+/// Returns the number of layers that moved.
+pub fn balance_layers(positions: &mut [VertexPosition], graph: &Graph, mode: LayerBalance) -> usize {
+    if positions.is_empty() {
+        return 0;
+    }
+
+    let mut layers: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (idx, pos) in positions.iter().enumerate() {
+        layers.entry(pos.layer).or_default().push(idx);
+    }
+    let index_of: HashMap<String, usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(idx, pos)| (pos.vertex_id.clone(), idx))
+        .collect();
+
+    let top = positions.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let bottom = positions.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+    let midline = (top + bottom) / 2.0;
+
+    let mut moved = 0;
+    for (&layer, members) in &layers {
+        let layer_top = members.iter().map(|&i| positions[i].y).fold(f32::INFINITY, f32::min);
+        let layer_bottom = members.iter().map(|&i| positions[i].y).fold(f32::NEG_INFINITY, f32::max);
+        let centered = midline - (layer_top + layer_bottom) / 2.0;
+
+        let delta = match mode {
+            LayerBalance::Midline => centered,
+            LayerBalance::Barycenter => {
+                let (mut sum, mut count) = (0.0f32, 0usize);
+                for &idx in members {
+                    let vertex_id = positions[idx].vertex_id.as_str();
+                    let neighbors = graph
+                        .get_incoming_edges(vertex_id)
+                        .into_iter()
+                        .flatten()
+                        .chain(graph.get_outgoing_edges(vertex_id).into_iter().flatten());
+                    for neighbor in neighbors {
+                        if let Some(&n) = index_of.get(neighbor.as_str()) {
+                            if positions[n].layer != layer {
+                                sum += positions[n].y - positions[idx].y;
+                                count += 1;
+                            }
+                        }
+                    }
+                }
+                if count == 0 { centered } else { sum / count as f32 }
+            }
+        };
+        let delta = delta.max(top - layer_top).min(bottom - layer_bottom);
+
+        if delta.abs() > f32::EPSILON {
+            for &idx in members {
+                positions[idx].y += delta;
+            }
+            moved += 1;
+        }
+    }
+
+    tracing::debug!("Balanced {} of {} layers ({:?})", moved, layers.len(), mode);
+    moved
+}
+
+/// Rectangle of a placed block with its actual size
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Block {
+    fn of(pos: &VertexPosition, config: &PlacementConfig) -> Self {
+        let (width, height) = config.vertex_size(&pos.vertex_id);
+        Self { left: pos.x, top: pos.y, width, height }
+    }
+
+    /// Whether two blocks overlap
+    ///
+    /// Compares the block centers: the blocks overlap when they are closer than
+    /// half their summed widths horizontally and half their summed heights
+    /// vertically (x ± w/2, y ± h/2). Touching blocks do not overlap.
+    fn overlaps(&self, other: &Block) -> bool {
+        let dx = (self.left + self.width / 2.0) - (other.left + other.width / 2.0);
+        let dy = (self.top + self.height / 2.0) - (other.top + other.height / 2.0);
+        dx.abs() < (self.width + other.width) / 2.0 && dy.abs() < (self.height + other.height) / 2.0
+    }
+}
+
+/// Blocks of one layer that stay in place, sorted by top
+#[derive(Debug, Default)]
+struct KeptBlocks {
+    blocks: Vec<Block>,
+    max_height: f32,
+}
+
+impl KeptBlocks {
+    /// Whether `block` overlaps a kept block; only blocks whose top lies
+    /// within the tallest kept height above it are compared
+    fn overlaps(&self, block: &Block) -> bool {
+        let first = self.blocks.partition_point(|b| b.top <= block.top - self.max_height);
+        self.blocks[first..]
+            .iter()
+            .take_while(|b| b.top < block.top + block.height)
+            .any(|b| b.overlaps(block))
+    }
+
+    fn insert(&mut self, block: Block) {
+        let at = self.blocks.partition_point(|b| b.top <= block.top);
+        self.blocks.insert(at, block);
+        self.max_height = self.max_height.max(block.height);
+    }
+}
+
+/// Vertices whose blocks overlap a block of their layer visited before them
+///
+/// Within every layer the blocks are visited by (y, x, ID); a block that
+/// overlaps a kept one is reported (in visiting order) and not kept itself.
+/// Blocks sharing a level but not an area, as with `LevelPacking::Interval`
+/// and `PlacementMode::CompactGrid`, do not overlap. Also returns the kept
+/// blocks of every layer with an overlap.
+fn overlapping_blocks(
+    positions: &[VertexPosition],
+    config: &PlacementConfig,
+) -> (Vec<usize>, HashMap<i32, KeptBlocks>) {
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&positions[a], &positions[b]);
+        a.layer
+            .cmp(&b.layer)
+            .then(a.y.total_cmp(&b.y))
+            .then(a.x.total_cmp(&b.x))
+            .then_with(|| a.vertex_id.cmp(&b.vertex_id))
+    });
+
+    let mut overlapping = Vec::new();
+    let mut layers: HashMap<i32, KeptBlocks> = HashMap::new();
+    let mut kept = KeptBlocks::default();
+    let mut overlap_in_layer = false;
+    for (i, &idx) in order.iter().enumerate() {
+        let pos = &positions[idx];
+        let block = Block::of(pos, config);
+        if kept.overlaps(&block) {
+            overlapping.push(idx);
+            overlap_in_layer = true;
+        } else {
+            // Visited by top, so pushing keeps the order
+            kept.max_height = kept.max_height.max(block.height);
+            kept.blocks.push(block);
+        }
+        let layer_ends = order.get(i + 1).map_or(true, |&next| positions[next].layer != pos.layer);
+        if layer_ends {
+            let blocks = std::mem::take(&mut kept);
+            if std::mem::take(&mut overlap_in_layer) {
+                layers.insert(pos.layer, blocks);
+            }
+        }
+    }
+    (overlapping, layers)
+}
+
+/// Number of vertices whose blocks overlap another block of their layer
+///
+/// Counts every block `repair_overlaps` has to move (see `overlapping_blocks`);
+/// blocks side by side on one level do not count.
+pub fn count_overlaps(positions: &[VertexPosition], config: &PlacementConfig) -> usize {
+    overlapping_blocks(positions, config).0.len()
+}
+
+/// Fail if two blocks of a layer overlap
+pub fn assert_no_overlaps(positions: &[VertexPosition], config: &PlacementConfig) -> Result<()> {
+    let (overlapping, _) = overlapping_blocks(positions, config);
+    if let Some(&idx) = overlapping.first() {
+        let pos = &positions[idx];
+        return Err(anyhow::anyhow!(
+            "Vertex {} overlaps another block at layer {}, level {} ({} overlapping vertices)",
+            pos.vertex_id,
+            pos.layer,
+            pos.level,
+            overlapping.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Move blocks that overlap another block of their layer
+///
+/// Overlaps are geometric (see `overlapping_blocks`), so blocks packed side by
+/// side on one level stay where they are, and only blocks that actually
+/// collide move. Of two overlapping blocks the one visited first keeps its
+/// place; the others move, in visiting order, by whole rows
+/// (`block_height + vertical_gap`) to the nearest level where they overlap
+/// nothing (upwards first on ties). x does not change.
+///
+/// Returns the number of vertices moved.
+pub fn repair_overlaps(positions: &mut [VertexPosition], config: &PlacementConfig) -> usize {
+    let (displaced, mut layers) = overlapping_blocks(positions, config);
+    let row_step = config.block_height + config.vertical_gap;
+
+    for &idx in &displaced {
+        let pos = &mut positions[idx];
+        let kept = layers.get_mut(&pos.layer).expect("layers with overlaps keep their blocks");
+        let block = Block::of(pos, config);
+        let at_level = |level: i32| Block { top: block.top + (level - pos.level) as f32 * row_step, ..block };
+        let level = (0..)
+            .find_map(|distance| {
+                if pos.level >= distance && !kept.overlaps(&at_level(pos.level - distance)) {
+                    return Some(pos.level - distance);
+                }
+                (!kept.overlaps(&at_level(pos.level + distance))).then_some(pos.level + distance)
+            })
+            .expect("a layer has finitely many blocks");
+        kept.insert(at_level(level));
+        pos.y += (level - pos.level) as f32 * row_step;
+        pos.level = level;
+    }
+
+    if !displaced.is_empty() {
+        tracing::warn!("Repaired {} overlapping vertices", displaced.len());
+    }
+    displaced.len()
+}
+
 /// Calculate the number of edge crossings in the layout
+///
+/// Exact count of crossings between edges connecting the same pair of layers
+/// (see `count_crossings`).
 pub fn count_edge_crossings(positions: &[VertexPosition], graph: &Graph) -> usize {
-    let mut crossings = 0;
+    count_crossings(positions.iter().map(|p| (p.vertex_id.as_str(), p.layer, p.y)), graph)
+}
 
-    // Build position map
-    let pos_map: HashMap<&str, &VertexPosition> = positions
-        .iter()
-        .map(|p| (p.vertex_id.as_str(), p))
+/// Exact edge crossing count from (vertex_id, layer, y) of every vertex
+///
+/// Vertices are ranked by y within their layer (ties by ID) and the ranks go
+/// to `count_layer_crossings`: O(V log V + E log E), so it is cheap enough for
+/// the statistics of every layout. Edges to vertices without a position are
+/// skipped.
+pub fn count_crossings<'a>(
+    positions: impl IntoIterator<Item = (&'a str, i32, f32)>,
+    graph: &Graph,
+) -> usize {
+    let mut ranked: Vec<(i32, f32, &str)> = positions
+        .into_iter()
+        .map(|(id, layer, y)| (layer, y, id))
         .collect();
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then_with(|| a.2.cmp(b.2)));
 
-    // Check all pairs of edges
-    let edges: Vec<_> = positions
+    let index: HashMap<&str, usize> = ranked.iter().enumerate().map(|(i, &(_, _, id))| (id, i)).collect();
+    let layer_of: Vec<i32> = ranked.iter().map(|&(layer, _, _)| layer).collect();
+    let mut order_pos = vec![0; ranked.len()];
+    for i in 1..ranked.len() {
+        order_pos[i] = if layer_of[i] == layer_of[i - 1] { order_pos[i - 1] + 1 } else { 0 };
+    }
+    let succs: Vec<Vec<usize>> = ranked
         .iter()
-        .flat_map(|pos| {
+        .map(|&(_, _, id)| {
             graph
-                .get_outgoing_edges(&pos.vertex_id)
+                .get_outgoing_edges(id)
                 .into_iter()
                 .flatten()
-                .filter_map(|target| {
-                    pos_map
-                        .get(target.as_str())
-                        .map(|target_pos| (pos, *target_pos))
-                })
-                .collect::<Vec<_>>()
+                .filter_map(|target| index.get(target.as_str()).copied())
+                .collect()
         })
         .collect();
 
-    for i in 0..edges.len() {
-        for j in (i + 1)..edges.len() {
-            if edges_cross(&edges[i], &edges[j]) {
-                crossings += 1;
+    count_layer_crossings(&succs, &layer_of, &order_pos)
+}
+
+/// Crossings of the routed layout: the total and the count of every edge
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutedCrossings {
+    /// Crossing pairs of edge segments
+    pub total: usize,
+
+    /// Crossings every edge takes part in, keyed by (source_id, target_id);
+    /// edges without crossings have no entry
+    pub per_edge: HashMap<(String, String), usize>,
+}
+
+/// Exact crossing count of the routed layout (see `routed_crossings`)
+pub fn count_routed_crossings<'a>(
+    positions: impl IntoIterator<Item = (&'a str, i32, f32, f32)>,
+    graph: &Graph,
+    edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>,
+    block_width: f32,
+) -> usize {
+    routed_crossings(positions, graph, edge_paths, block_width).total
+}
+
+/// Exact crossings of the routed layout, in total and per edge
+///
+/// Unlike `count_crossings`, edges spanning several layers are split into one
+/// segment per pair of adjacent layers, as with dummy vertices: the height of
+/// an edge in an intermediate layer is the height of its routed path at the
+/// middle of that layer column (the straight line between the endpoints when
+/// the edge has no path), so a long edge crosses the edges and long edges it
+/// passes. `positions` gives (vertex_id, layer, x, y) of every vertex;
+/// `block_width` locates the column middles. Segments between one pair of
+/// layers cross when their heights are inverted, segments sharing an end
+/// never do. O(S log S) for S segments, i.e. the total layer span of all edges.
+pub fn routed_crossings<'a>(
+    positions: impl IntoIterator<Item = (&'a str, i32, f32, f32)>,
+    graph: &Graph,
+    edge_paths: &HashMap<(String, String), Vec<(f32, f32)>>,
+    block_width: f32,
+) -> RoutedCrossings {
+    let pos: HashMap<&str, (i32, f32, f32)> = positions
+        .into_iter()
+        .map(|(id, layer, x, y)| (id, (layer, x, y)))
+        .collect();
+
+    // (left layer, height in the left layer, height in the right layer, edge)
+    let mut edges: Vec<(&str, &String)> = Vec::new();
+    let mut segments: Vec<(i32, f32, f32, usize)> = Vec::new();
+    let mut heights: Vec<f32> = Vec::new();
+    for (&source, &(source_layer, source_x, source_y)) in &pos {
+        for target in graph.get_outgoing_edges(source).into_iter().flatten() {
+            let Some(&(target_layer, target_x, target_y)) = pos.get(target.as_str()) else {
+                continue;
+            };
+            let span = target_layer - source_layer;
+            if span == 0 {
+                continue;
+            }
+            let steps = span.abs();
+            let path = edge_paths.get(&(source.to_string(), target.clone()));
+
+            heights.clear();
+            heights.push(source_y);
+            for i in 1..steps {
+                let t = i as f32 / steps as f32;
+                let x = source_x + (target_x - source_x) * t + block_width / 2.0;
+                heights.push(
+                    path.and_then(|path| path_height_at(path, x))
+                        .unwrap_or(source_y + (target_y - source_y) * t),
+                );
+            }
+            heights.push(target_y);
+
+            let edge = edges.len();
+            edges.push((source, target));
+            let step = span.signum();
+            for (i, pair) in heights.windows(2).enumerate() {
+                let layer = source_layer + step * i as i32;
+                if step > 0 {
+                    segments.push((layer, pair[0], pair[1], edge));
+                } else {
+                    segments.push((layer - 1, pair[1], pair[0], edge));
+                }
             }
         }
     }
+    segments.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.total_cmp(&b.2)));
 
-    crossings
+    let mut total = 0;
+    let mut per_edge = vec![0usize; edges.len()];
+    let mut ranks: Vec<usize> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+    let mut distinct: Vec<f32> = Vec::new();
+    let mut fenwick: Vec<usize> = Vec::new();
+    let mut start = 0;
+    while start < segments.len() {
+        let layer = segments[start].0;
+        let end = start + segments[start..].iter().take_while(|s| s.0 == layer).count();
+        let group = &segments[start..end];
+        distinct.clear();
+        distinct.extend(group.iter().map(|s| s.2));
+        distinct.sort_by(|a, b| a.total_cmp(b));
+        distinct.dedup();
+        ranks.clear();
+        ranks.extend(group.iter().map(|s| distinct.partition_point(|&y| y < s.2)));
+        fenwick.resize(distinct.len() + 2, 0);
+        counts.clear();
+        counts.resize(group.len(), 0);
+        total += count_inversions_each(&ranks, &mut fenwick, &mut counts);
+        for (segment, &count) in group.iter().zip(counts.iter()) {
+            per_edge[segment.3] += count;
+        }
+        start = end;
+    }
+
+    RoutedCrossings {
+        total,
+        per_edge: edges
+            .into_iter()
+            .zip(per_edge)
+            .filter(|&(_, count)| count > 0)
+            .map(|((source, target), count)| ((source.to_string(), target.clone()), count))
+            .collect(),
+    }
 }
 
-/// Check if two edges cross each other
-fn edges_cross(
-    edge1: &(&VertexPosition, &VertexPosition),
-    edge2: &(&VertexPosition, &VertexPosition),
-) -> bool {
-    let (a1, a2) = edge1;
-    let (b1, b2) = edge2;
+/// `count_inversions` with the inversions every value takes part in
+///
+/// `counts[i]` gets the earlier greater plus the later smaller values of
+/// `values[i]`; the return value is the number of inverted pairs. `tree` is
+/// zeroed before each pass and must be longer than the largest value by at
+/// least 2.
+fn count_inversions_each(values: &[usize], tree: &mut [usize], counts: &mut [usize]) -> usize {
+    let mut inversions = 0;
+    tree.fill(0);
+    for (seen, &value) in values.iter().enumerate() {
+        let mut not_greater = 0;
+        let mut i = value + 1;
+        while i > 0 {
+            not_greater += tree[i];
+            i &= i - 1;
+        }
+        counts[seen] = seen - not_greater;
+        inversions += counts[seen];
+
+        let mut i = value + 1;
+        while i < tree.len() {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // Later values smaller than `value`
+    tree.fill(0);
+    for (index, &value) in values.iter().enumerate().rev() {
+        let mut smaller = 0;
+        let mut i = value;
+        while i > 0 {
+            smaller += tree[i];
+            i &= i - 1;
+        }
+        counts[index] += smaller;
+
+        let mut i = value + 1;
+        while i < tree.len() {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+    inversions
+}
+
+/// Height of a polyline at `x` (first segment spanning `x`); None outside it
+fn path_height_at(path: &[(f32, f32)], x: f32) -> Option<f32> {
+    path.windows(2).find_map(|segment| {
+        let ((x1, y1), (x2, y2)) = (segment[0], segment[1]);
+        if x < x1.min(x2) || x > x1.max(x2) {
+            None
+        } else if x1 == x2 {
+            Some(y1)
+        } else {
+            Some(y1 + (y2 - y1) * (x - x1) / (x2 - x1))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::GraphBuilder;
+
+    fn position(id: &str, layer: i32, level: i32) -> VertexPosition {
+        VertexPosition {
+            vertex_id: id.to_string(),
+            x: layer as f32 * 240.0,
+            y: level as f32 * 130.0,
+            layer,
+            level,
+        }
+    }
 
-    // Edges can only cross if they span overlapping layer ranges
-    let a_min_layer = a1.layer.min(a2.layer);
-    let a_max_layer = a1.layer.max(a2.layer);
-    let b_min_layer = b1.layer.min(b2.layer);
-    let b_max_layer = b1.layer.max(b2.layer);
+    #[test]
+    fn test_count_inversions() {
+        assert_eq!(count_inversions(&[3, 1, 2, 0], &mut [0; 5]), 5);
+        // Equal values are not inverted
+        assert_eq!(count_inversions(&[1, 1, 0, 2], &mut [0; 4]), 2);
 
-    if a_max_layer < b_min_layer || b_max_layer < a_min_layer {
-        return false; // No overlap in layers
+        let mut counts = [0; 4];
+        assert_eq!(count_inversions_each(&[3, 1, 2, 0], &mut [0; 5], &mut counts), 5);
+        assert_eq!(counts, [3, 2, 2, 3]);
     }
 
-    // Simple crossing check: if edges are on same layers and Y positions cross
-    if a1.layer == b1.layer && a2.layer == b2.layer {
-        let a_y_increasing = a2.y > a1.y;
-        let b_y_increasing = b2.y > b1.y;
+    #[test]
+    fn test_routed_crossings_count_long_edges() -> Result<()> {
+        // A -> D passes layer 1 below B; C -> E runs from above to below it
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "D".to_string(), 1.0)?;
+        builder.add_edge("C".to_string(), "E".to_string(), 1.0)?;
+        let graph = builder.build()?;
+        let positions = [
+            position("A", 0, 0),
+            position("C", 1, 0),
+            position("D", 2, 0),
+            position("E", 2, 2),
+        ];
+        let tuples = || positions.iter().map(|p| (p.vertex_id.as_str(), p.layer, p.x, p.y));
+
+        // Layer pairs only: A -> D and C -> E never share one
+        assert_eq!(count_crossings(positions.iter().map(|p| (p.vertex_id.as_str(), p.layer, p.y)), &graph), 0);
+
+        // A -> D routed below C in layer 1, then up to D: crosses C -> E
+        let mut paths = HashMap::new();
+        paths.insert(
+            ("A".to_string(), "D".to_string()),
+            vec![(160.0, 40.0), (240.0, 100.0), (400.0, 100.0), (480.0, 40.0)],
+        );
+        assert_eq!(count_routed_crossings(tuples(), &graph, &paths, 160.0), 1);
+        let crossings = routed_crossings(tuples(), &graph, &paths, 160.0);
+        assert_eq!(crossings.per_edge[&("A".to_string(), "D".to_string())], 1);
+        assert_eq!(crossings.per_edge[&("C".to_string(), "E".to_string())], 1);
+
+        // Without a path the straight line stays level with C: shared height, no crossing
+        assert_eq!(count_routed_crossings(tuples(), &graph, &HashMap::new(), 160.0), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_crossings_ranks_by_y() -> Result<()> {
+        // Complete bipartite K(3,3) between two layers: every pair of sources
+        // and pair of targets gives one crossing, 3 * 3 in total
+        let mut builder = GraphBuilder::new();
+        for source in ["A", "B", "C"] {
+            for target in ["D", "E", "F"] {
+                builder.add_edge(source.to_string(), target.to_string(), 1.0)?;
+            }
+        }
+        // A -> G skips layer 1 and is counted against its own layer pair only
+        builder.add_edge("A".to_string(), "G".to_string(), 1.0)?;
+        let graph = builder.build()?;
+
+        // Levels disagree with y: ranks come from the coordinates
+        let mut positions = vec![
+            position("A", 0, 0),
+            position("B", 0, 1),
+            position("C", 0, 2),
+            position("D", 1, 0),
+            position("E", 1, 1),
+            position("F", 1, 2),
+            position("G", 2, 0),
+        ];
+        positions[0].level = 2;
+        positions[2].level = 0;
+        assert_eq!(count_edge_crossings(&positions, &graph), 9);
+
+        let triples = positions.iter().map(|p| (p.vertex_id.as_str(), p.layer, p.y));
+        assert_eq!(count_crossings(triples, &graph), 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimize_removes_simple_crossing() -> Result<()> {
+        // A -> D, B -> C with A above B and C above D: one crossing
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "D".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0)?;
+        let graph = builder.build()?;
+
+        let mut positions = vec![
+            position("A", 0, 0),
+            position("B", 0, 1),
+            position("C", 1, 0),
+            position("D", 1, 1),
+        ];
+
+        for heuristic in [CrossingHeuristic::Barycenter, CrossingHeuristic::Median] {
+            let mut candidate = positions.clone();
+            let result = minimize_edge_crossings(&mut candidate, &graph, heuristic, 10, true);
+            assert_eq!(result.crossings_before, 1);
+            assert_eq!(result.crossings_after, 0);
+
+            let level_of = |id: &str| candidate.iter().find(|p| p.vertex_id == id).unwrap().level;
+            assert!(level_of("D") < level_of("C"));
+        }
+
+        // Without iterations the layout is left untouched
+        let result = minimize_edge_crossings(&mut positions, &graph, CrossingHeuristic::Barycenter, 0, false);
+        assert_eq!(result.crossings_after, 1);
+        Ok(())
+    }
 
-        if a_y_increasing != b_y_increasing {
-            // One edge goes up, the other goes down - potential crossing
-            return (a1.y < b1.y && a2.y > b2.y) || (a1.y > b1.y && a2.y < b2.y);
+    #[test]
+    fn test_approximate_ordering_for_huge_layers() -> Result<()> {
+        // Layer 1 is wired to layer 0 in reverse: every pair of edges crosses
+        let n = 40;
+        let mut builder = GraphBuilder::new();
+        let mut positions = Vec::new();
+        for i in 0..n {
+            let source = format!("S{:02}", i);
+            let target = format!("T{:02}", n - 1 - i);
+            builder.add_edge(source.clone(), target.clone(), 1.0)?;
+            positions.push(position(&source, 0, i as i32));
+        }
+        for i in 0..n {
+            positions.push(position(&format!("T{:02}", i), 1, i as i32));
         }
+        let graph = builder.build()?;
+
+        let approximate = ApproximateOrdering {
+            layer_threshold: n - 1,
+            buckets: 8,
+            sample: 4,
+        };
+        let result = minimize_edge_crossings_with(
+            &mut positions,
+            &graph,
+            CrossingHeuristic::Median,
+            10,
+            true,
+            Some(&approximate),
+        );
+        assert_eq!(result.approximate_layers, 2);
+        assert_eq!(result.crossings_before, n * (n - 1) / 2);
+        // Buckets keep the input order inside them, so only in-bucket pairs may still cross
+        assert!(result.crossings_after <= result.crossings_before / 4);
+        assert_eq!(count_overlaps(&positions, &PlacementConfig::default()), 0);
+
+        // Below the threshold the exact sort is used
+        let small = minimize_edge_crossings(&mut positions, &graph, CrossingHeuristic::Barycenter, 10, true);
+        assert_eq!(small.approximate_layers, 0);
+        assert_eq!(small.crossings_after, 0);
+        Ok(())
     }
 
-    false
+    #[test]
+    fn test_balance_layers() -> Result<()> {
+        // Layer 0 has four vertices, C and D feed the single vertex E of layer 1
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("C".to_string(), "E".to_string(), 1.0)?;
+        builder.add_edge("D".to_string(), "E".to_string(), 1.0)?;
+        builder.add_vertex("A".to_string());
+        builder.add_vertex("B".to_string());
+        let graph = builder.build()?;
+
+        let positions = vec![
+            position("A", 0, 0),
+            position("B", 0, 1),
+            position("C", 0, 2),
+            position("D", 0, 3),
+            position("E", 1, 0),
+        ];
+        let y_of = |positions: &[VertexPosition], id: &str| {
+            positions.iter().find(|p| p.vertex_id == id).unwrap().y
+        };
+
+        let mut midline = positions.clone();
+        assert_eq!(balance_layers(&mut midline, &graph, LayerBalance::Midline), 1);
+        assert_eq!(y_of(&midline, "A"), 0.0);
+        assert_eq!(y_of(&midline, "E"), 1.5 * 130.0);
+
+        let mut barycenter = positions.clone();
+        assert_eq!(balance_layers(&mut barycenter, &graph, LayerBalance::Barycenter), 1);
+        assert_eq!(y_of(&barycenter, "E"), 2.5 * 130.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlaps_are_detected_and_repaired() {
+        // B and C were written into A's slot, D into the slot of E in another layer
+        let mut positions = vec![
+            position("A", 0, 1),
+            position("C", 0, 1),
+            position("B", 0, 1),
+            position("E", 1, 0),
+            position("D", 1, 0),
+        ];
+        let config = PlacementConfig::default();
+        assert_eq!(count_overlaps(&positions, &config), 3);
+        assert!(assert_no_overlaps(&positions, &config).is_err());
+
+        assert_eq!(repair_overlaps(&mut positions, &config), 3);
+        assert_eq!(count_overlaps(&positions, &config), 0);
+        assert!(assert_no_overlaps(&positions, &config).is_ok());
+
+        // A keeps the slot, B and C take the nearest free levels (upwards first)
+        let slot_of = |id: &str| {
+            let p = positions.iter().find(|p| p.vertex_id == id).unwrap();
+            (p.layer, p.level, p.y)
+        };
+        assert_eq!(slot_of("A"), (0, 1, 130.0));
+        assert_eq!(slot_of("B"), (0, 0, 0.0));
+        assert_eq!(slot_of("C"), (0, 2, 260.0));
+        assert_eq!(slot_of("D"), (1, 0, 0.0));
+        assert_eq!(slot_of("E").1, 1);
+
+        assert_eq!(repair_overlaps(&mut positions, &config), 0);
+    }
+
+    #[test]
+    fn test_packed_blocks_sharing_a_level_are_kept() {
+        // Interval packing: two narrow blocks side by side on level 0
+        let mut config = PlacementConfig::default();
+        config.vertex_sizes.insert("A".to_string(), (40.0, 80.0));
+        config.vertex_sizes.insert("B".to_string(), (40.0, 80.0));
+        let mut positions = vec![position("A", 0, 0), position("B", 0, 0), position("C", 0, 1)];
+        positions[1].x += 120.0;
+        assert_eq!(count_overlaps(&positions, &config), 0);
+        assert_eq!(repair_overlaps(&mut positions, &config), 0);
+
+        // C shifted half a row up collides with both and is the only one to move
+        positions[2].y -= 65.0;
+        positions[2].x += 20.0;
+        assert_eq!(count_overlaps(&positions, &config), 1);
+        assert_eq!(repair_overlaps(&mut positions, &config), 1);
+        assert_eq!((positions[0].y, positions[1].y), (0.0, 0.0));
+        assert_eq!((positions[2].level, positions[2].y), (2, 195.0));
+    }
 }
@@ -3,7 +3,11 @@
 /// This module handles the placement of vertices at specific (x, y) coordinates
 /// within their assigned layers.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::data_structures::{Graph, VertexId};
+use crate::messages::Msg;
+use super::layer_assignment::{LayerMap, LayeringStrategy};
 
 /// Represents a single vertex's position in the layout
 #[derive(Debug, Clone)]
@@ -29,6 +33,56 @@ pub struct PlacementConfig {
 
     /// Vertical gap between levels (pixels)
     pub vertical_gap: f32,
+
+    /// Overall shape of each layer (column, square grid, neighbor-following)
+    pub placement_mode: PlacementMode,
+
+    /// How vertices are distributed over levels within a layer
+    /// (used by `PlacementMode::Column` and `PlacementMode::CentroidSorted`)
+    pub level_packing: LevelPacking,
+
+    /// How vertices sharing a layer are ordered before level assignment
+    pub tie_break: TieBreak,
+
+    /// How vertices are assigned to layers
+    pub layering: LayeringStrategy,
+
+    /// Maximum number of vertices per layer (0 = unlimited)
+    pub max_layer_width: usize,
+
+    /// Vertices pinned to fixed layers; with pins or lower bounds the layers
+    /// are assigned by `assign_layers_bfs_with_lower_bounds` whatever `layering` says
+    pub layer_constraints: HashMap<String, i32>,
+
+    /// Minimal layers of vertices (e.g. from a previous run)
+    pub layer_lower_bounds: HashMap<String, i32>,
+
+    /// Split edges spanning several layers into chains of dummy vertices
+    /// (see `dummy_vertices`); off routes edges with `edge_routing`
+    pub dummy_vertices: bool,
+
+    /// Upper bound on the number of dummy vertices; a graph needing more is
+    /// routed with `edge_routing` instead
+    pub max_dummy_vertices: usize,
+
+    /// Per-vertex block sizes `(width, height)`, e.g. for labels of different
+    /// lengths; vertices without an entry are `block_width × block_height`
+    pub vertex_sizes: HashMap<String, (f32, f32)>,
+
+    /// Fixed `(x, y)` coordinates of manually positioned vertices; placement
+    /// keeps them and moves other vertices out of their way
+    /// (see `apply_vertex_anchors`)
+    pub anchors: HashMap<String, (f32, f32)>,
+}
+
+impl PlacementConfig {
+    /// Block size of a vertex
+    pub fn vertex_size(&self, vertex_id: &str) -> (f32, f32) {
+        self.vertex_sizes
+            .get(vertex_id)
+            .copied()
+            .unwrap_or((self.block_width, self.block_height))
+    }
 }
 
 impl Default for PlacementConfig {
@@ -38,6 +92,127 @@ impl Default for PlacementConfig {
             block_height: 80.0,
             horizontal_gap: 80.0,
             vertical_gap: 50.0,
+            placement_mode: PlacementMode::Column,
+            level_packing: LevelPacking::OnePerLevel,
+            tie_break: TieBreak::VertexId,
+            layering: LayeringStrategy::Bfs,
+            max_layer_width: 0,
+            layer_constraints: HashMap::new(),
+            layer_lower_bounds: HashMap::new(),
+            dummy_vertices: false,
+            max_dummy_vertices: 1_000_000,
+            vertex_sizes: HashMap::new(),
+            anchors: HashMap::new(),
+        }
+    }
+}
+
+/// Shape in which the vertices of a layer are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementMode {
+    /// One column per layer: narrow and tall
+    Column,
+
+    /// Each layer is a near-square grid of `ceil(sqrt(n))` sub-columns; wider
+    /// layers push the following layers to the right
+    CompactGrid,
+
+    /// Column whose order follows the centroid of each vertex's predecessors
+    /// (see `centroid_sort_layers`; needs the graph, so without it the result
+    /// equals `Column`)
+    CentroidSorted,
+}
+
+impl Default for PlacementMode {
+    fn default() -> Self {
+        PlacementMode::Column
+    }
+}
+
+/// Strategy for assigning levels to vertices within a single layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelPacking {
+    /// Every vertex gets its own level (classic tall column)
+    OnePerLevel,
+
+    /// Interval packing: vertices narrower than the layer column share a
+    /// level side by side while their real widths fit into the column
+    /// (first-fit, see `place_vertices_in_layer_packed`)
+    Interval,
+}
+
+impl Default for LevelPacking {
+    fn default() -> Self {
+        LevelPacking::OnePerLevel
+    }
+}
+
+/// Rule for ordering vertices that share a layer
+///
+/// Layer members are collected from a `HashMap`, so without an explicit rule
+/// their order (and therefore their levels) would change from run to run.
+/// The chosen rule fixes the initial order used by level assignment; crossing
+/// minimization then reorders with stable sorts, so remaining ties keep this
+/// order too. Every rule falls back to the vertex ID, which makes the result
+/// fully reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Lexicographic order of vertex IDs
+    VertexId,
+
+    /// Higher total degree (in + out) first, then vertex ID
+    Degree,
+
+    /// Order of first appearance in the input edges, then vertex ID
+    InputOrder,
+}
+
+impl Default for TieBreak {
+    fn default() -> Self {
+        TieBreak::VertexId
+    }
+}
+
+/// Per-vertex sort keys for `TieBreak::Degree` / `TieBreak::InputOrder`
+///
+/// Smaller keys come first; vertices without a key go after all keyed ones.
+pub type TieBreakKeys = HashMap<String, i64>;
+
+/// Keys that put high-degree vertices first
+pub fn degree_keys(graph: &Graph) -> TieBreakKeys {
+    graph
+        .vertices()
+        .map(|vertex_id| {
+            let degree = graph.get_outgoing_edges(vertex_id).map_or(0, |edges| edges.count())
+                + graph.get_incoming_edges(vertex_id).map_or(0, |edges| edges.count());
+            (vertex_id.clone(), -(degree as i64))
+        })
+        .collect()
+}
+
+/// Keys that preserve the order in which vertices were first seen
+pub fn input_order_keys<'a, I>(vertex_ids: I) -> TieBreakKeys
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut keys = TieBreakKeys::new();
+    for vertex_id in vertex_ids {
+        let next = keys.len() as i64;
+        keys.entry(vertex_id.clone()).or_insert(next);
+    }
+    keys
+}
+
+/// Sort the members of one layer according to the tie-breaking rule
+pub fn order_layer_members(vertex_ids: &mut [String], tie_break: TieBreak, keys: &TieBreakKeys) {
+    match tie_break {
+        TieBreak::VertexId => vertex_ids.sort(),
+        TieBreak::Degree | TieBreak::InputOrder => {
+            vertex_ids.sort_by(|a, b| {
+                let key_a = keys.get(a).copied().unwrap_or(i64::MAX);
+                let key_b = keys.get(b).copied().unwrap_or(i64::MAX);
+                key_a.cmp(&key_b).then_with(|| a.cmp(b))
+            });
         }
     }
 }
@@ -72,64 +247,549 @@ pub fn place_vertices_in_layer(
     positions
 }
 
+/// Place vertices of a layer using interval packing
+///
+/// Each vertex is an interval `[offset, offset + width]` across the layer
+/// column, whose capacity is the widest block of the layer (at least
+/// `config.block_width`). Vertices are taken in the given order and put on the
+/// first level that still has room (first-fit), so a narrow vertex fills the
+/// gap left on an earlier level instead of opening a new one; a level only
+/// opens when no existing level fits. Members of a level keep the given order
+/// from left to right, so the ordering of earlier passes (e.g. crossing
+/// minimization) survives within levels.
+///
+/// Widths come from `widths`, then from `config.vertex_sizes`, otherwise the
+/// block is full-width; they are not clamped, so a block wider than
+/// `block_width` widens the column. Every level is as tall as its tallest
+/// member (`config.vertex_size`), so with uniform sizes the result is
+/// identical to `place_vertices_in_layer`.
+pub fn place_vertices_in_layer_packed(
+    layer: i32,
+    vertex_ids: &[String],
+    widths: &HashMap<String, f32>,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    let layer_x = layer as f32 * (config.block_width + config.horizontal_gap);
+    let sizes: Vec<(f32, f32)> = vertex_ids
+        .iter()
+        .map(|vertex_id| {
+            let (width, height) = config.vertex_size(vertex_id);
+            (widths.get(vertex_id).copied().unwrap_or(width).max(0.0), height)
+        })
+        .collect();
+    let column_width = sizes.iter().map(|&(width, _)| width).fold(config.block_width, f32::max);
+
+    // Used width and height of every open level; a new level starts one gap
+    // to the left, so its first block lands at offset 0
+    let mut levels: Vec<(f32, f32)> = Vec::new();
+    let mut slots = Vec::with_capacity(vertex_ids.len());
+    for &(width, height) in &sizes {
+        // Horizontal gap is only needed between neighbours sharing a level
+        let fits = |&(used, _): &(f32, f32)| used + config.horizontal_gap + width <= column_width;
+        let level = match levels.iter().position(fits) {
+            Some(level) => level,
+            None => {
+                levels.push((-config.horizontal_gap, 0.0));
+                levels.len() - 1
+            }
+        };
+        let (used, level_height) = &mut levels[level];
+        let offset = *used + config.horizontal_gap;
+        *used = offset + width;
+        *level_height = level_height.max(height);
+        slots.push((level, offset));
+    }
+
+    // Levels stack by their own heights
+    let mut level_y = Vec::with_capacity(levels.len());
+    let mut y = 0.0f32;
+    for &(_, height) in &levels {
+        level_y.push(y);
+        y += height + config.vertical_gap;
+    }
+
+    vertex_ids
+        .iter()
+        .zip(slots)
+        .map(|(vertex_id, (level, offset))| VertexPosition {
+            vertex_id: vertex_id.clone(),
+            x: layer_x + offset,
+            y: level_y[level],
+            layer,
+            level: level as i32,
+        })
+        .collect()
+}
+
+/// Place vertices of a layer as a near-square grid
+///
+/// Vertices fill rows of `ceil(sqrt(n))` sub-columns in the given order; the
+/// row becomes the level. x offsets inside a row assume uniform blocks, the
+/// final offsets come from `apply_vertex_sizes`.
+pub fn place_vertices_in_layer_grid(
+    layer: i32,
+    vertex_ids: &[String],
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    let columns = (vertex_ids.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let layer_x = layer as f32 * (config.block_width + config.horizontal_gap);
+
+    vertex_ids
+        .iter()
+        .enumerate()
+        .map(|(i, vertex_id)| {
+            let level = (i / columns) as i32;
+            VertexPosition {
+                vertex_id: vertex_id.clone(),
+                x: layer_x + (i % columns) as f32 * (config.block_width + config.horizontal_gap),
+                y: level as f32 * (config.block_height + config.vertical_gap),
+                layer,
+                level,
+            }
+        })
+        .collect()
+}
+
 /// Place all vertices based on their layer assignments
 ///
-/// Takes a HashMap of layer assignments and produces a Vec of VertexPosition
+/// Takes a HashMap of layer assignments and produces a Vec of VertexPosition.
+/// Layers here are keyed by UID, as the streaming `GlobalLayerState` keeps
+/// them; layers of a built graph go through `place_graph_vertices`.
 pub fn place_all_vertices(
     layer_map: &HashMap<String, i32>,
     config: &PlacementConfig,
 ) -> Vec<VertexPosition> {
-    // Group vertices by layer
-    let mut layer_assignments: HashMap<i32, Vec<String>> = HashMap::new();
+    place_all_vertices_with_widths(layer_map, &HashMap::new(), config)
+}
 
-    for (vertex_id, &layer) in layer_map {
-        layer_assignments
-            .entry(layer)
-            .or_insert_with(Vec::new)
-            .push(vertex_id.clone());
+/// Place all vertices taking per-vertex block widths into account
+///
+/// Widths are only used when `config.level_packing` is `LevelPacking::Interval`.
+pub fn place_all_vertices_with_widths(
+    layer_map: &HashMap<String, i32>,
+    widths: &HashMap<String, f32>,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    place_all_vertices_ordered(layer_map, widths, &TieBreakKeys::new(), config)
+}
+
+/// Place all vertices, ordering each layer by `config.tie_break`
+///
+/// `keys` are only consulted for `TieBreak::Degree` and `TieBreak::InputOrder`;
+/// see `degree_keys` and `input_order_keys`.
+pub fn place_all_vertices_ordered(
+    layer_map: &HashMap<String, i32>,
+    widths: &HashMap<String, f32>,
+    keys: &TieBreakKeys,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    place_layers(layer_map, widths, keys, config)
+}
+
+/// Place the vertices of a built graph from its handle-keyed layers
+///
+/// Layers are grouped and ordered by handle; UIDs are only resolved for the
+/// returned positions. For the same layers keyed by UID the result equals
+/// `place_all_vertices_ordered`.
+pub fn place_graph_vertices(
+    layer_map: &LayerMap,
+    graph: &Graph,
+    keys: &TieBreakKeys,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    place_graph_layers(layer_map, graph, keys, false, config)
+}
+
+/// Place all vertices, ordering each layer by the centroid of its neighbors
+///
+/// Layers are placed from left to right. Within a layer, vertices are first
+/// ordered by `config.tie_break`, then stably sorted by the mean y of their
+/// neighbors in the layers already placed (`calculate_neighbor_centroid`);
+/// vertices without such neighbors keep the y of their tie-break slot.
+/// Equal keys put the vertex with the higher degree first, so hubs take the
+/// upper slot.
+pub fn place_all_vertices_centroid(
+    layer_map: &LayerMap,
+    graph: &Graph,
+    keys: &TieBreakKeys,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    place_graph_layers(layer_map, graph, keys, true, config)
+}
+
+/// Mean y of a vertex's neighbors (both directions) among the placed vertices
+///
+/// `placed` holds the y of every placed vertex at its handle index. Returns
+/// `None` when none of the neighbors has been placed yet.
+pub fn calculate_neighbor_centroid(
+    vertex: VertexId,
+    graph: &Graph,
+    placed: &[Option<f32>],
+) -> Option<f32> {
+    let (sum, count) = graph
+        .predecessors(vertex)
+        .chain(graph.successors(vertex))
+        .filter_map(|neighbor| placed[neighbor.index()])
+        .fold((0.0f32, 0usize), |(sum, count), y| (sum + y, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// `order_layer_members` for the handles of `graph`
+fn order_layer_handles(members: &mut [VertexId], graph: &Graph, tie_break: TieBreak, keys: &TieBreakKeys) {
+    let uid = |vertex: VertexId| graph.vertex_uid(vertex);
+    match tie_break {
+        TieBreak::VertexId => members.sort_by_key(|&vertex| uid(vertex)),
+        TieBreak::Degree | TieBreak::InputOrder => members.sort_by_key(|&vertex| {
+            (keys.get(uid(vertex)).copied().unwrap_or(i64::MAX), uid(vertex))
+        }),
+    }
+}
+
+/// Order a layer by neighbor centroid on top of its tie-break order
+fn order_layer_by_centroid(
+    members: &mut Vec<VertexId>,
+    graph: &Graph,
+    placed: &[Option<f32>],
+    config: &PlacementConfig,
+) {
+    let row = config.block_height + config.vertical_gap;
+    let mut keyed: Vec<(f32, usize, VertexId)> = members
+        .drain(..)
+        .enumerate()
+        .map(|(slot, vertex)| {
+            let key = calculate_neighbor_centroid(vertex, graph, placed)
+                .unwrap_or(slot as f32 * row);
+            let degree = graph.predecessors(vertex).len() + graph.successors(vertex).len();
+            (key, degree, vertex)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+    members.extend(keyed.into_iter().map(|(_, _, vertex)| vertex));
+}
+
+/// Positions of one layer whose members are already ordered
+fn place_layer(
+    layer: i32,
+    vertex_ids: &[String],
+    widths: &HashMap<String, f32>,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    match (config.placement_mode, config.level_packing) {
+        (PlacementMode::CompactGrid, _) => place_vertices_in_layer_grid(layer, vertex_ids, config),
+        (_, LevelPacking::OnePerLevel) => place_vertices_in_layer(layer, vertex_ids, config),
+        (_, LevelPacking::Interval) => place_vertices_in_layer_packed(layer, vertex_ids, widths, config),
     }
+}
+
+/// Per-vertex sizes and the summary shared by all placement entry points
+fn finish_placement(positions: &mut [VertexPosition], max_layer: i32, config: &PlacementConfig) {
+    // Grid layers are wider than one block; the offsets of the following
+    // layers are recomputed together with per-vertex sizes
+    if !config.vertex_sizes.is_empty() || config.placement_mode == PlacementMode::CompactGrid {
+        apply_vertex_sizes(positions, config);
+    }
+
+    tracing::info!("{}", Msg::VerticesPlaced { vertices: positions.len(), layers: max_layer + 1 });
+}
 
-    // Sort layers for consistent ordering
-    let mut sorted_layers: Vec<_> = layer_assignments.into_iter().collect();
-    sorted_layers.sort_by_key(|(layer, _)| *layer);
+fn place_layers(
+    layer_map: &HashMap<String, i32>,
+    widths: &HashMap<String, f32>,
+    keys: &TieBreakKeys,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    // Group vertices by layer; BTreeMap keeps the layers sorted
+    let mut layer_assignments: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+    for (vertex_id, &layer) in layer_map {
+        layer_assignments.entry(layer).or_default().push(vertex_id.clone());
+    }
 
     // Place vertices in each layer
-    let mut all_positions = Vec::new();
+    let mut all_positions = Vec::with_capacity(layer_map.len());
+    for (layer, mut vertex_ids) in layer_assignments {
+        order_layer_members(&mut vertex_ids, config.tie_break, keys);
+        all_positions.extend(place_layer(layer, &vertex_ids, widths, config));
+    }
 
-    for (layer, vertex_ids) in sorted_layers {
-        let positions = place_vertices_in_layer(layer, &vertex_ids, config);
-        all_positions.extend(positions);
+    finish_placement(&mut all_positions, layer_map.values().copied().max().unwrap_or(0), config);
+    all_positions
+}
+
+fn place_graph_layers(
+    layer_map: &LayerMap,
+    graph: &Graph,
+    keys: &TieBreakKeys,
+    centroid: bool,
+    config: &PlacementConfig,
+) -> Vec<VertexPosition> {
+    let mut layer_assignments: BTreeMap<i32, Vec<VertexId>> = BTreeMap::new();
+    for (&vertex, &layer) in layer_map {
+        layer_assignments.entry(layer).or_default().push(vertex);
     }
 
-    tracing::info!(
-        "Placed {} vertices across {} layers",
-        all_positions.len(),
-        layer_map.values().max().unwrap_or(&0) + 1
-    );
+    let mut all_positions = Vec::with_capacity(layer_map.len());
+    // y of the placed vertices by handle index, only needed for the centroid order
+    let mut placed_y: Vec<Option<f32>> = if centroid { vec![None; graph.vertex_count()] } else { Vec::new() };
+    let widths = HashMap::new();
 
+    for (layer, mut members) in layer_assignments {
+        order_layer_handles(&mut members, graph, config.tie_break, keys);
+        if centroid {
+            order_layer_by_centroid(&mut members, graph, &placed_y, config);
+        }
+        let vertex_ids: Vec<String> = members.iter().map(|&vertex| graph.vertex_uid(vertex).to_string()).collect();
+        let positions = place_layer(layer, &vertex_ids, &widths, config);
+        if centroid {
+            for (vertex, position) in members.iter().zip(&positions) {
+                placed_y[vertex.index()] = Some(position.y);
+            }
+        }
+        all_positions.extend(positions);
+    }
+
+    finish_placement(&mut all_positions, layer_map.values().copied().max().unwrap_or(0), config);
     all_positions
 }
 
-/// Track occupied positions to avoid overlaps
+/// Reorder every layer by the centroid of its vertices' predecessors
+///
+/// Layers are processed from left to right, so each layer follows the already
+/// reordered layers before it. The key of a vertex is the mean level of its
+/// predecessors in lower layers; vertices without such predecessors keep their
+/// current level as the key. Members then take the layer's existing slots
+/// (level, x, y) in key order, so the shape of the layer does not change.
+///
+/// Returns the number of layers whose order changed.
+pub fn centroid_sort_layers(positions: &mut [VertexPosition], graph: &Graph) -> usize {
+    let mut layers: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (idx, pos) in positions.iter().enumerate() {
+        layers.entry(pos.layer).or_default().push(idx);
+    }
+    let index_of: HashMap<String, usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(idx, pos)| (pos.vertex_id.clone(), idx))
+        .collect();
+
+    let mut reordered = 0;
+    for members in layers.values_mut() {
+        members.sort_by(|&a, &b| {
+            (positions[a].level, positions[a].x)
+                .partial_cmp(&(positions[b].level, positions[b].x))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let slots: Vec<(i32, f32, f32)> = members
+            .iter()
+            .map(|&i| (positions[i].level, positions[i].x, positions[i].y))
+            .collect();
+
+        let keys: HashMap<usize, f32> = members
+            .iter()
+            .map(|&idx| {
+                let layer = positions[idx].layer;
+                let levels: Vec<f32> = graph
+                    .get_incoming_edges(&positions[idx].vertex_id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|pred| index_of.get(pred.as_str()))
+                    .filter(|&&p| positions[p].layer < layer)
+                    .map(|&p| positions[p].level as f32)
+                    .collect();
+                let key = if levels.is_empty() {
+                    positions[idx].level as f32
+                } else {
+                    levels.iter().sum::<f32>() / levels.len() as f32
+                };
+                (idx, key)
+            })
+            .collect();
+
+        let before = members.clone();
+        members.sort_by(|&a, &b| keys[&a].total_cmp(&keys[&b]));
+        if *members != before {
+            reordered += 1;
+        }
+        for (&(level, x, y), &idx) in slots.iter().zip(members.iter()) {
+            positions[idx].level = level;
+            positions[idx].x = x;
+            positions[idx].y = y;
+        }
+    }
+
+    tracing::info!("{}", Msg::CentroidReordered { changed: reordered, layers: layers.len() });
+    reordered
+}
+
+/// Recompute x/y from layers and levels using per-vertex block sizes
+///
+/// Layers and levels are kept; only the coordinates change. Every layer
+/// column is as wide as its widest level, and every level is as tall as its
+/// tallest vertex, so blocks never overlap. Vertices sharing a level (interval
+/// packing) are laid out side by side in their current x order.
+///
+/// Offsets are accumulated as corrections to the uniform grid: a layer or level
+/// that is missing or holds only default-sized blocks keeps its uniform
+/// extent, so with no sizes the result equals the uniform placement.
+pub fn apply_vertex_sizes(positions: &mut [VertexPosition], config: &PlacementConfig) {
+    // layer -> level -> members in x order
+    let mut rows: BTreeMap<i32, BTreeMap<i32, Vec<usize>>> = BTreeMap::new();
+    for (idx, pos) in positions.iter().enumerate() {
+        rows.entry(pos.layer).or_default().entry(pos.level).or_default().push(idx);
+    }
+
+    let column_step = config.block_width + config.horizontal_gap;
+    let row_step = config.block_height + config.vertical_gap;
+
+    // Extra width accumulated by wider layers to the left
+    let mut x_shift = 0.0f32;
+    for (&layer, levels) in &mut rows {
+        let layer_x = layer as f32 * column_step + x_shift;
+        let mut column_width = config.block_width;
+
+        // Extra height accumulated by taller levels above
+        let mut y_shift = 0.0f32;
+        for (&level, members) in levels.iter_mut() {
+            members.sort_by(|&a, &b| positions[a].x.total_cmp(&positions[b].x));
+
+            let y = level as f32 * row_step + y_shift;
+            let mut offset = 0.0f32;
+            let mut row_height = 0.0f32;
+            for &idx in members.iter() {
+                let (width, height) = config.vertex_size(&positions[idx].vertex_id);
+                positions[idx].x = layer_x + offset;
+                positions[idx].y = y;
+                offset += width + config.horizontal_gap;
+                row_height = row_height.max(height);
+            }
+
+            column_width = column_width.max(offset - config.horizontal_gap);
+            y_shift += row_height - config.block_height;
+        }
+
+        if layer >= 0 {
+            x_shift += column_width - config.block_width;
+        }
+    }
+}
+
+/// Move anchored vertices to their fixed coordinates and level-solve the rest
+///
+/// An anchored vertex keeps its layer, takes `(x, y)` from `config.anchors`
+/// and the level nearest to `y`. Unanchored vertices of the same layer on an
+/// anchored level move, in (level, ID) order, to the nearest free level; their
+/// y shifts by whole rows (`block_height + vertical_gap`). Anchors of vertices
+/// missing from `positions` are ignored.
+///
+/// Returns the number of anchored vertices.
+pub fn apply_vertex_anchors(positions: &mut [VertexPosition], config: &PlacementConfig) -> usize {
+    if config.anchors.is_empty() {
+        return 0;
+    }
+
+    let row_step = config.block_height + config.vertical_gap;
+    let mut anchored_slots = OccupiedPositions::default();
+    let mut anchored = 0;
+    for pos in positions.iter_mut() {
+        if let Some(&(x, y)) = config.anchors.get(&pos.vertex_id) {
+            pos.x = x;
+            pos.y = y;
+            pos.level = (y / row_step).round().max(0.0) as i32;
+            if anchored_slots.is_occupied(pos.layer, pos.level) {
+                tracing::warn!("{}", Msg::AnchorsOverlap {
+                    vertex: &pos.vertex_id,
+                    layer: pos.layer,
+                    level: pos.level,
+                });
+            }
+            anchored_slots.mark_occupied(pos.layer, pos.level);
+            anchored += 1;
+        }
+    }
+
+    let mut order: Vec<usize> = (0..positions.len())
+        .filter(|&idx| !config.anchors.contains_key(&positions[idx].vertex_id))
+        .collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&positions[a], &positions[b]);
+        (a.layer, a.level, &a.vertex_id).cmp(&(b.layer, b.level, &b.vertex_id))
+    });
+
+    // Unanchored vertices keep their slots unless an anchor took them
+    let mut occupancy = anchored_slots.clone();
+    let mut displaced = Vec::new();
+    for idx in order {
+        let pos = &positions[idx];
+        if anchored_slots.is_occupied(pos.layer, pos.level) {
+            displaced.push(idx);
+        } else {
+            occupancy.mark_occupied(pos.layer, pos.level);
+        }
+    }
+    for &idx in &displaced {
+        let pos = &mut positions[idx];
+        let level = occupancy.nearest_free_level(pos.layer, pos.level);
+        occupancy.mark_occupied(pos.layer, level);
+        pos.y += (level - pos.level) as f32 * row_step;
+        pos.level = level;
+    }
+
+    tracing::debug!("Anchored {} vertices, moved {} vertices around them", anchored, displaced.len());
+    anchored
+}
+
+/// Occupancy grid of a layout: occupied levels of every layer
+///
+/// Each layer keeps a bitmap of its levels (bit `level % 64` of word
+/// `level / 64`), so the grid costs one bit per slot up to the deepest level of
+/// the layer. It is returned with the layout result, which lets annotation
+/// tools find free space near a vertex without rebuilding the grid.
+///
+/// Levels are non-negative; negative levels are never occupied.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OccupiedPositions {
-    occupied: HashSet<(i32, i32)>,
+    layers: BTreeMap<i32, Vec<u64>>,
 }
 
 impl OccupiedPositions {
     pub fn new() -> Self {
-        Self {
-            occupied: HashSet::new(),
+        Self::default()
+    }
+
+    /// Grid with the given `(layer, level)` slots occupied
+    pub fn from_slots<I>(slots: I) -> Self
+    where
+        I: IntoIterator<Item = (i32, i32)>,
+    {
+        let mut occupied = Self::new();
+        for (layer, level) in slots {
+            occupied.mark_occupied(layer, level);
         }
+        occupied
     }
 
     /// Check if a position is occupied
     pub fn is_occupied(&self, layer: i32, level: i32) -> bool {
-        self.occupied.contains(&(layer, level))
+        if level < 0 {
+            return false;
+        }
+        let (word, bit) = (level as usize / 64, level as usize % 64);
+        self.layers
+            .get(&layer)
+            .and_then(|bitmap| bitmap.get(word))
+            .map_or(false, |bits| bits & (1 << bit) != 0)
     }
 
     /// Mark a position as occupied
     pub fn mark_occupied(&mut self, layer: i32, level: i32) {
-        self.occupied.insert((layer, level));
+        if level < 0 {
+            return;
+        }
+        let (word, bit) = (level as usize / 64, level as usize % 64);
+        let bitmap = self.layers.entry(layer).or_default();
+        if bitmap.len() <= word {
+            bitmap.resize(word + 1, 0);
+        }
+        bitmap[word] |= 1 << bit;
     }
 
     /// Find the next available level in a given layer
@@ -140,15 +800,70 @@ impl OccupiedPositions {
         }
         level
     }
-}
 
-impl Default for OccupiedPositions {
-    fn default() -> Self {
-        Self::new()
+    /// Nearest free level to `level` in `layer` (upwards first on ties)
+    pub fn nearest_free_level(&self, layer: i32, level: i32) -> i32 {
+        let level = level.max(0);
+        for distance in 0.. {
+            if level >= distance && !self.is_occupied(layer, level - distance) {
+                return level - distance;
+            }
+            if !self.is_occupied(layer, level + distance) {
+                return level + distance;
+            }
+        }
+        unreachable!("a layer has finitely many occupied levels")
+    }
+
+    /// Free slots within `radius` layers and levels of `(layer, level)`,
+    /// closest first (Chebyshev distance, then layer, then level)
+    pub fn free_slots_near(&self, layer: i32, level: i32, radius: i32) -> Vec<(i32, i32)> {
+        let mut slots: Vec<(i32, i32)> = (layer - radius..=layer + radius)
+            .flat_map(|l| ((level - radius).max(0)..=level + radius).map(move |v| (l, v)))
+            .filter(|&(l, v)| !self.is_occupied(l, v))
+            .collect();
+        slots.sort_by_key(|&(l, v)| ((l - layer).abs().max((v - level).abs()), l, v));
+        slots
+    }
+
+    /// Layers with at least one occupied level, in ascending order
+    pub fn layers(&self) -> impl Iterator<Item = i32> + '_ {
+        self.layers
+            .iter()
+            .filter(|(_, bitmap)| bitmap.iter().any(|&bits| bits != 0))
+            .map(|(&layer, _)| layer)
+    }
+
+    /// Bitmap of occupied levels of a layer (empty for unknown layers)
+    pub fn layer_bitmap(&self, layer: i32) -> &[u64] {
+        self.layers.get(&layer).map_or(&[], Vec::as_slice)
+    }
+
+    /// Occupied levels of a layer in ascending order
+    pub fn occupied_levels(&self, layer: i32) -> impl Iterator<Item = i32> + '_ {
+        self.layer_bitmap(layer).iter().enumerate().flat_map(|(word, &bits)| {
+            (0..64).filter(move |bit| bits & (1 << bit) != 0).map(move |bit| (word * 64 + bit) as i32)
+        })
+    }
+
+    /// Number of occupied slots
+    pub fn len(&self) -> usize {
+        self.layers
+            .values()
+            .flatten()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// Whether no slot is occupied
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
 /// Calculate layout dimensions
+///
+/// Each block contributes its own size from `config.vertex_sizes`.
 pub fn calculate_layout_dimensions(positions: &[VertexPosition], config: &PlacementConfig) -> (f32, f32) {
     if positions.is_empty() {
         return (0.0, 0.0);
@@ -156,13 +871,13 @@ pub fn calculate_layout_dimensions(positions: &[VertexPosition], config: &Placem
 
     let max_x = positions
         .iter()
-        .map(|p| p.x + config.block_width)
+        .map(|p| p.x + config.vertex_size(&p.vertex_id).0)
         .max_by(|a, b| a.partial_cmp(b).unwrap())
         .unwrap_or(0.0);
 
     let max_y = positions
         .iter()
-        .map(|p| p.y + config.block_height)
+        .map(|p| p.y + config.vertex_size(&p.vertex_id).1)
         .max_by(|a, b| a.partial_cmp(b).unwrap())
         .unwrap_or(0.0);
 
@@ -192,6 +907,283 @@ mod tests {
         assert_eq!(positions[2].level, 2);
     }
 
+    #[test]
+    fn test_interval_packing_shares_levels() {
+        let config = PlacementConfig {
+            level_packing: LevelPacking::Interval,
+            ..PlacementConfig::default()
+        };
+        let vertices = vec!["A".to_string(), "B".to_string(), "C".to_string(), "D".to_string()];
+        let mut widths = HashMap::new();
+        widths.insert("A".to_string(), 40.0);
+        widths.insert("B".to_string(), 40.0);
+        widths.insert("C".to_string(), 120.0);
+
+        let positions = place_vertices_in_layer_packed(0, &vertices, &widths, &config);
+
+        // A and B fit side by side (40 + 80 gap + 40 = 160), C and D need their own levels
+        assert_eq!(positions[0].level, 0);
+        assert_eq!(positions[1].level, 0);
+        assert!(positions[1].x > positions[0].x);
+        assert_eq!(positions[2].level, 1);
+        assert_eq!(positions[3].level, 2);
+    }
+
+    #[test]
+    fn test_interval_packing_shrinks_layer_height() {
+        let config = PlacementConfig {
+            horizontal_gap: 10.0,
+            vertex_sizes: [("W1", (120.0, 80.0)), ("W2", (120.0, 80.0)), ("N1", (30.0, 40.0)), ("N2", (30.0, 40.0))]
+                .into_iter()
+                .map(|(id, size)| (id.to_string(), size))
+                .collect(),
+            ..PlacementConfig::default()
+        };
+        let interval = PlacementConfig {
+            level_packing: LevelPacking::Interval,
+            ..config.clone()
+        };
+        let vertices: Vec<String> = ["W1", "W2", "N1", "N2"].map(String::from).to_vec();
+
+        let packed = place_vertices_in_layer_packed(0, &vertices, &HashMap::new(), &interval);
+
+        // N1 and N2 fill the gaps next to W1 and W2 instead of opening a third level
+        let levels: Vec<i32> = packed.iter().map(|p| p.level).collect();
+        assert_eq!(levels, vec![0, 1, 0, 1]);
+        assert_eq!(packed[2].x, packed[0].x + 130.0);
+        assert_eq!(packed[3].y, packed[1].y);
+
+        let mut column = place_vertices_in_layer(0, &vertices, &config);
+        apply_vertex_sizes(&mut column, &config);
+        let height = |positions: &[VertexPosition]| {
+            positions
+                .iter()
+                .map(|p| p.y + config.vertex_size(&p.vertex_id).1)
+                .fold(0.0f32, f32::max)
+        };
+        // Two rows of 80 px and one gap instead of 80 + 80 + 40 + 40 px and three gaps
+        assert_eq!(height(&packed), 80.0 + 50.0 + 80.0);
+        assert_eq!(height(&column), 80.0 + 80.0 + 40.0 + 40.0 + 3.0 * 50.0);
+    }
+
+    #[test]
+    fn test_interval_packing_keeps_wide_blocks() {
+        let config = PlacementConfig {
+            level_packing: LevelPacking::Interval,
+            horizontal_gap: 10.0,
+            ..PlacementConfig::default()
+        };
+        let vertices: Vec<String> = ["Wide", "A", "B"].map(String::from).to_vec();
+        let widths: HashMap<String, f32> =
+            [("Wide", 300.0), ("A", 100.0), ("B", 100.0)].into_iter().map(|(id, w)| (id.to_string(), w)).collect();
+
+        let packed = place_vertices_in_layer_packed(0, &vertices, &widths, &config);
+
+        // The 300 px block widens the column instead of being clamped to 160 px
+        let levels: Vec<i32> = packed.iter().map(|p| p.level).collect();
+        assert_eq!(levels, vec![0, 1, 1]);
+        assert_eq!(packed[2].x, packed[1].x + 110.0);
+    }
+
+    #[test]
+    fn test_interval_packing_uniform_widths_matches_column() {
+        let config = PlacementConfig {
+            level_packing: LevelPacking::Interval,
+            ..PlacementConfig::default()
+        };
+        let vertices = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+
+        let packed = place_vertices_in_layer_packed(2, &vertices, &HashMap::new(), &config);
+        let column = place_vertices_in_layer(2, &vertices, &config);
+
+        for (p, c) in packed.iter().zip(column.iter()) {
+            assert_eq!(p.level, c.level);
+            assert_eq!(p.x, c.x);
+            assert_eq!(p.y, c.y);
+        }
+    }
+
+    #[test]
+    fn test_tie_break_is_deterministic() {
+        let mut layer_map = HashMap::new();
+        for id in ["D", "B", "A", "C"] {
+            layer_map.insert(id.to_string(), 1);
+        }
+        let config = PlacementConfig::default();
+
+        let positions = place_all_vertices(&layer_map, &config);
+        let order: Vec<&str> = positions.iter().map(|p| p.vertex_id.as_str()).collect();
+        assert_eq!(order, vec!["A", "B", "C", "D"]);
+
+        let input = ["C", "A", "C", "D", "B"].map(String::from);
+        let keys = input_order_keys(input.iter());
+        let config = PlacementConfig {
+            tie_break: TieBreak::InputOrder,
+            ..PlacementConfig::default()
+        };
+        let positions = place_all_vertices_ordered(&layer_map, &HashMap::new(), &keys, &config);
+        let order: Vec<&str> = positions.iter().map(|p| p.vertex_id.as_str()).collect();
+        assert_eq!(order, vec!["C", "A", "D", "B"]);
+    }
+
+    #[test]
+    fn test_order_layer_members_by_degree() {
+        let mut keys = TieBreakKeys::new();
+        keys.insert("A".to_string(), -1);
+        keys.insert("B".to_string(), -3);
+        keys.insert("C".to_string(), -3);
+
+        let mut ids = vec!["A".to_string(), "C".to_string(), "B".to_string(), "Z".to_string()];
+        order_layer_members(&mut ids, TieBreak::Degree, &keys);
+        assert_eq!(ids, vec!["B", "C", "A", "Z"]);
+    }
+
+    #[test]
+    fn test_vertex_sizes_do_not_overlap() {
+        let mut config = PlacementConfig::default();
+        config.vertex_sizes.insert("A".to_string(), (400.0, 30.0));
+        config.vertex_sizes.insert("B".to_string(), (100.0, 200.0));
+
+        let mut layer_map = HashMap::new();
+        for (id, layer) in [("A", 0), ("B", 0), ("C", 0), ("D", 1), ("E", 2)] {
+            layer_map.insert(id.to_string(), layer);
+        }
+        let positions = place_all_vertices(&layer_map, &config);
+        let pos = |id: &str| positions.iter().find(|p| p.vertex_id == id).unwrap().clone();
+
+        // Levels stack by their own heights
+        assert_eq!(pos("A").y, 0.0);
+        assert_eq!(pos("B").y, 30.0 + config.vertical_gap);
+        assert_eq!(pos("C").y, 30.0 + 200.0 + 2.0 * config.vertical_gap);
+
+        // The next layer starts after the widest block of layer 0
+        assert_eq!(pos("D").x, 400.0 + config.horizontal_gap);
+        assert_eq!(pos("E").x, pos("D").x + config.block_width + config.horizontal_gap);
+
+        for a in &positions {
+            for b in &positions {
+                if a.vertex_id == b.vertex_id {
+                    continue;
+                }
+                let (aw, ah) = config.vertex_size(&a.vertex_id);
+                let (bw, bh) = config.vertex_size(&b.vertex_id);
+                let overlap = a.x < b.x + bw && b.x < a.x + aw && a.y < b.y + bh && b.y < a.y + ah;
+                assert!(!overlap, "{} overlaps {}", a.vertex_id, b.vertex_id);
+            }
+        }
+
+        let (width, height) = calculate_layout_dimensions(&positions, &config);
+        assert_eq!(width, pos("E").x + config.block_width);
+        assert_eq!(height, pos("C").y + config.block_height);
+    }
+
+    #[test]
+    fn test_compact_grid_mode() {
+        let config = PlacementConfig {
+            placement_mode: PlacementMode::CompactGrid,
+            ..PlacementConfig::default()
+        };
+        let mut layer_map = HashMap::new();
+        for i in 0..9 {
+            layer_map.insert(format!("v{}", i), 0);
+        }
+        layer_map.insert("next".to_string(), 1);
+
+        let positions = place_all_vertices(&layer_map, &config);
+        let layer0: Vec<_> = positions.iter().filter(|p| p.layer == 0).collect();
+        assert_eq!(layer0.iter().map(|p| p.level).max(), Some(2));
+        assert_eq!(layer0.iter().filter(|p| p.level == 0).count(), 3);
+
+        // Three sub-columns push the next layer to the right
+        let step = config.block_width + config.horizontal_gap;
+        let next = positions.iter().find(|p| p.vertex_id == "next").unwrap();
+        assert_eq!(next.x, 3.0 * step);
+        assert_eq!(next.y, 0.0);
+    }
+
+    #[test]
+    fn test_centroid_sort_follows_predecessors() -> anyhow::Result<()> {
+        // A above B in layer 0; B -> X and A -> Y, but X starts above Y
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        builder.add_edge("A".to_string(), "Y".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "X".to_string(), 1.0)?;
+        let graph = builder.build()?;
+
+        let mut layer_map = HashMap::new();
+        for (id, layer) in [("A", 0), ("B", 0), ("X", 1), ("Y", 1)] {
+            layer_map.insert(id.to_string(), layer);
+        }
+        let mut positions = place_all_vertices(&layer_map, &PlacementConfig::default());
+        assert_eq!(centroid_sort_layers(&mut positions, &graph), 1);
+
+        let level_of = |id: &str| positions.iter().find(|p| p.vertex_id == id).unwrap().level;
+        assert_eq!((level_of("Y"), level_of("X")), (0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_centroid_placement_reduces_crossings() -> anyhow::Result<()> {
+        // Layer 1 is connected to layer 0 in reverse ID order, so the plain
+        // tie-break order crosses every pair of edges
+        let mut builder = crate::data_structures::GraphBuilder::new();
+        for (source, target) in [("A", "Z"), ("B", "Y"), ("C", "X"), ("C", "W")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0)?;
+        }
+        let graph = builder.build()?;
+
+        let mut layer_map = HashMap::new();
+        for (id, layer) in [("A", 0), ("B", 0), ("C", 0), ("W", 1), ("X", 1), ("Y", 1), ("Z", 1)] {
+            layer_map.insert(id.to_string(), layer);
+        }
+        let config = PlacementConfig::default();
+
+        let layers: LayerMap = layer_map
+            .iter()
+            .map(|(vertex_id, &layer)| (graph.vertex_handle(vertex_id).unwrap(), layer))
+            .collect();
+
+        let plain = place_all_vertices(&layer_map, &config);
+        let by_handle = place_graph_vertices(&layers, &graph, &TieBreakKeys::new(), &config);
+        let slot = |p: &VertexPosition| (p.vertex_id.clone(), p.layer, p.level);
+        assert!(plain.iter().map(slot).eq(by_handle.iter().map(slot)));
+
+        let centroid = place_all_vertices_centroid(&layers, &graph, &TieBreakKeys::new(), &config);
+        let plain_crossings = super::super::optimization::count_edge_crossings(&plain, &graph);
+        let centroid_crossings = super::super::optimization::count_edge_crossings(&centroid, &graph);
+        assert!(plain_crossings > 0);
+        assert_eq!(centroid_crossings, 0);
+
+        // Same slots, different order
+        let level_of = |id: &str| centroid.iter().find(|p| p.vertex_id == id).unwrap().level;
+        assert_eq!(level_of("Z"), 0);
+        assert_eq!(level_of("Y"), 1);
+        assert_eq!(centroid.len(), plain.len());
+
+        let handle = |vertex_id: &str| graph.vertex_handle(vertex_id).unwrap();
+        let mut placed = vec![None; graph.vertex_count()];
+        placed[handle("A").index()] = Some(0.0);
+        placed[handle("B").index()] = Some(100.0);
+        assert_eq!(calculate_neighbor_centroid(handle("Z"), &graph, &placed), Some(0.0));
+        assert_eq!(calculate_neighbor_centroid(handle("X"), &graph, &placed), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vertex_sizes_uniform_matches_grid() {
+        let mut layer_map = HashMap::new();
+        for (id, layer) in [("A", 0), ("B", 0), ("C", 1), ("D", 3)] {
+            layer_map.insert(id.to_string(), layer);
+        }
+        let config = PlacementConfig::default();
+        let expected = place_all_vertices(&layer_map, &config);
+
+        let mut positions = expected.clone();
+        apply_vertex_sizes(&mut positions, &config);
+        for (p, e) in positions.iter().zip(expected.iter()) {
+            assert_eq!((p.x, p.y), (e.x, e.y));
+        }
+    }
+
     #[test]
     fn test_occupied_positions() {
         let mut occupied = OccupiedPositions::new();
@@ -204,4 +1196,48 @@ mod tests {
         let next_level = occupied.find_next_available_level(0, 0);
         assert_eq!(next_level, 1);
     }
+
+    #[test]
+    fn test_occupancy_queries() {
+        let occupied = OccupiedPositions::from_slots([(0, 0), (0, 1), (0, 70), (2, 3), (0, -1)]);
+
+        assert_eq!(occupied.len(), 4);
+        assert_eq!(occupied.layers().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(occupied.occupied_levels(0).collect::<Vec<_>>(), vec![0, 1, 70]);
+        assert_eq!(occupied.layer_bitmap(0).len(), 2);
+        assert!(occupied.layer_bitmap(1).is_empty());
+        assert!(!occupied.is_occupied(0, -1));
+
+        assert_eq!(occupied.nearest_free_level(0, 1), 2);
+        assert_eq!(occupied.nearest_free_level(2, 3), 2);
+
+        let near = occupied.free_slots_near(2, 3, 1);
+        assert_eq!(near.first(), Some(&(1, 2)));
+        assert!(!near.contains(&(2, 3)));
+        assert_eq!(near.len(), 8);
+    }
+
+    #[test]
+    fn test_anchors_keep_coordinates_and_move_others() {
+        let mut config = PlacementConfig::default();
+        let vertices = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let mut positions = place_vertices_in_layer(0, &vertices, &config);
+
+        // C is dragged to the top of the column (level 0 is taken by A)
+        config.anchors.insert("C".to_string(), (15.0, 4.0));
+        config.anchors.insert("missing".to_string(), (0.0, 0.0));
+        assert_eq!(apply_vertex_anchors(&mut positions, &config), 1);
+
+        let find = |id: &str| positions.iter().find(|p| p.vertex_id == id).unwrap().clone();
+        let c = find("C");
+        assert_eq!((c.x, c.y, c.level), (15.0, 4.0, 0));
+        // A gives way to the nearest free level, B stays
+        assert_eq!((find("A").level, find("A").y), (2, 260.0));
+        assert_eq!(find("B").level, 1);
+
+        // No anchors, no changes
+        let before: Vec<(i32, f32)> = positions.iter().map(|p| (p.level, p.y)).collect();
+        assert_eq!(apply_vertex_anchors(&mut positions, &PlacementConfig::default()), 0);
+        assert_eq!(positions.iter().map(|p| (p.level, p.y)).collect::<Vec<_>>(), before);
+    }
 }
@@ -0,0 +1,191 @@
+/// Layering by a vertex property (publication year)
+///
+/// For a citation graph the publication year is often a more meaningful layer
+/// than the depth of the citation chain: articles of one year form a column,
+/// older years are on the left. Articles of the same year that cite each other
+/// are split into sub-layers by their longest path within the year, so such
+/// edges still point to a higher layer.
+///
+/// The final layer is the dense rank of `(year, depth within year)`. A vertex
+/// without a known year joins the median year of its neighbours that have one
+/// (or the median of all known years), so it sits next to the articles it is
+/// linked with instead of after the latest year. Years are read from the
+/// `ATTR_YEAR` vertex attribute of the graph (see `years_from_edges` and
+/// `GraphBuilder::set_attribute`).
+
+use std::collections::{BTreeSet, HashMap};
+use crate::data_structures::{AttrValue, Graph, VertexId, ATTR_YEAR};
+use crate::neo4j::GraphEdge;
+use super::layer_assignment::LayerMap;
+
+/// Publication year per vertex (vertices without a year are absent)
+pub type VertexYears = HashMap<String, i32>;
+
+/// Collect publication years from edge endpoints
+///
+/// If the same vertex is given different years, the first one wins.
+pub fn years_from_edges(edges: &[GraphEdge]) -> VertexYears {
+    let mut years = VertexYears::new();
+    for edge in edges {
+        let endpoints = [
+            (&edge.source_id, edge.source_year),
+            (&edge.target_id, edge.target_year),
+        ];
+        for (vertex_id, year) in endpoints {
+            if let Some(year) = year {
+                years.entry(vertex_id.clone()).or_insert(year);
+            }
+        }
+    }
+    years
+}
+
+/// Set the `ATTR_YEAR` attribute of every vertex of `years` that is in the graph
+pub fn set_vertex_years(graph: &mut Graph, years: &VertexYears) {
+    for (vertex_id, &year) in years {
+        if let Some(vertex) = graph.vertex_handle(vertex_id) {
+            graph.set_attribute(vertex, ATTR_YEAR, AttrValue::Int(year.into()));
+        }
+    }
+}
+
+/// Number of vertices with a known publication year
+pub fn year_count(graph: &Graph) -> usize {
+    graph.attribute_values(ATTR_YEAR).count()
+}
+
+/// Grouping year of every vertex, indexed by handle
+///
+/// Known years are kept; a vertex without one gets the median year of its
+/// neighbours with a known year, or the median of all known years.
+fn grouping_years(graph: &Graph) -> Vec<i32> {
+    let known: Vec<Option<i32>> = graph.handles().map(|vertex| graph.vertex_year(vertex)).collect();
+    let mut all: Vec<i32> = known.iter().flatten().copied().collect();
+    let fallback = median(&mut all).unwrap_or(0);
+
+    graph
+        .handles()
+        .map(|vertex| {
+            known[vertex.index()].unwrap_or_else(|| {
+                let mut neighbours: Vec<i32> = graph
+                    .predecessors(vertex)
+                    .chain(graph.successors(vertex))
+                    .filter_map(|neighbour| known[neighbour.index()])
+                    .collect();
+                median(&mut neighbours).unwrap_or(fallback)
+            })
+        })
+        .collect()
+}
+
+/// Median (upper for an even count) of `years`, reordering them
+fn median(years: &mut [i32]) -> Option<i32> {
+    if years.is_empty() {
+        return None;
+    }
+    let middle = years.len() / 2;
+    Some(*years.select_nth_unstable(middle).1)
+}
+
+/// Assign layers from publication years (`ATTR_YEAR` vertex attribute)
+///
+/// `base_layers` (a longest-path layering of the same graph) fixes the order in
+/// which same-year vertices are visited, so every same-year predecessor is
+/// processed before its successors.
+pub fn assign_layers_by_property(graph: &Graph, base_layers: &LayerMap) -> LayerMap {
+    let years = grouping_years(graph);
+    let group = |vertex: VertexId| years[vertex.index()];
+
+    let mut order: Vec<VertexId> = graph.handles().collect();
+    order.sort_by(|&a, &b| {
+        let layer = |vertex: VertexId| base_layers.get(&vertex).copied().unwrap_or(0);
+        layer(a).cmp(&layer(b)).then_with(|| graph.vertex_uid(a).cmp(graph.vertex_uid(b)))
+    });
+
+    // Depth within the year; `None` until the vertex is visited
+    let mut depth: Vec<Option<i32>> = vec![None; graph.vertex_count()];
+    for &vertex in &order {
+        let year = group(vertex);
+        let vertex_depth = graph
+            .predecessors(vertex)
+            .filter(|&pred| group(pred) == year)
+            .filter_map(|pred| depth[pred.index()])
+            .max()
+            .map_or(0, |d| d + 1);
+        depth[vertex.index()] = Some(vertex_depth);
+    }
+
+    let key = |vertex: VertexId| (group(vertex), depth[vertex.index()].unwrap_or(0));
+    let keys: BTreeSet<(i32, i32)> = order.iter().map(|&vertex| key(vertex)).collect();
+    let rank: HashMap<(i32, i32), i32> = keys
+        .into_iter()
+        .enumerate()
+        .map(|(rank, key)| (key, rank as i32))
+        .collect();
+
+    let unknown = order.iter().filter(|&&vertex| graph.vertex_year(vertex).is_none()).count();
+    tracing::info!(
+        "Year layering complete: {} vertices in {} layers ({} without a year placed by neighbours)",
+        order.len(),
+        rank.len(),
+        unknown
+    );
+
+    order
+        .into_iter()
+        .map(|vertex| (vertex, rank[&key(vertex)]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::GraphBuilder;
+
+    #[test]
+    fn test_layers_follow_years() {
+        // A(2001) -> B(2001) -> C(1990), D(1995) -> C, E (no year) -> A, F (no year) -> E
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0).unwrap();
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("D".to_string(), "C".to_string(), 1.0).unwrap();
+        builder.add_edge("E".to_string(), "A".to_string(), 1.0).unwrap();
+        builder.add_edge("F".to_string(), "E".to_string(), 1.0).unwrap();
+        for (vertex_id, year) in [("A", 2001), ("B", 2001), ("C", 1990), ("D", 1995)] {
+            builder.set_attribute(vertex_id, ATTR_YEAR, AttrValue::Int(year));
+        }
+        let graph = builder.build().unwrap();
+        assert_eq!(year_count(&graph), 4);
+
+        let base = super::super::assign_layers_kahn(&graph).unwrap();
+        let layers = assign_layers_by_property(&graph, &base);
+
+        let layer_of = |vertex_id: &str| layers[&graph.vertex_handle(vertex_id).unwrap()];
+        assert_eq!(layer_of("C"), 0);
+        assert_eq!(layer_of("D"), 1);
+        // E joins 2001 through A, F has no dated neighbour and gets the median
+        // of all years (2001); the edges F -> E -> A stay forward within the year
+        assert_eq!(layer_of("F"), 2);
+        assert_eq!(layer_of("E"), 3);
+        // Same year: the edge A -> B puts B one sub-layer further
+        assert_eq!(layer_of("A"), 4);
+        assert_eq!(layer_of("B"), 5);
+    }
+
+    #[test]
+    fn test_years_from_edges() {
+        let edge = |source: &str, target: &str, source_year, target_year| GraphEdge {
+            source_year,
+            target_year,
+            ..GraphEdge::new(source, target, 1.0)
+        };
+        let years = years_from_edges(&[
+            edge("A", "B", Some(2010), None),
+            edge("A", "C", Some(2011), Some(1999)),
+        ]);
+
+        assert_eq!(years.len(), 2);
+        assert_eq!(years["A"], 2010);
+        assert_eq!(years["C"], 1999);
+    }
+}
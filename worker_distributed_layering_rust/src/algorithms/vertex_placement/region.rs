@@ -0,0 +1,230 @@
+/// Local re-layout of a region of an existing layout
+///
+/// When a user zooms into a neighborhood, only the selected vertices are
+/// re-optimized; every other vertex keeps its slot and acts as a fixed
+/// obstacle, so the rest of the map does not move.
+///
+/// Selected vertices stay in their layers. Each sweep orders the selected
+/// vertices of a layer by the barycenter (or median) of their neighbors'
+/// levels and moves each one, in that order, to the free level closest to its
+/// key. Sweeps repeat until no vertex moves or the sweep limit is reached.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::data_structures::Graph;
+use crate::neo4j::VertexPosition;
+use super::optimization::CrossingHeuristic;
+use super::placement::{OccupiedPositions, PlacementConfig};
+
+/// Outcome of a region refinement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegionRefinement {
+    /// Number of selected vertices
+    pub vertices: usize,
+
+    /// Selected vertices whose level changed
+    pub moved: usize,
+
+    /// Sweeps performed
+    pub sweeps: usize,
+}
+
+/// Re-optimize levels and coordinates of `vertex_ids` only
+///
+/// Fails if a selected vertex is not in `positions`. The y coordinate of a
+/// moved vertex shifts by whole rows (`block_height + vertical_gap`), so any
+/// per-layer offset of the original layout is kept; x does not change.
+pub fn refine_region(
+    vertex_ids: &[String],
+    positions: &mut [VertexPosition],
+    graph: &Graph,
+    config: &PlacementConfig,
+    heuristic: CrossingHeuristic,
+    max_sweeps: usize,
+) -> Result<RegionRefinement> {
+    let index_of: HashMap<String, usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(idx, pos)| (pos.article_id.clone(), idx))
+        .collect();
+
+    let mut selected = HashSet::new();
+    for vertex_id in vertex_ids {
+        let idx = *index_of
+            .get(vertex_id)
+            .ok_or_else(|| anyhow::anyhow!("Vertex '{}' is not in the layout", vertex_id))?;
+        selected.insert(idx);
+    }
+
+    let fixed = OccupiedPositions::from_slots(
+        positions
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !selected.contains(idx))
+            .map(|(_, pos)| (pos.layer, pos.level)),
+    );
+
+    let mut by_layer: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for &idx in &selected {
+        by_layer.entry(positions[idx].layer).or_default().push(idx);
+    }
+
+    let original_levels: HashMap<usize, i32> =
+        selected.iter().map(|&idx| (idx, positions[idx].level)).collect();
+    let row_step = config.block_height + config.vertical_gap;
+
+    let mut sweeps = 0;
+    for _ in 0..max_sweeps.max(1) {
+        sweeps += 1;
+        let mut occupancy = fixed.clone();
+        let mut changed = false;
+
+        for (&layer, members) in &mut by_layer {
+            let keys: HashMap<usize, f32> = members
+                .iter()
+                .map(|&idx| (idx, ordering_key(idx, positions, graph, &index_of, heuristic)))
+                .collect();
+            members.sort_by(|&a, &b| {
+                keys[&a]
+                    .total_cmp(&keys[&b])
+                    .then_with(|| positions[a].level.cmp(&positions[b].level))
+                    .then_with(|| positions[a].article_id.cmp(&positions[b].article_id))
+            });
+
+            for &idx in members.iter() {
+                let level = occupancy.nearest_free_level(layer, keys[&idx].round() as i32);
+                occupancy.mark_occupied(layer, level);
+
+                let pos = &mut positions[idx];
+                if level != pos.level {
+                    pos.y += (level - pos.level) as f32 * row_step;
+                    pos.level = level;
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let moved = original_levels
+        .iter()
+        .filter(|&(&idx, &level)| positions[idx].level != level)
+        .count();
+
+    tracing::info!(
+        "Region refinement: {} of {} selected vertices moved in {} sweeps",
+        moved,
+        selected.len(),
+        sweeps
+    );
+
+    Ok(RegionRefinement {
+        vertices: selected.len(),
+        moved,
+        sweeps,
+    })
+}
+
+/// Barycenter or median of the neighbors' levels (own level without neighbors)
+fn ordering_key(
+    idx: usize,
+    positions: &[VertexPosition],
+    graph: &Graph,
+    index_of: &HashMap<String, usize>,
+    heuristic: CrossingHeuristic,
+) -> f32 {
+    let vertex_id = positions[idx].article_id.as_str();
+    let mut levels: Vec<f32> = graph
+        .get_incoming_edges(vertex_id)
+        .into_iter()
+        .flatten()
+        .chain(graph.get_outgoing_edges(vertex_id).into_iter().flatten())
+        .filter_map(|neighbor| index_of.get(neighbor.as_str()))
+        .filter(|&&n| positions[n].layer != positions[idx].layer)
+        .map(|&n| positions[n].level as f32)
+        .collect();
+
+    if levels.is_empty() {
+        return positions[idx].level as f32;
+    }
+    match heuristic {
+        CrossingHeuristic::Barycenter => levels.iter().sum::<f32>() / levels.len() as f32,
+        CrossingHeuristic::Median => {
+            levels.sort_by(f32::total_cmp);
+            let mid = levels.len() / 2;
+            if levels.len() % 2 == 1 {
+                levels[mid]
+            } else {
+                (levels[mid - 1] + levels[mid]) / 2.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::GraphBuilder;
+
+    fn position(id: &str, layer: i32, level: i32) -> VertexPosition {
+        VertexPosition {
+            article_id: id.to_string(),
+            layer,
+            level,
+            x: layer as f32 * 240.0,
+            y: level as f32 * 130.0,
+        }
+    }
+
+    #[test]
+    fn test_refine_region_moves_only_selected() -> Result<()> {
+        // A and B feed P, which was left far below them; Q is a fixed obstacle
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "P".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "P".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "Q".to_string(), 1.0)?;
+        let graph = builder.build()?;
+
+        let mut positions = vec![
+            position("A", 0, 0),
+            position("B", 0, 1),
+            position("Q", 1, 1),
+            position("P", 1, 5),
+        ];
+        let before = positions.clone();
+        let config = PlacementConfig::default();
+
+        let result = refine_region(
+            &["P".to_string()],
+            &mut positions,
+            &graph,
+            &config,
+            CrossingHeuristic::Barycenter,
+            10,
+        )?;
+        assert_eq!(result.vertices, 1);
+        assert_eq!(result.moved, 1);
+
+        // Barycenter 0.5 rounds to the level of Q, so P takes the free level above
+        assert_eq!(positions[3].level, 0);
+        assert_eq!(positions[3].y, 0.0);
+        assert_eq!(positions[3].x, before[3].x);
+        for (after, before) in positions.iter().zip(&before).take(3) {
+            assert_eq!((after.level, after.x, after.y), (before.level, before.x, before.y));
+        }
+
+        assert!(refine_region(
+            &["missing".to_string()],
+            &mut positions,
+            &graph,
+            &config,
+            CrossingHeuristic::Barycenter,
+            10,
+        )
+        .is_err());
+        Ok(())
+    }
+}
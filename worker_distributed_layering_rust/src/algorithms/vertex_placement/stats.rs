@@ -21,6 +21,41 @@ pub struct PlacementStats {
 
     /// Total height of the layout in pixels
     pub total_height: f32,
+
+    /// Edge crossings before crossing minimization
+    pub crossings_before: usize,
+
+    /// Edge crossings after crossing minimization
+    pub crossings_after: usize,
+
+    /// Vertices found sharing a (layer, level) slot after optimization
+    /// (all of them were moved to free levels)
+    #[serde(default)]
+    pub overlaps_repaired: usize,
+
+    /// Vertices placed at fixed anchor coordinates
+    #[serde(default)]
+    pub anchored_vertices: usize,
+
+    /// Layers too large for exact crossing minimization, ordered approximately
+    #[serde(default)]
+    pub approximate_layers: usize,
+
+    /// Edges routed around blocks (`EdgeRoutingOptions::avoid_vertices`)
+    #[serde(default)]
+    pub edges_detoured: usize,
+
+    /// Layer crossings moved to separate tracks (`EdgeRoutingOptions::channel_spacing`)
+    #[serde(default)]
+    pub channel_shifts: usize,
+
+    /// Waypoints dropped by path simplification (`EdgeRoutingOptions::simplify_tolerance`)
+    #[serde(default)]
+    pub path_points_removed: usize,
+
+    /// Bytes reserved by the temporary arena of the run (see `LayoutArena`)
+    #[serde(default)]
+    pub arena_peak_bytes: usize,
 }
 
 impl PlacementStats {
@@ -33,6 +68,15 @@ impl PlacementStats {
             max_vertices_in_layer: 0,
             total_width: 0.0,
             total_height: 0.0,
+            crossings_before: 0,
+            crossings_after: 0,
+            overlaps_repaired: 0,
+            anchored_vertices: 0,
+            approximate_layers: 0,
+            edges_detoured: 0,
+            channel_shifts: 0,
+            path_points_removed: 0,
+            arena_peak_bytes: 0,
         }
     }
 
@@ -44,6 +88,15 @@ impl PlacementStats {
         self.max_vertices_in_layer = 0;
         self.total_width = 0.0;
         self.total_height = 0.0;
+        self.crossings_before = 0;
+        self.crossings_after = 0;
+        self.overlaps_repaired = 0;
+        self.anchored_vertices = 0;
+        self.approximate_layers = 0;
+        self.edges_detoured = 0;
+        self.channel_shifts = 0;
+        self.path_points_removed = 0;
+        self.arena_peak_bytes = 0;
     }
 }
 
@@ -0,0 +1,383 @@
+/*!
+# Артефакты задач укладки
+
+Каждая задача складывает свои артефакты в отдельный каталог
+`{dir}/{tenant}/{job_id}/` (см. `crate::tenant`):
+
+- `layout.json` — экспорт позиций вершин
+- `validation.json` — отчёт валидации входных связей
+- `quality.json` — результаты этапов (пересечения, изломы, слои и т.п.)
+
+Пакетная укладка (`auto-layout`) пишет в каталог `auto-layout-{run_id}`
+арендатора по умолчанию `layout.json` и `meta.json` (метаданные укладки).
+
+Каталог можно дополнять любыми файлами (например, профилем прогона) через
+`ArtifactStore::write`. Операторы получают артефакты через RPC
+`ListArtifacts`/`DownloadArtifact`, без доступа к файловой системе воркера.
+
+Политика хранения применяется после каждой задачи к каталогам её арендатора:
+удаляются каталоги старше `max_age_hours` и самые старые сверх `max_jobs`.
+Поток задач одного арендатора не вытесняет артефакты другого.
+
+Имена задач и артефактов приходят из запросов, поэтому в путь попадают только
+имена без разделителей каталогов, `.` и `..`.
+*/
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::algorithms::LayoutResult;
+use crate::config::ArtifactsConfig;
+
+/// Артефакт задачи
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArtifactInfo {
+    /// Идентификатор задачи
+    pub job_id: String,
+
+    /// Имя файла артефакта
+    pub name: String,
+
+    /// Размер (байты)
+    pub size_bytes: u64,
+
+    /// Время последнего изменения (секунды Unix)
+    pub modified_timestamp: i64,
+}
+
+/// Политика хранения каталогов задач
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Сколько последних задач хранить (0 — без ограничения)
+    pub max_jobs: usize,
+
+    /// Максимальный возраст каталога задачи (None — без ограничения)
+    pub max_age: Option<Duration>,
+}
+
+/// Хранилище артефактов задач на локальном диске
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    root: PathBuf,
+    retention: RetentionPolicy,
+}
+
+impl ArtifactStore {
+    pub fn new(root: impl Into<PathBuf>, retention: RetentionPolicy) -> Self {
+        Self {
+            root: root.into(),
+            retention,
+        }
+    }
+
+    /// Хранилище по конфигурации (None, если артефакты выключены)
+    pub fn from_config(config: &ArtifactsConfig) -> Option<Self> {
+        config.enabled.then(|| {
+            Self::new(
+                &config.dir,
+                RetentionPolicy {
+                    max_jobs: config.max_jobs,
+                    max_age: (config.max_age_hours > 0)
+                        .then(|| Duration::from_secs(config.max_age_hours * 3600)),
+                },
+            )
+        })
+    }
+
+    /// Корневой каталог
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Хранилище арендатора в `{root}/{tenant}/` с той же политикой хранения
+    pub fn for_tenant(&self, tenant: &str) -> Result<Self> {
+        check_name("арендатора", tenant)?;
+        Ok(Self::new(self.root.join(tenant), self.retention))
+    }
+
+    /// Каталог задачи
+    pub fn job_dir(&self, job_id: &str) -> Result<PathBuf> {
+        check_name("задачи", job_id)?;
+        Ok(self.root.join(job_id))
+    }
+
+    /// Путь к артефакту задачи
+    pub fn artifact_path(&self, job_id: &str, name: &str) -> Result<PathBuf> {
+        check_name("артефакта", name)?;
+        Ok(self.job_dir(job_id)?.join(name))
+    }
+
+    /// Запись артефакта (существующий файл перезаписывается)
+    pub fn write(&self, job_id: &str, name: &str, contents: &[u8]) -> Result<ArtifactInfo> {
+        let path = self.artifact_path(job_id, name)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)
+            .map_err(|e| anyhow::anyhow!("Не удалось записать артефакт {}: {}", path.display(), e))?;
+        artifact_info(job_id, &path)
+    }
+
+    /// Запись артефакта в JSON
+    pub fn write_json<T: Serialize + ?Sized>(&self, job_id: &str, name: &str, value: &T) -> Result<ArtifactInfo> {
+        self.write(job_id, name, &serde_json::to_vec_pretty(value)?)
+    }
+
+    /// Артефакты задачи по имени; пусто, если каталога задачи нет
+    pub fn list(&self, job_id: &str) -> Result<Vec<ArtifactInfo>> {
+        let dir = self.job_dir(job_id)?;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut artifacts = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                artifacts.push(artifact_info(job_id, &path)?);
+            }
+        }
+        artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(artifacts)
+    }
+
+    /// Идентификаторы задач с артефактами, от новых к старым
+    pub fn jobs(&self) -> Result<Vec<String>> {
+        Ok(self.job_dirs()?.into_iter().map(|(job_id, _)| job_id).collect())
+    }
+
+    /// Применение политики хранения; возвращает количество удалённых задач
+    pub fn enforce_retention(&self) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+
+        for (index, (job_id, modified)) in self.job_dirs()?.into_iter().enumerate() {
+            let too_many = self.retention.max_jobs > 0 && index >= self.retention.max_jobs;
+            let too_old = self
+                .retention
+                .max_age
+                .map_or(false, |max_age| now.duration_since(modified).unwrap_or_default() > max_age);
+            if !(too_many || too_old) {
+                continue;
+            }
+
+            match std::fs::remove_dir_all(self.root.join(&job_id)) {
+                Ok(()) => removed += 1,
+                Err(e) => warn!("⚠️ Не удалось удалить артефакты задачи {}: {}", job_id, e),
+            }
+        }
+
+        if removed > 0 {
+            info!("🧹 Удалены артефакты {} задач по политике хранения", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Каталоги задач с временем изменения, от новых к старым
+    fn job_dirs(&self) -> Result<Vec<(String, SystemTime)>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut dirs = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_dir() {
+                continue;
+            }
+            if let Some(job_id) = entry.file_name().to_str() {
+                dirs.push((job_id.to_string(), metadata.modified()?));
+            }
+        }
+        dirs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(dirs)
+    }
+}
+
+/// Запись стандартных артефактов задачи укладки и применение политики хранения
+pub fn write_layout_artifacts(store: &ArtifactStore, job_id: &str, result: &LayoutResult) -> Result<Vec<ArtifactInfo>> {
+    let artifacts = vec![
+        store.write_json(job_id, "layout.json", &result.positions)?,
+        store.write_json(job_id, "validation.json", &result.validation)?,
+        store.write_json(job_id, "quality.json", &result.phases)?,
+    ];
+    info!(
+        "🗃️ Артефакты задачи {}: {} файлов в {}",
+        job_id,
+        artifacts.len(),
+        store.job_dir(job_id)?.display()
+    );
+    store.enforce_retention()?;
+    Ok(artifacts)
+}
+
+/// Запись артефактов пакетной укладки и применение политики хранения
+pub fn write_batch_artifacts(
+    store: &ArtifactStore,
+    job_id: &str,
+    positions: &[crate::neo4j::VertexPosition],
+    meta: &crate::neo4j::LayoutMeta,
+) -> Result<Vec<ArtifactInfo>> {
+    let artifacts = vec![
+        store.write_json(job_id, "layout.json", positions)?,
+        store.write_json(job_id, "meta.json", meta)?,
+    ];
+    info!(
+        "🗃️ Артефакты задачи {}: {} файлов в {}",
+        job_id,
+        artifacts.len(),
+        store.job_dir(job_id)?.display()
+    );
+    store.enforce_retention()?;
+    Ok(artifacts)
+}
+
+/// Идентификатор задачи, пригодный для имени каталога
+///
+/// Недопустимые символы заменяются на `_`; пустой ID заменяется случайным.
+pub fn job_id_for(task_id: &str) -> String {
+    let sanitized: String = task_id
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() || check_name("задачи", &sanitized).is_err() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// Проверка имени задачи или артефакта: один компонент пути
+pub fn check_name(kind: &str, name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0']);
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Недопустимое имя {}: '{}'", kind, name))
+    }
+}
+
+fn artifact_info(job_id: &str, path: &Path) -> Result<ArtifactInfo> {
+    let metadata = std::fs::metadata(path)?;
+    Ok(ArtifactInfo {
+        job_id: job_id.to_string(),
+        name: path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        size_bytes: metadata.len(),
+        modified_timestamp: metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(max_jobs: usize) -> ArtifactStore {
+        let dir = std::env::temp_dir().join(format!("artifacts-{}", uuid::Uuid::new_v4()));
+        ArtifactStore::new(dir, RetentionPolicy { max_jobs, max_age: None })
+    }
+
+    #[test]
+    fn test_write_list_and_retention() {
+        let store = temp_store(2);
+
+        store.write("job-1", "b.txt", b"bb").unwrap();
+        store.write_json("job-1", "a.json", &[1, 2, 3]).unwrap();
+        let listed = store.list("job-1").unwrap();
+        let names: Vec<&str> = listed.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["a.json", "b.txt"]);
+        assert_eq!(listed[1].size_bytes, 2);
+        assert!(store.list("missing").unwrap().is_empty());
+
+        // Разные mtime каталогов: самый старый — job-1
+        std::thread::sleep(Duration::from_millis(20));
+        store.write("job-2", "x", b"x").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        store.write("job-3", "x", b"x").unwrap();
+
+        assert_eq!(store.enforce_retention().unwrap(), 1);
+        assert_eq!(store.jobs().unwrap(), vec!["job-3".to_string(), "job-2".to_string()]);
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn test_retention_is_per_tenant() {
+        let store = temp_store(1);
+        let (big, small) = (store.for_tenant("big").unwrap(), store.for_tenant("small").unwrap());
+
+        small.write("job-1", "x", b"x").unwrap();
+        for job in ["job-1", "job-2", "job-3"] {
+            std::thread::sleep(Duration::from_millis(20));
+            big.write(job, "x", b"x").unwrap();
+            big.enforce_retention().unwrap();
+        }
+
+        assert_eq!(big.jobs().unwrap(), vec!["job-3".to_string()]);
+        assert_eq!(small.jobs().unwrap(), vec!["job-1".to_string()]);
+        assert!(store.for_tenant("..").is_err());
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn test_write_batch_artifacts() {
+        let store = temp_store(0);
+        let positions = vec![crate::neo4j::VertexPosition {
+            article_id: "a".to_string(),
+            layer: 0,
+            level: 1,
+            x: 0.0,
+            y: 10.0,
+        }];
+        let meta = crate::neo4j::LayoutMeta {
+            run_id: "run-1".to_string(),
+            algorithm_version: "test".to_string(),
+            block_width: 4.0,
+            block_height: 2.0,
+            horizontal_gap: 1.0,
+            vertical_gap: 1.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            level_packing: "OnePerLevel".to_string(),
+            vertex_count: 1,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            scale_profile: String::new(),
+        };
+
+        let written = write_batch_artifacts(&store, "auto-layout-run-1", &positions, &meta).unwrap();
+        let names: Vec<&str> = written.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["layout.json", "meta.json"]);
+        let layout = std::fs::read_to_string(store.artifact_path("auto-layout-run-1", "layout.json").unwrap()).unwrap();
+        assert!(layout.contains("\"article_id\": \"a\""));
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_path_traversal() {
+        let store = temp_store(0);
+
+        assert!(store.write("..", "x", b"x").is_err());
+        assert!(store.write("job", "../x", b"x").is_err());
+        assert!(store.artifact_path("job", "a/b").is_err());
+        assert_eq!(job_id_for("tasks/../42"), "tasks_.._42");
+        assert_eq!(job_id_for("t-1"), "t-1");
+        assert!(!job_id_for("").is_empty());
+    }
+}
@@ -0,0 +1,494 @@
+/*!
+# Нагрузочное тестирование gRPC сервера
+
+Режим `--mode benchmark --grpc`: поднимает `GraphLayoutServer` внутри процесса
+поверх `InMemoryGraphStore` (Neo4j не нужен), запускает N конкурентных
+клиентов, которые отправляют синтетические запросы укладки заданных размеров,
+и выводит перцентили задержек.
+
+Используется для подбора размеров production инстансов.
+
+Режим `--mode benchmark` без `--grpc` сравнивает стратегии назначения слоёв
+(BFS, Coffman–Graham, network simplex) на тех же синтетических графах:
+время, количество слоёв, максимальная ширина слоя и суммарная длина связей,
+а также хранение связей графа: списки смежности против CSR (построение,
+память, обход с чтением весов).
+*/
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tracing::{info, warn};
+
+use crate::algorithms::network_simplex::total_edge_length;
+use crate::algorithms::vertex_placement::{assign_layers, LayeringStrategy};
+use crate::config::Config;
+use crate::data_structures::{Graph, GraphBuilder};
+use crate::generated::{
+    graph_layout_service_client::GraphLayoutServiceClient,
+    graph_layout_service_server::GraphLayoutServiceServer,
+    GraphEdge, LayoutRequest,
+};
+use crate::graph_store::InMemoryGraphStore;
+use crate::server::GraphLayoutServer;
+
+/// Параметры нагрузочного теста
+#[derive(Debug, Clone)]
+pub struct GrpcBenchOptions {
+    /// Количество одновременных клиентов
+    pub concurrency: usize,
+
+    /// Общее количество запросов (на каждый размер графа)
+    pub requests: usize,
+
+    /// Размеры синтетических графов (количество вершин)
+    pub sizes: Vec<usize>,
+
+    /// Среднее количество исходящих связей на вершину
+    pub avg_out_degree: usize,
+}
+
+/// Результат прогона для одного размера графа
+#[derive(Debug, Clone)]
+pub struct GrpcBenchReport {
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub requests: usize,
+    pub failures: usize,
+    pub total_time: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl GrpcBenchReport {
+    /// Пропускная способность (запросов/сек)
+    pub fn throughput(&self) -> f64 {
+        if self.total_time.as_secs_f64() > 0.0 {
+            (self.requests - self.failures) as f64 / self.total_time.as_secs_f64()
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Запуск нагрузочного теста gRPC сервера
+pub async fn run_grpc_bench(config: Config, options: GrpcBenchOptions) -> Result<Vec<GrpcBenchReport>> {
+    if options.concurrency == 0 || options.requests == 0 {
+        return Err(anyhow::anyhow!("concurrency и requests должны быть больше 0"));
+    }
+
+    // Связи приходят в запросах, поэтому Neo4j не нужен; артефакты и токены
+    // арендаторов выключены, чтобы прогон не писал на диск и не требовал авторизации
+    let mut config = config;
+    config.artifacts.enabled = false;
+    config.server.tenant_tokens.clear();
+    let layout_service = GraphLayoutServer::with_store(config, Arc::new(InMemoryGraphStore::default()))?;
+
+    // Сервер слушает тот же сокет, на котором выбран свободный порт
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr: SocketAddr = listener.local_addr()?;
+
+    info!("🚀 Запуск in-process gRPC сервера на {}", addr);
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let server_handle = tokio::spawn(async move {
+        Server::builder()
+            .add_service(GraphLayoutServiceServer::new(layout_service))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+    });
+
+    let client = connect_with_retry(addr).await?;
+
+    let mut reports = Vec::with_capacity(options.sizes.len());
+    for &size in &options.sizes {
+        let edges = synthetic_dag(size, options.avg_out_degree, size as u64);
+        info!(
+            "📊 Нагрузка: {} вершин, {} связей, {} запросов, {} клиентов",
+            size, edges.len(), options.requests, options.concurrency
+        );
+        let report = bench_single_size(client.clone(), edges, size, &options).await;
+        print_report(&report);
+        reports.push(report);
+    }
+
+    let _ = shutdown_tx.send(());
+    if let Err(e) = server_handle.await {
+        warn!("⚠️ Ошибка остановки in-process сервера: {}", e);
+    }
+
+    Ok(reports)
+}
+
+/// Подключение клиента, пока сервер поднимается
+async fn connect_with_retry(
+    addr: SocketAddr,
+) -> Result<GraphLayoutServiceClient<tonic::transport::Channel>> {
+    let endpoint = format!("http://{}", addr);
+    let mut last_error = None;
+
+    for _ in 0..50 {
+        match GraphLayoutServiceClient::connect(endpoint.clone()).await {
+            Ok(client) => return Ok(client.max_decoding_message_size(usize::MAX)),
+            Err(e) => {
+                last_error = Some(e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Не удалось подключиться к in-process серверу {}: {:?}",
+        endpoint,
+        last_error
+    ))
+}
+
+/// Прогон нагрузки для одного размера графа
+async fn bench_single_size(
+    client: GraphLayoutServiceClient<tonic::transport::Channel>,
+    edges: Vec<GraphEdge>,
+    vertex_count: usize,
+    options: &GrpcBenchOptions,
+) -> GrpcBenchReport {
+    let edge_count = edges.len();
+    let edges = Arc::new(edges);
+    let remaining = Arc::new(Mutex::new(options.requests));
+    let latencies = Arc::new(Mutex::new(Vec::with_capacity(options.requests)));
+    let failures = Arc::new(Mutex::new(0usize));
+
+    let start = Instant::now();
+    let mut workers = Vec::with_capacity(options.concurrency);
+
+    for worker_id in 0..options.concurrency {
+        let mut client = client.clone();
+        let edges = Arc::clone(&edges);
+        let remaining = Arc::clone(&remaining);
+        let latencies = Arc::clone(&latencies);
+        let failures = Arc::clone(&failures);
+
+        workers.push(tokio::spawn(async move {
+            loop {
+                let request_no = {
+                    let mut left = remaining.lock().await;
+                    if *left == 0 {
+                        break;
+                    }
+                    *left -= 1;
+                    *left
+                };
+
+                let request = LayoutRequest {
+                    task_id: format!("bench-{}-{}", worker_id, request_no),
+                    edges: edges.as_ref().clone(),
+                    options: None,
+                    metadata: None,
+                    stream_chunk_size: 0,
+                };
+
+                let request_start = Instant::now();
+                let ok = match client.compute_layout(request).await {
+                    Ok(response) => response.into_inner().success,
+                    Err(_) => false,
+                };
+                let elapsed = request_start.elapsed();
+
+                latencies.lock().await.push(elapsed);
+                if !ok {
+                    *failures.lock().await += 1;
+                }
+            }
+        }));
+    }
+
+    for worker in workers {
+        let _ = worker.await;
+    }
+
+    let total_time = start.elapsed();
+    let mut latencies = latencies.lock().await.clone();
+    latencies.sort();
+
+    GrpcBenchReport {
+        vertex_count,
+        edge_count,
+        requests: latencies.len(),
+        failures: *failures.lock().await,
+        total_time,
+        p50: percentile(&latencies, 0.50),
+        p90: percentile(&latencies, 0.90),
+        p99: percentile(&latencies, 0.99),
+        max: latencies.last().copied().unwrap_or_default(),
+    }
+}
+
+/// Перцентиль по отсортированному массиву задержек
+fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() as f64 - 1.0) * q).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Генерация синтетического DAG (детерминированно по seed)
+///
+/// Связи идут только от вершин с меньшим индексом к большему, поэтому граф ацикличен.
+pub fn synthetic_dag(vertex_count: usize, avg_out_degree: usize, seed: u64) -> Vec<GraphEdge> {
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407) | 1;
+    let mut next = move || {
+        // xorshift64*
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545F4914F6CDD1D)
+    };
+
+    let mut edges = Vec::with_capacity(vertex_count * avg_out_degree);
+    for source in 0..vertex_count.saturating_sub(1) {
+        let remaining = vertex_count - source - 1;
+        let degree = avg_out_degree.min(remaining).max(1);
+        for _ in 0..degree {
+            let target = source + 1 + (next() as usize % remaining);
+            edges.push(GraphEdge {
+                source_id: format!("bench_{}", source),
+                target_id: format!("bench_{}", target),
+                weight: 1.0,
+                edge_type: "BENCH".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    edges
+}
+
+/// Результат сравнения стратегии назначения слоёв
+#[derive(Debug, Clone)]
+pub struct LayeringBenchReport {
+    pub strategy: LayeringStrategy,
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub time: Duration,
+    pub layers: usize,
+    pub max_layer_width: usize,
+    pub total_edge_length: f64,
+}
+
+/// Сравнение стратегий назначения слоёв на синтетических DAG
+pub async fn run_layering_bench(
+    sizes: &[usize],
+    avg_out_degree: usize,
+    max_layer_width: usize,
+) -> Result<Vec<LayeringBenchReport>> {
+    let strategies = [
+        LayeringStrategy::Bfs,
+        LayeringStrategy::CoffmanGraham,
+        LayeringStrategy::NetworkSimplex,
+    ];
+    let mut reports = Vec::with_capacity(sizes.len() * strategies.len());
+
+    for &size in sizes {
+        let edges = synthetic_dag(size, avg_out_degree, 42);
+        let mut builder = GraphBuilder::new().deterministic(true);
+        for edge in &edges {
+            builder.add_edge(edge.source_id.clone(), edge.target_id.clone(), edge.weight)?;
+        }
+        let graph = builder.build()?;
+
+        for strategy in strategies {
+            let start = Instant::now();
+            let layer_map = assign_layers(&graph, strategy, max_layer_width).await?;
+            let time = start.elapsed();
+
+            let mut widths: std::collections::HashMap<i32, usize> = std::collections::HashMap::new();
+            for &layer in layer_map.values() {
+                *widths.entry(layer).or_insert(0) += 1;
+            }
+
+            let report = LayeringBenchReport {
+                strategy,
+                vertex_count: graph.vertex_count(),
+                edge_count: graph.edge_count(),
+                time,
+                layers: widths.len(),
+                max_layer_width: widths.values().copied().max().unwrap_or(0),
+                total_edge_length: total_edge_length(&graph, &layer_map),
+            };
+            print_layering_report(&report);
+            reports.push(report);
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Результат сравнения представлений связей графа
+#[derive(Debug, Clone)]
+pub struct StorageBenchReport {
+    pub csr: bool,
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub build_time: Duration,
+    pub storage_bytes: usize,
+    pub traversal_time: Duration,
+
+    /// Сумма весов, прочитанных при обходе (одинакова для обоих представлений)
+    pub weight_sum: f64,
+}
+
+/// Сравнение списков смежности и CSR на синтетических DAG
+///
+/// Обход проходит исходящие связи всех вершин и читает вес каждой связи —
+/// так же, как это делают назначение слоёв и network simplex.
+pub fn run_graph_storage_bench(sizes: &[usize], avg_out_degree: usize) -> Result<Vec<StorageBenchReport>> {
+    let mut reports = Vec::with_capacity(sizes.len() * 2);
+
+    for &size in sizes {
+        let edges = synthetic_dag(size, avg_out_degree, 42);
+        for csr in [false, true] {
+            let start = Instant::now();
+            let mut builder = GraphBuilder::new()
+                .deterministic(true)
+                .csr_threshold(if csr { 0 } else { usize::MAX });
+            for edge in &edges {
+                builder.ingest_edge(&edge.source_id, &edge.target_id, edge.weight);
+            }
+            let graph = builder.build()?;
+            let build_time = start.elapsed();
+
+            let start = Instant::now();
+            let weight_sum = traverse_weights(&graph);
+            let report = StorageBenchReport {
+                csr: graph.is_csr(),
+                vertex_count: graph.vertex_count(),
+                edge_count: graph.edge_count(),
+                build_time,
+                storage_bytes: graph.edge_storage_bytes(),
+                traversal_time: start.elapsed(),
+                weight_sum,
+            };
+            print_storage_report(&report);
+            reports.push(report);
+        }
+    }
+
+    Ok(reports)
+}
+
+/// Сумма весов всех связей через исходящие списки
+fn traverse_weights(graph: &Graph) -> f64 {
+    let mut sum = 0.0;
+    for vertex in graph.vertices() {
+        if let Some(targets) = graph.get_outgoing_edges(vertex) {
+            for target in targets {
+                sum += graph.get_edge_weight(vertex, target).unwrap_or(0.0) as f64;
+            }
+        }
+    }
+    sum
+}
+
+/// Вывод сравнения представлений связей
+fn print_storage_report(report: &StorageBenchReport) {
+    println!(
+        "🗃️ {}: {} вершин / {} связей — построение {:.2?}, связи {:.1} МБ, обход {:.2?}",
+        if report.csr { "CSR" } else { "Списки смежности" },
+        report.vertex_count,
+        report.edge_count,
+        report.build_time,
+        report.storage_bytes as f64 / (1024.0 * 1024.0),
+        report.traversal_time
+    );
+}
+
+/// Вывод сравнения стратегий
+fn print_layering_report(report: &LayeringBenchReport) {
+    println!(
+        "📐 {:?}: {} вершин / {} связей за {:.2?} — слоёв {}, макс. ширина {}, длина связей {:.0}",
+        report.strategy,
+        report.vertex_count,
+        report.edge_count,
+        report.time,
+        report.layers,
+        report.max_layer_width,
+        report.total_edge_length
+    );
+}
+
+/// Вывод отчёта
+fn print_report(report: &GrpcBenchReport) {
+    println!("📈 {} вершин / {} связей", report.vertex_count, report.edge_count);
+    println!("   - Запросов: {} (ошибок: {})", report.requests, report.failures);
+    println!("   - Общее время: {:.2?}", report.total_time);
+    println!("   - Пропускная способность: {:.2} запросов/сек", report.throughput());
+    println!(
+        "   - Задержка: p50={:.2?} p90={:.2?} p99={:.2?} max={:.2?}",
+        report.p50, report.p90, report.p99, report.max
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_dag_is_acyclic_and_deterministic() {
+        let a = synthetic_dag(100, 3, 42);
+        let b = synthetic_dag(100, 3, 42);
+
+        assert_eq!(a.len(), b.len());
+        for (ea, eb) in a.iter().zip(b.iter()) {
+            assert_eq!(ea.source_id, eb.source_id);
+            assert_eq!(ea.target_id, eb.target_id);
+
+            let s: usize = ea.source_id["bench_".len()..].parse().unwrap();
+            let t: usize = ea.target_id["bench_".len()..].parse().unwrap();
+            assert!(s < t);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_network_simplex_shortens_edges() -> Result<()> {
+        let reports = run_layering_bench(&[200], 3, 0).await?;
+        let length = |strategy| {
+            reports
+                .iter()
+                .find(|r| r.strategy == strategy)
+                .map(|r| r.total_edge_length)
+                .unwrap()
+        };
+
+        assert!(length(LayeringStrategy::NetworkSimplex) <= length(LayeringStrategy::Bfs));
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_storage_bench_agrees() -> Result<()> {
+        let reports = run_graph_storage_bench(&[300], 4)?;
+        assert_eq!(reports.len(), 2);
+        let (lists, csr) = (&reports[0], &reports[1]);
+        assert!(!lists.csr && csr.csr);
+        assert_eq!(lists.edge_count, csr.edge_count);
+        assert_eq!(lists.weight_sum, csr.weight_sum);
+        assert!(csr.storage_bytes < lists.storage_bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 0.5), Duration::from_millis(51));
+        assert_eq!(percentile(&latencies, 0.99), Duration::from_millis(99));
+        assert_eq!(percentile(&[], 0.5), Duration::ZERO);
+    }
+}
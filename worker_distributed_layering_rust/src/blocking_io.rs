@@ -0,0 +1,41 @@
+/*!
+# Блокирующий ввод-вывод вне асинхронных потоков
+
+Сериализация и запись больших экспортов занимают сотни миллисекунд. Если
+выполнять их прямо в async-задаче, поток runtime простаивает для остальных
+задач, и параллельные gRPC запросы получают всплески задержки.
+
+`run_blocking` выполняет такую работу через `tokio::task::block_in_place`:
+runtime переносит остальные задачи этого потока на другие потоки, а данные
+прогона можно передавать по ссылке, без копирования для `spawn_blocking`.
+В однопоточном runtime (тесты) и вне runtime работа выполняется на месте.
+*/
+
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Выполнение блокирующей работы без занятия потока async runtime
+pub fn run_blocking<T>(work: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(work)
+        }
+        _ => work(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_blocking_outside_runtime() {
+        assert_eq!(run_blocking(|| 2 + 2), 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_run_blocking_in_multi_thread_runtime() {
+        let data = vec![1, 2, 3];
+        let sum: i32 = run_blocking(|| data.iter().sum());
+        assert_eq!(sum, 6);
+    }
+}
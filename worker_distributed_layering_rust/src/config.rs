@@ -5,9 +5,12 @@
 */
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use anyhow::Result;
 
+use crate::sinks::SinkConfig;
+
 /// Основная конфигурация сервиса
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -28,6 +31,230 @@ pub struct Config {
     
     /// Конфигурация метрик
     pub metrics: MetricsConfig,
+    
+    /// Жёсткие ограничения размера графа
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    
+    /// Укладка по временным окнам (режим time-slices)
+    #[serde(default)]
+    pub time_slices: TimeSlicesConfig,
+    
+    /// Приёмники результатов укладки
+    #[serde(default)]
+    pub output: OutputConfig,
+    
+    /// Артефакты задач (экспорт, отчёт валидации, метрики качества)
+    #[serde(default)]
+    pub artifacts: ArtifactsConfig,
+}
+
+/// Каталоги артефактов задач и политика их хранения
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactsConfig {
+    /// Сохранять артефакты задач
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Корневой каталог; артефакты задачи лежат в `{dir}/{tenant}/{job_id}/`
+    #[serde(default = "default_artifacts_dir")]
+    pub dir: String,
+    
+    /// Сколько последних задач хранить на арендатора (0 — без ограничения)
+    #[serde(default = "default_artifacts_max_jobs")]
+    pub max_jobs: usize,
+    
+    /// Максимальный возраст артефактов (часы, 0 — без ограничения)
+    #[serde(default = "default_artifacts_max_age_hours")]
+    pub max_age_hours: u64,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_artifacts_dir(),
+            max_jobs: default_artifacts_max_jobs(),
+            max_age_hours: default_artifacts_max_age_hours(),
+        }
+    }
+}
+
+fn default_artifacts_dir() -> String {
+    "artifacts".to_string()
+}
+
+fn default_artifacts_max_jobs() -> usize {
+    100
+}
+
+fn default_artifacts_max_age_hours() -> u64 {
+    168
+}
+
+/// Куда сохраняются результаты укладки
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    /// Приёмники; результат одного прогона пишется во все по очереди
+    pub sinks: Vec<SinkConfig>,
+    
+    /// Подсказки оформления для экспорта (JSON/SVG/GML)
+    #[serde(default)]
+    pub style: StyleConfig,
+    
+    /// Строить маршруты связей в батчевой укладке и передавать их приёмникам
+    /// (Neo4j пишет их в свойства `path_x`/`path_y` связей, которые читает клиент).
+    /// Для маршрутизации все загруженные связи держатся в памяти
+    #[serde(default)]
+    pub edge_paths: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            sinks: vec![SinkConfig::Neo4j],
+            style: StyleConfig::default(),
+            edge_paths: false,
+        }
+    }
+}
+
+/// Подсказки оформления слоёв в экспорте: цвета полос, чередующийся фон, подписи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleConfig {
+    /// Добавлять подсказки в экспорт
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Цвета слоёв, повторяются по кругу
+    #[serde(default = "default_style_palette")]
+    pub palette: Vec<String>,
+    
+    /// Фон для каждого второго слоя (пустая строка — без чередования)
+    #[serde(default = "default_style_band_background")]
+    pub band_background: String,
+    
+    /// Свойство Article с годом публикации для подписей слоёв ("1998–2004");
+    /// без него слои подписываются номерами
+    #[serde(default)]
+    pub year_property: Option<String>,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            palette: default_style_palette(),
+            band_background: default_style_band_background(),
+            year_property: None,
+        }
+    }
+}
+
+fn default_style_palette() -> Vec<String> {
+    ["#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#edc948", "#b07aa1", "#9c755f"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+}
+
+fn default_style_band_background() -> String {
+    "#f4f4f4".to_string()
+}
+
+/// Конфигурация укладки по временным окнам
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSlicesConfig {
+    /// Свойство Article со временем публикации (год)
+    pub time_property: String,
+    
+    /// Ширина окна (лет)
+    pub window_years: i32,
+    
+    /// Каждое окно включает все предыдущие связи
+    pub cumulative: bool,
+    
+    /// Файл с результатом (JSON с кадрами для анимации)
+    pub output_path: String,
+}
+
+impl Default for TimeSlicesConfig {
+    fn default() -> Self {
+        Self {
+            time_property: "publication_time".to_string(),
+            window_years: 5,
+            cumulative: true,
+            output_path: "layout_time_slices.json".to_string(),
+        }
+    }
+}
+
+/// Жёсткие ограничения размера графа (0 — без ограничения)
+///
+/// Запросы и загрузки из БД сверх лимитов отклоняются до начала тяжёлых вычислений.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Максимальное количество вершин
+    pub max_vertices: usize,
+    
+    /// Максимальное количество связей
+    pub max_edges: usize,
+    
+    /// Глубина догрузки связей сдвинутых вершин в инкрементальной укладке;
+    /// при более длинном каскаде сдвигов выполняется полная укладка
+    #[serde(default = "default_incremental_max_depth")]
+    pub incremental_max_depth: usize,
+    
+    /// Сдвинутых вершин в инкрементальной укладке, сверх которых выполняется полная
+    #[serde(default = "default_incremental_max_vertices")]
+    pub incremental_max_vertices: usize,
+}
+
+fn default_incremental_max_depth() -> usize {
+    32
+}
+
+fn default_incremental_max_vertices() -> usize {
+    100_000
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_vertices: 50_000_000,
+            max_edges: 200_000_000,
+            incremental_max_depth: default_incremental_max_depth(),
+            incremental_max_vertices: default_incremental_max_vertices(),
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Проверка количества связей
+    pub fn check_edges(&self, edges: usize) -> Result<()> {
+        if self.max_edges > 0 && edges > self.max_edges {
+            return Err(anyhow::anyhow!(
+                "Граф слишком большой: {} связей при лимите limits.max_edges = {}. \
+                 Используйте выборку (neo4j.sample_rate / neo4j.max_edges) или потоковый режим ComputeLayoutStreaming",
+                edges,
+                self.max_edges
+            ));
+        }
+        Ok(())
+    }
+    
+    /// Проверка количества вершин
+    pub fn check_vertices(&self, vertices: usize) -> Result<()> {
+        if self.max_vertices > 0 && vertices > self.max_vertices {
+            return Err(anyhow::anyhow!(
+                "Граф слишком большой: {} вершин при лимите limits.max_vertices = {}. \
+                 Используйте выборку (neo4j.sample_rate / neo4j.max_edges) или потоковый режим ComputeLayoutStreaming",
+                vertices,
+                self.max_vertices
+            ));
+        }
+        Ok(())
+    }
 }
 
 /// Конфигурация сервера
@@ -50,6 +277,104 @@ pub struct ServerConfig {
     
     /// Размер буфера для streaming
     pub stream_buffer_size: usize,
+
+    /// Язык сообщений логов и ошибок из каталога `messages`
+    #[serde(default)]
+    pub language: crate::messages::Language,
+
+    /// Доля загрузки CPU (0.0 - 1.0), выше которой сервис сообщает NOT_SERVING
+    #[serde(default = "default_health_cpu_threshold")]
+    pub health_cpu_threshold: f32,
+
+    /// Доля занятой памяти (0.0 - 1.0), выше которой сервис сообщает NOT_SERVING
+    #[serde(default = "default_health_memory_threshold")]
+    pub health_memory_threshold: f32,
+
+    /// Количество связей, начиная с которого запрос считается тяжёлым прогоном
+    #[serde(default = "default_heavy_run_edge_threshold")]
+    pub heavy_run_edge_threshold: usize,
+
+    /// Каталог файлов-отметок тяжёлых прогонов, общий для сервера и процессов
+    /// автоукладки (пусто — прогоны видны только своему процессу)
+    #[serde(default)]
+    pub heavy_run_dir: String,
+
+    /// Интервал обновления статуса grpc.health.v1 (секунды)
+    #[serde(default = "default_health_check_interval")]
+    pub health_check_interval: u64,
+
+    /// Максимальный радиус ComputeNeighborhoodLayout
+    #[serde(default = "default_neighborhood_max_radius")]
+    pub neighborhood_max_radius: u32,
+
+    /// Максимальное количество вершин окрестности
+    #[serde(default = "default_neighborhood_max_vertices")]
+    pub neighborhood_max_vertices: usize,
+
+    /// Максимум позиций в чанке ComputeLayoutStreaming
+    #[serde(default = "default_stream_chunk_size")]
+    pub stream_chunk_size: usize,
+
+    /// Ёмкость канала потокового ответа (чанков в буфере до ожидания клиента)
+    #[serde(default = "default_stream_channel_capacity")]
+    pub stream_channel_capacity: usize,
+
+    /// Токены доступа арендаторов (токен → ID арендатора). Если задан хотя бы
+    /// один, арендатор запроса определяется по `authorization: Bearer <токен>`,
+    /// а не по `tenant_id` из запроса; пусто — сервис в доверенной сети
+    #[serde(default)]
+    pub tenant_tokens: HashMap<String, String>,
+}
+
+/// Токены арендаторов без пробелов по краям и с проверенными ID арендаторов
+fn normalized_tenant_tokens(tokens: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    let mut normalized = HashMap::with_capacity(tokens.len());
+    for (token, tenant) in tokens {
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(anyhow::anyhow!("Пустой токен в [server] tenant_tokens"));
+        }
+        if tenant.trim().is_empty() {
+            return Err(anyhow::anyhow!("Пустой ID арендатора в [server] tenant_tokens"));
+        }
+        let tenant = crate::tenant::tenant_id(tenant)?;
+        if normalized.insert(token.to_string(), tenant).is_some() {
+            return Err(anyhow::anyhow!("Повторяющийся токен в [server] tenant_tokens"));
+        }
+    }
+    Ok(normalized)
+}
+
+fn default_health_cpu_threshold() -> f32 {
+    0.9
+}
+
+fn default_health_memory_threshold() -> f32 {
+    0.9
+}
+
+fn default_heavy_run_edge_threshold() -> usize {
+    1_000_000
+}
+
+fn default_health_check_interval() -> u64 {
+    5
+}
+
+fn default_neighborhood_max_radius() -> u32 {
+    3
+}
+
+fn default_neighborhood_max_vertices() -> usize {
+    5000
+}
+
+fn default_stream_chunk_size() -> usize {
+    10_000
+}
+
+fn default_stream_channel_capacity() -> usize {
+    4
 }
 
 /// Конфигурация Neo4j
@@ -58,7 +383,8 @@ pub struct Neo4jConfig {
     /// URI подключения
     pub uri: String,
     
-    /// Имя пользователя
+    /// Имя пользователя (аутентификация только basic: драйвер neo4rs 0.7
+    /// не поддерживает токены SSO и Kerberos)
     pub user: String,
     
     /// Пароль
@@ -84,11 +410,242 @@ pub struct Neo4jConfig {
 
     /// Количество параллельных транзакций сохранения
     pub save_parallelism: usize,
+
+    /// Адаптивный размер батча загрузки (уменьшение при таймаутах, рост на быстрых батчах)
+    #[serde(default = "default_adaptive_batch_size")]
+    pub adaptive_batch_size: bool,
+
+    /// Минимальный размер батча при адаптации
+    #[serde(default = "default_min_batch_size")]
+    pub min_batch_size: usize,
+
+    /// Максимальный размер батча при адаптации
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+
+    /// Батч быстрее этого порога (мс) считается быстрым, и размер батча растёт
+    #[serde(default = "default_fast_batch_ms")]
+    pub fast_batch_ms: u64,
+
+    /// Загружаемые типы связей с множителями веса (пусто — все типы с весом 1.0)
+    #[serde(default)]
+    pub relationship_types: Vec<RelationshipTypeConfig>,
+
+    /// Доля загружаемых связей для предварительных укладок (0.0..=1.0, 1.0 — все связи)
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+
+    /// Максимальное количество загружаемых связей (0 — без ограничения)
+    #[serde(default)]
+    pub max_edges: usize,
+
+    /// Файл карантина для позиций из батчей, которые не удалось сохранить
+    #[serde(default = "default_save_quarantine_path")]
+    pub save_quarantine_path: String,
+
+    /// Сохранять степени и суммарные веса связей статей (`in_degree`, `out_degree`,
+    /// `in_weight`, `out_weight`) в тех же батчах, что и координаты; по неполным
+    /// связям (`sample_rate` < 1 или срабатывание `max_edges`) агрегаты не пишутся
+    #[serde(default)]
+    pub save_vertex_aggregates: bool,
+
+    /// Пагинация батчевой загрузки связей (Auto — Keyset при заданном
+    /// `keyset_property`, иначе Skip)
+    #[serde(default)]
+    pub pagination: EdgePagination,
+
+    /// Свойство связи с range-индексом для keyset-пагинации (пусто — `elementId(r)`,
+    /// для которого индекса нет: каждый батч сортирует все связи); повторы значений
+    /// различаются по `elementId(r)`, связи без этого свойства не загружаются
+    #[serde(default)]
+    pub keyset_property: String,
+
+    /// Свойство Article с годом публикации для слоёв по годам (`layering = "ByProperty"`)
+    #[serde(default = "default_year_property")]
+    pub year_property: String,
+
+    /// Количество частей связей (диапазонов `id(r)`), читаемых параллельно по
+    /// отдельным соединениям (1 — один курсор; при `algorithms.deterministic` всегда 1)
+    #[serde(default = "default_load_parallelism")]
+    pub load_parallelism: usize,
+
+    /// Атомарное сохранение: координаты пишутся во временные свойства `*_new`
+    /// и переключаются одной транзакцией (при сбое граф не меняется)
+    #[serde(default)]
+    pub atomic_save: bool,
+
+    /// Повторные попытки запросов к Neo4j
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// TLS подключения (схемы `+s` / `+ssc` в `uri` или явный режим проверки)
+    #[serde(default)]
+    pub tls: Neo4jTlsConfig,
+}
+
+/// TLS подключения к Neo4j
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Neo4jTlsConfig {
+    /// Проверка сертификата сервера (Auto — по схеме `uri`)
+    #[serde(default)]
+    pub verify: TlsVerifyMode,
+
+    /// PEM с корневым сертификатом для проверки сервера (пусто — системные корни)
+    #[serde(default)]
+    pub ca_cert_path: String,
+}
+
+/// Режим проверки сертификата сервера Neo4j
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsVerifyMode {
+    /// Как задано схемой `uri`: `+s` — Full, `+ssc` — TrustAll, без суффикса — Disabled
+    Auto,
+    /// Цепочка сертификатов и имя хоста проверяются (схема `+s`)
+    Full,
+    /// Шифрование без проверки сертификата, для самоподписанных (схема `+ssc`)
+    TrustAll,
+    /// Без TLS
+    Disabled,
+}
+
+impl Default for TlsVerifyMode {
+    fn default() -> Self {
+        TlsVerifyMode::Auto
+    }
+}
+
+/// Политика повторов запросов к Neo4j (см. `retry::RetryPolicy`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Максимальное число попыток одной операции
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Задержка перед второй попыткой (мс), дальше удваивается
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+
+    /// Потолок задержки между попытками (мс)
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+
+    /// Доля задержки, на которую она случайно уменьшается (0.0..=1.0)
+    #[serde(default = "default_retry_jitter")]
+    pub jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            base_delay_ms: default_retry_base_delay_ms(),
+            max_delay_ms: default_retry_max_delay_ms(),
+            jitter: default_retry_jitter(),
+        }
+    }
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    500
+}
+
+fn default_retry_max_delay_ms() -> u64 {
+    30_000
+}
+
+fn default_retry_jitter() -> f64 {
+    0.2
+}
+
+fn default_load_parallelism() -> usize {
+    1
+}
+
+fn default_year_property() -> String {
+    "year".to_string()
+}
+
+/// Пагинация батчевой загрузки связей из Neo4j
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EdgePagination {
+    /// Keyset, если задан `keyset_property`, иначе Skip: keyset по `elementId(r)`
+    /// без индекса сортирует все связи в каждом батче
+    Auto,
+    /// Следующий батч начинается после ключа последней связи (`WHERE key > $after`);
+    /// требует `keyset_property` со range-индексом
+    Keyset,
+    /// SKIP/LIMIT: каждый батч заново проходит пропущенные строки
+    Skip,
+}
+
+impl Default for EdgePagination {
+    fn default() -> Self {
+        EdgePagination::Auto
+    }
+}
+
+impl EdgePagination {
+    /// Пагинация с учётом `keyset_property` (Auto заменяется на Keyset или Skip)
+    pub fn resolve(self, keyset_property: &str) -> Self {
+        match self {
+            EdgePagination::Auto if keyset_property.is_empty() => EdgePagination::Skip,
+            EdgePagination::Auto => EdgePagination::Keyset,
+            other => other,
+        }
+    }
+}
+
+fn default_save_quarantine_path() -> String {
+    "layout_save_quarantine.json".to_string()
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+/// Тип связи для загрузки и его множитель веса
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipTypeConfig {
+    /// Имя типа связи в Neo4j (например, BIBLIOGRAPHIC_LINK)
+    pub name: String,
+
+    /// Множитель веса связи (например, прямое цитирование 1.0, соавторство 0.3)
+    #[serde(default = "default_relationship_weight")]
+    pub weight: f32,
+}
+
+fn default_relationship_weight() -> f32 {
+    1.0
+}
+
+fn default_adaptive_batch_size() -> bool {
+    true
+}
+
+fn default_min_batch_size() -> usize {
+    500
+}
+
+fn default_max_batch_size() -> usize {
+    50_000
+}
+
+fn default_fast_batch_ms() -> u64 {
+    2_000
 }
 
 /// Конфигурация алгоритмов укладки
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlgorithmConfig {
+    /// Профиль масштаба: задаёт размеры блоков и отступы ниже
+    /// (`custom` — значения берутся как есть)
+    #[serde(default)]
+    pub scale_profile: ScaleProfile,
+
     /// Размеры блоков
     pub block_width: f32,
     pub block_height: f32,
@@ -108,6 +665,529 @@ pub struct AlgorithmConfig {
     
     /// Стратегия оптимизации
     pub optimization_strategy: OptimizationStrategy,
+
+    /// Распределение вершин по уровням внутри слоя
+    #[serde(default)]
+    pub level_packing: LevelPackingMode,
+
+    /// Форма размещения вершин в слое
+    #[serde(default)]
+    pub placement_mode: PlacementModeConfig,
+
+    /// Порядок вершин с одинаковым слоем (детерминированный tie-breaking)
+    #[serde(default)]
+    pub tie_break: TieBreakMode,
+
+    /// Порядок вершин в слое по средней y соседей в предыдущих слоях (поверх tie-break)
+    #[serde(default)]
+    pub centroid_ordering: bool,
+
+    /// Слои больше этого размера упорядочиваются приближённо (0 — всегда точно)
+    #[serde(default = "default_approximate_layer_threshold")]
+    pub approximate_layer_threshold: usize,
+
+    /// Вертикальная балансировка слоёв вместо выравнивания по верху
+    #[serde(default)]
+    pub balance_layers: bool,
+
+    /// Куда сдвигать слой при балансировке
+    #[serde(default)]
+    pub layer_balance: LayerBalanceMode,
+
+    /// Подробность диагностики валидации входных связей
+    #[serde(default)]
+    pub validation_verbosity: ValidationVerbosityMode,
+
+    /// Сколько примеров связей выводить в режиме Detailed
+    #[serde(default = "default_validation_samples")]
+    pub validation_samples: usize,
+
+    /// Укладывать несвязные компоненты независимо и упаковывать их рядом
+    #[serde(default = "default_pack_components")]
+    pub pack_components: bool,
+
+    /// Горизонтальный зазор между компонентами (пиксели)
+    #[serde(default = "default_component_gap")]
+    pub component_gap: f32,
+
+    /// Укладывать компоненты параллельно
+    #[serde(default = "default_pack_components")]
+    pub parallel_components: bool,
+
+    /// Число колонок сетки слотов для компонент (0 — упаковка подряд)
+    #[serde(default)]
+    pub component_grid_columns: usize,
+
+    /// Ширина слота сетки (пиксели)
+    #[serde(default = "default_component_cell_width")]
+    pub component_cell_width: f32,
+
+    /// Высота слота сетки (пиксели)
+    #[serde(default = "default_component_cell_height")]
+    pub component_cell_height: f32,
+
+    /// Явные начала компонент: наименьший ID вершины компоненты -> [x, y]
+    #[serde(default)]
+    pub component_origins: HashMap<String, [f32; 2]>,
+
+    /// Размеры блоков отдельных вершин: ID -> [ширина, высота]
+    #[serde(default)]
+    pub vertex_sizes: HashMap<String, [f32; 2]>,
+
+    /// Вершины, закреплённые редактором в заданных координатах: ID -> [x, y]
+    #[serde(default)]
+    pub vertex_anchors: HashMap<String, [f32; 2]>,
+
+    /// Свойства Article с шириной/высотой блока (загружаются из Neo4j при пакетной укладке)
+    #[serde(default)]
+    pub vertex_width_property: Option<String>,
+    #[serde(default)]
+    pub vertex_height_property: Option<String>,
+
+    /// Детерминированный режим: упорядоченная загрузка, отсортированные вершины,
+    /// выборка связей по seed вместо rand()
+    #[serde(default)]
+    pub deterministic: bool,
+
+    /// Seed выборки связей из Neo4j (`sample_rate`, резервуар `max_edges`);
+    /// сами алгоритмы укладки случайных шагов не делают
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+
+    /// Алгоритм распределения вершин по слоям
+    #[serde(default)]
+    pub layering: LayeringMode,
+
+    /// Максимальное количество вершин в слое (0 — без ограничения)
+    #[serde(default)]
+    pub max_layer_width: usize,
+
+    /// Сжатие слоёв после батчевого назначения: пустые слои удаляются
+    #[serde(default)]
+    pub compress_layers: bool,
+
+    /// Слои с меньшим числом вершин сливаются с соседними, если их не соединяют связи
+    /// (0 — только удаление пустых слоёв)
+    #[serde(default)]
+    pub merge_layers_below: usize,
+
+    /// Повторный прогон с другой стратегией слоёв при вырожденной укладке
+    /// (см. `algorithms::degenerate`)
+    #[serde(default = "default_degenerate_fallback")]
+    pub degenerate_fallback: bool,
+
+    /// Минимальное число вершин, при котором укладка проверяется на вырожденность
+    #[serde(default = "default_degenerate_min_vertices")]
+    pub degenerate_min_vertices: usize,
+
+    /// Доля вершин в одном слое, выше которой укладка вырождена
+    #[serde(default = "default_degenerate_single_layer_share")]
+    pub degenerate_single_layer_share: f64,
+
+    /// Доля вершин в трёх самых заполненных слоях, выше которой укладка вырождена
+    #[serde(default = "default_degenerate_top_layers_share")]
+    pub degenerate_top_layers_share: f64,
+
+    /// Закреплённые слои: ID статьи -> номер слоя (например, 0 для основополагающих работ).
+    /// Действуют в auto-layout, временных окнах и gRPC; пока они заданы, стратегия
+    /// слоёв и `max_layer_width` в gRPC не применяются
+    #[serde(default)]
+    pub layer_constraints: HashMap<String, i32>,
+
+    /// Известные слои (например, из предыдущего запуска): ID статьи -> минимальный слой.
+    /// Укладка продолжает имеющуюся раскладку, а не считает её с нуля
+    #[serde(default)]
+    pub layer_lower_bounds: HashMap<String, i32>,
+
+    /// Длинные связи разбиваются на цепочки dummy-вершин, которые занимают
+    /// собственные места в промежуточных слоях (иначе маршруты строятся по готовым позициям)
+    #[serde(default)]
+    pub dummy_vertices: bool,
+
+    /// Предел числа dummy-вершин: граф, которому нужно больше, укладывается без них
+    #[serde(default = "default_max_dummy_vertices")]
+    pub max_dummy_vertices: usize,
+
+    /// Длинные связи обходят блоки промежуточных слоёв (без dummy-вершин)
+    #[serde(default)]
+    pub avoid_vertices: bool,
+
+    /// Минимальное расстояние между дорожками связей, проходящих через один зазор слоя
+    /// (пиксели, 0 — связи могут проходить на одной высоте; с avoid_vertices или dummy_vertices)
+    #[serde(default)]
+    pub edge_channel_spacing: f32,
+
+    /// Свободные точки привязки подписей над, под и справа от блоков, не задетые связями
+    #[serde(default)]
+    pub annotation_anchors: bool,
+
+    /// Отступ точек привязки подписей от блока (пиксели)
+    #[serde(default = "default_annotation_margin")]
+    pub annotation_margin: f32,
+
+    /// Входящие связи вершин минимум с таким числом входящих связей сводятся в общий ствол
+    /// (0 — без связывания)
+    #[serde(default)]
+    pub bundle_min_fan_in: usize,
+
+    /// Self-loops сохраняются и получают маршрут-петлю у блока (иначе отбрасываются)
+    #[serde(default)]
+    pub allow_self_loops: bool,
+
+    /// Параллельные связи сохраняются, у каждой свой маршрут (иначе дубликаты отбрасываются)
+    #[serde(default)]
+    pub allow_parallel_edges: bool,
+
+    /// Транзитивная редукция: избыточные связи A -> C при наличии пути A -> B -> C удаляются
+    /// (число удалённых — в отчёте валидации)
+    #[serde(default)]
+    pub transitive_reduction: bool,
+
+    /// Граф до этого числа вершин редуцируется точно, больший — эвристикой по коротким путям
+    #[serde(default = "default_transitive_reduction_exact_max_vertices")]
+    pub transitive_reduction_exact_max_vertices: usize,
+
+    /// Концы связей распределяются по сторонам блока в порядке y соседей, а не сходятся в центре
+    #[serde(default)]
+    pub edge_ports: bool,
+
+    /// Допуск упрощения маршрутов по Дугласу–Пекеру (пиксели, 0 — все точки сохраняются)
+    #[serde(default)]
+    pub path_simplification_tolerance: f32,
+
+    /// Сглаживание маршрутов кубическими кривыми Безье (0 — ломаные, 1 — сплайн Катмулла–Рома)
+    #[serde(default)]
+    pub edge_smoothing: f32,
+
+    /// Сторона ячейки пространственного индекса итоговых позиций (0 — индекс не строится)
+    #[serde(default)]
+    pub spatial_index_cell_size: f32,
+}
+
+fn default_seed() -> u64 {
+    42
+}
+
+fn default_pack_components() -> bool {
+    true
+}
+
+fn default_degenerate_fallback() -> bool {
+    true
+}
+
+fn default_degenerate_min_vertices() -> usize {
+    crate::algorithms::degenerate::DegenerateThresholds::default().min_vertices
+}
+
+fn default_degenerate_single_layer_share() -> f64 {
+    crate::algorithms::degenerate::DegenerateThresholds::default().single_layer_share
+}
+
+fn default_degenerate_top_layers_share() -> f64 {
+    crate::algorithms::degenerate::DegenerateThresholds::default().top_layers_share
+}
+
+fn default_component_gap() -> f32 {
+    200.0
+}
+
+fn default_max_dummy_vertices() -> usize {
+    1_000_000
+}
+
+fn default_annotation_margin() -> f32 {
+    12.0
+}
+
+fn default_transitive_reduction_exact_max_vertices() -> usize {
+    10_000
+}
+
+fn default_approximate_layer_threshold() -> usize {
+    100_000
+}
+
+fn default_validation_samples() -> usize {
+    5
+}
+
+fn default_component_cell_width() -> f32 {
+    4000.0
+}
+
+fn default_component_cell_height() -> f32 {
+    4000.0
+}
+
+impl AlgorithmConfig {
+    /// Подробность валидации входных связей
+    pub fn validation_verbosity(&self) -> crate::algorithms::validation::ValidationVerbosity {
+        use crate::algorithms::validation::ValidationVerbosity;
+        match self.validation_verbosity {
+            ValidationVerbosityMode::Quiet => ValidationVerbosity::Quiet,
+            ValidationVerbosityMode::Summary => ValidationVerbosity::Summary,
+            ValidationVerbosityMode::Detailed => ValidationVerbosity::Detailed {
+                samples: self.validation_samples,
+            },
+        }
+    }
+
+    /// Размещение упакованных компонент: сетка, если задано число колонок
+    pub fn component_slots(&self) -> crate::algorithms::component_packing::ComponentSlots {
+        use crate::algorithms::component_packing::ComponentSlots;
+        if self.component_grid_columns == 0 {
+            return ComponentSlots::Sequential;
+        }
+        ComponentSlots::Grid {
+            columns: self.component_grid_columns,
+            cell_width: self.component_cell_width,
+            cell_height: self.component_cell_height,
+        }
+    }
+
+    /// Пороги вырожденной укладки (None, если повторный прогон отключён)
+    pub fn degenerate_thresholds(&self) -> Option<crate::algorithms::degenerate::DegenerateThresholds> {
+        self.degenerate_fallback.then(|| crate::algorithms::degenerate::DegenerateThresholds {
+            min_vertices: self.degenerate_min_vertices,
+            single_layer_share: self.degenerate_single_layer_share,
+            top_layers_share: self.degenerate_top_layers_share,
+            ..Default::default()
+        })
+    }
+}
+
+/// Именованный профиль масштаба: перевод сетки (слой, уровень) в пиксели
+///
+/// Профиль разрешается один раз при загрузке конфигурации, поэтому экспорт,
+/// ответы gRPC и координаты в Neo4j используют одни и те же размеры, а имя
+/// профиля сохраняется в `LayoutMeta` и метаданных ответа.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleProfile {
+    /// Размеры из `block_width`/`block_height`/`horizontal_gap`/`vertical_gap`
+    Custom,
+    /// Экран: 160×80, отступы 80/50
+    Web,
+    /// Печать: 240×120, отступы 120/75
+    Print,
+    /// Обзор больших графов: 80×40, отступы 40/25
+    Dense,
+}
+
+impl Default for ScaleProfile {
+    fn default() -> Self {
+        ScaleProfile::Custom
+    }
+}
+
+/// Размеры блока и отступы в пикселях
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleDimensions {
+    pub block_width: f32,
+    pub block_height: f32,
+    pub horizontal_gap: f32,
+    pub vertical_gap: f32,
+}
+
+impl ScaleProfile {
+    /// Размеры профиля (`None` для `Custom`)
+    pub fn dimensions(self) -> Option<ScaleDimensions> {
+        let (block_width, block_height, horizontal_gap, vertical_gap) = match self {
+            ScaleProfile::Custom => return None,
+            ScaleProfile::Web => (160.0, 80.0, 80.0, 50.0),
+            ScaleProfile::Print => (240.0, 120.0, 120.0, 75.0),
+            ScaleProfile::Dense => (80.0, 40.0, 40.0, 25.0),
+        };
+        Some(ScaleDimensions { block_width, block_height, horizontal_gap, vertical_gap })
+    }
+
+    /// Имя профиля, как в конфигурации
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleProfile::Custom => "custom",
+            ScaleProfile::Web => "web",
+            ScaleProfile::Print => "print",
+            ScaleProfile::Dense => "dense",
+        }
+    }
+}
+
+/// Режим упаковки уровней внутри слоя
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum LevelPackingMode {
+    /// Одна вершина на уровень
+    OnePerLevel,
+    /// Interval packing: узкие вершины делят уровень
+    Interval,
+}
+
+impl Default for LevelPackingMode {
+    fn default() -> Self {
+        LevelPackingMode::OnePerLevel
+    }
+}
+
+impl From<LevelPackingMode> for crate::algorithms::vertex_placement::LevelPacking {
+    fn from(mode: LevelPackingMode) -> Self {
+        use crate::algorithms::vertex_placement::LevelPacking;
+        match mode {
+            LevelPackingMode::OnePerLevel => LevelPacking::OnePerLevel,
+            LevelPackingMode::Interval => LevelPacking::Interval,
+        }
+    }
+}
+
+impl From<LevelPackingMode> for crate::generated::LevelPacking {
+    fn from(mode: LevelPackingMode) -> Self {
+        match mode {
+            LevelPackingMode::OnePerLevel => crate::generated::LevelPacking::OnePerLevel,
+            LevelPackingMode::Interval => crate::generated::LevelPacking::Interval,
+        }
+    }
+}
+
+/// Форма размещения вершин в слое
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum PlacementModeConfig {
+    /// Одна колонка на слой: узко и высоко
+    Column,
+    /// Слой — почти квадратная сетка
+    CompactGrid,
+    /// Колонка, упорядоченная по центроиду предшественников
+    CentroidSorted,
+}
+
+impl Default for PlacementModeConfig {
+    fn default() -> Self {
+        PlacementModeConfig::Column
+    }
+}
+
+impl From<PlacementModeConfig> for crate::algorithms::vertex_placement::PlacementMode {
+    fn from(mode: PlacementModeConfig) -> Self {
+        use crate::algorithms::vertex_placement::PlacementMode;
+        match mode {
+            PlacementModeConfig::Column => PlacementMode::Column,
+            PlacementModeConfig::CompactGrid => PlacementMode::CompactGrid,
+            PlacementModeConfig::CentroidSorted => PlacementMode::CentroidSorted,
+        }
+    }
+}
+
+/// Правило упорядочивания вершин внутри слоя
+///
+/// Все правила при равенстве ключей досортировывают по ID вершины,
+/// поэтому укладка воспроизводима между запусками.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TieBreakMode {
+    /// По ID вершины (лексикографически)
+    VertexId,
+    /// По степени вершины (сначала с большей степенью)
+    Degree,
+    /// По порядку первого появления во входных связях
+    InputOrder,
+}
+
+impl Default for TieBreakMode {
+    fn default() -> Self {
+        TieBreakMode::VertexId
+    }
+}
+
+/// Куда сдвигать слой при вертикальной балансировке
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum LayerBalanceMode {
+    /// Центрировать относительно средней линии рисунка
+    Midline,
+    /// К среднему положению соседей (слои без соседей — по средней линии)
+    Barycenter,
+}
+
+impl Default for LayerBalanceMode {
+    fn default() -> Self {
+        LayerBalanceMode::Midline
+    }
+}
+
+impl From<LayerBalanceMode> for crate::algorithms::vertex_placement::LayerBalance {
+    fn from(mode: LayerBalanceMode) -> Self {
+        use crate::algorithms::vertex_placement::LayerBalance;
+        match mode {
+            LayerBalanceMode::Midline => LayerBalance::Midline,
+            LayerBalanceMode::Barycenter => LayerBalance::Barycenter,
+        }
+    }
+}
+
+/// Подробность диагностики валидации входных связей
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ValidationVerbosityMode {
+    /// Без логов, проверка до первой валидной связи
+    Quiet,
+    /// Только счётчики
+    Summary,
+    /// Счётчики и примеры связей
+    Detailed,
+}
+
+impl Default for ValidationVerbosityMode {
+    fn default() -> Self {
+        ValidationVerbosityMode::Summary
+    }
+}
+
+impl From<TieBreakMode> for crate::algorithms::vertex_placement::TieBreak {
+    fn from(mode: TieBreakMode) -> Self {
+        use crate::algorithms::vertex_placement::TieBreak;
+        match mode {
+            TieBreakMode::VertexId => TieBreak::VertexId,
+            TieBreakMode::Degree => TieBreak::Degree,
+            TieBreakMode::InputOrder => TieBreak::InputOrder,
+        }
+    }
+}
+
+/// Алгоритм распределения вершин по слоям
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum LayeringMode {
+    /// Самый длинный путь от источников (BFS)
+    Bfs,
+    /// Coffman–Graham: ограниченная ширина слоя ценой дополнительных слоёв
+    CoffmanGraham,
+    /// Network simplex: минимальная суммарная длина связей
+    NetworkSimplex,
+    /// По году публикации (`neo4j.year_property`), внутри года — самый длинный путь
+    ByProperty,
+}
+
+impl Default for LayeringMode {
+    fn default() -> Self {
+        LayeringMode::Bfs
+    }
+}
+
+impl From<LayeringMode> for crate::algorithms::vertex_placement::LayeringStrategy {
+    fn from(mode: LayeringMode) -> Self {
+        use crate::algorithms::vertex_placement::LayeringStrategy;
+        match mode {
+            LayeringMode::Bfs => LayeringStrategy::Bfs,
+            LayeringMode::CoffmanGraham => LayeringStrategy::CoffmanGraham,
+            LayeringMode::NetworkSimplex => LayeringStrategy::NetworkSimplex,
+            LayeringMode::ByProperty => LayeringStrategy::ByProperty,
+        }
+    }
+}
+
+impl From<LayeringMode> for crate::generated::LayeringStrategy {
+    fn from(mode: LayeringMode) -> Self {
+        match mode {
+            LayeringMode::Bfs => crate::generated::LayeringStrategy::LayeringBfs,
+            LayeringMode::CoffmanGraham => crate::generated::LayeringStrategy::LayeringCoffmanGraham,
+            LayeringMode::NetworkSimplex => crate::generated::LayeringStrategy::LayeringNetworkSimplex,
+            LayeringMode::ByProperty => crate::generated::LayeringStrategy::LayeringByProperty,
+        }
+    }
 }
 
 /// Стратегия оптимизации
@@ -175,7 +1255,7 @@ pub struct MemoryConfig {
     /// Путь для временных файлов
     pub temp_dir: String,
     
-    /// Использовать memory mapping
+    /// Хранить связи графов от 50M связей в CSR-файле в `temp_dir` (memory mapping)
     pub use_memory_mapping: bool,
     
     /// Размер страницы для memory mapping
@@ -191,7 +1271,7 @@ pub enum MemoryStrategy {
     RamFirst,
     /// Использование SSD кеша
     SsdCache,
-    /// Потоковая обработка
+    /// Потоковая обработка: связи батчевой укладки хранятся на диске в `temp_dir`
     Streaming,
 }
 
@@ -229,9 +1309,39 @@ impl Config {
     /// Загрузка конфигурации из файла
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+        let mut config: Config = toml::from_str(&content)?;
+        config.apply_scale_profile();
+        config.normalize_tenant_tokens()?;
         Ok(config)
     }
+
+    /// Нормализация `[server] tenant_tokens`: пробелы вокруг токенов отбрасываются,
+    /// ID арендаторов приводятся к виду `tenant::tenant_id`
+    ///
+    /// Нормализованный ID становится именем каталога артефактов и меткой метрик,
+    /// поэтому сервер возвращает его как есть.
+    pub fn normalize_tenant_tokens(&mut self) -> Result<()> {
+        self.server.tenant_tokens = normalized_tenant_tokens(&self.server.tenant_tokens)?;
+        Ok(())
+    }
+
+    /// Замена размеров блоков и отступов значениями профиля масштаба
+    ///
+    /// Для `custom` ничего не меняется. Явные размеры отдельных вершин
+    /// (`vertex_sizes`) и закреплённые координаты задаются в пикселях выбранного
+    /// профиля и не пересчитываются.
+    pub fn apply_scale_profile(&mut self) {
+        let profile = self.algorithms.scale_profile;
+        if let Some(dimensions) = profile.dimensions() {
+            self.algorithms.block_width = dimensions.block_width;
+            self.algorithms.block_height = dimensions.block_height;
+            self.algorithms.horizontal_gap = dimensions.horizontal_gap;
+            self.algorithms.vertical_gap = dimensions.vertical_gap;
+            tracing::info!("📐 Профиль масштаба {}: блок {}×{}, отступы {}/{}",
+                           profile.name(), dimensions.block_width, dimensions.block_height,
+                           dimensions.horizontal_gap, dimensions.vertical_gap);
+        }
+    }
     
     /// Конфигурация по умолчанию
     pub fn default() -> Self {
@@ -243,6 +1353,17 @@ impl Config {
                 max_connections: 1000,
                 request_timeout: 300,
                 stream_buffer_size: 1024,
+                language: crate::messages::Language::default(),
+                health_cpu_threshold: default_health_cpu_threshold(),
+                health_memory_threshold: default_health_memory_threshold(),
+                heavy_run_edge_threshold: default_heavy_run_edge_threshold(),
+                heavy_run_dir: String::new(),
+                health_check_interval: default_health_check_interval(),
+                neighborhood_max_radius: default_neighborhood_max_radius(),
+                neighborhood_max_vertices: default_neighborhood_max_vertices(),
+                stream_chunk_size: default_stream_chunk_size(),
+                stream_channel_capacity: default_stream_channel_capacity(),
+                tenant_tokens: HashMap::new(),
             },
             neo4j: Neo4jConfig {
                 uri: "bolt://localhost:7687".to_string(),
@@ -255,8 +1376,25 @@ impl Config {
                 batch_size: 5000,
                 save_batch_size: 1000,
                 save_parallelism: 4,
+                adaptive_batch_size: default_adaptive_batch_size(),
+                min_batch_size: default_min_batch_size(),
+                max_batch_size: default_max_batch_size(),
+                fast_batch_ms: default_fast_batch_ms(),
+                relationship_types: Vec::new(),
+                sample_rate: default_sample_rate(),
+                max_edges: 0,
+                save_quarantine_path: default_save_quarantine_path(),
+                save_vertex_aggregates: false,
+                pagination: EdgePagination::default(),
+                keyset_property: String::new(),
+                year_property: default_year_property(),
+                load_parallelism: default_load_parallelism(),
+                atomic_save: false,
+                retry: RetryConfig::default(),
+                tls: Neo4jTlsConfig::default(),
             },
             algorithms: AlgorithmConfig {
+                scale_profile: ScaleProfile::Custom,
                 block_width: 200.0,
                 block_height: 80.0,
                 horizontal_gap: 40.0,
@@ -265,6 +1403,53 @@ impl Config {
                 max_iterations: 1000,
                 convergence_threshold: 0.001,
                 optimization_strategy: OptimizationStrategy::Balanced,
+                level_packing: LevelPackingMode::OnePerLevel,
+                placement_mode: PlacementModeConfig::Column,
+                tie_break: TieBreakMode::VertexId,
+                centroid_ordering: false,
+                approximate_layer_threshold: default_approximate_layer_threshold(),
+                balance_layers: false,
+                layer_balance: LayerBalanceMode::Midline,
+                validation_verbosity: ValidationVerbosityMode::Summary,
+                validation_samples: default_validation_samples(),
+                pack_components: true,
+                component_gap: 200.0,
+                parallel_components: true,
+                component_grid_columns: 0,
+                component_cell_width: default_component_cell_width(),
+                component_cell_height: default_component_cell_height(),
+                component_origins: HashMap::new(),
+                vertex_sizes: HashMap::new(),
+                vertex_anchors: HashMap::new(),
+                vertex_width_property: None,
+                vertex_height_property: None,
+                deterministic: false,
+                seed: default_seed(),
+                layering: LayeringMode::Bfs,
+                max_layer_width: 0,
+                compress_layers: false,
+                merge_layers_below: 0,
+                degenerate_fallback: default_degenerate_fallback(),
+                degenerate_min_vertices: default_degenerate_min_vertices(),
+                degenerate_single_layer_share: default_degenerate_single_layer_share(),
+                degenerate_top_layers_share: default_degenerate_top_layers_share(),
+                layer_constraints: HashMap::new(),
+                layer_lower_bounds: HashMap::new(),
+                dummy_vertices: false,
+                max_dummy_vertices: default_max_dummy_vertices(),
+                avoid_vertices: false,
+                edge_channel_spacing: 0.0,
+                annotation_anchors: false,
+                annotation_margin: default_annotation_margin(),
+                bundle_min_fan_in: 0,
+                allow_self_loops: false,
+                allow_parallel_edges: false,
+                transitive_reduction: false,
+                transitive_reduction_exact_max_vertices: default_transitive_reduction_exact_max_vertices(),
+                edge_ports: false,
+                path_simplification_tolerance: 0.0,
+                edge_smoothing: 0.0,
+                spatial_index_cell_size: 0.0,
             },
             performance: PerformanceConfig {
                 worker_threads: num_cpus::get(),
@@ -292,6 +1477,10 @@ impl Config {
                 tracing_endpoint: None,
                 detail_level: MetricDetailLevel::Detailed,
             },
+            limits: LimitsConfig::default(),
+            time_slices: TimeSlicesConfig::default(),
+            output: OutputConfig::default(),
+            artifacts: ArtifactsConfig::default(),
         }
     }
     
@@ -304,6 +1493,21 @@ impl Config {
             ));
         }
         
+        // Проверка токенов арендаторов (нормализуются при загрузке)
+        if normalized_tenant_tokens(&self.server.tenant_tokens)? != self.server.tenant_tokens {
+            return Err(anyhow::anyhow!(
+                "[server] tenant_tokens не нормализованы: токены и ID арендаторов без пробелов по краям"
+            ));
+        }
+        
+        // Keyset по elementId(r) без индекса квадратичен по числу связей
+        if self.neo4j.pagination == EdgePagination::Keyset && self.neo4j.keyset_property.trim().is_empty() {
+            return Err(anyhow::anyhow!(
+                "neo4j.pagination = \"Keyset\" требует neo4j.keyset_property со range-индексом \
+                 (без него используйте \"Auto\" или \"Skip\")"
+            ));
+        }
+        
         // Проверка лимитов памяти
         if self.memory.memory_limit_bytes < 1024 * 1024 * 1024 {
             return Err(anyhow::anyhow!(
@@ -318,9 +1522,126 @@ impl Config {
             ));
         }
         
+        // Проверка сетки компонент
+        if self.algorithms.component_grid_columns > 0
+            && (self.algorithms.component_cell_width <= 0.0 || self.algorithms.component_cell_height <= 0.0)
+        {
+            return Err(anyhow::anyhow!(
+                "Размер слота сетки компонент должен быть больше 0"
+            ));
+        }
+        
+        // Проверка размеров блоков вершин
+        if let Some((vertex_id, _)) = self
+            .algorithms
+            .vertex_sizes
+            .iter()
+            .find(|(_, &[width, height])| !(width > 0.0 && height > 0.0))
+        {
+            return Err(anyhow::anyhow!(
+                "Размер блока вершины '{}' должен быть больше 0",
+                vertex_id
+            ));
+        }
+        
+        // Проверка закреплённых координат вершин
+        if let Some((vertex_id, _)) = self
+            .algorithms
+            .vertex_anchors
+            .iter()
+            .find(|(_, &[x, y])| !(x.is_finite() && y.is_finite()))
+        {
+            return Err(anyhow::anyhow!(
+                "Координаты закреплённой вершины '{}' должны быть конечными числами",
+                vertex_id
+            ));
+        }
+        
+        // Проверка отступа точек привязки подписей
+        let margin = self.algorithms.annotation_margin;
+        if !(margin.is_finite() && margin > 0.0) {
+            return Err(anyhow::anyhow!(
+                "Отступ точек привязки подписей должен быть больше 0: {}",
+                margin
+            ));
+        }
+        
+        // Проверка расстояния между дорожками связей
+        let spacing = self.algorithms.edge_channel_spacing;
+        if !(spacing.is_finite() && spacing >= 0.0) {
+            return Err(anyhow::anyhow!(
+                "Расстояние между дорожками связей должно быть неотрицательным: {}",
+                spacing
+            ));
+        }
+        
+        // Проверка допуска упрощения маршрутов
+        let tolerance = self.algorithms.path_simplification_tolerance;
+        if !(tolerance.is_finite() && tolerance >= 0.0) {
+            return Err(anyhow::anyhow!(
+                "Допуск упрощения маршрутов должен быть неотрицательным: {}",
+                tolerance
+            ));
+        }
+        
+        // Проверка сглаживания маршрутов
+        let smoothing = self.algorithms.edge_smoothing;
+        if !(0.0..=1.0).contains(&smoothing) {
+            return Err(anyhow::anyhow!(
+                "Сглаживание маршрутов должно быть от 0 до 1: {}",
+                smoothing
+            ));
+        }
+        
+        // Проверка размера ячейки пространственного индекса
+        let cell_size = self.algorithms.spatial_index_cell_size;
+        if !(cell_size.is_finite() && cell_size >= 0.0) {
+            return Err(anyhow::anyhow!(
+                "Размер ячейки пространственного индекса должен быть неотрицательным: {}",
+                cell_size
+            ));
+        }
+        
+        // Проверка закреплённых слоёв
+        if let Some((article_id, layer)) = self.algorithms.layer_constraints.iter().find(|(_, &layer)| layer < 0) {
+            return Err(anyhow::anyhow!(
+                "Статья {} закреплена за отрицательным слоем {}", article_id, layer
+            ));
+        }
+        if let Some((article_id, layer)) = self.algorithms.layer_lower_bounds.iter().find(|(_, &layer)| layer < 0) {
+            return Err(anyhow::anyhow!(
+                "Нижняя граница слоя статьи {} отрицательна: {}", article_id, layer
+            ));
+        }
+        
         Ok(())
     }
     
+    /// Отпечаток конфигурации (FNV-1a 64 в hex) для сравнения запусков укладки
+    ///
+    /// Учитываются только параметры, от которых зависит укладка: секция
+    /// `algorithms` и выбор связей в `neo4j` (типы и веса, выборка, лимит).
+    /// Подключение, пароль, размеры батчей, приёмники и лимиты на отпечаток не
+    /// влияют. Считается по JSON с отсортированными ключами, поэтому не зависит
+    /// от порядка полей в файле и порядка обхода словарей.
+    pub fn fingerprint(&self) -> Result<String> {
+        let mut layout = serde_json::Map::new();
+        layout.insert("algorithms".to_string(), serde_json::to_value(&self.algorithms)?);
+        layout.insert(
+            "relationship_types".to_string(),
+            serde_json::to_value(&self.neo4j.relationship_types)?,
+        );
+        layout.insert("sample_rate".to_string(), serde_json::to_value(self.neo4j.sample_rate)?);
+        layout.insert("max_edges".to_string(), serde_json::to_value(self.neo4j.max_edges)?);
+        let canonical = serde_json::Value::Object(layout).to_string();
+        let mut hash = 0xcbf29ce484222325u64;
+        for byte in canonical.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        Ok(format!("{:016x}", hash))
+    }
+
     /// Оптимизация конфигурации под конкретную систему
     pub fn optimize_for_system(&mut self) -> Result<()> {
         // Определение доступной памяти
@@ -368,3 +1689,56 @@ impl Default for Config {
         Self::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_covers_only_layout_settings() {
+        let config = Config::default();
+        let fingerprint = config.fingerprint().unwrap();
+        assert_eq!(fingerprint.len(), 16);
+
+        let mut connection = config.clone();
+        connection.neo4j.password = "другой пароль".to_string();
+        connection.neo4j.uri = "bolt+s://db.example.org:7687".to_string();
+        connection.neo4j.batch_size += 1;
+        assert_eq!(connection.fingerprint().unwrap(), fingerprint);
+
+        let mut algorithm = config.clone();
+        algorithm.algorithms.horizontal_gap += 10.0;
+        assert_ne!(algorithm.fingerprint().unwrap(), fingerprint);
+
+        let mut sampled = config;
+        sampled.neo4j.sample_rate = 0.5;
+        assert_ne!(sampled.fingerprint().unwrap(), fingerprint);
+    }
+
+    #[test]
+    fn test_keyset_pagination_requires_property() {
+        assert_eq!(EdgePagination::default().resolve(""), EdgePagination::Skip);
+        assert_eq!(EdgePagination::Auto.resolve("created_at"), EdgePagination::Keyset);
+        assert_eq!(EdgePagination::Skip.resolve("created_at"), EdgePagination::Skip);
+
+        let mut config = Config::default();
+        config.neo4j.pagination = EdgePagination::Keyset;
+        assert!(config.validate().unwrap_err().to_string().contains("keyset_property"));
+        config.neo4j.keyset_property = "created_at".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_tenant_tokens_are_normalized() {
+        let mut config = Config::default();
+        config.server.tenant_tokens.insert(" secret ".to_string(), " acme ".to_string());
+        assert!(config.validate().is_err());
+
+        config.normalize_tenant_tokens().unwrap();
+        assert_eq!(config.server.tenant_tokens.get("secret").map(String::as_str), Some("acme"));
+        assert_eq!(normalized_tenant_tokens(&config.server.tenant_tokens).unwrap(), config.server.tenant_tokens);
+
+        config.server.tenant_tokens.insert("other".to_string(), "../acme".to_string());
+        assert!(config.normalize_tenant_tokens().is_err());
+    }
+}
@@ -3,38 +3,55 @@
 
 Оптимизированные структуры данных для работы с большими графами:
 
-- **Эффективное представление графов** с минимальным использованием памяти
+- **Эффективное представление графов** с минимальным использованием памяти:
+  большие графы хранят связи в CSR (compressed sparse row) вместо списков смежности
+- **Handles вершин** (`VertexId`, u32): UID интернируются один раз, сортировка,
+  longest path и глубина компонент работают с handles, а строки нужны только на
+  границе с Neo4j и protobuf
 - **SIMD-friendly layouts** для векторных операций
 - **Lock-free структуры** для параллельного доступа
-- **Memory-mapped storage** для работы с данными, не помещающимися в RAM
+- **Memory-mapped storage** для работы с данными, не помещающимися в RAM:
+  CSR очень больших графов пишется в файл и читается через mmap (`Graph::to_mmap`),
+  а `GraphBuilder::build_mmap_streamed` раскладывает связи прямо в файл за два прохода
+- **Параллельное построение** (`GraphBuilder::sharded`): связи принимаются из
+  нескольких потоков в шарды по хешу пары, CSR собирается через Rayon
+- **Атрибуты вершин** (год, кластер, название) в столбцах по индексу вершины:
+  их читают стратегии назначения слоёв и размещения
+- **Совместимость с petgraph** (feature `petgraph`): `Graph::to_petgraph` и
+  `From<DiGraph<N, E>>` для алгоритмов petgraph и укладки графов petgraph
 
 */
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use hashbrown::{HashMap, HashSet};
 use smallvec::SmallVec;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Основная структура графа
 #[derive(Debug, Clone)]
 pub struct Graph {
-    /// Вершины графа (ID -> индекс)
-    vertex_map: HashMap<String, usize>,
+    /// Вершины графа: UID <-> `VertexId` (индекс вершины)
+    interner: VertexInterner,
     
-    /// Обратный маппинг (индекс -> ID)
-    vertex_ids: Vec<String>,
+    /// Связи (по одной на пару вершин) с весами: списки смежности или CSR
+    edges: EdgeStorage,
     
-    /// Списки смежности (исходящие связи)
-    adjacency_out: Vec<SmallVec<[usize; 4]>>,
+    /// Веса параллельных связей сверх первой (мультиграф); вес первой — в `edges`
+    parallel_weights: HashMap<(usize, usize), SmallVec<[f32; 1]>>,
     
-    /// Списки смежности (входящие связи)
-    adjacency_in: Vec<SmallVec<[usize; 4]>>,
+    /// Self-loops: индекс вершины -> веса петель. В списки смежности не входят,
+    /// поэтому сортировка, слои и поиск циклов их не видят
+    self_loops: HashMap<usize, SmallVec<[f32; 1]>>,
     
-    /// Веса связей
-    edge_weights: HashMap<(usize, usize), f32>,
-    
-    /// Количество связей
+    /// Количество связей (различных пар вершин)
     edge_count: usize,
     
+    /// Атрибуты вершин (год, кластер, название...) по столбцам
+    attributes: VertexAttributes,
+    
     /// Компоненты связности (кеш)
     components_cache: Option<Vec<Vec<usize>>>,
 }
@@ -43,89 +60,457 @@ impl Graph {
     /// Создание нового графа
     pub fn new() -> Self {
         Self {
-            vertex_map: HashMap::new(),
-            vertex_ids: Vec::new(),
-            adjacency_out: Vec::new(),
-            adjacency_in: Vec::new(),
-            edge_weights: HashMap::new(),
+            interner: VertexInterner::new(),
+            edges: EdgeStorage::default(),
+            parallel_weights: HashMap::new(),
+            self_loops: HashMap::new(),
             edge_count: 0,
+            attributes: VertexAttributes::default(),
             components_cache: None,
         }
     }
     
     /// Получение количества вершин
     pub fn vertex_count(&self) -> usize {
-        self.vertex_ids.len()
+        self.interner.len()
     }
     
     /// Получение количества связей
+    ///
+    /// Параллельные связи считаются одной, self-loops не считаются
+    /// (см. `multi_edge_count`).
     pub fn edge_count(&self) -> usize {
         self.edge_count
     }
     
+    /// Количество связей мультиграфа: с параллельными связями и self-loops
+    pub fn multi_edge_count(&self) -> usize {
+        self.edge_count
+            + self.parallel_weights.values().map(|w| w.len()).sum::<usize>()
+            + self.self_loops.values().map(|w| w.len()).sum::<usize>()
+    }
+    
+    /// Число связей `source -> target` (для `source == target` — число self-loops)
+    pub fn edge_multiplicity(&self, source: &str, target: &str) -> usize {
+        match (self.index_of(source), self.index_of(target)) {
+            (Some(source_idx), Some(target_idx)) => self.multiplicity(source_idx, target_idx),
+            _ => 0,
+        }
+    }
+    
+    fn multiplicity(&self, source_idx: usize, target_idx: usize) -> usize {
+        if source_idx == target_idx {
+            return self.self_loops.get(&source_idx).map_or(0, |w| w.len());
+        }
+        if !self.edges.contains(source_idx, target_idx) {
+            return 0;
+        }
+        1 + self.parallel_weights.get(&(source_idx, target_idx)).map_or(0, |w| w.len())
+    }
+    
+    /// Веса всех связей `source -> target`, включая параллельные (self-loops при `source == target`)
+    pub fn multi_edge_weights(&self, source: &str, target: &str) -> Vec<f32> {
+        let (Some(source), Some(target)) = (self.index_of(source), self.index_of(target)) else {
+            return Vec::new();
+        };
+        (0..self.multiplicity(source, target))
+            .filter_map(|index| self.edge_key_weight(EdgeKey { source, target, index }))
+            .collect()
+    }
+    
+    /// Количество self-loops вершины
+    pub fn self_loop_count(&self, vertex_id: &str) -> usize {
+        self.edge_multiplicity(vertex_id, vertex_id)
+    }
+    
+    /// Вершины с self-loops
+    pub fn self_loop_vertices(&self) -> impl Iterator<Item = &String> {
+        self.self_loops.keys().map(move |&idx| &self.interner.uids()[idx])
+    }
+    
+    /// Ключи всех связей мультиграфа, включая параллельные связи и self-loops
+    ///
+    /// Порядок пар не определён; параллельные связи пары идут по возрастанию номера.
+    pub fn edge_keys(&self) -> impl Iterator<Item = EdgeKey> + '_ {
+        self.edges
+            .pairs()
+            .chain(self.self_loops.keys().map(|&idx| (idx, idx)))
+            .flat_map(move |(source, target)| {
+                (0..self.multiplicity(source, target)).map(move |index| EdgeKey { source, target, index })
+            })
+    }
+    
+    /// Вес связи по ключу мультиграфа
+    pub fn edge_key_weight(&self, key: EdgeKey) -> Option<f32> {
+        if key.source == key.target {
+            return self.self_loops.get(&key.source)?.get(key.index).copied();
+        }
+        match key.index {
+            0 => self.edges.weight(key.source, key.target),
+            index => self.parallel_weights.get(&(key.source, key.target))?.get(index - 1).copied(),
+        }
+    }
+    
     /// Итератор по вершинам
     pub fn vertices(&self) -> impl Iterator<Item = &String> {
-        self.vertex_ids.iter()
+        self.interner.uids().iter()
+    }
+    
+    /// Получение ID вершины по внутреннему индексу
+    pub fn vertex_id(&self, idx: usize) -> Option<&String> {
+        self.interner.uids().get(idx)
+    }
+    
+    /// Интернер UID вершин графа
+    pub fn interner(&self) -> &VertexInterner {
+        &self.interner
+    }
+    
+    /// Handle вершины по UID
+    pub fn vertex_handle(&self, vertex_id: &str) -> Option<VertexId> {
+        self.interner.get(vertex_id)
+    }
+    
+    /// UID вершины по handle
+    pub fn vertex_uid(&self, vertex: VertexId) -> &str {
+        self.interner.resolve(vertex)
+    }
+    
+    /// Handles всех вершин по возрастанию индекса
+    pub fn handles(&self) -> impl ExactSizeIterator<Item = VertexId> {
+        (0..self.interner.len() as u32).map(VertexId)
+    }
+    
+    /// Handles вершин, в которые ведут связи из `vertex`
+    pub fn successors(&self, vertex: VertexId) -> impl ExactSizeIterator<Item = VertexId> + '_ {
+        self.edges.outgoing(vertex.index()).map(VertexId::from_index)
+    }
+    
+    /// Handles вершин, из которых ведут связи в `vertex`
+    pub fn predecessors(&self, vertex: VertexId) -> impl ExactSizeIterator<Item = VertexId> + '_ {
+        self.edges.incoming(vertex.index()).map(VertexId::from_index)
+    }
+    
+    /// Вес связи `source -> target` по handles
+    pub fn edge_weight(&self, source: VertexId, target: VertexId) -> Option<f32> {
+        self.edges.weight(source.index(), target.index())
+    }
+    
+    /// Значение атрибута `key` вершины
+    pub fn attribute(&self, vertex: VertexId, key: &str) -> Option<&AttrValue> {
+        self.attributes.get(key, vertex.index())
+    }
+    
+    /// Установка атрибута вершины (прежнее значение заменяется)
+    pub fn set_attribute(&mut self, vertex: VertexId, key: &'static str, value: AttrValue) {
+        self.attributes.set(key, vertex.index(), value);
+    }
+    
+    /// Вершины, у которых задан атрибут `key`, и его значения
+    pub fn attribute_values<'a>(&'a self, key: &str) -> impl Iterator<Item = (VertexId, &'a AttrValue)> + 'a {
+        self.attributes
+            .columns
+            .get(key)
+            .into_iter()
+            .flat_map(|column| column.iter().enumerate())
+            .filter_map(|(idx, value)| Some((VertexId::from_index(idx), value.as_ref()?)))
+    }
+    
+    /// Все атрибуты вершины
+    pub fn vertex_attributes(&self, vertex: VertexId) -> impl Iterator<Item = (&'static str, &AttrValue)> {
+        self.attributes
+            .columns
+            .iter()
+            .filter_map(move |(&key, column)| Some((key, column.get(vertex.index())?.as_ref()?)))
+    }
+    
+    /// Год публикации вершины (атрибут `ATTR_YEAR`)
+    pub fn vertex_year(&self, vertex: VertexId) -> Option<i32> {
+        self.attribute(vertex, ATTR_YEAR)?.as_int().and_then(|year| i32::try_from(year).ok())
+    }
+    
+    /// Кластер вершины (атрибут `ATTR_CLUSTER`)
+    pub fn vertex_cluster(&self, vertex: VertexId) -> Option<&str> {
+        self.attribute(vertex, ATTR_CLUSTER)?.as_text()
+    }
+    
+    /// Внутренний индекс вершины по UID
+    fn index_of(&self, vertex_id: &str) -> Option<usize> {
+        self.interner.get(vertex_id).map(VertexId::index)
     }
     
     /// Получение исходящих связей для вершины
     pub fn get_outgoing_edges(&self, vertex_id: &str) -> Option<impl Iterator<Item = &String>> {
-        self.vertex_map.get(vertex_id).map(|&idx| {
-            self.adjacency_out[idx]
-                .iter()
-                .map(move |&target_idx| &self.vertex_ids[target_idx])
+        self.index_of(vertex_id).map(|idx| {
+            self.edges
+                .outgoing(idx)
+                .map(move |target_idx| &self.interner.uids()[target_idx])
         })
     }
     
     /// Получение входящих связей для вершины
     pub fn get_incoming_edges(&self, vertex_id: &str) -> Option<impl Iterator<Item = &String>> {
-        self.vertex_map.get(vertex_id).map(|&idx| {
-            self.adjacency_in[idx]
-                .iter()
-                .map(move |&source_idx| &self.vertex_ids[source_idx])
+        self.index_of(vertex_id).map(|idx| {
+            self.edges
+                .incoming(idx)
+                .map(move |source_idx| &self.interner.uids()[source_idx])
         })
     }
     
     /// Получение веса связи
     pub fn get_edge_weight(&self, source: &str, target: &str) -> Option<f32> {
-        let source_idx = self.vertex_map.get(source)?;
-        let target_idx = self.vertex_map.get(target)?;
-        self.edge_weights.get(&(*source_idx, *target_idx)).copied()
+        let source_idx = self.index_of(source)?;
+        let target_idx = self.index_of(target)?;
+        self.edges.weight(source_idx, target_idx)
     }
     
     /// Получение степени исхода вершины
     pub fn out_degree(&self, vertex_id: &str) -> usize {
-        self.vertex_map
-            .get(vertex_id)
-            .map(|&idx| self.adjacency_out[idx].len())
+        self.index_of(vertex_id)
+            .map(|idx| self.edges.outgoing(idx).len())
             .unwrap_or(0)
     }
     
     /// Получение степени входа вершины
     pub fn in_degree(&self, vertex_id: &str) -> usize {
-        self.vertex_map
-            .get(vertex_id)
-            .map(|&idx| self.adjacency_in[idx].len())
+        self.index_of(vertex_id)
+            .map(|idx| self.edges.incoming(idx).len())
             .unwrap_or(0)
     }
     
     /// Проверка наличия вершины
     pub fn contains_vertex(&self, vertex_id: &str) -> bool {
-        self.vertex_map.contains_key(vertex_id)
+        self.interner.get(vertex_id).is_some()
     }
     
     /// Проверка наличия связи
     pub fn contains_edge(&self, source: &str, target: &str) -> bool {
-        if let (Some(&source_idx), Some(&target_idx)) = (
-            self.vertex_map.get(source),
-            self.vertex_map.get(target),
+        if let (Some(source_idx), Some(target_idx)) = (
+            self.index_of(source),
+            self.index_of(target),
         ) {
-            self.adjacency_out[source_idx].contains(&target_idx)
+            self.edges.contains(source_idx, target_idx)
         } else {
             false
         }
     }
     
+    /// Индекс вершины, с добавлением вершины при необходимости
+    fn ensure_vertex(&mut self, vertex_id: &str) -> usize {
+        if let Some(idx) = self.index_of(vertex_id) {
+            return idx;
+        }
+        let idx = self.interner.intern(vertex_id).index();
+        let lists = self.edges.lists_mut();
+        lists.out.push(SmallVec::new());
+        lists.incoming.push(SmallVec::new());
+        idx
+    }
+    
+    /// Добавление связи в построенный граф
+    ///
+    /// Недостающие вершины добавляются. Возвращает false, если связь уже есть.
+    /// Граф в CSR переходит обратно на списки смежности.
+    pub fn add_edge(&mut self, source: &str, target: &str, weight: f32) -> Result<bool> {
+        if !GraphBuilder::accepts(source, target) {
+            return Err(anyhow::anyhow!("Недопустимая связь '{}' -> '{}'", source, target));
+        }
+        
+        let source_idx = self.ensure_vertex(source);
+        let target_idx = self.ensure_vertex(target);
+        if self.edges.contains(source_idx, target_idx) {
+            return Ok(false);
+        }
+        
+        let lists = self.edges.lists_mut();
+        lists.weights.insert((source_idx, target_idx), weight);
+        lists.out[source_idx].push(target_idx);
+        lists.incoming[target_idx].push(source_idx);
+        self.edge_count += 1;
+        self.components_cache = None;
+        Ok(true)
+    }
+    
+    /// Удаление связи; возвращает её вес, если связь была
+    ///
+    /// Параллельные связи пары удаляются вместе с ней. Вершины остаются
+    /// в графе, даже если у них не осталось связей. Граф в CSR переходит
+    /// обратно на списки смежности.
+    pub fn remove_edge(&mut self, source: &str, target: &str) -> Option<f32> {
+        let source_idx = self.index_of(source)?;
+        let target_idx = self.index_of(target)?;
+        if !self.edges.contains(source_idx, target_idx) {
+            return None;
+        }
+        let lists = self.edges.lists_mut();
+        let weight = lists.weights.remove(&(source_idx, target_idx))?;
+        self.parallel_weights.remove(&(source_idx, target_idx));
+        
+        let out = &mut lists.out[source_idx];
+        if let Some(pos) = out.iter().position(|&idx| idx == target_idx) {
+            out.remove(pos);
+        }
+        let incoming = &mut lists.incoming[target_idx];
+        if let Some(pos) = incoming.iter().position(|&idx| idx == source_idx) {
+            incoming.remove(pos);
+        }
+        self.edge_count -= 1;
+        self.components_cache = None;
+        Some(weight)
+    }
+    
+    /// Разворот связи с сохранением веса
+    ///
+    /// Если обратная связь уже есть, исходная просто удаляется.
+    /// Возвращает false, если связи `source -> target` нет.
+    pub fn reverse_edge(&mut self, source: &str, target: &str) -> Result<bool> {
+        match self.remove_edge(source, target) {
+            Some(weight) => {
+                self.add_edge(target, source, weight)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+    
+    /// Удаление вершины вместе со всеми её связями, параллельными и self-loops
+    ///
+    /// Возвращает число удалённых связей мультиграфа или None, если вершины нет.
+    /// Последняя вершина занимает индекс удалённой (swap-remove), поэтому меняется
+    /// только её `VertexId`. Граф в CSR переходит обратно на списки смежности.
+    pub fn remove_vertex(&mut self, vertex_id: &str) -> Option<usize> {
+        let idx = self.index_of(vertex_id)?;
+        let mut removed = self.self_loops.remove(&idx).map_or(0, |w| w.len());
+        
+        let lists = self.edges.lists_mut();
+        for target in std::mem::take(&mut lists.out[idx]) {
+            lists.incoming[target].retain(|source| *source != idx);
+            lists.weights.remove(&(idx, target));
+            removed += 1 + self.parallel_weights.remove(&(idx, target)).map_or(0, |w| w.len());
+            self.edge_count -= 1;
+        }
+        for source in std::mem::take(&mut lists.incoming[idx]) {
+            lists.out[source].retain(|target| *target != idx);
+            lists.weights.remove(&(source, idx));
+            removed += 1 + self.parallel_weights.remove(&(source, idx)).map_or(0, |w| w.len());
+            self.edge_count -= 1;
+        }
+        
+        // Последняя вершина переезжает на освободившийся индекс
+        let last = lists.out.len() - 1;
+        if idx != last {
+            for target in lists.out[last].clone() {
+                for source in lists.incoming[target].iter_mut().filter(|source| **source == last) {
+                    *source = idx;
+                }
+                if let Some(weight) = lists.weights.remove(&(last, target)) {
+                    lists.weights.insert((idx, target), weight);
+                }
+                if let Some(weights) = self.parallel_weights.remove(&(last, target)) {
+                    self.parallel_weights.insert((idx, target), weights);
+                }
+            }
+            for source in lists.incoming[last].clone() {
+                for target in lists.out[source].iter_mut().filter(|target| **target == last) {
+                    *target = idx;
+                }
+                if let Some(weight) = lists.weights.remove(&(source, last)) {
+                    lists.weights.insert((source, idx), weight);
+                }
+                if let Some(weights) = self.parallel_weights.remove(&(source, last)) {
+                    self.parallel_weights.insert((source, idx), weights);
+                }
+            }
+            if let Some(weights) = self.self_loops.remove(&last) {
+                self.self_loops.insert(idx, weights);
+            }
+        }
+        lists.out.swap_remove(idx);
+        lists.incoming.swap_remove(idx);
+        self.attributes.swap_remove(idx, self.interner.len());
+        self.interner.swap_remove(VertexId::from_index(idx));
+        self.components_cache = None;
+        Some(removed)
+    }
+    
+    /// Подграф, индуцированный вершинами `vertices`: они и все связи между ними
+    ///
+    /// Вершины подграфа идут в порядке первого появления в `vertices`
+    /// (повторы пропускаются), параллельные связи, self-loops и атрибуты
+    /// вершин сохраняются.
+    pub fn induced_subgraph(&self, vertices: &[VertexId]) -> Subgraph {
+        let mut index = vec![u32::MAX; self.vertex_count()];
+        let mut original = Vec::with_capacity(vertices.len());
+        for &vertex in vertices {
+            if index[vertex.index()] == u32::MAX {
+                index[vertex.index()] = original.len() as u32;
+                original.push(vertex);
+            }
+        }
+        
+        let mut graph = Graph::new();
+        graph.interner = VertexInterner::from_uids(
+            original.iter().map(|&vertex| self.vertex_uid(vertex).to_string()).collect(),
+        );
+        let mut lists = AdjacencyLists::with_vertices(original.len());
+        for (source, &vertex) in original.iter().enumerate() {
+            for parent_target in self.edges.outgoing(vertex.index()) {
+                let target = index[parent_target];
+                if target == u32::MAX {
+                    continue;
+                }
+                let target = target as usize;
+                let weight = self.edges.weight(vertex.index(), parent_target).unwrap_or_default();
+                lists.out[source].push(target);
+                lists.incoming[target].push(source);
+                lists.weights.insert((source, target), weight);
+                if let Some(weights) = self.parallel_weights.get(&(vertex.index(), parent_target)) {
+                    graph.parallel_weights.insert((source, target), weights.clone());
+                }
+                graph.edge_count += 1;
+            }
+            if let Some(weights) = self.self_loops.get(&vertex.index()) {
+                graph.self_loops.insert(source, weights.clone());
+            }
+        }
+        graph.edges = EdgeStorage::Lists(lists);
+        graph.attributes = self.attributes.select(&original);
+        
+        Subgraph { graph, original }
+    }
+    
+    /// Окрестность `seeds` радиуса `depth`: вершины не дальше `depth` связей
+    /// от любой из них без учёта направления, с индуцированными связями
+    ///
+    /// Seed-вершины идут первыми, остальные — в порядке обхода в ширину.
+    pub fn neighborhood(&self, seeds: &[VertexId], depth: usize) -> Subgraph {
+        let mut visited = vec![false; self.vertex_count()];
+        let mut order = Vec::new();
+        for &seed in seeds {
+            if !std::mem::replace(&mut visited[seed.index()], true) {
+                order.push(seed);
+            }
+        }
+        
+        let mut frontier = 0..order.len();
+        for _ in 0..depth {
+            for position in frontier.clone() {
+                let vertex = order[position];
+                for neighbor in self.successors(vertex).chain(self.predecessors(vertex)) {
+                    if !std::mem::replace(&mut visited[neighbor.index()], true) {
+                        order.push(neighbor);
+                    }
+                }
+            }
+            if frontier.end == order.len() {
+                break;
+            }
+            frontier = frontier.end..order.len();
+        }
+        
+        self.induced_subgraph(&order)
+    }
+    
     /// Получение компонент связности
     pub fn get_connected_components(&mut self) -> &[Vec<usize>] {
         if self.components_cache.is_none() {
@@ -168,14 +553,14 @@ impl Graph {
             component.push(current_idx);
             
             // Добавляем исходящие связи
-            for &neighbor_idx in &self.adjacency_out[current_idx] {
+            for neighbor_idx in self.edges.outgoing(current_idx) {
                 if !visited[neighbor_idx] {
                     stack.push(neighbor_idx);
                 }
             }
             
             // Добавляем входящие связи (для слабой связности)
-            for &neighbor_idx in &self.adjacency_in[current_idx] {
+            for neighbor_idx in self.edges.incoming(current_idx) {
                 if !visited[neighbor_idx] {
                     stack.push(neighbor_idx);
                 }
@@ -209,7 +594,7 @@ impl Graph {
     fn dfs_cycle_check(&self, current_idx: usize, color: &mut [Color]) -> bool {
         color[current_idx] = Color::Gray;
         
-        for &neighbor_idx in &self.adjacency_out[current_idx] {
+        for neighbor_idx in self.edges.outgoing(current_idx) {
             match color[neighbor_idx] {
                 Color::Gray => return true, // Обнаружен цикл
                 Color::White => {
@@ -227,11 +612,12 @@ impl Graph {
     
     /// Получение изолированных вершин
     pub fn get_isolated_vertices(&self) -> Vec<&String> {
-        self.vertex_ids
+        self.interner
+            .uids()
             .iter()
             .enumerate()
             .filter(|(idx, _)| {
-                self.adjacency_out[*idx].is_empty() && self.adjacency_in[*idx].is_empty()
+                self.edges.outgoing(*idx).len() == 0 && self.edges.incoming(*idx).len() == 0
             })
             .map(|(_, vertex_id)| vertex_id)
             .collect()
@@ -239,8 +625,8 @@ impl Graph {
     
     /// Статистика графа
     pub fn get_statistics(&self) -> GraphStatistics {
-        let total_out_degree: usize = self.adjacency_out.iter().map(|adj| adj.len()).sum();
-        let total_in_degree: usize = self.adjacency_in.iter().map(|adj| adj.len()).sum();
+        let total_out_degree: usize = (0..self.vertex_count()).map(|idx| self.edges.outgoing(idx).len()).sum();
+        let total_in_degree: usize = (0..self.vertex_count()).map(|idx| self.edges.incoming(idx).len()).sum();
         
         let avg_out_degree = if self.vertex_count() > 0 {
             total_out_degree as f64 / self.vertex_count() as f64
@@ -260,6 +646,20 @@ impl Graph {
             0.0
         };
         
+        let out_degrees: Vec<usize> = (0..self.vertex_count()).map(|idx| self.edges.outgoing(idx).len()).collect();
+        let in_degrees: Vec<usize> = (0..self.vertex_count()).map(|idx| self.edges.incoming(idx).len()).collect();
+        
+        // Наибольшая компонента: из кеша, если компоненты уже посчитаны
+        let computed;
+        let components = match &self.components_cache {
+            Some(components) => components,
+            None => {
+                computed = self.compute_connected_components();
+                &computed
+            }
+        };
+        let largest = components.iter().max_by_key(|component| component.len());
+        
         GraphStatistics {
             vertex_count: self.vertex_count(),
             edge_count: self.edge_count,
@@ -268,111 +668,2417 @@ impl Graph {
             density,
             is_dag: self.is_dag(),
             isolated_vertices: self.get_isolated_vertices().len(),
+            sources: in_degrees.iter().filter(|&&degree| degree == 0).count(),
+            sinks: out_degrees.iter().filter(|&&degree| degree == 0).count(),
+            out_degree: DegreeDistribution::from_degrees(out_degrees),
+            in_degree: DegreeDistribution::from_degrees(in_degrees),
+            largest_component: largest.map_or(0, Vec::len),
+            approximate_diameter: largest.map_or(0, |component| self.approximate_diameter(component[0])),
         }
     }
-}
-
-/// Цвета для DFS
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Color {
-    White, // Не посещена
-    Gray,  // В процессе обработки
-    Black, // Обработана
-}
-
-/// Статистика графа
-#[derive(Debug, Clone)]
-pub struct GraphStatistics {
-    pub vertex_count: usize,
-    pub edge_count: usize,
-    pub avg_out_degree: f64,
-    pub avg_in_degree: f64,
-    pub density: f64,
-    pub is_dag: bool,
-    pub isolated_vertices: usize,
-}
-
-/// Строитель графа
-pub struct GraphBuilder {
-    vertices: HashSet<String>,
-    edges: Vec<(String, String, f32)>,
-}
-
-impl GraphBuilder {
-    /// Создание нового строителя
-    pub fn new() -> Self {
-        Self {
-            vertices: HashSet::new(),
-            edges: Vec::new(),
+    
+    /// Оценка диаметра компоненты вершины `start` двойным обходом BFS
+    ///
+    /// Направление связей не учитывается. Второй обход идёт от самой дальней
+    /// вершины первого, его глубина — нижняя граница диаметра (на деревьях точная).
+    fn approximate_diameter(&self, start: usize) -> usize {
+        let (farthest, _) = self.undirected_eccentricity(start);
+        self.undirected_eccentricity(farthest).1
+    }
+    
+    /// Самая дальняя от `start` вершина без учёта направления связей и расстояние до неё
+    fn undirected_eccentricity(&self, start: usize) -> (usize, usize) {
+        let mut distance = vec![usize::MAX; self.vertex_count()];
+        let mut queue = std::collections::VecDeque::from([start]);
+        distance[start] = 0;
+        let mut farthest = (start, 0);
+        while let Some(current) = queue.pop_front() {
+            let next = distance[current] + 1;
+            for neighbor in self.edges.outgoing(current).chain(self.edges.incoming(current)) {
+                if distance[neighbor] == usize::MAX {
+                    distance[neighbor] = next;
+                    farthest = (neighbor, next);
+                    queue.push_back(neighbor);
+                }
+            }
         }
+        farthest
     }
     
-    /// Добавление связи
-    pub fn add_edge(&mut self, source: String, target: String, weight: f32) -> Result<()> {
-        if source == target {
-            return Err(anyhow::anyhow!("Self-loops не поддерживаются"));
+    /// Хранятся ли связи в CSR
+    pub fn is_csr(&self) -> bool {
+        matches!(self.edges, EdgeStorage::Csr(_))
+    }
+    
+    /// Перевод связей в CSR; всегда возвращает true, так как индексы вершин
+    /// (`VertexId`) уже помещаются в u32
+    ///
+    /// Порядок соседей сохраняется, поэтому обходы дают тот же результат.
+    pub fn to_csr(&mut self) -> bool {
+        if let EdgeStorage::Lists(lists) = &mut self.edges {
+            self.edges = EdgeStorage::Csr(CsrEdges::from_lists(std::mem::take(lists)));
+        }
+        true
+    }
+    
+    /// Перевод связей на списки смежности (изменение графа делает это само)
+    ///
+    /// Из CSR и mmap разворачиваются все связи, за O(V + E); перед серией
+    /// изменений это происходит один раз, вернуть CSR можно `to_csr`.
+    pub fn to_adjacency_lists(&mut self) {
+        self.edges.lists_mut();
+    }
+    
+    /// Хранятся ли связи в файле, отображённом в память
+    pub fn is_mmap(&self) -> bool {
+        matches!(self.edges, EdgeStorage::Mmap(_))
+    }
+    
+    /// Перевод связей в CSR-файл в каталоге `dir`, отображённый в память
+    ///
+    /// Связи перестают занимать кучу: страницы файла подгружает и вытесняет ОС.
+    /// Файл удаляется вместе с последней копией графа. Изменение графа
+    /// возвращает связи в списки смежности.
+    pub fn to_mmap(&mut self, dir: &Path) -> Result<()> {
+        if !self.is_mmap() {
+            self.edges = EdgeStorage::Mmap(MmapEdges::write(&self.edges, dir)?);
         }
-        
-        self.vertices.insert(source.clone());
-        self.vertices.insert(target.clone());
-        self.edges.push((source, target, weight));
-        
         Ok(())
     }
     
-    /// Добавление вершины
-    pub fn add_vertex(&mut self, vertex_id: String) {
-        self.vertices.insert(vertex_id);
+    /// Оценка памяти под связи и их веса (байты)
+    pub fn edge_storage_bytes(&self) -> usize {
+        self.edges.heap_bytes()
     }
     
-    /// Построение графа
-    pub fn build(self) -> Result<Graph> {
+    /// Размер CSR-файла связей, отображённого в память (0, если связи в куче)
+    pub fn mapped_bytes(&self) -> usize {
+        match &self.edges {
+            EdgeStorage::Mmap(mmap) => mmap.file.map.len(),
+            _ => 0,
+        }
+    }
+
+    /// Сохранение графа в файл `path` в компактном бинарном формате
+    ///
+    /// Происхождение графа не записывается (см. `serialize_with_source`).
+    pub fn serialize_to(&self, path: &Path) -> Result<()> {
+        self.serialize_with_source(path, &GraphSource::default())
+    }
+
+    /// Сохранение графа в файл `path` вместе с его происхождением `source`
+    ///
+    /// Формат (little-endian): сигнатура, версия (u32), число вершин и связей
+    /// (u64), происхождение (`GraphSource`), UID вершин, исходящие связи с весами и входящие связи каждой
+    /// вершины, параллельные связи, self-loops и контрольная сумма FNV-1a
+    /// всего предыдущего содержимого (u64). Порядок соседей сохраняется,
+    /// поэтому обходы загруженного графа дают тот же результат. Атрибуты
+    /// вершин в файл не записываются.
+    pub fn serialize_with_source(&self, path: &Path, source: &GraphSource) -> Result<()> {
+        use std::io::Write;
+
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Не удалось создать файл графа {}", path.display()))?;
+        let mut out = ChecksumWriter::new(std::io::BufWriter::new(file));
+        out.write_all(GRAPH_FILE_MAGIC)?;
+        out.write_all(&GRAPH_FILE_VERSION.to_le_bytes())?;
+        out.write_all(&(self.vertex_count() as u64).to_le_bytes())?;
+        out.write_all(&(self.edge_count as u64).to_le_bytes())?;
+        out.write_all(&source.source_edges.to_le_bytes())?;
+        out.write_all(&source.loaded_at_ms.to_le_bytes())?;
+        out.write_all(&source.sample_rate.to_le_bytes())?;
+        out.write_all(&source.max_edges.to_le_bytes())?;
+        for uid in self.interner.uids() {
+            out.write_all(&(uid.len() as u32).to_le_bytes())?;
+            out.write_all(uid.as_bytes())?;
+        }
+        for idx in 0..self.vertex_count() {
+            out.write_all(&(self.edges.outgoing(idx).len() as u32).to_le_bytes())?;
+            for (target, weight) in self.edges.outgoing_weighted(idx) {
+                out.write_all(&(target as u32).to_le_bytes())?;
+                out.write_all(&weight.to_le_bytes())?;
+            }
+            let sources = self.edges.incoming(idx);
+            out.write_all(&(sources.len() as u32).to_le_bytes())?;
+            for source in sources {
+                out.write_all(&(source as u32).to_le_bytes())?;
+            }
+        }
+
+        // Таблицы сортируются, чтобы одинаковые графы давали одинаковые файлы
+        let mut parallel: Vec<_> = self.parallel_weights.iter().collect();
+        parallel.sort_unstable_by_key(|&(&pair, _)| pair);
+        out.write_all(&(parallel.len() as u64).to_le_bytes())?;
+        for (&(source, target), weights) in parallel {
+            out.write_all(&(source as u32).to_le_bytes())?;
+            out.write_all(&(target as u32).to_le_bytes())?;
+            write_weights(&mut out, weights)?;
+        }
+        let mut self_loops: Vec<_> = self.self_loops.iter().collect();
+        self_loops.sort_unstable_by_key(|&(&idx, _)| idx);
+        out.write_all(&(self_loops.len() as u64).to_le_bytes())?;
+        for (&idx, weights) in self_loops {
+            out.write_all(&(idx as u32).to_le_bytes())?;
+            write_weights(&mut out, weights)?;
+        }
+
+        let checksum = out.checksum();
+        let mut out = out.into_inner();
+        out.write_all(&checksum.to_le_bytes())?;
+        out.flush()
+            .with_context(|| format!("Не удалось записать файл графа {}", path.display()))
+    }
+
+    /// Загрузка графа, сохранённого `serialize_to`
+    ///
+    /// Файл другой версии формата, обрезанный или с неверной контрольной суммой
+    /// не загружается. Связи загружаются в списки смежности и от
+    /// `DEFAULT_CSR_THRESHOLD` связей переводятся в CSR, как в `GraphBuilder`.
+    pub fn deserialize_from(path: &Path) -> Result<Graph> {
+        Self::deserialize_with_source(path).map(|(graph, _)| graph)
+    }
+
+    /// Загрузка графа и его происхождения, сохранённых `serialize_with_source`
+    pub fn deserialize_with_source(path: &Path) -> Result<(Graph, GraphSource)> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Не удалось открыть файл графа {}", path.display()))?;
+        let mut input = ChecksumReader::new(std::io::BufReader::new(file));
+        let (mut graph, source) = Self::read_from(&mut input)
+            .with_context(|| format!("Не удалось загрузить граф из {}", path.display()))?;
+        if graph.edge_count >= DEFAULT_CSR_THRESHOLD {
+            graph.to_csr();
+        }
+        Ok((graph, source))
+    }
+
+    fn read_from<R: std::io::Read>(input: &mut ChecksumReader<R>) -> Result<(Graph, GraphSource)> {
+        use std::io::Read;
+
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        anyhow::ensure!(&magic == GRAPH_FILE_MAGIC, "Неверная сигнатура файла графа");
+        let version = input.u32()?;
+        anyhow::ensure!(
+            version == GRAPH_FILE_VERSION,
+            "Версия формата {} не поддерживается (ожидается {})", version, GRAPH_FILE_VERSION
+        );
+        let vertex_count = input.u64()? as usize;
+        let edge_count = input.u64()? as usize;
+        anyhow::ensure!(vertex_count <= u32::MAX as usize, "Слишком много вершин: {}", vertex_count);
+        let source = GraphSource {
+            source_edges: input.u64()?,
+            loaded_at_ms: input.u64()? as i64,
+            sample_rate: f64::from_bits(input.u64()?),
+            max_edges: input.u64()?,
+        };
+
+        // Ёмкости не берутся из заголовка: повреждённый заголовок не должен вызывать огромных аллокаций
+        let mut uids = Vec::new();
+        for _ in 0..vertex_count {
+            let len = input.u32()? as usize;
+            let mut bytes = Vec::new();
+            input.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+            anyhow::ensure!(bytes.len() == len, "Файл графа обрезан");
+            uids.push(String::from_utf8(bytes).context("UID вершины не в UTF-8")?);
+        }
+        let interner = VertexInterner::from_uids(uids);
+        anyhow::ensure!(interner.handles.len() == vertex_count, "Повторяющиеся UID вершин");
+
+        let mut lists = AdjacencyLists::with_vertices(vertex_count);
+        for source in 0..vertex_count {
+            for _ in 0..input.u32()? {
+                let target = input.index(vertex_count)?;
+                let weight = input.f32()?;
+                anyhow::ensure!(
+                    target != source && lists.weights.insert((source, target), weight).is_none(),
+                    "Недопустимая связь {} -> {}", source, target
+                );
+                lists.out[source].push(target);
+            }
+            for _ in 0..input.u32()? {
+                let incoming = input.index(vertex_count)?;
+                lists.incoming[source].push(incoming);
+            }
+        }
+        anyhow::ensure!(lists.weights.len() == edge_count, "Число связей не совпадает с заголовком");
+        let incoming_consistent = lists.incoming.iter().map(|sources| sources.len()).sum::<usize>() == edge_count
+            && lists.incoming.iter().enumerate().all(|(target, sources)| {
+                sources.iter().all(|&source| lists.weights.contains_key(&(source, target)))
+            });
+        anyhow::ensure!(incoming_consistent, "Входящие связи не совпадают с исходящими");
+
         let mut graph = Graph::new();
-        
-        // Создание маппинга вершин
-        let vertices: Vec<_> = self.vertices.into_iter().collect();
-        graph.vertex_ids = vertices.clone();
-        graph.vertex_map = vertices
-            .iter()
-            .enumerate()
-            .map(|(idx, vertex_id)| (vertex_id.clone(), idx))
-            .collect();
-        
-        // Инициализация списков смежности
-        let vertex_count = graph.vertex_count();
-        graph.adjacency_out = vec![SmallVec::new(); vertex_count];
-        graph.adjacency_in = vec![SmallVec::new(); vertex_count];
-        
-        // Добавление связей
-        for (source, target, weight) in self.edges {
-            let source_idx = graph.vertex_map[&source];
-            let target_idx = graph.vertex_map[&target];
-            
-            graph.adjacency_out[source_idx].push(target_idx);
-            graph.adjacency_in[target_idx].push(source_idx);
-            graph.edge_weights.insert((source_idx, target_idx), weight);
-            graph.edge_count += 1;
+        for _ in 0..input.u64()? {
+            let pair = (input.index(vertex_count)?, input.index(vertex_count)?);
+            anyhow::ensure!(lists.weights.contains_key(&pair), "Параллельные связи без основной связи");
+            graph.parallel_weights.insert(pair, input.weights()?);
         }
-        
-        Ok(graph)
+        for _ in 0..input.u64()? {
+            let idx = input.index(vertex_count)?;
+            graph.self_loops.insert(idx, input.weights()?);
+        }
+
+        let checksum = input.checksum();
+        let mut stored = [0u8; 8];
+        input.inner.read_exact(&mut stored)?;
+        anyhow::ensure!(u64::from_le_bytes(stored) == checksum, "Контрольная сумма файла графа не совпадает");
+        anyhow::ensure!(input.inner.read(&mut [0u8; 1])? == 0, "Лишние данные после контрольной суммы");
+
+        graph.interner = interner;
+        graph.edges = EdgeStorage::Lists(lists);
+        graph.edge_count = edge_count;
+        Ok((graph, source))
     }
 }
 
-impl Default for GraphBuilder {
-    fn default() -> Self {
-        Self::new()
+/// Происхождение графа в файле (`Graph::serialize_with_source`)
+///
+/// По нему решается, можно ли использовать файл вместо загрузки связей:
+/// граф другой выборки не подменяет граф, который загрузила бы конфигурация,
+/// а по времени загрузки ищутся изменения в источнике.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GraphSource {
+    /// Число связей в источнике при загрузке (0 — неизвестно)
+    pub source_edges: u64,
+    /// Начало загрузки связей, мс от эпохи Unix (0 — неизвестно)
+    pub loaded_at_ms: i64,
+    /// Доля загруженных связей (`neo4j.sample_rate`)
+    pub sample_rate: f64,
+    /// Лимит числа загруженных связей (`neo4j.max_edges`, 0 — без лимита)
+    pub max_edges: u64,
+}
+
+/// Подграф и соответствие его вершин вершинам исходного графа
+#[derive(Debug, Clone)]
+pub struct Subgraph {
+    /// Новый граф (связи в списках смежности)
+    pub graph: Graph,
+    
+    /// Handle вершины в исходном графе по индексу её handle в подграфе
+    pub original: Vec<VertexId>,
+}
+
+impl Subgraph {
+    /// Handle вершины подграфа в исходном графе
+    pub fn original(&self, vertex: VertexId) -> VertexId {
+        self.original[vertex.index()]
     }
 }
 
-impl Default for Graph {
+/// Порог числа связей, с которого `GraphBuilder` хранит граф в CSR
+pub const DEFAULT_CSR_THRESHOLD: usize = 1_000_000;
+
+/// Порог числа связей, с которого `GraphBuilder` с каталогом для mmap
+/// хранит CSR в файле
+pub const DEFAULT_MMAP_THRESHOLD: usize = 50_000_000;
+
+/// Связи графа: по одной на пару вершин, без параллельных связей и self-loops
+#[derive(Debug, Clone)]
+enum EdgeStorage {
+    /// Списки смежности и хеш-таблица весов: дешёвые вставка и удаление
+    Lists(AdjacencyLists),
+
+    /// Непрерывные массивы смещений, соседей и весов: меньше памяти
+    /// и промахов кеша на больших графах, но только для чтения
+    Csr(CsrEdges),
+
+    /// Те же массивы CSR в файле, отображённом в память: для графов больше RAM
+    Mmap(MmapEdges),
+}
+
+impl Default for EdgeStorage {
     fn default() -> Self {
-        Self::new()
+        EdgeStorage::Lists(AdjacencyLists::default())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[derive(Debug, Clone, Default)]
+struct AdjacencyLists {
+    out: Vec<SmallVec<[usize; 4]>>,
+    incoming: Vec<SmallVec<[usize; 4]>>,
+    weights: HashMap<(usize, usize), f32>,
+}
+
+impl AdjacencyLists {
+    fn with_vertices(vertex_count: usize) -> Self {
+        Self {
+            out: vec![SmallVec::new(); vertex_count],
+            incoming: vec![SmallVec::new(); vertex_count],
+            weights: HashMap::new(),
+        }
+    }
+}
+
+/// Compressed sparse row: исходящие связи вершины `v` — позиции
+/// `out_offsets[v]..out_offsets[v + 1]` в `out_targets` и `out_weights`,
+/// входящие — так же в `in_sources`. `out_order` — те же позиции строки в
+/// порядке возрастания цели: по ним вес ищется двоичным поиском
+#[derive(Debug, Clone, Default)]
+struct CsrEdges {
+    out_offsets: Vec<usize>,
+    out_targets: Vec<u32>,
+    out_weights: Vec<f32>,
+    out_order: Vec<u32>,
+    in_offsets: Vec<usize>,
+    in_sources: Vec<u32>,
+}
+
+impl CsrEdges {
+    /// Массивы под `capacity` связей; смещения задаёт `compact`
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            out_targets: vec![0; capacity],
+            out_weights: vec![0.0; capacity],
+            out_order: vec![0; capacity],
+            in_sources: vec![0; capacity],
+            ..Self::default()
+        }
+    }
+    
+    fn from_lists(lists: AdjacencyLists) -> Self {
+        let mut csr = Self::with_capacity(lists.weights.len());
+        let mut out_offsets = Vec::with_capacity(lists.out.len() + 1);
+        let mut in_offsets = Vec::with_capacity(lists.incoming.len() + 1);
+        out_offsets.push(0);
+        for (source, targets) in lists.out.iter().enumerate() {
+            let start = *out_offsets.last().expect("смещение начала");
+            for (i, &target) in targets.iter().enumerate() {
+                csr.set_out(start + i, target as u32, lists.weights[&(source, target)]);
+            }
+            out_offsets.push(start + targets.len());
+        }
+        in_offsets.push(0);
+        for sources in &lists.incoming {
+            let start = *in_offsets.last().expect("смещение начала");
+            for (i, &source) in sources.iter().enumerate() {
+                csr.set_source(start + i, source as u32);
+            }
+            in_offsets.push(start + sources.len());
+        }
+        // Повторов в списках смежности нет: строится только `out_order`
+        csr.compact(out_offsets, in_offsets, None);
+        csr
+    }
+    
+    /// Удаление повторов пар и построение `out_order` (см. `compact_rows`)
+    ///
+    /// Возвращает число убранных повторов.
+    fn compact(
+        &mut self,
+        mut out_offsets: Vec<usize>,
+        mut in_offsets: Vec<usize>,
+        parallel: Option<&mut HashMap<(usize, usize), SmallVec<[f32; 1]>>>,
+    ) -> usize {
+        let duplicates = compact_rows(self, &mut out_offsets, &mut in_offsets, parallel);
+        let edge_count = *out_offsets.last().expect("смещение конца");
+        self.out_offsets = out_offsets;
+        self.in_offsets = in_offsets;
+        if duplicates > 0 {
+            for array in [&mut self.out_targets, &mut self.out_order, &mut self.in_sources] {
+                array.truncate(edge_count);
+                array.shrink_to_fit();
+            }
+            self.out_weights.truncate(edge_count);
+            self.out_weights.shrink_to_fit();
+        }
+        duplicates
+    }
+    
+    /// `out_order` заполненных строк без повторов пар (параллельно по строкам)
+    fn index_rows(&mut self) {
+        use rayon::prelude::*;
+        
+        let vertex_count = self.out_offsets.len().saturating_sub(1);
+        self.out_order = (0..vertex_count)
+            .into_par_iter()
+            .flat_map_iter(|source| {
+                let targets = &self.out_targets[self.out_range(source)];
+                let mut row: Vec<u32> = (0..targets.len() as u32).collect();
+                row.sort_unstable_by_key(|&k| targets[k as usize]);
+                row
+            })
+            .collect();
+    }
+    
+    /// Вес связи двоичным поиском по `out_order` строки источника
+    fn weight(&self, source: usize, target: usize) -> Option<f32> {
+        let range = self.out_range(source);
+        let row = &self.out_order[range.clone()];
+        let position = search_row(row.len(), target, |k| {
+            let i = range.start + row[k] as usize;
+            (i, self.out_targets[i] as usize)
+        })?;
+        Some(self.out_weights[position])
+    }
+
+    fn into_lists(self) -> AdjacencyLists {
+        let vertex_count = self.out_offsets.len().saturating_sub(1);
+        let mut lists = AdjacencyLists::with_vertices(vertex_count);
+        lists.weights.reserve(self.out_targets.len());
+        for source in 0..vertex_count {
+            for i in self.out_offsets[source]..self.out_offsets[source + 1] {
+                let target = self.out_targets[i] as usize;
+                lists.out[source].push(target);
+                lists.weights.insert((source, target), self.out_weights[i]);
+            }
+            let sources = &self.in_sources[self.in_offsets[source]..self.in_offsets[source + 1]];
+            lists.incoming[source].extend(sources.iter().map(|&s| s as usize));
+        }
+        lists
+    }
+
+    fn out_range(&self, idx: usize) -> std::ops::Range<usize> {
+        self.out_offsets[idx]..self.out_offsets[idx + 1]
+    }
+}
+
+/// Сигнатура и версия формата CSR-файла
+const MMAP_MAGIC: &[u8; 8] = b"GLCSR\0\0\x02";
+
+/// Заголовок CSR-файла: сигнатура, число вершин и число связей (u64)
+const MMAP_HEADER_BYTES: usize = 24;
+
+/// Начало секции CSR-файла: 0 — `out_offsets`, 1 — `in_offsets`,
+/// 2 — `out_targets`, 3 — `out_weights`, 4 — `in_sources`, 5 — `out_order`;
+/// с `index = 6` — длина файла
+fn mmap_section(vertex_count: usize, edge_count: usize, index: usize) -> usize {
+    let offsets = 8 * (vertex_count + 1);
+    let array = 4 * edge_count;
+    match index {
+        0 => MMAP_HEADER_BYTES,
+        1 => MMAP_HEADER_BYTES + offsets,
+        _ => MMAP_HEADER_BYTES + 2 * offsets + (index - 2) * array,
+    }
+}
+
+/// CSR в файле, отображённом в память
+///
+/// Формат (little-endian): заголовок, `out_offsets` и `in_offsets` (u64, по
+/// `n + 1`), `out_targets` (u32), `out_weights` (f32), `in_sources` (u32) —
+/// те же массивы, что у `CsrEdges`, — и `out_order` (u32): номера связей
+/// каждой строки в порядке возрастания цели, по ним вес ищется двоичным
+/// поиском. Копии графа разделяют один файл.
+#[derive(Debug, Clone)]
+struct MmapEdges {
+    file: Arc<MappedFile>,
+    vertex_count: usize,
+    edge_count: usize,
+}
+
+/// Отображённый файл; удаляется с диска при освобождении
+#[derive(Debug)]
+struct MappedFile {
+    map: memmap2::Mmap,
+    /// Освобождается после `map`: файл удаляется, когда отображение уже снято
+    _path: TempFile,
+}
+
+/// Путь временного файла, удаляемого при освобождении
+#[derive(Debug)]
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl MmapEdges {
+    /// Запись связей в новый файл в `dir` и его отображение в память
+    ///
+    /// Порядок исходящих и входящих связей каждой вершины сохраняется.
+    fn write(edges: &EdgeStorage, dir: &Path) -> Result<Self> {
+        let (vertex_count, edge_count) = match edges {
+            EdgeStorage::Lists(lists) => (lists.out.len(), lists.weights.len()),
+            EdgeStorage::Csr(csr) => (csr.out_offsets.len().saturating_sub(1), csr.out_targets.len()),
+            EdgeStorage::Mmap(mmap) => (mmap.vertex_count, mmap.edge_count),
+        };
+        let mut writer = MmapCsrWriter::create(dir, vertex_count, edge_count)?;
+        let mut out_offsets = vec![0usize; vertex_count + 1];
+        let mut in_offsets = vec![0usize; vertex_count + 1];
+        for idx in 0..vertex_count {
+            let mut at = out_offsets[idx];
+            for (target, weight) in edges.outgoing_weighted(idx) {
+                writer.set_out(at, target as u32, weight);
+                at += 1;
+            }
+            out_offsets[idx + 1] = at;
+            let mut at = in_offsets[idx];
+            for source in edges.incoming(idx) {
+                writer.set_source(at, source as u32);
+                at += 1;
+            }
+            in_offsets[idx + 1] = at;
+        }
+        compact_rows(&mut writer, &mut out_offsets, &mut in_offsets, None);
+        writer.finish(&out_offsets, &in_offsets)
+    }
+    
+    fn section(&self, index: usize) -> usize {
+        mmap_section(self.vertex_count, self.edge_count, index)
+    }
+    
+    fn offset(&self, section: usize, idx: usize) -> usize {
+        let at = self.section(section) + 8 * idx;
+        u64::from_le_bytes(self.file.map[at..at + 8].try_into().expect("8 байт")) as usize
+    }
+    
+    fn out_range(&self, idx: usize) -> std::ops::Range<usize> {
+        self.offset(0, idx)..self.offset(0, idx + 1)
+    }
+    
+    fn in_range(&self, idx: usize) -> std::ops::Range<usize> {
+        self.offset(1, idx)..self.offset(1, idx + 1)
+    }
+    
+    /// Байты элементов `range` массива из 4-байтовых значений
+    fn array(&self, section: usize, range: std::ops::Range<usize>) -> &[u8] {
+        let at = self.section(section);
+        &self.file.map[at + 4 * range.start..at + 4 * range.end]
+    }
+    
+    /// 4-байтовое значение `i` массива секции `section`
+    fn value(&self, section: usize, i: usize) -> usize {
+        read_u32(self.array(section, i..i + 1))
+    }
+    
+    /// Вес связи двоичным поиском по `out_order` строки источника
+    fn weight(&self, source: usize, target: usize) -> Option<f32> {
+        let range = self.out_range(source);
+        let start = range.start;
+        let position = search_row(range.len(), target, |k| {
+            let i = start + self.value(5, start + k);
+            (i, self.value(2, i))
+        })?;
+        Some(read_f32(self.array(3, position..position + 1)))
+    }
+    
+    fn to_lists(&self) -> AdjacencyLists {
+        let mut lists = AdjacencyLists::with_vertices(self.vertex_count);
+        lists.weights.reserve(self.edge_count);
+        for source in 0..self.vertex_count {
+            let range = self.out_range(source);
+            let targets = self.array(2, range.clone()).chunks_exact(4).map(read_u32);
+            let weights = self.array(3, range).chunks_exact(4).map(read_f32);
+            for (target, weight) in targets.zip(weights) {
+                lists.out[source].push(target);
+                lists.weights.insert((source, target), weight);
+            }
+            let sources = self.array(4, self.in_range(source)).chunks_exact(4).map(read_u32);
+            lists.incoming[source].extend(sources);
+        }
+        lists
+    }
+}
+
+/// Двоичный поиск цели `target` в строке CSR
+///
+/// `entry(k)` — номер связи с k-й по возрастанию целью строки из `len` связей
+/// и сама цель. Возвращает номер найденной связи.
+fn search_row(len: usize, target: usize, entry: impl Fn(usize) -> (usize, usize)) -> Option<usize> {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (position, candidate) = entry(mid);
+        match candidate.cmp(&target) {
+            std::cmp::Ordering::Less => low = mid + 1,
+            std::cmp::Ordering::Greater => high = mid,
+            std::cmp::Ordering::Equal => return Some(position),
+        }
+    }
+    None
+}
+
+/// Массивы CSR, которые заполняет `compact_rows`: в куче или в файле
+trait CsrBuffers {
+    fn target(&self, i: usize) -> u32;
+    fn weight(&self, i: usize) -> f32;
+    fn source(&self, i: usize) -> u32;
+    fn set_out(&mut self, i: usize, target: u32, weight: f32);
+    fn set_source(&mut self, i: usize, source: u32);
+    fn set_order(&mut self, i: usize, position: u32);
+}
+
+/// Смещения строк CSR по степеням вершин
+fn degree_offsets(degrees: &[usize]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(degrees.len() + 1);
+    offsets.push(0);
+    let mut total = 0;
+    for &degree in degrees {
+        total += degree;
+        offsets.push(total);
+    }
+    offsets
+}
+
+/// Смещения строк CSR (исходящих и входящих) по связям с повторами пар
+fn csr_row_offsets(edges: &[(String, String, f32)], interner: &VertexInterner) -> (Vec<usize>, Vec<usize>) {
+    let mut out_degrees = vec![0; interner.len()];
+    let mut in_degrees = vec![0; interner.len()];
+    for (source, target, _) in edges {
+        out_degrees[interner[source.as_str()].index()] += 1;
+        in_degrees[interner[target.as_str()].index()] += 1;
+    }
+    (degree_offsets(&out_degrees), degree_offsets(&in_degrees))
+}
+
+/// Раскладка связей по строкам CSR со смещениями `csr_row_offsets` в порядке приёма
+fn scatter_csr_rows<B: CsrBuffers>(
+    edges: &[(String, String, f32)],
+    interner: &VertexInterner,
+    buffers: &mut B,
+    out_offsets: &[usize],
+    in_offsets: &[usize],
+) {
+    let mut out_cursors = out_offsets[..out_offsets.len() - 1].to_vec();
+    let mut in_cursors = in_offsets[..in_offsets.len() - 1].to_vec();
+    for (source, target, weight) in edges {
+        let source_idx = interner[source.as_str()].index();
+        let target_idx = interner[target.as_str()].index();
+        buffers.set_out(out_cursors[source_idx], target_idx as u32, *weight);
+        buffers.set_source(in_cursors[target_idx], source_idx as u32);
+        out_cursors[source_idx] += 1;
+        in_cursors[target_idx] += 1;
+    }
+}
+
+/// Удаление повторов пар из разложенных по строкам связей и построение `out_order`
+///
+/// Строки заполнены в порядке приёма связей, смещения посчитаны по степеням
+/// с повторами. Из повторов пары остаётся первая связь, веса остальных при
+/// `parallel` становятся параллельными связями; оставшиеся связи сдвигаются
+/// к началу массивов, смещения исправляются. В памяти — только одна строка.
+/// Возвращает число убранных повторов.
+fn compact_rows<B: CsrBuffers>(
+    buffers: &mut B,
+    out_offsets: &mut [usize],
+    in_offsets: &mut [usize],
+    mut parallel: Option<&mut HashMap<(usize, usize), SmallVec<[f32; 1]>>>,
+) -> usize {
+    let vertex_count = out_offsets.len() - 1;
+    let mut duplicates = 0;
+    let mut row: Vec<(u32, f32)> = Vec::new();
+    let mut order: Vec<u32> = Vec::new();
+    let mut kept: Vec<Option<u32>> = Vec::new();
+    let mut written = 0;
+    for source in 0..vertex_count {
+        let range = out_offsets[source]..out_offsets[source + 1];
+        out_offsets[source] = written;
+        row.clear();
+        row.extend(range.map(|i| (buffers.target(i), buffers.weight(i))));
+        // Устойчивая сортировка: повторы пары идут в порядке приёма
+        order.clear();
+        order.extend(0..row.len() as u32);
+        order.sort_by_key(|&k| row[k as usize].0);
+        kept.clear();
+        kept.resize(row.len(), Some(0));
+        for pair in order.windows(2) {
+            let (first, repeat) = (pair[0] as usize, pair[1] as usize);
+            if row[first].0 == row[repeat].0 {
+                kept[repeat] = None;
+                duplicates += 1;
+                if let Some(ref mut parallel) = parallel {
+                    parallel.entry((source, row[repeat].0 as usize)).or_default().push(row[repeat].1);
+                }
+            }
+        }
+        let mut count = 0;
+        for (k, &(target, weight)) in row.iter().enumerate() {
+            if let Some(ref mut position) = kept[k] {
+                *position = count;
+                buffers.set_out(written + count as usize, target, weight);
+                count += 1;
+            }
+        }
+        for (rank, position) in order.iter().filter_map(|&k| kept[k as usize]).enumerate() {
+            buffers.set_order(written + rank, position);
+        }
+        written += count as usize;
+    }
+    out_offsets[vertex_count] = written;
+    
+    // Входящие повторяются вместе с исходящими: первое вхождение источника — та же связь
+    if duplicates > 0 {
+        let mut sources: Vec<u32> = Vec::new();
+        let mut seen: HashSet<u32> = HashSet::new();
+        let mut written = 0;
+        for target in 0..vertex_count {
+            let range = in_offsets[target]..in_offsets[target + 1];
+            in_offsets[target] = written;
+            sources.clear();
+            sources.extend(range.map(|i| buffers.source(i)));
+            seen.clear();
+            for &source in &sources {
+                if seen.insert(source) {
+                    buffers.set_source(written, source);
+                    written += 1;
+                }
+            }
+        }
+        in_offsets[vertex_count] = written;
+    }
+    duplicates
+}
+
+/// CSR-файл, который заполняется на месте и затем отображается только для чтения
+///
+/// Файл создаётся сразу под `capacity` связей (с повторами), поэтому связи
+/// пишутся в свои строки в порядке приёма, без промежуточных структур в куче.
+struct MmapCsrWriter {
+    file: std::fs::File,
+    path: TempFile,
+    map: memmap2::MmapMut,
+    vertex_count: usize,
+    capacity: usize,
+}
+
+impl MmapCsrWriter {
+    /// Новый файл в `dir` для графа из `vertex_count` вершин и не более `capacity` связей
+    fn create(dir: &Path, vertex_count: usize, capacity: usize) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Не удалось создать каталог {}", dir.display()))?;
+        let path = TempFile(dir.join(format!("graph_csr_{}.bin", uuid::Uuid::new_v4())));
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path.0)
+            .with_context(|| format!("Не удалось создать файл связей {}", path.0.display()))?;
+        file.set_len(mmap_section(vertex_count, capacity, 6) as u64)
+            .with_context(|| format!("Не удалось выделить место под файл связей {}", path.0.display()))?;
+        // SAFETY: файл создан этим процессом с уникальным именем, другие его не открывают
+        let map = unsafe { memmap2::MmapMut::map_mut(&file) }
+            .with_context(|| format!("Не удалось отобразить файл связей {}", path.0.display()))?;
+        Ok(Self { file, path, map, vertex_count, capacity })
+    }
+    
+    fn slot(&self, section: usize, i: usize) -> std::ops::Range<usize> {
+        let at = mmap_section(self.vertex_count, self.capacity, section) + 4 * i;
+        at..at + 4
+    }
+    
+    fn put(&mut self, section: usize, i: usize, bytes: [u8; 4]) {
+        let slot = self.slot(section, i);
+        self.map[slot].copy_from_slice(&bytes);
+    }
+    
+    /// Заголовок и смещения, сдвиг массивов под итоговое число связей,
+    /// обрезка файла и его отображение только для чтения
+    fn finish(mut self, out_offsets: &[usize], in_offsets: &[usize]) -> Result<MmapEdges> {
+        let vertex_count = self.vertex_count;
+        let edge_count = out_offsets[vertex_count];
+        self.map[..8].copy_from_slice(MMAP_MAGIC);
+        self.map[8..16].copy_from_slice(&(vertex_count as u64).to_le_bytes());
+        self.map[16..24].copy_from_slice(&(edge_count as u64).to_le_bytes());
+        for (section, offsets) in [(0, out_offsets), (1, in_offsets)] {
+            let at = mmap_section(vertex_count, edge_count, section);
+            for (idx, &offset) in offsets.iter().enumerate() {
+                self.map[at + 8 * idx..at + 8 * idx + 8].copy_from_slice(&(offset as u64).to_le_bytes());
+            }
+        }
+        if edge_count < self.capacity {
+            for section in 2..6 {
+                let from = mmap_section(vertex_count, self.capacity, section);
+                let to = mmap_section(vertex_count, edge_count, section);
+                self.map.copy_within(from..from + 4 * edge_count, to);
+            }
+        }
+        self.map.flush()?;
+        
+        let Self { file, path, map, .. } = self;
+        drop(map);
+        file.set_len(mmap_section(vertex_count, edge_count, 6) as u64)?;
+        // SAFETY: файл больше не изменяется
+        let map = unsafe { memmap2::Mmap::map(&file) }
+            .with_context(|| format!("Не удалось отобразить файл связей {}", path.0.display()))?;
+        Ok(MmapEdges {
+            file: Arc::new(MappedFile { map, _path: path }),
+            vertex_count,
+            edge_count,
+        })
+    }
+}
+
+impl CsrBuffers for MmapCsrWriter {
+    fn target(&self, i: usize) -> u32 {
+        read_u32(&self.map[self.slot(2, i)]) as u32
+    }
+    
+    fn weight(&self, i: usize) -> f32 {
+        read_f32(&self.map[self.slot(3, i)])
+    }
+    
+    fn source(&self, i: usize) -> u32 {
+        read_u32(&self.map[self.slot(4, i)]) as u32
+    }
+    
+    fn set_out(&mut self, i: usize, target: u32, weight: f32) {
+        self.put(2, i, target.to_le_bytes());
+        self.put(3, i, weight.to_le_bytes());
+    }
+    
+    fn set_source(&mut self, i: usize, source: u32) {
+        self.put(4, i, source.to_le_bytes());
+    }
+    
+    fn set_order(&mut self, i: usize, position: u32) {
+        self.put(5, i, position.to_le_bytes());
+    }
+}
+
+impl CsrBuffers for CsrEdges {
+    fn target(&self, i: usize) -> u32 {
+        self.out_targets[i]
+    }
+    
+    fn weight(&self, i: usize) -> f32 {
+        self.out_weights[i]
+    }
+    
+    fn source(&self, i: usize) -> u32 {
+        self.in_sources[i]
+    }
+    
+    fn set_out(&mut self, i: usize, target: u32, weight: f32) {
+        self.out_targets[i] = target;
+        self.out_weights[i] = weight;
+    }
+    
+    fn set_source(&mut self, i: usize, source: u32) {
+        self.in_sources[i] = source;
+    }
+    
+    fn set_order(&mut self, i: usize, position: u32) {
+        self.out_order[i] = position;
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(bytes.try_into().expect("4 байта")) as usize
+}
+
+fn read_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes(bytes.try_into().expect("4 байта"))
+}
+
+/// Сигнатура файла графа (`Graph::serialize_to`)
+const GRAPH_FILE_MAGIC: &[u8; 8] = b"GLGRAPH\0";
+
+/// Версия формата файла графа; файлы других версий не загружаются
+const GRAPH_FILE_VERSION: u32 = 2;
+
+/// Контрольная сумма FNV-1a (64 бита)
+#[derive(Debug, Clone, Copy)]
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Запись с подсчётом контрольной суммы записанных байт
+struct ChecksumWriter<W> {
+    inner: W,
+    hash: Fnv1a,
+}
+
+impl<W: std::io::Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, hash: Fnv1a::new() }
+    }
+
+    fn checksum(&self) -> u64 {
+        self.hash.0
+    }
+
+    fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hash.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Чтение с подсчётом контрольной суммы прочитанных байт
+struct ChecksumReader<R> {
+    inner: R,
+    hash: Fnv1a,
+}
+
+impl<R: std::io::Read> ChecksumReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, hash: Fnv1a::new() }
+    }
+
+    fn checksum(&self) -> u64 {
+        self.hash.0
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        std::io::Read::read_exact(self, &mut bytes).context("Файл графа обрезан")?;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    /// Индекс вершины с проверкой диапазона
+    fn index(&mut self, vertex_count: usize) -> Result<usize> {
+        let idx = self.u32()? as usize;
+        anyhow::ensure!(idx < vertex_count, "Индекс вершины {} вне диапазона", idx);
+        Ok(idx)
+    }
+
+    /// Веса параллельных связей или self-loops: количество (u32) и веса
+    fn weights(&mut self) -> Result<SmallVec<[f32; 1]>> {
+        let count = self.u32()?;
+        anyhow::ensure!(count > 0, "Пустой список весов");
+        (0..count).map(|_| self.f32()).collect()
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for ChecksumReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hash.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+fn write_weights(out: &mut impl std::io::Write, weights: &[f32]) -> std::io::Result<()> {
+    out.write_all(&(weights.len() as u32).to_le_bytes())?;
+    for weight in weights {
+        out.write_all(&weight.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+impl EdgeStorage {
+    fn outgoing(&self, idx: usize) -> Neighbors<'_> {
+        match self {
+            EdgeStorage::Lists(lists) => Neighbors::Lists(lists.out[idx].iter()),
+            EdgeStorage::Csr(csr) => Neighbors::Csr(csr.out_targets[csr.out_range(idx)].iter()),
+            EdgeStorage::Mmap(mmap) => Neighbors::Mmap(mmap.array(2, mmap.out_range(idx)).chunks_exact(4)),
+        }
+    }
+
+    fn incoming(&self, idx: usize) -> Neighbors<'_> {
+        match self {
+            EdgeStorage::Lists(lists) => Neighbors::Lists(lists.incoming[idx].iter()),
+            EdgeStorage::Csr(csr) => {
+                Neighbors::Csr(csr.in_sources[csr.in_offsets[idx]..csr.in_offsets[idx + 1]].iter())
+            }
+            EdgeStorage::Mmap(mmap) => Neighbors::Mmap(mmap.array(4, mmap.in_range(idx)).chunks_exact(4)),
+        }
+    }
+
+    /// Вес связи; в CSR и mmap — двоичный поиск по `out_order` строки источника
+    fn weight(&self, source: usize, target: usize) -> Option<f32> {
+        match self {
+            EdgeStorage::Lists(lists) => lists.weights.get(&(source, target)).copied(),
+            EdgeStorage::Csr(csr) => csr.weight(source, target),
+            EdgeStorage::Mmap(mmap) => mmap.weight(source, target),
+        }
+    }
+
+    /// Исходящие связи вершины с весами за один проход (без поиска веса в CSR)
+    fn outgoing_weighted(&self, idx: usize) -> Box<dyn Iterator<Item = (usize, f32)> + '_> {
+        match self {
+            EdgeStorage::Lists(lists) => {
+                Box::new(lists.out[idx].iter().map(move |&target| (target, lists.weights[&(idx, target)])))
+            }
+            EdgeStorage::Csr(csr) => {
+                let range = csr.out_range(idx);
+                Box::new(
+                    csr.out_targets[range.clone()]
+                        .iter()
+                        .zip(&csr.out_weights[range])
+                        .map(|(&target, &weight)| (target as usize, weight)),
+                )
+            }
+            EdgeStorage::Mmap(mmap) => {
+                let range = mmap.out_range(idx);
+                let targets = mmap.array(2, range.clone()).chunks_exact(4).map(read_u32);
+                Box::new(targets.zip(mmap.array(3, range).chunks_exact(4).map(read_f32)))
+            }
+        }
+    }
+
+    fn contains(&self, source: usize, target: usize) -> bool {
+        self.weight(source, target).is_some()
+    }
+
+    /// Все пары (source, target); порядок не определён
+    fn pairs(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        match self {
+            EdgeStorage::Lists(lists) => Box::new(lists.weights.keys().copied()),
+            EdgeStorage::Csr(csr) => Box::new((0..csr.out_offsets.len().saturating_sub(1)).flat_map(
+                move |source| csr.out_targets[csr.out_range(source)].iter().map(move |&t| (source, t as usize)),
+            )),
+            EdgeStorage::Mmap(mmap) => Box::new((0..mmap.vertex_count).flat_map(
+                move |source| self.outgoing(source).map(move |target| (source, target)),
+            )),
+        }
+    }
+
+    /// Списки смежности для изменения графа
+    ///
+    /// CSR и mmap разворачиваются целиком, за O(V + E) времени и памяти, даже
+    /// ради одной связи; граф остаётся в списках, пока не вызван `Graph::to_csr`.
+    fn lists_mut(&mut self) -> &mut AdjacencyLists {
+        match self {
+            EdgeStorage::Csr(csr) => *self = EdgeStorage::Lists(std::mem::take(csr).into_lists()),
+            EdgeStorage::Mmap(mmap) => *self = EdgeStorage::Lists(mmap.to_lists()),
+            EdgeStorage::Lists(_) => {}
+        }
+        match self {
+            EdgeStorage::Lists(lists) => lists,
+            _ => unreachable!("CSR развёрнут выше"),
+        }
+    }
+
+    /// Оценка занятой кучи: буферы векторов и таблица весов (по ёмкости)
+    fn heap_bytes(&self) -> usize {
+        use std::mem::size_of;
+        match self {
+            EdgeStorage::Lists(lists) => {
+                let list_bytes = |adjacency: &Vec<SmallVec<[usize; 4]>>| {
+                    adjacency.capacity() * size_of::<SmallVec<[usize; 4]>>()
+                        + adjacency
+                            .iter()
+                            .filter(|list| list.spilled())
+                            .map(|list| list.capacity() * size_of::<usize>())
+                            .sum::<usize>()
+                };
+                // Элемент таблицы и байт управляющих данных hashbrown на слот
+                let weight_bytes = lists.weights.capacity() * (size_of::<((usize, usize), f32)>() + 1);
+                list_bytes(&lists.out) + list_bytes(&lists.incoming) + weight_bytes
+            }
+            EdgeStorage::Csr(csr) => {
+                (csr.out_offsets.capacity() + csr.in_offsets.capacity()) * size_of::<usize>()
+                    + (csr.out_targets.capacity() + csr.out_order.capacity() + csr.in_sources.capacity())
+                        * size_of::<u32>()
+                    + csr.out_weights.capacity() * size_of::<f32>()
+            }
+            // Страницы файла принадлежат кешу ОС, а не куче процесса
+            EdgeStorage::Mmap(_) => 0,
+        }
+    }
+}
+
+/// Соседи вершины в любом из представлений связей
+enum Neighbors<'a> {
+    Lists(std::slice::Iter<'a, usize>),
+    Csr(std::slice::Iter<'a, u32>),
+    Mmap(std::slice::ChunksExact<'a, u8>),
+}
+
+impl Iterator for Neighbors<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match self {
+            Neighbors::Lists(iter) => iter.next().copied(),
+            Neighbors::Csr(iter) => iter.next().map(|&idx| idx as usize),
+            Neighbors::Mmap(iter) => iter.next().map(read_u32),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Neighbors::Lists(iter) => iter.size_hint(),
+            Neighbors::Csr(iter) => iter.size_hint(),
+            Neighbors::Mmap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Neighbors<'_> {}
+
+/// Handle вершины: её индекс в `VertexInterner` графа
+///
+/// Копируется без аллокаций, поэтому алгоритмы конвейера работают с handles,
+/// а UID вершин (`Graph::vertex_uid`) нужны только на границе с Neo4j и protobuf.
+/// Handle осмыслен только для графа, который его выдал; `Graph::remove_vertex`
+/// меняет handle последней вершины.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VertexId(u32);
+
+impl VertexId {
+    /// Индекс вершины в графе
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+    
+    fn from_index(idx: usize) -> Self {
+        Self(idx as u32)
+    }
+}
+
+/// Интернер UID вершин: каждая строка хранится один раз, handles выдаются подряд
+///
+/// Строки лежат только в `uids`; таблица `handles` хранит handles, размещённые
+/// по хешу их UID, и при поиске сравнивает UID из `uids`.
+#[derive(Debug, Clone, Default)]
+pub struct VertexInterner {
+    /// Handles по хешу UID
+    handles: hashbrown::HashTable<VertexId>,
+    
+    hasher: hashbrown::hash_map::DefaultHashBuilder,
+    
+    /// Handle (индекс) -> UID
+    uids: Vec<String>,
+}
+
+impl VertexInterner {
+    /// Пустой интернер
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Интернер с handles по порядку уникальных UID
+    ///
+    /// Повторный UID не получает handle (остаётся handle первого вхождения).
+    pub fn from_uids(uids: Vec<String>) -> Self {
+        let mut interner = Self { uids, ..Self::default() };
+        let Self { handles, hasher, uids } = &mut interner;
+        handles.reserve(uids.len(), |&h| hasher.hash_one(uids[h.index()].as_str()));
+        for (idx, uid) in uids.iter().enumerate() {
+            let hash = hasher.hash_one(uid.as_str());
+            if let hashbrown::hash_table::Entry::Vacant(entry) =
+                handles.entry(hash, |&h| uids[h.index()] == *uid, |&h| hasher.hash_one(uids[h.index()].as_str()))
+            {
+                entry.insert(VertexId::from_index(idx));
+            }
+        }
+        interner
+    }
+    
+    /// Handle UID с добавлением нового UID при необходимости
+    ///
+    /// # Panics
+    /// Если вершин становится больше `u32::MAX`.
+    pub fn intern(&mut self, uid: &str) -> VertexId {
+        if let Some(handle) = self.get(uid) {
+            return handle;
+        }
+        assert!(self.uids.len() < u32::MAX as usize, "Слишком много вершин для VertexId");
+        let handle = VertexId::from_index(self.uids.len());
+        let Self { handles, hasher, uids } = self;
+        handles.insert_unique(hasher.hash_one(uid), handle, |&h| hasher.hash_one(uids[h.index()].as_str()));
+        uids.push(uid.to_string());
+        handle
+    }
+    
+    /// Удаление UID: последний UID занимает освободившийся handle
+    fn swap_remove(&mut self, handle: VertexId) {
+        let hash = self.hasher.hash_one(self.uids[handle.index()].as_str());
+        if let Ok(entry) = self.handles.find_entry(hash, |&h| h == handle) {
+            entry.remove();
+        }
+        self.uids.swap_remove(handle.index());
+        if let Some(moved) = self.uids.get(handle.index()) {
+            let last = VertexId::from_index(self.uids.len());
+            let hash = self.hasher.hash_one(moved.as_str());
+            if let Some(slot) = self.handles.find_mut(hash, |&h| h == last) {
+                *slot = handle;
+            }
+        }
+    }
+    
+    /// Handle уже добавленного UID
+    pub fn get(&self, uid: &str) -> Option<VertexId> {
+        self.handles
+            .find(self.hasher.hash_one(uid), |&h| self.uids[h.index()] == uid)
+            .copied()
+    }
+    
+    /// UID по handle
+    pub fn resolve(&self, handle: VertexId) -> &str {
+        &self.uids[handle.index()]
+    }
+    
+    /// Все UID в порядке handles
+    pub fn uids(&self) -> &[String] {
+        &self.uids
+    }
+    
+    /// Количество вершин
+    pub fn len(&self) -> usize {
+        self.uids.len()
+    }
+    
+    /// Пуст ли интернер
+    pub fn is_empty(&self) -> bool {
+        self.uids.is_empty()
+    }
+}
+
+impl std::ops::Index<&str> for VertexInterner {
+    type Output = VertexId;
+    
+    fn index(&self, uid: &str) -> &VertexId {
+        self.handles
+            .find(self.hasher.hash_one(uid), |&h| self.uids[h.index()] == uid)
+            .unwrap_or_else(|| panic!("Неизвестный UID вершины: {}", uid))
+    }
+}
+
+/// Цвета для DFS
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    White, // Не посещена
+    Gray,  // В процессе обработки
+    Black, // Обработана
+}
+
+/// Атрибут вершины: название статьи (`Text`)
+pub const ATTR_TITLE: &str = "title";
+
+/// Атрибут вершины: год публикации (`Int`), слои `LayeringStrategy::ByProperty`
+pub const ATTR_YEAR: &str = "year";
+
+/// Атрибут вершины: кластер (домен/тема, `Text`), группировка слоёв при размещении
+pub const ATTR_CLUSTER: &str = "cluster";
+
+/// Атрибут вершины: степенная центральность (`Float`)
+pub const ATTR_DEGREE_CENTRALITY: &str = "degree_centrality";
+
+/// Значение атрибута вершины
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttrValue {
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl AttrValue {
+    /// Целое значение
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            AttrValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+    
+    /// Числовое значение (целые приводятся к f64)
+    pub fn as_float(&self) -> Option<f64> {
+        match *self {
+            AttrValue::Float(value) => Some(value),
+            AttrValue::Int(value) => Some(value as f64),
+            _ => None,
+        }
+    }
+    
+    /// Строковое значение
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            AttrValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+    
+    /// Логическое значение
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            AttrValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Атрибуты вершин по столбцам: значение вершины — элемент столбца с её индексом
+///
+/// Столбец короче числа вершин, если у последних вершин атрибут не задан.
+#[derive(Debug, Clone, Default)]
+struct VertexAttributes {
+    columns: HashMap<&'static str, Vec<Option<AttrValue>>>,
+}
+
+impl VertexAttributes {
+    fn get(&self, key: &str, idx: usize) -> Option<&AttrValue> {
+        self.columns.get(key)?.get(idx)?.as_ref()
+    }
+    
+    fn set(&mut self, key: &'static str, idx: usize, value: AttrValue) {
+        let column = self.columns.entry(key).or_default();
+        if column.len() <= idx {
+            column.resize(idx + 1, None);
+        }
+        column[idx] = Some(value);
+    }
+    
+    /// Удаление значений вершины `idx`: значения последней вершины переезжают на её место
+    fn swap_remove(&mut self, idx: usize, vertex_count: usize) {
+        for column in self.columns.values_mut() {
+            column.resize(vertex_count, None);
+            column.swap_remove(idx);
+        }
+    }
+    
+    /// Значения вершин `vertices` в их порядке (для подграфа)
+    fn select(&self, vertices: &[VertexId]) -> Self {
+        let columns = self
+            .columns
+            .iter()
+            .map(|(&key, column)| {
+                let values = vertices.iter().map(|vertex| column.get(vertex.index()).cloned().flatten());
+                (key, values.collect())
+            })
+            .collect();
+        Self { columns }
+    }
+}
+
+/// Ключ связи мультиграфа
+///
+/// Индексы вершин пары и номер связи среди параллельных (0 — первая,
+/// её вес возвращает `Graph::get_edge_weight`). У self-loop `source == target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeKey {
+    pub source: usize,
+    pub target: usize,
+    pub index: usize,
+}
+
+/// Статистика графа
+#[derive(Debug, Clone)]
+pub struct GraphStatistics {
+    pub vertex_count: usize,
+    pub edge_count: usize,
+    pub avg_out_degree: f64,
+    pub avg_in_degree: f64,
+    pub density: f64,
+    pub is_dag: bool,
+    pub isolated_vertices: usize,
+    /// Распределение исходящих степеней
+    pub out_degree: DegreeDistribution,
+    /// Распределение входящих степеней
+    pub in_degree: DegreeDistribution,
+    /// Вершин в наибольшей компоненте слабой связности
+    pub largest_component: usize,
+    /// Вершины без входящих связей
+    pub sources: usize,
+    /// Вершины без исходящих связей
+    pub sinks: usize,
+    /// Оценка диаметра наибольшей компоненты без учёта направления (нижняя граница)
+    pub approximate_diameter: usize,
+}
+
+/// Перцентили степеней вершин
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DegreeDistribution {
+    pub p50: usize,
+    pub p90: usize,
+    pub p99: usize,
+    pub max: usize,
+}
+
+impl DegreeDistribution {
+    /// Перцентили по степеням вершин (nearest-rank)
+    pub fn from_degrees(mut degrees: Vec<usize>) -> Self {
+        if degrees.is_empty() {
+            return Self::default();
+        }
+        degrees.sort_unstable();
+        let percentile = |p: usize| degrees[(degrees.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: degrees[degrees.len() - 1],
+        }
+    }
+}
+
+/// Счётчики приёма связей строителем графа
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestStats {
+    /// Всего переданных связей
+    pub edges_seen: usize,
+
+    /// Связи с пустым source или target
+    pub invalid_ids: usize,
+
+    /// Отброшенные self-loops
+    pub self_loops: usize,
+
+    /// Повторные связи (заполняется при построении)
+    pub duplicates: usize,
+
+    /// Связи, вошедшие в граф (заполняется при построении)
+    pub edges_added: usize,
+
+    /// Сохранённые self-loops (`GraphBuilder::allow_self_loops`)
+    #[serde(default)]
+    pub self_loops_kept: usize,
+
+    /// Сохранённые параллельные связи сверх первой (`GraphBuilder::allow_parallel_edges`)
+    #[serde(default)]
+    pub parallel_edges: usize,
+}
+
+impl IngestStats {
+    /// Количество отброшенных связей
+    pub fn dropped(&self) -> usize {
+        self.invalid_ids + self.self_loops + self.duplicates
+    }
+}
+
+/// Строитель графа
+pub struct GraphBuilder {
+    vertices: HashSet<String>,
+    edges: Vec<(String, String, f32)>,
+    self_loops: Vec<(String, f32)>,
+    attributes: Vec<(String, &'static str, AttrValue)>,
+    deterministic: bool,
+    allow_self_loops: bool,
+    allow_parallel_edges: bool,
+    csr_threshold: usize,
+    mmap_dir: Option<PathBuf>,
+    mmap_threshold: usize,
+    stats: IngestStats,
+}
+
+impl GraphBuilder {
+    /// Создание нового строителя
+    pub fn new() -> Self {
+        Self {
+            vertices: HashSet::new(),
+            edges: Vec::new(),
+            self_loops: Vec::new(),
+            attributes: Vec::new(),
+            deterministic: false,
+            allow_self_loops: false,
+            allow_parallel_edges: false,
+            csr_threshold: DEFAULT_CSR_THRESHOLD,
+            mmap_dir: None,
+            mmap_threshold: DEFAULT_MMAP_THRESHOLD,
+            stats: IngestStats::default(),
+        }
+    }
+    
+    /// Детерминированное построение: индексы вершин по возрастанию ID
+    ///
+    /// Без этого порядок вершин (и всё, что от него зависит: порядок обхода,
+    /// топологический порядок) определяется итерацией `HashSet`.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+    
+    /// Сохранять self-loops (отдельно от списков смежности, см. `Graph::self_loop_count`)
+    ///
+    /// Без этого self-loops отбрасываются и учитываются в `IngestStats::self_loops`.
+    pub fn allow_self_loops(mut self, allow: bool) -> Self {
+        self.allow_self_loops = allow;
+        self
+    }
+    
+    /// Сохранять параллельные связи как связи мультиграфа (см. `Graph::edge_keys`)
+    ///
+    /// Без этого повторные связи отбрасываются и учитываются в `IngestStats::duplicates`.
+    pub fn allow_parallel_edges(mut self, allow: bool) -> Self {
+        self.allow_parallel_edges = allow;
+        self
+    }
+    
+    /// Число связей, с которого граф хранится в CSR (см. `Graph::is_csr`)
+    ///
+    /// 0 — всегда CSR, `usize::MAX` — всегда списки смежности.
+    pub fn csr_threshold(mut self, threshold: usize) -> Self {
+        self.csr_threshold = threshold;
+        self
+    }
+    
+    /// Каталог для CSR-файлов графов от `mmap_threshold` связей (см. `Graph::to_mmap`)
+    ///
+    /// None — граф всегда в куче.
+    pub fn memory_mapped(mut self, dir: Option<PathBuf>) -> Self {
+        self.mmap_dir = dir;
+        self
+    }
+    
+    /// Число связей, с которого граф при заданном каталоге хранится в mmap-файле
+    pub fn mmap_threshold(mut self, threshold: usize) -> Self {
+        self.mmap_threshold = threshold;
+        self
+    }
+    
+    /// Допустима ли связь: оба ID непустые и связь не является self-loop
+    pub fn accepts(source: &str, target: &str) -> bool {
+        !source.trim().is_empty() && !target.trim().is_empty() && source != target
+    }
+    
+    /// Добавление связи
+    pub fn add_edge(&mut self, source: String, target: String, weight: f32) -> Result<()> {
+        if source == target && !self.allow_self_loops {
+            self.stats.edges_seen += 1;
+            self.stats.self_loops += 1;
+            return Err(anyhow::anyhow!("Self-loops не поддерживаются"));
+        }
+        
+        self.ingest_edge(&source, &target, weight);
+        Ok(())
+    }
+    
+    /// Приём связи из внешних данных
+    ///
+    /// Связи с пустыми ID и self-loops (если они не разрешены) не добавляются,
+    /// а учитываются в статистике. Возвращает true, если связь принята
+    /// (дубликаты отсеиваются при построении).
+    pub fn ingest_edge(&mut self, source: &str, target: &str, weight: f32) -> bool {
+        self.stats.edges_seen += 1;
+        if source.trim().is_empty() || target.trim().is_empty() {
+            self.stats.invalid_ids += 1;
+            return false;
+        }
+        if source == target {
+            if !self.allow_self_loops {
+                self.stats.self_loops += 1;
+                return false;
+            }
+            self.vertices.insert(source.to_string());
+            self.self_loops.push((source.to_string(), weight));
+            self.stats.self_loops_kept += 1;
+            return true;
+        }
+        
+        self.vertices.insert(source.to_string());
+        self.vertices.insert(target.to_string());
+        self.edges.push((source.to_string(), target.to_string(), weight));
+        true
+    }
+    
+    /// Счётчики принятых и отброшенных связей на текущий момент
+    pub fn stats(&self) -> &IngestStats {
+        &self.stats
+    }
+    
+    /// Добавление вершины
+    pub fn add_vertex(&mut self, vertex_id: String) {
+        self.vertices.insert(vertex_id);
+    }
+    
+    /// Атрибут вершины (см. `Graph::set_attribute`)
+    ///
+    /// При повторной установке остаётся последнее значение; атрибуты вершин,
+    /// не попавших в граф, отбрасываются.
+    pub fn set_attribute(&mut self, vertex_id: &str, key: &'static str, value: AttrValue) {
+        self.attributes.push((vertex_id.to_string(), key, value));
+    }
+    
+    /// Построение графа
+    pub fn build(self) -> Result<Graph> {
+        self.build_with_stats().map(|(graph, _)| graph)
+    }
+    
+    /// Построение графа со статистикой приёма связей
+    ///
+    /// Повторные связи отбрасываются (остаётся вес первой), а с
+    /// `allow_parallel_edges` сохраняются как параллельные. Граф собирается
+    /// в списках смежности, а от `csr_threshold` принятых связей (с повторами)
+    /// раскладывается подсчётом степеней сразу в CSR, минуя списки, с
+    /// `memory_mapped` от `mmap_threshold` связей — в CSR-файл.
+    pub fn build_with_stats(self) -> Result<(Graph, IngestStats)> {
+        let mut stats = self.stats;
+        let mut graph = Graph::new();
+        
+        // Создание маппинга вершин
+        let mut vertices: Vec<_> = self.vertices.into_iter().collect();
+        if self.deterministic {
+            vertices.sort_unstable();
+        }
+        if vertices.len() > u32::MAX as usize {
+            return Err(anyhow::anyhow!("Слишком много вершин: {}", vertices.len()));
+        }
+        graph.interner = VertexInterner::from_uids(vertices);
+        
+        let vertex_count = graph.vertex_count();
+        let accepted = self.edges.len();
+        let mmap_dir = self.mmap_dir.as_deref().filter(|_| accepted >= self.mmap_threshold);
+        if mmap_dir.is_some() || accepted >= self.csr_threshold {
+            // CSR из связей подсчётом степеней: строки заполняются в порядке приёма
+            let (mut out_offsets, mut in_offsets) = csr_row_offsets(&self.edges, &graph.interner);
+            let parallel = self.allow_parallel_edges.then_some(&mut graph.parallel_weights);
+            let repeated = match mmap_dir {
+                Some(dir) => {
+                    let mut writer = MmapCsrWriter::create(dir, vertex_count, accepted)?;
+                    scatter_csr_rows(&self.edges, &graph.interner, &mut writer, &out_offsets, &in_offsets);
+                    let repeated = compact_rows(&mut writer, &mut out_offsets, &mut in_offsets, parallel);
+                    graph.edges = EdgeStorage::Mmap(writer.finish(&out_offsets, &in_offsets)?);
+                    repeated
+                }
+                None => {
+                    let mut csr = CsrEdges::with_capacity(accepted);
+                    scatter_csr_rows(&self.edges, &graph.interner, &mut csr, &out_offsets, &in_offsets);
+                    let repeated = csr.compact(out_offsets, in_offsets, parallel);
+                    graph.edges = EdgeStorage::Csr(csr);
+                    repeated
+                }
+            };
+            graph.edge_count = accepted - repeated;
+            if self.allow_parallel_edges {
+                stats.parallel_edges += repeated;
+            } else {
+                stats.duplicates += repeated;
+            }
+        } else {
+            let mut lists = AdjacencyLists::with_vertices(vertex_count);
+            
+            // Добавление связей
+            for (source, target, weight) in self.edges {
+                let source_idx = graph.interner[source.as_str()].index();
+                let target_idx = graph.interner[target.as_str()].index();
+            
+                match lists.weights.entry((source_idx, target_idx)) {
+                    hashbrown::hash_map::Entry::Occupied(_) if self.allow_parallel_edges => {
+                        graph.parallel_weights.entry((source_idx, target_idx)).or_default().push(weight);
+                        stats.parallel_edges += 1;
+                        continue;
+                    }
+                    hashbrown::hash_map::Entry::Occupied(_) => {
+                        stats.duplicates += 1;
+                        continue;
+                    }
+                    hashbrown::hash_map::Entry::Vacant(entry) => {
+                        entry.insert(weight);
+                    }
+                }
+                lists.out[source_idx].push(target_idx);
+                lists.incoming[target_idx].push(source_idx);
+                graph.edge_count += 1;
+            }
+            graph.edges = EdgeStorage::Lists(lists);
+        }
+        for (vertex_id, weight) in self.self_loops {
+            let idx = graph.interner[vertex_id.as_str()].index();
+            graph.self_loops.entry(idx).or_default().push(weight);
+        }
+        for (vertex_id, key, value) in self.attributes {
+            if let Some(vertex) = graph.vertex_handle(&vertex_id) {
+                graph.set_attribute(vertex, key, value);
+            }
+        }
+        stats.edges_added = graph.edge_count;
+        
+        Ok((graph, stats))
+    }
+    
+    /// Построение графа прямо в CSR-файле каталога `memory_mapped` за два прохода по связям
+    ///
+    /// `edges` вызывается дважды и должен оба раза выдавать одни и те же связи
+    /// (ошибка чтения связей прерывает построение):
+    /// первый проход интернирует вершины и считает степени, второй раскладывает
+    /// связи по строкам файла. В куче остаются только UID и смещения строк,
+    /// поэтому так строятся графы, связи которых не помещаются в RAM. Результат
+    /// совпадает с `build_with_stats` для тех же связей.
+    pub fn build_mmap_streamed<I, S>(self, mut edges: impl FnMut() -> Result<I>) -> Result<(Graph, IngestStats)>
+    where
+        I: Iterator<Item = Result<(S, S, f32)>>,
+        S: AsRef<str>,
+    {
+        let dir = self
+            .mmap_dir
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Потоковое построение требует каталога memory_mapped"))?;
+        if !self.edges.is_empty() || !self.self_loops.is_empty() {
+            return Err(anyhow::anyhow!("Потоковое построение не принимает связи, добавленные через ingest_edge"));
+        }
+        let mut stats = self.stats;
+        let mut graph = Graph::new();
+        
+        // Первый проход: вершины, степени и self-loops
+        let mut interner = VertexInterner::new();
+        for vertex_id in &self.vertices {
+            interner.intern(vertex_id);
+        }
+        let mut out_degrees: Vec<usize> = vec![0; interner.len()];
+        let mut in_degrees: Vec<usize> = vec![0; interner.len()];
+        let mut self_loops = Vec::new();
+        for edge in edges()? {
+            let (source, target, weight) = edge?;
+            let (source, target) = (source.as_ref(), target.as_ref());
+            stats.edges_seen += 1;
+            if source.trim().is_empty() || target.trim().is_empty() {
+                stats.invalid_ids += 1;
+                continue;
+            }
+            if source == target {
+                if !self.allow_self_loops {
+                    stats.self_loops += 1;
+                    continue;
+                }
+                interner.intern(source);
+                self_loops.push((source.to_string(), weight));
+                stats.self_loops_kept += 1;
+                continue;
+            }
+            let source_idx = interner.intern(source).index();
+            let target_idx = interner.intern(target).index();
+            out_degrees.resize(interner.len(), 0);
+            in_degrees.resize(interner.len(), 0);
+            out_degrees[source_idx] += 1;
+            in_degrees[target_idx] += 1;
+        }
+        out_degrees.resize(interner.len(), 0);
+        in_degrees.resize(interner.len(), 0);
+        if self.deterministic {
+            let mut rows: Vec<_> = interner.uids.into_iter().zip(out_degrees).zip(in_degrees).collect();
+            rows.sort_unstable_by(|((a, _), _), ((b, _), _)| a.cmp(b));
+            let (uids, degrees): (Vec<_>, Vec<_>) = rows.into_iter().map(|((uid, out), inc)| (uid, (out, inc))).unzip();
+            (out_degrees, in_degrees) = degrees.into_iter().unzip();
+            interner = VertexInterner::from_uids(uids);
+        }
+        graph.interner = interner;
+        
+        // Второй проход: связи в строки файла в порядке приёма
+        let mut out_offsets = degree_offsets(&out_degrees);
+        let mut in_offsets = degree_offsets(&in_degrees);
+        let vertex_count = graph.vertex_count();
+        let capacity = out_offsets[vertex_count];
+        let mut writer = MmapCsrWriter::create(&dir, vertex_count, capacity)?;
+        let mut out_cursors = out_offsets[..vertex_count].to_vec();
+        let mut in_cursors = in_offsets[..vertex_count].to_vec();
+        let changed = || anyhow::anyhow!("Связи второго прохода не совпадают с первым");
+        for edge in edges()? {
+            let (source, target, weight) = edge?;
+            let (source, target) = (source.as_ref(), target.as_ref());
+            if source.trim().is_empty() || target.trim().is_empty() || source == target {
+                continue;
+            }
+            let source_idx = graph.interner.get(source).ok_or_else(changed)?.index();
+            let target_idx = graph.interner.get(target).ok_or_else(changed)?.index();
+            let out_at = out_cursors[source_idx];
+            let in_at = in_cursors[target_idx];
+            if out_at == out_offsets[source_idx + 1] || in_at == in_offsets[target_idx + 1] {
+                return Err(changed());
+            }
+            writer.set_out(out_at, target_idx as u32, weight);
+            writer.set_source(in_at, source_idx as u32);
+            out_cursors[source_idx] += 1;
+            in_cursors[target_idx] += 1;
+        }
+        if out_cursors.iter().zip(&out_offsets[1..]).any(|(cursor, end)| cursor != end) {
+            return Err(changed());
+        }
+        
+        let parallel = self.allow_parallel_edges.then_some(&mut graph.parallel_weights);
+        let repeated = compact_rows(&mut writer, &mut out_offsets, &mut in_offsets, parallel);
+        if self.allow_parallel_edges {
+            stats.parallel_edges += repeated;
+        } else {
+            stats.duplicates += repeated;
+        }
+        let edges = writer.finish(&out_offsets, &in_offsets)?;
+        graph.edge_count = edges.edge_count;
+        graph.edges = EdgeStorage::Mmap(edges);
+        for (vertex_id, weight) in self_loops {
+            let idx = graph.interner[vertex_id.as_str()].index();
+            graph.self_loops.entry(idx).or_default().push(weight);
+        }
+        for (vertex_id, key, value) in self.attributes {
+            if let Some(vertex) = graph.vertex_handle(&vertex_id) {
+                graph.set_attribute(vertex, key, value);
+            }
+        }
+        stats.edges_added = graph.edge_count;
+        
+        Ok((graph, stats))
+    }
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Связи, сброшенные на диск, чтобы построить граф после их загрузки
+///
+/// Запись связи — длины (u32) и байты UID источника и цели, затем вес (f32).
+/// Граф строится `GraphBuilder::build_mmap_streamed`, поэтому ни связи, ни
+/// их UID не копятся в куче. Файл удаляется при освобождении.
+pub struct EdgeSpill {
+    dir: PathBuf,
+    path: TempFile,
+    out: std::io::BufWriter<std::fs::File>,
+    edges: usize,
+}
+
+impl EdgeSpill {
+    /// Новый файл связей в каталоге `dir`; там же будет CSR-файл графа
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Не удалось создать каталог {}", dir.display()))?;
+        let path = TempFile(dir.join(format!("graph_edges_{}.bin", uuid::Uuid::new_v4())));
+        let file = std::fs::File::create(&path.0)
+            .with_context(|| format!("Не удалось создать файл связей {}", path.0.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            path,
+            out: std::io::BufWriter::new(file),
+            edges: 0,
+        })
+    }
+    
+    /// Запись связи
+    pub fn push(&mut self, source: &str, target: &str, weight: f32) -> Result<()> {
+        use std::io::Write;
+        
+        for uid in [source, target] {
+            self.out.write_all(&(uid.len() as u32).to_le_bytes())?;
+            self.out.write_all(uid.as_bytes())?;
+        }
+        self.out.write_all(&weight.to_le_bytes())?;
+        self.edges += 1;
+        Ok(())
+    }
+    
+    /// Количество записанных связей
+    pub fn len(&self) -> usize {
+        self.edges
+    }
+    
+    /// Нет ни одной записанной связи
+    pub fn is_empty(&self) -> bool {
+        self.edges == 0
+    }
+    
+    /// Граф из записанных связей с настройками `builder` (связи — в CSR-файле)
+    pub fn build(self, builder: GraphBuilder) -> Result<(Graph, IngestStats)> {
+        use std::io::Write;
+        
+        let Self { dir, path, mut out, .. } = self;
+        out.flush()
+            .with_context(|| format!("Не удалось записать файл связей {}", path.0.display()))?;
+        drop(out);
+        builder.memory_mapped(Some(dir)).build_mmap_streamed(|| {
+            let file = std::fs::File::open(&path.0)
+                .with_context(|| format!("Не удалось открыть файл связей {}", path.0.display()))?;
+            let mut input = std::io::BufReader::new(file);
+            Ok(std::iter::from_fn(move || read_spilled_edge(&mut input).transpose()))
+        })
+    }
+}
+
+/// Следующая связь файла `EdgeSpill` (None — файл прочитан)
+fn read_spilled_edge(input: &mut impl std::io::Read) -> Result<Option<(String, String, f32)>> {
+    let mut len = [0u8; 4];
+    // Конец файла допустим только между записями
+    if input.read(&mut len[..1])? == 0 {
+        return Ok(None);
+    }
+    input.read_exact(&mut len[1..])?;
+    let source = read_spilled_uid(input, len)?;
+    input.read_exact(&mut len)?;
+    let target = read_spilled_uid(input, len)?;
+    let mut weight = [0u8; 4];
+    input.read_exact(&mut weight)?;
+    Ok(Some((source, target, f32::from_le_bytes(weight))))
+}
+
+fn read_spilled_uid(input: &mut impl std::io::Read, len: [u8; 4]) -> Result<String> {
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    input.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).context("UID вершины не в UTF-8")
+}
+
+/// Число шардов `ShardedGraphBuilder`
+const BUILDER_SHARDS: usize = 64;
+
+/// Строитель графа с параллельным приёмом связей
+///
+/// Связи раскладываются по шардам по хешу пары (source, target); у каждого
+/// шарда свой мьютекс, поэтому потоки почти не конкурируют, а дубликаты
+/// отсеиваются сразу при вставке. `build` собирает CSR через Rayon.
+///
+/// Результат совпадает с детерминированным `GraphBuilder`, получившим те же
+/// связи в порядке их позиций (`ingest_edge_at`): вершины по возрастанию ID,
+/// соседи в порядке приёма, у повторной связи остаётся вес первой.
+pub struct ShardedGraphBuilder {
+    /// Настройки, отдельные вершины и атрибуты
+    base: GraphBuilder,
+    shards: Vec<std::sync::Mutex<EdgeShard>>,
+    hasher: hashbrown::hash_map::DefaultHashBuilder,
+    next_position: std::sync::atomic::AtomicUsize,
+}
+
+/// Связи одного шарда: (source, target) -> (позиция приёма, вес) по каждой связи пары
+#[derive(Default)]
+struct EdgeShard {
+    edges: HashMap<(String, String), SmallVec<[(usize, f32); 1]>>,
+    stats: IngestStats,
+}
+
+impl GraphBuilder {
+    /// Строитель с параллельным приёмом связей и теми же настройками
+    ///
+    /// Уже добавленные связи, вершины и атрибуты переносятся.
+    pub fn sharded(mut self) -> ShardedGraphBuilder {
+        let edges = std::mem::take(&mut self.edges);
+        let self_loops = std::mem::take(&mut self.self_loops);
+        let builder = ShardedGraphBuilder {
+            base: self,
+            shards: (0..BUILDER_SHARDS).map(|_| Default::default()).collect(),
+            hasher: Default::default(),
+            next_position: Default::default(),
+        };
+        for (source, target, weight) in edges {
+            let mut shard = builder.shard(&source, &target);
+            builder.insert(&mut shard, builder.next_position(), source, target, weight);
+        }
+        for (vertex_id, weight) in self_loops {
+            let mut shard = builder.shard(&vertex_id, &vertex_id);
+            builder.insert(&mut shard, builder.next_position(), vertex_id.clone(), vertex_id, weight);
+        }
+        builder
+    }
+}
+
+impl ShardedGraphBuilder {
+    /// Строитель с настройками `GraphBuilder` по умолчанию
+    pub fn new() -> Self {
+        GraphBuilder::new().sharded()
+    }
+    
+    fn next_position(&self) -> usize {
+        self.next_position.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    }
+    
+    /// Приём связи (см. `GraphBuilder::ingest_edge`); можно вызывать из нескольких потоков
+    ///
+    /// Позицией связи становится порядок вызова, поэтому при одновременной вставке
+    /// порядок соседей и вес повторной связи не детерминированы (см. `ingest_edge_at`).
+    pub fn ingest_edge(&self, source: &str, target: &str, weight: f32) -> bool {
+        self.ingest_edge_at(self.next_position(), source, target, weight)
+    }
+    
+    /// Приём связи с позицией `position` во входных данных
+    ///
+    /// Соседи вершины упорядочиваются по позициям связей, у повторной связи
+    /// остаётся вес связи с меньшей позицией. Позиции должны быть уникальны.
+    pub fn ingest_edge_at(&self, position: usize, source: &str, target: &str, weight: f32) -> bool {
+        let mut shard = self.shard(source, target);
+        shard.stats.edges_seen += 1;
+        if source.trim().is_empty() || target.trim().is_empty() {
+            shard.stats.invalid_ids += 1;
+            return false;
+        }
+        if source == target {
+            if !self.base.allow_self_loops {
+                shard.stats.self_loops += 1;
+                return false;
+            }
+            shard.stats.self_loops_kept += 1;
+        }
+        self.insert(&mut shard, position, source.to_string(), target.to_string(), weight);
+        true
+    }
+    
+    fn shard(&self, source: &str, target: &str) -> std::sync::MutexGuard<'_, EdgeShard> {
+        let index = self.hasher.hash_one((source, target)) as usize % self.shards.len();
+        self.shards[index].lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+    
+    /// Вставка принятой связи в её шард; self-loops и (если разрешены)
+    /// параллельные связи сохраняются все, иначе остаётся связь с меньшей позицией
+    fn insert(&self, shard: &mut EdgeShard, position: usize, source: String, target: String, weight: f32) {
+        let self_loop = source == target;
+        match shard.edges.entry((source, target)) {
+            hashbrown::hash_map::Entry::Vacant(entry) => {
+                entry.insert(SmallVec::from_elem((position, weight), 1));
+            }
+            hashbrown::hash_map::Entry::Occupied(mut entry) => {
+                let weights = entry.get_mut();
+                if self_loop {
+                    weights.push((position, weight));
+                } else if self.base.allow_parallel_edges {
+                    weights.push((position, weight));
+                    shard.stats.parallel_edges += 1;
+                } else {
+                    shard.stats.duplicates += 1;
+                    if position < weights[0].0 {
+                        weights[0] = (position, weight);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Добавление вершины без связей
+    pub fn add_vertex(&mut self, vertex_id: String) {
+        self.base.add_vertex(vertex_id);
+    }
+    
+    /// Атрибут вершины (см. `GraphBuilder::set_attribute`)
+    pub fn set_attribute(&mut self, vertex_id: &str, key: &'static str, value: AttrValue) {
+        self.base.set_attribute(vertex_id, key, value);
+    }
+    
+    /// Построение графа
+    pub fn build(self) -> Result<Graph> {
+        self.build_with_stats().map(|(graph, _)| graph)
+    }
+    
+    /// Построение графа со статистикой приёма связей
+    ///
+    /// Вершины сортируются, а CSR собирается параллельной сортировкой связей;
+    /// ниже `csr_threshold` связей граф переводится в списки смежности, а с
+    /// `memory_mapped` от `mmap_threshold` связей — в CSR-файл.
+    pub fn build_with_stats(self) -> Result<(Graph, IngestStats)> {
+        use rayon::prelude::*;
+        
+        let Self { base, shards, .. } = self;
+        let shards: Vec<EdgeShard> = shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner))
+            .collect();
+        let mut stats = base.stats;
+        for shard in &shards {
+            stats.edges_seen += shard.stats.edges_seen;
+            stats.invalid_ids += shard.stats.invalid_ids;
+            stats.self_loops += shard.stats.self_loops;
+            stats.duplicates += shard.stats.duplicates;
+            stats.self_loops_kept += shard.stats.self_loops_kept;
+            stats.parallel_edges += shard.stats.parallel_edges;
+        }
+        
+        let mut graph = crate::algorithms::scheduler::install(|| -> Result<Graph> {
+            // Уникальные ID вершин: сначала внутри шарда, затем общая сортировка
+            let mut vertices: Vec<&str> = shards
+                .par_iter()
+                .flat_map_iter(|shard| {
+                    let endpoints: HashSet<&str> = shard
+                        .edges
+                        .keys()
+                        .flat_map(|(source, target)| [source.as_str(), target.as_str()])
+                        .collect();
+                    endpoints.into_iter()
+                })
+                .collect();
+            vertices.extend(base.vertices.iter().map(String::as_str));
+            vertices.par_sort_unstable();
+            vertices.dedup();
+            if vertices.len() > u32::MAX as usize {
+                return Err(anyhow::anyhow!("Слишком много вершин: {}", vertices.len()));
+            }
+            let interner = VertexInterner::from_uids(vertices.into_iter().map(str::to_string).collect());
+            
+            // Связи с индексами вершин; веса пары по возрастанию позиций
+            let (mut pairs, loops): (Vec<_>, Vec<_>) = shards
+                .par_iter()
+                .flat_map_iter(|shard| {
+                    shard.edges.iter().map(|((source, target), weights)| {
+                        let mut weights = weights.clone();
+                        weights.sort_unstable_by_key(|&(position, _)| position);
+                        (interner[source.as_str()].0, interner[target.as_str()].0, weights)
+                    })
+                })
+                .partition(|(source, target, _)| source != target);
+            
+            // Исходящие связи по (source, позиция), входящие по (target, позиция)
+            pairs.par_sort_unstable_by_key(|(source, _, weights)| (*source, weights[0].0));
+            let mut incoming: Vec<(u32, usize, u32)> = pairs
+                .par_iter()
+                .map(|(source, target, weights)| (*target, weights[0].0, *source))
+                .collect();
+            incoming.par_sort_unstable();
+            
+            let vertex_count = interner.len();
+            let mut csr = CsrEdges {
+                out_offsets: csr_offsets(vertex_count, pairs.iter().map(|&(source, _, _)| source as usize)),
+                out_targets: pairs.par_iter().map(|&(_, target, _)| target).collect(),
+                out_weights: pairs.par_iter().map(|(_, _, weights)| weights[0].1).collect(),
+                in_offsets: csr_offsets(vertex_count, incoming.iter().map(|&(target, _, _)| target as usize)),
+                in_sources: incoming.par_iter().map(|&(_, _, source)| source).collect(),
+                out_order: Vec::new(),
+            };
+            csr.index_rows();
+            
+            let mut graph = Graph::new();
+            graph.interner = interner;
+            graph.edge_count = pairs.len();
+            graph.edges = EdgeStorage::Csr(csr);
+            for (source, target, weights) in pairs.into_iter().filter(|(_, _, weights)| weights.len() > 1) {
+                let rest = weights[1..].iter().map(|&(_, weight)| weight).collect();
+                graph.parallel_weights.insert((source as usize, target as usize), rest);
+            }
+            for (vertex, _, weights) in loops {
+                graph.self_loops.insert(vertex as usize, weights.iter().map(|&(_, weight)| weight).collect());
+            }
+            Ok(graph)
+        })?;
+        
+        match base.mmap_dir {
+            Some(ref dir) if graph.edge_count >= base.mmap_threshold => graph.to_mmap(dir)?,
+            _ if graph.edge_count < base.csr_threshold => graph.to_adjacency_lists(),
+            _ => {}
+        }
+        for (vertex_id, key, value) in base.attributes {
+            if let Some(vertex) = graph.vertex_handle(&vertex_id) {
+                graph.set_attribute(vertex, key, value);
+            }
+        }
+        stats.edges_added = graph.edge_count;
+        
+        Ok((graph, stats))
+    }
+}
+
+impl Default for ShardedGraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Смещения CSR по отсортированным индексам вершин связей
+fn csr_offsets(vertex_count: usize, sorted: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for idx in sorted {
+        offsets[idx + 1] += 1;
+    }
+    for idx in 0..vertex_count {
+        offsets[idx + 1] += offsets[idx];
+    }
+    offsets
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Преобразования между `Graph` и `petgraph::graph::DiGraph` (feature `petgraph`)
+///
+/// Вершины сохраняют порядок: `NodeIndex` узла равен индексу `VertexId`
+/// (если метки узлов уникальны). Параллельные связи и self-loops сохраняются.
+#[cfg(feature = "petgraph")]
+impl Graph {
+    /// Граф petgraph с UID вершин в узлах и весами в связях
+    pub fn to_petgraph(&self) -> petgraph::graph::DiGraph<String, f32> {
+        use petgraph::graph::NodeIndex;
+
+        let mut graph = petgraph::graph::DiGraph::with_capacity(self.vertex_count(), self.multi_edge_count());
+        for uid in self.interner.uids() {
+            graph.add_node(uid.clone());
+        }
+        for source in 0..self.vertex_count() {
+            for (target, weight) in self.edges.outgoing_weighted(source) {
+                let parallel = self.parallel_weights.get(&(source, target)).into_iter().flatten().copied();
+                for weight in std::iter::once(weight).chain(parallel) {
+                    graph.add_edge(NodeIndex::new(source), NodeIndex::new(target), weight);
+                }
+            }
+            for &weight in self.self_loops.get(&source).into_iter().flatten() {
+                graph.add_edge(NodeIndex::new(source), NodeIndex::new(source), weight);
+            }
+        }
+        graph
+    }
+
+    /// Граф из petgraph: UID вершины — `to_string()` метки узла, вес связи — `weight`
+    ///
+    /// Узлы с одинаковыми метками становятся одной вершиной.
+    pub fn from_petgraph<N: ToString, E>(
+        graph: &petgraph::graph::DiGraph<N, E>,
+        weight: impl Fn(&E) -> f32,
+    ) -> Self {
+        let mut result = Graph::new();
+        let vertices: Vec<usize> = graph
+            .raw_nodes()
+            .iter()
+            .map(|node| result.interner.intern(&node.weight.to_string()).index())
+            .collect();
+        let mut lists = AdjacencyLists::with_vertices(result.vertex_count());
+        for edge in graph.raw_edges() {
+            let (source, target) = (vertices[edge.source().index()], vertices[edge.target().index()]);
+            let weight = weight(&edge.weight);
+            if source == target {
+                result.self_loops.entry(source).or_default().push(weight);
+                continue;
+            }
+            match lists.weights.entry((source, target)) {
+                hashbrown::hash_map::Entry::Occupied(_) => {
+                    result.parallel_weights.entry((source, target)).or_default().push(weight);
+                }
+                hashbrown::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(weight);
+                    lists.out[source].push(target);
+                    lists.incoming[target].push(source);
+                    result.edge_count += 1;
+                }
+            }
+        }
+        result.edges = EdgeStorage::Lists(lists);
+        if result.edge_count >= DEFAULT_CSR_THRESHOLD {
+            result.to_csr();
+        }
+        result
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<N: ToString, E: Clone + Into<f64>> From<&petgraph::graph::DiGraph<N, E>> for Graph {
+    fn from(graph: &petgraph::graph::DiGraph<N, E>) -> Self {
+        Graph::from_petgraph(graph, |weight| weight.clone().into() as f32)
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<N: ToString, E: Clone + Into<f64>> From<petgraph::graph::DiGraph<N, E>> for Graph {
+    fn from(graph: petgraph::graph::DiGraph<N, E>) -> Self {
+        Graph::from(&graph)
+    }
+}
+
+/// Ячейка пространственного индекса: вершины, чьи блоки её пересекают
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpatialCell {
+    /// Номер колонки (floor(x / cell_size))
+    pub col: i32,
+
+    /// Номер строки (floor(y / cell_size))
+    pub row: i32,
+
+    /// ID вершин в порядке возрастания
+    pub vertex_ids: Vec<String>,
+}
+
+/// Пространственный индекс готовой укладки: равномерная сетка ячеек со списками вершин
+///
+/// Не зависит от `Graph`: строится по прямоугольникам (x, y — левый верхний угол),
+/// поэтому пригоден и для позиций, прочитанных из Neo4j. Сериализуется как есть —
+/// клиент может подгружать вершины по видимым ячейкам, не сканируя всю укладку.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpatialIndex {
+    /// Сторона квадратной ячейки
+    pub cell_size: f32,
+
+    /// Непустые ячейки, упорядоченные по (col, row)
+    pub cells: Vec<SpatialCell>,
+}
+
+impl SpatialIndex {
+    /// Построение индекса по прямоугольникам вершин (id, x, y, ширина, высота)
+    pub fn from_rects<I>(cell_size: f32, rects: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, f32, f32, f32, f32)>,
+    {
+        if !(cell_size.is_finite() && cell_size > 0.0) {
+            return Err(anyhow::anyhow!(
+                "Размер ячейки пространственного индекса должен быть больше 0: {}",
+                cell_size
+            ));
+        }
+
+        let mut cells: std::collections::BTreeMap<(i32, i32), Vec<String>> =
+            std::collections::BTreeMap::new();
+        for (vertex_id, x, y, width, height) in rects {
+            let (col_min, row_min) = Self::cell_of(cell_size, x, y);
+            // Правая и нижняя граница блока не входят в него: блок, заканчивающийся
+            // ровно на границе ячейки, соседнюю ячейку не занимает
+            let col_max = (((x + width.max(0.0)) / cell_size).ceil() as i32 - 1).max(col_min);
+            let row_max = (((y + height.max(0.0)) / cell_size).ceil() as i32 - 1).max(row_min);
+            for col in col_min..=col_max {
+                for row in row_min..=row_max {
+                    cells.entry((col, row)).or_default().push(vertex_id.clone());
+                }
+            }
+        }
+
+        let cells = cells
+            .into_iter()
+            .map(|((col, row), mut vertex_ids)| {
+                vertex_ids.sort_unstable();
+                vertex_ids.dedup();
+                SpatialCell { col, row, vertex_ids }
+            })
+            .collect();
+
+        Ok(Self { cell_size, cells })
+    }
+
+    /// Ячейка, в которую попадает точка
+    fn cell_of(cell_size: f32, x: f32, y: f32) -> (i32, i32) {
+        ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32)
+    }
+
+    /// Количество непустых ячеек
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Вершины ячейки (col, row)
+    pub fn cell(&self, col: i32, row: i32) -> &[String] {
+        self.cells
+            .binary_search_by_key(&(col, row), |cell| (cell.col, cell.row))
+            .map(|idx| self.cells[idx].vertex_ids.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Вершины, чьи блоки могут содержать точку (кандидаты из ячейки точки)
+    pub fn query_point(&self, x: f32, y: f32) -> &[String] {
+        if self.cell_size <= 0.0 {
+            return &[];
+        }
+        let (col, row) = Self::cell_of(self.cell_size, x, y);
+        self.cell(col, row)
+    }
+
+    /// Вершины из ячеек, пересекающих прямоугольник; ID без повторов, по возрастанию
+    pub fn query_rect(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<&str> {
+        if self.cell_size <= 0.0 || min_x > max_x || min_y > max_y {
+            return Vec::new();
+        }
+        let (col_min, row_min) = Self::cell_of(self.cell_size, min_x, min_y);
+        let (col_max, row_max) = Self::cell_of(self.cell_size, max_x, max_y);
+
+        // Ячейки упорядочены по колонке: начинаем с первой колонки диапазона
+        let start = self.cells.partition_point(|cell| cell.col < col_min);
+        let mut ids: Vec<&str> = self.cells[start..]
+            .iter()
+            .take_while(|cell| cell.col <= col_max)
+            .filter(|cell| cell.row >= row_min && cell.row <= row_max)
+            .flat_map(|cell| cell.vertex_ids.iter().map(String::as_str))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     
     #[test]
     fn test_graph_creation() -> Result<()> {
@@ -393,6 +3099,688 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_graph_statistics() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        for (source, target) in [("A", "B"), ("A", "C"), ("A", "D"), ("B", "E"), ("E", "F"), ("G", "H")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0)?;
+        }
+        builder.add_vertex("I".to_string());
+        let stats = builder.build()?.get_statistics();
+        
+        assert_eq!(stats.out_degree, DegreeDistribution { p50: 0, p90: 3, p99: 3, max: 3 });
+        assert_eq!(stats.in_degree, DegreeDistribution { p50: 1, p90: 1, p99: 1, max: 1 });
+        assert_eq!((stats.sources, stats.sinks), (3, 5));
+        assert_eq!(stats.largest_component, 6);
+        // C - A - B - E - F
+        assert_eq!(stats.approximate_diameter, 4);
+        assert_eq!(DegreeDistribution::from_degrees(Vec::new()), DegreeDistribution::default());
+        Ok(())
+    }
+    
+    #[test]
+    fn test_graph_mutation() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "C".to_string(), 2.0)?;
+        let mut graph = builder.build()?;
+        assert_eq!(graph.component_count(), 1);
+        
+        assert!(graph.add_edge("C", "D", 3.0)?);
+        assert!(!graph.add_edge("C", "D", 4.0)?);
+        assert!(graph.add_edge("D", "D", 1.0).is_err());
+        assert_eq!(graph.vertex_count(), 4);
+        assert_eq!(graph.get_edge_weight("C", "D"), Some(3.0));
+        
+        assert_eq!(graph.remove_edge("B", "C"), Some(2.0));
+        assert_eq!(graph.remove_edge("B", "C"), None);
+        assert!(!graph.contains_edge("B", "C"));
+        assert_eq!(graph.in_degree("C"), 0);
+        assert_eq!(graph.component_count(), 2);
+        
+        assert!(graph.reverse_edge("A", "B")?);
+        assert!(!graph.reverse_edge("A", "B")?);
+        assert!(graph.contains_edge("B", "A"));
+        assert_eq!(graph.get_edge_weight("B", "A"), Some(1.0));
+        assert_eq!(graph.edge_count(), 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_deterministic_builder_orders_vertices() -> Result<()> {
+        let mut builder = GraphBuilder::new().deterministic(true);
+        builder.add_edge("C".to_string(), "A".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "D".to_string(), 1.0)?;
+        let graph = builder.build()?;
+        
+        let order: Vec<&String> = graph.vertices().collect();
+        assert_eq!(order, vec!["A", "B", "C", "D"]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_ingest_stats() -> Result<()> {
+        let mut builder = GraphBuilder::new();
+        assert!(builder.ingest_edge("A", "B", 1.0));
+        assert!(builder.ingest_edge("A", "B", 2.0));
+        assert!(!builder.ingest_edge("C", "C", 1.0));
+        assert!(!builder.ingest_edge(" ", "B", 1.0));
+        assert!(builder.add_edge("D".to_string(), "D".to_string(), 1.0).is_err());
+        assert!(builder.ingest_edge("B", "C", 1.0));
+        
+        let (graph, stats) = builder.build_with_stats()?;
+        
+        assert_eq!(stats, IngestStats {
+            edges_seen: 6,
+            invalid_ids: 1,
+            self_loops: 2,
+            duplicates: 1,
+            edges_added: 2,
+            self_loops_kept: 0,
+            parallel_edges: 0,
+        });
+        assert_eq!(stats.dropped(), 4);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.get_edge_weight("A", "B"), Some(1.0));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_multigraph_keeps_self_loops_and_parallel_edges() -> Result<()> {
+        let mut builder = GraphBuilder::new().allow_self_loops(true).allow_parallel_edges(true);
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("A".to_string(), "B".to_string(), 2.0)?;
+        builder.add_edge("B".to_string(), "B".to_string(), 3.0)?;
+        assert!(builder.ingest_edge("C", "C", 4.0));
+        assert!(!builder.ingest_edge("", "C", 1.0));
+        
+        let (mut graph, stats) = builder.build_with_stats()?;
+        assert_eq!((stats.self_loops_kept, stats.parallel_edges, stats.duplicates), (2, 1, 0));
+        assert_eq!(stats.dropped(), 1);
+        
+        // Списки смежности остаются простыми: одна связь A -> B, без петель
+        assert_eq!(graph.vertex_count(), 3);
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.multi_edge_count(), 4);
+        assert_eq!(graph.out_degree("B"), 0);
+        assert!(graph.is_dag());
+        
+        assert_eq!(graph.edge_multiplicity("A", "B"), 2);
+        assert_eq!(graph.self_loop_count("B"), 1);
+        assert_eq!(graph.self_loop_count("A"), 0);
+        let mut weights: Vec<f32> = graph
+            .edge_keys()
+            .map(|key| graph.edge_key_weight(key).unwrap())
+            .collect();
+        weights.sort_by(f32::total_cmp);
+        assert_eq!(weights, vec![1.0, 2.0, 3.0, 4.0]);
+        
+        assert_eq!(graph.remove_edge("A", "B"), Some(1.0));
+        assert_eq!(graph.edge_multiplicity("A", "B"), 0);
+        assert_eq!(graph.multi_edge_count(), 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sharded_builder_matches_builder() -> Result<()> {
+        use rayon::prelude::*;
+        
+        let mut edges: Vec<(String, String, f32)> = [("", "A", 1.0), ("B", "B", 2.0), ("B", "B", 3.0)]
+            .into_iter()
+            .map(|(source, target, weight)| (source.to_string(), target.to_string(), weight))
+            .collect();
+        for idx in 0..500u32 {
+            edges.push((format!("v{}", idx % 37), format!("v{}", (idx * 7 + 3) % 41), idx as f32));
+        }
+        for (allow_parallel_edges, csr_threshold) in [(false, usize::MAX), (true, 0)] {
+            let configure = |builder: GraphBuilder| {
+                builder
+                    .deterministic(true)
+                    .allow_self_loops(allow_parallel_edges)
+                    .allow_parallel_edges(allow_parallel_edges)
+                    .csr_threshold(csr_threshold)
+            };
+            let mut builder = configure(GraphBuilder::new());
+            for (source, target, weight) in &edges {
+                builder.ingest_edge(source, target, *weight);
+            }
+            builder.add_vertex("isolated".to_string());
+            let (expected, expected_stats) = builder.build_with_stats()?;
+            
+            let mut sharded = configure(GraphBuilder::new()).sharded();
+            edges.par_iter().enumerate().for_each(|(position, (source, target, weight))| {
+                sharded.ingest_edge_at(position, source, target, *weight);
+            });
+            sharded.add_vertex("isolated".to_string());
+            let (graph, stats) = sharded.build_with_stats()?;
+            
+            assert_eq!(stats, expected_stats);
+            assert_eq!(graph.is_csr(), expected.is_csr());
+            assert_eq!(graph.vertices().collect::<Vec<_>>(), expected.vertices().collect::<Vec<_>>());
+            assert_eq!(graph.multi_edge_count(), expected.multi_edge_count());
+            for vertex in expected.vertices() {
+                let out = |g: &Graph| g.get_outgoing_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+                let incoming = |g: &Graph| g.get_incoming_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+                assert_eq!(out(&graph), out(&expected));
+                assert_eq!(incoming(&graph), incoming(&expected));
+                assert_eq!(graph.self_loop_count(vertex), expected.self_loop_count(vertex));
+                for target in out(&expected) {
+                    assert_eq!(graph.get_edge_weight(vertex, &target), expected.get_edge_weight(vertex, &target));
+                    assert_eq!(graph.edge_multiplicity(vertex, &target), expected.edge_multiplicity(vertex, &target));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    #[test]
+    fn test_csr_matches_adjacency_lists() -> Result<()> {
+        let build = |threshold: usize| -> Result<Graph> {
+            let mut builder = GraphBuilder::new().deterministic(true).csr_threshold(threshold);
+            for (source, target, weight) in [("A", "C", 2.0), ("A", "B", 1.0), ("B", "C", 3.0), ("C", "D", 4.0), ("A", "B", 9.0)] {
+                builder.add_edge(source.to_string(), target.to_string(), weight)?;
+            }
+            builder.add_vertex("E".to_string());
+            builder.build()
+        };
+        let lists = build(usize::MAX)?;
+        let mut csr = build(0)?;
+        assert!(!lists.is_csr());
+        assert!(csr.is_csr());
+        
+        for vertex in lists.vertices() {
+            let out = |g: &Graph| g.get_outgoing_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+            let incoming = |g: &Graph| g.get_incoming_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+            assert_eq!(out(&csr), out(&lists));
+            assert_eq!(incoming(&csr), incoming(&lists));
+            assert_eq!(csr.out_degree(vertex), lists.out_degree(vertex));
+            for target in lists.vertices() {
+                assert_eq!(csr.get_edge_weight(vertex, target), lists.get_edge_weight(vertex, target));
+            }
+        }
+        assert_eq!(csr.get_edge_weight("A", "B"), Some(1.0));
+        assert_eq!(csr.get_edge_weight("B", "A"), None);
+        assert!(csr.contains_edge("C", "D"));
+        assert_eq!(csr.edge_keys().count(), 4);
+        assert_eq!(csr.get_isolated_vertices(), vec!["E"]);
+        assert_eq!(csr.component_count(), 2);
+        assert!(csr.is_dag());
+        assert!(csr.edge_storage_bytes() < lists.edge_storage_bytes());
+        
+        // Изменение возвращает списки смежности
+        assert!(csr.add_edge("D", "E", 5.0)?);
+        assert!(!csr.is_csr());
+        assert_eq!(csr.get_edge_weight("B", "C"), Some(3.0));
+        assert!(csr.to_csr());
+        assert!(csr.to_csr());
+        assert_eq!(csr.get_edge_weight("D", "E"), Some(5.0));
+        assert_eq!(csr.remove_edge("A", "C"), Some(2.0));
+        assert_eq!(csr.edge_count(), 4);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_mmap_matches_adjacency_lists() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("graph-mmap-{}", uuid::Uuid::new_v4()));
+        let build = |mmap: bool| -> Result<Graph> {
+            let mut builder = GraphBuilder::new()
+                .deterministic(true)
+                .csr_threshold(usize::MAX)
+                .memory_mapped(mmap.then(|| dir.clone()))
+                .mmap_threshold(0);
+            for (source, target, weight) in [("A", "B", 1.0), ("A", "C", 2.0), ("B", "C", 3.0), ("C", "D", 4.0)] {
+                builder.add_edge(source.to_string(), target.to_string(), weight)?;
+            }
+            builder.add_vertex("E".to_string());
+            builder.build()
+        };
+        let lists = build(false)?;
+        let mut mapped = build(true)?;
+        assert!(mapped.is_mmap());
+        assert_eq!(mapped.edge_storage_bytes(), 0);
+        assert!(mapped.mapped_bytes() > 0);
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        for vertex in lists.vertices() {
+            let out = |g: &Graph| g.get_outgoing_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+            let incoming = |g: &Graph| g.get_incoming_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+            assert_eq!(out(&mapped), out(&lists));
+            assert_eq!(incoming(&mapped), incoming(&lists));
+        }
+        assert_eq!(mapped.get_edge_weight("C", "D"), Some(4.0));
+        assert_eq!(mapped.get_edge_weight("D", "C"), None);
+        assert_eq!(mapped.edge_keys().count(), 4);
+        assert_eq!(mapped.component_count(), 2);
+
+        // Копия разделяет файл; изменение разворачивает связи в списки смежности
+        let copy = mapped.clone();
+        assert!(mapped.add_edge("D", "E", 5.0)?);
+        assert!(!mapped.is_mmap());
+        assert_eq!(mapped.get_edge_weight("B", "C"), Some(3.0));
+        assert_eq!(copy.get_edge_weight("B", "C"), Some(3.0));
+        drop(copy);
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamed_mmap_matches_builder() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("graph-mmap-{}", uuid::Uuid::new_v4()));
+        let edges = [
+            ("C", "A", 1.0), ("A", "D", 2.0), ("A", "B", 3.0), ("A", "D", 4.0), ("B", "D", 5.0),
+            ("E", "E", 6.0), ("", "A", 7.0), ("D", "B", 8.0), ("A", "B", 9.0), ("C", "D", 10.0),
+        ];
+        for parallel in [false, true] {
+            let builder = || {
+                GraphBuilder::new()
+                    .deterministic(true)
+                    .allow_self_loops(parallel)
+                    .allow_parallel_edges(parallel)
+                    .memory_mapped(Some(dir.clone()))
+                    .mmap_threshold(0)
+            };
+            let mut expected = builder();
+            for (source, target, weight) in edges {
+                expected.ingest_edge(source, target, weight);
+            }
+            let (expected, expected_stats) = expected.build_with_stats()?;
+            let (streamed, stats) = builder().build_mmap_streamed(|| Ok(edges.iter().copied().map(Ok)))?;
+            assert!(streamed.is_mmap());
+            assert_eq!(stats.edges_seen, expected_stats.edges_seen);
+            assert_eq!(stats.dropped(), expected_stats.dropped());
+            assert_eq!(stats.parallel_edges, expected_stats.parallel_edges);
+            assert_eq!(stats.edges_added, expected_stats.edges_added);
+            
+            assert_eq!(streamed.vertices().collect::<Vec<_>>(), expected.vertices().collect::<Vec<_>>());
+            for vertex in expected.vertices() {
+                let out = |g: &Graph| g.get_outgoing_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+                let incoming = |g: &Graph| g.get_incoming_edges(vertex).unwrap().cloned().collect::<Vec<_>>();
+                assert_eq!(out(&streamed), out(&expected));
+                assert_eq!(incoming(&streamed), incoming(&expected));
+                for target in expected.vertices() {
+                    assert_eq!(streamed.get_edge_weight(vertex, target), expected.get_edge_weight(vertex, target));
+                    assert_eq!(streamed.edge_multiplicity(vertex, target), expected.edge_multiplicity(vertex, target));
+                }
+            }
+            assert_eq!(streamed.multi_edge_count(), expected.multi_edge_count());
+            
+            drop((expected, streamed));
+            assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+        }
+        
+        // Второй проход обязан выдать те же связи
+        let mut calls = 0;
+        let result = GraphBuilder::new().memory_mapped(Some(dir.clone())).build_mmap_streamed(|| {
+            calls += 1;
+            Ok(edges[..calls + 2].iter().copied().map(Ok))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+        
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_spill_builds_graph() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("graph-spill-{}", uuid::Uuid::new_v4()));
+        let mut spill = EdgeSpill::create(&dir)?;
+        for (source, target, weight) in [("B", "A", 1.0), ("статья", "B", 2.0), ("B", "A", 3.0)] {
+            spill.push(source, target, weight)?;
+        }
+        assert_eq!(spill.len(), 3);
+        let (graph, stats) = spill.build(GraphBuilder::new().deterministic(true).allow_parallel_edges(true))?;
+        assert!(graph.is_mmap());
+        assert_eq!(graph.interner().uids(), ["A", "B", "статья"]);
+        assert_eq!(graph.multi_edge_weights("B", "A"), vec![1.0, 3.0]);
+        assert_eq!(graph.get_edge_weight("статья", "B"), Some(2.0));
+        assert_eq!((stats.edges_added, stats.parallel_edges), (2, 1));
+        
+        // Файл связей удалён после построения, CSR-файл — вместе с графом
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+        drop(graph);
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+        
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialization_round_trip() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("graph-{}.bin", uuid::Uuid::new_v4()));
+        let mut builder = GraphBuilder::new()
+            .allow_parallel_edges(true)
+            .allow_self_loops(true)
+            .csr_threshold(0);
+        for (source, target, weight) in [("A", "B", 1.0), ("C", "B", 2.0), ("A", "B", 3.0), ("B", "B", 4.0), ("B", "D", 5.0)] {
+            builder.add_edge(source.to_string(), target.to_string(), weight)?;
+        }
+        builder.add_vertex("E".to_string());
+        let graph = builder.build()?;
+        assert!(graph.is_csr());
+        graph.serialize_to(&path)?;
+
+        let loaded = Graph::deserialize_from(&path)?;
+        assert_eq!(loaded.interner().uids(), graph.interner().uids());
+        assert_eq!(loaded.edge_count(), graph.edge_count());
+        assert_eq!(loaded.multi_edge_count(), graph.multi_edge_count());
+        for vertex in graph.handles() {
+            assert!(loaded.successors(vertex).eq(graph.successors(vertex)));
+            assert!(loaded.predecessors(vertex).eq(graph.predecessors(vertex)));
+        }
+        assert_eq!(loaded.multi_edge_weights("A", "B"), vec![1.0, 3.0]);
+        assert_eq!(loaded.multi_edge_weights("B", "B"), vec![4.0]);
+        assert_eq!(loaded.get_edge_weight("B", "D"), Some(5.0));
+
+        // Одинаковые графы дают одинаковые файлы
+        let bytes = std::fs::read(&path)?;
+        loaded.serialize_to(&path)?;
+        assert_eq!(std::fs::read(&path)?, bytes);
+        let source = GraphSource {
+            source_edges: 7,
+            loaded_at_ms: 1_700_000_000_000,
+            sample_rate: 0.25,
+            max_edges: 100,
+        };
+        loaded.serialize_with_source(&path, &source)?;
+        assert_eq!(Graph::deserialize_with_source(&path)?.1, source);
+
+        // Повреждённое содержимое и чужая версия формата не загружаются
+        let mut corrupted = bytes.clone();
+        corrupted[bytes.len() / 2] ^= 0xff;
+        std::fs::write(&path, &corrupted)?;
+        assert!(Graph::deserialize_from(&path).is_err());
+        let mut future = bytes.clone();
+        future[8..12].copy_from_slice(&(GRAPH_FILE_VERSION + 1).to_le_bytes());
+        std::fs::write(&path, &future)?;
+        let error = format!("{:#}", Graph::deserialize_from(&path).unwrap_err());
+        assert!(error.contains("Версия формата"), "{}", error);
+        std::fs::write(&path, &bytes[..bytes.len() - 1])?;
+        assert!(Graph::deserialize_from(&path).is_err());
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_petgraph_conversion() {
+        use petgraph::visit::EdgeRef;
+
+        let mut source = petgraph::graph::DiGraph::<&str, f32>::new();
+        let a = source.add_node("A");
+        let b = source.add_node("B");
+        let c = source.add_node("C");
+        source.add_node("D");
+        source.add_edge(a, b, 1.0);
+        source.add_edge(a, b, 2.0);
+        source.add_edge(b, c, 3.0);
+        source.add_edge(c, c, 4.0);
+
+        let graph = Graph::from(&source);
+        assert_eq!(graph.interner().uids(), ["A", "B", "C", "D"]);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.multi_edge_count(), 4);
+        assert_eq!(graph.multi_edge_weights("A", "B"), vec![1.0, 2.0]);
+        assert_eq!(graph.self_loop_count("C"), 1);
+
+        // Обратное преобразование: те же узлы и связи, алгоритмы petgraph работают на результате
+        let converted = graph.to_petgraph();
+        assert_eq!(converted.node_count(), 4);
+        assert_eq!(converted.edge_count(), 4);
+        assert_eq!(converted[b], "B");
+        let mut weights: Vec<f32> = converted.edges_connecting(a, b).map(|edge| *edge.weight()).collect();
+        weights.sort_by(f32::total_cmp);
+        assert_eq!(weights, vec![1.0, 2.0]);
+        assert!(petgraph::algo::is_cyclic_directed(&converted));
+        assert_eq!(petgraph::algo::connected_components(&converted), 2);
+    }
+
+    #[test]
+    fn test_vertex_attributes() -> Result<()> {
+        let mut builder = GraphBuilder::new().deterministic(true);
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("B".to_string(), "C".to_string(), 1.0)?;
+        builder.set_attribute("A", ATTR_YEAR, AttrValue::Int(1999));
+        builder.set_attribute("C", ATTR_YEAR, AttrValue::Int(2005));
+        builder.set_attribute("C", ATTR_YEAR, AttrValue::Int(2010));
+        builder.set_attribute("C", ATTR_CLUSTER, AttrValue::Text("physics".to_string()));
+        builder.set_attribute("Z", ATTR_TITLE, AttrValue::Text("нет в графе".to_string()));
+        let mut graph = builder.build()?;
+
+        let handle = |graph: &Graph, uid: &str| graph.vertex_handle(uid).unwrap();
+        assert_eq!(graph.vertex_year(handle(&graph, "A")), Some(1999));
+        // Повторная установка заменяет значение
+        assert_eq!(graph.vertex_year(handle(&graph, "C")), Some(2010));
+        assert_eq!(graph.vertex_cluster(handle(&graph, "C")), Some("physics"));
+        assert_eq!(graph.vertex_year(handle(&graph, "B")), None);
+        assert_eq!(graph.attribute_values(ATTR_YEAR).count(), 2);
+        assert_eq!(graph.attribute_values(ATTR_TITLE).count(), 0);
+        assert_eq!(graph.vertex_attributes(handle(&graph, "C")).count(), 2);
+
+        let b = handle(&graph, "B");
+        graph.set_attribute(b, ATTR_DEGREE_CENTRALITY, AttrValue::Float(1.0));
+        assert_eq!(graph.attribute(b, ATTR_DEGREE_CENTRALITY).and_then(AttrValue::as_float), Some(1.0));
+
+        // Подграф и удаление вершины сохраняют атрибуты остальных вершин
+        let subgraph = graph.induced_subgraph(&[handle(&graph, "C"), b]);
+        assert_eq!(subgraph.graph.vertex_cluster(handle(&subgraph.graph, "C")), Some("physics"));
+        graph.remove_vertex("A");
+        assert_eq!(graph.vertex_year(handle(&graph, "C")), Some(2010));
+        assert_eq!(graph.vertex_cluster(handle(&graph, "C")), Some("physics"));
+        assert_eq!(graph.attribute_values(ATTR_YEAR).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_subgraph_extraction() -> Result<()> {
+        // A -> B -> C -> D -> E, F -> C, G отдельно
+        let mut builder = GraphBuilder::new().deterministic(true).allow_parallel_edges(true).csr_threshold(0);
+        for (source, target) in [("A", "B"), ("B", "C"), ("C", "D"), ("D", "E"), ("F", "C"), ("B", "C")] {
+            builder.add_edge(source.to_string(), target.to_string(), 2.0)?;
+        }
+        builder.add_vertex("G".to_string());
+        let graph = builder.build()?;
+        let handle = |id: &str| graph.vertex_handle(id).unwrap();
+
+        let induced = graph.induced_subgraph(&[handle("C"), handle("B"), handle("E"), handle("C")]);
+        assert_eq!(induced.graph.interner().uids(), ["C", "B", "E"]);
+        assert_eq!(induced.graph.edge_count(), 1);
+        assert_eq!(induced.graph.edge_multiplicity("B", "C"), 2);
+        assert_eq!(induced.graph.get_edge_weight("B", "C"), Some(2.0));
+        let e = induced.graph.vertex_handle("E").unwrap();
+        assert_eq!(induced.original(e), handle("E"));
+
+        // Окрестность радиуса 1 вокруг C идёт против направления связей тоже
+        let around_c = graph.neighborhood(&[handle("C")], 1);
+        let mut ids: Vec<&String> = around_c.graph.vertices().collect();
+        assert_eq!(ids[0], "C");
+        ids.sort();
+        assert_eq!(ids, vec!["B", "C", "D", "F"]);
+        assert_eq!(around_c.graph.edge_count(), 3);
+
+        let around_a = graph.neighborhood(&[handle("A"), handle("G")], 2);
+        assert_eq!(around_a.graph.vertex_count(), 4);
+        assert!(around_a.graph.contains_edge("B", "C"));
+        assert!(!around_a.graph.contains_vertex("D"));
+        assert_eq!(graph.neighborhood(&[handle("A")], 10).graph.vertex_count(), 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vertex_handles() -> Result<()> {
+        let mut builder = GraphBuilder::new().deterministic(true);
+        builder.add_edge("A".to_string(), "B".to_string(), 1.0)?;
+        builder.add_edge("A".to_string(), "C".to_string(), 1.0)?;
+        let mut graph = builder.build()?;
+
+        let a = graph.vertex_handle("A").unwrap();
+        let c = graph.vertex_handle("C").unwrap();
+        assert_eq!(graph.vertex_uid(a), "A");
+        assert_eq!(graph.vertex_handle("Z"), None);
+        let successors: Vec<&str> = graph.successors(a).map(|v| graph.vertex_uid(v)).collect();
+        assert_eq!(successors, vec!["B", "C"]);
+        assert_eq!(graph.predecessors(c).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(graph.handles().len(), 3);
+
+        // Новые вершины получают следующие handles, старые не меняются
+        graph.add_edge("C", "D", 1.0)?;
+        let d = graph.vertex_handle("D").unwrap();
+        assert_eq!(d.index(), 3);
+        assert_eq!(graph.vertex_handle("A"), Some(a));
+        assert_eq!(graph.interner().uids(), ["A", "B", "C", "D"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_vertex() -> Result<()> {
+        let mut builder = GraphBuilder::new().deterministic(true).allow_self_loops(true).allow_parallel_edges(true);
+        for (source, target) in [("A", "B"), ("B", "C"), ("B", "C"), ("C", "D"), ("B", "B"), ("D", "A")] {
+            builder.add_edge(source.to_string(), target.to_string(), 1.0)?;
+        }
+        let mut graph = builder.csr_threshold(0).build()?;
+        let d = graph.vertex_handle("D").unwrap();
+        assert_eq!(graph.component_count(), 1);
+
+        assert_eq!(graph.remove_vertex("B"), Some(4));
+        assert_eq!(graph.remove_vertex("B"), None);
+        assert!(!graph.is_csr());
+        assert_eq!(graph.vertex_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.multi_edge_count(), 2);
+
+        // D переехала на индекс B, её связи сохранились
+        let moved = graph.vertex_handle("D").unwrap();
+        assert_ne!(moved, d);
+        assert_eq!(graph.vertex_uid(moved), "D");
+        assert!(graph.contains_edge("C", "D"));
+        assert!(graph.contains_edge("D", "A"));
+        assert_eq!(graph.in_degree("C"), 0);
+        assert_eq!(graph.component_count(), 1);
+
+        assert_eq!(graph.remove_vertex("A"), Some(1));
+        assert_eq!(graph.component_count(), 1);
+        assert_eq!(graph.get_edge_weight("C", "D"), Some(1.0));
+
+        Ok(())
+    }
+
+    /// Мутация графа для проверки инвариантов на случайных последовательностях
+    #[derive(Debug, Clone)]
+    enum Mutation {
+        Add(u8, u8),
+        Remove(u8, u8),
+        Reverse(u8, u8),
+        RemoveVertex(u8),
+    }
+
+    fn mutation() -> impl proptest::strategy::Strategy<Value = Mutation> {
+        use proptest::prelude::*;
+        prop_oneof![
+            (0..8u8, 0..8u8).prop_map(|(a, b)| Mutation::Add(a, b)),
+            (0..8u8, 0..8u8).prop_map(|(a, b)| Mutation::Remove(a, b)),
+            (0..8u8, 0..8u8).prop_map(|(a, b)| Mutation::Reverse(a, b)),
+            (0..8u8).prop_map(Mutation::RemoveVertex),
+        ]
+    }
+
+    /// Списки смежности, веса, счётчики, интернер и кеш компонент согласованы
+    /// между собой и с моделью связей
+    fn assert_consistent(graph: &mut Graph, model: &std::collections::BTreeMap<(String, String), f32>) {
+        let mut out_total = 0;
+        let mut in_total = 0;
+        for vertex in graph.handles() {
+            assert_eq!(graph.vertex_handle(graph.vertex_uid(vertex)), Some(vertex));
+            for target in graph.successors(vertex) {
+                assert!(graph.predecessors(target).any(|source| source == vertex));
+                out_total += 1;
+            }
+            in_total += graph.predecessors(vertex).len();
+        }
+        assert_eq!(out_total, graph.edge_count());
+        assert_eq!(in_total, graph.edge_count());
+        if let EdgeStorage::Lists(lists) = &graph.edges {
+            assert_eq!(lists.weights.len(), graph.edge_count());
+        }
+
+        let edges: std::collections::BTreeMap<(String, String), f32> = graph
+            .handles()
+            .flat_map(|source| graph.successors(source).map(move |target| (source, target)))
+            .map(|(source, target)| {
+                let (source, target) = (graph.vertex_uid(source), graph.vertex_uid(target));
+                ((source.to_string(), target.to_string()), graph.get_edge_weight(source, target).unwrap())
+            })
+            .collect();
+        assert_eq!(&edges, model);
+
+        let fresh = graph.compute_connected_components().len();
+        assert_eq!(graph.component_count(), fresh);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_mutations_keep_graph_consistent(
+            initial in proptest::collection::vec((0..8u8, 0..8u8), 0..20),
+            mutations in proptest::collection::vec(mutation(), 0..40),
+            csr in proptest::bool::ANY,
+        ) {
+            use std::collections::BTreeMap;
+            let name = |v: u8| format!("v{}", v);
+
+            let mut builder = GraphBuilder::new().csr_threshold(if csr { 0 } else { usize::MAX });
+            let mut model = BTreeMap::new();
+            for (weight, &(a, b)) in initial.iter().enumerate() {
+                if builder.ingest_edge(&name(a), &name(b), weight as f32) {
+                    model.entry((name(a), name(b))).or_insert(weight as f32);
+                }
+            }
+            let mut graph = builder.build().unwrap();
+            assert_consistent(&mut graph, &model);
+
+            for (step, mutation) in mutations.into_iter().enumerate() {
+                match mutation {
+                    Mutation::Add(a, b) => {
+                        let added = graph.add_edge(&name(a), &name(b), step as f32);
+                        if a == b {
+                            proptest::prop_assert!(added.is_err());
+                        } else if !model.contains_key(&(name(a), name(b))) {
+                            proptest::prop_assert!(added.unwrap());
+                            model.insert((name(a), name(b)), step as f32);
+                        }
+                    }
+                    Mutation::Remove(a, b) => {
+                        proptest::prop_assert_eq!(graph.remove_edge(&name(a), &name(b)), model.remove(&(name(a), name(b))));
+                    }
+                    Mutation::Reverse(a, b) => {
+                        let reversed = graph.reverse_edge(&name(a), &name(b)).unwrap();
+                        proptest::prop_assert_eq!(reversed, model.contains_key(&(name(a), name(b))));
+                        if let Some(weight) = model.remove(&(name(a), name(b))) {
+                            model.entry((name(b), name(a))).or_insert(weight);
+                        }
+                    }
+                    Mutation::RemoveVertex(v) => {
+                        let existed = graph.contains_vertex(&name(v));
+                        let before = model.len();
+                        model.retain(|(source, target), _| *source != name(v) && *target != name(v));
+                        let removed = graph.remove_vertex(&name(v));
+                        proptest::prop_assert_eq!(removed, existed.then_some(before - model.len()));
+                        proptest::prop_assert!(!graph.contains_vertex(&name(v)));
+                    }
+                }
+                assert_consistent(&mut graph, &model);
+            }
+        }
+    }
+
     #[test]
     fn test_dag_detection() -> Result<()> {
         // DAG
@@ -434,4 +3822,36 @@ mod tests {
         
         Ok(())
     }
+    
+    #[test]
+    fn test_spatial_index_queries() -> Result<()> {
+        // Блоки 80×40: A и B в ячейке (0, 0), C пересекает границу колонок 0 и 1
+        let index = SpatialIndex::from_rects(
+            100.0,
+            vec![
+                ("A".to_string(), 0.0, 0.0, 80.0, 40.0),
+                ("B".to_string(), 10.0, 50.0, 80.0, 40.0),
+                ("C".to_string(), 60.0, 200.0, 80.0, 40.0),
+                ("D".to_string(), 300.0, 0.0, 100.0, 100.0),
+            ],
+        )?;
+        
+        assert_eq!(index.cell_count(), 4);
+        assert_eq!(index.query_point(5.0, 5.0), ["A".to_string(), "B".to_string()]);
+        assert_eq!(index.cell(1, 2), ["C".to_string()]);
+        assert_eq!(index.cell(0, 2), ["C".to_string()]);
+        // D заканчивается ровно на границе: соседние ячейки не заняты
+        assert!(index.cell(4, 0).is_empty());
+        assert!(index.cell(3, 1).is_empty());
+        assert_eq!(index.query_rect(150.0, 0.0, 350.0, 250.0), vec!["C", "D"]);
+        assert!(index.query_rect(500.0, 500.0, 600.0, 600.0).is_empty());
+        
+        // Индекс переживает сериализацию
+        let json = serde_json::to_string(&index)?;
+        assert_eq!(serde_json::from_str::<SpatialIndex>(&json)?, index);
+        
+        assert!(SpatialIndex::from_rects(0.0, Vec::new()).is_err());
+        
+        Ok(())
+    }
 }
@@ -5,14 +5,16 @@
 а также применения оптимизаций базы данных перед запуском укладки графа.
 */
 
+use crate::retry::{with_retries, RetryPolicy};
 use anyhow::{Context, Result};
-use neo4rs::{Graph, query};
+use neo4rs::{Graph, Row, query};
 use std::sync::Arc;
 use tracing::{info, warn};
 
 /// Структура для управления оптимизацией базы данных
 pub struct DatabaseOptimizer {
     graph: Arc<Graph>,
+    retry: RetryPolicy,
 }
 
 /// Описание индекса для создания
@@ -26,7 +28,35 @@ struct IndexDefinition {
 impl DatabaseOptimizer {
     /// Создать новый оптимизатор БД
     pub fn new(graph: Arc<Graph>) -> Self {
-        Self { graph }
+        Self { graph, retry: RetryPolicy::default() }
+    }
+
+    /// Политика повторов запросов (по умолчанию `RetryPolicy::default()`)
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Выполнение запроса без результата с повторами
+    async fn run(&self, operation: &'static str, cypher: &str) -> Result<()> {
+        with_retries(&self.retry, operation, |_| async move {
+            self.graph.run(query(cypher)).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Выполнение запроса с повторами; строки читаются целиком внутри попытки
+    async fn fetch(&self, operation: &'static str, cypher: &str) -> Result<Vec<Row>> {
+        with_retries(&self.retry, operation, |_| async move {
+            let mut result = self.graph.execute(query(cypher)).await?;
+            let mut rows = Vec::new();
+            while let Some(row) = result.next().await? {
+                rows.push(row);
+            }
+            Ok(rows)
+        })
+        .await
     }
 
     /// Проверить и создать все необходимые индексы
@@ -80,11 +110,10 @@ impl DatabaseOptimizer {
 
     /// Получить список существующих индексов
     async fn get_existing_indexes(&self) -> Result<Vec<String>> {
-        let query = query("SHOW INDEXES");
-        let mut result = self.graph.execute(query).await?;
+        let rows = self.fetch("show_indexes", "SHOW INDEXES").await?;
 
         let mut indexes = Vec::new();
-        while let Some(row) = result.next().await? {
+        for row in rows {
             if let Ok(name) = row.get::<String>("name") {
                 indexes.push(name);
             }
@@ -170,8 +199,7 @@ impl DatabaseOptimizer {
 
         info!("📝 Выполняем: {}", cypher);
 
-        let query = query(&cypher);
-        self.graph.run(query).await.context(format!(
+        self.run("create_index", &cypher).await.context(format!(
             "Не удалось создать индекс '{}'",
             index_def.name
         ))?;
@@ -182,10 +210,9 @@ impl DatabaseOptimizer {
 
     /// Проверить существование constraint
     async fn check_constraint_exists(&self, label: &str, property: &str) -> Result<bool> {
-        let query = query("SHOW CONSTRAINTS");
-        let mut result = self.graph.execute(query).await?;
+        let rows = self.fetch("show_constraints", "SHOW CONSTRAINTS").await?;
 
-        while let Some(row) = result.next().await? {
+        for row in rows {
             if let (Ok(labels), Ok(properties)) = (
                 row.get::<Vec<String>>("labelsOrTypes"),
                 row.get::<Vec<String>>("properties"),
@@ -206,11 +233,12 @@ impl DatabaseOptimizer {
         // UID должен быть уникальным
         if !self.check_constraint_exists("Article", "uid").await? {
             info!("🔧 Создание UNIQUE constraint на Article.uid...");
-            let query = query(
+            self.run(
+                "create_constraint",
                 "CREATE CONSTRAINT article_uid_unique IF NOT EXISTS \
-                 FOR (n:Article) REQUIRE n.uid IS UNIQUE"
-            );
-            self.graph.run(query).await?;
+                 FOR (n:Article) REQUIRE n.uid IS UNIQUE",
+            )
+            .await?;
             info!("✅ Constraint на Article.uid создан");
         } else {
             info!("✅ Constraint на Article.uid уже существует");
@@ -224,28 +252,30 @@ impl DatabaseOptimizer {
         info!("📊 Сбор статистики базы данных...");
 
         // Количество статей
-        let articles_query = query("MATCH (n:Article) RETURN count(n) as count");
-        let mut articles_result = self.graph.execute(articles_query).await?;
-        if let Some(row) = articles_result.next().await? {
+        let articles = self.fetch("database_stats", "MATCH (n:Article) RETURN count(n) as count").await?;
+        if let Some(row) = articles.first() {
             let count: i64 = row.get("count")?;
             info!("📄 Статей в базе: {}", count);
         }
 
         // Количество связей
-        let links_query = query("MATCH ()-[r:BIBLIOGRAPHIC_LINK]->() RETURN count(r) as count");
-        let mut links_result = self.graph.execute(links_query).await?;
-        if let Some(row) = links_result.next().await? {
+        let links = self
+            .fetch("database_stats", "MATCH ()-[r:BIBLIOGRAPHIC_LINK]->() RETURN count(r) as count")
+            .await?;
+        if let Some(row) = links.first() {
             let count: i64 = row.get("count")?;
             info!("🔗 Библиографических связей: {}", count);
         }
 
         // Статьи с координатами
-        let positioned_query = query(
-            "MATCH (n:Article) WHERE n.layer IS NOT NULL AND n.level IS NOT NULL \
-             AND n.x IS NOT NULL AND n.y IS NOT NULL RETURN count(n) as count"
-        );
-        let mut positioned_result = self.graph.execute(positioned_query).await?;
-        if let Some(row) = positioned_result.next().await? {
+        let positioned = self
+            .fetch(
+                "database_stats",
+                "MATCH (n:Article) WHERE n.layer IS NOT NULL AND n.level IS NOT NULL \
+                 AND n.x IS NOT NULL AND n.y IS NOT NULL RETURN count(n) as count",
+            )
+            .await?;
+        if let Some(row) = positioned.first() {
             let count: i64 = row.get("count")?;
             info!("📍 Статей с позициями: {}", count);
         }
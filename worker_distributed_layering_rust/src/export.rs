@@ -0,0 +1,411 @@
+/*!
+# Экспорт укладки с подсказками оформления
+
+Экспортированная картинка из тысяч одинаковых блоков плохо читается без
+собственного рендерера. Подсказки оформления (`StyleHints`) описывают каждый
+слой как вертикальную полосу:
+
+- цвет слоя из палитры (блоки слоя окрашиваются в него);
+- фон каждого второго слоя, чтобы соседние слои различались;
+- подпись: диапазон лет публикации статей слоя («1998–2004») или номер слоя.
+
+Подсказки попадают в JSON-экспорт (поле `style` прогона) и используются
+при выводе в SVG и GML. CSV и JSONL содержат только позиции — по строке на
+вершину, для просмотра результата перед записью в базу.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::config::StyleConfig;
+use crate::neo4j::{LayoutMeta, VertexPosition};
+use crate::sinks::LayoutRun;
+
+/// Цвет блоков, если подсказки не заданы
+const DEFAULT_FILL: &str = "#4e79a7";
+
+/// Высота строки подписей над полосами в SVG
+const CAPTION_HEIGHT: f32 = 32.0;
+
+/// Формат файла экспорта
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Svg,
+    Gml,
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// Формат по расширению файла (`json`, `svg`, `gml`, `csv`, `jsonl`)
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let extension = std::path::Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "svg" => Some(Self::Svg),
+            "gml" => Some(Self::Gml),
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// Оформление одного слоя
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerBand {
+    pub layer: i32,
+
+    /// Левая граница полосы (с половиной горизонтального зазора)
+    pub x: f32,
+    pub width: f32,
+
+    /// Цвет блоков слоя
+    pub color: String,
+
+    /// Фон полосы (только у чередующихся слоёв)
+    pub background: Option<String>,
+
+    /// Подпись слоя
+    pub caption: String,
+}
+
+/// Подсказки оформления всех слоёв
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StyleHints {
+    /// Полосы слоёв по возрастанию номера слоя
+    pub bands: Vec<LayerBand>,
+
+    /// Высота области блоков (общая для всех полос)
+    pub height: f32,
+}
+
+impl StyleHints {
+    /// Цвет блоков слоя
+    pub fn color_of(&self, layer: i32) -> &str {
+        self.bands
+            .binary_search_by_key(&layer, |band| band.layer)
+            .map(|i| self.bands[i].color.as_str())
+            .unwrap_or(DEFAULT_FILL)
+    }
+}
+
+/// Построение подсказок оформления
+///
+/// `years` — год публикации статей; слои без известных лет подписываются номером.
+pub fn style_hints(
+    positions: &[VertexPosition],
+    meta: &LayoutMeta,
+    years: &HashMap<String, i32>,
+    config: &StyleConfig,
+) -> StyleHints {
+    // Диапазон лет каждого слоя (None — лет нет)
+    let mut layers: BTreeMap<i32, Option<(i32, i32)>> = BTreeMap::new();
+    let mut height = 0.0f32;
+    for position in positions {
+        let range = layers.entry(position.layer).or_insert(None);
+        if let Some(&year) = years.get(&position.article_id) {
+            *range = Some(range.map_or((year, year), |(min, max)| (min.min(year), max.max(year))));
+        }
+        height = height.max(position.y - meta.origin_y + meta.block_height);
+    }
+
+    let column = meta.block_width + meta.horizontal_gap;
+    let bands = layers
+        .into_iter()
+        .map(|(layer, years)| {
+            let color = if config.palette.is_empty() {
+                DEFAULT_FILL.to_string()
+            } else {
+                config.palette[layer.rem_euclid(config.palette.len() as i32) as usize].clone()
+            };
+            let background = (layer % 2 == 1 && !config.band_background.is_empty())
+                .then(|| config.band_background.clone());
+            let caption = match years {
+                Some((min, max)) if min == max => min.to_string(),
+                Some((min, max)) => format!("{}–{}", min, max),
+                None => format!("Слой {}", layer),
+            };
+
+            LayerBand {
+                layer,
+                x: meta.origin_x + layer as f32 * column - meta.horizontal_gap / 2.0,
+                width: column,
+                color,
+                background,
+                caption,
+            }
+        })
+        .collect();
+
+    StyleHints { bands, height }
+}
+
+/// Вывод прогона в SVG
+///
+/// Полосы слоёв и подписи рисуются только при наличии подсказок оформления.
+pub fn render_svg(run: &LayoutRun<'_>) -> String {
+    let meta = run.meta;
+    let max_x = run
+        .positions
+        .iter()
+        .map(|p| p.x + meta.block_width)
+        .fold(meta.origin_x, f32::max);
+    let max_y = run
+        .positions
+        .iter()
+        .map(|p| p.y + meta.block_height)
+        .fold(meta.origin_y, f32::max);
+    let top = if run.style.is_some() { CAPTION_HEIGHT } else { 0.0 };
+    let left = meta.origin_x - meta.horizontal_gap / 2.0;
+    let width = max_x - left + meta.horizontal_gap / 2.0;
+    let height = max_y - meta.origin_y + top;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="{x:.1} {y:.1} {w:.1} {h:.1}">"#,
+        x = left,
+        y = meta.origin_y - top,
+        w = width,
+        h = height,
+    );
+
+    if let Some(style) = run.style {
+        svg.push_str("  <g class=\"layers\">\n");
+        for band in &style.bands {
+            if let Some(ref background) = band.background {
+                let _ = writeln!(
+                    svg,
+                    r#"    <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+                    band.x,
+                    meta.origin_y - top,
+                    band.width,
+                    style.height + top,
+                    xml_escape(background),
+                );
+            }
+            let _ = writeln!(
+                svg,
+                r#"    <text x="{:.1}" y="{:.1}" text-anchor="middle" font-family="sans-serif" font-size="14" fill="{}">{}</text>"#,
+                band.x + band.width / 2.0,
+                meta.origin_y - top / 2.0 + 5.0,
+                xml_escape(&band.color),
+                xml_escape(&band.caption),
+            );
+        }
+        svg.push_str("  </g>\n");
+    }
+
+    svg.push_str("  <g class=\"vertices\">\n");
+    for position in run.positions {
+        let fill = run.style.map_or(DEFAULT_FILL, |style| style.color_of(position.layer));
+        let _ = writeln!(
+            svg,
+            r#"    <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="4" fill="{}"><title>{}</title></rect>"#,
+            position.x,
+            position.y,
+            meta.block_width,
+            meta.block_height,
+            xml_escape(fill),
+            xml_escape(&position.article_id),
+        );
+    }
+    svg.push_str("  </g>\n</svg>\n");
+
+    svg
+}
+
+/// Вывод прогона в GML
+///
+/// Каждый блок — узел с `graphics` (центр, размер, цвет слоя); подписи слоёв
+/// записываются отдельными записями `layerStyle`.
+pub fn render_gml(run: &LayoutRun<'_>) -> String {
+    let meta = run.meta;
+    let mut gml = String::new();
+    gml.push_str("graph [\n  directed 1\n");
+    let _ = writeln!(gml, "  label {}", gml_string(run.run_id));
+
+    if let Some(style) = run.style {
+        for band in &style.bands {
+            let _ = writeln!(
+                gml,
+                "  layerStyle [ layer {} x {:.1} width {:.1} color {} background {} caption {} ]",
+                band.layer,
+                band.x,
+                band.width,
+                gml_string(&band.color),
+                gml_string(band.background.as_deref().unwrap_or("")),
+                gml_string(&band.caption),
+            );
+        }
+    }
+
+    for (id, position) in run.positions.iter().enumerate() {
+        let fill = run.style.map_or(DEFAULT_FILL, |style| style.color_of(position.layer));
+        let _ = writeln!(
+            gml,
+            "  node [ id {} label {} layer {} level {} graphics [ x {:.1} y {:.1} w {:.1} h {:.1} type \"rectangle\" fill {} ] ]",
+            id,
+            gml_string(&position.article_id),
+            position.layer,
+            position.level,
+            position.x + meta.block_width / 2.0,
+            position.y + meta.block_height / 2.0,
+            meta.block_width,
+            meta.block_height,
+            gml_string(fill),
+        );
+    }
+
+    gml.push_str("]\n");
+    gml
+}
+
+/// Вывод позиций в CSV: `article_id,layer,level,x,y`
+pub fn render_csv(run: &LayoutRun<'_>) -> String {
+    let mut csv = String::from("article_id,layer,level,x,y\n");
+    for position in run.positions {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{}",
+            csv_field(&position.article_id),
+            position.layer,
+            position.level,
+            position.x,
+            position.y,
+        );
+    }
+    csv
+}
+
+/// Вывод позиций в JSONL: по одному объекту `VertexPosition` на строку
+pub fn render_jsonl(run: &LayoutRun<'_>) -> serde_json::Result<String> {
+    let mut jsonl = String::new();
+    for position in run.positions {
+        jsonl.push_str(&serde_json::to_string(position)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Поле CSV: значения с разделителями и кавычками берутся в кавычки
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Строка GML: кавычки внутри значения недопустимы и заменяются сущностью
+fn gml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('&', "&amp;").replace('"', "&quot;"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> LayoutMeta {
+        LayoutMeta {
+            run_id: "run-1".to_string(),
+            algorithm_version: "test".to_string(),
+            block_width: 160.0,
+            block_height: 80.0,
+            horizontal_gap: 80.0,
+            vertical_gap: 50.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            level_packing: "OnePerLevel".to_string(),
+            vertex_count: 3,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            scale_profile: "web".to_string(),
+        }
+    }
+
+    fn positions() -> Vec<VertexPosition> {
+        [("a", 0, 0), ("b", 1, 0), ("c", 1, 1)]
+            .iter()
+            .map(|&(id, layer, level)| VertexPosition {
+                article_id: id.to_string(),
+                layer,
+                level,
+                x: layer as f32 * 240.0,
+                y: level as f32 * 130.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_style_hints_bands_and_captions() {
+        let meta = meta();
+        let positions = positions();
+        let years = HashMap::from([("b".to_string(), 2004), ("c".to_string(), 1998)]);
+
+        let hints = style_hints(&positions, &meta, &years, &StyleConfig::default());
+
+        assert_eq!(hints.bands.len(), 2);
+        assert_eq!(hints.bands[0].caption, "Слой 0");
+        assert_eq!(hints.bands[0].background, None);
+        assert_eq!(hints.bands[1].caption, "1998–2004");
+        assert_eq!(hints.bands[1].background.as_deref(), Some("#f4f4f4"));
+        assert_eq!(hints.bands[1].x, 200.0);
+        assert_eq!(hints.height, 210.0);
+        assert_ne!(hints.color_of(0), hints.color_of(1));
+    }
+
+    #[test]
+    fn test_render_svg_and_gml() {
+        let meta = meta();
+        let positions = positions();
+        let hints = style_hints(&positions, &meta, &HashMap::new(), &StyleConfig::default());
+        let run = LayoutRun { run_id: "run-1", meta: &meta, positions: &positions, style: Some(&hints), aggregates: None, edge_paths: &[] };
+
+        let svg = render_svg(&run);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<title>").count(), 3);
+        assert!(svg.contains(">Слой 1</text>"));
+        assert!(svg.contains(r##"fill="#f4f4f4""##));
+
+        let gml = render_gml(&run);
+        assert_eq!(gml.matches("node [").count(), 3);
+        assert!(gml.contains("caption \"Слой 0\""));
+        assert!(gml.trim_end().ends_with(']'));
+    }
+
+    #[test]
+    fn test_render_csv_and_jsonl() {
+        let meta = meta();
+        let mut positions = positions();
+        positions[2].article_id = "c,\"2\"".to_string();
+        let run = LayoutRun { run_id: "run-1", meta: &meta, positions: &positions, style: None, aggregates: None, edge_paths: &[] };
+
+        let csv = render_csv(&run);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "article_id,layer,level,x,y");
+        assert_eq!(lines[2], "b,1,0,240,0");
+        assert_eq!(lines[3], "\"c,\"\"2\"\"\",1,1,240,130");
+
+        let jsonl = render_jsonl(&run).unwrap();
+        assert_eq!(jsonl.lines().count(), 3);
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["article_id"], "a");
+
+        assert_eq!(ExportFormat::from_extension("out/run.CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::from_extension("out/{run_id}.jsonl"), Some(ExportFormat::Jsonl));
+        assert_eq!(ExportFormat::from_extension("out/run"), None);
+    }
+}
@@ -0,0 +1,245 @@
+/*!
+# Внедрение сбоев для тестов устойчивости
+
+С фичей `failure-injection` в заданных точках кода можно имитировать сбои:
+
+- **`Neo4jOperation`** — любая операция Neo4j через `neo4j::with_timeout`
+  (таймаут, ошибка или медленный ответ)
+- **`SaveBatch`** — сохранение одного батча позиций (Neo4j и `InMemoryGraphStore`)
+- **`Allocation`** — выделение памяти под граф укладки
+
+Так повторные попытки и частичное сохранение с карантином проверяются в CI,
+а не впервые в продакшене. Сбои задаются планом на время теста:
+
+```text
+let plan = FaultPlan::install();
+plan.arm(FaultPoint::SaveBatch, Fault::Timeout, Some(2));
+// ... два первых батча получат таймаут, остальные пройдут
+```
+
+План действует только в потоке, который его установил, и снимается при
+удалении: параллельные тесты друг другу не мешают. Тесты с задачами tokio
+должны идти в однопоточном runtime (`#[tokio::test]` по умолчанию).
+
+```text
+cargo test --features failure-injection
+```
+
+Без фичи функции модуля ничего не меняют и не стоят ничего.
+*/
+
+use std::time::Duration;
+
+/// Включено ли внедрение сбоев
+pub const ENABLED: bool = cfg!(feature = "failure-injection");
+
+/// Точка внедрения сбоя
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultPoint {
+    /// Операция Neo4j с таймаутом (`neo4j::with_timeout`)
+    Neo4jOperation,
+    /// Сохранение батча позиций
+    SaveBatch,
+    /// Выделение памяти под граф
+    Allocation,
+}
+
+/// Внедряемый сбой
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Операция завершается таймаутом
+    Timeout,
+    /// Операция выполняется с задержкой (медленный батч)
+    Delay(Duration),
+    /// Операция завершается ошибкой (в точке `Allocation` — нехватка памяти)
+    Error,
+}
+
+/// Сбой для операции в точке, если план его предусматривает
+///
+/// Каждое срабатывание уменьшает счётчик правила; исчерпанные правила
+/// больше не срабатывают.
+pub fn take(point: FaultPoint, operation: &str) -> Option<Fault> {
+    #[cfg(feature = "failure-injection")]
+    {
+        plan::take(point, operation)
+    }
+    #[cfg(not(feature = "failure-injection"))]
+    {
+        let _ = (point, operation);
+        None
+    }
+}
+
+/// Внедрение сбоя в асинхронную операцию: задержка выдерживается, таймаут и ошибка возвращаются
+pub async fn inject(point: FaultPoint, operation: &str) -> anyhow::Result<()> {
+    match take(point, operation) {
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            Ok(())
+        }
+        Some(fault) => Err(fault_error(point, operation, fault)),
+        None => Ok(()),
+    }
+}
+
+/// Внедрение сбоя в синхронную операцию
+pub fn check(point: FaultPoint, operation: &str) -> anyhow::Result<()> {
+    match take(point, operation) {
+        Some(Fault::Delay(delay)) => {
+            std::thread::sleep(delay);
+            Ok(())
+        }
+        Some(fault) => Err(fault_error(point, operation, fault)),
+        None => Ok(()),
+    }
+}
+
+fn fault_error(point: FaultPoint, operation: &str, fault: Fault) -> anyhow::Error {
+    match (point, fault) {
+        (_, Fault::Timeout) => anyhow::anyhow!("Внедрённый таймаут операции '{}'", operation),
+        (FaultPoint::Allocation, _) => {
+            anyhow::anyhow!("Внедрённая нехватка памяти: {}", operation)
+        }
+        _ => anyhow::anyhow!("Внедрённая ошибка операции '{}' ({:?})", operation, point),
+    }
+}
+
+#[cfg(feature = "failure-injection")]
+pub use plan::FaultPlan;
+
+#[cfg(feature = "failure-injection")]
+mod plan {
+    use super::{Fault, FaultPoint};
+    use std::cell::RefCell;
+    use std::marker::PhantomData;
+
+    /// Правило плана: сбой в точке, опционально только для операций с подстрокой
+    #[derive(Debug)]
+    struct Rule {
+        point: FaultPoint,
+        fault: Fault,
+        matching: Option<String>,
+        remaining: Option<u32>,
+        hits: usize,
+    }
+
+    thread_local! {
+        static RULES: RefCell<Vec<Rule>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn take(point: FaultPoint, operation: &str) -> Option<Fault> {
+        let fault = RULES.with(|rules| {
+            let mut rules = rules.borrow_mut();
+            let rule = rules.iter_mut().find(|rule| {
+                rule.point == point
+                    && rule.remaining != Some(0)
+                    && rule.matching.as_deref().map_or(true, |m| operation.contains(m))
+            })?;
+            if let Some(ref mut remaining) = rule.remaining {
+                *remaining -= 1;
+            }
+            rule.hits += 1;
+            Some(rule.fault)
+        })?;
+        tracing::warn!("🧨 Внедрён сбой {:?} в {:?}: {}", fault, point, operation);
+        Some(fault)
+    }
+
+    /// План сбоев текущего потока; правила снимаются при удалении
+    pub struct FaultPlan {
+        /// План привязан к потоку: не `Send`
+        _thread: PhantomData<*const ()>,
+    }
+
+    impl FaultPlan {
+        /// Установка пустого плана в текущем потоке
+        pub fn install() -> Self {
+            RULES.with(|rules| rules.borrow_mut().clear());
+            Self { _thread: PhantomData }
+        }
+
+        /// Сбой во всех операциях точки (`times` — сколько раз, None — всегда)
+        pub fn arm(&self, point: FaultPoint, fault: Fault, times: Option<u32>) -> &Self {
+            self.push(point, fault, None, times)
+        }
+
+        /// Сбой только в операциях, имя которых содержит `operation`
+        pub fn arm_matching(
+            &self,
+            point: FaultPoint,
+            operation: &str,
+            fault: Fault,
+            times: Option<u32>,
+        ) -> &Self {
+            self.push(point, fault, Some(operation.to_string()), times)
+        }
+
+        fn push(&self, point: FaultPoint, fault: Fault, matching: Option<String>, remaining: Option<u32>) -> &Self {
+            RULES.with(|rules| {
+                rules.borrow_mut().push(Rule {
+                    point,
+                    fault,
+                    matching,
+                    remaining,
+                    hits: 0,
+                })
+            });
+            self
+        }
+
+        /// Сколько раз сработали сбои в точке
+        pub fn hits(&self, point: FaultPoint) -> usize {
+            RULES.with(|rules| {
+                rules
+                    .borrow()
+                    .iter()
+                    .filter(|rule| rule.point == point)
+                    .map(|rule| rule.hits)
+                    .sum()
+            })
+        }
+    }
+
+    impl Drop for FaultPlan {
+        fn drop(&mut self) {
+            RULES.with(|rules| rules.borrow_mut().clear());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "failure-injection"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_plan_counts_and_matching() {
+        let plan = FaultPlan::install();
+        plan.arm(FaultPoint::SaveBatch, Fault::Timeout, Some(2))
+            .arm_matching(FaultPoint::Neo4jOperation, "чтение связей", Fault::Error, None);
+
+        assert!(check(FaultPoint::SaveBatch, "батч 1").is_err());
+        assert!(check(FaultPoint::SaveBatch, "батч 2").is_err());
+        assert!(check(FaultPoint::SaveBatch, "батч 3").is_ok());
+        assert_eq!(plan.hits(FaultPoint::SaveBatch), 2);
+
+        assert_eq!(take(FaultPoint::Neo4jOperation, "чтение связей"), Some(Fault::Error));
+        assert_eq!(take(FaultPoint::Neo4jOperation, "проверка здоровья"), None);
+        assert_eq!(take(FaultPoint::Allocation, "граф"), None);
+
+        drop(plan);
+        let plan = FaultPlan::install();
+        assert_eq!(take(FaultPoint::Neo4jOperation, "чтение связей"), None);
+        assert_eq!(plan.hits(FaultPoint::Neo4jOperation), 0);
+    }
+
+    #[tokio::test]
+    async fn test_inject_delay() {
+        let plan = FaultPlan::install();
+        plan.arm(FaultPoint::SaveBatch, Fault::Delay(Duration::from_millis(20)), Some(1));
+
+        let start = std::time::Instant::now();
+        inject(FaultPoint::SaveBatch, "батч 1").await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,236 @@
+/*!
+# Хранилище графа
+
+Трейт `GraphStore` описывает операции, которые нужны gRPC серверу от базы:
+проверка доступности, загрузка связей и окрестности вершины, сохранение позиций.
+Основная реализация — `Neo4jClient`; `InMemoryGraphStore` держит граф в памяти
+и позволяет тестировать логику сервера (параметры по умолчанию, ошибки,
+сохранение) без Neo4j.
+*/
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::failure_injection::FaultPoint;
+use crate::neo4j::{FailedSaveBatch, GraphEdge, Neo4jClient, SaveReport, VertexPosition};
+
+/// Источник связей и приёмник позиций для сервера укладки
+#[async_trait]
+pub trait GraphStore: Send + Sync + 'static {
+    /// Проверка доступности хранилища
+    async fn health_check(&self) -> Result<()>;
+
+    /// Загрузка всех связей графа
+    async fn load_graph_edges(&self) -> Result<Vec<GraphEdge>>;
+
+    /// Загрузка связей k-окрестности вершины (не больше `max_vertices` ближайших
+    /// вершин, при равном числе переходов — по uid); None — вершины нет
+    async fn load_neighborhood_edges(
+        &self,
+        center_id: &str,
+        radius: u32,
+        max_vertices: usize,
+    ) -> Result<Option<Vec<GraphEdge>>>;
+
+    /// Сохранение позиций вершин
+    async fn save_layout_results(&self, positions: &[VertexPosition]) -> Result<SaveReport>;
+}
+
+#[async_trait]
+impl GraphStore for Neo4jClient {
+    async fn health_check(&self) -> Result<()> {
+        Neo4jClient::health_check(self).await
+    }
+
+    async fn load_graph_edges(&self) -> Result<Vec<GraphEdge>> {
+        Neo4jClient::load_graph_edges(self).await
+    }
+
+    async fn load_neighborhood_edges(
+        &self,
+        center_id: &str,
+        radius: u32,
+        max_vertices: usize,
+    ) -> Result<Option<Vec<GraphEdge>>> {
+        Neo4jClient::load_neighborhood_edges(self, center_id, radius, max_vertices).await
+    }
+
+    async fn save_layout_results(&self, positions: &[VertexPosition]) -> Result<SaveReport> {
+        Neo4jClient::save_layout_results(self, positions).await
+    }
+}
+
+/// Граф в памяти для тестов сервера
+#[derive(Debug, Default)]
+pub struct InMemoryGraphStore {
+    edges: Vec<GraphEdge>,
+    positions: Mutex<HashMap<String, VertexPosition>>,
+    unavailable: AtomicBool,
+    save_batch_size: usize,
+}
+
+impl InMemoryGraphStore {
+    pub fn new(edges: Vec<GraphEdge>) -> Self {
+        Self {
+            edges,
+            ..Self::default()
+        }
+    }
+
+    /// Сохранение батчами по `batch_size` позиций (0 — одним батчем)
+    ///
+    /// Батч, на котором сработал сбой `SaveBatch`, не записывается и попадает
+    /// в `SaveReport::failed_batches`, как при частичном сохранении в Neo4j.
+    pub fn with_save_batch_size(mut self, batch_size: usize) -> Self {
+        self.save_batch_size = batch_size;
+        self
+    }
+
+    /// Имитация недоступной базы: все операции возвращают ошибку
+    pub fn set_available(&self, available: bool) {
+        self.unavailable.store(!available, Ordering::SeqCst);
+    }
+
+    /// Сохранённые позиции, отсортированные по ID вершины
+    pub fn saved_positions(&self) -> Vec<VertexPosition> {
+        let mut positions: Vec<VertexPosition> = self
+            .positions
+            .lock()
+            .map(|p| p.values().cloned().collect())
+            .unwrap_or_default();
+        positions.sort_by(|a, b| a.article_id.cmp(&b.article_id));
+        positions
+    }
+
+    fn ensure_available(&self) -> Result<()> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("хранилище недоступно"));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GraphStore for InMemoryGraphStore {
+    async fn health_check(&self) -> Result<()> {
+        self.ensure_available()
+    }
+
+    async fn load_graph_edges(&self) -> Result<Vec<GraphEdge>> {
+        self.ensure_available()?;
+        Ok(self.edges.clone())
+    }
+
+    async fn load_neighborhood_edges(
+        &self,
+        center_id: &str,
+        radius: u32,
+        max_vertices: usize,
+    ) -> Result<Option<Vec<GraphEdge>>> {
+        self.ensure_available()?;
+
+        let mut neighbors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            neighbors.entry(edge.source_id.as_str()).or_default().push(edge.target_id.as_str());
+            neighbors.entry(edge.target_id.as_str()).or_default().push(edge.source_id.as_str());
+        }
+        // Вершины известны только по связям
+        if !neighbors.contains_key(center_id) {
+            return Ok(None);
+        }
+
+        // BFS по уровням без учёта направления, как в запросе к Neo4j: уровень
+        // берётся в порядке uid, пока не наберётся `max_vertices` вершин
+        let mut visited: HashSet<&str> = HashSet::from([center_id]);
+        let mut frontier = vec![center_id];
+        for _ in 0..radius {
+            let mut next: Vec<&str> = frontier
+                .iter()
+                .flat_map(|vertex| neighbors.get(vertex).into_iter().flatten().copied())
+                .filter(|vertex| !visited.contains(vertex))
+                .collect();
+            next.sort_unstable();
+            next.dedup();
+            next.truncate(max_vertices.saturating_sub(visited.len()));
+            if next.is_empty() {
+                break;
+            }
+            visited.extend(next.iter().copied());
+            frontier = next;
+        }
+
+        Ok(Some(
+            self.edges
+                .iter()
+                .filter(|e| visited.contains(e.source_id.as_str()) && visited.contains(e.target_id.as_str()))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    async fn save_layout_results(&self, positions: &[VertexPosition]) -> Result<SaveReport> {
+        self.ensure_available()?;
+        let mut report = SaveReport {
+            total_rows: positions.len(),
+            ..SaveReport::default()
+        };
+        let batch_size = if self.save_batch_size == 0 { positions.len().max(1) } else { self.save_batch_size };
+        for (batch, chunk) in positions.chunks(batch_size).enumerate() {
+            let start = batch * batch_size;
+            let end = start + chunk.len();
+            let operation = format!("сохранение батча {} (строки {}-{})", batch + 1, start, end - 1);
+            if let Err(e) = crate::failure_injection::inject(FaultPoint::SaveBatch, &operation).await {
+                report.failed_batches.push(FailedSaveBatch {
+                    batch,
+                    start,
+                    end,
+                    error: e.to_string(),
+                });
+                continue;
+            }
+
+            let mut stored = self
+                .positions
+                .lock()
+                .map_err(|_| anyhow::anyhow!("хранилище позиций повреждено"))?;
+            for position in chunk {
+                stored.insert(position.article_id.clone(), position.clone());
+            }
+            report.saved_rows += chunk.len();
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: &str, target: &str) -> GraphEdge {
+        GraphEdge::new(source, target, 1.0)
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_neighborhood() {
+        let store = InMemoryGraphStore::new(vec![edge("A", "B"), edge("B", "C"), edge("C", "D")]);
+
+        let edges = store.load_neighborhood_edges("B", 1, 100).await.unwrap().unwrap();
+        assert_eq!(edges.len(), 2);
+
+        let edges = store.load_neighborhood_edges("A", 3, 2).await.unwrap().unwrap();
+        assert_eq!(edges.len(), 1);
+
+        // Отсечение по uid: из соседей C на одном расстоянии остаётся B
+        let edges = store.load_neighborhood_edges("C", 1, 2).await.unwrap().unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!((edges[0].source_id.as_str(), edges[0].target_id.as_str()), ("B", "C"));
+
+        assert!(store.load_neighborhood_edges("Z", 2, 100).await.unwrap().is_none());
+
+        store.set_available(false);
+        assert!(store.load_graph_edges().await.is_err());
+    }
+}
@@ -6,30 +6,56 @@
 ## Модули
 
 - `algorithms` - Основные алгоритмы укладки
+- `artifacts` - Артефакты задач и политика их хранения
 - `data_structures` - Оптимизированные структуры данных для графов
+- `failure_injection` - Внедрение сбоев для тестов устойчивости (feature `failure-injection`)
 - `memory` - Управление памятью и кешированием
 - `metrics` - Сбор метрик производительности
+- `messages` - Каталог сообщений логов и ошибок (ru/en)
 - `neo4j` - Интеграция с Neo4j базой данных
+- `retry` - Политика повторных попыток операций Neo4j
 - `server` - gRPC сервер
+- `tenant` - Идентификатор арендатора для изоляции метрик и артефактов
+- `prelude` - стабильный публичный API для встраивания движка
+
+## Стабильность API
+
+Семантическое версионирование распространяется на `prelude` и на типы,
+достижимые из его сигнатур. Внутренние этапы движка (топологическая
+сортировка, longest path, управление памятью, параллельная обработка) и
+вспомогательные модули сервиса доступны только внутри крейта.
 
 */
 #![allow(dead_code)]
 
 pub mod algorithms;
+pub mod artifacts;
+pub(crate) mod blocking_io;
 pub mod config;
 pub mod data_structures;
 pub mod db_optimizer;
-pub mod memory;
-pub mod metrics;
+pub mod export;
+pub mod failure_injection;
+pub mod graph_store;
+pub(crate) mod memory;
+pub mod messages;
+pub(crate) mod metrics;
 pub mod neo4j;
+pub mod prelude;
+pub mod retry;
 pub mod server;
-pub mod test_layout;
+pub mod sinks;
+pub mod tenant;
+pub(crate) mod test_layout;
 
 // Re-export основных типов
 pub use algorithms::{HighPerformanceLayoutEngine, LayoutAlgorithm, LayoutResult};
+pub use algorithms::phases::LayoutPhases;
+pub use algorithms::incremental::{GraphDelta, IncrementalLayoutEngine, IncrementalUpdate};
 pub use config::Config;
-pub use data_structures::{Graph, GraphBuilder};
+pub use data_structures::{Graph, GraphBuilder, VertexId};
 pub use db_optimizer::DatabaseOptimizer;
+pub use graph_store::GraphStore;
 pub use server::GraphLayoutServer;
 
 // Подключаем сгенерированные protobuf типы
@@ -23,6 +23,7 @@ Rust-based микросервис для высокопроизводитель
 #![allow(dead_code)]
 
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::Result;
 use clap::Parser;
@@ -32,12 +33,22 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
 mod config;
 mod algorithms;
+mod artifacts;
+mod bench;
+mod blocking_io;
 mod data_structures;
 mod db_optimizer;
+mod export;
+mod failure_injection;
+mod graph_store;
 mod memory;
+mod messages;
 mod metrics;
 mod neo4j;
+mod retry;
 mod server;
+mod sinks;
+mod tenant;
 
 // Подключаем сгенерированные protobuf типы
 pub mod generated {
@@ -47,6 +58,7 @@ pub mod generated {
 
 use crate::config::Config;
 use crate::db_optimizer::DatabaseOptimizer;
+use crate::messages::Msg;
 use crate::server::GraphLayoutServer;
 
 #[cfg(feature = "mimalloc")]
@@ -82,6 +94,52 @@ struct Args {
     /// Режим работы
     #[arg(short, long, default_value = "auto-layout")]
     mode: ServerMode,
+
+    /// Бенчмарк: нагрузочный тест in-process gRPC сервера
+    #[arg(long)]
+    grpc: bool,
+
+    /// Бенчмарк --grpc: количество одновременных клиентов
+    #[arg(long, default_value_t = 8)]
+    bench_concurrency: usize,
+
+    /// Бенчмарк --grpc: количество запросов на каждый размер графа
+    #[arg(long, default_value_t = 100)]
+    bench_requests: usize,
+
+    /// Бенчмарк: размеры синтетических графов (вершин), через запятую
+    #[arg(long, value_delimiter = ',', default_value = "1000,10000")]
+    bench_sizes: Vec<usize>,
+
+    /// Бенчмарк: среднее количество исходящих связей на вершину
+    #[arg(long, default_value_t = 3)]
+    bench_degree: usize,
+
+    /// Auto-layout: файл графа (`Graph::serialize_with_source`). Если файл есть
+    /// и не устарел, граф загружается из него вместо Neo4j; иначе граф из Neo4j
+    /// сохраняется в него
+    #[arg(long)]
+    graph_file: Option<std::path::PathBuf>,
+
+    /// Auto-layout: загрузить граф из Neo4j и перезаписать `--graph-file`,
+    /// даже если файл не устарел
+    #[arg(long)]
+    refresh_graph: bool,
+
+    /// Auto-layout: укладка только связей, изменённых после предыдущего запуска
+    /// (время хранится в узле `(:LayoutRun)`); без него выполняется полная укладка
+    #[arg(long)]
+    incremental: bool,
+
+    /// Приёмники результатов вместо `[output] sinks`: neo4j, stdout или путь
+    /// к файлу (.json/.svg/.gml/.csv/.jsonl, поддерживает `{run_id}`)
+    #[arg(long)]
+    sink: Vec<String>,
+
+    /// Не записывать координаты в Neo4j: приёмник neo4j пропускается
+    /// (без других приёмников результат выводится в stdout, а логи — в stderr)
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[derive(Debug, Clone, clap::ValueEnum)]
@@ -92,6 +150,10 @@ enum ServerMode {
     AutoLayout,
     /// Режим проверки здоровья
     Health,
+    /// Укладка по временным окнам (кадры эволюции карты знаний)
+    TimeSlices,
+    /// Откат координат к значениям до последнего атомарного сохранения
+    Rollback,
     /// Режим бенчмарков
     Benchmark,
     /// Режим тестирования
@@ -102,57 +164,121 @@ enum ServerMode {
 async fn main() -> Result<()> {
     let args = Args::parse();
     
-    // Инициализация логирования
-    init_logging(&args.log_level)?;
+    // Инициализация логирования (guards держат потоки записи логов до завершения)
+    let _log_guards = init_logging(&args.log_level)?;
     
-    info!(
-        "🦀 Запуск Graph Layout Engine v{}", 
-        env!("CARGO_PKG_VERSION")
-    );
+    info!("{}", Msg::Startup { version: env!("CARGO_PKG_VERSION") });
     
     // Загрузка конфигурации
-    let config = match Config::load(&args.config) {
+    let mut config = match Config::load(&args.config) {
         Ok(config) => {
-            info!("📋 Конфигурация загружена из {}", args.config);
+            messages::set_language(config.server.language);
+            info!("{}", Msg::ConfigLoaded { path: &args.config });
             config
         },
         Err(e) => {
-            error!("❌ Ошибка загрузки конфигурации: {}", e);
+            error!("{}", Msg::ConfigLoadFailed { error: &e });
             return Err(e);
         }
     };
     
+    // Приёмники результатов из командной строки
+    if !args.sink.is_empty() {
+        config.output.sinks = args
+            .sink
+            .iter()
+            .map(|spec| sinks::SinkConfig::from_cli(spec))
+            .collect::<Result<_>>()?;
+    }
+    if args.dry_run {
+        config.output.sinks.retain(|sink| !matches!(sink, sinks::SinkConfig::Neo4j));
+        if config.output.sinks.is_empty() {
+            config.output.sinks.push(sinks::SinkConfig::Stdout);
+        }
+        info!("{}", Msg::DryRun);
+    }
+    // Приёмник stdout занимает стандартный вывод целиком, логи уходят в stderr
+    let stdout_sink = config.output.sinks.iter().any(|sink| matches!(sink, sinks::SinkConfig::Stdout));
+    CONSOLE_LOGS_TO_STDOUT.store(!stdout_sink, Ordering::Relaxed);
+
     // Выбор режима работы
-    info!("🎯 Режим работы: {:?}", args.mode);
+    info!("{}", Msg::ModeSelected { mode: &args.mode });
     match args.mode {
         ServerMode::Server => {
-            info!("🚀 Запуск в режиме gRPC сервера");
+            info!("{}", Msg::ServerModeStart);
             run_server(args.address, config).await?;
         },
         ServerMode::AutoLayout => {
-            info!("🧮 Запуск в режиме автоматической укладки");
-            run_auto_layout(config).await?;
+            info!("{}", Msg::AutoLayoutModeStart);
+            run_auto_layout(config, args.graph_file.as_deref(), args.refresh_graph, args.incremental).await?;
+        },
+        ServerMode::TimeSlices => {
+            info!("{}", Msg::TimeSlicesModeStart);
+            run_time_slices(config).await?;
+        },
+        ServerMode::Rollback => {
+            info!("{}", Msg::RollbackModeStart);
+            let store = crate::neo4j::Neo4jClient::new(&config).await?;
+            // Остатки прерванных атомарных сохранений не должны попасть в следующее переключение
+            let stale = store.clear_staged_layout(None).await?;
+            if stale > 0 {
+                warn!("{}", Msg::StagingCleared { articles: stale });
+            }
+            store.rollback_layout().await?;
         },
         ServerMode::Health => {
-            info!("🏥 Запуск проверки здоровья");
+            info!("{}", Msg::HealthModeStart);
             run_health_check().await?;
         },
+        ServerMode::Benchmark if args.grpc => {
+            info!("{}", Msg::GrpcBenchModeStart);
+            let options = bench::GrpcBenchOptions {
+                concurrency: args.bench_concurrency,
+                requests: args.bench_requests,
+                sizes: args.bench_sizes.clone(),
+                avg_out_degree: args.bench_degree,
+            };
+            bench::run_grpc_bench(config, options).await?;
+        },
         ServerMode::Benchmark => {
-            info!("📊 Запуск бенчмарков");
-            run_benchmarks().await?;
+            info!("{}", Msg::BenchModeStart);
+            run_benchmarks(&args.bench_sizes, args.bench_degree, config.algorithms.max_layer_width).await?;
         },
         ServerMode::Test => {
-            info!("🧪 Запуск тестов укладки");
+            info!("{}", Msg::TestModeStart);
             run_tests().await?;
         },
     }
     
-    info!("✅ Программа завершена успешно");
+    info!("{}", Msg::Finished);
     Ok(())
 }
 
+/// Выводятся ли консольные логи в stdout (иначе в stderr)
+///
+/// До загрузки конфигурации логи идут в stderr: пока неизвестно, займёт ли
+/// stdout приёмник результатов, его нельзя засорять.
+static CONSOLE_LOGS_TO_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// Поток консольных логов по `CONSOLE_LOGS_TO_STDOUT`
+fn console_writer() -> Box<dyn std::io::Write> {
+    if CONSOLE_LOGS_TO_STDOUT.load(Ordering::Relaxed) {
+        Box::new(std::io::stdout())
+    } else {
+        Box::new(std::io::stderr())
+    }
+}
+
 /// Инициализация системы логирования
-fn init_logging(level: &str) -> Result<()> {
+///
+/// Файлы логов пишутся отдельными потоками (`tracing_appender::non_blocking`),
+/// чтобы запись на диск не задерживала async-задачи. Возвращённые guards нужно
+/// держать до выхода из программы: при их удалении буферы сбрасываются на диск.
+///
+/// Консольные логи пишутся в stdout, кроме запусков с приёмником `stdout`
+/// (в том числе `--dry-run` без других приёмников): там stdout содержит только
+/// JSON результата, а логи выводятся в stderr.
+fn init_logging(level: &str) -> Result<Vec<tracing_appender::non_blocking::WorkerGuard>> {
     let level = level.parse::<tracing::Level>()
         .map_err(|e| anyhow::anyhow!("Неверный уровень логирования: {}", e))?;
     
@@ -168,12 +294,16 @@ fn init_logging(level: &str) -> Result<()> {
         });
     
     // Используем rolling file appender для файлового логирования
-    let file_appender = tracing_appender::rolling::never("logs", "rust_layout.log");
-    let error_appender = tracing_appender::rolling::never("logs", "rust_layout_error.log");
+    let (file_appender, file_guard) = tracing_appender::non_blocking(
+        tracing_appender::rolling::never("logs", "rust_layout.log"),
+    );
+    let (error_appender, error_guard) = tracing_appender::non_blocking(
+        tracing_appender::rolling::never("logs", "rust_layout_error.log"),
+    );
     
     // Слой для консоли
     let stdout_layer = tracing_subscriber::fmt::layer()
-        .with_writer(std::io::stdout)
+        .with_writer(console_writer)
         .with_target(false)
         .with_ansi(false);
     
@@ -198,130 +328,537 @@ fn init_logging(level: &str) -> Result<()> {
         .with(error_layer)
         .init();
     
-    Ok(())
+    Ok(vec![file_guard, error_guard])
 }
 
 /// Автоматическая укладка графа
-async fn run_auto_layout(config: Config) -> Result<()> {
-    info!("🔄 Запуск автоматической укладки графа...");
+async fn run_auto_layout(
+    config: Config,
+    graph_file: Option<&std::path::Path>,
+    refresh_graph: bool,
+    incremental: bool,
+) -> Result<()> {
+    info!("{}", Msg::AutoLayoutStart);
 
     // Создание сервиса укладки графов
-    info!("🔧 Создание GraphLayoutServer...");
+    info!("{}", Msg::CreatingServer);
     let layout_service = match GraphLayoutServer::new(config.clone()).await {
         Ok(service) => {
-            info!("✅ GraphLayoutServer создан успешно");
+            info!("{}", Msg::ServerCreated);
             service
         },
         Err(e) => {
-            error!("❌ Ошибка создания GraphLayoutServer: {}", e);
+            error!("{}", Msg::ServerCreateFailed { error: &e });
             return Err(e);
         }
     };
 
     // Подготовка базы данных: проверка и создание индексов
-    info!("🔧 Подготовка базы данных...");
-    let db_optimizer = DatabaseOptimizer::new(layout_service.neo4j_client.graph());
+    info!("{}", Msg::PreparingDatabase);
+    let db_optimizer = DatabaseOptimizer::new(layout_service.store.graph())
+        .with_retry_policy(crate::retry::RetryPolicy::from_config(&config.neo4j.retry));
     match db_optimizer.prepare_database().await {
         Ok(_) => {
-            info!("✅ База данных подготовлена");
+            info!("{}", Msg::DatabasePrepared);
         },
         Err(e) => {
-            warn!("⚠️ Ошибка подготовки базы данных: {}. Продолжаем без оптимизаций.", e);
+            warn!("{}", Msg::DatabasePrepareFailed { error: &e });
         }
     }
     
-    info!("🧮 Начинаем батчевую обработку...");
+    if incremental {
+        return run_incremental_layout(&layout_service, &config).await.map_err(|e| {
+            error!("{}", Msg::IncrementalLayoutFailed { error: &e });
+            e
+        });
+    }
+
+    info!("{}", Msg::BatchLayoutStart);
     // Батчевая обработка
-    match run_batch_layout(&layout_service, &config).await {
+    match run_batch_layout(&layout_service, &config, graph_file, refresh_graph).await {
         Ok(_) => {
-            info!("✅ Батчевая обработка завершена успешно");
+            info!("{}", Msg::BatchLayoutDone);
             Ok(())
         },
         Err(e) => {
-            error!("❌ Ошибка батчевой обработки: {}", e);
+            error!("{}", Msg::BatchLayoutFailed { error: &e });
             Err(e)
         }
     }
 }
 
+/// Инкрементальная укладка связей, изменённых после предыдущего запуска
+///
+/// Сохранённые в Neo4j слои служат нижними границами; к изменённым связям
+/// догружаются исходящие связи сдвинутых вершин, пока сдвиги не закончатся.
+/// Каскад глубже `limits.incremental_max_depth` или со сдвигом больше
+/// `limits.incremental_max_vertices` вершин заменяется полной укладкой.
+/// В приёмники пишутся только новые и сдвинутые позиции, а с
+/// `neo4j.save_vertex_aggregates` — и концы изменённых связей с пересчитанными
+/// агрегатами. Без отметки о предыдущем запуске выполняется полная укладка.
+/// Удалённые связи не учитываются.
+async fn run_incremental_layout(layout_service: &GraphLayoutServer, config: &Config) -> Result<()> {
+    use crate::algorithms::incremental::{GraphDelta, IncrementalLayoutEngine};
+    use crate::algorithms::vertex_placement::{LevelPacking, PlacementConfig};
+
+    let store = &layout_service.store;
+    let Some(since) = store.load_last_layout_run().await? else {
+        info!("{}", Msg::NoPreviousLayout);
+        return run_batch_layout(layout_service, config, None, false).await;
+    };
+
+    let heavy_run = layout_service.begin_heavy_run("auto-layout", "инкрементальная укладка");
+    let started_at = chrono::Utc::now();
+    info!("{}", Msg::IncrementalSince { since: &since.to_rfc3339() });
+
+    let added = store.load_graph_edges_since(since).await?;
+    if added.is_empty() {
+        // Запись запуска не создаётся: текущей остаётся предыдущая укладка,
+        // и следующий запуск ищет изменения с того же момента
+        info!("{}", Msg::NoChangedEdges);
+        return Ok(());
+    }
+    config.limits.check_edges(added.len())?;
+    // Степени концов изменённых связей поменялись, даже если позиции остались прежними
+    let touched: std::collections::HashSet<String> = added
+        .iter()
+        .flat_map(|e| [e.source_id.clone(), e.target_id.clone()])
+        .collect();
+
+    let previous = store.load_layout_positions().await?;
+    let placement_config = PlacementConfig {
+        block_width: config.algorithms.block_width,
+        block_height: config.algorithms.block_height,
+        horizontal_gap: config.algorithms.horizontal_gap,
+        vertical_gap: config.algorithms.vertical_gap,
+        ..PlacementConfig::default()
+    };
+    let mut engine = IncrementalLayoutEngine::from_positions(&previous, placement_config)?;
+
+    // Сдвинутая вершина сдвигает свои цели: их связи догружаются, пока есть сдвиги
+    let mut changed: std::collections::HashMap<String, crate::neo4j::VertexPosition> =
+        std::collections::HashMap::new();
+    let mut expanded: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut loaded_edges = added.len();
+    let mut rejected = 0usize;
+    let mut delta = GraphDelta { added, removed: Vec::new() };
+    let limits = &config.limits;
+    let mut depth = 0usize;
+    while !delta.added.is_empty() {
+        let update = engine.apply_delta(&delta)?;
+        rejected += update.rejected.len();
+
+        let frontier: Vec<String> = update
+            .changed
+            .iter()
+            .filter(|p| expanded.insert(p.article_id.clone()))
+            .map(|p| p.article_id.clone())
+            .collect();
+        changed.extend(update.changed.into_iter().map(|p| (p.article_id.clone(), p)));
+        if frontier.is_empty() {
+            break;
+        }
+
+        let too_deep = limits.incremental_max_depth > 0 && depth >= limits.incremental_max_depth;
+        let too_wide = limits.incremental_max_vertices > 0 && changed.len() > limits.incremental_max_vertices;
+        if too_deep || too_wide {
+            warn!("{}", Msg::ShiftCascadeTooLarge { depth, vertices: changed.len() });
+            drop(heavy_run);
+            return run_batch_layout(layout_service, config, None, false).await;
+        }
+        depth += 1;
+
+        delta.added = store.load_outgoing_edges(&frontier).await?;
+        loaded_edges += delta.added.len();
+    }
+    if rejected > 0 {
+        warn!("{}", Msg::CycleEdgesRejected { count: rejected });
+    }
+
+    info!("{}", Msg::PositionsChanged { count: changed.len() });
+
+    // Агрегаты пишутся в строках позиций, поэтому пересчитываются для всех
+    // записываемых вершин; концы изменённых связей записываются с прежними позициями
+    let vertex_aggregates = if config.neo4j.save_vertex_aggregates {
+        for position in engine.positions() {
+            if touched.contains(&position.article_id) && !changed.contains_key(&position.article_id) {
+                changed.insert(position.article_id.clone(), position.clone());
+            }
+        }
+        let uids: Vec<String> = changed.keys().cloned().collect();
+        Some(store.load_vertex_aggregates(&uids).await?)
+    } else {
+        None
+    };
+    let mut positions: Vec<crate::neo4j::VertexPosition> = changed.into_values().collect();
+    positions.sort_by(|a, b| a.article_id.cmp(&b.article_id));
+
+    let layout_meta = crate::neo4j::LayoutMeta {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        algorithm_version: env!("CARGO_PKG_VERSION").to_string(),
+        block_width: config.algorithms.block_width,
+        block_height: config.algorithms.block_height,
+        horizontal_gap: config.algorithms.horizontal_gap,
+        vertical_gap: config.algorithms.vertical_gap,
+        origin_x: 0.0,
+        origin_y: 0.0,
+        level_packing: format!("{:?}", LevelPacking::OnePerLevel),
+        vertex_count: engine.positions().count(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        scale_profile: config.algorithms.scale_profile.name().to_string(),
+    };
+    let sinks = sinks::build_sinks(config, store.clone())?;
+    let run = sinks::LayoutRun {
+        run_id: &layout_meta.run_id,
+        meta: &layout_meta,
+        positions: &positions,
+        style: None,
+        aggregates: vertex_aggregates.as_ref(),
+        edge_paths: &[],
+    };
+    let reports = sinks::write_all(&sinks, &run).await?;
+    if reports.len() < sinks.len() || reports.iter().any(|r| r.partial) {
+        return Err(anyhow::anyhow!(
+            "Инкрементальная укладка записана не во все приёмники ({} из {})",
+            reports.iter().filter(|r| !r.partial).count(),
+            sinks.len()
+        ));
+    }
+    if !config.output.sinks.iter().any(|sink| matches!(sink, sinks::SinkConfig::Neo4j)) {
+        info!("{}", Msg::AllPhasesDone);
+        return Ok(());
+    }
+
+    let stats = crate::neo4j::LayoutRunStats {
+        incremental: true,
+        edges_source: "neo4j".to_string(),
+        edges: loaded_edges,
+        positions: positions.len(),
+        layers: engine.positions().map(|p| p.layer as usize + 1).max().unwrap_or(0),
+        cycle_edges: rejected,
+        ..Default::default()
+    };
+    save_layout_run(store, &layout_meta.run_id, stats, config, started_at).await?;
+
+    info!("{}", Msg::AllPhasesDone);
+    Ok(())
+}
+
 /// Батчевая обработка графа с глобальным назначением слоёв
-async fn run_batch_layout(layout_service: &GraphLayoutServer, config: &Config) -> Result<()> {
+///
+/// С `graph_file` существующий файл графа заменяет загрузку связей из Neo4j,
+/// а отсутствующий, устаревший (см. `load_graph_file`) или с `refresh_graph`
+/// создаётся заново из загруженных связей для следующих запусков.
+/// Укладка, полностью записанная во все приёмники (среди них Neo4j),
+/// сохраняется узлом `(:LayoutRun)`; от него отсчитывает изменения следующий
+/// запуск с `--incremental`.
+async fn run_batch_layout(
+    layout_service: &GraphLayoutServer,
+    config: &Config,
+    graph_file: Option<&std::path::Path>,
+    refresh_graph: bool,
+) -> Result<()> {
     use tracing::info;
+    use crate::algorithms::cycle_breaking::CycleBreakStrategy;
     use crate::algorithms::vertex_placement::{GlobalLayerState, PlacementConfig};
 
-    info!("=== БАТЧЕВАЯ ОБРАБОТКА С ГЛОБАЛЬНЫМ НАЗНАЧЕНИЕМ СЛОЁВ ===");
-    info!("📊 Загрузка данных графа из Neo4j...");
+    info!("{}", Msg::BatchLayoutHeader);
+    let _heavy_run = layout_service.begin_heavy_run("auto-layout", "полная автоматическая укладка");
+    info!("{}", Msg::LoadingGraph);
+
+    // Связи, изменённые после этого момента, попадут в следующую инкрементальную укладку
+    let started_at = chrono::Utc::now();
+
+    // Граф из файла вместо Neo4j; без файла связи из Neo4j собираются для его создания
+    let cached_graph = match graph_file {
+        Some(path) if path.exists() && !refresh_graph => load_graph_file(path, config, &layout_service.store).await?,
+        _ => None,
+    };
+    let from_neo4j = cached_graph.is_none();
+    // Загруженные связи сбрасываются на диск и после загрузки собираются в граф
+    // для файла графа и для маршрутов связей
+    let mut edge_spill = match &cached_graph {
+        None if graph_file.is_some() || config.output.edge_paths => {
+            Some(crate::data_structures::EdgeSpill::create(std::path::Path::new(&config.memory.temp_dir))?)
+        }
+        _ => None,
+    };
 
     // Получаем общее количество связей
-    let total_edges = layout_service.neo4j_client.get_total_edges_count().await?;
-    info!("📈 Всего связей в БД: {}", total_edges);
+    let total_edges = match cached_graph {
+        Some(ref graph) => graph.multi_edge_count(),
+        None => layout_service.store.get_total_edges_count().await?,
+    };
+    info!("{}", Msg::TotalEdges { count: total_edges });
+
+    // Проверка жёстких лимитов: подсчёт уже учитывает условие выборки
+    let expected_edges = if config.neo4j.max_edges > 0 {
+        total_edges.min(config.neo4j.max_edges)
+    } else {
+        total_edges
+    };
+    config.limits.check_edges(expected_edges)?;
 
-    // Определяем размер батча из конфигурации
-    let batch_size = config.neo4j.batch_size;
-    let total_batches = (total_edges + batch_size - 1) / batch_size;
+    // Размер страниц загрузки из конфигурации (адаптируется к времени ответа Neo4j)
+    let adaptive = if config.neo4j.adaptive_batch_size {
+        crate::neo4j::AdaptiveBatchSize::new(
+            config.neo4j.batch_size,
+            config.neo4j.min_batch_size,
+            config.neo4j.max_batch_size,
+            std::time::Duration::from_millis(config.neo4j.fast_batch_ms),
+        )
+    } else {
+        crate::neo4j::AdaptiveBatchSize::fixed(config.neo4j.batch_size)
+    };
+    let batch_size = config.neo4j.batch_size.max(1);
 
-    info!("🔄 Будет загружено {} батчей по {} связей", total_batches, batch_size);
+    info!("{}", Msg::StreamingLoad {
+        batch_size,
+        page_size: adaptive.current(),
+        adaptive: config.neo4j.adaptive_batch_size,
+    });
 
     // Фаза 1: Глобальное назначение слоёв
-    info!("=== ФАЗА 1: ГЛОБАЛЬНОЕ НАЗНАЧЕНИЕ СЛОЁВ ===");
-    let mut global_state = GlobalLayerState::new();
+    info!("{}", Msg::Phase1Header);
+    let mut global_state = match config.memory.strategy {
+        crate::config::MemoryStrategy::Streaming => {
+            info!("{}", Msg::DiskSpill { dir: &config.memory.temp_dir });
+            GlobalLayerState::with_disk_spill(&config.memory.temp_dir)?
+        }
+        _ => GlobalLayerState::new(),
+    };
+    if !config.algorithms.layer_constraints.is_empty() {
+        info!("{}", Msg::LayerConstraints { count: config.algorithms.layer_constraints.len() });
+        global_state.set_layer_constraints(config.algorithms.layer_constraints.clone())?;
+    }
+    if !config.algorithms.layer_lower_bounds.is_empty() {
+        info!("{}", Msg::LayerLowerBounds { count: config.algorithms.layer_lower_bounds.len() });
+        global_state.set_layer_lower_bounds(config.algorithms.layer_lower_bounds.clone())?;
+    }
 
-    for batch_num in 0..total_batches {
-        let offset = batch_num * batch_size;
-        info!("📥 Загрузка батча {}/{} (offset={})", batch_num + 1, total_batches, offset);
+    // Связи читаются в фоне, пока слои обновляются по уже полученным
+    let edge_stream = (cached_graph.is_none() && total_edges > 0)
+        .then(|| layout_service.store.stream_edges(adaptive));
+    let mut batch_num = 0usize;
+    let mut loaded_edges = 0usize;
+    let mut broken_edges = 0usize;
+    // Степени и веса по выборке связей занижены, поэтому сохраняются только по всем связям
+    let sampled = config.neo4j.sample_rate < 1.0;
+    if config.neo4j.save_vertex_aggregates && sampled {
+        tracing::warn!("{}", Msg::AggregatesSkippedSampled { sample_rate: config.neo4j.sample_rate });
+    }
+    let mut vertex_aggregates = (config.neo4j.save_vertex_aggregates && !sampled).then(std::collections::HashMap::new);
+    if let Some(edge_stream) = edge_stream {
+        use tokio_stream::StreamExt;
+        tokio::pin!(edge_stream);
 
-        let batch_edges = layout_service.neo4j_client.load_graph_edges_batch(batch_size, offset).await?;
+        // Ограничение количества связей для предварительных укладок
+        let max_edges = layout_service.store.max_edges();
+        loop {
+            let take = if max_edges > 0 { batch_size.min(max_edges - loaded_edges) } else { batch_size };
+            let mut received = 0usize;
+            let mut stream_error = None;
 
-        // Конвертируем в формат (source, target)
-        // Направление сохраняется как есть из Neo4j
-        let edge_tuples: Vec<(String, String)> = batch_edges
-            .into_iter()
-            .map(|e| (e.source_id, e.target_id))
-            .collect();
+            // Связи передаются парами (source, target) прямо из потока;
+            // направление сохраняется как есть из Neo4j
+            let batch = edge_stream.as_mut().take(take).map_while(|edge| match edge {
+                Ok(edge) => {
+                    received += 1;
+                    if let Some(ref mut aggregates) = vertex_aggregates {
+                        crate::neo4j::accumulate_vertex_aggregates(aggregates, [&edge]);
+                    }
+                    if let Some(ref mut spill) = edge_spill {
+                        if let Err(e) = spill.push(&edge.source_id, &edge.target_id, edge.weight) {
+                            stream_error = Some(e);
+                            return None;
+                        }
+                    }
+                    Some((edge.source_id, edge.target_id))
+                }
+                Err(e) => {
+                    stream_error = Some(e);
+                    None
+                }
+            });
+            loaded_edges += global_state.add_edges_from_stream(batch).await?;
+            if let Some(e) = stream_error {
+                return Err(e);
+            }
+            if received == 0 {
+                break;
+            }
+            config.limits.check_vertices(global_state.vertex_count())?;
 
-        info!("📊 Добавление {} связей в глобальное состояние", edge_tuples.len());
-        global_state.add_edges_batch(&edge_tuples)?;
+            // Обновляем слои после каждого батча; циклы разрываются разворотом связей,
+            // а следующая страница тем временем читается из Neo4j
+            info!("{}", Msg::LayerUpdate { edges: received });
+            let (updates, broken) = global_state.propagate_with_cycle_breaking(CycleBreakStrategy::Reverse)?;
+            if !broken.is_empty() {
+                tracing::warn!("{}", Msg::CycleEdgesReversed { count: broken.len() });
+                broken_edges += broken.len();
+            }
 
-        // Обновляем слои после каждого батча
-        info!("🔄 Обновление слоёв после добавления батча");
-        let updates = global_state.propagate_until_convergence()?;
+            batch_num += 1;
+            let progress = (loaded_edges.min(expected_edges) as f64 / expected_edges.max(1) as f64) * 100.0;
+            info!("{}", Msg::BatchProgress {
+                percent: progress,
+                loaded: loaded_edges,
+                total: expected_edges,
+                batches: batch_num,
+                updates,
+            });
 
-        let progress = ((batch_num + 1) as f64 / total_batches as f64) * 100.0;
-        info!("📊 Прогресс: {:.1}% ({}/{} батчей), {} обновлений слоёв",
-              progress, batch_num + 1, total_batches, updates);
+            // Периодически выводим статистику
+            if batch_num % 10 == 0 || received < take {
+                global_state.log_statistics();
+            }
 
-        // Периодически выводим статистику
-        if (batch_num + 1) % 10 == 0 || batch_num == total_batches - 1 {
-            global_state.log_statistics();
+            if max_edges > 0 && loaded_edges >= max_edges {
+                info!("{}", Msg::MaxEdgesReached { max_edges });
+                if total_edges > max_edges && vertex_aggregates.take().is_some() {
+                    tracing::warn!("{}", Msg::AggregatesSkippedMaxEdges);
+                }
+                break;
+            }
+            if received < take {
+                break;
+            }
         }
     }
 
-    info!("=== ФАЗА 1 ЗАВЕРШЕНА ===");
+    if let Some(ref graph) = cached_graph {
+        let uids = graph.interner().uids();
+        let edges = graph.edge_keys().map(|key| (uids[key.source].clone(), uids[key.target].clone()));
+        loaded_edges += global_state.add_edges_from(edges)?;
+        config.limits.check_vertices(global_state.vertex_count())?;
+        let (_, broken) = global_state.propagate_with_cycle_breaking(CycleBreakStrategy::Reverse)?;
+        broken_edges += broken.len();
+        if vertex_aggregates.is_some() {
+            tracing::warn!("{}", Msg::AggregatesSkippedGraphFile);
+            vertex_aggregates = None;
+        }
+        info!("{}", Msg::EdgesLoadedFromFile { count: loaded_edges });
+    }
+    let loaded_graph = match edge_spill {
+        Some(spill) => {
+            let builder = crate::data_structures::GraphBuilder::new()
+                .deterministic(true)
+                .allow_self_loops(true)
+                .allow_parallel_edges(true);
+            Some(spill.build(builder)?.0)
+        }
+        None => None,
+    };
+    if let (Some(path), Some(graph)) = (graph_file, &loaded_graph) {
+        // Происхождение графа: по нему следующий запуск проверяет, не устарел ли файл
+        let source = crate::data_structures::GraphSource {
+            source_edges: total_edges as u64,
+            loaded_at_ms: started_at.timestamp_millis(),
+            sample_rate: config.neo4j.sample_rate,
+            max_edges: config.neo4j.max_edges as u64,
+        };
+        graph.serialize_with_source(path, &source)?;
+        info!("{}", Msg::GraphFileSaved {
+            vertices: graph.vertex_count(),
+            edges: graph.multi_edge_count(),
+            path: &path.display(),
+        });
+    }
+    let routing_graph = match (cached_graph, loaded_graph) {
+        (Some(graph), _) | (None, Some(graph)) if config.output.edge_paths => Some(graph),
+        _ => None,
+    };
+
+    info!("{}", Msg::Phase1Done);
+    if broken_edges > 0 {
+        info!("{}", Msg::CycleEdgesReversedTotal { count: broken_edges });
+    }
     global_state.log_statistics();
 
     // Валидация слоёв
-    info!("🔍 Валидация назначенных слоёв...");
-    let invalid_edges = global_state.validate_layers();
+    info!("{}", Msg::ValidatingLayers);
+    let invalid_edges = global_state.validate_layers()?;
     if invalid_edges > 0 {
-        info!("⚠️ Обнаружено {} невалидных связей (возможно, циклы)", invalid_edges);
+        info!("{}", Msg::InvalidLayerEdges { count: invalid_edges });
+    }
+    let violations = global_state.constraint_violations()?;
+    if !violations.is_empty() {
+        tracing::warn!("{}", Msg::LayerConstraintsViolated { count: violations.len() });
+        for violation in violations.iter().take(10) {
+            tracing::warn!("{}", Msg::LayerConstraintViolation {
+                vertex: &violation.vertex,
+                layer: violation.pinned_layer,
+                predecessor: &violation.predecessor,
+                predecessor_layer: violation.predecessor_layer,
+            });
+        }
+    }
+
+    if config.algorithms.compress_layers {
+        info!("{}", Msg::CompressingLayers { threshold: config.algorithms.merge_layers_below });
+        global_state.compress_layers(config.algorithms.merge_layers_below)?;
     }
 
     // Фаза 2: Размещение вершин по координатам
-    info!("=== ФАЗА 2: РАЗМЕЩЕНИЕ ВЕРШИН ПО КООРДИНАТАМ ===");
-    let layer_map = global_state.get_layer_map();
+    info!("{}", Msg::Phase2Header);
+    let mut layer_map = global_state.get_layer_map();
+
+    // Проверка на вырожденность: слои пересчитываются по графу из накопленных связей
+    let mut layering_fallback = None;
+    if let Some(thresholds) = config.algorithms.degenerate_thresholds() {
+        use crate::algorithms::degenerate;
+
+        let distribution = degenerate::LayerDistribution::from_layers(layer_map.values().copied());
+        let depth = layer_map.values().max().map_or(0, |&layer| layer as usize + 1);
+        if let Some(reason) = degenerate::detect_degenerate(&distribution, depth, &thresholds) {
+            if global_state.has_layer_constraints() {
+                tracing::warn!("{}", Msg::DegenerateLayersPinned { reason: &reason });
+            } else {
+                tracing::warn!("{}", Msg::DegenerateLayers { reason: &reason });
+                let graph = global_state.build_graph()?;
+                let (relayered, fallback) = degenerate::relayer_degenerate(&graph, &layer_map, &distribution, reason);
+                match relayered {
+                    Some(relayered) => {
+                        layer_map = relayered;
+                        let layers = layer_map.values().max().map_or(0, |&layer| layer as usize + 1);
+                        info!("{}", Msg::DegenerateLayersReplaced { layers });
+                    }
+                    None => tracing::warn!("{}", Msg::DegenerateLayersKept),
+                }
+                layering_fallback = Some(serde_json::to_string(&fallback)?);
+            }
+        }
+    }
 
     let placement_config = PlacementConfig {
         block_width: config.algorithms.block_width,
         block_height: config.algorithms.block_height,
         horizontal_gap: config.algorithms.horizontal_gap,
         vertical_gap: config.algorithms.vertical_gap,
+        level_packing: config.algorithms.level_packing.into(),
+        tie_break: config.algorithms.tie_break.into(),
+        placement_mode: config.algorithms.placement_mode.into(),
+        vertex_sizes: load_vertex_sizes(config, &layout_service.store).await,
+        anchors: vertex_anchors(config),
+        ..PlacementConfig::default()
     };
 
-    info!("📍 Размещение {} вершин на основе глобальных слоёв", layer_map.len());
-    let positions = crate::algorithms::vertex_placement::place_all_vertices(
-        layer_map,
+    info!("{}", Msg::PlacingVertices { count: layer_map.len() });
+    let tie_break_keys = global_state.tie_break_keys(placement_config.tie_break);
+    let mut positions = crate::algorithms::vertex_placement::place_all_vertices_ordered(
+        &layer_map,
+        &std::collections::HashMap::new(),
+        &tie_break_keys,
         &placement_config,
     );
+    let anchored = crate::algorithms::vertex_placement::apply_vertex_anchors(&mut positions, &placement_config);
+    if anchored > 0 {
+        info!("{}", Msg::AnchoredVertices { count: anchored });
+    }
+    let edge_paths = match routing_graph {
+        Some(graph) => route_batch_edges(&positions, &graph, &placement_config)?,
+        None => Vec::new(),
+    };
 
     // Конвертируем в формат Neo4j
     let neo4j_positions: Vec<crate::neo4j::VertexPosition> = positions
@@ -335,32 +872,309 @@ async fn run_batch_layout(layout_service: &GraphLayoutServer, config: &Config) -
         })
         .collect();
 
-    info!("📊 Подготовлено {} позиций для сохранения", neo4j_positions.len());
+    info!("{}", Msg::PositionsPrepared { count: neo4j_positions.len() });
 
     // Фаза 3: Сохранение результатов
-    info!("=== ФАЗА 3: СОХРАНЕНИЕ РЕЗУЛЬТАТОВ В NEO4J ===");
-    layout_service.neo4j_client.save_layout_results_with_batch_size(
-        &neo4j_positions,
-        config.neo4j.save_batch_size
-    ).await?;
+    info!("{}", Msg::Phase3Header);
+    let layout_meta = crate::neo4j::LayoutMeta {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        algorithm_version: env!("CARGO_PKG_VERSION").to_string(),
+        block_width: placement_config.block_width,
+        block_height: placement_config.block_height,
+        horizontal_gap: placement_config.horizontal_gap,
+        vertical_gap: placement_config.vertical_gap,
+        origin_x: 0.0,
+        origin_y: 0.0,
+        level_packing: format!("{:?}", placement_config.level_packing),
+        vertex_count: neo4j_positions.len(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        scale_profile: config.algorithms.scale_profile.name().to_string(),
+    };
 
-    info!("✅ Результаты успешно сохранены в Neo4j");
-    info!("=== ВСЕ ФАЗЫ ЗАВЕРШЕНЫ УСПЕШНО ===");
+    let style_config = &config.output.style;
+    let style_hints = if style_config.enabled {
+        let years = match style_config.year_property {
+            Some(ref property) => layout_service.store.load_vertex_times(property).await.unwrap_or_else(|e| {
+                tracing::warn!("{}", Msg::LayerYearsNotLoaded { error: &e });
+                std::collections::HashMap::new()
+            }),
+            None => std::collections::HashMap::new(),
+        };
+        Some(crate::export::style_hints(&neo4j_positions, &layout_meta, &years, style_config))
+    } else {
+        None
+    };
+
+    let sinks = sinks::build_sinks(config, layout_service.store.clone())?;
+    let run = sinks::LayoutRun {
+        run_id: &layout_meta.run_id,
+        meta: &layout_meta,
+        positions: &neo4j_positions,
+        style: style_hints.as_ref(),
+        aggregates: vertex_aggregates.as_ref(),
+        edge_paths: &edge_paths,
+    };
+    let reports = sinks::write_all(&sinks, &run).await?;
+    layout_service.save_batch_artifacts(&neo4j_positions, &layout_meta);
+
+    if reports.len() == sinks.len() && reports.iter().all(|r| !r.partial) {
+        info!("{}", Msg::ResultsSaved { sinks: reports.len() });
+        if config.output.sinks.iter().any(|sink| matches!(sink, sinks::SinkConfig::Neo4j)) {
+            let stats = crate::neo4j::LayoutRunStats {
+                incremental: false,
+                edges_source: if from_neo4j { "neo4j" } else { "graph_file" }.to_string(),
+                edges: loaded_edges,
+                positions: neo4j_positions.len(),
+                layers: neo4j_positions.iter().map(|p| p.layer as usize + 1).max().unwrap_or(0),
+                cycle_edges: broken_edges,
+                layering_fallback,
+                ..Default::default()
+            };
+            save_layout_run(&layout_service.store, &layout_meta.run_id, stats, config, started_at).await?;
+        }
+    }
+    info!("{}", Msg::AllPhasesDone);
 
     Ok(())
 }
 
 
+/// Запись запуска укладки в узел `(:LayoutRun)` с длительностью от `started_at`
+async fn save_layout_run(
+    store: &crate::neo4j::Neo4jClient,
+    run_id: &str,
+    mut stats: crate::neo4j::LayoutRunStats,
+    config: &Config,
+    started_at: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    stats.duration_ms = (chrono::Utc::now() - started_at).num_milliseconds().max(0) as u64;
+    stats.algorithm_version = env!("CARGO_PKG_VERSION").to_string();
+    store.save_layout_run(run_id, &stats, &config.fingerprint()?, started_at).await
+}
+
+/// Закреплённые координаты вершин из конфигурации
+fn vertex_anchors(config: &Config) -> std::collections::HashMap<String, (f32, f32)> {
+    config
+        .algorithms
+        .vertex_anchors
+        .iter()
+        .map(|(vertex_id, &[x, y])| (vertex_id.clone(), (x, y)))
+        .collect()
+}
+
+/// Граф из файла `--graph-file`, если он ещё соответствует Neo4j (None — устарел)
+///
+/// Файл другой выборки (`sample_rate`, `max_edges`) не используется: укладка
+/// по нему незаметно отличалась бы от укладки по конфигурации. Файл устарел,
+/// если после его загрузки в Neo4j появились или изменились связи или (без
+/// случайной выборки) изменилось их число — так видны и удаления.
+async fn load_graph_file(
+    path: &std::path::Path,
+    config: &Config,
+    store: &crate::neo4j::Neo4jClient,
+) -> Result<Option<crate::data_structures::Graph>> {
+    info!("{}", Msg::GraphFileLoading { path: &path.display() });
+    let (graph, source) = crate::data_structures::Graph::deserialize_with_source(path)?;
+    if source.sample_rate != config.neo4j.sample_rate || source.max_edges != config.neo4j.max_edges as u64 {
+        return Err(anyhow::anyhow!(
+            "Граф в {} загружен с sample_rate={}, max_edges={}, а в конфигурации sample_rate={}, max_edges={}; \
+             перезапустите с --refresh-graph",
+            path.display(),
+            source.sample_rate,
+            source.max_edges,
+            config.neo4j.sample_rate,
+            config.neo4j.max_edges
+        ));
+    }
+
+    let loaded_at = (source.loaded_at_ms > 0)
+        .then(|| chrono::DateTime::from_timestamp_millis(source.loaded_at_ms))
+        .flatten();
+    let Some(loaded_at) = loaded_at else {
+        warn!("{}", Msg::GraphFileLoadTimeUnknown { path: &path.display() });
+        return Ok(None);
+    };
+    let changed = store.count_edges_since(loaded_at).await?;
+    if changed > 0 {
+        info!("{}", Msg::GraphFileEdgesChanged {
+            path: &path.display(),
+            changed,
+            since: &loaded_at.to_rfc3339(),
+        });
+        return Ok(None);
+    }
+    // Случайная выборка при каждом подсчёте даёт другое число связей
+    let random_sample = config.neo4j.sample_rate < 1.0 && !config.algorithms.deterministic;
+    if !random_sample {
+        let total = store.get_total_edges_count().await? as u64;
+        if total != source.source_edges {
+            info!("{}", Msg::GraphFileEdgeCountChanged {
+                path: &path.display(),
+                total,
+                expected: source.source_edges,
+            });
+            return Ok(None);
+        }
+    }
+    Ok(Some(graph))
+}
+
+/// Размеры блоков вершин: из свойств статей в Neo4j, поверх — явные из конфигурации
+async fn load_vertex_sizes(
+    config: &Config,
+    store: &crate::neo4j::Neo4jClient,
+) -> std::collections::HashMap<String, (f32, f32)> {
+    let algorithms = &config.algorithms;
+    let default_size = (algorithms.block_width, algorithms.block_height);
+    let mut sizes = store
+        .load_vertex_sizes(
+            algorithms.vertex_width_property.as_deref(),
+            algorithms.vertex_height_property.as_deref(),
+            default_size,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("{}", Msg::VertexSizesNotLoaded { error: &e });
+            std::collections::HashMap::new()
+        });
+    sizes.extend(
+        algorithms
+            .vertex_sizes
+            .iter()
+            .map(|(vertex_id, &[width, height])| (vertex_id.clone(), (width, height))),
+    );
+    sizes
+}
+
+/// Маршруты связей батчевой укладки в порядке (источник, цель)
+///
+/// Связи обходят блоки по столбцам слоёв; из параллельных связей пары
+/// маршрут строится один (`edge_index == 0`), как и сохраняется в Neo4j.
+fn route_batch_edges(
+    positions: &[crate::algorithms::vertex_placement::VertexPosition],
+    graph: &crate::data_structures::Graph,
+    placement_config: &crate::algorithms::vertex_placement::PlacementConfig,
+) -> Result<Vec<crate::neo4j::EdgePath>> {
+    use crate::algorithms::vertex_placement::{self, EdgeRoutingOptions, LayoutArena};
+
+    let options = EdgeRoutingOptions { avoid_vertices: true, ..EdgeRoutingOptions::default() };
+    let (paths, stats) =
+        vertex_placement::compute_edge_paths_in(&LayoutArena::new(), positions, graph, placement_config, &options)?;
+    let layers: std::collections::HashMap<&str, i32> =
+        positions.iter().map(|p| (p.vertex_id.as_str(), p.layer)).collect();
+    let report = vertex_placement::get_edge_statistics(&paths, |id| layers.get(id).copied());
+
+    let mut edge_paths: Vec<crate::neo4j::EdgePath> = paths
+        .into_iter()
+        .map(|((source_id, target_id), points)| {
+            let route = report.get(&source_id, &target_id);
+            crate::neo4j::EdgePath {
+                length: route.map_or(0.0, |r| r.length),
+                bends: route.map_or(0, |r| r.bends),
+                layer_span: route.map_or(0, |r| r.layer_span),
+                crossings: 0,
+                bundle_id: None,
+                control_points: Vec::new(),
+                source_port: 0.0,
+                target_port: 0.0,
+                edge_index: 0,
+                source_id,
+                target_id,
+                points,
+            }
+        })
+        .collect();
+    edge_paths.sort_by(|a, b| (&a.source_id, &a.target_id).cmp(&(&b.source_id, &b.target_id)));
+    info!("{}", Msg::EdgePathsBuilt { count: edge_paths.len(), detoured: stats.edges_detoured });
+    Ok(edge_paths)
+}
+
+/// Укладка по временным окнам с сохранением кадров в JSON
+async fn run_time_slices(config: Config) -> Result<()> {
+    use crate::algorithms::time_slicing::{layout_time_slices, TimeSliceOptions};
+    use crate::algorithms::vertex_placement::{
+        EdgeRoutingOptions, OptimalVertexPlacer, OptimizationOptions, PlacementConfig,
+    };
+
+    let neo4j_client = crate::neo4j::Neo4jClient::new(&config).await?;
+    let slices_config = &config.time_slices;
+
+    let edges = neo4j_client.load_graph_edges().await?;
+    config.limits.check_edges(edges.len())?;
+    let vertex_times = neo4j_client.load_vertex_times(&slices_config.time_property).await?;
+
+    let placement_config = PlacementConfig {
+        block_width: config.algorithms.block_width,
+        block_height: config.algorithms.block_height,
+        horizontal_gap: config.algorithms.horizontal_gap,
+        vertical_gap: config.algorithms.vertical_gap,
+        level_packing: config.algorithms.level_packing.into(),
+        tie_break: config.algorithms.tie_break.into(),
+        placement_mode: config.algorithms.placement_mode.into(),
+        layering: config.algorithms.layering.into(),
+        max_layer_width: config.algorithms.max_layer_width,
+        layer_constraints: config.algorithms.layer_constraints.clone(),
+        layer_lower_bounds: config.algorithms.layer_lower_bounds.clone(),
+        dummy_vertices: config.algorithms.dummy_vertices,
+        max_dummy_vertices: config.algorithms.max_dummy_vertices,
+        vertex_sizes: load_vertex_sizes(&config, &neo4j_client).await,
+        anchors: vertex_anchors(&config),
+    };
+    let placer = OptimalVertexPlacer::with_config(
+        placement_config,
+        OptimizationOptions::default(),
+        EdgeRoutingOptions::default(),
+    );
+
+    let options = TimeSliceOptions {
+        window_size: slices_config.window_years,
+        cumulative: slices_config.cumulative,
+    };
+    let slices = layout_time_slices(&edges, &vertex_times, &options, &placer).await?;
+
+    crate::blocking_io::run_blocking(|| -> Result<()> {
+        let file = std::fs::File::create(&slices_config.output_path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), &slices)?;
+        Ok(())
+    })?;
+    info!("{}", Msg::TimeSlicesSaved { count: slices.len(), path: &slices_config.output_path });
+
+    Ok(())
+}
+
 /// Запуск gRPC сервера
 async fn run_server(address: String, config: Config) -> Result<()> {
     let addr: SocketAddr = address.parse()
         .map_err(|e| anyhow::anyhow!("Неверный адрес {}: {}", address, e))?;
     
-    info!("🚀 Запуск gRPC сервера на {}", addr);
+    info!("{}", Msg::ServerListening { address: &addr });
     
     // Создание сервиса укладки графов
+    let health_interval = std::time::Duration::from_secs(config.server.health_check_interval.max(1));
     let layout_service = GraphLayoutServer::new(config).await?;
     
+    // grpc.health.v1: статус обновляется по той же логике, что и GetHealth
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    let health_source = layout_service.clone();
+    tokio::spawn(async move {
+        type LayoutService = generated::graph_layout_service_server::GraphLayoutServiceServer<GraphLayoutServer>;
+        let mut was_serving = None;
+        loop {
+            let (status, message, _) = health_source.evaluate_health().await;
+            let serving = status == generated::health_response::ServingStatus::Serving;
+            if serving {
+                health_reporter.set_serving::<LayoutService>().await;
+            } else {
+                health_reporter.set_not_serving::<LayoutService>().await;
+            }
+            if was_serving != Some(serving) {
+                info!("{}", Msg::HealthStatus { message: &message });
+                was_serving = Some(serving);
+            }
+            tokio::time::sleep(health_interval).await;
+        }
+    });
+    
     // Добавление middleware для метрик и логирования
     let service = tower::ServiceBuilder::new()
         // .layer(tower_http::trace::TraceLayer::new_for_grpc()) // Упрощено для совместимости
@@ -368,6 +1182,7 @@ async fn run_server(address: String, config: Config) -> Result<()> {
     
     // Запуск сервера
     Server::builder()
+        .add_service(health_service)
         .add_service(service)
         .serve(addr)
         .await
@@ -378,7 +1193,7 @@ async fn run_server(address: String, config: Config) -> Result<()> {
 
 /// Проверка здоровья сервиса
 async fn run_health_check() -> Result<()> {
-    info!("🏥 Выполнение проверки здоровья...");
+    info!("{}", Msg::HealthCheckRunning);
     
     // Проверка подключения к Neo4j
     // Проверка доступности памяти
@@ -389,11 +1204,16 @@ async fn run_health_check() -> Result<()> {
 }
 
 /// Запуск бенчмарков
-async fn run_benchmarks() -> Result<()> {
-    info!("📊 Запуск бенчмарков производительности...");
+async fn run_benchmarks(sizes: &[usize], avg_out_degree: usize, max_layer_width: usize) -> Result<()> {
+    info!("{}", Msg::BenchRunning);
+    
+    // Сравнение стратегий назначения слоёв (BFS, Coffman–Graham, network simplex)
+    bench::run_layering_bench(sizes, avg_out_degree, max_layer_width).await?;
+    
+    // Хранение связей: списки смежности против CSR
+    bench::run_graph_storage_bench(sizes, avg_out_degree)?;
     
     // Бенчмарк топологической сортировки
-    // Бенчмарк longest path алгоритма
     // Бенчмарк размещения вершин
     // Сравнение с Python реализацией
     
@@ -403,7 +1223,7 @@ async fn run_benchmarks() -> Result<()> {
 
 /// Запуск тестов
 async fn run_tests() -> Result<()> {
-    info!("🧪 Запуск тестов корректности укладки графа...");
+    info!("{}", Msg::LayoutTestsRunning);
     println!();
 
     // Путь к тестовому GML файлу
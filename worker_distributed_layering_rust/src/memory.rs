@@ -104,6 +104,10 @@ impl MemoryManager {
     
     /// Выделение памяти
     pub fn allocate(&self, size: usize) -> Result<()> {
+        crate::failure_injection::check(
+            crate::failure_injection::FaultPoint::Allocation,
+            &format!("{} байт", size),
+        )?;
         if !self.can_allocate(size) {
             return Err(anyhow::anyhow!(
                 "Недостаточно памяти для выделения {} байт. Текущее использование: {}, лимит: {}",
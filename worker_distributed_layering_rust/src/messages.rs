@@ -0,0 +1,1231 @@
+/*!
+# Каталог сообщений для логов и ошибок
+
+Сообщения, которые читают операторы и на которые настроены алерты, собраны
+в `Msg`: все логи процесса и режимов (`main.rs`: пакетная и инкрементальная
+укладка, файл графа, проверка здоровья), gRPC-сервиса (`server.rs`: причины
+NOT_SERVING и тексты ошибок `Status`), шагов укладки запроса
+(`HighPerformanceLayoutEngine::compute_layout`), итогов размещения вершин и
+сохранения укладки в Neo4j, а также ошибки валидации входа.
+Язык выбирается в конфигурации (`server.language = "ru" | "en"`) и задаётся
+один раз при старте через `set_language`; `Msg` форматируется через `Display`
+на текущем языке:
+
+```ignore
+info!("{}", Msg::Phase1Done);
+return Err(anyhow::anyhow!("{}", Msg::EmptyGraph));
+```
+
+Эмодзи-префиксы одинаковы в обоих языках, поэтому правила, которые ищут их,
+работают при любом выборе. Подробная диагностика внутри модулей (загрузка
+связей и подключение клиента Neo4j, оптимизатор БД, промежуточные шаги
+`vertex_placement`) в каталог не входит и пишется на языке своего модуля.
+*/
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Язык сообщений
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Ru,
+    En,
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Language::Ru
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Установка языка сообщений для всего процесса
+pub fn set_language(language: Language) {
+    let value = match language {
+        Language::Ru => 0,
+        Language::En => 1,
+    };
+    LANGUAGE.store(value, Ordering::Relaxed);
+}
+
+/// Текущий язык сообщений
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::En,
+        _ => Language::Ru,
+    }
+}
+
+/// Сообщение каталога
+#[derive(Clone, Copy)]
+pub enum Msg<'a> {
+    Startup { version: &'a str },
+    ConfigLoaded { path: &'a str },
+    ConfigLoadFailed { error: &'a dyn fmt::Display },
+    ServerModeStart,
+    AutoLayoutModeStart,
+    TimeSlicesModeStart,
+    RollbackModeStart,
+    HealthModeStart,
+    GrpcBenchModeStart,
+    BenchModeStart,
+    TestModeStart,
+    Finished,
+    ServerListening { address: &'a dyn fmt::Display },
+    BatchLayoutHeader,
+    LoadingGraph,
+    TotalEdges { count: usize },
+    Phase1Header,
+    BatchProgress { percent: f64, loaded: usize, total: usize, batches: usize, updates: usize },
+    Phase1Done,
+    Phase2Header,
+    Phase3Header,
+    ResultsSaved { sinks: usize },
+    AllPhasesDone,
+    BatchLayoutFailed { error: &'a dyn fmt::Display },
+    EmptyGraph,
+    NoValidEdges,
+    EdgeValidationSummary { seen: usize, valid: usize, invalid_ids: usize, self_loops: usize, non_finite: usize },
+    FirstEdges { count: usize },
+    FirstInvalidEdges { count: usize },
+
+    // Режимы и подготовка автоукладки (main.rs)
+    DryRun,
+    ModeSelected { mode: &'a dyn fmt::Debug },
+    StagingCleared { articles: usize },
+    AutoLayoutStart,
+    CreatingServer,
+    ServerCreated,
+    ServerCreateFailed { error: &'a dyn fmt::Display },
+    PreparingDatabase,
+    DatabasePrepared,
+    DatabasePrepareFailed { error: &'a dyn fmt::Display },
+    IncrementalLayoutFailed { error: &'a dyn fmt::Display },
+    BatchLayoutStart,
+    BatchLayoutDone,
+
+    // Инкрементальная укладка (main.rs)
+    NoPreviousLayout,
+    IncrementalSince { since: &'a str },
+    NoChangedEdges,
+    ShiftCascadeTooLarge { depth: usize, vertices: usize },
+    CycleEdgesRejected { count: usize },
+    PositionsChanged { count: usize },
+
+    // Пакетная укладка (main.rs)
+    StreamingLoad { batch_size: usize, page_size: usize, adaptive: bool },
+    DiskSpill { dir: &'a str },
+    LayerConstraints { count: usize },
+    LayerLowerBounds { count: usize },
+    AggregatesSkippedSampled { sample_rate: f64 },
+    LayerUpdate { edges: usize },
+    CycleEdgesReversed { count: usize },
+    MaxEdgesReached { max_edges: usize },
+    AggregatesSkippedMaxEdges,
+    AggregatesSkippedGraphFile,
+    EdgesLoadedFromFile { count: usize },
+    GraphFileSaved { vertices: usize, edges: usize, path: &'a dyn fmt::Display },
+    CycleEdgesReversedTotal { count: usize },
+    ValidatingLayers,
+    InvalidLayerEdges { count: usize },
+    LayerConstraintsViolated { count: usize },
+    LayerConstraintViolation { vertex: &'a str, layer: i32, predecessor: &'a str, predecessor_layer: i32 },
+    CompressingLayers { threshold: usize },
+    DegenerateLayers { reason: &'a dyn fmt::Display },
+    DegenerateLayersPinned { reason: &'a dyn fmt::Display },
+    DegenerateLayersReplaced { layers: usize },
+    DegenerateLayersKept,
+    PlacingVertices { count: usize },
+    AnchoredVertices { count: usize },
+    PositionsPrepared { count: usize },
+    LayerYearsNotLoaded { error: &'a dyn fmt::Display },
+    VertexSizesNotLoaded { error: &'a dyn fmt::Display },
+    EdgePathsBuilt { count: usize, detoured: usize },
+
+    // Файл графа (main.rs)
+    GraphFileLoading { path: &'a dyn fmt::Display },
+    GraphFileLoadTimeUnknown { path: &'a dyn fmt::Display },
+    GraphFileEdgesChanged { path: &'a dyn fmt::Display, changed: usize, since: &'a str },
+    GraphFileEdgeCountChanged { path: &'a dyn fmt::Display, total: u64, expected: u64 },
+
+    // Остальные режимы (main.rs)
+    TimeSlicesSaved { count: usize, path: &'a str },
+    HealthStatus { message: &'a str },
+    HealthCheckRunning,
+    BenchRunning,
+    LayoutTestsRunning,
+
+    // gRPC сервис (server.rs)
+    HeavyRunDirUnavailable { dir: &'a dyn fmt::Display, error: &'a dyn fmt::Display },
+    HeavyRunMarkerNotWritten { path: &'a dyn fmt::Display, error: &'a dyn fmt::Display },
+    ServerInitializing,
+    ArtifactsDir { path: &'a dyn fmt::Display },
+    ServerInitialized { id: &'a str },
+    HeavyRun { id: &'a str, reason: &'a str },
+    LoadingEdgesFromStore,
+    EdgesLoaded { count: usize, seconds: f64 },
+    SavingPositions { count: usize },
+    PositionsSaved { seconds: f64 },
+    PartialSave { saved: usize, total: usize },
+    ArtifactsNotSaved { job_id: &'a str, error: &'a dyn fmt::Display },
+    LayoutRequestReceived { task_id: &'a str, tenant: &'a str },
+    SavingPositionsToNeo4j { count: usize },
+    LayoutDone { seconds: f64, task_id: &'a str },
+    LayoutFailed { error: &'a dyn fmt::Display, task_id: &'a str, seconds: f64 },
+    StreamingRequestReceived { task_id: &'a str, tenant: &'a str },
+    StreamingDone { seconds: f64, chunks: usize, task_id: &'a str },
+    StreamingFailed { error: &'a dyn fmt::Display, task_id: &'a str, seconds: f64 },
+    NeighborhoodRequestReceived { center: &'a str, radius: i32, task_id: &'a str, tenant: &'a str },
+    NeighborhoodDone { seconds: f64, vertices: usize, task_id: &'a str },
+    NeighborhoodFailed { error: &'a dyn fmt::Display, task_id: &'a str, seconds: f64 },
+    ArtifactDownload { job_id: &'a str, name: &'a str, bytes: i64 },
+    // Проверка здоровья (server.rs)
+    StoreUnavailable,
+    MemoryBusy { percent: f32 },
+    CpuBusy { percent: f32 },
+    HeavyRunActive { run: &'a str },
+    ServiceHealthy,
+    HealthProblems { problems: &'a str },
+
+    // Ошибки запросов gRPC сервиса (server.rs)
+    TenantTokenMissing,
+    TenantTokenForbidden,
+    ArtifactsDisabled,
+    ArtifactNotFound { job_id: &'a str, name: &'a str },
+    ClientDisconnectedAtChunk { chunk: i32, total: i32 },
+    ClientDisconnectedBeforeSummary,
+    CenterIdEmpty,
+    RadiusOutOfRange { max: u32 },
+    ArticleNotFound { id: &'a str },
+
+    // Шаги укладки запроса (algorithms/mod.rs)
+    IngestStatsHeader,
+    IngestEdgesSeen { count: usize },
+    IngestInvalidIds { count: usize },
+    IngestSelfLoops { count: usize },
+    IngestDuplicates { count: usize },
+    IngestMultigraph { self_loops: usize, parallel: usize },
+    IngestDropped { count: usize },
+    IngestEdgesAdded { count: usize },
+    GraphMapped { megabytes: usize },
+    SeedVerticesNotFound { seeds: &'a dyn fmt::Debug },
+    LayoutInitHeader,
+    InputEdges { count: usize },
+    ValidatingInput,
+    InputValid,
+    SeedNeighborhood { seeds: usize, depth: u32, kept: usize, total: usize },
+    ClusteredVertices { count: usize },
+    VerticesWithYear { count: usize },
+    BreakingCycles,
+    CyclesBroken { components: usize, edges: usize, strategy: &'a dyn fmt::Debug },
+    NoCycles,
+    ReducingTransitively,
+    TransitiveEdgesRemoved { count: usize, exact: bool },
+    GraphBuildHeader,
+    BuildingGraph,
+    GraphBuilt { vertices: usize, edges: usize, components: usize },
+    DegreeStats {
+        out_degree: [usize; 4],
+        in_degree: [usize; 4],
+        sources: usize,
+        sinks: usize,
+        largest_component: usize,
+        diameter: usize,
+    },
+    TopoSortHeader,
+    TopoSorting,
+    TopoSortDone { ms: u64 },
+    VerticesOrdered { count: usize },
+    LongestPathHeader,
+    FindingLongestPath,
+    LongestPathFound { ms: u64 },
+    LongestPathLength { vertices: usize },
+    PlacementHeader,
+    PlacingWithOptimization,
+    PackingComponents { count: usize, parallel: bool, gap: f32 },
+    DegenerateLayoutRetry { reason: &'a dyn fmt::Display, strategy: &'a dyn fmt::Debug, width: usize },
+    LayeringFallbackAccepted { strategy: &'a dyn fmt::Debug, from_percent: f32, to_percent: f32 },
+    LayeringFallbackRejected,
+    PlacementDone { ms: u64 },
+    VerticesPlacedTotal { count: usize },
+    DeepestComponent { representative: &'a str, vertices: usize, path: usize, layers: i32 },
+    FinalizationHeader,
+    BuildingStatistics,
+    EdgeCrossings { count: usize },
+    SpatialIndexBuilt { cells: usize, cell_size: f32 },
+    LayoutCompleteHeader,
+    LayoutTotalTime { ms: u64 },
+    VerticesProcessed { count: usize },
+    EdgesProcessed { count: usize },
+    LayoutThroughput { per_second: f32 },
+    AllStepsDone,
+
+    // Размещение вершин (vertex_placement/placement.rs)
+    VerticesPlaced { vertices: usize, layers: i32 },
+    CentroidReordered { changed: usize, layers: usize },
+    AnchorsOverlap { vertex: &'a str, layer: i32, level: i32 },
+
+    // Сохранение укладки (neo4j.rs)
+    SavingLayout { rows: usize, aggregates: bool },
+    NoPositionsToSave,
+    SaveBatchesPlanned { batches: usize, batch_size: usize },
+    SaveBatchJoinFailed { batch: usize, error: &'a dyn fmt::Display },
+    SaveBatchQuarantined { batch: usize, first_row: usize, last_row: usize, error: &'a str },
+    SaveBatchFinished { batch: usize, total: usize, first_row: usize, last_row: usize },
+    SaveProgress { percent: f64, saved: usize, total: usize, rate: f64 },
+    AllSaveBatchesFailed { batches: usize, error: &'a str },
+    SaveQuarantined { saved: usize, total: usize, batches: usize, path: &'a dyn fmt::Display },
+    LayoutWritten { seconds: f64, rate: f64 },
+    LayoutSwitched { articles: usize, seconds: f64 },
+    PostProcessingStart,
+    PostProcessingRunning,
+    PostProcessingDone { seconds: f32, removed: usize },
+    ScanningIsolated,
+    NoIsolatedVertices,
+    IsolatedFound { count: usize },
+    IsolatedCleared { removed: usize, total: usize, found: usize },
+}
+
+impl fmt::Display for Msg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let en = language() == Language::En;
+        match *self {
+            Msg::Startup { version } if en => write!(f, "🦀 Starting Graph Layout Engine v{}", version),
+            Msg::Startup { version } => write!(f, "🦀 Запуск Graph Layout Engine v{}", version),
+            Msg::ConfigLoaded { path } if en => write!(f, "📋 Configuration loaded from {}", path),
+            Msg::ConfigLoaded { path } => write!(f, "📋 Конфигурация загружена из {}", path),
+            Msg::ConfigLoadFailed { error } if en => write!(f, "❌ Failed to load configuration: {}", error),
+            Msg::ConfigLoadFailed { error } => write!(f, "❌ Ошибка загрузки конфигурации: {}", error),
+            Msg::ServerModeStart if en => write!(f, "🚀 Starting in gRPC server mode"),
+            Msg::ServerModeStart => write!(f, "🚀 Запуск в режиме gRPC сервера"),
+            Msg::AutoLayoutModeStart if en => write!(f, "🧮 Starting in automatic layout mode"),
+            Msg::AutoLayoutModeStart => write!(f, "🧮 Запуск в режиме автоматической укладки"),
+            Msg::TimeSlicesModeStart if en => write!(f, "🕰️ Starting time-slice layout"),
+            Msg::TimeSlicesModeStart => write!(f, "🕰️ Запуск укладки по временным окнам"),
+            Msg::RollbackModeStart if en => write!(f, "↩️ Rolling back layout to the previous atomic save"),
+            Msg::RollbackModeStart => write!(f, "↩️ Откат укладки к предыдущему атомарному сохранению"),
+            Msg::HealthModeStart if en => write!(f, "🏥 Starting health check"),
+            Msg::HealthModeStart => write!(f, "🏥 Запуск проверки здоровья"),
+            Msg::GrpcBenchModeStart if en => write!(f, "📊 Starting gRPC server load test"),
+            Msg::GrpcBenchModeStart => write!(f, "📊 Запуск нагрузочного теста gRPC сервера"),
+            Msg::BenchModeStart if en => write!(f, "📊 Starting benchmarks"),
+            Msg::BenchModeStart => write!(f, "📊 Запуск бенчмарков"),
+            Msg::TestModeStart if en => write!(f, "🧪 Starting layout tests"),
+            Msg::TestModeStart => write!(f, "🧪 Запуск тестов укладки"),
+            Msg::Finished if en => write!(f, "✅ Program finished successfully"),
+            Msg::Finished => write!(f, "✅ Программа завершена успешно"),
+            Msg::ServerListening { address } if en => write!(f, "🚀 Starting gRPC server on {}", address),
+            Msg::ServerListening { address } => write!(f, "🚀 Запуск gRPC сервера на {}", address),
+            Msg::BatchLayoutHeader if en => write!(f, "=== BATCH PROCESSING WITH GLOBAL LAYER ASSIGNMENT ==="),
+            Msg::BatchLayoutHeader => write!(f, "=== БАТЧЕВАЯ ОБРАБОТКА С ГЛОБАЛЬНЫМ НАЗНАЧЕНИЕМ СЛОЁВ ==="),
+            Msg::LoadingGraph if en => write!(f, "📊 Loading graph data from Neo4j..."),
+            Msg::LoadingGraph => write!(f, "📊 Загрузка данных графа из Neo4j..."),
+            Msg::TotalEdges { count } if en => write!(f, "📈 Total edges in the database: {}", count),
+            Msg::TotalEdges { count } => write!(f, "📈 Всего связей в БД: {}", count),
+            Msg::Phase1Header if en => write!(f, "=== PHASE 1: GLOBAL LAYER ASSIGNMENT ==="),
+            Msg::Phase1Header => write!(f, "=== ФАЗА 1: ГЛОБАЛЬНОЕ НАЗНАЧЕНИЕ СЛОЁВ ==="),
+            Msg::BatchProgress { percent, loaded, total, batches, updates } if en => write!(
+                f,
+                "📊 Progress: {:.1}% ({}/{} edges, {} batches), {} layer updates",
+                percent, loaded, total, batches, updates
+            ),
+            Msg::BatchProgress { percent, loaded, total, batches, updates } => write!(
+                f,
+                "📊 Прогресс: {:.1}% ({}/{} связей, {} батчей), {} обновлений слоёв",
+                percent, loaded, total, batches, updates
+            ),
+            Msg::Phase1Done if en => write!(f, "=== PHASE 1 COMPLETE ==="),
+            Msg::Phase1Done => write!(f, "=== ФАЗА 1 ЗАВЕРШЕНА ==="),
+            Msg::Phase2Header if en => write!(f, "=== PHASE 2: VERTEX COORDINATE PLACEMENT ==="),
+            Msg::Phase2Header => write!(f, "=== ФАЗА 2: РАЗМЕЩЕНИЕ ВЕРШИН ПО КООРДИНАТАМ ==="),
+            Msg::Phase3Header if en => write!(f, "=== PHASE 3: SAVING RESULTS ==="),
+            Msg::Phase3Header => write!(f, "=== ФАЗА 3: СОХРАНЕНИЕ РЕЗУЛЬТАТОВ ==="),
+            Msg::ResultsSaved { sinks } if en => write!(f, "✅ Results saved to all sinks ({})", sinks),
+            Msg::ResultsSaved { sinks } => write!(f, "✅ Результаты сохранены во все приёмники ({})", sinks),
+            Msg::AllPhasesDone if en => write!(f, "=== ALL PHASES COMPLETED SUCCESSFULLY ==="),
+            Msg::AllPhasesDone => write!(f, "=== ВСЕ ФАЗЫ ЗАВЕРШЕНЫ УСПЕШНО ==="),
+            Msg::BatchLayoutFailed { error } if en => write!(f, "❌ Batch processing failed: {}", error),
+            Msg::BatchLayoutFailed { error } => write!(f, "❌ Ошибка батчевой обработки: {}", error),
+            Msg::EmptyGraph if en => write!(f, "The graph must not be empty"),
+            Msg::EmptyGraph => write!(f, "Граф не может быть пустым"),
+            Msg::NoValidEdges if en => write!(f, "No valid edges left after filtering"),
+            Msg::NoValidEdges => write!(f, "Нет валидных связей после фильтрации"),
+            Msg::EdgeValidationSummary { seen, valid, invalid_ids, self_loops, non_finite } if en => write!(
+                f,
+                "📝 Edges: {}, valid: {}, empty ID: {}, self-loops: {}, non-finite weight: {}",
+                seen, valid, invalid_ids, self_loops, non_finite
+            ),
+            Msg::EdgeValidationSummary { seen, valid, invalid_ids, self_loops, non_finite } => write!(
+                f,
+                "📝 Связей: {}, валидных: {}, с пустым ID: {}, self-loops: {}, с нечисловым весом: {}",
+                seen, valid, invalid_ids, self_loops, non_finite
+            ),
+            Msg::FirstEdges { count } if en => write!(f, "📝 First {} edges:", count),
+            Msg::FirstEdges { count } => write!(f, "📝 Первые {} связей:", count),
+            Msg::FirstInvalidEdges { count } if en => write!(f, "⚠️ First {} invalid edges:", count),
+            Msg::FirstInvalidEdges { count } => write!(f, "⚠️ Первые {} невалидных связей:", count),
+            Msg::DryRun if en => write!(f, "🧪 Dry run: coordinates are not written to Neo4j"),
+            Msg::DryRun => write!(f, "🧪 Пробный запуск: координаты в Neo4j не записываются"),
+            Msg::ModeSelected { mode } if en => write!(f, "🎯 Mode: {:?}", mode),
+            Msg::ModeSelected { mode } => write!(f, "🎯 Режим работы: {:?}", mode),
+            Msg::StagingCleared { articles } if en => write!(
+                f,
+                "⚠️ Removed staging properties of an interrupted save from {} articles",
+                articles
+            ),
+            Msg::StagingCleared { articles } => write!(
+                f,
+                "⚠️ Удалены временные свойства прерванного сохранения у {} статей",
+                articles
+            ),
+            Msg::AutoLayoutStart if en => write!(f, "🔄 Starting automatic graph layout..."),
+            Msg::AutoLayoutStart => write!(f, "🔄 Запуск автоматической укладки графа..."),
+            Msg::CreatingServer if en => write!(f, "🔧 Creating GraphLayoutServer..."),
+            Msg::CreatingServer => write!(f, "🔧 Создание GraphLayoutServer..."),
+            Msg::ServerCreated if en => write!(f, "✅ GraphLayoutServer created"),
+            Msg::ServerCreated => write!(f, "✅ GraphLayoutServer создан успешно"),
+            Msg::ServerCreateFailed { error } if en => write!(f, "❌ Failed to create GraphLayoutServer: {}", error),
+            Msg::ServerCreateFailed { error } => write!(f, "❌ Ошибка создания GraphLayoutServer: {}", error),
+            Msg::PreparingDatabase if en => write!(f, "🔧 Preparing the database..."),
+            Msg::PreparingDatabase => write!(f, "🔧 Подготовка базы данных..."),
+            Msg::DatabasePrepared if en => write!(f, "✅ Database prepared"),
+            Msg::DatabasePrepared => write!(f, "✅ База данных подготовлена"),
+            Msg::DatabasePrepareFailed { error } if en => write!(
+                f,
+                "⚠️ Database preparation failed: {}. Continuing without optimizations.",
+                error
+            ),
+            Msg::DatabasePrepareFailed { error } => write!(
+                f,
+                "⚠️ Ошибка подготовки базы данных: {}. Продолжаем без оптимизаций.",
+                error
+            ),
+            Msg::IncrementalLayoutFailed { error } if en => write!(f, "❌ Incremental layout failed: {}", error),
+            Msg::IncrementalLayoutFailed { error } => write!(
+                f,
+                "❌ Инкрементальная укладка завершилась с ошибкой: {}",
+                error
+            ),
+            Msg::BatchLayoutStart if en => write!(f, "🧮 Starting batch processing..."),
+            Msg::BatchLayoutStart => write!(f, "🧮 Начинаем батчевую обработку..."),
+            Msg::BatchLayoutDone if en => write!(f, "✅ Batch processing completed successfully"),
+            Msg::BatchLayoutDone => write!(f, "✅ Батчевая обработка завершена успешно"),
+            Msg::NoPreviousLayout if en => write!(f, "🆕 No previous layout found, running a full layout"),
+            Msg::NoPreviousLayout => write!(f, "🆕 Предыдущая укладка не найдена, выполняется полная"),
+            Msg::IncrementalSince { since } if en => write!(f, "🔁 Incremental layout of changes since {}", since),
+            Msg::IncrementalSince { since } => write!(f, "🔁 Инкрементальная укладка изменений после {}", since),
+            Msg::NoChangedEdges if en => write!(f, "✅ No edges changed, no layout needed"),
+            Msg::NoChangedEdges => write!(f, "✅ Связи не менялись, укладка не требуется"),
+            Msg::ShiftCascadeTooLarge { depth, vertices } if en => write!(
+                f,
+                "⚠️ Shift cascade is too large (depth {}, {} vertices shifted), running a full layout",
+                depth, vertices
+            ),
+            Msg::ShiftCascadeTooLarge { depth, vertices } => write!(
+                f,
+                "⚠️ Каскад сдвигов слишком велик (глубина {}, сдвинуто {} вершин), выполняется полная укладка",
+                depth, vertices
+            ),
+            Msg::CycleEdgesRejected { count } if en => write!(
+                f,
+                "⚠️ Rejected {} edges closing a cycle; a full layout is needed to include them",
+                count
+            ),
+            Msg::CycleEdgesRejected { count } => write!(
+                f,
+                "⚠️ Отклонено {} связей, замыкающих цикл; для их учёта нужна полная укладка",
+                count
+            ),
+            Msg::PositionsChanged { count } if en => write!(f, "📊 {} positions changed", count),
+            Msg::PositionsChanged { count } => write!(f, "📊 Изменено {} позиций", count),
+            Msg::StreamingLoad { batch_size, page_size, adaptive } if en => write!(
+                f,
+                "🔄 Streaming edge load: layers updated every {} edges, initial page size {} (adaptive: {})",
+                batch_size, page_size, adaptive
+            ),
+            Msg::StreamingLoad { batch_size, page_size, adaptive } => write!(
+                f,
+                "🔄 Потоковая загрузка связей: обновление слоёв каждые {} связей, начальный размер страницы {} (адаптивный: {})",
+                batch_size, page_size, adaptive
+            ),
+            Msg::DiskSpill { dir } if en => write!(f, "💾 Streaming strategy: edges are kept on disk in {}", dir),
+            Msg::DiskSpill { dir } => write!(f, "💾 Стратегия Streaming: связи хранятся на диске в {}", dir),
+            Msg::LayerConstraints { count } if en => write!(f, "📌 Pinning {} vertices to layers", count),
+            Msg::LayerConstraints { count } => write!(f, "📌 Закрепление {} вершин за слоями", count),
+            Msg::LayerLowerBounds { count } if en => write!(
+                f,
+                "🧱 Known layers of {} vertices are used as lower bounds",
+                count
+            ),
+            Msg::LayerLowerBounds { count } => write!(
+                f,
+                "🧱 Известные слои для {} вершин используются как нижние границы",
+                count
+            ),
+            Msg::AggregatesSkippedSampled { sample_rate } if en => write!(
+                f,
+                "⚠️ Vertex aggregates are not saved: edges are sampled (sample_rate={})",
+                sample_rate
+            ),
+            Msg::AggregatesSkippedSampled { sample_rate } => write!(
+                f,
+                "⚠️ Агрегаты вершин не сохраняются: связи загружаются выборкой (sample_rate={})",
+                sample_rate
+            ),
+            Msg::LayerUpdate { edges } if en => write!(f, "🔄 Updating layers after adding {} edges", edges),
+            Msg::LayerUpdate { edges } => write!(f, "🔄 Обновление слоёв после добавления {} связей", edges),
+            Msg::CycleEdgesReversed { count } if en => write!(f, "✂️ Reversed {} edges closing cycles", count),
+            Msg::CycleEdgesReversed { count } => write!(f, "✂️ Развёрнуто {} связей, замыкавших циклы", count),
+            Msg::MaxEdgesReached { max_edges } if en => write!(f, "🎲 Reached max_edges={}, loading stopped", max_edges),
+            Msg::MaxEdgesReached { max_edges } => write!(
+                f,
+                "🎲 Достигнут лимит max_edges={}, загрузка остановлена",
+                max_edges
+            ),
+            Msg::AggregatesSkippedMaxEdges if en => write!(
+                f,
+                "⚠️ Vertex aggregates are not saved: not all edges were loaded (max_edges limit)",
+                
+            ),
+            Msg::AggregatesSkippedMaxEdges => write!(
+                f,
+                "⚠️ Агрегаты вершин не сохраняются: загружены не все связи (лимит max_edges)",
+                
+            ),
+            Msg::AggregatesSkippedGraphFile if en => write!(
+                f,
+                "⚠️ Vertex aggregates are not saved: the graph file has no clusters and years",
+                
+            ),
+            Msg::AggregatesSkippedGraphFile => write!(
+                f,
+                "⚠️ Агрегаты вершин не сохраняются: в файле графа нет кластеров и годов",
+                
+            ),
+            Msg::EdgesLoadedFromFile { count } if en => write!(f, "✅ Loaded {} edges from file", count),
+            Msg::EdgesLoadedFromFile { count } => write!(f, "✅ Из файла загружено {} связей", count),
+            Msg::GraphFileSaved { vertices, edges, path } if en => write!(
+                f,
+                "💾 Graph ({} vertices, {} edges) saved to {}",
+                vertices, edges, path
+            ),
+            Msg::GraphFileSaved { vertices, edges, path } => write!(
+                f,
+                "💾 Граф ({} вершин, {} связей) сохранён в {}",
+                vertices, edges, path
+            ),
+            Msg::CycleEdgesReversedTotal { count } if en => write!(
+                f,
+                "✂️ Reversed {} edges in total to break cycles",
+                count
+            ),
+            Msg::CycleEdgesReversedTotal { count } => write!(
+                f,
+                "✂️ Всего развёрнуто {} связей для разрыва циклов",
+                count
+            ),
+            Msg::ValidatingLayers if en => write!(f, "🔍 Validating assigned layers..."),
+            Msg::ValidatingLayers => write!(f, "🔍 Валидация назначенных слоёв..."),
+            Msg::InvalidLayerEdges { count } if en => write!(f, "⚠️ Found {} invalid edges (possibly cycles)", count),
+            Msg::InvalidLayerEdges { count } => write!(
+                f,
+                "⚠️ Обнаружено {} невалидных связей (возможно, циклы)",
+                count
+            ),
+            Msg::LayerConstraintsViolated { count } if en => write!(f, "⚠️ {} layer pins violated", count),
+            Msg::LayerConstraintsViolated { count } => write!(f, "⚠️ Нарушено {} закреплений слоёв", count),
+            Msg::LayerConstraintViolation { vertex, layer, predecessor, predecessor_layer } if en => write!(
+                f,
+                "   {} (layer {}) ← {} (layer {})",
+                vertex, layer, predecessor, predecessor_layer
+            ),
+            Msg::LayerConstraintViolation { vertex, layer, predecessor, predecessor_layer } => write!(
+                f,
+                "   {} (слой {}) ← {} (слой {})",
+                vertex, layer, predecessor, predecessor_layer
+            ),
+            Msg::CompressingLayers { threshold } if en => write!(
+                f,
+                "🗜️ Compressing layers (merge threshold: {} vertices)...",
+                threshold
+            ),
+            Msg::CompressingLayers { threshold } => write!(
+                f,
+                "🗜️ Сжатие слоёв (порог слияния: {} вершин)...",
+                threshold
+            ),
+            Msg::DegenerateLayers { reason } if en => {
+                write!(f, "⚠️ Degenerate layering ({}), recomputing layers with Coffman-Graham", reason)
+            }
+            Msg::DegenerateLayers { reason } => {
+                write!(f, "⚠️ Вырожденная укладка ({}): слои пересчитываются Coffman–Graham", reason)
+            }
+            Msg::DegenerateLayersPinned { reason } if en => write!(
+                f,
+                "⚠️ Degenerate layering ({}) kept: layer pins or lower bounds are set",
+                reason
+            ),
+            Msg::DegenerateLayersPinned { reason } => write!(
+                f,
+                "⚠️ Вырожденная укладка ({}) оставлена: заданы закрепления или нижние границы слоёв",
+                reason
+            ),
+            Msg::DegenerateLayersReplaced { layers } if en => {
+                write!(f, "✅ Coffman-Graham layers accepted: {} layers", layers)
+            }
+            Msg::DegenerateLayersReplaced { layers } => {
+                write!(f, "✅ Приняты слои Coffman–Graham: {} слоёв", layers)
+            }
+            Msg::DegenerateLayersKept if en => {
+                write!(f, "⚠️ Recomputed layers are no better, keeping the original layers")
+            }
+            Msg::DegenerateLayersKept => {
+                write!(f, "⚠️ Пересчёт не улучшил слои, оставлен исходный результат")
+            }
+            Msg::PlacingVertices { count } if en => write!(f, "📍 Placing {} vertices by global layers", count),
+            Msg::PlacingVertices { count } => write!(f, "📍 Размещение {} вершин на основе глобальных слоёв", count),
+            Msg::AnchoredVertices { count } if en => write!(
+                f,
+                "📌 {} vertices kept at their anchored coordinates",
+                count
+            ),
+            Msg::AnchoredVertices { count } => write!(f, "📌 {} вершин оставлены в закреплённых координатах", count),
+            Msg::PositionsPrepared { count } if en => write!(f, "📊 Prepared {} positions for saving", count),
+            Msg::PositionsPrepared { count } => write!(f, "📊 Подготовлено {} позиций для сохранения", count),
+            Msg::LayerYearsNotLoaded { error } if en => write!(
+                f,
+                "⚠️ Publication years for layer labels were not loaded: {}",
+                error
+            ),
+            Msg::LayerYearsNotLoaded { error } => write!(
+                f,
+                "⚠️ Годы публикации для подписей слоёв не загружены: {}",
+                error
+            ),
+            Msg::VertexSizesNotLoaded { error } if en => write!(
+                f,
+                "⚠️ Block sizes were not loaded, using uniform ones: {}",
+                error
+            ),
+            Msg::VertexSizesNotLoaded { error } => write!(
+                f,
+                "⚠️ Размеры блоков не загружены, используются одинаковые: {}",
+                error
+            ),
+            Msg::EdgePathsBuilt { count, detoured } if en => write!(
+                f,
+                "🛤️ Built {} edge paths ({} detour around blocks)",
+                count, detoured
+            ),
+            Msg::EdgePathsBuilt { count, detoured } => write!(
+                f,
+                "🛤️ Построено {} маршрутов связей ({} в обход блоков)",
+                count, detoured
+            ),
+            Msg::GraphFileLoading { path } if en => write!(f, "📂 Loading graph from file {}", path),
+            Msg::GraphFileLoading { path } => write!(f, "📂 Загрузка графа из файла {}", path),
+            Msg::GraphFileLoadTimeUnknown { path } if en => write!(
+                f,
+                "⚠️ Load time of the graph in {} is unknown, loading the graph from Neo4j",
+                path
+            ),
+            Msg::GraphFileLoadTimeUnknown { path } => write!(
+                f,
+                "⚠️ Время загрузки графа в {} неизвестно, граф загружается из Neo4j",
+                path
+            ),
+            Msg::GraphFileEdgesChanged { path, changed, since } if en => write!(
+                f,
+                "🔄 Graph in {} is stale: {} edges changed after {}, loading the graph from Neo4j",
+                path, changed, since
+            ),
+            Msg::GraphFileEdgesChanged { path, changed, since } => write!(
+                f,
+                "🔄 Граф в {} устарел: {} связей изменено после {}, граф загружается из Neo4j",
+                path, changed, since
+            ),
+            Msg::GraphFileEdgeCountChanged { path, total, expected } if en => write!(
+                f,
+                "🔄 Graph in {} is stale: Neo4j has {} edges instead of {}, loading the graph from Neo4j",
+                path, total, expected
+            ),
+            Msg::GraphFileEdgeCountChanged { path, total, expected } => write!(
+                f,
+                "🔄 Граф в {} устарел: в Neo4j {} связей вместо {}, граф загружается из Neo4j",
+                path, total, expected
+            ),
+            Msg::TimeSlicesSaved { count, path } if en => write!(f, "💾 {} time windows saved to {}", count, path),
+            Msg::TimeSlicesSaved { count, path } => write!(f, "💾 {} временных окон сохранено в {}", count, path),
+            Msg::HealthStatus { message } if en => write!(f, "🏥 Health status: {}", message),
+            Msg::HealthStatus { message } => write!(f, "🏥 Статус здоровья: {}", message),
+            Msg::HealthCheckRunning if en => write!(f, "🏥 Running health check..."),
+            Msg::HealthCheckRunning => write!(f, "🏥 Выполнение проверки здоровья..."),
+            Msg::BenchRunning if en => write!(f, "📊 Running performance benchmarks..."),
+            Msg::BenchRunning => write!(f, "📊 Запуск бенчмарков производительности..."),
+            Msg::LayoutTestsRunning if en => write!(f, "🧪 Running layout correctness tests..."),
+            Msg::LayoutTestsRunning => write!(f, "🧪 Запуск тестов корректности укладки графа..."),
+            Msg::HeavyRunDirUnavailable { dir, error } if en => write!(
+                f,
+                "⚠️ Heavy run marker directory {} is unavailable: {}",
+                dir, error
+            ),
+            Msg::HeavyRunDirUnavailable { dir, error } => write!(
+                f,
+                "⚠️ Каталог отметок тяжёлых прогонов {} недоступен: {}",
+                dir, error
+            ),
+            Msg::HeavyRunMarkerNotWritten { path, error } if en => write!(
+                f,
+                "⚠️ Heavy run marker {} was not written: {}",
+                path, error
+            ),
+            Msg::HeavyRunMarkerNotWritten { path, error } => write!(
+                f,
+                "⚠️ Отметка тяжёлого прогона {} не записана: {}",
+                path, error
+            ),
+            Msg::ServerInitializing if en => write!(f, "🔧 Initializing GraphLayoutServer..."),
+            Msg::ServerInitializing => write!(f, "🔧 Инициализация GraphLayoutServer..."),
+            Msg::ArtifactsDir { path } if en => write!(f, "🗃️ Job artifacts are stored in {}", path),
+            Msg::ArtifactsDir { path } => write!(f, "🗃️ Артефакты задач сохраняются в {}", path),
+            Msg::ServerInitialized { id } if en => write!(f, "✅ GraphLayoutServer initialized (ID: {})", id),
+            Msg::ServerInitialized { id } => write!(f, "✅ GraphLayoutServer инициализирован (ID: {})", id),
+            Msg::HeavyRun { id, reason } if en => write!(f, "🏋️ Heavy run {}: {}", id, reason),
+            Msg::HeavyRun { id, reason } => write!(f, "🏋️ Тяжёлый прогон {}: {}", id, reason),
+            Msg::LoadingEdgesFromStore if en => write!(f, "📥 Loading edges from the store..."),
+            Msg::LoadingEdgesFromStore => write!(f, "📥 Загрузка связей из хранилища..."),
+            Msg::EdgesLoaded { count, seconds } if en => write!(f, "✅ Loaded {} edges in {:.2}s", count, seconds),
+            Msg::EdgesLoaded { count, seconds } => write!(f, "✅ Загружено {} связей за {:.2}с", count, seconds),
+            Msg::SavingPositions { count } if en => write!(f, "💾 Saving {} positions...", count),
+            Msg::SavingPositions { count } => write!(f, "💾 Сохранение {} позиций...", count),
+            Msg::PositionsSaved { seconds } if en => write!(f, "✅ Positions saved in {:.2}s", seconds),
+            Msg::PositionsSaved { seconds } => write!(f, "✅ Позиции сохранены за {:.2}с", seconds),
+            Msg::PartialSave { saved, total } if en => write!(f, "⚠️ Partial save: {}/{} positions", saved, total),
+            Msg::PartialSave { saved, total } => write!(f, "⚠️ Частичное сохранение: {}/{} позиций", saved, total),
+            Msg::ArtifactsNotSaved { job_id, error } if en => write!(
+                f,
+                "⚠️ Failed to save artifacts of job {}: {}",
+                job_id, error
+            ),
+            Msg::ArtifactsNotSaved { job_id, error } => write!(
+                f,
+                "⚠️ Не удалось сохранить артефакты задачи {}: {}",
+                job_id, error
+            ),
+            Msg::LayoutRequestReceived { task_id, tenant } if en => write!(
+                f,
+                "🎯 Processing layout request (ID: {}, tenant: {})",
+                task_id, tenant
+            ),
+            Msg::LayoutRequestReceived { task_id, tenant } => write!(
+                f,
+                "🎯 Обработка запроса укладки (ID: {}, арендатор: {})",
+                task_id, tenant
+            ),
+            Msg::SavingPositionsToNeo4j { count } if en => write!(f, "💾 Saving {} positions to Neo4j", count),
+            Msg::SavingPositionsToNeo4j { count } => write!(f, "💾 Сохранение {} позиций в Neo4j", count),
+            Msg::LayoutDone { seconds, task_id } if en => write!(
+                f,
+                "✅ Layout completed in {:.2}s (ID: {})",
+                seconds, task_id
+            ),
+            Msg::LayoutDone { seconds, task_id } => write!(
+                f,
+                "✅ Укладка завершена за {:.2}с (ID: {})",
+                seconds, task_id
+            ),
+            Msg::LayoutFailed { error, task_id, seconds } if en => write!(
+                f,
+                "❌ Layout failed: {} (ID: {}, time: {:.2}s)",
+                error, task_id, seconds
+            ),
+            Msg::LayoutFailed { error, task_id, seconds } => write!(
+                f,
+                "❌ Ошибка укладки: {} (ID: {}, время: {:.2}с)",
+                error, task_id, seconds
+            ),
+            Msg::StreamingRequestReceived { task_id, tenant } if en => write!(
+                f,
+                "🌊 Processing streaming layout request (ID: {}, tenant: {})",
+                task_id, tenant
+            ),
+            Msg::StreamingRequestReceived { task_id, tenant } => write!(
+                f,
+                "🌊 Обработка потокового запроса укладки (ID: {}, арендатор: {})",
+                task_id, tenant
+            ),
+            Msg::StreamingDone { seconds, chunks, task_id } if en => write!(
+                f,
+                "✅ Streaming layout completed in {:.2}s, {} chunks sent (ID: {})",
+                seconds, chunks, task_id
+            ),
+            Msg::StreamingDone { seconds, chunks, task_id } => write!(
+                f,
+                "✅ Потоковая укладка завершена за {:.2}с, отправлено {} чанков (ID: {})",
+                seconds, chunks, task_id
+            ),
+            Msg::StreamingFailed { error, task_id, seconds } if en => write!(
+                f,
+                "❌ Streaming layout failed: {} (ID: {}, time: {:.2}s)",
+                error, task_id, seconds
+            ),
+            Msg::StreamingFailed { error, task_id, seconds } => write!(
+                f,
+                "❌ Ошибка потоковой укладки: {} (ID: {}, время: {:.2}с)",
+                error, task_id, seconds
+            ),
+            Msg::NeighborhoodRequestReceived { center, radius, task_id, tenant } if en => write!(
+                f,
+                "🔭 Laying out neighborhood of '{}' with radius {} (ID: {}, tenant: {})",
+                center, radius, task_id, tenant
+            ),
+            Msg::NeighborhoodRequestReceived { center, radius, task_id, tenant } => write!(
+                f,
+                "🔭 Укладка окрестности '{}' радиуса {} (ID: {}, арендатор: {})",
+                center, radius, task_id, tenant
+            ),
+            Msg::NeighborhoodDone { seconds, vertices, task_id } if en => write!(
+                f,
+                "✅ Neighborhood laid out in {:.2}s: {} vertices (ID: {})",
+                seconds, vertices, task_id
+            ),
+            Msg::NeighborhoodDone { seconds, vertices, task_id } => write!(
+                f,
+                "✅ Окрестность уложена за {:.2}с: {} вершин (ID: {})",
+                seconds, vertices, task_id
+            ),
+            Msg::NeighborhoodFailed { error, task_id, seconds } if en => write!(
+                f,
+                "❌ Neighborhood layout failed: {} (ID: {}, time: {:.2}s)",
+                error, task_id, seconds
+            ),
+            Msg::NeighborhoodFailed { error, task_id, seconds } => write!(
+                f,
+                "❌ Ошибка укладки окрестности: {} (ID: {}, время: {:.2}с)",
+                error, task_id, seconds
+            ),
+            Msg::ArtifactDownload { job_id, name, bytes } if en => write!(
+                f,
+                "📤 Downloading artifact {}/{} ({} bytes)",
+                job_id, name, bytes
+            ),
+            Msg::ArtifactDownload { job_id, name, bytes } => write!(
+                f,
+                "📤 Скачивание артефакта {}/{} ({} байт)",
+                job_id, name, bytes
+            ),
+            Msg::StoreUnavailable if en => write!(f, "Neo4j is unavailable"),
+            Msg::StoreUnavailable => write!(f, "Neo4j недоступен"),
+            Msg::MemoryBusy { percent } if en => write!(f, "memory is {:.0}% used", percent),
+            Msg::MemoryBusy { percent } => write!(f, "память занята на {:.0}%", percent),
+            Msg::CpuBusy { percent } if en => write!(f, "CPU is {:.0}% busy", percent),
+            Msg::CpuBusy { percent } => write!(f, "CPU загружен на {:.0}%", percent),
+            Msg::HeavyRunActive { run } if en => write!(f, "heavy run in progress: {}", run),
+            Msg::HeavyRunActive { run } => write!(f, "идёт тяжёлый прогон: {}", run),
+            Msg::ServiceHealthy if en => write!(f, "Service is operating normally"),
+            Msg::ServiceHealthy => write!(f, "Сервис работает нормально"),
+            Msg::HealthProblems { problems } if en => write!(f, "Problems: {}", problems),
+            Msg::HealthProblems { problems } => write!(f, "Проблемы: {}", problems),
+            Msg::TenantTokenMissing if en => write!(f, "No valid tenant token (authorization: Bearer <token>)"),
+            Msg::TenantTokenMissing => write!(f, "Нет действительного токена арендатора (authorization: Bearer <токен>)"),
+            Msg::TenantTokenForbidden if en => write!(f, "The token does not grant access to the requested tenant"),
+            Msg::TenantTokenForbidden => write!(f, "Токен не даёт доступа к указанному арендатору"),
+            Msg::ArtifactsDisabled if en => write!(f, "Job artifacts are disabled ([artifacts] enabled)"),
+            Msg::ArtifactsDisabled => write!(f, "Артефакты задач выключены ([artifacts] enabled)"),
+            Msg::ArtifactNotFound { job_id, name } if en => write!(f, "Artifact {} of job {} not found", name, job_id),
+            Msg::ArtifactNotFound { job_id, name } => write!(f, "Артефакт {} задачи {} не найден", name, job_id),
+            Msg::ClientDisconnectedAtChunk { chunk, total } if en => {
+                write!(f, "client disconnected at chunk {}/{}", chunk, total)
+            }
+            Msg::ClientDisconnectedAtChunk { chunk, total } => write!(f, "клиент отключился на чанке {}/{}", chunk, total),
+            Msg::ClientDisconnectedBeforeSummary if en => write!(f, "client disconnected before the final chunk"),
+            Msg::ClientDisconnectedBeforeSummary => write!(f, "клиент отключился перед итоговым чанком"),
+            Msg::CenterIdEmpty if en => write!(f, "center_id must not be empty"),
+            Msg::CenterIdEmpty => write!(f, "center_id не может быть пустым"),
+            Msg::RadiusOutOfRange { max } if en => write!(f, "radius must be between 1 and {}", max),
+            Msg::RadiusOutOfRange { max } => write!(f, "radius должен быть от 1 до {}", max),
+            Msg::ArticleNotFound { id } if en => write!(f, "Article '{}' not found", id),
+            Msg::ArticleNotFound { id } => write!(f, "Статья '{}' не найдена", id),
+            Msg::IngestStatsHeader if en => write!(f, "📊 Edge ingest statistics:"),
+            Msg::IngestStatsHeader => write!(f, "📊 Статистика приёма связей:"),
+            Msg::IngestEdgesSeen { count } if en => write!(f, "   - Total edges: {}", count),
+            Msg::IngestEdgesSeen { count } => write!(f, "   - Всего связей: {}", count),
+            Msg::IngestInvalidIds { count } if en => write!(f, "   - Edges with an empty ID: {}", count),
+            Msg::IngestInvalidIds { count } => write!(f, "   - Связей с пустым ID: {}", count),
+            Msg::IngestSelfLoops { count } => write!(f, "   - Self-loops: {}", count),
+            Msg::IngestDuplicates { count } if en => write!(f, "   - Duplicate edges: {}", count),
+            Msg::IngestDuplicates { count } => write!(f, "   - Дублирующих связей: {}", count),
+            Msg::IngestMultigraph { self_loops, parallel } if en => write!(
+                f,
+                "   - Multigraph: kept {} self-loops, {} parallel edges",
+                self_loops, parallel
+            ),
+            Msg::IngestMultigraph { self_loops, parallel } => write!(
+                f,
+                "   - Мультиграф: сохранено self-loops {}, параллельных связей {}",
+                self_loops, parallel
+            ),
+            Msg::IngestDropped { count } if en => write!(f, "⚠️ Filtered out {} edges", count),
+            Msg::IngestDropped { count } => write!(f, "⚠️ Отфильтровано {} связей", count),
+            Msg::IngestEdgesAdded { count } if en => write!(
+                f,
+                "🏗️ Added {} unique edges to the graph (SOURCE->TARGET, cited->citing)",
+                count
+            ),
+            Msg::IngestEdgesAdded { count } => write!(
+                f,
+                "🏗️ Добавлено {} уникальных связей в граф (SOURCE->TARGET, cited->citing)",
+                count
+            ),
+            Msg::GraphMapped { megabytes } if en => write!(f, "💾 Graph edges in an mmap file: {} MB", megabytes),
+            Msg::GraphMapped { megabytes } => write!(f, "💾 Связи графа в mmap-файле: {} МБ", megabytes),
+            Msg::SeedVerticesNotFound { seeds } if en => write!(f, "None of the seed vertices occur in the edges: {:?}", seeds),
+            Msg::SeedVerticesNotFound { seeds } => write!(f, "Ни одна seed-вершина не найдена среди связей: {:?}", seeds),
+            Msg::LayoutInitHeader if en => write!(f, "=== STEP 0: LAYOUT INITIALIZATION ==="),
+            Msg::LayoutInitHeader => write!(f, "=== ШАГ 0: ИНИЦИАЛИЗАЦИЯ УКЛАДКИ ==="),
+            Msg::InputEdges { count } if en => write!(f, "📊 Input: {} edges", count),
+            Msg::InputEdges { count } => write!(f, "📊 Входные данные: {} связей", count),
+            Msg::ValidatingInput if en => write!(f, "🔍 Validating input..."),
+            Msg::ValidatingInput => write!(f, "🔍 Валидация входных данных..."),
+            Msg::InputValid if en => write!(f, "✅ Validation passed"),
+            Msg::InputValid => write!(f, "✅ Валидация успешна"),
+            Msg::SeedNeighborhood { seeds, depth, kept, total } if en => write!(
+                f,
+                "🎯 Neighborhood of {} seed vertices with radius {}: {} of {} edges",
+                seeds, depth, kept, total
+            ),
+            Msg::SeedNeighborhood { seeds, depth, kept, total } => write!(
+                f,
+                "🎯 Окрестность {} seed-вершин радиуса {}: {} из {} связей",
+                seeds, depth, kept, total
+            ),
+            Msg::ClusteredVertices { count } if en => write!(f, "🏷️ Vertices with a cluster: {}", count),
+            Msg::ClusteredVertices { count } => write!(f, "🏷️ Вершин с кластером: {}", count),
+            Msg::VerticesWithYear { count } if en => write!(f, "📅 Vertices with a publication year: {}", count),
+            Msg::VerticesWithYear { count } => write!(f, "📅 Вершин с годом публикации: {}", count),
+            Msg::BreakingCycles if en => write!(f, "🔁 Finding and breaking cycles..."),
+            Msg::BreakingCycles => write!(f, "🔁 Поиск и разрыв циклов..."),
+            Msg::CyclesBroken { components, edges, strategy } if en => write!(
+                f,
+                "⚠️ Found {} components with cycles, broke {} edges ({:?})",
+                components, edges, strategy
+            ),
+            Msg::CyclesBroken { components, edges, strategy } => write!(
+                f,
+                "⚠️ Найдено {} компонент с циклами, разорвано {} связей ({:?})",
+                components, edges, strategy
+            ),
+            Msg::NoCycles if en => write!(f, "✅ No cycles found"),
+            Msg::NoCycles => write!(f, "✅ Циклов не найдено"),
+            Msg::ReducingTransitively if en => write!(f, "✂️ Transitive reduction of edges..."),
+            Msg::ReducingTransitively => write!(f, "✂️ Транзитивная редукция связей..."),
+            Msg::TransitiveEdgesRemoved { count, exact } if en => write!(
+                f,
+                "✅ Redundant edges removed: {} ({})",
+                count,
+                if exact { "exact" } else { "heuristic" }
+            ),
+            Msg::TransitiveEdgesRemoved { count, exact } => write!(
+                f,
+                "✅ Удалено избыточных связей: {} ({})",
+                count,
+                if exact { "точно" } else { "эвристика" }
+            ),
+            Msg::GraphBuildHeader if en => write!(f, "=== STEP 1: GRAPH CONSTRUCTION ==="),
+            Msg::GraphBuildHeader => write!(f, "=== ШАГ 1: ПОСТРОЕНИЕ ГРАФА ==="),
+            Msg::BuildingGraph if en => write!(f, "🏗️ Building the internal graph representation..."),
+            Msg::BuildingGraph => write!(f, "🏗️ Построение внутреннего представления графа..."),
+            Msg::GraphBuilt { vertices, edges, components } if en => write!(
+                f,
+                "✅ Graph built: {} vertices, {} edges, {} connected components",
+                vertices, edges, components
+            ),
+            Msg::GraphBuilt { vertices, edges, components } => write!(
+                f,
+                "✅ Граф построен: {} вершин, {} связей, {} компонент связности",
+                vertices, edges, components
+            ),
+            Msg::DegreeStats { out_degree: o, in_degree: i, sources, sinks, largest_component, diameter } if en => write!(
+                f,
+                "📐 Degrees (p50/p90/p99/max): out {}/{}/{}/{}, in {}/{}/{}/{}; sources {}, sinks {}, largest component {}, diameter ≈{}",
+                o[0], o[1], o[2], o[3], i[0], i[1], i[2], i[3], sources, sinks, largest_component, diameter
+            ),
+            Msg::DegreeStats { out_degree: o, in_degree: i, sources, sinks, largest_component, diameter } => write!(
+                f,
+                "📐 Степени (p50/p90/p99/max): исходящие {}/{}/{}/{}, входящие {}/{}/{}/{}; источников {}, стоков {}, наибольшая компонента {}, диаметр ≈{}",
+                o[0], o[1], o[2], o[3], i[0], i[1], i[2], i[3], sources, sinks, largest_component, diameter
+            ),
+            Msg::TopoSortHeader if en => write!(f, "=== STEP 2: TOPOLOGICAL SORT ==="),
+            Msg::TopoSortHeader => write!(f, "=== ШАГ 2: ТОПОЛОГИЧЕСКАЯ СОРТИРОВКА ==="),
+            Msg::TopoSorting if en => write!(f, "🔄 Running parallel topological sort..."),
+            Msg::TopoSorting => write!(f, "🔄 Выполнение параллельной топологической сортировки..."),
+            Msg::TopoSortDone { ms } if en => write!(f, "✅ Topological sort finished in {} ms", ms),
+            Msg::TopoSortDone { ms } => write!(f, "✅ Топологическая сортировка завершена за {} мс", ms),
+            Msg::VerticesOrdered { count } if en => write!(f, "📊 Ordered {} vertices", count),
+            Msg::VerticesOrdered { count } => write!(f, "📊 Упорядочено {} вершин", count),
+            Msg::LongestPathHeader if en => write!(f, "=== STEP 3: LONGEST PATH ==="),
+            Msg::LongestPathHeader => write!(f, "=== ШАГ 3: ПОИСК САМОГО ДЛИННОГО ПУТИ ==="),
+            Msg::FindingLongestPath if en => write!(f, "🛤️ Finding the longest path with SIMD optimization..."),
+            Msg::FindingLongestPath => write!(f, "🛤️ Поиск самого длинного пути с SIMD оптимизацией..."),
+            Msg::LongestPathFound { ms } if en => write!(f, "✅ Longest path found in {} ms", ms),
+            Msg::LongestPathFound { ms } => write!(f, "✅ Самый длинный путь найден за {} мс", ms),
+            Msg::LongestPathLength { vertices } if en => write!(f, "📏 Longest path length: {} vertices", vertices),
+            Msg::LongestPathLength { vertices } => write!(f, "📏 Длина самого длинного пути: {} вершин", vertices),
+            Msg::PlacementHeader if en => write!(f, "=== STEP 4: VERTEX PLACEMENT ==="),
+            Msg::PlacementHeader => write!(f, "=== ШАГ 4: РАЗМЕЩЕНИЕ ВЕРШИН ==="),
+            Msg::PlacingWithOptimization if en => write!(f, "📍 Placing vertices with space optimization..."),
+            Msg::PlacingWithOptimization => write!(f, "📍 Размещение вершин с оптимизацией пространства..."),
+            Msg::PackingComponents { count, parallel, gap } if en => write!(
+                f,
+                "🧩 Laying out {} components independently (parallel: {}, gap: {} px)",
+                count, parallel, gap
+            ),
+            Msg::PackingComponents { count, parallel, gap } => write!(
+                f,
+                "🧩 Независимая укладка {} компонент (параллельно: {}, зазор: {} px)",
+                count, parallel, gap
+            ),
+            Msg::DegenerateLayoutRetry { reason, strategy, width } if en => write!(
+                f,
+                "⚠️ Degenerate layout ({}): retrying with {:?} (layer width {})",
+                reason, strategy, width
+            ),
+            Msg::DegenerateLayoutRetry { reason, strategy, width } => write!(
+                f,
+                "⚠️ Вырожденная укладка ({}): повтор с {:?} (ширина слоя {})",
+                reason, strategy, width
+            ),
+            Msg::LayeringFallbackAccepted { strategy, from_percent, to_percent } if en => write!(
+                f,
+                "✅ Accepted {:?} layout: densest layer {:.0}% → {:.0}% of vertices",
+                strategy, from_percent, to_percent
+            ),
+            Msg::LayeringFallbackAccepted { strategy, from_percent, to_percent } => write!(
+                f,
+                "✅ Принята укладка {:?}: самый плотный слой {:.0}% → {:.0}% вершин",
+                strategy, from_percent, to_percent
+            ),
+            Msg::LayeringFallbackRejected if en => write!(f, "⚠️ The retry did not improve the layout, keeping the original result"),
+            Msg::LayeringFallbackRejected => write!(f, "⚠️ Повторный прогон не улучшил укладку, оставлен исходный результат"),
+            Msg::PlacementDone { ms } if en => write!(f, "✅ Vertex placement finished in {} ms", ms),
+            Msg::PlacementDone { ms } => write!(f, "✅ Размещение вершин завершено за {} мс", ms),
+            Msg::VerticesPlacedTotal { count } if en => write!(f, "📌 Placed {} vertices", count),
+            Msg::VerticesPlacedTotal { count } => write!(f, "📌 Размещено {} вершин", count),
+            Msg::DeepestComponent { representative, vertices, path, layers } if en => write!(
+                f,
+                "🔭 Deepest component: {} ({} vertices, path of {} vertices, {} layers)",
+                representative, vertices, path, layers
+            ),
+            Msg::DeepestComponent { representative, vertices, path, layers } => write!(
+                f,
+                "🔭 Самая глубокая компонента: {} ({} вершин, путь {} вершин, слоёв {})",
+                representative, vertices, path, layers
+            ),
+            Msg::FinalizationHeader if en => write!(f, "=== STEP 5: FINALIZATION ==="),
+            Msg::FinalizationHeader => write!(f, "=== ШАГ 5: ФИНАЛИЗАЦИЯ ==="),
+            Msg::BuildingStatistics if en => write!(f, "📊 Building statistics and metadata..."),
+            Msg::BuildingStatistics => write!(f, "📊 Создание статистики и метаданных..."),
+            Msg::EdgeCrossings { count } if en => write!(f, "✖️ Edge crossings: {}", count),
+            Msg::EdgeCrossings { count } => write!(f, "✖️ Пересечений связей: {}", count),
+            Msg::SpatialIndexBuilt { cells, cell_size } if en => write!(f, "🗺️ Spatial index: {} cells of {}", cells, cell_size),
+            Msg::SpatialIndexBuilt { cells, cell_size } => write!(f, "🗺️ Пространственный индекс: {} ячеек по {}", cells, cell_size),
+            Msg::LayoutCompleteHeader if en => write!(f, "=== LAYOUT COMPLETED SUCCESSFULLY ==="),
+            Msg::LayoutCompleteHeader => write!(f, "=== УКЛАДКА УСПЕШНО ЗАВЕРШЕНА ==="),
+            Msg::LayoutTotalTime { ms } if en => write!(f, "⏱️ Total time: {} ms", ms),
+            Msg::LayoutTotalTime { ms } => write!(f, "⏱️ Общее время: {} мс", ms),
+            Msg::VerticesProcessed { count } if en => write!(f, "📈 Vertices processed: {}", count),
+            Msg::VerticesProcessed { count } => write!(f, "📈 Обработано вершин: {}", count),
+            Msg::EdgesProcessed { count } if en => write!(f, "🔗 Edges processed: {}", count),
+            Msg::EdgesProcessed { count } => write!(f, "🔗 Обработано связей: {}", count),
+            Msg::LayoutThroughput { per_second } if en => write!(f, "⚡ Throughput: {:.1} vertices/s", per_second),
+            Msg::LayoutThroughput { per_second } => write!(f, "⚡ Скорость: {:.1} вершин/сек", per_second),
+            Msg::AllStepsDone if en => write!(f, "=== ALL STEPS COMPLETED ==="),
+            Msg::AllStepsDone => write!(f, "=== ВСЕ ЭТАПЫ ЗАВЕРШЕНЫ ==="),
+            Msg::VerticesPlaced { vertices, layers } if en => write!(f, "Placed {} vertices across {} layers", vertices, layers),
+            Msg::VerticesPlaced { vertices, layers } => write!(f, "Размещено {} вершин в {} слоях", vertices, layers),
+            Msg::CentroidReordered { changed, layers } if en => {
+                write!(f, "Centroid ordering changed {} of {} layers", changed, layers)
+            }
+            Msg::CentroidReordered { changed, layers } => {
+                write!(f, "Упорядочивание по центроидам изменило {} из {} слоёв", changed, layers)
+            }
+            Msg::AnchorsOverlap { vertex, layer, level } if en => write!(
+                f,
+                "Anchored vertex {} shares layer {}, level {} with another anchor",
+                vertex, layer, level
+            ),
+            Msg::AnchorsOverlap { vertex, layer, level } => write!(
+                f,
+                "Закреплённая вершина {} делит слой {}, уровень {} с другой закреплённой",
+                vertex, layer, level
+            ),
+            Msg::SavingLayout { rows, aggregates } if en => write!(
+                f,
+                "Saving layout positions to Neo4j: {} rows (vertex aggregates: {})",
+                rows, aggregates
+            ),
+            Msg::SavingLayout { rows, aggregates } => write!(
+                f,
+                "Сохранение позиций укладки в Neo4j: {} строк (агрегаты вершин: {})",
+                rows, aggregates
+            ),
+            Msg::NoPositionsToSave if en => write!(f, "No layout positions provided; skipping save."),
+            Msg::NoPositionsToSave => write!(f, "Позиций укладки нет, сохранение пропущено."),
+            Msg::SaveBatchesPlanned { batches, batch_size } if en => write!(
+                f,
+                "Saving layout using {} batches with up to {} rows each",
+                batches, batch_size
+            ),
+            Msg::SaveBatchesPlanned { batches, batch_size } => write!(
+                f,
+                "Сохранение укладки в {} батчах, до {} строк в каждом",
+                batches, batch_size
+            ),
+            Msg::SaveBatchJoinFailed { batch, error } if en => {
+                write!(f, "Failed to join layout batch task {}: {}", batch, error)
+            }
+            Msg::SaveBatchJoinFailed { batch, error } => {
+                write!(f, "Задача батча укладки {} завершилась аварийно: {}", batch, error)
+            }
+            Msg::SaveBatchQuarantined { batch, first_row, last_row, error } if en => write!(
+                f,
+                "Layout batch {} (rows {}-{}) failed after retries, quarantining: {}",
+                batch, first_row, last_row, error
+            ),
+            Msg::SaveBatchQuarantined { batch, first_row, last_row, error } => write!(
+                f,
+                "Батч укладки {} (строки {}-{}) не сохранён после повторов, отправлен в карантин: {}",
+                batch, first_row, last_row, error
+            ),
+            Msg::SaveBatchFinished { batch, total, first_row, last_row } if en => write!(
+                f,
+                "Finished layout batch {}/{} (rows {}-{})",
+                batch, total, first_row, last_row
+            ),
+            Msg::SaveBatchFinished { batch, total, first_row, last_row } => write!(
+                f,
+                "Сохранён батч укладки {}/{} (строки {}-{})",
+                batch, total, first_row, last_row
+            ),
+            Msg::SaveProgress { percent, saved, total, rate } if en => write!(
+                f,
+                "Layout save progress: {:.1}% ({}/{} rows, {:.0} rows/sec)",
+                percent, saved, total, rate
+            ),
+            Msg::SaveProgress { percent, saved, total, rate } => write!(
+                f,
+                "Прогресс сохранения укладки: {:.1}% ({}/{} строк, {:.0} строк/сек)",
+                percent, saved, total, rate
+            ),
+            Msg::AllSaveBatchesFailed { batches, error } if en => {
+                write!(f, "All {} layout batches failed: {}", batches, error)
+            }
+            Msg::AllSaveBatchesFailed { batches, error } => {
+                write!(f, "Не сохранён ни один из {} батчей укладки: {}", batches, error)
+            }
+            Msg::SaveQuarantined { saved, total, batches, path } if en => write!(
+                f,
+                "Partial save: {} of {} rows saved, {} batches quarantined to {}",
+                saved, total, batches, path
+            ),
+            Msg::SaveQuarantined { saved, total, batches, path } => write!(
+                f,
+                "Частичное сохранение: сохранено {} из {} строк, {} батчей в карантине {}",
+                saved, total, batches, path
+            ),
+            Msg::LayoutWritten { seconds, rate } if en => write!(
+                f,
+                "Completed writing layout to Neo4j in {:.2}s ({:.0} rows/sec)",
+                seconds, rate
+            ),
+            Msg::LayoutWritten { seconds, rate } => write!(
+                f,
+                "Укладка записана в Neo4j за {:.2}с ({:.0} строк/сек)",
+                seconds, rate
+            ),
+            Msg::LayoutSwitched { articles, seconds } if en => write!(
+                f,
+                "Atomically switched layout of {} articles in {:.2}s (previous coordinates kept for rollback)",
+                articles, seconds
+            ),
+            Msg::LayoutSwitched { articles, seconds } => write!(
+                f,
+                "Укладка {} статей атомарно переключена за {:.2}с (прежние координаты сохранены для отката)",
+                articles, seconds
+            ),
+            Msg::PostProcessingStart if en => write!(f, "Starting layout post-processing in Neo4j..."),
+            Msg::PostProcessingStart => write!(f, "Запуск постобработки укладки в Neo4j..."),
+            Msg::PostProcessingRunning if en => write!(f, "Running layout post-processing tasks..."),
+            Msg::PostProcessingRunning => write!(f, "Выполнение задач постобработки укладки..."),
+            Msg::PostProcessingDone { seconds, removed } if en => write!(
+                f,
+                "Post-processing finished in {:.1}s ({} isolated vertices cleaned)",
+                seconds, removed
+            ),
+            Msg::PostProcessingDone { seconds, removed } => write!(
+                f,
+                "Постобработка завершена за {:.1}с (очищено изолированных вершин: {})",
+                seconds, removed
+            ),
+            Msg::ScanningIsolated if en => write!(f, "Scanning for isolated vertices with layout coordinates..."),
+            Msg::ScanningIsolated => write!(f, "Поиск изолированных вершин с координатами укладки..."),
+            Msg::NoIsolatedVertices if en => write!(f, "No isolated vertices require cleanup."),
+            Msg::NoIsolatedVertices => write!(f, "Изолированных вершин для очистки нет."),
+            Msg::IsolatedFound { count } if en => write!(
+                f,
+                "Found {} isolated vertices with layout data; removing coordinates...",
+                count
+            ),
+            Msg::IsolatedFound { count } => write!(
+                f,
+                "Найдено {} изолированных вершин с данными укладки, удаление координат...",
+                count
+            ),
+            Msg::IsolatedCleared { removed, total, found } if en => write!(
+                f,
+                "Cleared layout properties from {} vertices (total {}/{})",
+                removed, total, found
+            ),
+            Msg::IsolatedCleared { removed, total, found } => write!(
+                f,
+                "Координаты укладки сняты с {} вершин (всего {}/{})",
+                removed, total, found
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_follow_selected_language() {
+        set_language(Language::En);
+        assert_eq!(Msg::TotalEdges { count: 3 }.to_string(), "📈 Total edges in the database: 3");
+        assert_eq!(Msg::EmptyGraph.to_string(), "The graph must not be empty");
+        assert_eq!(
+            Msg::LayoutDone { seconds: 1.5, task_id: "t1" }.to_string(),
+            "✅ Layout completed in 1.50s (ID: t1)"
+        );
+        assert_eq!(Msg::HealthProblems { problems: "Neo4j is unavailable" }.to_string(), "Problems: Neo4j is unavailable");
+        assert_eq!(Msg::LayoutInitHeader.to_string(), "=== STEP 0: LAYOUT INITIALIZATION ===");
+
+        set_language(Language::Ru);
+        assert_eq!(Msg::TotalEdges { count: 3 }.to_string(), "📈 Всего связей в БД: 3");
+        assert_eq!(language(), Language::Ru);
+    }
+
+    #[test]
+    fn test_language_from_config_value() {
+        #[derive(Deserialize)]
+        struct Section {
+            language: Language,
+        }
+        let section: Section = toml::from_str("language = \"en\"").unwrap();
+        assert_eq!(section.language, Language::En);
+    }
+}
@@ -7,44 +7,58 @@
 - Качества укладки графов
 - Статистики gRPC запросов
 
+Метрики запросов, длительностей и объёма обработки размечены меткой
+`tenant` (см. `crate::tenant`); ресурсы процесса (память, CPU) общие.
 */
 
-use crate::generated::{PrometheusMetric, MetricSample};
+use crate::generated::{LayoutStatistics, MetricsSnapshot, PrometheusMetric, MetricSample};
 use anyhow::Result;
 use prometheus::{
-    Counter, Gauge, Histogram, Registry, Encoder, TextEncoder,
+    CounterVec, Gauge, GaugeVec, HistogramVec, Registry, Encoder, TextEncoder,
     HistogramOpts, Opts,
 };
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Метка арендатора у метрик запросов
+const TENANT_LABEL: &[&str] = &["tenant"];
+
 /// Сборщик метрик
 #[derive(Debug)]
 pub struct MetricsCollector {
     /// Prometheus registry
     registry: Registry,
     
-    /// Счетчики запросов
-    layout_requests_total: Counter,
-    layout_requests_success: Counter,
-    layout_requests_failed: Counter,
+    /// Счетчики запросов (по арендаторам)
+    layout_requests_total: CounterVec,
+    layout_requests_success: CounterVec,
+    layout_requests_failed: CounterVec,
     
-    /// Гистограммы времени выполнения
-    layout_duration: Histogram,
-    topo_sort_duration: Histogram,
-    longest_path_duration: Histogram,
-    placement_duration: Histogram,
+    /// Гистограммы времени выполнения (по арендаторам)
+    layout_duration: HistogramVec,
+    topo_sort_duration: HistogramVec,
+    longest_path_duration: HistogramVec,
+    placement_duration: HistogramVec,
     
     /// Метрики ресурсов
     memory_usage_bytes: Gauge,
     memory_peak_bytes: Gauge,
     cpu_usage_percent: Gauge,
     
-    /// Метрики качества укладки
-    vertices_processed: Counter,
-    edges_processed: Counter,
-    vertices_per_second: Gauge,
+    /// Метрики качества укладки (по арендаторам)
+    vertices_processed: CounterVec,
+    edges_processed: CounterVec,
+    vertices_per_second: GaugeVec,
+    edge_crossings: GaugeVec,
+    edge_wire_length: GaugeVec,
+    
+    /// Форма графа последней укладки (по арендаторам)
+    graph_degree: GaugeVec,
+    graph_largest_component: GaugeVec,
+    graph_sources: GaugeVec,
+    graph_sinks: GaugeVec,
+    graph_diameter: GaugeVec,
     
     /// Активные задачи
     active_tasks: Arc<RwLock<usize>>,
@@ -59,41 +73,41 @@ impl MetricsCollector {
         let registry = Registry::new();
         
         // Создание счетчиков запросов
-        let layout_requests_total = Counter::with_opts(Opts::new(
-            "graph_layout_requests_total",
-            "Total number of layout requests"
-        ))?;
+        let layout_requests_total = CounterVec::new(
+            Opts::new("graph_layout_requests_total", "Total number of layout requests"),
+            TENANT_LABEL,
+        )?;
         
-        let layout_requests_success = Counter::with_opts(Opts::new(
-            "graph_layout_requests_success_total", 
-            "Total number of successful layout requests"
-        ))?;
+        let layout_requests_success = CounterVec::new(
+            Opts::new("graph_layout_requests_success_total", "Total number of successful layout requests"),
+            TENANT_LABEL,
+        )?;
         
-        let layout_requests_failed = Counter::with_opts(Opts::new(
-            "graph_layout_requests_failed_total",
-            "Total number of failed layout requests"
-        ))?;
+        let layout_requests_failed = CounterVec::new(
+            Opts::new("graph_layout_requests_failed_total", "Total number of failed layout requests"),
+            TENANT_LABEL,
+        )?;
         
         // Создание гистограмм времени
-        let layout_duration = Histogram::with_opts(HistogramOpts::new(
+        let layout_duration = HistogramVec::new(HistogramOpts::new(
             "graph_layout_duration_seconds",
             "Duration of layout computation in seconds"
-        ).buckets(vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 120.0]))?;
+        ).buckets(vec![0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 120.0]), TENANT_LABEL)?;
         
-        let topo_sort_duration = Histogram::with_opts(HistogramOpts::new(
+        let topo_sort_duration = HistogramVec::new(HistogramOpts::new(
             "graph_layout_topo_sort_duration_seconds", 
             "Duration of topological sort in seconds"
-        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]))?;
+        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]), TENANT_LABEL)?;
         
-        let longest_path_duration = Histogram::with_opts(HistogramOpts::new(
+        let longest_path_duration = HistogramVec::new(HistogramOpts::new(
             "graph_layout_longest_path_duration_seconds",
             "Duration of longest path computation in seconds"
-        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]))?;
+        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]), TENANT_LABEL)?;
         
-        let placement_duration = Histogram::with_opts(HistogramOpts::new(
+        let placement_duration = HistogramVec::new(HistogramOpts::new(
             "graph_layout_placement_duration_seconds",
             "Duration of vertex placement in seconds"
-        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]))?;
+        ).buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0]), TENANT_LABEL)?;
         
         // Создание метрик ресурсов
         let memory_usage_bytes = Gauge::with_opts(Opts::new(
@@ -112,20 +126,55 @@ impl MetricsCollector {
         ))?;
         
         // Создание метрик качества
-        let vertices_processed = Counter::with_opts(Opts::new(
-            "graph_layout_vertices_processed_total",
-            "Total number of vertices processed"
-        ))?;
+        let vertices_processed = CounterVec::new(
+            Opts::new("graph_layout_vertices_processed_total", "Total number of vertices processed"),
+            TENANT_LABEL,
+        )?;
         
-        let edges_processed = Counter::with_opts(Opts::new(
-            "graph_layout_edges_processed_total",
-            "Total number of edges processed"
-        ))?;
+        let edges_processed = CounterVec::new(
+            Opts::new("graph_layout_edges_processed_total", "Total number of edges processed"),
+            TENANT_LABEL,
+        )?;
         
-        let vertices_per_second = Gauge::with_opts(Opts::new(
-            "graph_layout_vertices_per_second",
-            "Processing rate in vertices per second"
-        ))?;
+        let vertices_per_second = GaugeVec::new(
+            Opts::new("graph_layout_vertices_per_second", "Processing rate in vertices per second"),
+            TENANT_LABEL,
+        )?;
+        
+        let edge_crossings = GaugeVec::new(
+            Opts::new("graph_layout_edge_crossings", "Edge crossings of the last layout"),
+            TENANT_LABEL,
+        )?;
+        
+        let edge_wire_length = GaugeVec::new(
+            Opts::new("graph_layout_edge_wire_length", "Total length of the edge paths of the last layout"),
+            TENANT_LABEL,
+        )?;
+        
+        let graph_degree = GaugeVec::new(
+            Opts::new("graph_layout_graph_degree", "Vertex degree percentiles of the last layout graph"),
+            &["tenant", "direction", "percentile"],
+        )?;
+        
+        let graph_largest_component = GaugeVec::new(
+            Opts::new("graph_layout_graph_largest_component", "Vertices in the largest component of the last layout graph"),
+            TENANT_LABEL,
+        )?;
+        
+        let graph_sources = GaugeVec::new(
+            Opts::new("graph_layout_graph_sources", "Vertices without incoming edges in the last layout graph"),
+            TENANT_LABEL,
+        )?;
+        
+        let graph_sinks = GaugeVec::new(
+            Opts::new("graph_layout_graph_sinks", "Vertices without outgoing edges in the last layout graph"),
+            TENANT_LABEL,
+        )?;
+        
+        let graph_diameter = GaugeVec::new(
+            Opts::new("graph_layout_graph_diameter", "Approximate undirected diameter of the last layout graph"),
+            TENANT_LABEL,
+        )?;
         
         // Регистрация метрик
         registry.register(Box::new(layout_requests_total.clone()))?;
@@ -141,6 +190,14 @@ impl MetricsCollector {
         registry.register(Box::new(vertices_processed.clone()))?;
         registry.register(Box::new(edges_processed.clone()))?;
         registry.register(Box::new(vertices_per_second.clone()))?;
+        registry.register(Box::new(edge_crossings.clone()))?;
+        registry.register(Box::new(edge_wire_length.clone()))?;
+        registry.register(Box::new(graph_degree.clone()))?;
+        registry.register(Box::new(graph_largest_component.clone()))?;
+        registry.register(Box::new(graph_sources.clone()))?;
+        registry.register(Box::new(graph_sinks.clone()))?;
+        registry.register(Box::new(graph_diameter.clone()))?;
+        crate::retry::register_metrics(&registry)?;
         
         Ok(Self {
             registry,
@@ -157,38 +214,45 @@ impl MetricsCollector {
             vertices_processed,
             edges_processed,
             vertices_per_second,
+            edge_crossings,
+            edge_wire_length,
+            graph_degree,
+            graph_largest_component,
+            graph_sources,
+            graph_sinks,
+            graph_diameter,
             active_tasks: Arc::new(RwLock::new(0)),
             start_time: Instant::now(),
         })
     }
     
     /// Запись метрики успешной укладки
-    pub async fn record_successful_layout(&self, duration: Duration) {
-        self.layout_requests_total.inc();
-        self.layout_requests_success.inc();
-        self.layout_duration.observe(duration.as_secs_f64());
+    pub async fn record_successful_layout(&self, tenant: &str, duration: Duration) {
+        self.layout_requests_total.with_label_values(&[tenant]).inc();
+        self.layout_requests_success.with_label_values(&[tenant]).inc();
+        self.layout_duration.with_label_values(&[tenant]).observe(duration.as_secs_f64());
     }
     
     /// Запись метрики неудачной укладки
-    pub async fn record_failed_layout(&self, duration: Duration) {
-        self.layout_requests_total.inc();
-        self.layout_requests_failed.inc();
-        self.layout_duration.observe(duration.as_secs_f64());
+    pub async fn record_failed_layout(&self, tenant: &str, duration: Duration) {
+        self.layout_requests_total.with_label_values(&[tenant]).inc();
+        self.layout_requests_failed.with_label_values(&[tenant]).inc();
+        self.layout_duration.with_label_values(&[tenant]).observe(duration.as_secs_f64());
     }
     
     /// Запись времени топологической сортировки
-    pub async fn record_topo_sort(&self, duration: Duration) {
-        self.topo_sort_duration.observe(duration.as_secs_f64());
+    pub async fn record_topo_sort(&self, tenant: &str, duration: Duration) {
+        self.topo_sort_duration.with_label_values(&[tenant]).observe(duration.as_secs_f64());
     }
     
     /// Запись времени поиска longest path
-    pub async fn record_longest_path(&self, duration: Duration) {
-        self.longest_path_duration.observe(duration.as_secs_f64());
+    pub async fn record_longest_path(&self, tenant: &str, duration: Duration) {
+        self.longest_path_duration.with_label_values(&[tenant]).observe(duration.as_secs_f64());
     }
     
     /// Запись времени размещения вершин
-    pub async fn record_placement(&self, duration: Duration) {
-        self.placement_duration.observe(duration.as_secs_f64());
+    pub async fn record_placement(&self, tenant: &str, duration: Duration) {
+        self.placement_duration.with_label_values(&[tenant]).observe(duration.as_secs_f64());
     }
     
     /// Обновление использования памяти
@@ -203,26 +267,82 @@ impl MetricsCollector {
     }
     
     /// Запись обработанных вершин и связей
-    pub async fn record_processing(&self, vertices: usize, edges: usize, duration: Duration) {
-        self.vertices_processed.inc_by(vertices as f64);
-        self.edges_processed.inc_by(edges as f64);
+    pub async fn record_processing(&self, tenant: &str, vertices: usize, edges: usize, duration: Duration) {
+        self.vertices_processed.with_label_values(&[tenant]).inc_by(vertices as f64);
+        self.edges_processed.with_label_values(&[tenant]).inc_by(edges as f64);
         
         if duration.as_secs_f64() > 0.0 {
             let rate = vertices as f64 / duration.as_secs_f64();
-            self.vertices_per_second.set(rate);
+            self.vertices_per_second.with_label_values(&[tenant]).set(rate);
         }
     }
     
     /// Запись загрузки данных
-    pub async fn record_data_load(&self, edge_count: usize, _duration: Duration) {
+    pub async fn record_data_load(&self, tenant: &str, edge_count: usize, _duration: Duration) {
         // Можно добавить специальные метрики для загрузки данных
-        self.edges_processed.inc_by(edge_count as f64);
+        self.edges_processed.with_label_values(&[tenant]).inc_by(edge_count as f64);
     }
     
     /// Запись сохранения данных
-    pub async fn record_data_save(&self, position_count: usize, _duration: Duration) {
+    pub async fn record_data_save(&self, tenant: &str, position_count: usize, _duration: Duration) {
         // Можно добавить специальные метрики для сохранения данных
-        self.vertices_processed.inc_by(position_count as f64);
+        self.vertices_processed.with_label_values(&[tenant]).inc_by(position_count as f64);
+    }
+    
+    /// Запись статистики завершённой укладки (длительности фаз, память, пересечения и длина связей)
+    pub async fn record_layout_statistics(&self, tenant: &str, stats: &LayoutStatistics) {
+        self.edge_crossings.with_label_values(&[tenant]).set(stats.edge_crossings as f64);
+        if let Some(ref routing) = stats.routing {
+            self.edge_wire_length.with_label_values(&[tenant]).set(routing.total_length as f64);
+        }
+        if let Some(ref shape) = stats.graph_shape {
+            for (direction, degrees) in [("in", &shape.in_degree), ("out", &shape.out_degree)] {
+                let Some(degrees) = degrees else { continue };
+                for (percentile, value) in [("p50", degrees.p50), ("p90", degrees.p90), ("p99", degrees.p99), ("max", degrees.max)] {
+                    self.graph_degree.with_label_values(&[tenant, direction, percentile]).set(value as f64);
+                }
+            }
+            self.graph_largest_component.with_label_values(&[tenant]).set(shape.largest_component as f64);
+            self.graph_sources.with_label_values(&[tenant]).set(shape.sources as f64);
+            self.graph_sinks.with_label_values(&[tenant]).set(shape.sinks as f64);
+            self.graph_diameter.with_label_values(&[tenant]).set(shape.approximate_diameter as f64);
+        }
+        if let Some(ref algo) = stats.algorithm_metrics {
+            self.record_topo_sort(tenant, Duration::from_millis(algo.topo_sort_time_ms.max(0) as u64)).await;
+            self.record_longest_path(tenant, Duration::from_millis(algo.longest_path_time_ms.max(0) as u64)).await;
+            self.record_placement(tenant, Duration::from_millis(algo.placement_time_ms.max(0) as u64)).await;
+        }
+        
+        let used = stats.memory_used_bytes.max(0) as f64;
+        self.memory_usage_bytes.set(used);
+        if used > self.memory_peak_bytes.get() {
+            self.memory_peak_bytes.set(used);
+        }
+    }
+    
+    /// Компактный снимок ключевых метрик для передачи вместе с результатом
+    ///
+    /// Счётчики запросов — только арендатора запроса.
+    pub fn snapshot(&self, tenant: &str, stats: &LayoutStatistics) -> MetricsSnapshot {
+        let algo = stats.algorithm_metrics.clone().unwrap_or_default();
+        
+        let edges_per_second = if stats.processing_time_ms > 0 {
+            stats.edges_processed as f32 / stats.processing_time_ms as f32 * 1000.0
+        } else {
+            0.0
+        };
+        
+        MetricsSnapshot {
+            topo_sort_time_ms: algo.topo_sort_time_ms,
+            longest_path_time_ms: algo.longest_path_time_ms,
+            placement_time_ms: algo.placement_time_ms,
+            total_time_ms: stats.processing_time_ms,
+            memory_peak_bytes: (self.memory_peak_bytes.get() as i64).max(stats.memory_used_bytes),
+            vertices_per_second: stats.vertices_per_second,
+            edges_per_second,
+            layout_requests_total: self.layout_requests_total.with_label_values(&[tenant]).get() as i64,
+            layout_requests_failed: self.layout_requests_failed.with_label_values(&[tenant]).get() as i64,
+        }
     }
     
     /// Увеличение счетчика активных задач
@@ -269,7 +389,10 @@ impl MetricsCollector {
     }
     
     /// Получение метрик в формате protobuf
-    pub async fn get_prometheus_metrics(&self) -> Vec<PrometheusMetric> {
+    ///
+    /// С `tenant` остаются только образцы этого арендатора и общие метрики
+    /// процесса (без метки `tenant`).
+    pub async fn get_prometheus_metrics(&self, tenant: Option<&str>) -> Vec<PrometheusMetric> {
         let metric_families = self.registry.gather();
         let mut metrics = Vec::new();
         
@@ -280,6 +403,14 @@ impl MetricsCollector {
                 metric_type: format!("{:?}", family.get_field_type()),
                 samples: family.get_metric()
                     .iter()
+                    .filter(|m| {
+                        tenant.map_or(true, |tenant| {
+                            m.get_label()
+                                .iter()
+                                .find(|l| l.get_name() == TENANT_LABEL[0])
+                                .map_or(true, |l| l.get_value() == tenant)
+                        })
+                    })
                     .map(|m| {
                         MetricSample {
                             label_names: m.get_label()
@@ -372,9 +503,9 @@ mod tests {
         let collector = MetricsCollector::new(&config)?;
         
         // Тест записи метрик
-        collector.record_successful_layout(Duration::from_secs(5)).await;
+        collector.record_successful_layout("default", Duration::from_secs(5)).await;
         collector.update_memory_usage(1024 * 1024, 2 * 1024 * 1024).await;
-        collector.record_processing(1000, 2000, Duration::from_secs(2)).await;
+        collector.record_processing("default", 1000, 2000, Duration::from_secs(2)).await;
         
         // Тест экспорта метрик
         let metrics = collector.export_metrics().await?;
@@ -383,4 +514,97 @@ mod tests {
         
         Ok(())
     }
+    
+    #[tokio::test]
+    async fn test_metrics_snapshot() -> Result<()> {
+        let config = MetricsConfig {
+            enabled: true,
+            collection_interval: 10,
+            prometheus_enabled: true,
+            opentelemetry_enabled: false,
+            tracing_endpoint: None,
+            detail_level: crate::config::MetricDetailLevel::Basic,
+        };
+        
+        let collector = MetricsCollector::new(&config)?;
+        
+        let stats = LayoutStatistics {
+            processing_time_ms: 2000,
+            vertices_processed: 1000,
+            edges_processed: 4000,
+            memory_used_bytes: 4096,
+            vertices_per_second: 500.0,
+            edge_crossings: 7,
+            routing: Some(crate::generated::EdgeRoutingMetrics {
+                total_length: 1250.0,
+                ..Default::default()
+            }),
+            graph_shape: Some(crate::generated::GraphShapeMetrics {
+                out_degree: Some(crate::generated::DegreeDistribution { p50: 1, p90: 4, p99: 9, max: 12 }),
+                largest_component: 900,
+                approximate_diameter: 15,
+                ..Default::default()
+            }),
+            algorithm_metrics: Some(crate::generated::AlgorithmMetrics {
+                topo_sort_time_ms: 10,
+                longest_path_time_ms: 20,
+                placement_time_ms: 30,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        
+        collector.record_layout_statistics("default", &stats).await;
+        collector.record_successful_layout("default", Duration::from_secs(2)).await;
+        let snapshot = collector.snapshot("default", &stats);
+        
+        assert_eq!(snapshot.topo_sort_time_ms, 10);
+        assert_eq!(snapshot.placement_time_ms, 30);
+        assert_eq!(snapshot.total_time_ms, 2000);
+        assert_eq!(snapshot.memory_peak_bytes, 4096);
+        assert_eq!(snapshot.edges_per_second, 2000.0);
+        assert_eq!(snapshot.layout_requests_total, 1);
+        let exported = collector.export_metrics().await?;
+        assert!(exported.contains("graph_layout_edge_crossings{tenant=\"default\"} 7"));
+        assert!(exported.contains("graph_layout_edge_wire_length{tenant=\"default\"} 1250"));
+        assert!(exported.contains("graph_layout_graph_degree{direction=\"out\",percentile=\"p99\",tenant=\"default\"} 9"));
+        assert!(exported.contains("graph_layout_graph_largest_component{tenant=\"default\"} 900"));
+        assert!(exported.contains("graph_layout_graph_diameter{tenant=\"default\"} 15"));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_metrics_are_partitioned_by_tenant() -> Result<()> {
+        let config = MetricsConfig {
+            enabled: true,
+            collection_interval: 10,
+            prometheus_enabled: true,
+            opentelemetry_enabled: false,
+            tracing_endpoint: None,
+            detail_level: crate::config::MetricDetailLevel::Basic,
+        };
+        let collector = MetricsCollector::new(&config)?;
+        
+        for _ in 0..3 {
+            collector.record_successful_layout("big", Duration::from_secs(60)).await;
+        }
+        collector.record_failed_layout("small", Duration::from_secs(1)).await;
+        
+        let snapshot = collector.snapshot("small", &LayoutStatistics::default());
+        assert_eq!(snapshot.layout_requests_total, 1);
+        assert_eq!(snapshot.layout_requests_failed, 1);
+        
+        let metrics = collector.get_prometheus_metrics(Some("small")).await;
+        let requests = metrics
+            .iter()
+            .find(|m| m.name == "graph_layout_requests_total")
+            .expect("счётчик запросов");
+        assert_eq!(requests.samples.len(), 1);
+        assert_eq!(requests.samples[0].label_values, vec!["small".to_string()]);
+        assert_eq!(requests.samples[0].value, 1.0);
+        assert!(metrics.iter().any(|m| m.name == "graph_layout_memory_usage_bytes"));
+        
+        Ok(())
+    }
 }
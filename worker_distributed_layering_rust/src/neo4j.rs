@@ -5,12 +5,14 @@
 Пока что реализует только базовую функциональность без сложных зависимостей.
 */
 
-use crate::config::Config;
+use crate::config::{Config, EdgePagination, TlsVerifyMode};
+use crate::messages::Msg;
+use crate::retry::{with_retries, RetryPolicy};
 use anyhow::Result;
 use neo4rs::BoltType;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio_stream::Stream;
 use tracing::info;
 use neo4rs::*;
 
@@ -25,18 +27,247 @@ pub struct Neo4jConfig {
     pub connection_timeout: u64,
     pub transaction_timeout: u64,
     pub batch_size: usize,
+    /// Загружаемые типы связей и их веса (пусто — все типы с весом 1.0)
+    pub relationship_weights: Vec<(String, f32)>,
+    /// Доля загружаемых связей (1.0 — все связи)
+    pub sample_rate: f64,
+    /// Максимальное количество загружаемых связей (0 — без ограничения)
+    pub max_edges: usize,
+    /// Файл карантина для несохранённых позиций
+    pub save_quarantine_path: String,
+    /// Детерминированная загрузка: ORDER BY и выборка по seed
+    pub deterministic: bool,
+    /// Seed выборки связей
+    pub seed: u64,
+    /// Пагинация батчевой загрузки связей (Keyset или Skip, Auto уже разрешён)
+    pub pagination: EdgePagination,
+    /// Свойство связи с range-индексом для keyset-пагинации (пусто — `elementId(r)`)
+    pub keyset_property: String,
+    /// Свойство Article с годом публикации
+    pub year_property: String,
+    /// Количество частей связей, загружаемых параллельно по отдельным соединениям
+    pub load_parallelism: usize,
+    /// Атомарное сохранение через временные свойства `*_new`
+    pub atomic_save: bool,
+    /// Политика повторов запросов
+    pub retry: RetryPolicy,
+    /// Проверка сертификата сервера (Auto — по схеме `uri`)
+    pub tls_verify: TlsVerifyMode,
+    /// PEM с корневым сертификатом (пусто — системные корни)
+    pub tls_ca_cert_path: String,
+}
+
+impl Neo4jConfig {
+    /// Шаблон связи для MATCH: `[r]` или `[r:A|B]` при заданных типах
+    pub fn relationship_pattern(&self) -> String {
+        format!("[r{}]", self.relationship_types_clause())
+    }
+
+    /// Типы связей для шаблонов без переменной: `` или `:A|B`
+    pub fn relationship_types_clause(&self) -> String {
+        if self.relationship_weights.is_empty() {
+            return String::new();
+        }
+        let types: Vec<String> = self
+            .relationship_weights
+            .iter()
+            .map(|(name, _)| format!("`{}`", name.replace('`', "")))
+            .collect();
+        format!(":{}", types.join("|"))
+    }
+
+    /// Условие выборки связей для предварительных укладок (пусто, если выборка не нужна)
+    ///
+    /// Обычно это `rand()`. В детерминированном режиме связь отбирается по хешу
+    /// `id(r)` и seed: решение не зависит от порядка строк и страниц, поэтому
+    /// батчевая, полная загрузка и подсчёт связей дают одну и ту же выборку.
+    /// Хеш считается по модулю простого числа, чтобы целые Cypher не переполнялись.
+    pub fn sampling_clause(&self) -> String {
+        if self.sample_rate <= 0.0 || self.sample_rate >= 1.0 {
+            return String::new();
+        }
+        if !self.deterministic {
+            return format!(" AND rand() < {}", self.sample_rate);
+        }
+        const MODULUS: u64 = 1_000_003;
+        let salt = self.seed % MODULUS;
+        let threshold = (self.sample_rate * MODULUS as f64).ceil() as u64;
+        format!(
+            " AND (((id(r) % {m}) * 7919 + {salt}) % {m} * 104729 + {salt}) % {m} < {threshold}",
+            m = MODULUS,
+            salt = salt,
+            threshold = threshold
+        )
+    }
+
+    /// Сортировка результата в детерминированном режиме (иначе пусто)
+    pub fn order_clause(&self) -> &'static str {
+        if self.deterministic {
+            "\n            ORDER BY source, target, edge_type"
+        } else {
+            ""
+        }
+    }
+
+    /// Таймаут подключения
+    pub fn connection_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.connection_timeout.max(1))
+    }
+
+    /// Таймаут одного запроса/транзакции
+    pub fn query_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.transaction_timeout.max(1))
+    }
+
+    /// URI подключения с TLS по `tls_verify` после проверки параметров подключения
+    ///
+    /// Схемы `bolt://` и `neo4j://` подключают к серверу из URI напрямую:
+    /// драйвер neo4rs 0.7 не запрашивает таблицу маршрутизации, поэтому для
+    /// кластера в URI указывается сервер, принимающий запись, а запросы не
+    /// распределяются по репликам и не переключаются при смене лидера.
+    /// Суффикс `+s` включает TLS с проверкой сертификата, `+ssc` — без неё.
+    /// Ошибка описывает неизвестную схему, противоречие схемы и `tls_verify`
+    /// или недоступный файл CA.
+    pub fn connection_uri(&self) -> Result<String> {
+        let (scheme, address) = self
+            .uri
+            .split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("URI Neo4j без схемы: '{}'", self.uri))?;
+        let (base, scheme_tls) = match scheme {
+            "bolt" | "neo4j" => (scheme, TlsVerifyMode::Disabled),
+            "bolt+s" | "neo4j+s" => (&scheme[..scheme.len() - 2], TlsVerifyMode::Full),
+            "bolt+ssc" | "neo4j+ssc" => (&scheme[..scheme.len() - 4], TlsVerifyMode::TrustAll),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Неподдерживаемая схема URI Neo4j '{}' (bolt или neo4j, с суффиксом +s или +ssc)",
+                    scheme
+                ))
+            }
+        };
+        let verify = match self.tls_verify {
+            TlsVerifyMode::Auto => scheme_tls,
+            explicit if scheme_tls == TlsVerifyMode::Disabled || scheme_tls == explicit => explicit,
+            explicit => {
+                return Err(anyhow::anyhow!(
+                    "Схема URI Neo4j '{}' противоречит neo4j.tls.verify = {:?}",
+                    scheme,
+                    explicit
+                ))
+            }
+        };
+
+        if !self.tls_ca_cert_path.is_empty() {
+            if verify != TlsVerifyMode::Full {
+                return Err(anyhow::anyhow!(
+                    "neo4j.tls.ca_cert_path используется только с проверкой сертификата (схема +s или tls.verify = Full)"
+                ));
+            }
+            if !std::path::Path::new(&self.tls_ca_cert_path).is_file() {
+                return Err(anyhow::anyhow!("Файл CA для Neo4j не найден: '{}'", self.tls_ca_cert_path));
+            }
+        }
+
+        let suffix = match verify {
+            TlsVerifyMode::Full => "+s",
+            TlsVerifyMode::TrustAll => "+ssc",
+            TlsVerifyMode::Disabled | TlsVerifyMode::Auto => "",
+        };
+        Ok(format!("{}{}://{}", base, suffix, address))
+    }
+
+    /// Ключ связи для keyset-пагинации: `r.<keyset_property>` или `elementId(r)`
+    ///
+    /// Значения свойства могут повторяться, поэтому курсор составной:
+    /// (ключ, `elementId(r)`). Для `elementId(r)` индекса нет — каждый батч
+    /// просматривает и сортирует все подходящие связи, и полная загрузка
+    /// квадратична по числу связей; на больших графах задавайте
+    /// `keyset_property` со range-индексом.
+    pub fn keyset_key(&self) -> String {
+        if self.keyset_property.is_empty() {
+            "elementId(r)".to_string()
+        } else {
+            format!("r.`{}`", self.keyset_property.replace('`', ""))
+        }
+    }
+
+    /// Столбцы `source_year` и `target_year`: свойство `year_property` концов связи
+    pub fn year_columns(&self) -> String {
+        format!(
+            "toString(a.`{0}`) as source_year, toString(b.`{0}`) as target_year",
+            self.year_property.replace('`', "")
+        )
+    }
+
+    /// Вес связи по её типу
+    pub fn edge_weight(&self, edge_type: &str) -> f32 {
+        self.relationship_weights
+            .iter()
+            .find(|(name, _)| name == edge_type)
+            .map(|(_, weight)| *weight)
+            .unwrap_or(1.0)
+    }
+}
+
+/// Выполнение операции Neo4j с таймаутом
+///
+/// Ошибка таймаута и ошибка самой операции содержат имя операции.
+/// С фичей `failure-injection` здесь же внедряются сбои точки `Neo4jOperation`.
+pub async fn with_timeout<T, E, F>(operation: &str, timeout: std::time::Duration, fut: F) -> Result<T>
+where
+    F: std::future::Future<Output = std::result::Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    use crate::failure_injection::{Fault, FaultPoint};
+
+    let timeout_error = || anyhow::anyhow!(
+        "Таймаут операции Neo4j '{}' (> {} сек)",
+        operation,
+        timeout.as_secs()
+    );
+    let delay = match crate::failure_injection::take(FaultPoint::Neo4jOperation, operation) {
+        Some(Fault::Timeout) => return Err(timeout_error()),
+        Some(Fault::Error) => {
+            return Err(anyhow::anyhow!("внедрённый сбой")
+                .context(format!("Ошибка операции Neo4j '{}'", operation)))
+        }
+        Some(Fault::Delay(delay)) => Some(delay),
+        None => None,
+    };
+
+    // Задержка медленного ответа входит в таймаут, как и настоящая
+    let fut = async move {
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        fut.await
+    };
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.into().context(format!("Ошибка операции Neo4j '{}'", operation))),
+        Err(_) => Err(timeout_error()),
+    }
+}
+
+/// Год из значения свойства: "1998", "1998-05-01" или число
+fn parse_year(value: &str) -> Option<i32> {
+    value.trim().get(..4).and_then(|year| year.parse::<i32>().ok())
+}
+
+/// Год из необязательной колонки строки результата (NULL — год неизвестен)
+fn row_year(row: &Row, column: &str) -> Option<i32> {
+    row.get::<String>(column).ok().and_then(|value| parse_year(&value))
 }
 
 /// Клиент для работы с Neo4j (упрощенная версия)
+///
+/// Клоны делят одно подключение (`Arc<Graph>`).
+#[derive(Clone)]
 pub struct Neo4jClient {
     /// Конфигурация
     config: Neo4jConfig,
     
     /// Neo4j Graph connection
     graph: Arc<Graph>,
-    
-    /// Статус подключения
-    connected: Arc<RwLock<bool>>,
 }
 
 impl Neo4jClient {
@@ -53,25 +284,73 @@ impl Neo4jClient {
             connection_timeout: config.neo4j.connection_timeout,
             transaction_timeout: config.neo4j.transaction_timeout,
             batch_size: config.neo4j.batch_size,
+            relationship_weights: config.neo4j.relationship_types
+                .iter()
+                .map(|t| (t.name.clone(), t.weight))
+                .collect(),
+            sample_rate: config.neo4j.sample_rate.clamp(0.0, 1.0),
+            max_edges: config.neo4j.max_edges,
+            save_quarantine_path: config.neo4j.save_quarantine_path.clone(),
+            deterministic: config.algorithms.deterministic,
+            seed: config.algorithms.seed,
+            pagination: config.neo4j.pagination.resolve(&config.neo4j.keyset_property),
+            keyset_property: config.neo4j.keyset_property.clone(),
+            year_property: config.neo4j.year_property.clone(),
+            // Порядок связей между частями не определён, поэтому детерминированная загрузка — одним курсором
+            load_parallelism: if config.algorithms.deterministic { 1 } else { config.neo4j.load_parallelism.max(1) },
+            atomic_save: config.neo4j.atomic_save,
+            retry: RetryPolicy::from_config(&config.neo4j.retry),
+            tls_verify: config.neo4j.tls.verify,
+            tls_ca_cert_path: config.neo4j.tls.ca_cert_path.clone(),
         };
+        // Ошибки параметров подключения видны при старте, до попытки соединения
+        let uri = neo4j_config.connection_uri()?;
         
         info!("📡 Параметры подключения: uri={}, database={}, pool_size={}", 
-              neo4j_config.uri, neo4j_config.database, neo4j_config.pool_size);
+              uri, neo4j_config.database, neo4j_config.pool_size);
+        if !neo4j_config.tls_ca_cert_path.is_empty() {
+            info!("🔒 Сертификат сервера проверяется по CA из {}", neo4j_config.tls_ca_cert_path);
+        }
+        if !neo4j_config.relationship_weights.is_empty() {
+            info!("🔗 Типы связей и веса: {:?}", neo4j_config.relationship_weights);
+        }
+        if neo4j_config.load_parallelism > 1 {
+            info!("🔀 Параллельная загрузка связей: {} частей", neo4j_config.load_parallelism);
+        }
+        if neo4j_config.pagination == EdgePagination::Keyset {
+            info!("📑 Keyset-пагинация связей по {}", neo4j_config.keyset_key());
+            if neo4j_config.keyset_property.is_empty() {
+                tracing::warn!("⚠️ Keyset по elementId(r) без индекса: каждый батч сортирует все связи; задайте neo4j.keyset_property");
+            }
+        }
+        if neo4j_config.sample_rate < 1.0 || neo4j_config.max_edges > 0 {
+            info!("🎲 Выборка связей: sample_rate={}, max_edges={}",
+                  neo4j_config.sample_rate, neo4j_config.max_edges);
+        }
         
         // Создаем подключение к Neo4j
         info!("🔧 Создание конфигурации Neo4j...");
-        let graph_config = ConfigBuilder::default()
-            .uri(&neo4j_config.uri)
+        let mut graph_config = ConfigBuilder::default()
+            .uri(&uri)
             .user(&neo4j_config.user)
             .password(&neo4j_config.password)
             .db(&*neo4j_config.database)
+            .max_connections(neo4j_config.pool_size.max(neo4j_config.load_parallelism as u32) as usize);
+        if !neo4j_config.tls_ca_cert_path.is_empty() {
+            graph_config = graph_config.with_client_certificate(std::path::Path::new(&neo4j_config.tls_ca_cert_path));
+        }
+        let graph_config = graph_config
             .build()
-            .expect("Failed to build Neo4j config");
+            .map_err(|e| anyhow::anyhow!("Некорректная конфигурация подключения к Neo4j: {}", e))?;
         
-        info!("🔌 Установка соединения с Neo4j...");
+        info!("🔌 Установка соединения с Neo4j (таймаут {} сек)...", neo4j_config.connection_timeout().as_secs());
         let start_connect = std::time::Instant::now();
         
-        let graph = Graph::connect(graph_config).await.expect("Failed to connect to Neo4j");
+        let graph = with_timeout(
+            "подключение к Neo4j",
+            neo4j_config.connection_timeout(),
+            Graph::connect(graph_config),
+        ).await?;
         
         let connect_time = start_connect.elapsed();
         info!("✅ Соединение с Neo4j установлено за {:.2?}", connect_time);
@@ -79,57 +358,106 @@ impl Neo4jClient {
         Ok(Self {
             config: neo4j_config,
             graph: Arc::new(graph),
-            connected: Arc::new(RwLock::new(true)),
         })
     }
 
+    /// Ограничение количества загружаемых связей (0 — без ограничения)
+    pub fn max_edges(&self) -> usize {
+        self.config.max_edges
+    }
+
     /// Получить ссылку на граф
     pub fn graph(&self) -> Arc<Graph> {
         Arc::clone(&self.graph)
     }
 
-    /// Подключение к Neo4j (заглушка)
-    pub async fn connect(&self) -> Result<()> {
-        info!("🔌 Подключение к Neo4j: {}", self.config.uri);
-        
-        // Имитация подключения
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        
-        let mut connected = self.connected.write().await;
-        *connected = true;
-        
-        info!("✅ Neo4j клиент подключен");
-        Ok(())
+    /// Выполнение произвольного читающего запроса с параметрами
+    ///
+    /// Запрос повторяется по `neo4j.retry`, поэтому он должен только читать:
+    /// при неизвестном исходе коммита повтор применил бы запись дважды. Запросы
+    /// с записью (`CREATE`, `MERGE`, `SET`, `DELETE`) выполняйте через
+    /// `execute_write`. Каждая строка результата — словарь «колонка → значение
+    /// Bolt»; для разбора в свою структуру удобнее `execute_query_as`.
+    pub async fn execute_query(&self, query: &str, params: Option<HashMap<String, BoltType>>) -> Result<Vec<HashMap<String, BoltType>>> {
+        self.execute_query_as(query, params).await
     }
-    
-    /// Отключение от Neo4j
-    pub async fn close(&self) -> Result<()> {
-        info!("🔌 Отключение от Neo4j");
-        
-        let mut connected = self.connected.write().await;
-        *connected = false;
-        
-        info!("✅ Neo4j клиент отключен");
-        Ok(())
+
+    /// Выполнение читающего запроса с разбором строк в `T` (колонки — поля структуры)
+    ///
+    /// Повторяется по `neo4j.retry`, как `execute_query`. Строка, не подходящая
+    /// под `T`, прерывает запрос ошибкой с её номером.
+    pub async fn execute_query_as<T>(&self, query: &str, params: Option<HashMap<String, BoltType>>) -> Result<Vec<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        self.run_query_as("execute_query", query, params, &self.config.retry).await
     }
-    
-    /// Выполнение запроса (заглушка)
-    pub async fn execute_query(&self, query: &str, _params: Option<HashMap<String, BoltType>>) -> Result<Vec<HashMap<String, BoltType>>> {
-        info!("📝 Выполнение запроса: {}", query);
-        
-        // Проверка подключения
-        {
-            let connected = self.connected.read().await;
-            if !*connected {
-                return Err(anyhow::anyhow!("Не подключен к Neo4j"));
-            }
+
+    /// Выполнение запроса с записью: один раз, без повторов
+    ///
+    /// Если соединение оборвалось после отправки, исход коммита неизвестен, и
+    /// ошибка возвращается вызывающему — он сам решает, безопасно ли повторять.
+    pub async fn execute_write(&self, query: &str, params: Option<HashMap<String, BoltType>>) -> Result<Vec<HashMap<String, BoltType>>> {
+        let once = self.config.retry.clone().with_max_attempts(1);
+        self.run_query_as("execute_write", query, params, &once).await
+    }
+
+    async fn run_query_as<T>(
+        &self,
+        label: &'static str,
+        query: &str,
+        params: Option<HashMap<String, BoltType>>,
+        policy: &RetryPolicy,
+    ) -> Result<Vec<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        tracing::debug!("📝 Выполнение запроса: {}", query);
+
+        let mut q = Query::new(query.to_string());
+        for (name, value) in params.unwrap_or_default() {
+            q = q.param(&name, value);
         }
-        
-        // Имитация выполнения запроса
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-        
-        // Возвращаем пустой результат для заглушки
-        Ok(vec![])
+
+        self.fetch_rows_with(policy, label, "выполнение запроса", q)
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.to::<T>()
+                    .map_err(|e| anyhow::anyhow!("Строка {} результата запроса не разобрана: {}", i + 1, e))
+            })
+            .collect()
+    }
+
+    /// Выполнение читающего запроса с повторами по `neo4j.retry`
+    ///
+    /// Повтор заново выполняет весь запрос, поэтому строки возвращаются только
+    /// после чтения всего результата. `label` — метка операции в метриках.
+    async fn fetch_rows(&self, label: &'static str, operation: &str, query: Query) -> Result<Vec<Row>> {
+        self.fetch_rows_with(&self.config.retry, label, operation, query).await
+    }
+
+    /// Выполнение запроса с чтением всего результата по политике повторов `policy`
+    async fn fetch_rows_with(
+        &self,
+        policy: &RetryPolicy,
+        label: &'static str,
+        operation: &str,
+        query: Query,
+    ) -> Result<Vec<Row>> {
+        let query_timeout = self.config.query_timeout();
+        with_retries(policy, label, |_| {
+            let query = query.clone();
+            async move {
+                let mut result = with_timeout(operation, query_timeout, self.graph.execute(query)).await?;
+                let mut rows = Vec::new();
+                while let Some(row) = with_timeout(operation, query_timeout, result.next()).await? {
+                    rows.push(row);
+                }
+                Ok(rows)
+            }
+        }).await
     }
     
     /// Загрузка связей графа из Neo4j
@@ -143,8 +471,8 @@ impl Neo4jClient {
         LIMIT 1
         "#;
         
-        let mut schema_result = self.graph.execute(schema_query.into()).await?;
-        if let Ok(Some(row)) = schema_result.next().await {
+        let schema_rows = self.fetch_rows("edges_diagnostics", "проверка схемы Article", schema_query.into()).await?;
+        if let Some(row) = schema_rows.first() {
             if let Ok(properties) = row.get::<Vec<String>>("article_properties") {
                 info!("🔍 Свойства Article узлов: {:?}", properties);
             }
@@ -156,8 +484,8 @@ impl Neo4jClient {
         RETURN count(n) as node_count
         "#;
         
-        let mut count_result = self.graph.execute(count_query.into()).await?;
-        if let Ok(Some(row)) = count_result.next().await {
+        let count_rows = self.fetch_rows("edges_diagnostics", "подсчёт статей", count_query.into()).await?;
+        if let Some(row) = count_rows.first() {
             if let Ok(count) = row.get::<i64>("node_count") {
                 info!("📊 Всего Article узлов в БД: {}", count);
             }
@@ -169,8 +497,8 @@ impl Neo4jClient {
         RETURN count(r) as edge_count
         "#;
         
-        let mut edges_count_result = self.graph.execute(edges_count_query.into()).await?;
-        if let Ok(Some(row)) = edges_count_result.next().await {
+        let edges_count_rows = self.fetch_rows("edges_diagnostics", "подсчёт связей", edges_count_query.into()).await?;
+        if let Some(row) = edges_count_rows.first() {
             if let Ok(count) = row.get::<i64>("edge_count") {
                 info!("🔗 Всего связей в БД: {}", count);
             }
@@ -183,8 +511,8 @@ impl Neo4jClient {
         RETURN count(n) as empty_uid_count
         "#;
         
-        let mut empty_uid_result = self.graph.execute(empty_uid_query.into()).await?;
-        if let Ok(Some(row)) = empty_uid_result.next().await {
+        let empty_uid_rows = self.fetch_rows("edges_diagnostics", "подсчёт статей без uid", empty_uid_query.into()).await?;
+        if let Some(row) = empty_uid_rows.first() {
             if let Ok(count) = row.get::<i64>("empty_uid_count") {
                 info!("⚠️ Узлов с пустым uid: {}", count);
             }
@@ -198,10 +526,10 @@ impl Neo4jClient {
         LIMIT 5
         "#;
         
-        let mut sample_result = self.graph.execute(sample_edges_query.into()).await?;
+        let sample_rows = self.fetch_rows("edges_diagnostics", "примеры связей", sample_edges_query.into()).await?;
         info!("📝 Примеры связей:");
         let mut sample_count = 0;
-        while let Ok(Some(row)) = sample_result.next().await {
+        for row in &sample_rows {
             if sample_count < 5 {
                 let source: String = row.get("source").unwrap_or_default();
                 let target: String = row.get("target").unwrap_or_default();
@@ -212,175 +540,769 @@ impl Neo4jClient {
         }
         
             // Используем правильное поле uid вместо id
-            let query = r#"
-            MATCH (a:Article)-[r]->(b:Article)
-            WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL 
-            RETURN a.uid as source, b.uid as target, type(r) as edge_type
-            "#;
+            let query = format!(
+                r#"
+            MATCH (a:Article)-{}->(b:Article)
+            WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL{}
+            RETURN a.uid as source, b.uid as target, type(r) as edge_type,
+                   {}{}
+            "#,
+                self.config.relationship_pattern(),
+                self.config.sampling_clause(),
+                self.config.year_columns(),
+                self.config.order_clause()
+            );
         
-        let mut result = self.graph.execute(query.into()).await?;
-        let mut edges = Vec::new();
-        let mut batch_count = 0;
-        let mut total_loaded = 0;
+        let query_timeout = self.config.query_timeout();
+        let query = Query::new(query);
+        // Повтор начинает загрузку заново, с пустой выборкой
+        let (edges, total_loaded) = with_retries(&self.config.retry, "load_all_edges", |_| {
+            let query = query.clone();
+            async move {
+                let mut result = with_timeout(
+                    "загрузка всех связей",
+                    query_timeout,
+                    self.graph.execute(query),
+                ).await?;
+                let mut edges = EdgeReservoir::new(self.config.max_edges, 0x9E3779B97F4A7C15 ^ self.config.seed);
+                let mut batch_count = 0;
+                let mut total_loaded = 0;
         
-        info!("📥 Начинаем загрузку всех связей из Neo4j...");
-        let start_time = std::time::Instant::now();
+                info!("📥 Начинаем загрузку всех связей из Neo4j...");
+                let start_time = std::time::Instant::now();
         
-        while let Ok(Some(row)) = result.next().await {
-            let source: String = row.get("source").unwrap_or_default();
-            let target: String = row.get("target").unwrap_or_default();
-            let edge_type: String = row.get("edge_type").unwrap_or_else(|_| "RELATES_TO".to_string());
+                while let Some(row) = with_timeout("чтение связей", query_timeout, result.next()).await? {
+                    let source: String = row.get("source").unwrap_or_default();
+                    let target: String = row.get("target").unwrap_or_default();
+                    let edge_type: String = row.get("edge_type").unwrap_or_else(|_| "RELATES_TO".to_string());
+                    let weight = self.config.edge_weight(&edge_type);
             
+                    total_loaded += 1;
+                    batch_count += 1;
+            
+                    edges.push(GraphEdge {
+                        source_id: source,
+                        target_id: target,
+                        edge_type,
+                        weight,
+                        source_cluster: None,
+                        target_cluster: None,
+                        source_year: row_year(&row, "source_year"),
+                        target_year: row_year(&row, "target_year"),
+                    });
+            
+                    // Показываем прогресс каждые 100,000 связей
+                    if batch_count >= 100_000 {
+                        let elapsed = start_time.elapsed();
+                        let rate = total_loaded as f64 / elapsed.as_secs_f64();
+                        info!("📊 Загружено {} связей (скорость: {:.0} связей/сек)", total_loaded, rate);
+                        batch_count = 0;
+                    }
+                }
+        
+                let total_time = start_time.elapsed();
+                let rate = total_loaded as f64 / total_time.as_secs_f64();
+                info!("✅ Загружено {} связей из Neo4j за {:.2?} (скорость: {:.0} связей/сек)", 
+                       total_loaded, total_time, rate);
+                Ok((edges.into_inner(), total_loaded))
+            }
+        }).await?;
+        if edges.len() < total_loaded {
+            info!("🎲 Reservoir sampling: оставлено {} из {} связей", edges.len(), total_loaded);
+        }
+        Ok(edges)
+    }
+    
+    /// Загрузка связей k-окрестности вершины
+    ///
+    /// Обход идёт без учёта направления на глубину `radius`; в окрестность
+    /// попадает не больше `max_vertices` вершин (включая центр), ближайшие
+    /// по числу переходов, при равенстве — по uid, так что отсечение не зависит
+    /// от порядка обхода. Возвращаются все связи между вершинами окрестности
+    /// в исходном направлении; None — статьи `center_id` нет.
+    pub async fn load_neighborhood_edges(
+        &self,
+        center_id: &str,
+        radius: u32,
+        max_vertices: usize,
+    ) -> Result<Option<Vec<GraphEdge>>> {
+        use neo4rs::Query;
+        
+        // OPTIONAL MATCH оставляет строку с пустой связью для центра без связей,
+        // а отсутствие строк означает, что центра нет
+        let query = Query::new(format!(
+            "MATCH (c:Article {{uid: $center}}) \
+             MATCH p = (c)-[{types}*0..{radius}]-(n:Article) \
+             WHERE n.uid IS NOT NULL \
+             WITH n, min(length(p)) AS hops \
+             ORDER BY hops, n.uid LIMIT $max_vertices \
+             WITH collect(n) AS nodes \
+             UNWIND nodes AS a \
+             OPTIONAL MATCH (a)-{pattern}->(b:Article) \
+             WHERE b IN nodes \
+             RETURN a.uid AS source, b.uid AS target, type(r) AS edge_type, \
+                    {years}{order}",
+            types = self.config.relationship_types_clause(),
+            years = self.config.year_columns(),
+            radius = radius,
+            pattern = self.config.relationship_pattern(),
+            order = self.config.order_clause(),
+        ))
+        .param("center", center_id.to_string())
+        .param("max_vertices", max_vertices as i64);
+        
+        let rows = self.fetch_rows("load_neighborhood", "загрузка окрестности вершины", query).await?;
+        if rows.is_empty() {
+            info!("🔭 Статья '{}' не найдена", center_id);
+            return Ok(None);
+        }
+        
+        let mut edges = Vec::new();
+        for row in rows {
+            let Ok(target_id) = row.get::<String>("target") else {
+                continue;
+            };
+            let edge_type: String = row.get("edge_type").unwrap_or_else(|_| "RELATES_TO".to_string());
+            let weight = self.config.edge_weight(&edge_type);
             edges.push(GraphEdge {
-                source_id: source,
-                target_id: target,
+                source_id: row.get("source").unwrap_or_default(),
+                target_id,
                 edge_type,
-                weight: 1.0,
+                weight,
+                source_cluster: None,
+                target_cluster: None,
+                source_year: row_year(&row, "source_year"),
+                target_year: row_year(&row, "target_year"),
             });
-            
-            total_loaded += 1;
-            batch_count += 1;
-            
-            // Показываем прогресс каждые 100,000 связей
-            if batch_count >= 100_000 {
-                let elapsed = start_time.elapsed();
-                let rate = total_loaded as f64 / elapsed.as_secs_f64();
-                info!("📊 Загружено {} связей (скорость: {:.0} связей/сек)", total_loaded, rate);
-                batch_count = 0;
+        }
+        
+        info!("🔭 Окрестность '{}' (радиус {}): {} связей", center_id, radius, edges.len());
+        Ok(Some(edges))
+    }
+    
+    /// Загрузка времени публикации статей (год) из свойства `property`
+    ///
+    /// Значения хранятся строками ("1998") или числами; нераспознанные пропускаются.
+    pub async fn load_vertex_times(&self, property: &str) -> Result<HashMap<String, i32>> {
+        if property.is_empty() || !property.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("Недопустимое имя свойства времени: '{}'", property));
+        }
+        
+        let query = format!(
+            "MATCH (n:Article) WHERE n.uid IS NOT NULL AND n.{0} IS NOT NULL \
+             RETURN n.uid AS uid, toString(n.{0}) AS time",
+            property
+        );
+        
+        let rows = self.fetch_rows("load_vertex_times", "загрузка времени публикации", query.into()).await?;
+        
+        let mut times = HashMap::new();
+        let mut skipped = 0usize;
+        for row in rows {
+            let uid: String = row.get("uid").unwrap_or_default();
+            let time: String = row.get("time").unwrap_or_default();
+            match parse_year(&time) {
+                Some(year) if !uid.is_empty() => {
+                    times.insert(uid, year);
+                }
+                _ => skipped += 1,
             }
         }
         
-        let total_time = start_time.elapsed();
-        let rate = total_loaded as f64 / total_time.as_secs_f64();
-        info!("✅ Загружено {} связей из Neo4j за {:.2?} (скорость: {:.0} связей/сек)", 
-               total_loaded, total_time, rate);
-        Ok(edges)
+        info!("🕰️ Загружено время публикации для {} статей (пропущено {})", times.len(), skipped);
+        Ok(times)
+    }
+    
+    /// Загрузка размеров блоков статей из свойств `width_property` / `height_property`
+    ///
+    /// Отсутствующее свойство, значение или неположительное значение заменяется
+    /// размером по умолчанию `default_size`; статьи без обоих значений не возвращаются.
+    pub async fn load_vertex_sizes(
+        &self,
+        width_property: Option<&str>,
+        height_property: Option<&str>,
+        default_size: (f32, f32),
+    ) -> Result<HashMap<String, (f32, f32)>> {
+        let mut expressions = Vec::with_capacity(2);
+        for property in [width_property, height_property] {
+            match property {
+                Some(property) if property.is_empty() || !property.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                    return Err(anyhow::anyhow!("Недопустимое имя свойства размера: '{}'", property));
+                }
+                Some(property) => expressions.push(format!("toFloat(n.{})", property)),
+                None => expressions.push("null".to_string()),
+            }
+        }
+        if width_property.is_none() && height_property.is_none() {
+            return Ok(HashMap::new());
+        }
+        
+        let query = format!(
+            "MATCH (n:Article) WHERE n.uid IS NOT NULL \
+             WITH n.uid AS uid, {0} AS width, {1} AS height \
+             WHERE width IS NOT NULL OR height IS NOT NULL \
+             RETURN uid, width, height",
+            expressions[0], expressions[1]
+        );
+        
+        let rows = self.fetch_rows("load_vertex_sizes", "загрузка размеров блоков", query.into()).await?;
+        
+        let mut sizes = HashMap::new();
+        for row in rows {
+            let uid: String = row.get("uid").unwrap_or_default();
+            if uid.is_empty() {
+                continue;
+            }
+            let dimension = |column: &str, default: f32| {
+                row.get::<f64>(column)
+                    .ok()
+                    .map(|value| value as f32)
+                    .filter(|value| *value > 0.0)
+                    .unwrap_or(default)
+            };
+            sizes.insert(uid, (dimension("width", default_size.0), dimension("height", default_size.1)));
+        }
+        
+        info!("📐 Загружены размеры блоков для {} статей", sizes.len());
+        Ok(sizes)
     }
     
+    /// Загрузка сохранённой укладки (статьи с заданными layer/level)
+    ///
+    /// Используется инкрементальной укладкой как исходное состояние.
+    pub async fn load_layout_positions(&self) -> Result<Vec<VertexPosition>> {
+        let query = "MATCH (n:Article) WHERE n.uid IS NOT NULL AND n.layer IS NOT NULL AND n.level IS NOT NULL \
+                     RETURN n.uid AS uid, n.layer AS layer, n.level AS level, \
+                            coalesce(n.x, 0.0) AS x, coalesce(n.y, 0.0) AS y";
+        
+        let rows = self.fetch_rows("load_layout_positions", "загрузка сохранённой укладки", query.into()).await?;
+        
+        let mut positions = Vec::new();
+        for row in rows {
+            let article_id: String = row.get("uid").unwrap_or_default();
+            if article_id.is_empty() {
+                continue;
+            }
+            positions.push(VertexPosition {
+                article_id,
+                layer: row.get::<i64>("layer").unwrap_or_default() as i32,
+                level: row.get::<i64>("level").unwrap_or_default() as i32,
+                x: row.get::<f64>("x").unwrap_or_default() as f32,
+                y: row.get::<f64>("y").unwrap_or_default() as f32,
+            });
+        }
+        
+        info!("📍 Загружено {} сохранённых позиций", positions.len());
+        Ok(positions)
+    }
+
+    /// Загрузка связей, созданных или изменённых после `since`
+    ///
+    /// Время связи — `coalesce(r.updated_at, r.created_at)` (значения Neo4j
+    /// `datetime`); связи без этих свойств не возвращаются. Удалённые связи так
+    /// не увидеть — их учитывает только полная укладка.
+    pub async fn load_graph_edges_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<GraphEdge>> {
+        let query = Query::new(format!(
+            "MATCH (a:Article)-{}->(b:Article) \
+             WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL \
+               AND coalesce(r.updated_at, r.created_at) > datetime({{epochMillis: $since}}){} \
+             RETURN a.uid as source, b.uid as target, type(r) as edge_type, {}{}",
+            self.config.relationship_pattern(),
+            self.config.sampling_clause(),
+            self.config.year_columns(),
+            self.config.order_clause(),
+        ))
+        .param("since", since.timestamp_millis());
+
+        let rows = self.fetch_rows("load_edges_since", "загрузка изменённых связей", query).await?;
+
+        let edges: Vec<GraphEdge> = rows.iter().map(|row| self.edge_from_row(row)).collect();
+
+        info!("🆕 Загружено {} связей, изменённых после {}", edges.len(), since.to_rfc3339());
+        Ok(edges)
+    }
+
+    /// Число связей, созданных или изменённых после `since` (без условия выборки)
+    ///
+    /// Время связи то же, что у `load_graph_edges_since`. По нему решается,
+    /// устарел ли файл графа, поэтому учитываются все связи, а не только
+    /// попавшие бы в выборку.
+    pub async fn count_edges_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<usize> {
+        let query = Query::new(format!(
+            "MATCH (a:Article)-{}->(b:Article) \
+             WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL \
+               AND coalesce(r.updated_at, r.created_at) > datetime({{epochMillis: $since}}) \
+             RETURN count(r) AS total_count",
+            self.config.relationship_pattern(),
+        ))
+        .param("since", since.timestamp_millis());
+
+        let rows = self.fetch_rows("count_edges_since", "подсчёт изменённых связей", query).await?;
+        let count = rows
+            .first()
+            .and_then(|row| row.get::<i64>("total_count").ok())
+            .ok_or_else(|| anyhow::anyhow!("Подсчёт изменённых связей не вернул поле total_count"))?;
+        Ok(count as usize)
+    }
+
+    /// Загрузка исходящих связей статей `uids`
+    ///
+    /// Инкрементальная укладка догружает их для вершин, сменивших слой, чтобы
+    /// сдвинуть следующие за ними вершины.
+    pub async fn load_outgoing_edges(&self, uids: &[String]) -> Result<Vec<GraphEdge>> {
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = Query::new(format!(
+            "UNWIND $uids AS uid \
+             MATCH (a:Article {{uid: uid}})-{}->(b:Article) \
+             WHERE b.uid IS NOT NULL{} \
+             RETURN a.uid as source, b.uid as target, type(r) as edge_type, {}{}",
+            self.config.relationship_pattern(),
+            self.config.sampling_clause(),
+            self.config.year_columns(),
+            self.config.order_clause(),
+        ))
+        .param("uids", uids.to_vec());
+
+        let rows = self.fetch_rows("load_outgoing_edges", "загрузка исходящих связей", query).await?;
+
+        Ok(rows.iter().map(|row| self.edge_from_row(row)).collect())
+    }
+
+    /// Агрегаты связей статей `uids` по всем их входящим и исходящим связям
+    ///
+    /// Инкрементальная укладка пересчитывает их для статей, которые получили
+    /// новые связи или сменили позицию: агрегаты пишутся в тех же строках,
+    /// что и координаты.
+    pub async fn load_vertex_aggregates(&self, uids: &[String]) -> Result<HashMap<String, VertexAggregates>> {
+        if uids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // Связь между двумя статьями из `uids` найдётся с обоих концов
+        let query = Query::new(format!(
+            "UNWIND $uids AS uid \
+             MATCH (:Article {{uid: uid}})-{}-(:Article) \
+             WITH DISTINCT r \
+             WITH startNode(r) AS a, endNode(r) AS b, r \
+             WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL \
+             RETURN a.uid as source, b.uid as target, type(r) as edge_type",
+            self.config.relationship_pattern(),
+        ))
+        .param("uids", uids.to_vec());
+
+        let rows = self.fetch_rows("load_vertex_aggregates", "загрузка агрегатов связей", query).await?;
+        let edges: Vec<GraphEdge> = rows.iter().map(|row| self.edge_from_row(row)).collect();
+
+        let mut aggregates = HashMap::new();
+        accumulate_vertex_aggregates(&mut aggregates, &edges);
+        let requested: std::collections::HashSet<&str> = uids.iter().map(String::as_str).collect();
+        aggregates.retain(|uid, _| requested.contains(uid.as_str()));
+        // Статья без связей получает нулевые агрегаты, а не прежние
+        for uid in uids {
+            aggregates.entry(uid.clone()).or_default();
+        }
+        Ok(aggregates)
+    }
+
+    /// Время начала последней укладки связей из Neo4j (None — укладок не было)
+    ///
+    /// Берётся из узлов `(:LayoutRun)` (см. `save_layout_run`); запуски по
+    /// файлу графа не учитываются — их связи могли устареть, откаченные
+    /// (`rollback_layout`) — их изменения уже не в координатах.
+    pub async fn load_last_layout_run(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let query = "MATCH (run:LayoutRun) WHERE run.edges_source = 'neo4j' AND run.rolled_back IS NULL \
+                     RETURN max(run.started_at).epochMillis AS at";
+
+        let rows = self.fetch_rows("load_last_layout_run", "загрузка времени последней укладки", query.into()).await?;
+        let Some(row) = rows.first() else {
+            return Ok(None);
+        };
+        // Без запусков max() возвращает null
+        Ok(row.get::<i64>("at").ok().and_then(chrono::DateTime::from_timestamp_millis))
+    }
+
+    /// Сохранение метаданных запуска укладки в узел `(:LayoutRun {run_id})`
+    ///
+    /// Узел получает статистику запуска, отпечаток конфигурации `config_hash`,
+    /// `started_at` (`timestamp` — момент начала загрузки связей) и
+    /// `finished_at`. Последний запуск помечается `current: true` и ссылается
+    /// на предыдущий через `[:PREVIOUS]`, так что источник координат в базе —
+    /// `MATCH (run:LayoutRun {current: true}) RETURN run`.
+    pub async fn save_layout_run(
+        &self,
+        run_id: &str,
+        statistics: &LayoutRunStats,
+        config_hash: &str,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let q = Query::new(
+            "OPTIONAL MATCH (prev:LayoutRun {current: true}) WHERE prev.run_id <> $run_id \
+             MERGE (run:LayoutRun {run_id: $run_id}) \
+             SET run += $props, \
+                 run.config_hash = $config_hash, \
+                 run.started_at = datetime({epochMillis: $started_at}), \
+                 run.finished_at = datetime(), \
+                 run.current = true \
+             FOREACH (p IN CASE WHEN prev IS NULL THEN [] ELSE [prev] END | \
+                 REMOVE p.current \
+                 MERGE (run)-[:PREVIOUS]->(p))".to_string()
+        )
+        .param("run_id", run_id.to_string())
+        .param("props", statistics.to_params())
+        .param("config_hash", config_hash.to_string())
+        .param("started_at", timestamp.timestamp_millis());
+
+        // MERGE по run_id: повтор после неизвестного исхода не создаёт второй узел
+        with_retries(&self.config.retry, "save_layout_run", |_| {
+            with_timeout("сохранение LayoutRun", self.config.query_timeout(), self.graph.run(q.clone()))
+        }).await
+            .map_err(|e| anyhow::anyhow!("Не удалось сохранить LayoutRun (run_id={}): {}", run_id, e))?;
+
+        info!("🕒 Запуск укладки сохранён: run_id={}, начало {}, конфигурация {}",
+              run_id, timestamp.to_rfc3339(), config_hash);
+        Ok(())
+    }
+
     /// Получение связей графа (алиас для load_graph_edges)
     pub async fn get_edges(&self) -> Result<Vec<GraphEdge>> {
         self.load_graph_edges().await
     }
 
-    /// Батчевая загрузка связей графа
+    /// Батчевая загрузка связей графа через SKIP/LIMIT
+    ///
+    /// Каждый батч заново проходит все пропускаемые строки, поэтому на больших
+    /// графах лучше `load_graph_edges_page` с keyset-курсором.
     pub async fn load_graph_edges_batch(&self, batch_size: usize, offset: usize) -> Result<Vec<GraphEdge>> {
-        info!("📥 Загрузка батча связей: offset={}, batch_size={}", offset, batch_size);
+        self.load_graph_edges_page(batch_size, &EdgeCursor::Offset(offset))
+            .await
+            .map(|page| page.edges)
+    }
+
+    /// Начальный курсор батчевой загрузки по `neo4j.pagination`
+    pub fn first_edge_cursor(&self) -> EdgeCursor {
+        match self.config.pagination {
+            EdgePagination::Keyset => EdgeCursor::After(None),
+            // Auto разрешается при создании клиента (`EdgePagination::resolve`)
+            EdgePagination::Skip | EdgePagination::Auto => EdgeCursor::Offset(0),
+        }
+    }
+
+    /// Загрузка батча связей с позиции `cursor`
+    ///
+    /// Keyset-курсор продолжает загрузку после ключа последней связи
+    /// (`Neo4jConfig::keyset_key`), не пропуская уже прочитанные строки.
+    /// `EdgePage::next` пуст, когда связи закончились.
+    pub async fn load_graph_edges_page(&self, batch_size: usize, cursor: &EdgeCursor) -> Result<EdgePage> {
+        info!("📥 Загрузка батча связей: {}, batch_size={}", cursor, batch_size);
         
-        let query = format!(
-            r#"
-            MATCH (a:Article)-[r]->(b:Article)
-            WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL 
-            RETURN a.uid as source, b.uid as target, type(r) as edge_type
-            SKIP {} LIMIT {}
-            "#,
-            offset, batch_size
-        );
+        let query = self.edge_page_query(batch_size, cursor, None);
         
         info!("📝 Выполнение запроса загрузки батча...");
         let start_query = std::time::Instant::now();
         
-        // Попытка с ретраями
-        let max_retries = 3;
-        for attempt in 1..=max_retries {
-            if attempt > 1 {
-                info!("🔄 Повторная попытка {} из {}", attempt, max_retries);
-            }
-            
-            match self.graph.execute(query.clone().into()).await {
-                Ok(mut result) => {
-                    info!("✅ Запрос выполнен, обработка результатов...");
-                    let mut edges = Vec::new();
-                    let mut row_count = 0;
-                    
-                    // Добавляем таймаут для получения первой строки
-                    let timeout_duration = std::time::Duration::from_secs(60);
-                    info!("⏱️ Ожидание первой строки результата (таймаут {} сек)...", timeout_duration.as_secs());
-                    
-                    loop {
-                        match tokio::time::timeout(timeout_duration, result.next()).await {
-                            Ok(Ok(Some(row))) => {
-                                if row_count == 0 {
-                                    info!("📦 Первая строка получена, обработка данных...");
-                                }
-                                
-                                let source: String = row.get("source").unwrap_or_default();
-                                let target: String = row.get("target").unwrap_or_default();
-                                let edge_type: String = row.get("edge_type").unwrap_or_else(|_| "RELATES_TO".to_string());
-                                
-                                edges.push(GraphEdge {
-                                    source_id: source,
-                                    target_id: target,
-                                    edge_type,
-                                    weight: 1.0,
-                                });
-                                
-                                row_count += 1;
-                                if row_count % 10000 == 0 {
-                                    info!("📊 Обработано {} строк из батча...", row_count);
-                                }
-                            }
-                            Ok(Ok(None)) => {
-                                info!("✅ Конец результатов, обработано {} строк", row_count);
-                                break;
+        let timeout_duration = self.config.query_timeout();
+        let operation = format!("загрузка батча связей ({}, batch_size={})", cursor, batch_size);
+        
+        let page = with_retries(&self.config.retry, "load_edges_page", |attempt| {
+            let query = query.clone();
+            let operation = &operation;
+            async move {
+                if attempt > 1 {
+                    info!("🔄 Повторная попытка {} из {}", attempt, self.config.retry.max_attempts);
+                }
+                let mut result = with_timeout(operation, timeout_duration, self.graph.execute(query)).await?;
+                info!("✅ Запрос выполнен, обработка результатов...");
+                let mut edges = Vec::new();
+                let mut row_count = 0;
+                let mut last_key = None;
+                
+                info!("⏱️ Ожидание первой строки результата (таймаут {} сек)...", timeout_duration.as_secs());
+                
+                loop {
+                    match tokio::time::timeout(timeout_duration, result.next()).await {
+                        Ok(Ok(Some(row))) => {
+                            if row_count == 0 {
+                                info!("📦 Первая строка получена, обработка данных...");
                             }
-                            Ok(Err(e)) => {
-                                info!("❌ Ошибка при чтении строки: {}", e);
-                                break;
+                            
+                            row_count += 1;
+                            if matches!(cursor, EdgeCursor::After(_)) {
+                                last_key = EdgeCursorKey::from_row(&row);
                             }
-                            Err(_) => {
-                                info!("⏱️ Таймаут при получении строки {} (> {} сек)", row_count + 1, timeout_duration.as_secs());
-                                if attempt < max_retries {
-                                    let backoff_ms = (1u64 << attempt.min(6)) * 500;
-                                    info!("⏳ Ожидание {} мс перед повтором всего батча...", backoff_ms);
-                                    tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                                    continue;
-                                }
-                                return Err(anyhow::anyhow!("Таймаут при получении данных после {} попыток", max_retries));
+                            edges.push(self.edge_from_row(&row));
+                            
+                            if row_count % 10000 == 0 {
+                                info!("📊 Обработано {} строк из батча...", row_count);
                             }
                         }
+                        Ok(Ok(None)) => {
+                            info!("✅ Конец результатов, обработано {} строк", row_count);
+                            break;
+                        }
+                        Ok(Err(e)) => {
+                            // Неполный батч сдвинул бы курсор мимо непрочитанных связей
+                            return Err(anyhow::Error::from(e).context(format!(
+                                "Ошибка операции Neo4j '{}' при чтении строки {}",
+                                operation, row_count + 1
+                            )));
+                        }
+                        Err(_) => {
+                            // Повторяется весь батч: курсор прочитанных строк не сохраняется
+                            return Err(anyhow::anyhow!(
+                                "Таймаут операции Neo4j '{}' при чтении строки {} (> {} сек)",
+                                operation, row_count + 1, timeout_duration.as_secs()
+                            ));
+                        }
+                    }
+                }
+                Ok((edges, row_count, last_key))
+            }
+        }).await;
+        
+        let (edges, row_count, last_key) = page
+            .map_err(|e| anyhow::anyhow!("Не удалось выполнить '{}': {:#}", operation, e))?;
+        let query_time = start_query.elapsed();
+        info!("✅ Загружен батч: {} связей ({}, время: {:.2?})", edges.len(), cursor, query_time);
+        let next = if row_count < batch_size {
+            None
+        } else {
+            Some(cursor.advance(row_count, last_key))
+        };
+        Ok(EdgePage { edges, rows: row_count, next })
+    }
+
+    /// Запрос батча связей части `partition` с позиции `cursor` (SKIP/LIMIT или keyset)
+    fn edge_page_query(&self, batch_size: usize, cursor: &EdgeCursor, partition: Option<EdgePartition>) -> Query {
+        let select = format!(
+            r#"
+            MATCH (a:Article)-{}->(b:Article)
+            WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL{}{}"#,
+            self.config.relationship_pattern(),
+            partition.map(|p| p.clause()).unwrap_or_default(),
+            self.config.sampling_clause()
+        );
+        let columns = format!(
+            "RETURN a.uid as source, b.uid as target, type(r) as edge_type, {}",
+            self.config.year_columns()
+        );
+        match cursor {
+            EdgeCursor::Offset(offset) => Query::new(format!(
+                "{}\n            {}{}\n            SKIP {} LIMIT {}",
+                select, columns, self.config.order_clause(), offset, batch_size
+            )),
+            EdgeCursor::After(after) => {
+                let key = self.config.keyset_key();
+                let by_element_id = self.config.keyset_property.is_empty();
+                let mut condition = format!(" AND {} IS NOT NULL", key);
+                if after.is_some() {
+                    // Связи с тем же значением ключа продолжаются по elementId(r)
+                    condition.push_str(&if by_element_id {
+                        " AND elementId(r) > $after_id".to_string()
+                    } else {
+                        format!(" AND ({0} > $after OR ({0} = $after AND elementId(r) > $after_id))", key)
+                    });
+                }
+                let query = Query::new(format!(
+                    "{}{}\n            {}, {} as edge_key, elementId(r) as edge_id\n            ORDER BY edge_key, edge_id LIMIT {}",
+                    select, condition, columns, key, batch_size
+                ));
+                match after {
+                    Some(after) => {
+                        let query = query.param("after_id", after.element_id.clone());
+                        match &after.value {
+                            _ if by_element_id => query,
+                            EdgeKeyValue::Int(value) => query.param("after", *value),
+                            EdgeKeyValue::Text(value) => query.param("after", value.clone()),
+                        }
+                    }
+                    None => query,
+                }
+            }
+        }
+    }
+
+    /// Связь из строки результата загрузки
+    fn edge_from_row(&self, row: &Row) -> GraphEdge {
+        let source: String = row.get("source").unwrap_or_default();
+        let target: String = row.get("target").unwrap_or_default();
+        let edge_type: String = row.get("edge_type").unwrap_or_else(|_| "RELATES_TO".to_string());
+        GraphEdge {
+            source_id: source,
+            target_id: target,
+            weight: self.config.edge_weight(&edge_type),
+            edge_type,
+            source_cluster: None,
+            target_cluster: None,
+            source_year: row_year(row, "source_year"),
+            target_year: row_year(row, "target_year"),
+        }
+    }
+
+    /// Поток связей графа без материализации батчей
+    ///
+    /// Фоновая задача читает страницы по курсору `neo4j.pagination` и отдаёт
+    /// связи по одной, пока потребитель обрабатывает предыдущие (буфер — одна
+    /// страница). Размер страницы адаптируется `batch`; сбой посреди страницы
+    /// повторяется с последней отданной связи, так что связи не дублируются.
+    ///
+    /// С `neo4j.load_parallelism` > 1 связи делятся на диапазоны `id(r)`
+    /// (`EdgePartition`) между минимальным и максимальным id, и каждая часть
+    /// читается своей задачей по отдельному соединению из пула; порядок связей
+    /// между частями не определён (в детерминированном режиме часть одна).
+    pub fn stream_edges(&self, batch: AdaptiveBatchSize) -> impl Stream<Item = Result<GraphEdge>> {
+        let (tx, rx) = tokio::sync::mpsc::channel(batch.current().max(1));
+        let client = self.clone();
+        tokio::spawn(async move {
+            let partitions: Vec<Option<EdgePartition>> = match client.config.load_parallelism {
+                0 | 1 => vec![None],
+                count => match client.edge_id_bounds().await {
+                    Ok(Some((min_id, max_id))) => EdgePartition::split(count, min_id, max_id).map(Some).collect(),
+                    Ok(None) => return,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                },
+            };
+            for partition in partitions {
+                let client = client.clone();
+                let batch = batch.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = client.send_edges(batch, partition, &tx).await {
+                        let _ = tx.send(Err(e)).await;
+                    }
+                });
+            }
+        });
+        tokio_stream::wrappers::ReceiverStream::new(rx)
+    }
+
+    /// Наименьший и наибольший `id(r)` загружаемых связей (None — связей нет)
+    async fn edge_id_bounds(&self) -> Result<Option<(i64, i64)>> {
+        let query = Query::new(format!(
+            "MATCH (a:Article)-{}->(b:Article) WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL \
+             RETURN min(id(r)) AS min_id, max(id(r)) AS max_id",
+            self.config.relationship_pattern()
+        ));
+        let rows = self.fetch_rows("edge_id_bounds", "границы id связей", query).await?;
+        Ok(rows.first().and_then(|row| {
+            Some((row.get::<i64>("min_id").ok()?, row.get::<i64>("max_id").ok()?))
+        }))
+    }
+
+    /// Чтение страниц связей части `partition` (None — всех связей) в канал `tx`
+    /// до конца связей или закрытия канала
+    async fn send_edges(
+        &self,
+        mut batch: AdaptiveBatchSize,
+        partition: Option<EdgePartition>,
+        tx: &tokio::sync::mpsc::Sender<Result<GraphEdge>>,
+    ) -> Result<()> {
+        let retry = &self.config.retry;
+        let timeout_duration = self.config.query_timeout();
+        let mut cursor = self.first_edge_cursor();
+        let mut failures = 0;
+        loop {
+            let batch_size = batch.current();
+            let operation = match partition {
+                Some(partition) => format!("потоковая загрузка связей (часть {}, {}, batch_size={})", partition, cursor, batch_size),
+                None => format!("потоковая загрузка связей ({}, batch_size={})", cursor, batch_size),
+            };
+            let start = std::time::Instant::now();
+            let mut rows = 0;
+            let mut last_key = None;
+            
+            let page = async {
+                let mut result = with_timeout(
+                    &operation,
+                    timeout_duration,
+                    self.graph.execute(self.edge_page_query(batch_size, &cursor, partition)),
+                ).await?;
+                while let Some(row) = with_timeout(&operation, timeout_duration, result.next()).await? {
+                    rows += 1;
+                    if matches!(cursor, EdgeCursor::After(_)) {
+                        last_key = EdgeCursorKey::from_row(&row);
+                    }
+                    if tx.send(Ok(self.edge_from_row(&row))).await.is_err() {
+                        break;
+                    }
+                }
+                Ok::<(), anyhow::Error>(())
+            }.await;
+            crate::retry::observe_duration("stream_edges_page", start.elapsed());
+            
+            if tx.is_closed() {
+                info!("⏹️ Потребитель связей завершился, загрузка остановлена");
+                return Ok(());
+            }
+            // Прочитанные строки уже отданы: следующий запрос продолжает после них
+            let complete = page.is_ok() && rows < batch_size;
+            if rows > 0 {
+                cursor = cursor.advance(rows, last_key);
+            }
+            match page {
+                Ok(()) if complete => {
+                    crate::retry::record_attempt("stream_edges_page", "success");
+                    match partition {
+                        Some(partition) => info!("✅ Часть {} связей загружена ({})", partition, cursor),
+                        None => info!("✅ Поток связей завершён ({})", cursor),
                     }
-                    
-                    let query_time = start_query.elapsed();
-                    info!("✅ Загружен батч: {} связей (offset={}, время: {:.2?})", edges.len(), offset, query_time);
-                    return Ok(edges);
+                    return Ok(());
+                }
+                Ok(()) => {
+                    crate::retry::record_attempt("stream_edges_page", "success");
+                    failures = 0;
+                    batch.on_success(start.elapsed());
                 }
                 Err(e) => {
-                    info!("❌ Ошибка выполнения запроса батча (попытка {}): {}", attempt, e);
-                    if attempt < max_retries {
-                        let backoff_ms = (1u64 << attempt.min(6)) * 500;
-                        info!("⏳ Ожидание {} мс перед повтором...", backoff_ms);
-                        tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                        continue;
+                    failures += 1;
+                    // Пока батч уменьшается, повторы не ограничены числом попыток политики
+                    let shrunk = batch.on_failure();
+                    if !(retry.retryable)(&e) || (!shrunk && failures >= retry.max_attempts) {
+                        crate::retry::record_attempt("stream_edges_page", "failure");
+                        return Err(e);
                     }
-                    return Err(anyhow::anyhow!("Ошибка загрузки батча после {} попыток: {}", max_retries, e));
+                    crate::retry::record_attempt("stream_edges_page", "retry");
+                    let delay = retry.delay(failures);
+                    tracing::warn!("⚠️ {} (попытка {}), продолжение с {} через {} мс, batch_size={}",
+                                   e, failures, cursor, delay.as_millis(), batch.current());
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
-        
-        Err(anyhow::anyhow!("Не удалось загрузить батч после {} попыток", max_retries))
     }
 
-    /// Получение общего количества связей
+    /// Получение количества загружаемых связей (с условием выборки `sampling_clause`)
     pub async fn get_total_edges_count(&self) -> Result<usize> {
-        let queries = [
-            (
-                "CALL db.stats.retrieve('GRAPH COUNTS') YIELD data RETURN data['relationshipCount'] AS total_count",
+        let sampling = self.config.sampling_clause();
+        let match_count = format!(
+            "MATCH (a:Article)-{}->(b:Article) WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL{} RETURN count(r) AS total_count",
+            self.config.relationship_pattern(),
+            sampling
+        );
+
+        let mut queries = Vec::new();
+        // Статистика БД считает все связи, поэтому при фильтре по типам или выборке используем только MATCH
+        if self.config.relationship_weights.is_empty() && sampling.is_empty() {
+            queries.push((
+                "CALL db.stats.retrieve('GRAPH COUNTS') YIELD data RETURN data['relationshipCount'] AS total_count".to_string(),
                 "db.stats.retrieve('GRAPH COUNTS')"
-            ),
-            (
-                "CALL apoc.meta.stats() YIELD relCount RETURN relCount AS total_count",
+            ));
+            queries.push((
+                "CALL apoc.meta.stats() YIELD relCount RETURN relCount AS total_count".to_string(),
                 "apoc.meta.stats()"
-            ),
-            (
-                "MATCH (a:Article)-[r]->(b:Article) WHERE a.uid IS NOT NULL AND b.uid IS NOT NULL RETURN count(r) AS total_count",
-                "fallback MATCH count"
-            ),
-        ];
+            ));
+        }
+        queries.push((match_count, "fallback MATCH count"));
 
         let mut last_error = None;
 
         for (query, description) in queries {
-            match self.run_edges_count_query(query, description).await {
+            match self.run_edges_count_query(&query, description).await {
                 Ok(count) => return Ok(count),
                 Err(e) => {
                     info!("⚠️ {} не сработал: {}", description, e);
@@ -395,77 +1317,70 @@ impl Neo4jClient {
     async fn run_edges_count_query(&self, query: &str, description: &str) -> Result<usize> {
         info!("📊 Получаем общее число связей через {}", description);
 
-        let max_retries = 3;
-        let timeout_duration = std::time::Duration::from_secs(60);
+        let timeout_duration = self.config.query_timeout();
         let start_query = std::time::Instant::now();
 
-        for attempt in 1..=max_retries {
-            info!("🔄 Попытка {} из {} ({})", attempt, max_retries, description);
+        // Повторяются только сбои запроса; пустой ответ повтором не исправить
+        let row = with_retries(&self.config.retry, "count_edges", |attempt| async move {
+            info!("🔄 Попытка {} из {} ({})", attempt, self.config.retry.max_attempts, description);
 
-            let mut result = match self.graph.execute(Query::new(query.to_string())).await {
-                Ok(result) => result,
-                Err(e) => {
-                    info!("❌ Ошибка выполнения {}: {}", description, e);
-                    if attempt < max_retries {
-                        let backoff_ms = (1u64 << attempt.min(6)) * 500;
-                        info!("⏳ Повтор через {} мс", backoff_ms);
-                        tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                        continue;
-                    }
-                    return Err(anyhow::anyhow!("{} завершился ошибкой: {}", description, e));
-                }
-            };
+            let mut result = with_timeout(
+                description,
+                timeout_duration,
+                self.graph.execute(Query::new(query.to_string())),
+            ).await?;
+            with_timeout(description, timeout_duration, result.next()).await
+        }).await?;
+        let count = row
+            .ok_or_else(|| anyhow::anyhow!("{} вернул пустой результат", description))?
+            .get::<i64>("total_count")
+            .map_err(|_| anyhow::anyhow!("{} не вернул поле total_count", description))?;
 
-            match tokio::time::timeout(timeout_duration, result.next()).await {
-                Ok(Ok(Some(row))) => {
-                    if let Ok(count) = row.get::<i64>("total_count") {
-                        let query_time = start_query.elapsed();
-                        info!("✅ {} → {} связей (за {:.2?})", description, count, query_time);
-                        return Ok(count as usize);
-                    } else {
-                        return Err(anyhow::anyhow!("{} не вернул поле total_count", description));
-                    }
-                }
-                Ok(Ok(None)) => {
-                    return Err(anyhow::anyhow!("{} вернул пустой результат", description));
-                }
-                Ok(Err(e)) => {
-                    info!("❌ Ошибка получения строки {}: {}", description, e);
-                }
-                Err(_) => {
-                    info!("⌛ {} не ответил за {} секунд", description, timeout_duration.as_secs());
-                }
-            }
-
-            if attempt < max_retries {
-                let backoff_ms = (1u64 << attempt.min(6)) * 500;
-                info!("⏳ Повтор {} через {} мс", description, backoff_ms);
-                tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-            }
-        }
-
-        Err(anyhow::anyhow!(
-            "{} не дал результата за {} попытки",
-            description,
-            max_retries
-        ))
+        let query_time = start_query.elapsed();
+        info!("✅ {} → {} связей (за {:.2?})", description, count, query_time);
+        Ok(count as usize)
     }
 
-    pub async fn save_layout_results(&self, positions: &[VertexPosition]) -> Result<()> {
+    pub async fn save_layout_results(&self, positions: &[VertexPosition]) -> Result<SaveReport> {
         self.save_layout_results_with_batch_size(positions, 1000).await
     }
 
     /// Сохранение результатов укладки в Neo4j с настраиваемым размером батча
     /// Save layout results to Neo4j using the configured batch size and run graph post-processing.
-    pub async fn save_layout_results_with_batch_size(&self, positions: &[VertexPosition], batch_size: usize) -> Result<()> {
+    ///
+    /// Batches that keep failing after retries do not abort the save: their rows are
+    /// written to the quarantine file and listed in the returned `SaveReport`.
+    pub async fn save_layout_results_with_batch_size(&self, positions: &[VertexPosition], batch_size: usize) -> Result<SaveReport> {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        self.save_layout_results_with_aggregates(&run_id, positions, None, batch_size).await
+    }
+
+    /// Сохранение результатов укладки вместе с агрегатами связей вершин
+    ///
+    /// С `aggregates` в тех же UNWIND-батчах записываются свойства `in_degree`,
+    /// `out_degree`, `in_weight` и `out_weight` (вершины без записи получают нули),
+    /// так что фронтенду не нужен отдельный проход агрегации по связям.
+    ///
+    /// С `neo4j.atomic_save` батчи пишут во временные свойства `*_new`, а
+    /// основные свойства переключаются одной транзакцией после записи всех
+    /// батчей. Если хоть один батч не записан, временные свойства удаляются и
+    /// возвращается ошибка — координаты в графе остаются прежними. Временные
+    /// свойства помечены `layout_run_new = run_id`: переключение и очистка
+    /// касаются только статей этого сохранения, а не чужих или прерванных.
+    pub async fn save_layout_results_with_aggregates(
+        &self,
+        run_id: &str,
+        positions: &[VertexPosition],
+        aggregates: Option<&HashMap<String, VertexAggregates>>,
+        batch_size: usize,
+    ) -> Result<SaveReport> {
         use neo4rs::Query;
-        use std::collections::HashMap;
 
-        info!("Saving layout positions to Neo4j: {} rows", positions.len());
+        info!("{}", Msg::SavingLayout { rows: positions.len(), aggregates: aggregates.is_some() });
 
         if positions.is_empty() {
-            info!("No layout positions provided; skipping save.");
-            return Ok(());
+            info!("{}", Msg::NoPositionsToSave);
+            return Ok(SaveReport::default());
         }
 
         let ensure_index = Query::new(
@@ -473,12 +1388,11 @@ impl Neo4jClient {
         );
         let _ = self.graph.execute(ensure_index).await;
 
+        let statement = save_batch_query(if self.config.atomic_save { "_new" } else { "" }, aggregates.is_some());
+
         let total_positions = positions.len();
         let total_batches = (total_positions + batch_size - 1) / batch_size;
-        info!(
-            "Saving layout using {} batches with up to {} rows each",
-            total_batches, batch_size
-        );
+        info!("{}", Msg::SaveBatchesPlanned { batches: total_batches, batch_size });
         let start_time = std::time::Instant::now();
 
         let max_parallel = 2;
@@ -492,91 +1406,84 @@ impl Neo4jClient {
             let start_idx = batch_num * batch_size;
             let end_idx = (start_idx + batch_size).min(total_positions);
             let slice = positions[start_idx..end_idx].to_vec();
+            let slice_aggregates: Option<Vec<VertexAggregates>> = aggregates.map(|aggregates| {
+                slice
+                    .iter()
+                    .map(|p| aggregates.get(&p.article_id).copied().unwrap_or_default())
+                    .collect()
+            });
+
+            let tx_timeout = self.config.query_timeout();
+            let operation = format!("сохранение батча укладки {} (строки {}-{})", batch_num + 1, start_idx, end_idx.saturating_sub(1));
+            let statement = statement.clone();
+
+            let retry = self.config.retry.clone();
+            let run_id = run_id.to_string();
 
             let handle = tokio::spawn(async move {
                 let _permit = permit;
-                let mut attempt = 0u32;
-                let max_attempts = 5u32;
 
-                loop {
-                    let mut txn = match graph.start_txn().await {
-                        Ok(t) => t,
-                        Err(e) => {
-                            if attempt >= max_attempts {
-                                return Err(anyhow::anyhow!(e));
-                            }
-                            attempt += 1;
-                            let backoff_ms = (1u64 << attempt.min(6)) * 100;
-                            tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                            continue;
-                        }
-                    };
-
-                    let mut rows: Vec<HashMap<String, BoltType>> = Vec::with_capacity(slice.len());
-                    for p in &slice {
-                        let mut m: HashMap<String, BoltType> = HashMap::new();
-                        m.insert("uid".to_string(), p.article_id.clone().into());
-                        m.insert("layer".to_string(), (p.layer as i64).into());
-                        m.insert("level".to_string(), (p.level as i64).into());
-                        m.insert("x".to_string(), (p.x as f64).into());
-                        m.insert("y".to_string(), (p.y as f64).into());
-                        rows.push(m);
+                let mut rows: Vec<HashMap<String, BoltType>> = Vec::with_capacity(slice.len());
+                for (i, p) in slice.iter().enumerate() {
+                    let mut m: HashMap<String, BoltType> = HashMap::new();
+                    m.insert("uid".to_string(), p.article_id.clone().into());
+                    m.insert("layer".to_string(), (p.layer as i64).into());
+                    m.insert("level".to_string(), (p.level as i64).into());
+                    m.insert("x".to_string(), (p.x as f64).into());
+                    m.insert("y".to_string(), (p.y as f64).into());
+                    if let Some(ref aggregates) = slice_aggregates {
+                        aggregates[i].insert_params(&mut m);
                     }
+                    rows.push(m);
+                }
 
-                    let q = Query::new(
-                        "UNWIND $rows AS row \
-                        MATCH (a:Article {uid: row.uid}) \
-                        SET a.layer = row.layer, a.level = row.level, a.x = row.x, a.y = row.y".to_string()
-                    )
-                    .param("rows", rows);
-
-                    match txn.run(q).await {
-                        Ok(_) => {
-                            if let Err(e) = txn.commit().await {
-                                if attempt >= max_attempts {
-                                    return Err(anyhow::anyhow!(e));
-                                }
-                                attempt += 1;
-                                let backoff_ms = (1u64 << attempt.min(6)) * 100;
-                                tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                                continue;
-                            }
-                            break anyhow::Ok(());
-                        }
-                        Err(e) => {
-                            if attempt >= max_attempts {
-                                return Err(anyhow::anyhow!(e));
-                            }
-                            attempt += 1;
-                            let backoff_ms = (1u64 << attempt.min(6)) * 100;
-                            tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
-                        }
+                with_retries(&retry, "save_layout_batch", |_| {
+                    let q = Query::new(statement.clone())
+                        .param("rows", rows.clone())
+                        .param("run_id", run_id.clone());
+                    let graph = &graph;
+                    let operation = &operation;
+                    async move {
+                        let mut txn = with_timeout(operation, tx_timeout, graph.start_txn()).await?;
+                        crate::failure_injection::inject(crate::failure_injection::FaultPoint::SaveBatch, operation).await?;
+                        with_timeout(operation, tx_timeout, txn.run(q)).await?;
+                        with_timeout(operation, tx_timeout, txn.commit()).await
                     }
-                }
+                }).await
             });
 
             join_handles.push((batch_num, start_idx, end_idx, handle));
         }
 
         let mut completed: usize = 0;
+        let mut report = SaveReport {
+            total_rows: total_positions,
+            ..SaveReport::default()
+        };
         for (batch_num, start_idx, end_idx, handle) in join_handles {
-            let res = handle.await;
-            if let Err(e) = res {
-                return Err(anyhow::anyhow!(
-                    "Failed to join layout batch task {}: {}",
-                    batch_num + 1,
-                    e
-                ));
-            }
-            if let Err(e) = res.unwrap() {
-                return Err(anyhow::anyhow!(
-                    "Layout batch {} failed: {}",
-                    batch_num + 1,
-                    e
-                ));
+            let failure = match handle.await {
+                Err(e) => Some(Msg::SaveBatchJoinFailed { batch: batch_num + 1, error: &e }.to_string()),
+                Ok(Err(e)) => Some(e.to_string()),
+                Ok(Ok(())) => None,
+            };
+            if let Some(error) = failure {
+                tracing::warn!("{}", Msg::SaveBatchQuarantined {
+                    batch: batch_num + 1,
+                    first_row: start_idx,
+                    last_row: end_idx.saturating_sub(1),
+                    error: &error,
+                });
+                report.failed_batches.push(FailedSaveBatch {
+                    batch: batch_num,
+                    start: start_idx,
+                    end: end_idx,
+                    error,
+                });
+                continue;
             }
 
             completed += end_idx.saturating_sub(start_idx);
+            report.saved_rows = completed;
             let progress = (completed as f64 / total_positions as f64) * 100.0;
             let elapsed = start_time.elapsed();
             let rate = if elapsed.as_secs_f64() > 0.0 {
@@ -585,58 +1492,267 @@ impl Neo4jClient {
                 0.0
             };
 
-            info!(
-                "Finished layout batch {}/{} (rows {}-{})",
-                batch_num + 1,
-                total_batches,
-                start_idx,
-                end_idx.saturating_sub(1)
-            );
-            info!(
-                "Layout save progress: {:.1}% ({}/{} rows, {:.0} rows/sec)",
-                progress,
-                completed,
-                total_positions,
-                rate
-            );
+            info!("{}", Msg::SaveBatchFinished {
+                batch: batch_num + 1,
+                total: total_batches,
+                first_row: start_idx,
+                last_row: end_idx.saturating_sub(1),
+            });
+            info!("{}", Msg::SaveProgress {
+                percent: progress,
+                saved: completed,
+                total: total_positions,
+                rate,
+            });
+        }
+
+        if self.config.atomic_save {
+            return self.finish_atomic_save(run_id, report, start_time).await;
+        }
+
+        if report.saved_rows == 0 {
+            let first_error = report
+                .failed_batches
+                .first()
+                .map(|f| f.error.clone())
+                .unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "{}",
+                Msg::AllSaveBatchesFailed { batches: total_batches, error: &first_error }
+            ));
+        }
+
+        if !report.is_complete() {
+            let path = std::path::PathBuf::from(&self.config.save_quarantine_path);
+            crate::blocking_io::run_blocking(|| write_quarantine_file(&path, &report, positions))?;
+            tracing::warn!("{}", Msg::SaveQuarantined {
+                saved: report.saved_rows,
+                total: report.total_rows,
+                batches: report.failed_batches.len(),
+                path: &path.display(),
+            });
+            report.quarantine_path = Some(path);
         }
 
         let total_time = start_time.elapsed();
         let save_rate = if total_time.as_secs_f64() > 0.0 {
-            total_positions as f64 / total_time.as_secs_f64()
+            report.saved_rows as f64 / total_time.as_secs_f64()
         } else {
             0.0
         };
-        info!(
-            "Completed writing layout to Neo4j in {:.2?} ({:.0} rows/sec)",
-            total_time,
-            save_rate
-        );
+        info!("{}", Msg::LayoutWritten { seconds: total_time.as_secs_f64(), rate: save_rate });
 
-        info!("Starting layout post-processing in Neo4j...");
+        info!("{}", Msg::PostProcessingStart);
         self.post_process_layout().await?;
 
-        Ok(())
+        Ok(report)
+    }
+
+    /// Завершение атомарного сохранения `run_id`: переключение `*_new` или откат записанного
+    ///
+    /// После переключения `post_process_layout` снимает координаты с
+    /// изолированных статей; прежние значения копируются в `*_prev`, так что
+    /// `rollback_layout` возвращает и их.
+    async fn finish_atomic_save(&self, run_id: &str, report: SaveReport, start_time: std::time::Instant) -> Result<SaveReport> {
+        if !report.is_complete() {
+            let cleared = self.clear_staged_layout(Some(run_id)).await?;
+            let first_error = report.failed_batches.first().map(|f| f.error.clone()).unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Атомарное сохранение отменено: не записано {} из {} позиций, временные свойства удалены у {} статей, координаты не изменены: {}",
+                report.failed_rows(),
+                report.total_rows,
+                cleared,
+                first_error
+            ));
+        }
+
+        // Повтор после неизвестного исхода фиксации стёр бы копии `*_prev` для отката
+        let once = self.config.retry.clone().with_max_attempts(1);
+        let flipped = self
+            .execute_count(
+                &once,
+                "flip_staged_layout",
+                "переключение сохранённой укладки",
+                Query::new(flip_staged_query()).param("run_id", run_id.to_string()),
+                "flipped",
+            )
+            .await?;
+        info!("{}", Msg::LayoutSwitched { articles: flipped, seconds: start_time.elapsed().as_secs_f64() });
+
+        info!("{}", Msg::PostProcessingStart);
+        self.post_process_layout().await?;
+
+        Ok(report)
+    }
+
+    /// Удаление временных свойств `*_new` атомарного сохранения `run_id`
+    /// (None — всех, в том числе оставшихся от прерванных сохранений)
+    ///
+    /// Возвращает количество статей, у которых они были.
+    pub async fn clear_staged_layout(&self, run_id: Option<&str>) -> Result<usize> {
+        let mut properties = LAYOUT_PROPERTIES.to_vec();
+        properties.extend(AGGREGATE_PROPERTIES);
+        let condition = match run_id {
+            Some(_) => "a.layout_run_new = $run_id",
+            None => "a.layout_run_new IS NOT NULL OR a.layer_new IS NOT NULL",
+        };
+        let query = Query::new(format!(
+            "MATCH (a:Article) WHERE {} \
+             REMOVE {}, a.layout_run_new \
+             RETURN count(a) AS cleared",
+            condition,
+            property_list("a", &properties, "_new")
+        ))
+        .param("run_id", run_id.unwrap_or_default().to_string());
+        self.execute_count(&self.config.retry, "clear_staged_layout", "удаление временных свойств укладки", query, "cleared")
+            .await
+    }
+
+    /// Откат координат к значениям до последнего атомарного сохранения
+    ///
+    /// Восстанавливает `layer`, `level`, `x`, `y` и агрегаты связей из `*_prev`
+    /// (статьи без прежних значений их теряют) и удаляет копии, так что
+    /// повторный откат ничего не меняет. В той же транзакции текущим снова
+    /// становится предыдущий `(:LayoutRun)`, а откаченный помечается
+    /// `rolled_back`. Возвращает количество восстановленных статей.
+    pub async fn rollback_layout(&self) -> Result<usize> {
+        let query = Query::new(rollback_query());
+
+        let restored = self
+            .execute_count(&self.config.retry, "rollback_layout", "откат укладки", query, "restored")
+            .await?;
+        info!("↩️ Координаты {} статей восстановлены до последнего атомарного сохранения", restored);
+        Ok(restored)
+    }
+
+    /// Запрос с одной строкой-счётчиком в колонке `column` по политике повторов `policy`
+    async fn execute_count(
+        &self,
+        policy: &RetryPolicy,
+        label: &'static str,
+        operation: &str,
+        query: Query,
+        column: &str,
+    ) -> Result<usize> {
+        let query_timeout = self.config.query_timeout();
+        let row = with_retries(policy, label, |_| {
+            let query = query.clone();
+            async move {
+                let mut result = with_timeout(operation, query_timeout, self.graph.execute(query)).await?;
+                with_timeout(operation, query_timeout, result.next()).await
+            }
+        }).await?;
+        let count = row.map(|row| row.get::<i64>(column).unwrap_or_default()).unwrap_or_default();
+        Ok(count.max(0) as usize)
     }
 
     /// Post-process layout data to remove isolated vertices.
     /// Note: Edge reversal is no longer needed as edges are correctly oriented during graph construction.
     pub async fn post_process_layout(&self) -> Result<()> {
         let start_time = std::time::Instant::now();
-        info!("Running layout post-processing tasks...");
+        info!("{}", Msg::PostProcessingRunning);
 
         let removed_vertices = self.remove_isolated_vertices().await?;
 
         let elapsed = start_time.elapsed();
-        info!(
-            "Post-processing finished in {:.1}s ({} isolated vertices cleaned)",
-            elapsed.as_secs_f32(),
-            removed_vertices
-        );
+        info!("{}", Msg::PostProcessingDone { seconds: elapsed.as_secs_f32(), removed: removed_vertices });
 
         Ok(())
     }
 
+    /// Сохранение метаданных системы координат в singleton-узел `(:LayoutMeta)`
+    ///
+    /// Фронтенд читает этот узел, чтобы интерпретировать x/y без внешней конфигурации.
+    pub async fn save_layout_meta(&self, meta: &LayoutMeta) -> Result<()> {
+        use neo4rs::Query;
+
+        let q = Query::new(
+            "MERGE (m:LayoutMeta {singleton: true}) \
+             SET m += $props".to_string()
+        )
+        .param("props", meta.to_params());
+
+        with_retries(&self.config.retry, "save_layout_meta", |_| {
+            with_timeout("сохранение LayoutMeta", self.config.query_timeout(), self.graph.run(q.clone()))
+        }).await
+            .map_err(|e| anyhow::anyhow!("Не удалось сохранить LayoutMeta (run_id={}): {}", meta.run_id, e))?;
+
+        info!("🧭 Метаданные укладки сохранены: run_id={}, version={}", meta.run_id, meta.algorithm_version);
+        Ok(())
+    }
+
+    /// Сохранение маршрутов связей в свойства `path_x`, `path_y`, `bundle_id`, `control_x`, `control_y`,
+    /// `source_port` и `target_port` связей
+    ///
+    /// Батчи пишутся по очереди с повторами; батч, не записанный после всех
+    /// попыток, попадает в `SaveReport::failed_batches`, остальные сохраняются.
+    /// Связи без маршрута не меняются, маршруты без связи в базе пропускаются.
+    /// Параллельные связи пары по шаблону связи не различить, поэтому
+    /// сохраняется только маршрут первой из них (`edge_index == 0`).
+    pub async fn save_edge_paths(&self, paths: &[EdgePath], batch_size: usize) -> Result<SaveReport> {
+        use neo4rs::Query;
+
+        let paths: Vec<&EdgePath> = paths.iter().filter(|path| path.edge_index == 0).collect();
+        let mut report = SaveReport {
+            total_rows: paths.len(),
+            ..SaveReport::default()
+        };
+        if paths.is_empty() {
+            return Ok(report);
+        }
+
+        let batch_size = batch_size.max(1);
+        let statement = format!(
+            "UNWIND $rows AS row \
+             MATCH (s:Article {{uid: row.source}})-{}->(t:Article {{uid: row.target}}) \
+             SET r.path_x = row.path_x, r.path_y = row.path_y, \
+                 r.bundle_id = CASE row.bundle_id WHEN '' THEN null ELSE row.bundle_id END, \
+                 r.control_x = CASE size(row.control_x) WHEN 0 THEN null ELSE row.control_x END, \
+                 r.control_y = CASE size(row.control_y) WHEN 0 THEN null ELSE row.control_y END, \
+                 r.source_port = row.source_port, r.target_port = row.target_port",
+            self.config.relationship_pattern()
+        );
+        let start_time = std::time::Instant::now();
+
+        for (batch, chunk) in paths.chunks(batch_size).enumerate() {
+            let start = batch * batch_size;
+            let end = start + chunk.len();
+            let operation = format!("сохранение маршрутов связей, батч {} (строки {}-{})", batch + 1, start, end - 1);
+            let rows: Vec<HashMap<String, BoltType>> = chunk.iter().map(|path| path.to_params()).collect();
+
+            let result = with_retries(&self.config.retry, "save_edge_paths_batch", |_| {
+                let q = Query::new(statement.clone()).param("rows", rows.clone());
+                let operation = &operation;
+                async move {
+                    crate::failure_injection::inject(crate::failure_injection::FaultPoint::SaveBatch, operation).await?;
+                    with_timeout(operation, self.config.query_timeout(), self.graph.run(q)).await
+                }
+            }).await;
+
+            match result {
+                Ok(()) => report.saved_rows += chunk.len(),
+                Err(e) => report.failed_batches.push(FailedSaveBatch {
+                    batch,
+                    start,
+                    end,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        if report.saved_rows == 0 {
+            let first_error = report.failed_batches.first().map(|f| f.error.clone()).unwrap_or_default();
+            return Err(anyhow::anyhow!("Не сохранён ни один батч маршрутов связей: {}", first_error));
+        }
+        info!(
+            "🛤️ Маршруты связей сохранены: {}/{} за {:.2?}",
+            report.saved_rows,
+            report.total_rows,
+            start_time.elapsed()
+        );
+        Ok(report)
+    }
+
     // REMOVED: fix_inverted_edges() function
     // This function was incorrectly reversing ~1M edges due to wrong validation logic.
     // The edges in Neo4j are stored correctly as: citing article -> cited article (newer -> older)
@@ -645,69 +1761,62 @@ impl Neo4jClient {
 
 
     async fn remove_isolated_vertices(&self) -> Result<usize> {
-        use neo4rs::Query;
-
-        info!("Scanning for isolated vertices with layout coordinates...");
+        info!("{}", Msg::ScanningIsolated);
 
-        let count_query = Query::new(
-            "MATCH (n:Article) \
+        let count_query = "MATCH (n:Article) \
              WHERE (n.x IS NOT NULL OR n.layer IS NOT NULL) \
                AND NOT (n)-[:BIBLIOGRAPHIC_LINK]-() \
                AND NOT ()-[:BIBLIOGRAPHIC_LINK]-(n) \
              RETURN count(n) AS isolated_count"
-                .to_string(),
-        );
+            .to_string();
 
-        let mut result = self.graph.execute(count_query).await?;
-        let isolated_count = match result.next().await? {
-            Some(record) => record.get::<i64>("isolated_count").unwrap_or(0) as usize,
-            None => 0,
-        };
+        let isolated_count = self
+            .execute_count(&self.config.retry, "count_isolated_vertices", "поиск изолированных вершин", count_query.into(), "isolated_count")
+            .await?;
 
         if isolated_count == 0 {
-            info!("No isolated vertices require cleanup.");
+            info!("{}", Msg::NoIsolatedVertices);
             return Ok(0);
         }
 
-        info!(
-            "Found {} isolated vertices with layout data; removing coordinates...",
-            isolated_count
-        );
+        info!("{}", Msg::IsolatedFound { count: isolated_count });
 
         let mut total_removed = 0usize;
         let batch_size = 10_000usize;
 
+        // При атомарном сохранении снятые координаты остаются в `*_prev` для отката
+        let backup = if self.config.atomic_save {
+            let copies: Vec<String> = LAYOUT_PROPERTIES.iter().map(|p| format!("n.{0}_prev = n.{0}", p)).collect();
+            format!(
+                "FOREACH (_ IN CASE WHEN n.layout_prev IS NULL THEN [1] ELSE [] END | SET {}, n.layout_prev = true) ",
+                copies.join(", ")
+            )
+        } else {
+            String::new()
+        };
         loop {
-            let remove_query = Query::new(
-                format!(
-                    "MATCH (n:Article) \
-                     WHERE (n.x IS NOT NULL OR n.layer IS NOT NULL) \
-                       AND NOT (n)-[:BIBLIOGRAPHIC_LINK]-() \
-                       AND NOT ()-[:BIBLIOGRAPHIC_LINK]-(n) \
-                     WITH n LIMIT {} \
-                     REMOVE n.x, n.y, n.layer, n.level \
-                     RETURN count(n) AS removed_count",
-                    batch_size
-                )
+            let remove_query = format!(
+                "MATCH (n:Article) \
+                 WHERE (n.x IS NOT NULL OR n.layer IS NOT NULL) \
+                   AND NOT (n)-[:BIBLIOGRAPHIC_LINK]-() \
+                   AND NOT ()-[:BIBLIOGRAPHIC_LINK]-(n) \
+                 WITH n LIMIT {} \
+                 {}REMOVE n.x, n.y, n.layer, n.level \
+                 RETURN count(n) AS removed_count",
+                batch_size,
+                backup
             );
 
-            let mut result = self.graph.execute(remove_query).await?;
-            let removed = match result.next().await? {
-                Some(record) => record.get::<i64>("removed_count").unwrap_or(0) as usize,
-                None => 0,
-            };
+            let removed = self
+                .execute_count(&self.config.retry, "remove_isolated_vertices", "удаление координат изолированных вершин", remove_query.into(), "removed_count")
+                .await?;
 
             if removed == 0 {
                 break;
             }
 
             total_removed += removed;
-            info!(
-                "Cleared layout properties from {} vertices (total {}/{})",
-                removed,
-                total_removed,
-                isolated_count
-            );
+            info!("{}", Msg::IsolatedCleared { removed, total: total_removed, found: isolated_count });
         }
 
         Ok(total_removed)
@@ -715,28 +1824,679 @@ impl Neo4jClient {
 
 
     
-    /// Проверка здоровья соединения (заглушка)
+    /// Проверка здоровья соединения: `RETURN 1` с таймаутом подключения
+    ///
+    /// Без повторов: недоступность базы должна сразу попасть в статус здоровья.
     pub async fn health_check(&self) -> Result<()> {
-        let connected = self.connected.read().await;
-        if *connected {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("Neo4j не подключен"))
-        }
+        let mut result = with_timeout(
+            "проверка Neo4j",
+            self.config.connection_timeout(),
+            self.graph.execute(Query::new("RETURN 1 AS ok".to_string())),
+        ).await?;
+        with_timeout("проверка Neo4j", self.config.connection_timeout(), result.next()).await?
+            .ok_or_else(|| anyhow::anyhow!("Neo4j не вернул результат проверки"))?;
+        Ok(())
     }
 }
 
 /// Структура для представления связи графа
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GraphEdge {
     pub source_id: String,
     pub target_id: String,
     pub weight: f32,
     pub edge_type: String,
+    /// Кластер (домен/тема) источника, если задан
+    pub source_cluster: Option<String>,
+    /// Кластер цели, если задан
+    pub target_cluster: Option<String>,
+    /// Год публикации источника (свойство `neo4j.year_property`), если известен
+    pub source_year: Option<i32>,
+    /// Год публикации цели, если известен
+    pub target_year: Option<i32>,
 }
 
-/// Структура для представления позиции вершины
+impl GraphEdge {
+    /// Связь `BIBLIOGRAPHIC_LINK` без кластеров и годов; остальные поля
+    /// задаются через `GraphEdge { source_year, ..GraphEdge::new(..) }`
+    pub fn new(source_id: impl Into<String>, target_id: impl Into<String>, weight: f32) -> Self {
+        Self {
+            source_id: source_id.into(),
+            target_id: target_id.into(),
+            weight,
+            edge_type: "BIBLIOGRAPHIC_LINK".to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Связи мультиграфа `graph` для `compute_layout` (например, графа из petgraph)
+    pub fn from_graph(graph: &crate::data_structures::Graph) -> Vec<GraphEdge> {
+        let uids = graph.interner().uids();
+        graph
+            .edge_keys()
+            .map(|key| GraphEdge {
+                source_id: uids[key.source].clone(),
+                target_id: uids[key.target].clone(),
+                weight: graph.edge_key_weight(key).unwrap_or_default(),
+                edge_type: String::new(),
+                source_cluster: None,
+                target_cluster: None,
+                source_year: None,
+                target_year: None,
+            })
+            .collect()
+    }
+}
+
+/// Позиция батчевой загрузки связей
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdgeCursor {
+    /// SKIP/LIMIT: количество уже прочитанных строк
+    Offset(usize),
+    /// Keyset: ключ последней прочитанной связи (None — с начала)
+    After(Option<EdgeCursorKey>),
+}
+
+impl EdgeCursor {
+    /// Позиция после `rows` прочитанных строк; `last_key` — ключ последней из них
+    pub fn advance(&self, rows: usize, last_key: Option<EdgeCursorKey>) -> EdgeCursor {
+        match self {
+            EdgeCursor::Offset(offset) => EdgeCursor::Offset(offset + rows),
+            EdgeCursor::After(after) => EdgeCursor::After(last_key.or_else(|| after.clone())),
+        }
+    }
+}
+
+impl std::fmt::Display for EdgeCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeCursor::Offset(offset) => write!(f, "offset={}", offset),
+            EdgeCursor::After(None) => write!(f, "с начала"),
+            EdgeCursor::After(Some(key)) => write!(f, "после {}", key),
+        }
+    }
+}
+
+/// Часть связей для параллельной загрузки: связи с `start <= id(r) < end`
+///
+/// В отличие от остатка `id(r) % count` диапазон сужает просмотр связей
+/// каждой части. Части не пересекаются и вместе покрывают все связи; каждая
+/// читается своим курсором (`EdgeCursor`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgePartition {
+    pub index: usize,
+    pub count: usize,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl EdgePartition {
+    /// Не более `count` непустых частей диапазона `min_id..=max_id` примерно равной длины
+    pub fn split(count: usize, min_id: i64, max_id: i64) -> impl Iterator<Item = EdgePartition> {
+        let span = (max_id as i128 - min_id as i128 + 1).max(0);
+        let count = (count.max(1) as i128).min(span.max(1)) as usize;
+        let bound = move |i: usize| (min_id as i128 + span * i as i128 / count as i128) as i64;
+        (0..count).map(move |index| EdgePartition { index, count, start: bound(index), end: bound(index + 1) })
+    }
+
+    /// Условие WHERE части
+    fn clause(&self) -> String {
+        format!(" AND id(r) >= {} AND id(r) < {}", self.start, self.end)
+    }
+}
+
+impl std::fmt::Display for EdgePartition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index + 1, self.count)
+    }
+}
+
+/// Значение ключа keyset-пагинации: `elementId(r)` или свойство связи
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdgeKeyValue {
+    Int(i64),
+    Text(String),
+}
+
+impl EdgeKeyValue {
+    /// Ключ из колонки строки результата (целое или строка)
+    fn from_row(row: &Row, column: &str) -> Option<Self> {
+        row.get::<i64>(column)
+            .map(EdgeKeyValue::Int)
+            .or_else(|_| row.get::<String>(column).map(EdgeKeyValue::Text))
+            .ok()
+    }
+}
+
+/// Составной ключ keyset-курсора: значение ключа и `elementId(r)`,
+/// различающий связи с одинаковым значением свойства
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeCursorKey {
+    pub value: EdgeKeyValue,
+    pub element_id: String,
+}
+
+impl EdgeCursorKey {
+    /// Ключ из колонок `edge_key` и `edge_id` строки результата
+    fn from_row(row: &Row) -> Option<Self> {
+        Some(EdgeCursorKey {
+            value: EdgeKeyValue::from_row(row, "edge_key")?,
+            element_id: row.get::<String>("edge_id").ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for EdgeCursorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            EdgeKeyValue::Text(value) if *value == self.element_id => write!(f, "{}", value),
+            EdgeKeyValue::Text(value) => write!(f, "{} ({})", value, self.element_id),
+            EdgeKeyValue::Int(value) => write!(f, "{} ({})", value, self.element_id),
+        }
+    }
+}
+
+/// Батч связей и позиция следующего батча
 #[derive(Debug, Clone)]
+pub struct EdgePage {
+    /// Связи батча после выборки
+    pub edges: Vec<GraphEdge>,
+    /// Прочитанные строки
+    pub rows: usize,
+    /// Курсор следующего батча (None — связи закончились)
+    pub next: Option<EdgeCursor>,
+}
+
+/// Агрегаты связей вершины, сохраняемые вместе с координатами
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VertexAggregates {
+    /// Входящие связи (сколько раз статью цитируют)
+    pub in_degree: u32,
+    /// Исходящие связи (сколько статей она цитирует)
+    pub out_degree: u32,
+    /// Суммарный вес входящих связей
+    pub in_weight: f64,
+    /// Суммарный вес исходящих связей
+    pub out_weight: f64,
+}
+
+impl VertexAggregates {
+    fn insert_params(&self, row: &mut HashMap<String, BoltType>) {
+        row.insert("in_degree".to_string(), (self.in_degree as i64).into());
+        row.insert("out_degree".to_string(), (self.out_degree as i64).into());
+        row.insert("in_weight".to_string(), self.in_weight.into());
+        row.insert("out_weight".to_string(), self.out_weight.into());
+    }
+}
+
+/// Накопление агрегатов по батчу связей
+///
+/// Пропускаются те же связи, что отбрасывает построение графа (пустые ID и
+/// self-loops); повторная связь учитывается повторно, как и в Neo4j.
+pub fn accumulate_vertex_aggregates<'a>(
+    aggregates: &mut HashMap<String, VertexAggregates>,
+    edges: impl IntoIterator<Item = &'a GraphEdge>,
+) {
+    for edge in edges {
+        if !crate::data_structures::GraphBuilder::accepts(&edge.source_id, &edge.target_id) {
+            continue;
+        }
+        let weight = if edge.weight.is_finite() { edge.weight as f64 } else { 0.0 };
+
+        let source = aggregates.entry(edge.source_id.clone()).or_default();
+        source.out_degree += 1;
+        source.out_weight += weight;
+
+        let target = aggregates.entry(edge.target_id.clone()).or_default();
+        target.in_degree += 1;
+        target.in_weight += weight;
+    }
+}
+
+/// Диапазон позиций, который не удалось сохранить
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FailedSaveBatch {
+    /// Номер батча (с нуля)
+    pub batch: usize,
+    /// Начало диапазона строк (включительно)
+    pub start: usize,
+    /// Конец диапазона строк (не включительно)
+    pub end: usize,
+    /// Последняя ошибка
+    pub error: String,
+}
+
+/// Итог сохранения укладки
+#[derive(Debug, Clone, Default)]
+pub struct SaveReport {
+    pub total_rows: usize,
+    pub saved_rows: usize,
+    pub failed_batches: Vec<FailedSaveBatch>,
+    /// Файл карантина с позициями для ручного повтора
+    pub quarantine_path: Option<std::path::PathBuf>,
+}
+
+impl SaveReport {
+    /// Все ли батчи сохранены
+    pub fn is_complete(&self) -> bool {
+        self.failed_batches.is_empty()
+    }
+
+    /// Количество строк, требующих повторного сохранения
+    pub fn failed_rows(&self) -> usize {
+        self.failed_batches.iter().map(|f| f.end - f.start).sum()
+    }
+}
+
+/// Содержимое файла карантина
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct QuarantineFile {
+    pub created_at: String,
+    pub failed_batches: Vec<FailedSaveBatch>,
+    pub positions: Vec<VertexPosition>,
+}
+
+/// Запись несохранённых позиций в файл карантина
+pub fn write_quarantine_file(
+    path: &std::path::Path,
+    report: &SaveReport,
+    positions: &[VertexPosition],
+) -> Result<()> {
+    let quarantined: Vec<VertexPosition> = report
+        .failed_batches
+        .iter()
+        .flat_map(|f| positions[f.start..f.end.min(positions.len())].iter().cloned())
+        .collect();
+
+    let file = QuarantineFile {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        failed_batches: report.failed_batches.clone(),
+        positions: quarantined,
+    };
+
+    let json = serde_json::to_string(&file)?;
+    std::fs::write(path, json)
+        .map_err(|e| anyhow::anyhow!("Не удалось записать файл карантина {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Чтение позиций из файла карантина для повторного сохранения
+pub fn read_quarantine_file(path: &std::path::Path) -> Result<QuarantineFile> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Не удалось прочитать файл карантина {}: {}", path.display(), e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Reservoir sampling связей на стороне клиента
+///
+/// Хранит равномерную выборку не более `capacity` элементов из потока
+/// (capacity = 0 — без ограничения, сохраняются все элементы).
+#[derive(Debug)]
+pub struct EdgeReservoir {
+    capacity: usize,
+    seen: usize,
+    items: Vec<GraphEdge>,
+    rng_state: u64,
+}
+
+impl EdgeReservoir {
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity.min(1_000_000)),
+            rng_state: seed | 1,
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64*
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        self.rng_state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Добавление элемента потока
+    pub fn push(&mut self, edge: GraphEdge) {
+        self.seen += 1;
+        if self.capacity == 0 || self.items.len() < self.capacity {
+            self.items.push(edge);
+            return;
+        }
+        let j = (self.next_random() % self.seen as u64) as usize;
+        if j < self.capacity {
+            self.items[j] = edge;
+        }
+    }
+
+    /// Количество просмотренных элементов
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_inner(self) -> Vec<GraphEdge> {
+        self.items
+    }
+}
+
+/// Адаптивный размер батча загрузки
+///
+/// Стартует с настроенного `batch_size`: при таймауте/ошибке батч уменьшается вдвое
+/// (не ниже `min`), после быстрых батчей растёт в 1.5 раза (не выше `max`).
+#[derive(Debug, Clone)]
+pub struct AdaptiveBatchSize {
+    current: usize,
+    min: usize,
+    max: usize,
+    fast_threshold: std::time::Duration,
+    enabled: bool,
+}
+
+impl AdaptiveBatchSize {
+    /// Создание адаптивного контроллера
+    pub fn new(initial: usize, min: usize, max: usize, fast_threshold: std::time::Duration) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        Self {
+            current: initial.clamp(min, max),
+            min,
+            max,
+            fast_threshold,
+            enabled: true,
+        }
+    }
+
+    /// Фиксированный размер батча (адаптация выключена)
+    pub fn fixed(size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            current: size,
+            min: size,
+            max: size,
+            fast_threshold: std::time::Duration::ZERO,
+            enabled: false,
+        }
+    }
+
+    /// Текущий размер батча
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Учёт успешного батча: рост размера, если батч был быстрым
+    pub fn on_success(&mut self, elapsed: std::time::Duration) {
+        if self.enabled && elapsed < self.fast_threshold && self.current < self.max {
+            let grown = self.current + self.current / 2;
+            self.current = grown.max(self.current + 1).min(self.max);
+        }
+    }
+
+    /// Учёт неудачного батча (таймаут/ошибка).
+    ///
+    /// Возвращает `true`, если размер был уменьшен и батч стоит повторить.
+    pub fn on_failure(&mut self) -> bool {
+        if !self.enabled || self.current <= self.min {
+            return false;
+        }
+        self.current = (self.current / 2).max(self.min);
+        true
+    }
+}
+
+/// Метаданные системы координат сохранённой укладки
+///
+/// Начало координат — левый верхний угол: слой растёт по оси X, уровень — по оси Y (вниз).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LayoutMeta {
+    pub run_id: String,
+    pub algorithm_version: String,
+    pub block_width: f32,
+    pub block_height: f32,
+    pub horizontal_gap: f32,
+    pub vertical_gap: f32,
+    pub origin_x: f32,
+    pub origin_y: f32,
+    pub level_packing: String,
+    pub vertex_count: usize,
+    pub created_at: String,
+    /// Профиль масштаба, из которого взяты размеры блоков и отступы
+    #[serde(default)]
+    pub scale_profile: String,
+}
+
+impl LayoutMeta {
+    /// Свойства узла `(:LayoutMeta)`
+    pub fn to_params(&self) -> HashMap<String, BoltType> {
+        let mut m: HashMap<String, BoltType> = HashMap::new();
+        m.insert("run_id".to_string(), self.run_id.clone().into());
+        m.insert("algorithm_version".to_string(), self.algorithm_version.clone().into());
+        m.insert("block_width".to_string(), (self.block_width as f64).into());
+        m.insert("block_height".to_string(), (self.block_height as f64).into());
+        m.insert("horizontal_gap".to_string(), (self.horizontal_gap as f64).into());
+        m.insert("vertical_gap".to_string(), (self.vertical_gap as f64).into());
+        m.insert("origin_x".to_string(), (self.origin_x as f64).into());
+        m.insert("origin_y".to_string(), (self.origin_y as f64).into());
+        m.insert("x_axis".to_string(), "layer".to_string().into());
+        m.insert("y_axis".to_string(), "level".to_string().into());
+        m.insert("level_packing".to_string(), self.level_packing.clone().into());
+        m.insert("vertex_count".to_string(), (self.vertex_count as i64).into());
+        m.insert("created_at".to_string(), self.created_at.clone().into());
+        m.insert("scale_profile".to_string(), self.scale_profile.clone().into());
+        m
+    }
+}
+
+/// Свойства позиции статьи; атомарное сохранение держит их копии с суффиксами
+/// `_new` (записаны, ещё не переключены) и `_prev` (значения до переключения)
+const LAYOUT_PROPERTIES: [&str; 4] = ["layer", "level", "x", "y"];
+
+/// Агрегаты связей статьи (`save_vertex_aggregates`)
+const AGGREGATE_PROPERTIES: [&str; 4] = ["in_degree", "out_degree", "in_weight", "out_weight"];
+
+/// Список свойств `var.name{suffix}` через запятую (для REMOVE)
+fn property_list(var: &str, properties: &[&str], suffix: &str) -> String {
+    properties
+        .iter()
+        .map(|p| format!("{}.{}{}", var, p, suffix))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Запрос записи батча позиций в свойства с суффиксом `suffix` ("" или "_new")
+fn save_batch_query(suffix: &str, with_aggregates: bool) -> String {
+    let mut properties = LAYOUT_PROPERTIES.to_vec();
+    if with_aggregates {
+        properties.extend(AGGREGATE_PROPERTIES);
+    }
+    let mut assignments: Vec<String> = properties
+        .iter()
+        .map(|p| format!("a.{0}{1} = row.{0}", p, suffix))
+        .collect();
+    if !suffix.is_empty() {
+        assignments.push(format!("a.layout_run{} = $run_id", suffix));
+    }
+    format!(
+        "UNWIND $rows AS row \
+         MATCH (a:Article {{uid: row.uid}}) \
+         SET {}",
+        assignments.join(", ")
+    )
+}
+
+/// Переключение `*_new` сохранения `$run_id` в основные свойства одним запросом (одна транзакция)
+///
+/// Прежние координаты и агрегаты копируются в `*_prev` с флагом `layout_prev`
+/// для `rollback_layout`; копии предыдущего переключения сначала удаляются.
+/// Агрегаты без `*_new` остаются прежними.
+fn flip_staged_query() -> String {
+    let mut saved = LAYOUT_PROPERTIES.to_vec();
+    saved.extend(AGGREGATE_PROPERTIES);
+
+    let mut assignments: Vec<String> = saved
+        .iter()
+        .map(|p| format!("a.{0}_prev = a.{0}", p))
+        .collect();
+    assignments.push("a.layout_prev = true".to_string());
+    assignments.extend(LAYOUT_PROPERTIES.iter().map(|p| format!("a.{0} = a.{0}_new", p)));
+    assignments.extend(AGGREGATE_PROPERTIES.iter().map(|p| format!("a.{0} = coalesce(a.{0}_new, a.{0})", p)));
+
+    format!(
+        "OPTIONAL MATCH (old:Article) WHERE old.layout_prev = true \
+         REMOVE {}, old.layout_prev \
+         WITH count(old) AS cleared \
+         MATCH (a:Article) WHERE a.layout_run_new = $run_id \
+         SET {} \
+         REMOVE {}, a.layout_run_new \
+         RETURN count(a) AS flipped",
+        property_list("old", &saved, "_prev"),
+        assignments.join(", "),
+        property_list("a", &saved, "_new")
+    )
+}
+
+/// Откат к копиям `*_prev` и возврат отметки `current` предыдущему `(:LayoutRun)` (одна транзакция)
+///
+/// Запуски не трогаются, если восстанавливать нечего.
+fn rollback_query() -> String {
+    let mut saved = LAYOUT_PROPERTIES.to_vec();
+    saved.extend(AGGREGATE_PROPERTIES);
+    let restore: Vec<String> = saved.iter().map(|p| format!("a.{0} = a.{0}_prev", p)).collect();
+    format!(
+        "OPTIONAL MATCH (a:Article) WHERE a.layout_prev = true \
+         SET {} \
+         REMOVE {}, a.layout_prev \
+         WITH count(a) AS restored \
+         OPTIONAL MATCH (run:LayoutRun {{current: true}}) \
+         OPTIONAL MATCH (run)-[:PREVIOUS]->(prev:LayoutRun) \
+         FOREACH (r IN CASE WHEN restored > 0 AND run IS NOT NULL THEN [run] ELSE [] END | \
+             REMOVE r.current SET r.rolled_back = true) \
+         FOREACH (p IN CASE WHEN restored > 0 AND prev IS NOT NULL THEN [prev] ELSE [] END | \
+             SET p.current = true) \
+         RETURN restored",
+        restore.join(", "),
+        property_list("a", &saved, "_prev")
+    )
+}
+
+/// Статистика запуска укладки для узла `(:LayoutRun)`
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct LayoutRunStats {
+    /// Инкрементальный запуск (`--incremental`)
+    pub incremental: bool,
+    /// Откуда загружены связи: `neo4j` или `graph_file`
+    pub edges_source: String,
+    /// Загружено связей
+    pub edges: usize,
+    /// Записано позиций
+    pub positions: usize,
+    /// Количество слоёв
+    pub layers: usize,
+    /// Связей развёрнуто или отклонено из-за циклов
+    pub cycle_edges: usize,
+    /// Повторное разбиение вырожденной укладки на слои (JSON `LayeringFallback`)
+    pub layering_fallback: Option<String>,
+    /// Длительность запуска, мс
+    pub duration_ms: u64,
+    /// Версия движка укладки
+    pub algorithm_version: String,
+}
+
+impl LayoutRunStats {
+    /// Свойства узла `(:LayoutRun)`
+    pub fn to_params(&self) -> HashMap<String, BoltType> {
+        let mut m: HashMap<String, BoltType> = HashMap::new();
+        m.insert("incremental".to_string(), self.incremental.into());
+        m.insert("edges_source".to_string(), self.edges_source.clone().into());
+        m.insert("edges".to_string(), (self.edges as i64).into());
+        m.insert("positions".to_string(), (self.positions as i64).into());
+        m.insert("layers".to_string(), (self.layers as i64).into());
+        m.insert("cycle_edges".to_string(), (self.cycle_edges as i64).into());
+        if let Some(ref fallback) = self.layering_fallback {
+            m.insert("layering_fallback".to_string(), fallback.clone().into());
+        }
+        m.insert("duration_ms".to_string(), (self.duration_ms as i64).into());
+        m.insert("algorithm_version".to_string(), self.algorithm_version.clone().into());
+        m
+    }
+}
+
+/// Маршрут связи: ломаная от правого края блока источника до левого края блока цели
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EdgePath {
+    pub source_id: String,
+    pub target_id: String,
+
+    /// Точки ломаной (x, y), включая концы
+    pub points: Vec<(f32, f32)>,
+
+    /// Пучок связей (edge bundling), если связь в него входит
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+
+    /// Контрольные точки кривых Безье, по две на отрезок (пусто для ломаной)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub control_points: Vec<(f32, f32)>,
+
+    /// Порт на правой стороне блока источника: смещение начала от его вертикального центра
+    #[serde(default)]
+    pub source_port: f32,
+
+    /// Порт на левой стороне блока цели: смещение конца от его вертикального центра
+    #[serde(default)]
+    pub target_port: f32,
+
+    /// Номер связи среди параллельных связей пары (мультиграф); 0 — первая
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub edge_index: usize,
+
+    /// Длина ломаной в пикселях
+    #[serde(default)]
+    pub length: f32,
+
+    /// Изломы: смены направления ломаной
+    #[serde(default)]
+    pub bends: usize,
+
+    /// Слоёв между источником и целью
+    #[serde(default)]
+    pub layer_span: i32,
+
+    /// Пересечения маршрута с другими связями
+    #[serde(default)]
+    pub crossings: usize,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+impl EdgePath {
+    /// Строка UNWIND для `save_edge_paths`: свойства связи хранят только
+    /// списки примитивов, поэтому точки разложены на `path_x` и `path_y`
+    /// (контрольные точки — на `control_x` и `control_y`)
+    pub fn to_params(&self) -> HashMap<String, BoltType> {
+        let unzip = |points: &[(f32, f32)]| -> (Vec<f64>, Vec<f64>) {
+            points.iter().map(|&(x, y)| (x as f64, y as f64)).unzip()
+        };
+        let (xs, ys) = unzip(&self.points);
+        let (control_xs, control_ys) = unzip(&self.control_points);
+        let mut m: HashMap<String, BoltType> = HashMap::new();
+        m.insert("source".to_string(), self.source_id.clone().into());
+        m.insert("target".to_string(), self.target_id.clone().into());
+        m.insert("path_x".to_string(), xs.into());
+        m.insert("path_y".to_string(), ys.into());
+        m.insert("bundle_id".to_string(), self.bundle_id.clone().unwrap_or_default().into());
+        m.insert("control_x".to_string(), control_xs.into());
+        m.insert("control_y".to_string(), control_ys.into());
+        m.insert("source_port".to_string(), (self.source_port as f64).into());
+        m.insert("target_port".to_string(), (self.target_port as f64).into());
+        m
+    }
+}
+
+/// Структура для представления позиции вершины
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VertexPosition {
     pub article_id: String,
     pub layer: i32,
@@ -744,3 +2504,440 @@ pub struct VertexPosition {
     pub x: f32,
     pub y: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "failure-injection")]
+    #[tokio::test]
+    async fn test_with_timeout_injected_faults() {
+        use crate::failure_injection::{Fault, FaultPlan, FaultPoint};
+        use std::time::Duration;
+
+        let plan = FaultPlan::install();
+        plan.arm_matching(FaultPoint::Neo4jOperation, "чтение связей", Fault::Timeout, Some(1))
+            .arm_matching(FaultPoint::Neo4jOperation, "медленный батч", Fault::Delay(Duration::from_millis(200)), None);
+        let ok = || async { anyhow::Ok(1) };
+
+        let error = with_timeout("чтение связей", Duration::from_secs(1), ok()).await.unwrap_err();
+        assert!(error.to_string().contains("Таймаут операции Neo4j 'чтение связей'"));
+        // Правило однократное: повторная попытка проходит
+        assert_eq!(with_timeout("чтение связей", Duration::from_secs(1), ok()).await.unwrap(), 1);
+
+        // Задержка длиннее таймаута приводит к настоящему таймауту
+        assert!(with_timeout("медленный батч", Duration::from_millis(50), ok()).await.is_err());
+        assert_eq!(with_timeout("медленный батч", Duration::from_secs(1), ok()).await.unwrap(), 1);
+        assert_eq!(plan.hits(FaultPoint::Neo4jOperation), 3);
+    }
+
+    #[test]
+    fn test_edge_path_params() {
+        let path = EdgePath {
+            source_id: "A".to_string(),
+            target_id: "B".to_string(),
+            points: vec![(160.0, 40.0), (200.0, 40.0), (240.0, 90.0)],
+            bundle_id: None,
+            control_points: vec![],
+            source_port: -10.0,
+            target_port: 0.0,
+            edge_index: 0,
+            length: 94.0,
+            bends: 1,
+            layer_span: 1,
+            crossings: 0,
+        };
+
+        let params = path.to_params();
+        assert_eq!(params.len(), 9);
+        assert_eq!(params["path_x"], vec![160.0f64, 200.0, 240.0].into());
+        assert_eq!(params["path_y"], vec![40.0f64, 40.0, 90.0].into());
+        assert_eq!(params["bundle_id"], String::new().into());
+        assert_eq!(params["control_x"], Vec::<f64>::new().into());
+        assert_eq!(params["source_port"], (-10.0f64).into());
+    }
+
+    #[test]
+    fn test_layout_meta_params() {
+        let meta = LayoutMeta {
+            run_id: "run-1".to_string(),
+            algorithm_version: "1.0.0".to_string(),
+            block_width: 160.0,
+            block_height: 80.0,
+            horizontal_gap: 80.0,
+            vertical_gap: 50.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            level_packing: "OnePerLevel".to_string(),
+            vertex_count: 42,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            scale_profile: "web".to_string(),
+        };
+
+        let params = meta.to_params();
+        assert_eq!(params.len(), 14);
+        assert!(params.contains_key("scale_profile"));
+        assert!(params.contains_key("run_id"));
+        assert!(params.contains_key("block_width"));
+        assert!(params.contains_key("x_axis"));
+    }
+
+    #[test]
+    fn test_atomic_save_queries() {
+        let plain = save_batch_query("", false);
+        assert!(plain.ends_with("SET a.layer = row.layer, a.level = row.level, a.x = row.x, a.y = row.y"));
+
+        let staged = save_batch_query("_new", true);
+        assert!(staged.contains("a.x_new = row.x"));
+        assert!(staged.contains("a.out_weight_new = row.out_weight"));
+
+        assert!(staged.ends_with("a.layout_run_new = $run_id"));
+        assert!(!plain.contains("$run_id"));
+
+        let flip = flip_staged_query();
+        assert!(flip.contains("WHERE a.layout_run_new = $run_id"));
+        assert!(flip.contains("a.x_prev = a.x"));
+        assert!(flip.contains("a.in_degree_prev = a.in_degree"));
+        assert!(flip.contains("a.x = a.x_new"));
+        assert!(flip.contains("a.in_degree = coalesce(a.in_degree_new, a.in_degree)"));
+        assert!(flip.contains("REMOVE a.layer_new, a.level_new, a.x_new, a.y_new, a.in_degree_new"));
+        // Копии предыдущего переключения удаляются до записи новых
+        assert!(flip.find("old.layout_prev").unwrap() < flip.find("a.layout_prev = true").unwrap());
+
+        let rollback = rollback_query();
+        assert!(rollback.contains("a.out_weight = a.out_weight_prev"));
+        // Отметка текущего запуска переходит только после восстановления координат
+        assert!(rollback.find("count(a) AS restored").unwrap() < rollback.find("SET p.current = true").unwrap());
+    }
+
+    #[test]
+    fn test_layout_run_stats_params() {
+        let stats = LayoutRunStats {
+            incremental: true,
+            edges_source: "neo4j".to_string(),
+            edges: 10,
+            positions: 3,
+            layers: 4,
+            cycle_edges: 1,
+            duration_ms: 1500,
+            algorithm_version: "1.0.0".to_string(),
+            ..Default::default()
+        };
+
+        let params = stats.to_params();
+        assert_eq!(params.len(), 8);
+        assert!(params.contains_key("edges_source"));
+        assert!(params.contains_key("incremental"));
+        assert!(params.contains_key("duration_ms"));
+    }
+
+    #[test]
+    fn test_accumulate_vertex_aggregates() {
+        let edge = |source: &str, target: &str, weight: f32| GraphEdge::new(source, target, weight);
+
+        let mut aggregates = HashMap::new();
+        accumulate_vertex_aggregates(&mut aggregates, &[edge("A", "B", 1.0), edge("C", "B", 0.5)]);
+        // Следующий батч дополняет уже накопленные значения; self-loop и пустой ID пропускаются
+        accumulate_vertex_aggregates(&mut aggregates, &[edge("B", "D", 2.0), edge("B", "B", 1.0), edge("", "B", 1.0)]);
+
+        assert_eq!(aggregates.len(), 4);
+        let b = aggregates["B"];
+        assert_eq!((b.in_degree, b.out_degree), (2, 1));
+        assert_eq!((b.in_weight, b.out_weight), (1.5, 2.0));
+        assert_eq!(aggregates["A"].out_degree, 1);
+        assert_eq!(aggregates["D"].in_weight, 2.0);
+
+        let mut row = HashMap::new();
+        b.insert_params(&mut row);
+        assert_eq!(row.len(), 4);
+        assert!(row.contains_key("in_weight"));
+    }
+
+    fn test_config(relationship_weights: Vec<(String, f32)>) -> Neo4jConfig {
+        Neo4jConfig {
+            uri: "bolt://localhost:7687".to_string(),
+            user: "neo4j".to_string(),
+            password: "password".to_string(),
+            database: "neo4j".to_string(),
+            pool_size: 1,
+            connection_timeout: 30,
+            transaction_timeout: 300,
+            batch_size: 1000,
+            relationship_weights,
+            sample_rate: 1.0,
+            max_edges: 0,
+            save_quarantine_path: "layout_save_quarantine.json".to_string(),
+            deterministic: false,
+            seed: 42,
+            pagination: EdgePagination::Keyset,
+            keyset_property: String::new(),
+            year_property: "year".to_string(),
+            load_parallelism: 1,
+            atomic_save: false,
+            retry: RetryPolicy::default(),
+            tls_verify: TlsVerifyMode::Auto,
+            tls_ca_cert_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_quarantine_file_roundtrip() {
+        let positions: Vec<VertexPosition> = (0..10)
+            .map(|i| VertexPosition {
+                article_id: format!("a{}", i),
+                layer: i,
+                level: 0,
+                x: i as f32,
+                y: 0.0,
+            })
+            .collect();
+
+        let report = SaveReport {
+            total_rows: 10,
+            saved_rows: 7,
+            failed_batches: vec![FailedSaveBatch {
+                batch: 1,
+                start: 3,
+                end: 6,
+                error: "timeout".to_string(),
+            }],
+            quarantine_path: None,
+        };
+        assert!(!report.is_complete());
+        assert_eq!(report.failed_rows(), 3);
+
+        let path = std::env::temp_dir().join(format!("quarantine_{}.json", uuid::Uuid::new_v4()));
+        write_quarantine_file(&path, &report, &positions).unwrap();
+        let file = read_quarantine_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(file.failed_batches.len(), 1);
+        let ids: Vec<&str> = file.positions.iter().map(|p| p.article_id.as_str()).collect();
+        assert_eq!(ids, vec!["a3", "a4", "a5"]);
+    }
+
+    fn test_edge(i: usize) -> GraphEdge {
+        GraphEdge::new(format!("s{}", i), format!("t{}", i), 1.0)
+    }
+
+    #[tokio::test]
+    async fn test_with_timeout_reports_operation() {
+        let ok: Result<u32> = with_timeout(
+            "быстрая операция",
+            std::time::Duration::from_secs(1),
+            async { Ok::<u32, anyhow::Error>(7) },
+        ).await;
+        assert_eq!(ok.unwrap(), 7);
+
+        let timed_out: Result<()> = with_timeout(
+            "медленная операция",
+            std::time::Duration::from_millis(10),
+            async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                Ok::<(), anyhow::Error>(())
+            },
+        ).await;
+        let message = timed_out.unwrap_err().to_string();
+        assert!(message.contains("медленная операция"));
+    }
+
+    #[test]
+    fn test_parse_year() {
+        assert_eq!(parse_year("1998"), Some(1998));
+        assert_eq!(parse_year(" 2004-05-01"), Some(2004));
+        assert_eq!(parse_year("n/a"), None);
+        assert_eq!(parse_year("98"), None);
+    }
+    
+    #[test]
+    fn test_timeouts_from_config() {
+        let config = test_config(Vec::new());
+        assert_eq!(config.connection_timeout(), std::time::Duration::from_secs(30));
+        assert_eq!(config.query_timeout(), std::time::Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_connection_uri() {
+        let mut config = test_config(Vec::new());
+        assert_eq!(config.connection_uri().unwrap(), "bolt://localhost:7687");
+
+        config.uri = "neo4j+s://abc.databases.neo4j.io".to_string();
+        assert_eq!(config.connection_uri().unwrap(), "neo4j+s://abc.databases.neo4j.io");
+        config.tls_verify = TlsVerifyMode::Disabled;
+        assert!(config.connection_uri().is_err());
+
+        config.uri = "neo4j://cluster:7687".to_string();
+        config.tls_verify = TlsVerifyMode::TrustAll;
+        assert_eq!(config.connection_uri().unwrap(), "neo4j+ssc://cluster:7687");
+        config.tls_ca_cert_path = "ca.pem".to_string();
+        assert!(config.connection_uri().is_err());
+
+        config.tls_verify = TlsVerifyMode::Full;
+        config.tls_ca_cert_path = "/nonexistent/ca.pem".to_string();
+        assert!(config.connection_uri().is_err());
+        config.tls_ca_cert_path.clear();
+        assert_eq!(config.connection_uri().unwrap(), "neo4j+s://cluster:7687");
+
+        config.uri = "http://localhost:7474".to_string();
+        assert!(config.connection_uri().is_err());
+    }
+
+    #[test]
+    fn test_year_columns_use_configured_property() {
+        let mut config = test_config(Vec::new());
+        assert_eq!(
+            config.year_columns(),
+            "toString(a.`year`) as source_year, toString(b.`year`) as target_year"
+        );
+        config.year_property = "pub`_year".to_string();
+        assert!(config.year_columns().starts_with("toString(a.`pub_year`) as source_year"));
+    }
+
+    #[test]
+    fn test_keyset_cursor() {
+        let mut config = test_config(Vec::new());
+        assert_eq!(config.keyset_key(), "elementId(r)");
+        config.keyset_property = "created`_at".to_string();
+        assert_eq!(config.keyset_key(), "r.`created_at`");
+
+        assert_eq!(EdgeCursor::Offset(500).to_string(), "offset=500");
+        assert_eq!(EdgeCursor::After(None).to_string(), "с начала");
+        let element_key = |id: &str| EdgeCursorKey {
+            value: EdgeKeyValue::Text(id.to_string()),
+            element_id: id.to_string(),
+        };
+        assert_eq!(EdgeCursor::After(Some(element_key("5:abc:7"))).to_string(), "после 5:abc:7");
+        let property_key = |value: i64, id: &str| EdgeCursorKey {
+            value: EdgeKeyValue::Int(value),
+            element_id: id.to_string(),
+        };
+        assert_eq!(EdgeCursor::After(Some(property_key(7, "5:abc:9"))).to_string(), "после 7 (5:abc:9)");
+
+        let partitions: Vec<EdgePartition> = EdgePartition::split(4, 0, 99).collect();
+        assert_eq!(partitions.len(), 4);
+        assert_eq!(partitions[3].clause(), " AND id(r) >= 75 AND id(r) < 100");
+        assert_eq!(partitions[0].to_string(), "1/4");
+
+        // Продолжение после частично прочитанной страницы
+        assert_eq!(EdgeCursor::Offset(500).advance(20, None), EdgeCursor::Offset(520));
+        let after = EdgeCursor::After(Some(property_key(7, "5:abc:9")));
+        assert_eq!(
+            after.advance(3, Some(property_key(7, "5:abc:12"))),
+            EdgeCursor::After(Some(property_key(7, "5:abc:12")))
+        );
+        assert_eq!(after.advance(0, None), after);
+    }
+
+    #[test]
+    fn test_edge_partitions_are_disjoint_and_cover_all_edges() {
+        for (count, min_id, max_id) in [(4, 0, 99), (3, 17, 17), (8, 5, 9), (7, 1000, 123_456), (5, i64::MAX - 10, i64::MAX - 1)] {
+            let partitions: Vec<EdgePartition> = EdgePartition::split(count, min_id, max_id).collect();
+            assert!(!partitions.is_empty() && partitions.len() <= count);
+            assert_eq!(partitions[0].start, min_id);
+            assert_eq!(partitions.last().unwrap().end, max_id + 1);
+            for (index, partition) in partitions.iter().enumerate() {
+                assert_eq!(partition.index, index);
+                assert_eq!(partition.count, partitions.len());
+                assert!(partition.start < partition.end, "пустая часть {:?}", partition);
+            }
+            // Соседние части стыкуются: каждый id попадает ровно в одну
+            for pair in partitions.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+            }
+            let ids = [min_id, max_id, min_id + (max_id - min_id) / 2];
+            for id in ids {
+                let owners = partitions.iter().filter(|p| p.start <= id && id < p.end).count();
+                assert_eq!(owners, 1, "id {} в {} частях", id, owners);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sampling_clause() {
+        let mut config = test_config(Vec::new());
+        assert_eq!(config.sampling_clause(), "");
+        config.sample_rate = 0.1;
+        assert_eq!(config.sampling_clause(), " AND rand() < 0.1");
+
+        // Детерминированный режим: хеш id(r) и seed в самом запросе
+        config.deterministic = true;
+        let clause = config.sampling_clause();
+        assert_eq!(
+            clause,
+            " AND (((id(r) % 1000003) * 7919 + 42) % 1000003 * 104729 + 42) % 1000003 < 100001"
+        );
+        config.seed = 7;
+        assert_ne!(config.sampling_clause(), clause);
+        config.sample_rate = 1.0;
+        assert_eq!(config.sampling_clause(), "");
+    }
+
+    #[test]
+    fn test_edge_reservoir() {
+        let mut unlimited = EdgeReservoir::new(0, 42);
+        for i in 0..100 {
+            unlimited.push(test_edge(i));
+        }
+        assert_eq!(unlimited.into_inner().len(), 100);
+
+        let mut reservoir = EdgeReservoir::new(10, 42);
+        for i in 0..1000 {
+            reservoir.push(test_edge(i));
+        }
+        assert_eq!(reservoir.seen(), 1000);
+        let sample = reservoir.into_inner();
+        assert_eq!(sample.len(), 10);
+        // Выборка не должна состоять только из первых элементов потока
+        assert!(sample.iter().any(|e| e.source_id[1..].parse::<usize>().unwrap() >= 10));
+    }
+
+    #[test]
+    fn test_relationship_pattern_and_weights() {
+        let all = test_config(Vec::new());
+        assert_eq!(all.relationship_pattern(), "[r]");
+        assert_eq!(all.edge_weight("BIBLIOGRAPHIC_LINK"), 1.0);
+
+        let typed = test_config(vec![
+            ("BIBLIOGRAPHIC_LINK".to_string(), 1.0),
+            ("CO_AUTHORSHIP".to_string(), 0.3),
+        ]);
+        assert_eq!(typed.relationship_pattern(), "[r:`BIBLIOGRAPHIC_LINK`|`CO_AUTHORSHIP`]");
+        assert_eq!(typed.relationship_types_clause(), ":`BIBLIOGRAPHIC_LINK`|`CO_AUTHORSHIP`");
+        assert_eq!(all.relationship_types_clause(), "");
+        assert_eq!(typed.edge_weight("CO_AUTHORSHIP"), 0.3);
+        assert_eq!(typed.edge_weight("UNKNOWN"), 1.0);
+    }
+
+    #[test]
+    fn test_adaptive_batch_size() {
+        let fast = std::time::Duration::from_millis(100);
+        let mut adaptive = AdaptiveBatchSize::new(1000, 200, 2000, fast);
+
+        // Таймаут: уменьшение вдвое до минимума
+        assert!(adaptive.on_failure());
+        assert_eq!(adaptive.current(), 500);
+        assert!(adaptive.on_failure());
+        assert!(adaptive.on_failure());
+        assert_eq!(adaptive.current(), 200);
+        assert!(!adaptive.on_failure());
+
+        // Быстрые батчи: рост до максимума
+        adaptive.on_success(std::time::Duration::from_millis(10));
+        assert_eq!(adaptive.current(), 300);
+        for _ in 0..10 {
+            adaptive.on_success(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(adaptive.current(), 2000);
+
+        // Медленный батч не меняет размер
+        let mut slow = AdaptiveBatchSize::new(1000, 200, 2000, fast);
+        slow.on_success(std::time::Duration::from_secs(1));
+        assert_eq!(slow.current(), 1000);
+
+        // Фиксированный размер не адаптируется
+        let mut fixed = AdaptiveBatchSize::fixed(1000);
+        assert!(!fixed.on_failure());
+        fixed.on_success(std::time::Duration::ZERO);
+        assert_eq!(fixed.current(), 1000);
+    }
+}
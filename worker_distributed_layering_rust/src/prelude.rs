@@ -0,0 +1,28 @@
+/*!
+# Prelude
+
+Типы, необходимые для встраивания движка укладки в другое приложение:
+
+```rust,ignore
+use graph_layout_engine::prelude::*;
+
+let mut engine = HighPerformanceLayoutEngine::new(&options)?;
+let result: LayoutResult = engine.compute_layout(edges, &options).await?;
+```
+
+Содержимое prelude меняется только с новой мажорной версией крейта.
+*/
+
+pub use crate::algorithms::{
+    AlgorithmMetadata, AlgorithmStats, HighPerformanceLayoutEngine, LayoutAlgorithm, LayoutResult,
+};
+pub use crate::algorithms::component_packing::ComponentPackingOptions;
+pub use crate::algorithms::cycle_breaking::CycleBreakStrategy;
+pub use crate::algorithms::incremental::{GraphDelta, IncrementalLayoutEngine, IncrementalUpdate};
+pub use crate::algorithms::phases::LayoutPhases;
+pub use crate::algorithms::vertex_placement::{LayeringStrategy, TieBreak};
+pub use crate::config::Config;
+pub use crate::data_structures::{Graph, GraphBuilder};
+pub use crate::generated::{LayoutOptions, LayoutStatistics};
+pub use crate::graph_store::GraphStore;
+pub use crate::neo4j::{GraphEdge, VertexPosition};
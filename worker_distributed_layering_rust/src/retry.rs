@@ -0,0 +1,263 @@
+/*!
+# Повторные попытки операций Neo4j
+
+Единая политика повторов для всех обращений к Neo4j: число попыток,
+экспоненциальная задержка с потолком и случайным разбросом (jitter), чтобы
+параллельные батчи не повторялись синхронно, и классификация ошибок.
+Ошибки клиента Neo4j (`Neo.ClientError.*`: синтаксис, ограничения схемы) и
+отказ аутентификации не исправятся повтором и возвращаются сразу; таймауты,
+обрывы соединения и временные ошибки сервера повторяются.
+
+Каждая попытка учитывается в метриках `graph_layout_neo4j_attempts_total`
+(исход `success`, `retry`, `failure`) и
+`graph_layout_neo4j_operation_duration_seconds` с меткой операции.
+*/
+
+use anyhow::Result;
+use prometheus::{CounterVec, HistogramOpts, HistogramVec, Opts, Registry};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Политика повторных попыток операции Neo4j
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Максимальное число попыток (не меньше 1)
+    pub max_attempts: u32,
+    /// Задержка перед второй попыткой; дальше удваивается
+    pub base_delay: Duration,
+    /// Потолок задержки
+    pub max_delay: Duration,
+    /// Доля задержки, на которую она случайно уменьшается (0.0..=1.0)
+    pub jitter: f64,
+    /// Можно ли повторять операцию после этой ошибки
+    pub retryable: fn(&anyhow::Error) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+            retryable: is_retryable,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Политика из секции `[neo4j.retry]`
+    pub fn from_config(config: &crate::config::RetryConfig) -> Self {
+        Self::default()
+            .with_max_attempts(config.max_attempts)
+            .with_base_delay(Duration::from_millis(config.base_delay_ms))
+            .with_max_delay(Duration::from_millis(config.max_delay_ms))
+            .with_jitter(config.jitter)
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_retryable(mut self, retryable: fn(&anyhow::Error) -> bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Повторять ли операцию после ошибки `error`, сделав `attempt` попыток
+    pub fn should_retry(&self, attempt: u32, error: &anyhow::Error) -> bool {
+        attempt < self.max_attempts && (self.retryable)(error)
+    }
+
+    /// Задержка после неудачной попытки `attempt` (нумерация с 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay = self.base_delay.saturating_mul(1 << exponent).min(self.max_delay);
+        delay.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+}
+
+/// Случайное число в [0, 1) без отдельной зависимости: `RandomState`
+/// получает новые ключи SipHash при каждом создании
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Классификация ошибок по умолчанию: ошибки клиента и аутентификации постоянны
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error);
+    // Смена лидера кластера — клиентская ошибка, но запрос пройдёт на новом лидере
+    if message.contains("Neo.ClientError.Cluster.NotALeader") {
+        return true;
+    }
+    !(message.contains("Neo.ClientError.")
+        || message.contains("AuthenticationError")
+        || message.contains("authentication failure"))
+}
+
+/// Выполнение операции Neo4j по политике `policy`
+///
+/// `op` получает номер попытки (с 1) и создаёт операцию заново. Возвращается
+/// результат первой успешной попытки или ошибка последней.
+pub async fn with_retries<T, F, Fut>(policy: &RetryPolicy, operation: &'static str, mut op: F) -> Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let start = Instant::now();
+        let result = op(attempt).await;
+        observe_duration(operation, start.elapsed());
+        match result {
+            Ok(value) => {
+                record_attempt(operation, "success");
+                return Ok(value);
+            }
+            Err(e) if policy.should_retry(attempt, &e) => {
+                record_attempt(operation, "retry");
+                let delay = policy.delay(attempt);
+                tracing::warn!(
+                    "⚠️ {}: попытка {}/{} не удалась, повтор через {} мс: {:#}",
+                    operation,
+                    attempt,
+                    policy.max_attempts,
+                    delay.as_millis(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                record_attempt(operation, "failure");
+                return Err(e);
+            }
+        }
+    }
+}
+
+struct RetryMetrics {
+    attempts: CounterVec,
+    duration: HistogramVec,
+}
+
+fn metrics() -> &'static RetryMetrics {
+    static METRICS: OnceLock<RetryMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| RetryMetrics {
+        attempts: CounterVec::new(
+            Opts::new("graph_layout_neo4j_attempts_total", "Neo4j operation attempts by outcome"),
+            &["operation", "outcome"],
+        )
+        .expect("корректное описание метрики"),
+        duration: HistogramVec::new(
+            HistogramOpts::new(
+                "graph_layout_neo4j_operation_duration_seconds",
+                "Duration of a single Neo4j operation attempt in seconds",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0, 120.0]),
+            &["operation"],
+        )
+        .expect("корректное описание метрики"),
+    })
+}
+
+/// Учёт исхода попытки (`success`, `retry`, `failure`) для операций с собственным циклом повторов
+pub fn record_attempt(operation: &str, outcome: &str) {
+    metrics().attempts.with_label_values(&[operation, outcome]).inc();
+}
+
+/// Учёт длительности попытки для операций с собственным циклом повторов
+pub fn observe_duration(operation: &str, duration: Duration) {
+    metrics().duration.with_label_values(&[operation]).observe(duration.as_secs_f64());
+}
+
+/// Регистрация метрик повторов в реестре сборщика метрик
+pub fn register_metrics(registry: &Registry) -> Result<()> {
+    registry.register(Box::new(metrics().attempts.clone()))?;
+    registry.register(Box::new(metrics().duration.clone()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_is_capped_and_jittered() {
+        let policy = RetryPolicy::default()
+            .with_base_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_millis(350))
+            .with_jitter(0.5);
+        for _ in 0..20 {
+            let first = policy.delay(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let capped = policy.delay(10);
+            assert!(capped >= Duration::from_millis(175) && capped <= Duration::from_millis(350));
+        }
+        let exact = policy.clone().with_jitter(0.0);
+        assert_eq!(exact.delay(2), Duration::from_millis(200));
+        assert_eq!(exact.delay(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(is_retryable(&anyhow::anyhow!("Таймаут операции Neo4j 'x' (> 30 сек)")));
+        assert!(is_retryable(&anyhow::anyhow!("Neo.TransientError.Transaction.DeadlockDetected")));
+        assert!(is_retryable(&anyhow::anyhow!("Neo.ClientError.Cluster.NotALeader")));
+        let syntax = anyhow::anyhow!("Neo.ClientError.Statement.SyntaxError: Invalid input")
+            .context("Ошибка операции Neo4j 'x'");
+        assert!(!is_retryable(&syntax));
+        assert!(!is_retryable(&anyhow::anyhow!("AuthenticationError: invalid credentials")));
+    }
+
+    #[tokio::test]
+    async fn test_with_retries_stops_on_success_and_permanent_errors() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(4)
+            .with_base_delay(Duration::from_millis(1));
+
+        let mut calls = 0;
+        let value = with_retries(&policy, "test_transient", |attempt| {
+            calls += 1;
+            async move {
+                if attempt < 3 {
+                    Err(anyhow::anyhow!("временный сбой"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!((value, calls), (3, 3));
+
+        let mut calls = 0;
+        let result: Result<()> = with_retries(&policy, "test_permanent", |_| {
+            calls += 1;
+            async { Err(anyhow::anyhow!("Neo.ClientError.Schema.ConstraintValidationFailed")) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
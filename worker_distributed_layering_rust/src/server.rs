@@ -9,39 +9,169 @@
 */
 
 use crate::config::Config;
+use crate::artifacts::ArtifactStore;
 use crate::algorithms::{HighPerformanceLayoutEngine, LayoutAlgorithm};
-use crate::neo4j::{GraphEdge as Neo4jGraphEdge, VertexPosition as Neo4jVertexPosition};
-use neo4rs::BoltType;
+use crate::neo4j::{GraphEdge as Neo4jGraphEdge, SaveReport, VertexPosition as Neo4jVertexPosition};
+use crate::graph_store::GraphStore;
 use std::collections::HashMap;
 use crate::generated::{
     graph_layout_service_server::GraphLayoutService,
-    LayoutRequest, LayoutResponse, LayoutChunk,
+    LayoutRequest, LayoutResponse, LayoutChunk, NeighborhoodRequest,
     HealthRequest, HealthResponse, MetricsRequest, MetricsResponse,
-    ResponseMetadata, OptimizationFlags,
-    SystemMetrics,
+    ResponseMetadata, OptimizationFlags, MetricsSnapshot,
+    SystemMetrics, ListArtifactsRequest, ListArtifactsResponse,
+    DownloadArtifactRequest, ArtifactChunk,
 };
+use crate::messages::Msg;
 use crate::metrics::MetricsCollector;
 use crate::neo4j::Neo4jClient;
 
 use anyhow::Result;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
-use tracing::{info, error, instrument};
+use tracing::{info, warn, error, instrument};
 use uuid::Uuid;
 
+/// Размер чанка DownloadArtifact (байты)
+const ARTIFACT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// ID арендатора из тела запроса укладки (из метаданных запроса)
+fn layout_request_tenant_id(req: &LayoutRequest) -> &str {
+    req.metadata.as_ref().map_or("", |m| m.tenant_id.as_str())
+}
+
+/// Параметры укладки по умолчанию из конфигурации
+fn default_layout_options(config: &Config) -> crate::generated::LayoutOptions {
+    crate::generated::LayoutOptions {
+        block_width: config.algorithms.block_width,
+        block_height: config.algorithms.block_height,
+        horizontal_gap: config.algorithms.horizontal_gap,
+        vertical_gap: config.algorithms.vertical_gap,
+        exclude_isolated_vertices: config.algorithms.exclude_isolated_vertices,
+        optimize_layout: true,
+        max_iterations: config.algorithms.max_iterations as i32,
+        convergence_threshold: config.algorithms.convergence_threshold,
+        chunk_size: config.performance.chunk_size as i32,
+        max_workers: config.performance.worker_threads as i32,
+        enable_simd: config.performance.enable_simd,
+        enable_gpu: config.performance.enable_gpu,
+        memory_strategy: crate::generated::MemoryStrategy::MemoryAuto as i32,
+        deterministic: config.algorithms.deterministic,
+        layering_strategy: crate::generated::LayeringStrategy::from(config.algorithms.layering) as i32,
+        max_layer_width: config.algorithms.max_layer_width as u32,
+        component_grid_columns: config.algorithms.component_grid_columns as u32,
+        component_cell_width: config.algorithms.component_cell_width,
+        component_cell_height: config.algorithms.component_cell_height,
+        component_origins: config
+            .algorithms
+            .component_origins
+            .iter()
+            .map(|(anchor, &[x, y])| (anchor.clone(), crate::generated::ComponentOrigin { x, y }))
+            .collect(),
+        vertex_sizes: config
+            .algorithms
+            .vertex_sizes
+            .iter()
+            .map(|(vertex_id, &[width, height])| (vertex_id.clone(), crate::generated::VertexSize { width, height }))
+            .collect(),
+        vertex_anchors: config
+            .algorithms
+            .vertex_anchors
+            .iter()
+            .map(|(vertex_id, &[x, y])| (vertex_id.clone(), crate::generated::VertexAnchor { x, y }))
+            .collect(),
+        seed_ids: Vec::new(),
+        neighborhood_depth: 0,
+        level_packing: crate::generated::LevelPacking::from(config.algorithms.level_packing) as i32,
+    }
+}
+
+/// Алгоритм укладки с настройками из конфигурации
+fn configured_engine(
+    config: &Config,
+    options: &crate::generated::LayoutOptions,
+) -> Result<HighPerformanceLayoutEngine> {
+    let mut layout_engine = HighPerformanceLayoutEngine::new(options)?;
+    layout_engine.set_tie_break(config.algorithms.tie_break.into());
+    layout_engine.set_placement_mode(config.algorithms.placement_mode.into());
+    // Без упаковки уровней в запросе — значение из конфигурации сервиса
+    if options.level_packing == crate::generated::LevelPacking::Configured as i32 {
+        layout_engine.set_level_packing(config.algorithms.level_packing.into());
+    }
+    layout_engine.set_centroid_ordering(config.algorithms.centroid_ordering);
+    layout_engine.set_approximate_layer_threshold(config.algorithms.approximate_layer_threshold);
+    layout_engine.set_degenerate_fallback(config.algorithms.degenerate_thresholds());
+    layout_engine.set_layer_constraints(
+        config.algorithms.layer_constraints.clone(),
+        config.algorithms.layer_lower_bounds.clone(),
+    );
+    layout_engine.set_validation_verbosity(config.algorithms.validation_verbosity());
+    layout_engine.set_dummy_vertices(config.algorithms.dummy_vertices, config.algorithms.max_dummy_vertices);
+    layout_engine.set_avoid_vertices(config.algorithms.avoid_vertices);
+    layout_engine.set_edge_channels(Some(config.algorithms.edge_channel_spacing));
+    layout_engine.set_annotation_anchors(config.algorithms.annotation_anchors.then(|| {
+        crate::algorithms::vertex_placement::AnnotationOptions {
+            margin: config.algorithms.annotation_margin,
+            clearance: config.algorithms.annotation_margin / 2.0,
+            ..Default::default()
+        }
+    }));
+    layout_engine.set_edge_bundling(Some(crate::algorithms::vertex_placement::EdgeBundlingOptions {
+        min_fan_in: config.algorithms.bundle_min_fan_in,
+    }));
+    layout_engine.set_multigraph(config.algorithms.allow_self_loops, config.algorithms.allow_parallel_edges);
+    layout_engine.set_graph_memory_mapping(
+        config
+            .memory
+            .use_memory_mapping
+            .then(|| std::path::PathBuf::from(&config.memory.temp_dir)),
+    );
+    layout_engine.set_transitive_reduction(config.algorithms.transitive_reduction.then(|| {
+        crate::algorithms::transitive_reduction::TransitiveReductionOptions {
+            exact_max_vertices: config.algorithms.transitive_reduction_exact_max_vertices,
+            ..Default::default()
+        }
+    }));
+    layout_engine.set_edge_ports(config.algorithms.edge_ports);
+    layout_engine.set_path_simplification(Some(config.algorithms.path_simplification_tolerance));
+    layout_engine.set_edge_smoothing(Some(config.algorithms.edge_smoothing));
+    layout_engine.set_spatial_index(Some(config.algorithms.spatial_index_cell_size));
+    layout_engine.set_layer_balance(
+        config.algorithms.balance_layers.then(|| config.algorithms.layer_balance.into()),
+    );
+    layout_engine.set_component_packing(crate::algorithms::component_packing::ComponentPackingOptions {
+        enabled: config.algorithms.pack_components,
+        gap: config.algorithms.component_gap,
+        parallel: config.algorithms.parallel_components,
+        slots: config.algorithms.component_slots(),
+        origins: config
+            .algorithms
+            .component_origins
+            .iter()
+            .map(|(anchor, &[x, y])| (anchor.clone(), (x, y)))
+            .collect(),
+        cell_size: (config.algorithms.component_cell_width, config.algorithms.component_cell_height),
+    });
+    Ok(layout_engine)
+}
+
 /// gRPC сервер для укладки графов
-pub struct GraphLayoutServer {
+///
+/// Хранилище графа — параметр типа: в работе это `Neo4jClient`, в тестах
+/// `InMemoryGraphStore`.
+pub struct GraphLayoutServer<S: GraphStore = Neo4jClient> {
     /// Конфигурация сервиса
     config: Config,
     
     /// Алгоритм укладки
     layout_engine: Arc<RwLock<HighPerformanceLayoutEngine>>,
     
-    /// Клиент Neo4j для загрузки данных
-    pub neo4j_client: Arc<Neo4jClient>,
+    /// Хранилище графа для загрузки связей и сохранения позиций
+    pub store: Arc<S>,
     
     /// Сборщик метрик
     metrics: Arc<MetricsCollector>,
@@ -51,141 +181,477 @@ pub struct GraphLayoutServer {
     
     /// Время запуска
     startup_time: SystemTime,
+    
+    /// Активные тяжёлые прогоны, на время которых сервис не принимает интерактивные запросы
+    heavy_runs: Arc<HeavyRuns>,
+    
+    /// Системная информация для замера CPU/памяти
+    system: Arc<std::sync::Mutex<sysinfo::System>>,
+    
+    /// Артефакты задач (None — не сохраняются)
+    artifacts: Option<Arc<ArtifactStore>>,
+}
+
+/// Реестр тяжёлых прогонов
+///
+/// Каждый прогон получает собственный токен, так что прогоны с одинаковым id
+/// (две автоукладки, повтор task_id) не снимают отметки друг друга. С каталогом
+/// `server.heavy_run_dir` прогон также оставляет файл-отметку `<токен>.run`:
+/// так `GetHealth` сервера видит автоукладку, запущенную отдельным процессом
+/// (или контейнером с общим томом). Отметки завершившихся процессов
+/// пропускаются по PID.
+struct HeavyRuns {
+    dir: Option<std::path::PathBuf>,
+    local: std::sync::Mutex<HashMap<String, String>>,
+}
+
+impl HeavyRuns {
+    fn new(dir: &str) -> Self {
+        let dir = (!dir.is_empty()).then(|| std::path::PathBuf::from(dir));
+        if let Some(ref dir) = dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("{}", Msg::HeavyRunDirUnavailable { dir: &dir.display(), error: &e });
+            }
+        }
+        Self { dir, local: std::sync::Mutex::new(HashMap::new()) }
+    }
+
+    fn marker(&self, token: &str) -> Option<std::path::PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.run", token)))
+    }
+
+    fn insert(&self, token: &str, description: String) {
+        if let Some(path) = self.marker(token) {
+            if let Err(e) = std::fs::write(&path, format!("{}\n{}", std::process::id(), description)) {
+                warn!("{}", Msg::HeavyRunMarkerNotWritten { path: &path.display(), error: &e });
+            }
+        }
+        if let Ok(mut runs) = self.local.lock() {
+            runs.insert(token.to_string(), description);
+        }
+    }
+
+    fn remove(&self, token: &str) {
+        if let Ok(mut runs) = self.local.lock() {
+            runs.remove(token);
+        }
+        if let Some(path) = self.marker(token) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// Описания прогонов этого процесса и живых процессов из каталога отметок
+    fn active(&self) -> Vec<String> {
+        let local = self.local.lock().map(|runs| runs.clone()).unwrap_or_default();
+        let mut active: Vec<String> = local.values().cloned().collect();
+        let entries = self.dir.as_ref().and_then(|dir| std::fs::read_dir(dir).ok());
+        for entry in entries.into_iter().flatten().flatten() {
+            let path = entry.path();
+            let Some(token) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            if path.extension() != Some(std::ffi::OsStr::new("run")) || local.contains_key(token) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else { continue };
+            let (pid, description) = content.split_once('\n').unwrap_or(("", content.as_str()));
+            if pid.parse::<u32>().is_ok_and(|pid| !process_alive(pid)) {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            active.push(description.to_string());
+        }
+        active.sort();
+        active
+    }
+}
+
+/// Жив ли процесс `pid` (вне Linux считается живым)
+fn process_alive(pid: u32) -> bool {
+    !cfg!(target_os = "linux") || std::path::Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+/// Отметка о тяжёлом прогоне; снимается автоматически при Drop
+pub struct HeavyRunGuard {
+    token: String,
+    heavy_runs: Arc<HeavyRuns>,
+}
+
+impl Drop for HeavyRunGuard {
+    fn drop(&mut self) {
+        self.heavy_runs.remove(&self.token);
+    }
+}
+
+/// Причины, по которым сервис не должен принимать интерактивные запросы
+pub fn health_problems(
+    neo4j_healthy: bool,
+    system_metrics: &SystemMetrics,
+    heavy_runs: &[String],
+    cpu_threshold: f32,
+    memory_threshold: f32,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    
+    if !neo4j_healthy {
+        problems.push(Msg::StoreUnavailable.to_string());
+    }
+    
+    if system_metrics.memory_available_bytes > 0 {
+        let memory_ratio = system_metrics.memory_usage_bytes as f32 / system_metrics.memory_available_bytes as f32;
+        if memory_ratio >= memory_threshold {
+            problems.push(Msg::MemoryBusy { percent: memory_ratio * 100.0 }.to_string());
+        }
+    }
+    
+    if system_metrics.cpu_usage >= cpu_threshold {
+        problems.push(Msg::CpuBusy { percent: system_metrics.cpu_usage * 100.0 }.to_string());
+    }
+    
+    for run in heavy_runs {
+        problems.push(Msg::HeavyRunActive { run }.to_string());
+    }
+    
+    problems
 }
 
-impl GraphLayoutServer {
-    /// Создание нового экземпляра сервера
+impl GraphLayoutServer<Neo4jClient> {
+    /// Создание нового экземпляра сервера с подключением к Neo4j
     pub async fn new(config: Config) -> Result<Self> {
-        info!("🔧 Инициализация GraphLayoutServer...");
-        
-        // Создание алгоритма укладки
-        let default_options = crate::generated::LayoutOptions {
-            block_width: config.algorithms.block_width,
-            block_height: config.algorithms.block_height,
-            horizontal_gap: config.algorithms.horizontal_gap,
-            vertical_gap: config.algorithms.vertical_gap,
-            exclude_isolated_vertices: config.algorithms.exclude_isolated_vertices,
-            optimize_layout: true,
-            max_iterations: config.algorithms.max_iterations as i32,
-            convergence_threshold: config.algorithms.convergence_threshold,
-            chunk_size: config.performance.chunk_size as i32,
-            max_workers: config.performance.worker_threads as i32,
-            enable_simd: config.performance.enable_simd,
-            enable_gpu: config.performance.enable_gpu,
-            memory_strategy: crate::generated::MemoryStrategy::MemoryAuto as i32,
-        };
-        
-        let layout_engine = HighPerformanceLayoutEngine::new(&default_options)?;
+        info!("{}", Msg::ServerInitializing);
         
         // Создание клиента Neo4j
         let neo4j_client = Neo4jClient::new(&config).await?;
         
+        Self::with_store(config, Arc::new(neo4j_client))
+    }
+}
+
+impl<S: GraphStore> GraphLayoutServer<S> {
+    /// Создание сервера поверх произвольного хранилища графа
+    pub fn with_store(mut config: Config, store: Arc<S>) -> Result<Self> {
+        crate::messages::set_language(config.server.language);
+        // Токены арендаторов сравниваются и возвращаются в нормализованном виде
+        config.normalize_tenant_tokens()?;
+
+        // Создание алгоритма укладки
+        let layout_engine = configured_engine(&config, &default_layout_options(&config))?;
+        
         // Создание сборщика метрик
         let metrics = MetricsCollector::new(&config.metrics)?;
         
         let server_id = Uuid::new_v4().to_string();
         
-        info!("✅ GraphLayoutServer инициализирован (ID: {})", server_id);
+        let artifacts = ArtifactStore::from_config(&config.artifacts).map(Arc::new);
+        let heavy_runs = Arc::new(HeavyRuns::new(&config.server.heavy_run_dir));
+        if let Some(ref store) = artifacts {
+            info!("{}", Msg::ArtifactsDir { path: &store.root().display() });
+        }
+        
+        info!("{}", Msg::ServerInitialized { id: &server_id });
         
         Ok(Self {
             config,
             layout_engine: Arc::new(RwLock::new(layout_engine)),
-            neo4j_client: Arc::new(neo4j_client),
+            store,
             metrics: Arc::new(metrics),
             server_id,
             startup_time: SystemTime::now(),
+            heavy_runs,
+            system: Arc::new(std::sync::Mutex::new(sysinfo::System::new())),
+            artifacts,
         })
     }
     
-    /// Загрузка связей из Neo4j
-    #[instrument(skip(self))]
-    async fn load_edges_from_neo4j(&self) -> Result<Vec<crate::generated::GraphEdge>> {
-        info!("📥 Загрузка связей из Neo4j...");
+    /// Пометить сервис как занятый тяжёлым прогоном (например, полной автоукладкой)
+    ///
+    /// Пока guard жив, `GetHealth` и grpc.health.v1 этого и других процессов с
+    /// тем же `server.heavy_run_dir` сообщают NOT_SERVING с причиной.
+    pub fn begin_heavy_run(&self, id: &str, reason: &str) -> HeavyRunGuard {
+        let token = Uuid::new_v4().to_string();
+        self.heavy_runs.insert(&token, format!("{} ({})", id, reason));
+        info!("{}", Msg::HeavyRun { id, reason });
+        HeavyRunGuard {
+            token,
+            heavy_runs: Arc::clone(&self.heavy_runs),
+        }
+    }
+    
+    /// Оценка состояния сервиса: статус, причина и системные метрики
+    pub async fn evaluate_health(&self) -> (crate::generated::health_response::ServingStatus, String, SystemMetrics) {
+        let neo4j_healthy = self.store.health_check().await.is_ok();
+        let system_metrics = self.get_system_metrics().await;
+        let heavy_runs = self.heavy_runs.active();
         
-        let start_time = std::time::Instant::now();
+        let problems = health_problems(
+            neo4j_healthy,
+            &system_metrics,
+            &heavy_runs,
+            self.config.server.health_cpu_threshold,
+            self.config.server.health_memory_threshold,
+        );
         
-        // Запрос только связей (без данных о вершинах)
-        let query = r#"
-            MATCH (source:Article)-[r:BIBLIOGRAPHIC_LINK]->(target:Article)
-            RETURN source.uid as source_id, target.uid as target_id, 
-                   coalesce(r.weight, 1.0) as weight,
-                   type(r) as edge_type
-        "#;
-        
-        let records = self.neo4j_client.execute_query(query, None).await?;
-        
-        let edges: Vec<_> = records
-            .into_iter()
-            .map(|record| crate::generated::GraphEdge {
-                source_id: record.get("source_id").map(|v| v.to_string()).unwrap_or_default(),
-                target_id: record.get("target_id").map(|v| v.to_string()).unwrap_or_default(),
-                weight: record.get("weight").and_then(|v| v.to_string().parse::<f64>().ok()).unwrap_or(1.0) as f32,
-                edge_type: record.get("edge_type").map(|v| v.to_string()).unwrap_or_default(),
-            })
-            .collect();
+        if problems.is_empty() {
+            (
+                crate::generated::health_response::ServingStatus::Serving,
+                Msg::ServiceHealthy.to_string(),
+                system_metrics,
+            )
+        } else {
+            (
+                crate::generated::health_response::ServingStatus::NotServing,
+                Msg::HealthProblems { problems: &problems.join("; ") }.to_string(),
+                system_metrics,
+            )
+        }
+    }
+    
+    /// Загрузка связей из хранилища графа
+    #[instrument(skip(self))]
+    async fn load_edges_from_store(&self, tenant: &str) -> Result<Vec<Neo4jGraphEdge>> {
+        info!("{}", Msg::LoadingEdgesFromStore);
         
+        let start_time = std::time::Instant::now();
+        let edges = self.store.load_graph_edges().await?;
         let load_time = start_time.elapsed();
         
-        info!(
-            "✅ Загружено {} связей за {:.2}с", 
-            edges.len(), 
-            load_time.as_secs_f64()
-        );
+        info!("{}", Msg::EdgesLoaded { count: edges.len(), seconds: load_time.as_secs_f64() });
         
         // Записываем метрику
-        self.metrics.record_data_load(edges.len(), load_time).await;
+        self.metrics.record_data_load(tenant, edges.len(), load_time).await;
         
         Ok(edges)
     }
     
-    /// Сохранение результатов в Neo4j
+    /// Сохранение позиций в хранилище графа
+    ///
+    /// Частичное сохранение не считается ошибкой: отчёт возвращается вызывающему.
     #[instrument(skip(self, positions))]
-    async fn save_results_to_neo4j(&self, positions: &[Neo4jVertexPosition]) -> Result<()> {
-        info!("💾 Сохранение {} позиций в Neo4j...", positions.len());
+    pub async fn save_results(&self, positions: &[Neo4jVertexPosition]) -> Result<SaveReport> {
+        info!("{}", Msg::SavingPositions { count: positions.len() });
         
         let start_time = std::time::Instant::now();
+        let report = self.store.save_layout_results(positions).await?;
+        let save_time = start_time.elapsed();
         
-        // Батчевое обновление позиций в Neo4j
-        let batch_size = self.config.neo4j.batch_size;
-        
-        for chunk in positions.chunks(batch_size) {
-            let update_query = r#"
-                UNWIND $positions as pos
-                MATCH (a:Article {uid: pos.article_id})
-                SET a.layer = pos.layer,
-                    a.level = pos.level,
-                    a.x = pos.x,
-                    a.y = pos.y
-            "#;
-            
-            let mut params_map = HashMap::new();
-            params_map.insert("positions".to_string(), BoltType::from(chunk.iter().map(|p| {
-                let mut pos_map = HashMap::new();
-                pos_map.insert("article_id".to_string(), BoltType::from(p.article_id.clone()));
-                pos_map.insert("layer".to_string(), BoltType::from(p.layer));
-                pos_map.insert("level".to_string(), BoltType::from(p.level));
-                pos_map.insert("x".to_string(), BoltType::from(p.x));
-                pos_map.insert("y".to_string(), BoltType::from(p.y));
-                BoltType::from(pos_map)
-            }).collect::<Vec<_>>()));
-            self.neo4j_client.execute_query(update_query, Some(params_map)).await?;
+        if report.is_complete() {
+            info!("{}", Msg::PositionsSaved { seconds: save_time.as_secs_f64() });
+        } else {
+            warn!("{}", Msg::PartialSave { saved: report.saved_rows, total: report.total_rows });
         }
         
-        let save_time = start_time.elapsed();
+        // Записываем метрику
+        self.metrics.record_data_save(crate::tenant::DEFAULT_TENANT, report.saved_rows, save_time).await;
         
-        info!(
-            "✅ Позиции сохранены в Neo4j за {:.2}с", 
-            save_time.as_secs_f64()
-        );
+        Ok(report)
+    }
+    
+    /// Арендатор запроса
+    ///
+    /// Если заданы `[server] tenant_tokens`, арендатор определяется по токену
+    /// из метаданных `authorization: Bearer <токен>`, а `tenant_id` из тела
+    /// запроса может быть только пустым или совпадать с ним. Без токенов
+    /// сервис работает в доверенной сети: `tenant_id` берётся из запроса и
+    /// только проверяется (недопустимый ID — InvalidArgument).
+    fn request_tenant(&self, metadata: &MetadataMap, raw: &str) -> Result<String, Status> {
+        let tokens = &self.config.server.tenant_tokens;
+        if tokens.is_empty() {
+            return crate::tenant::tenant_id(raw).map_err(|e| Status::invalid_argument(e.to_string()));
+        }
         
-        // Записываем метрику
-        self.metrics.record_data_save(positions.len(), save_time).await;
+        // Токены и ID арендаторов нормализованы при создании сервера
+        let tenant = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| tokens.get(token.trim()))
+            .ok_or_else(|| Status::unauthenticated(Msg::TenantTokenMissing.to_string()))?;
+        let raw = raw.trim();
+        if !raw.is_empty() && raw != tenant.as_str() {
+            return Err(Status::permission_denied(Msg::TenantTokenForbidden.to_string()));
+        }
+        Ok(tenant.clone())
+    }
+    
+    /// Сохранение артефактов задачи, если они включены
+    ///
+    /// Ошибка записи не проваливает задачу: укладка уже посчитана.
+    fn save_artifacts(&self, tenant: &str, task_id: &str, layout_result: &crate::algorithms::LayoutResult) {
+        self.write_artifacts(tenant, task_id, |store, job_id| {
+            crate::artifacts::write_layout_artifacts(store, job_id, layout_result)
+        });
+    }
+    
+    /// Сохранение артефактов пакетной укладки (`auto-layout`) арендатора по умолчанию
+    pub fn save_batch_artifacts(
+        &self,
+        positions: &[Neo4jVertexPosition],
+        meta: &crate::neo4j::LayoutMeta,
+    ) {
+        let task_id = format!("auto-layout-{}", meta.run_id);
+        self.write_artifacts(crate::tenant::DEFAULT_TENANT, &task_id, |store, job_id| {
+            crate::artifacts::write_batch_artifacts(store, job_id, positions, meta)
+        });
+    }
+    
+    fn write_artifacts(
+        &self,
+        tenant: &str,
+        task_id: &str,
+        write: impl FnOnce(&ArtifactStore, &str) -> Result<Vec<crate::artifacts::ArtifactInfo>>,
+    ) {
+        let Some(ref store) = self.artifacts else {
+            return;
+        };
+        let job_id = crate::artifacts::job_id_for(task_id);
+        let saved = crate::blocking_io::run_blocking(|| write(&store.for_tenant(tenant)?, &job_id));
+        if let Err(e) = saved {
+            warn!("{}", Msg::ArtifactsNotSaved { job_id: &job_id, error: &e });
+        }
+    }
+    
+    /// Артефакты задачи или всех хранимых задач арендатора
+    fn collect_artifacts(&self, tenant: &str, job_id: &str) -> Result<Vec<crate::artifacts::ArtifactInfo>> {
+        let store = self
+            .artifacts
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("{}", Msg::ArtifactsDisabled))?
+            .for_tenant(tenant)?;
+        if !job_id.is_empty() {
+            return store.list(job_id);
+        }
+        let mut artifacts = Vec::new();
+        for job_id in store.jobs()? {
+            artifacts.extend(store.list(&job_id)?);
+        }
+        Ok(artifacts)
+    }
+    
+    /// Загрузка связей (если не переданы), проверка лимитов и вычисление укладки
+    async fn run_layout(
+        &self,
+        task_id: &str,
+        tenant: &str,
+        edges: Vec<crate::generated::GraphEdge>,
+        options: Option<crate::generated::LayoutOptions>,
+    ) -> Result<crate::algorithms::LayoutResult> {
+        // 1. Загрузка связей из хранилища (если не переданы в запросе)
+        let edges: Vec<Neo4jGraphEdge> = if edges.is_empty() {
+            self.load_edges_from_store(tenant).await?
+        } else {
+            edges.into_iter().map(|e| Neo4jGraphEdge {
+                source_id: e.source_id,
+                target_id: e.target_id,
+                weight: e.weight,
+                edge_type: e.edge_type,
+                source_cluster: Some(e.source_cluster).filter(|c| !c.is_empty()),
+                target_cluster: Some(e.target_cluster).filter(|c| !c.is_empty()),
+                source_year: Some(e.source_year).filter(|&y| y != 0),
+                target_year: Some(e.target_year).filter(|&y| y != 0),
+            }).collect()
+        };
+        
+        // Жёсткие лимиты размера графа
+        self.config.limits.check_edges(edges.len())?;
+        let vertex_count = edges
+            .iter()
+            .flat_map(|e| [e.source_id.as_str(), e.target_id.as_str()])
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        self.config.limits.check_vertices(vertex_count)?;
         
-        Ok(())
+        // 2. Вычисление укладки
+        let _heavy_run = (edges.len() >= self.config.server.heavy_run_edge_threshold).then(|| {
+            self.begin_heavy_run(task_id, &format!("укладка {} связей", edges.len()))
+        });
+        match options {
+            // Стратегия слоёв, ширина слоя, размеры и привязки вершин задаются
+            // при создании движка, поэтому параметры запроса — отдельный движок
+            Some(options) => {
+                let mut engine = configured_engine(&self.config, &options)?;
+                engine.compute_layout(edges, &options).await
+            }
+            None => {
+                let options = default_layout_options(&self.config);
+                let mut layout_engine = self.layout_engine.write().await;
+                layout_engine.compute_layout(edges, &options).await
+            }
+        }
+    }
+    
+    /// Укладка с отправкой позиций чанками по слоям
+    ///
+    /// Канал ограничен, и `send` ждёт, пока клиент заберёт предыдущие чанки:
+    /// медленный клиент замедляет отправку, а не копит ответ в памяти сервера.
+    /// Последний чанк — сводка со статистикой и метаданными, без позиций.
+    async fn stream_layout(
+        &self,
+        req: LayoutRequest,
+        tenant: &str,
+        chunk_size: usize,
+        tx: &mpsc::Sender<Result<LayoutChunk, Status>>,
+    ) -> Result<usize> {
+        let mut layout_result = self.run_layout(&req.task_id, tenant, req.edges, req.options).await?;
+        self.metrics.record_layout_statistics(tenant, &layout_result.statistics).await;
+        self.save_artifacts(tenant, &req.task_id, &layout_result);
+        
+        layout_result.positions.sort_by(|a, b| {
+            a.layer
+                .cmp(&b.layer)
+                .then(a.level.cmp(&b.level))
+                .then_with(|| a.article_id.cmp(&b.article_id))
+        });
+        let ranges = layer_chunk_ranges(&layout_result.positions, chunk_size);
+        let total_chunks = ranges.len() as i32 + 1;
+        let total_positions = layout_result.positions.len().max(1);
+        
+        for (chunk_id, range) in ranges.into_iter().enumerate() {
+            let slice = &layout_result.positions[range.clone()];
+            let chunk = LayoutChunk {
+                chunk_id: chunk_id as i32,
+                total_chunks,
+                positions: slice.iter().map(response_position).collect(),
+                progress: range.end as f32 / total_positions as f32,
+                is_final: false,
+                layer_start: slice.first().map_or(0, |p| p.layer),
+                layer_end: slice.last().map_or(0, |p| p.layer),
+                statistics: None,
+                metadata: None,
+            };
+            if tx.send(Ok(chunk)).await.is_err() {
+                return Err(anyhow::anyhow!(
+                    "{}",
+                    Msg::ClientDisconnectedAtChunk { chunk: chunk_id as i32 + 1, total: total_chunks }
+                ));
+            }
+        }
+        
+        let snapshot = self.metrics.snapshot(tenant, &layout_result.statistics);
+        let summary = LayoutChunk {
+            chunk_id: total_chunks - 1,
+            total_chunks,
+            positions: Vec::new(),
+            progress: 1.0,
+            is_final: true,
+            layer_start: layout_result.positions.first().map_or(0, |p| p.layer),
+            layer_end: layout_result.positions.last().map_or(0, |p| p.layer),
+            statistics: Some(layout_result.statistics),
+            metadata: Some(self.create_response_metadata(
+                &layout_result.metadata.optimizations_used,
+                Some(snapshot),
+            )),
+        };
+        if tx.send(Ok(summary)).await.is_err() {
+            return Err(anyhow::anyhow!("{}", Msg::ClientDisconnectedBeforeSummary));
+        }
+        
+        Ok(total_chunks as usize)
     }
     
     /// Создание метаданных ответа
-    fn create_response_metadata(&self, used_optimizations: &[String]) -> ResponseMetadata {
+    fn create_response_metadata(
+        &self,
+        used_optimizations: &[String],
+        metrics_snapshot: Option<MetricsSnapshot>,
+    ) -> ResponseMetadata {
         ResponseMetadata {
             server_id: self.server_id.clone(),
             algorithm_version: env!("CARGO_PKG_VERSION").to_string(),
@@ -201,6 +667,8 @@ impl GraphLayoutServer {
                 memory_mapping_used: used_optimizations.contains(&"Memory Mapping".to_string()),
                 vectorization_used: used_optimizations.contains(&"Vectorization".to_string()),
             }),
+            metrics_snapshot,
+            scale_profile: self.config.algorithms.scale_profile.name().to_string(),
         }
     }
     
@@ -211,10 +679,29 @@ impl GraphLayoutServer {
             .unwrap_or_default()
             .as_secs() as i64;
         
+        // Загрузка CPU считается между двумя обновлениями, поэтому System переиспользуется
+        let (cpu_usage, memory_used, memory_total) = match self.system.lock() {
+            Ok(mut system) => {
+                system.refresh_cpu();
+                system.refresh_memory();
+                (
+                    system.global_cpu_info().cpu_usage() / 100.0,
+                    system.used_memory(),
+                    system.total_memory(),
+                )
+            }
+            Err(_) => (
+                self.metrics.get_cpu_usage().await,
+                self.metrics.get_memory_usage().await as u64,
+                self.metrics.get_available_memory().await as u64,
+            ),
+        };
+        self.metrics.update_cpu_usage(cpu_usage as f64).await;
+        
         SystemMetrics {
-            cpu_usage: self.metrics.get_cpu_usage().await,
-            memory_usage_bytes: self.metrics.get_memory_usage().await as i64,
-            memory_available_bytes: self.metrics.get_available_memory().await as i64,
+            cpu_usage,
+            memory_usage_bytes: memory_used as i64,
+            memory_available_bytes: memory_total as i64,
             active_tasks: self.metrics.get_active_tasks().await as i32,
             uptime_seconds: uptime,
         }
@@ -222,15 +709,16 @@ impl GraphLayoutServer {
 }
 
 #[tonic::async_trait]
-impl GraphLayoutService for GraphLayoutServer {
+impl<S: GraphStore> GraphLayoutService for GraphLayoutServer<S> {
     /// Основной метод укладки графа
     #[instrument(skip(self, request))]
     async fn compute_layout(
         &self,
         request: Request<LayoutRequest>,
     ) -> Result<Response<LayoutResponse>, Status> {
-        let req = request.into_inner();
-        info!("🎯 Обработка запроса укладки (ID: {})", req.task_id);
+        let (metadata, _, req) = request.into_parts();
+        let tenant = self.request_tenant(&metadata, layout_request_tenant_id(&req))?;
+        info!("{}", Msg::LayoutRequestReceived { task_id: &req.task_id, tenant: &tenant });
         
         let start_time = std::time::Instant::now();
         
@@ -238,45 +726,20 @@ impl GraphLayoutService for GraphLayoutServer {
         self.metrics.increment_active_tasks().await;
         
         let result = async {
-            // 1. Загрузка связей из Neo4j (если не переданы в запросе)
-            let edges = if req.edges.is_empty() {
-                self.load_edges_from_neo4j().await?
-            } else {
-                req.edges
-            };
-            
-            // 2. Валидация опций
-            let options = req.options.unwrap_or_else(|| crate::generated::LayoutOptions {
-                block_width: self.config.algorithms.block_width,
-                block_height: self.config.algorithms.block_height,
-                horizontal_gap: self.config.algorithms.horizontal_gap,
-                vertical_gap: self.config.algorithms.vertical_gap,
-                exclude_isolated_vertices: self.config.algorithms.exclude_isolated_vertices,
-                optimize_layout: true,
-                max_iterations: self.config.algorithms.max_iterations as i32,
-                convergence_threshold: self.config.algorithms.convergence_threshold,
-                chunk_size: self.config.performance.chunk_size as i32,
-                max_workers: self.config.performance.worker_threads as i32,
-                enable_simd: self.config.performance.enable_simd,
-                enable_gpu: self.config.performance.enable_gpu,
-                memory_strategy: crate::generated::MemoryStrategy::MemoryAuto as i32,
-            });
-            
-            // 3. Вычисление укладки
-            let mut layout_engine = self.layout_engine.write().await;
-            // Конвертация типов
-            let neo4j_edges: Vec<Neo4jGraphEdge> = edges.into_iter().map(|e| Neo4jGraphEdge {
-                source_id: e.source_id,
-                target_id: e.target_id,
-                weight: e.weight,
-                edge_type: e.edge_type,
-            }).collect();
-            
-            let layout_result = layout_engine.compute_layout(neo4j_edges, &options).await?;
+            let layout_result = self.run_layout(&req.task_id, &tenant, req.edges, req.options).await?;
+            self.save_artifacts(&tenant, &req.task_id, &layout_result);
             
             // 4. Сохранение результатов в Neo4j
             // 5. Создание ответа
-            let metadata = self.create_response_metadata(&layout_result.metadata.optimizations_used);
+            self.metrics.record_layout_statistics(&tenant, &layout_result.statistics).await;
+            let snapshot = self.metrics.snapshot(&tenant, &layout_result.statistics);
+            let metadata = self.create_response_metadata(
+                &layout_result.metadata.optimizations_used,
+                Some(snapshot),
+            );
+            
+            let edge_paths = layout_result.edge_paths.iter().map(response_edge_path).collect();
+            let occupancy = response_occupancy(&layout_result.occupancy);
             
             // Конвертация позиций для ответа
             let response_positions: Vec<crate::generated::VertexPosition> = layout_result.positions.into_iter().map(|p| {
@@ -301,7 +764,7 @@ impl GraphLayoutService for GraphLayoutServer {
             }).collect();
             
             // Сохранение в Neo4j (упрощенная версия)
-            info!("💾 Сохранение {} позиций в Neo4j", response_positions.len());
+            info!("{}", Msg::SavingPositionsToNeo4j { count: response_positions.len() });
             
             Ok::<_, anyhow::Error>(LayoutResponse {
                 success: true,
@@ -309,6 +772,8 @@ impl GraphLayoutService for GraphLayoutServer {
                 positions: response_positions,
                 statistics: Some(layout_result.statistics),
                 metadata: Some(metadata),
+                edge_paths,
+                occupancy,
             })
         }.await;
         
@@ -319,32 +784,31 @@ impl GraphLayoutService for GraphLayoutServer {
         
         match result {
             Ok(response) => {
-                info!(
-                    "✅ Укладка завершена за {:.2}с (ID: {})", 
-                    total_time.as_secs_f64(),
-                    req.task_id
-                );
+                info!("{}", Msg::LayoutDone { seconds: total_time.as_secs_f64(), task_id: &req.task_id });
                 
                 // Записываем метрику успешного выполнения
-                self.metrics.record_successful_layout(total_time).await;
+                self.metrics.record_successful_layout(&tenant, total_time).await;
                 
                 Ok(Response::new(response))
             }
             Err(e) => {
-                error!(
-                    "❌ Ошибка укладки: {} (ID: {}, время: {:.2}с)", 
-                    e, req.task_id, total_time.as_secs_f64()
-                );
+                error!("{}", Msg::LayoutFailed {
+                    error: &e,
+                    task_id: &req.task_id,
+                    seconds: total_time.as_secs_f64(),
+                });
                 
                 // Записываем метрику ошибки
-                self.metrics.record_failed_layout(total_time).await;
+                self.metrics.record_failed_layout(&tenant, total_time).await;
                 
                 let error_response = LayoutResponse {
                     success: false,
                     error_message: e.to_string(),
                     positions: vec![],
                     statistics: None,
-                    metadata: Some(self.create_response_metadata(&[])),
+                    metadata: Some(self.create_response_metadata(&[], None)),
+                    edge_paths: vec![],
+                    occupancy: vec![],
                 };
                 
                 Ok(Response::new(error_response))
@@ -360,62 +824,185 @@ impl GraphLayoutService for GraphLayoutServer {
         &self,
         request: Request<LayoutRequest>,
     ) -> Result<Response<Self::ComputeLayoutStreamingStream>, Status> {
-        let req = request.into_inner();
-        info!("🌊 Обработка потокового запроса укладки (ID: {})", req.task_id);
-        
-        let (tx, rx) = tokio::sync::mpsc::channel(32);
+        let (metadata, _, req) = request.into_parts();
+        let tenant = self.request_tenant(&metadata, layout_request_tenant_id(&req))?;
+        info!("{}", Msg::StreamingRequestReceived { task_id: &req.task_id, tenant: &tenant });
         
-        // Клонируем нужные данные для фонового выполнения
-        let _server = self.clone();
-        let _task_id = req.task_id.clone();
+        let chunk_size = match req.stream_chunk_size {
+            n if n > 0 => n as usize,
+            _ => self.config.server.stream_chunk_size,
+        }.max(1);
+        let (tx, rx) = mpsc::channel(self.config.server.stream_channel_capacity.max(1));
         
-        // Запускаем обработку в фоновом режиме
+        let server = self.clone();
         tokio::spawn(async move {
-            // TODO: Реализация потоковой обработки
-            // 1. Разбиение на чанки
-            // 2. Обработка каждого чанка
-            // 3. Отправка промежуточных результатов
+            let task_id = req.task_id.clone();
+            let start_time = std::time::Instant::now();
+            server.metrics.increment_active_tasks().await;
             
-            if let Err(e) = tx.send(Err(Status::unimplemented(
-                "Потоковая обработка будет реализована в следующей версии"
-            ))).await {
-                error!("Ошибка отправки потокового ответа: {}", e);
+            let result = server.stream_layout(req, &tenant, chunk_size, &tx).await;
+            
+            server.metrics.decrement_active_tasks().await;
+            let total_time = start_time.elapsed();
+            match result {
+                Ok(chunks) => {
+                    info!("{}", Msg::StreamingDone { seconds: total_time.as_secs_f64(), chunks, task_id: &task_id });
+                    server.metrics.record_successful_layout(&tenant, total_time).await;
+                }
+                Err(e) => {
+                    error!("{}", Msg::StreamingFailed {
+                        error: &e,
+                        task_id: &task_id,
+                        seconds: total_time.as_secs_f64(),
+                    });
+                    server.metrics.record_failed_layout(&tenant, total_time).await;
+                    // Клиент мог уже отключиться — тогда ошибку некому доставить
+                    let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                }
             }
         });
         
         Ok(Response::new(ReceiverStream::new(rx)))
     }
     
-    /// Проверка состояния сервиса
-    #[instrument(skip(self, _request))]
-    async fn get_health(
+    /// Укладка k-окрестности вершины
+    ///
+    /// Быстрый путь: окрестность загружается одним запросом и укладывается
+    /// отдельным экземпляром алгоритма, не дожидаясь блокировки общего движка,
+    /// занятого полными укладками.
+    #[instrument(skip(self, request))]
+    async fn compute_neighborhood_layout(
         &self,
-        _request: Request<HealthRequest>,
-    ) -> Result<Response<HealthResponse>, Status> {
-        // Проверка подключения к Neo4j
-        let neo4j_healthy = self.neo4j_client.health_check().await.is_ok();
-        
-        // Проверка системных ресурсов
-        let system_metrics = self.get_system_metrics().await;
-        let memory_ok = system_metrics.memory_usage_bytes < (system_metrics.memory_available_bytes * 9 / 10);
+        request: Request<NeighborhoodRequest>,
+    ) -> Result<Response<LayoutResponse>, Status> {
+        let (metadata, _, req) = request.into_parts();
+        let tenant = self.request_tenant(&metadata, &req.tenant_id)?;
+        info!("{}", Msg::NeighborhoodRequestReceived {
+                center: &req.center_id,
+                radius: req.radius,
+                task_id: &req.task_id,
+                tenant: &tenant,
+            });
         
-        let status = if neo4j_healthy && memory_ok {
-            crate::generated::health_response::ServingStatus::Serving
-        } else {
-            crate::generated::health_response::ServingStatus::NotServing
+        if req.center_id.trim().is_empty() {
+            return Err(Status::invalid_argument(Msg::CenterIdEmpty.to_string()));
+        }
+        let max_radius = self.config.server.neighborhood_max_radius;
+        if req.radius < 1 || req.radius as u32 > max_radius {
+            return Err(Status::invalid_argument(Msg::RadiusOutOfRange { max: max_radius }.to_string()));
+        }
+        let max_vertices = match req.max_vertices {
+            n if n > 0 => (n as usize).min(self.config.server.neighborhood_max_vertices),
+            _ => self.config.server.neighborhood_max_vertices,
         };
         
-        let message = match status {
-            crate::generated::health_response::ServingStatus::Serving => {
-                "Сервис работает нормально".to_string()
+        let start_time = std::time::Instant::now();
+        self.metrics.increment_active_tasks().await;
+        
+        let result = async {
+            let Some(edges) = self
+                .store
+                .load_neighborhood_edges(&req.center_id, req.radius as u32, max_vertices)
+                .await?
+            else {
+                return Ok(None);
+            };
+            
+            let options = req.options.unwrap_or_else(|| default_layout_options(&self.config));
+            
+            // Изолированная вершина: укладывать нечего
+            if edges.is_empty() {
+                return Ok::<_, anyhow::Error>(Some(LayoutResponse {
+                    success: true,
+                    error_message: String::new(),
+                    positions: vec![crate::generated::VertexPosition {
+                        article_id: req.center_id.clone(),
+                        layer: 0,
+                        level: 0,
+                        x: 0.0,
+                        y: 0.0,
+                        status: crate::generated::VertexStatus::StatusPlaced as i32,
+                    }],
+                    statistics: None,
+                    metadata: Some(self.create_response_metadata(&[], None)),
+                    edge_paths: vec![],
+                    occupancy: vec![crate::generated::LayerOccupancy { layer: 0, level_bitmap: vec![1] }],
+                }));
             }
-            _ => {
-                format!(
-                    "Проблемы: Neo4j={}, Memory={}",
-                    neo4j_healthy, memory_ok
-                )
+            
+            let mut engine = configured_engine(&self.config, &options)?;
+            let layout_result = engine.compute_layout(edges, &options).await?;
+            
+            self.metrics.record_layout_statistics(&tenant, &layout_result.statistics).await;
+            let snapshot = self.metrics.snapshot(&tenant, &layout_result.statistics);
+            let metadata = self.create_response_metadata(
+                &layout_result.metadata.optimizations_used,
+                Some(snapshot),
+            );
+            
+            let edge_paths = layout_result.edge_paths.iter().map(response_edge_path).collect();
+            let occupancy = response_occupancy(&layout_result.occupancy);
+            let positions = layout_result.positions.into_iter().map(|p| crate::generated::VertexPosition {
+                article_id: p.article_id,
+                layer: p.layer,
+                level: p.level,
+                x: p.x,
+                y: p.y,
+                status: crate::generated::VertexStatus::StatusPlaced as i32,
+            }).collect();
+            
+            Ok(Some(LayoutResponse {
+                success: true,
+                error_message: String::new(),
+                positions,
+                statistics: Some(layout_result.statistics),
+                metadata: Some(metadata),
+                edge_paths,
+                occupancy,
+            }))
+        }.await;
+        
+        self.metrics.decrement_active_tasks().await;
+        let total_time = start_time.elapsed();
+        
+        match result {
+            Ok(None) => Err(Status::not_found(Msg::ArticleNotFound { id: &req.center_id }.to_string())),
+            Ok(Some(response)) => {
+                info!("{}", Msg::NeighborhoodDone {
+                        seconds: total_time.as_secs_f64(),
+                        vertices: response.positions.len(),
+                        task_id: &req.task_id,
+                    });
+                self.metrics.record_successful_layout(&tenant, total_time).await;
+                Ok(Response::new(response))
             }
-        };
+            Err(e) => {
+                error!("{}", Msg::NeighborhoodFailed {
+                    error: &e,
+                    task_id: &req.task_id,
+                    seconds: total_time.as_secs_f64(),
+                });
+                self.metrics.record_failed_layout(&tenant, total_time).await;
+                Ok(Response::new(LayoutResponse {
+                    success: false,
+                    error_message: e.to_string(),
+                    positions: vec![],
+                    statistics: None,
+                    metadata: Some(self.create_response_metadata(&[], None)),
+                    edge_paths: vec![],
+                    occupancy: vec![],
+                }))
+            }
+        }
+    }
+    
+    /// Проверка состояния сервиса
+    #[instrument(skip(self, _request))]
+    async fn get_health(
+        &self,
+        _request: Request<HealthRequest>,
+    ) -> Result<Response<HealthResponse>, Status> {
+        let (status, message, system_metrics) = self.evaluate_health().await;
         
         Ok(Response::new(HealthResponse {
             status: status as i32,
@@ -425,12 +1012,19 @@ impl GraphLayoutService for GraphLayoutServer {
     }
     
     /// Получение метрик производительности
-    #[instrument(skip(self, _request))]
+    #[instrument(skip(self, request))]
     async fn get_metrics(
         &self,
-        _request: Request<MetricsRequest>,
+        request: Request<MetricsRequest>,
     ) -> Result<Response<MetricsResponse>, Status> {
-        let metrics = self.metrics.get_prometheus_metrics().await;
+        let (metadata, _, req) = request.into_parts();
+        // Без арендатора — метрики всех арендаторов (только без токенов арендаторов)
+        let tenant = if req.tenant_id.trim().is_empty() && self.config.server.tenant_tokens.is_empty() {
+            None
+        } else {
+            Some(self.request_tenant(&metadata, &req.tenant_id)?)
+        };
+        let metrics = self.metrics.get_prometheus_metrics(tenant.as_deref()).await;
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
@@ -441,18 +1035,838 @@ impl GraphLayoutService for GraphLayoutServer {
             collection_timestamp: timestamp,
         }))
     }
+    
+    /// Список артефактов задач
+    #[instrument(skip(self, request))]
+    async fn list_artifacts(
+        &self,
+        request: Request<ListArtifactsRequest>,
+    ) -> Result<Response<ListArtifactsResponse>, Status> {
+        let (metadata, _, req) = request.into_parts();
+        let tenant = self.request_tenant(&metadata, &req.tenant_id)?;
+        if self.artifacts.is_none() {
+            return Err(Status::failed_precondition(Msg::ArtifactsDisabled.to_string()));
+        }
+        if !req.job_id.is_empty() {
+            crate::artifacts::check_name("задачи", &req.job_id)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        }
+        
+        let artifacts = crate::blocking_io::run_blocking(|| self.collect_artifacts(&tenant, &req.job_id))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        
+        Ok(Response::new(ListArtifactsResponse {
+            artifacts: artifacts
+                .into_iter()
+                .map(|a| crate::generated::Artifact {
+                    job_id: a.job_id,
+                    name: a.name,
+                    size_bytes: a.size_bytes as i64,
+                    modified_timestamp: a.modified_timestamp,
+                })
+                .collect(),
+        }))
+    }
+    
+    /// Скачивание артефакта задачи
+    type DownloadArtifactStream = ReceiverStream<Result<ArtifactChunk, Status>>;
+    
+    #[instrument(skip(self, request))]
+    async fn download_artifact(
+        &self,
+        request: Request<DownloadArtifactRequest>,
+    ) -> Result<Response<Self::DownloadArtifactStream>, Status> {
+        use tokio::io::AsyncReadExt;
+        
+        let (metadata, _, req) = request.into_parts();
+        let tenant = self.request_tenant(&metadata, &req.tenant_id)?;
+        let store = self
+            .artifacts
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition(Msg::ArtifactsDisabled.to_string()))?;
+        let path = store
+            .for_tenant(&tenant)
+            .and_then(|store| store.artifact_path(&req.job_id, &req.name))
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        
+        let mut file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Status::not_found(
+                    Msg::ArtifactNotFound { job_id: &req.job_id, name: &req.name }.to_string(),
+                ));
+            }
+            Err(e) => return Err(Status::internal(e.to_string())),
+        };
+        let total_size = file
+            .metadata()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .len() as i64;
+        info!("{}", Msg::ArtifactDownload { job_id: &req.job_id, name: &req.name, bytes: total_size });
+        
+        let (tx, rx) = mpsc::channel(self.config.server.stream_channel_capacity.max(1));
+        tokio::spawn(async move {
+            let mut offset = 0i64;
+            loop {
+                let mut data = vec![0u8; ARTIFACT_CHUNK_SIZE];
+                let read = match file.read(&mut data).await {
+                    Ok(read) => read,
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+                data.truncate(read);
+                let is_final = read == 0 || offset + read as i64 >= total_size;
+                let chunk = ArtifactChunk { data, offset, total_size, is_final };
+                offset += read as i64;
+                // Клиент отключился — дальше читать незачем
+                if tx.send(Ok(chunk)).await.is_err() || is_final {
+                    return;
+                }
+            }
+        });
+        
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// Маршрут связи для ответа gRPC
+fn response_edge_path(path: &crate::neo4j::EdgePath) -> crate::generated::EdgePath {
+    crate::generated::EdgePath {
+        source_id: path.source_id.clone(),
+        target_id: path.target_id.clone(),
+        points: path
+            .points
+            .iter()
+            .map(|&(x, y)| crate::generated::Waypoint { x, y })
+            .collect(),
+        bundle_id: path.bundle_id.clone().unwrap_or_default(),
+        control_points: path
+            .control_points
+            .iter()
+            .map(|&(x, y)| crate::generated::Waypoint { x, y })
+            .collect(),
+        source_port: path.source_port,
+        target_port: path.target_port,
+        edge_index: path.edge_index as u32,
+        length: path.length,
+        bends: path.bends as u32,
+        layer_span: path.layer_span,
+        crossings: path.crossings as u32,
+    }
+}
+
+/// Сетка занятости для ответа gRPC
+fn response_occupancy(
+    occupancy: &crate::algorithms::vertex_placement::OccupiedPositions,
+) -> Vec<crate::generated::LayerOccupancy> {
+    occupancy
+        .layers()
+        .map(|layer| crate::generated::LayerOccupancy {
+            layer,
+            level_bitmap: occupancy.layer_bitmap(layer).to_vec(),
+        })
+        .collect()
+}
+
+/// Позиция для ответа gRPC
+fn response_position(p: &Neo4jVertexPosition) -> crate::generated::VertexPosition {
+    crate::generated::VertexPosition {
+        article_id: p.article_id.clone(),
+        layer: p.layer,
+        level: p.level,
+        x: p.x,
+        y: p.y,
+        status: crate::generated::VertexStatus::StatusPlaced as i32,
+    }
+}
+
+/// Разбиение отсортированных по слою позиций на чанки
+///
+/// Слои не разрываются, пока помещаются в `max_chunk`; слой больше
+/// `max_chunk` делится на несколько чанков.
+pub fn layer_chunk_ranges(positions: &[Neo4jVertexPosition], max_chunk: usize) -> Vec<std::ops::Range<usize>> {
+    let max_chunk = max_chunk.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    
+    while i < positions.len() {
+        let layer = positions[i].layer;
+        let mut j = i;
+        while j < positions.len() && positions[j].layer == layer {
+            j += 1;
+        }
+        
+        // Слой не помещается в текущий чанк — закрываем его
+        if i > start && j - start > max_chunk {
+            ranges.push(start..i);
+            start = i;
+        }
+        while j - start > max_chunk {
+            ranges.push(start..start + max_chunk);
+            start += max_chunk;
+        }
+        i = j;
+    }
+    if start < positions.len() {
+        ranges.push(start..positions.len());
+    }
+    
+    ranges
 }
 
 // Реализация Clone для GraphLayoutServer (для потоковой обработки)
-impl Clone for GraphLayoutServer {
+impl<S: GraphStore> Clone for GraphLayoutServer<S> {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
             layout_engine: Arc::clone(&self.layout_engine),
-            neo4j_client: Arc::clone(&self.neo4j_client),
+            store: Arc::clone(&self.store),
             metrics: Arc::clone(&self.metrics),
             server_id: self.server_id.clone(),
             startup_time: self.startup_time,
+            heavy_runs: Arc::clone(&self.heavy_runs),
+            system: Arc::clone(&self.system),
+            artifacts: self.artifacts.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_store::InMemoryGraphStore;
+    use crate::tenant::DEFAULT_TENANT;
+    
+    fn edge(source: &str, target: &str) -> Neo4jGraphEdge {
+        Neo4jGraphEdge::new(source, target, 1.0)
+    }
+    
+    fn test_server(config: Config) -> (GraphLayoutServer<InMemoryGraphStore>, Arc<InMemoryGraphStore>) {
+        let store = Arc::new(InMemoryGraphStore::new(vec![edge("A", "B"), edge("B", "C"), edge("A", "D")]));
+        let server = GraphLayoutServer::with_store(config, Arc::clone(&store)).unwrap();
+        (server, store)
+    }
+    
+    fn layout_request(task_id: &str) -> LayoutRequest {
+        LayoutRequest {
+            task_id: task_id.to_string(),
+            ..LayoutRequest::default()
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_run_layout_loads_edges_with_default_options() {
+        let mut config = Config::default();
+        config.algorithms.deterministic = true;
+        let (server, _store) = test_server(config);
+        
+        let result = server.run_layout("t1", DEFAULT_TENANT, vec![], None).await.unwrap();
+        
+        assert_eq!(result.positions.len(), 4);
+        let params = &result.metadata.parameters;
+        assert_eq!(params.get("deterministic").map(String::as_str), Some("true"));
+        assert_eq!(params.get("max_layer_width").map(String::as_str), Some("0"));
+        assert!(!params.contains_key("edge_paths"));
+    }
+    
+    #[tokio::test]
+    async fn test_run_layout_applies_request_layering_options() {
+        let config = Config::default();
+        let (server, _store) = test_server(config.clone());
+        let options = crate::generated::LayoutOptions {
+            layering_strategy: crate::generated::LayeringStrategy::LayeringCoffmanGraham as i32,
+            max_layer_width: 1,
+            ..default_layout_options(&config)
+        };
+        
+        let result = server.run_layout("t-width", DEFAULT_TENANT, vec![], Some(options)).await.unwrap();
+        
+        let params = &result.metadata.parameters;
+        assert_eq!(params.get("layering").map(String::as_str), Some("CoffmanGraham"));
+        assert_eq!(params.get("max_layer_width").map(String::as_str), Some("1"));
+        let mut layers: Vec<i32> = result.positions.iter().map(|p| p.layer).collect();
+        layers.sort_unstable();
+        layers.dedup();
+        assert_eq!(layers.len(), result.positions.len());
+        
+        // Общий движок параметрами запроса не меняется
+        let default = server.run_layout("t-default", DEFAULT_TENANT, vec![], None).await.unwrap();
+        assert_eq!(default.metadata.parameters.get("layering").map(String::as_str), Some("Bfs"));
+    }
+    
+    #[tokio::test]
+    async fn test_run_layout_applies_level_packing() {
+        let mut config = Config::default();
+        config.algorithms.level_packing = crate::config::LevelPackingMode::Interval;
+        let (server, _store) = test_server(config.clone());
+        
+        // Общий движок и запрос без упаковки берут её из конфигурации
+        let default = server.run_layout("t-packing", DEFAULT_TENANT, vec![], None).await.unwrap();
+        assert_eq!(default.metadata.parameters.get("level_packing").map(String::as_str), Some("Interval"));
+        let unset = crate::generated::LayoutOptions {
+            level_packing: crate::generated::LevelPacking::Configured as i32,
+            ..default_layout_options(&config)
+        };
+        let result = server.run_layout("t-unset", DEFAULT_TENANT, vec![], Some(unset)).await.unwrap();
+        assert_eq!(result.metadata.parameters.get("level_packing").map(String::as_str), Some("Interval"));
+        
+        // Явное значение запроса важнее конфигурации
+        let column = crate::generated::LayoutOptions {
+            level_packing: crate::generated::LevelPacking::OnePerLevel as i32,
+            ..default_layout_options(&config)
+        };
+        let result = server.run_layout("t-column", DEFAULT_TENANT, vec![], Some(column)).await.unwrap();
+        assert_eq!(result.metadata.parameters.get("level_packing").map(String::as_str), Some("OnePerLevel"));
+    }
+    
+    #[tokio::test]
+    async fn test_run_layout_applies_layer_constraints_from_config() {
+        let mut config = Config::default();
+        config.algorithms.layer_constraints.insert("D".to_string(), 3);
+        config.algorithms.layer_lower_bounds.insert("B".to_string(), 2);
+        let (server, _store) = test_server(config);
+        
+        let result = server.run_layout("t-pins", DEFAULT_TENANT, vec![], None).await.unwrap();
+        
+        let layer = |id: &str| result.positions.iter().find(|p| p.article_id == id).unwrap().layer;
+        assert_eq!(layer("A"), 0);
+        assert_eq!(layer("B"), 2);
+        assert_eq!(layer("C"), 3);
+        assert_eq!(layer("D"), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_compute_layout_applies_request_sizes_and_anchors() {
+        let config = Config::default();
+        let (server, _store) = test_server(config.clone());
+        let mut options = default_layout_options(&config);
+        options.vertex_sizes.insert("A".to_string(), crate::generated::VertexSize { width: 400.0, height: 80.0 });
+        options.vertex_anchors.insert("C".to_string(), crate::generated::VertexAnchor { x: 1000.0, y: 0.0 });
+        let request = LayoutRequest {
+            options: Some(options),
+            ..layout_request("t-sizes")
+        };
+        
+        let response = server.compute_layout(Request::new(request)).await.unwrap().into_inner();
+        
+        let position = |id: &str| response.positions.iter().find(|p| p.article_id == id).unwrap();
+        assert!(position("B").x >= 400.0 + config.algorithms.horizontal_gap);
+        assert_eq!((position("C").x, position("C").y), (1000.0, 0.0));
+    }
+    
+    #[tokio::test]
+    async fn test_compute_layout_returns_edge_paths() {
+        let (server, _store) = test_server(Config::default());
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("t-paths")))
+            .await
+            .unwrap()
+            .into_inner();
+        
+        let edges: Vec<(&str, &str)> = response
+            .edge_paths
+            .iter()
+            .map(|p| (p.source_id.as_str(), p.target_id.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("A", "B"), ("A", "D"), ("B", "C")]);
+        for path in &response.edge_paths {
+            assert!(path.points.len() >= 2);
+            assert!(path.bundle_id.is_empty());
+            assert!(path.control_points.is_empty());
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_compute_layout_returns_occupancy() {
+        let (server, _store) = test_server(Config::default());
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("t-occupancy")))
+            .await
+            .unwrap()
+            .into_inner();
+        
+        let occupied: usize = response
+            .occupancy
+            .iter()
+            .flat_map(|layer| &layer.level_bitmap)
+            .map(|bits| bits.count_ones() as usize)
+            .sum();
+        assert_eq!(occupied, response.positions.len());
+        for position in &response.positions {
+            let layer = response.occupancy.iter().find(|l| l.layer == position.layer).unwrap();
+            let word = layer.level_bitmap[position.level as usize / 64];
+            assert_ne!(word & (1 << (position.level % 64)), 0);
         }
     }
+    
+    #[tokio::test]
+    async fn test_edge_smoothing_returns_bezier_control_points() {
+        let mut config = Config::default();
+        config.algorithms.edge_smoothing = 0.5;
+        let (server, _store) = test_server(config);
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("t-splines")))
+            .await
+            .unwrap()
+            .into_inner();
+        
+        assert!(!response.edge_paths.is_empty());
+        for path in &response.edge_paths {
+            assert_eq!(path.control_points.len(), 2 * (path.points.len() - 1));
+        }
+        
+        let result = server.run_layout("t-splines", DEFAULT_TENANT, vec![], None).await.unwrap();
+        assert!(result.metadata.optimizations_used.contains(&"Edge Smoothing".to_string()));
+        assert_eq!(result.metadata.parameters["edge_smoothing"], "0.5");
+    }
+    
+    #[tokio::test]
+    async fn test_edge_ports_spread_endpoints_of_shared_vertex() {
+        let mut config = Config::default();
+        config.algorithms.edge_ports = true;
+        let (server, _store) = test_server(config);
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("t-ports")))
+            .await
+            .unwrap()
+            .into_inner();
+        
+        // A -> B и A -> D выходят из A через разные порты
+        let ports: Vec<f32> = response
+            .edge_paths
+            .iter()
+            .filter(|p| p.source_id == "A")
+            .map(|p| p.source_port)
+            .collect();
+        assert_eq!(ports.len(), 2);
+        assert_ne!(ports[0], ports[1]);
+        assert!((ports[0] + ports[1]).abs() < 1e-3);
+        // Единственная входящая связь C приходит в центр
+        let into_c = response.edge_paths.iter().find(|p| p.target_id == "C").unwrap();
+        assert!(into_c.target_port.abs() < 1e-3);
+    }
+    
+    #[tokio::test]
+    async fn test_multigraph_routes_self_loops_and_parallel_edges() {
+        let mut config = Config::default();
+        config.algorithms.allow_self_loops = true;
+        config.algorithms.allow_parallel_edges = true;
+        let (server, _store) = test_server(config);
+        let request_edges = [("A", "B"), ("A", "B"), ("B", "B"), ("B", "C")]
+            .iter()
+            .map(|&(source, target)| crate::generated::GraphEdge {
+                source_id: source.to_string(),
+                target_id: target.to_string(),
+                weight: 1.0,
+                ..Default::default()
+            })
+            .collect();
+        
+        let result = server.run_layout("t-multigraph", DEFAULT_TENANT, request_edges, None).await.unwrap();
+        assert_eq!(result.phases.ingest.parallel_edges, 1);
+        assert_eq!(result.phases.ingest.self_loops_kept, 1);
+        
+        let parallel: Vec<_> = result
+            .edge_paths
+            .iter()
+            .filter(|p| p.source_id == "A" && p.target_id == "B")
+            .collect();
+        assert_eq!(parallel.len(), 2);
+        assert_eq!((parallel[0].edge_index, parallel[1].edge_index), (0, 1));
+        assert_ne!(parallel[0].points, parallel[1].points);
+        assert!(result.edge_paths.iter().any(|p| p.source_id == "B" && p.target_id == "B"));
+    }
+    
+    #[tokio::test]
+    async fn test_transitive_reduction_is_reported_in_validation() {
+        let mut config = Config::default();
+        config.algorithms.transitive_reduction = true;
+        let (server, _store) = test_server(config);
+        let request_edges = [("A", "B"), ("B", "C"), ("A", "C")]
+            .iter()
+            .map(|&(source, target)| crate::generated::GraphEdge {
+                source_id: source.to_string(),
+                target_id: target.to_string(),
+                weight: 1.0,
+                ..Default::default()
+            })
+            .collect();
+        
+        let result = server.run_layout("t-reduction", DEFAULT_TENANT, request_edges, None).await.unwrap();
+        let stats = result.validation.transitive_reduction.unwrap();
+        assert_eq!(stats.removed_edges, 1);
+        assert!(stats.exact);
+        assert_eq!(result.edge_paths.len(), 2);
+        assert!(result.metadata.optimizations_used.contains(&"Transitive Reduction".to_string()));
+        assert_eq!(result.metadata.parameters["transitive_edges_removed"], "1");
+    }
+    
+    #[tokio::test]
+    async fn test_run_layout_builds_spatial_index() {
+        let mut config = Config::default();
+        config.algorithms.spatial_index_cell_size = 500.0;
+        let (server, _store) = test_server(config);
+        
+        let result = server.run_layout("t1", DEFAULT_TENANT, vec![], None).await.unwrap();
+        
+        let index = result.metadata.spatial_index.as_ref().expect("индекс включён");
+        assert!(result.metadata.parameters.contains_key("spatial_index"));
+        for pos in &result.positions {
+            assert!(index.query_point(pos.x, pos.y).contains(&pos.article_id));
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_artifacts_are_listed_and_downloaded() {
+        use tokio_stream::StreamExt;
+        
+        let mut config = Config::default();
+        config.artifacts.enabled = true;
+        config.artifacts.dir = std::env::temp_dir()
+            .join(format!("server-artifacts-{}", Uuid::new_v4()))
+            .display()
+            .to_string();
+        let (server, _store) = test_server(config.clone());
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("job-1")))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        
+        let listed = server
+            .list_artifacts(Request::new(ListArtifactsRequest::default()))
+            .await
+            .unwrap()
+            .into_inner();
+        let names: Vec<&str> = listed.artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["layout.json", "quality.json", "validation.json"]);
+        assert!(listed.artifacts.iter().all(|a| a.job_id == "job-1" && a.size_bytes > 0));
+        
+        let mut stream = server
+            .download_artifact(Request::new(DownloadArtifactRequest {
+                job_id: "job-1".to_string(),
+                name: "layout.json".to_string(),
+                ..DownloadArtifactRequest::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let mut data = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            assert_eq!(chunk.offset as usize, data.len());
+            data.extend(chunk.data);
+            if chunk.is_final {
+                break;
+            }
+        }
+        let positions: Vec<serde_json::Value> = serde_json::from_slice(&data).unwrap();
+        assert_eq!(positions.len(), response.positions.len());
+        
+        let missing = server
+            .download_artifact(Request::new(DownloadArtifactRequest {
+                job_id: "job-1".to_string(),
+                name: "missing.json".to_string(),
+                ..DownloadArtifactRequest::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+        let traversal = server
+            .download_artifact(Request::new(DownloadArtifactRequest {
+                job_id: "..".to_string(),
+                name: "config.toml".to_string(),
+                ..DownloadArtifactRequest::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(traversal.code(), tonic::Code::InvalidArgument);
+        
+        std::fs::remove_dir_all(&config.artifacts.dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_artifacts_and_metrics_are_partitioned_by_tenant() {
+        let mut config = Config::default();
+        config.artifacts.enabled = true;
+        config.artifacts.dir = std::env::temp_dir()
+            .join(format!("server-tenants-{}", Uuid::new_v4()))
+            .display()
+            .to_string();
+        let (server, _store) = test_server(config.clone());
+        
+        let mut request = layout_request("job-1");
+        request.metadata = Some(crate::generated::RequestMetadata {
+            tenant_id: "acme".to_string(),
+            ..crate::generated::RequestMetadata::default()
+        });
+        assert!(server.compute_layout(Request::new(request)).await.unwrap().into_inner().success);
+        
+        let list = |tenant: &str| ListArtifactsRequest {
+            tenant_id: tenant.to_string(),
+            ..ListArtifactsRequest::default()
+        };
+        let acme = server.list_artifacts(Request::new(list("acme"))).await.unwrap().into_inner();
+        assert_eq!(acme.artifacts.len(), 3);
+        let default = server.list_artifacts(Request::new(list(""))).await.unwrap().into_inner();
+        assert!(default.artifacts.is_empty());
+        let invalid = server.list_artifacts(Request::new(list("../acme"))).await.unwrap_err();
+        assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+        
+        let metrics = server
+            .get_metrics(Request::new(MetricsRequest {
+                tenant_id: "other".to_string(),
+                ..MetricsRequest::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let requests = metrics
+            .metrics
+            .iter()
+            .find(|m| m.name == "graph_layout_requests_total")
+            .expect("счётчик запросов");
+        assert!(requests.samples.is_empty());
+        
+        std::fs::remove_dir_all(&config.artifacts.dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_tenant_is_taken_from_token_when_configured() {
+        let mut config = Config::default();
+        config.server.tenant_tokens.insert(" secret-acme ".to_string(), " acme ".to_string());
+        let (server, _store) = test_server(config);
+        let list = |tenant: &str, token: Option<&str>| {
+            let mut request = Request::new(ListArtifactsRequest {
+                tenant_id: tenant.to_string(),
+                ..ListArtifactsRequest::default()
+            });
+            if let Some(token) = token {
+                request.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+            }
+            request
+        };
+        
+        let tenant = |tenant: &str, token: Option<&str>| {
+            let (metadata, _, req) = list(tenant, token).into_parts();
+            server.request_tenant(&metadata, &req.tenant_id)
+        };
+        assert_eq!(tenant("", Some("secret-acme")).unwrap(), "acme");
+        assert_eq!(tenant("acme", Some("secret-acme")).unwrap(), "acme");
+        assert_eq!(tenant("other", Some("secret-acme")).unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert_eq!(tenant("acme", None).unwrap_err().code(), tonic::Code::Unauthenticated);
+        assert_eq!(tenant("acme", Some("wrong")).unwrap_err().code(), tonic::Code::Unauthenticated);
+        
+        let denied = server.list_artifacts(list("acme", None)).await.unwrap_err();
+        assert_eq!(denied.code(), tonic::Code::Unauthenticated);
+    }
+    
+    #[tokio::test]
+    async fn test_compute_layout_maps_errors_to_response() {
+        let mut config = Config::default();
+        config.limits.max_edges = 2;
+        let (server, _store) = test_server(config);
+        
+        let response = server
+            .compute_layout(Request::new(layout_request("t2")))
+            .await
+            .unwrap()
+            .into_inner();
+        
+        assert!(!response.success);
+        assert!(response.error_message.contains("limits.max_edges"));
+        assert!(response.positions.is_empty());
+        assert!(response.metadata.is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_neighborhood_rejects_invalid_arguments() {
+        let (server, _store) = test_server(Config::default());
+        
+        let status = server
+            .compute_neighborhood_layout(Request::new(NeighborhoodRequest {
+                task_id: "t3".to_string(),
+                center_id: " ".to_string(),
+                radius: 1,
+                ..NeighborhoodRequest::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        
+        let response = server
+            .compute_neighborhood_layout(Request::new(NeighborhoodRequest {
+                task_id: "t4".to_string(),
+                center_id: "B".to_string(),
+                radius: 1,
+                ..NeighborhoodRequest::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        assert_eq!(response.positions.len(), 3);
+        
+        let status = server
+            .compute_neighborhood_layout(Request::new(NeighborhoodRequest {
+                task_id: "t4b".to_string(),
+                center_id: "missing".to_string(),
+                radius: 1,
+                ..NeighborhoodRequest::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+    
+    #[tokio::test]
+    async fn test_save_results_writes_to_store() {
+        let (server, store) = test_server(Config::default());
+        let result = server.run_layout("t5", DEFAULT_TENANT, vec![], None).await.unwrap();
+        
+        let report = server.save_results(&result.positions).await.unwrap();
+        
+        assert!(report.is_complete());
+        assert_eq!(report.saved_rows, 4);
+        let saved = store.saved_positions();
+        assert_eq!(saved.len(), 4);
+        assert_eq!(saved[0].article_id, "A");
+        assert_eq!(saved[0].layer, 0);
+        
+        store.set_available(false);
+        assert!(server.save_results(&result.positions).await.is_err());
+    }
+    
+    #[cfg(feature = "failure-injection")]
+    #[tokio::test]
+    async fn test_save_results_reports_partial_save_on_injected_faults() {
+        use crate::failure_injection::{Fault, FaultPlan, FaultPoint};
+        
+        let store = Arc::new(
+            InMemoryGraphStore::new(vec![edge("A", "B"), edge("B", "C"), edge("A", "D")])
+                .with_save_batch_size(2),
+        );
+        let server = GraphLayoutServer::with_store(Config::default(), Arc::clone(&store)).unwrap();
+        let result = server.run_layout("t6", DEFAULT_TENANT, vec![], None).await.unwrap();
+        
+        let plan = FaultPlan::install();
+        plan.arm_matching(FaultPoint::SaveBatch, "батча 2", Fault::Timeout, None);
+        let report = server.save_results(&result.positions).await.unwrap();
+        
+        assert!(!report.is_complete());
+        assert_eq!(report.saved_rows, 2);
+        assert_eq!(report.failed_rows(), 2);
+        assert_eq!(report.failed_batches[0].batch, 1);
+        assert_eq!(store.saved_positions().len(), 2);
+        assert_eq!(plan.hits(FaultPoint::SaveBatch), 1);
+    }
+    
+    #[cfg(feature = "failure-injection")]
+    #[tokio::test]
+    async fn test_run_layout_fails_on_injected_allocation_failure() {
+        use crate::failure_injection::{Fault, FaultPlan, FaultPoint};
+        
+        let (server, _store) = test_server(Config::default());
+        let plan = FaultPlan::install();
+        plan.arm(FaultPoint::Allocation, Fault::Error, Some(1));
+        
+        let error = server.run_layout("t7", DEFAULT_TENANT, vec![], None).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("нехватка памяти"));
+        
+        // Сбой однократный: следующий запуск проходит
+        assert_eq!(server.run_layout("t7", DEFAULT_TENANT, vec![], None).await.unwrap().positions.len(), 4);
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_unavailable_store() {
+        let (server, store) = test_server(Config::default());
+        store.set_available(false);
+        
+        let (status, message, _) = server.evaluate_health().await;
+        
+        assert_eq!(status, crate::generated::health_response::ServingStatus::NotServing);
+        assert!(message.contains(&Msg::StoreUnavailable.to_string()), "{}", message);
+    }
+    
+    #[tokio::test]
+    async fn test_heavy_runs_are_tracked_per_run_and_across_servers() {
+        let dir = std::env::temp_dir().join(format!("heavy-runs-{}", Uuid::new_v4()));
+        let mut config = Config::default();
+        config.server.heavy_run_dir = dir.to_string_lossy().into_owned();
+        let (server, _store) = test_server(config.clone());
+        let (other, _) = test_server(config);
+        
+        // Одинаковый id: завершение одного прогона не снимает отметку другого
+        let first = server.begin_heavy_run("auto-layout", "полная укладка");
+        let second = server.begin_heavy_run("auto-layout", "инкрементальная укладка");
+        assert_eq!(server.heavy_runs.active().len(), 2);
+        drop(first);
+        assert_eq!(server.heavy_runs.active(), vec!["auto-layout (инкрементальная укладка)".to_string()]);
+        
+        // Другой экземпляр видит прогон через каталог отметок
+        let (_, message, _) = other.evaluate_health().await;
+        assert!(message.contains("auto-layout (инкрементальная укладка)"), "{}", message);
+        drop(second);
+        assert!(other.heavy_runs.active().is_empty());
+        
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    
+    fn system_metrics(cpu_usage: f32, memory_usage_bytes: i64) -> SystemMetrics {
+        SystemMetrics {
+            cpu_usage,
+            memory_usage_bytes,
+            memory_available_bytes: 100,
+            active_tasks: 0,
+            uptime_seconds: 0,
+        }
+    }
+    
+    #[test]
+    fn test_health_problems() {
+        let idle = system_metrics(0.1, 10);
+        assert!(health_problems(true, &idle, &[], 0.9, 0.9).is_empty());
+        
+        let busy = system_metrics(0.95, 95);
+        let problems = health_problems(true, &busy, &[], 0.9, 0.9);
+        assert_eq!(problems.len(), 2);
+        
+        let heavy = vec!["auto-layout (полная укладка)".to_string()];
+        let problems = health_problems(false, &idle, &heavy, 0.9, 0.9);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[1].contains("auto-layout"));
+    }
+    
+    #[test]
+    fn test_layer_chunk_ranges() {
+        let layers = [0, 0, 1, 1, 1, 2, 3, 3, 3, 3, 3];
+        let positions: Vec<Neo4jVertexPosition> = layers
+            .iter()
+            .enumerate()
+            .map(|(i, &layer)| Neo4jVertexPosition {
+                article_id: format!("v{}", i),
+                layer,
+                level: 0,
+                x: 0.0,
+                y: 0.0,
+            })
+            .collect();
+        
+        // Слои 0 и 1 не помещаются вместе, слой 3 не помещается к слою 2 и делится
+        let ranges = layer_chunk_ranges(&positions, 3);
+        assert_eq!(ranges, vec![0..2, 2..5, 5..6, 6..9, 9..11]);
+        
+        let ranges = layer_chunk_ranges(&positions, 100);
+        assert_eq!(ranges, vec![0..11]);
+    }
 }
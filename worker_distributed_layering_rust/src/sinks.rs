@@ -0,0 +1,604 @@
+/*!
+# Приёмники результатов укладки
+
+Трейт `ResultSink` отделяет вычисление укладки от места хранения результата.
+Набор приёмников задаётся в конфигурации (`[output] sinks`), и один прогон
+укладки может сохраняться сразу в несколько мест:
+
+- `neo4j` — свойства `x/y/layer/level` статей (и агрегаты связей, если они
+  посчитаны), маршруты `path_x/path_y` связей (если построены) и узел `(:LayoutMeta)`
+- `file` — файл JSON, SVG, GML, CSV или JSONL (шаблон пути поддерживает `{run_id}`)
+- `stdout` — JSON в стандартный вывод (для пайплайнов и отладки); консольные
+  логи при этом выводятся в stderr, так что stdout остаётся разбираемым
+- `object_store` — S3-совместимое хранилище (фича `object-store`): полный JSON,
+  метаданные и тайлы под префиксом `{prefix}/{run_id}/`, плюс указатель
+  `{prefix}/latest.json` для статического фронтенда
+
+Приёмники работают независимо: ошибка одного не мешает остальным.
+
+Из командной строки приёмники заменяются через `--sink` (`SinkConfig::from_cli`),
+а `--dry-run` убирает `neo4j`, чтобы проверить результат до перезаписи
+координат в базе.
+*/
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::blocking_io::run_blocking;
+use crate::config::Config;
+use crate::export::{self, ExportFormat, StyleHints};
+use crate::neo4j::{EdgePath, LayoutMeta, Neo4jClient, VertexAggregates, VertexPosition};
+
+/// Результат одного прогона укладки, передаваемый приёмникам
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutRun<'a> {
+    /// Идентификатор прогона
+    pub run_id: &'a str,
+
+    /// Метаданные системы координат
+    pub meta: &'a LayoutMeta,
+
+    /// Позиции вершин
+    pub positions: &'a [VertexPosition],
+
+    /// Подсказки оформления слоёв (если включены в `[output.style]`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<&'a StyleHints>,
+
+    /// Степени и суммарные веса связей вершин (если включены в `[neo4j]`);
+    /// записываются только приёмником Neo4j
+    #[serde(skip)]
+    pub aggregates: Option<&'a HashMap<String, VertexAggregates>>,
+
+    /// Маршруты связей (если включены `[output] edge_paths`); приёмник Neo4j
+    /// пишет их в свойства связей
+    #[serde(skip_serializing_if = "<[EdgePath]>::is_empty")]
+    pub edge_paths: &'a [EdgePath],
+}
+
+/// Отчёт приёмника о записи
+#[derive(Debug, Clone)]
+pub struct SinkReport {
+    /// Имя приёмника
+    pub sink: String,
+
+    /// Количество записанных позиций
+    pub written: usize,
+
+    /// Куда записано (путь, URI)
+    pub location: String,
+
+    /// Запись неполная (часть позиций не сохранена)
+    pub partial: bool,
+}
+
+/// Приёмник результатов укладки
+#[async_trait]
+pub trait ResultSink: Send + Sync {
+    /// Имя приёмника для логов и отчётов
+    fn name(&self) -> &str;
+
+    /// Запись результата прогона
+    async fn write(&self, run: &LayoutRun<'_>) -> Result<SinkReport>;
+}
+
+/// Конфигурация одного приёмника
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Сохранение в Neo4j
+    Neo4j,
+
+    /// Файл
+    File {
+        /// Путь к файлу, `{run_id}` заменяется на идентификатор прогона
+        path: String,
+
+        /// Формат: json, svg, gml, csv или jsonl
+        #[serde(default)]
+        format: ExportFormat,
+    },
+
+    /// JSON в стандартный вывод
+    Stdout,
+
+    /// S3-совместимое хранилище
+    ObjectStore {
+        /// Бакет
+        bucket: String,
+
+        /// Префикс ключей
+        #[serde(default)]
+        prefix: String,
+
+        /// Endpoint для S3-совместимых хранилищ (MinIO и т.п.)
+        #[serde(default)]
+        endpoint: Option<String>,
+
+        /// Регион
+        #[serde(default)]
+        region: Option<String>,
+
+        /// Размер тайла в пикселях (0 — без тайлов)
+        #[serde(default = "default_tile_size")]
+        tile_size: f32,
+    },
+}
+
+impl SinkConfig {
+    /// Приёмник из аргумента `--sink`: `neo4j`, `stdout` или путь к файлу
+    ///
+    /// Формат файла определяется по расширению (`ExportFormat::from_extension`).
+    pub fn from_cli(spec: &str) -> Result<Self> {
+        match spec {
+            "neo4j" => Ok(Self::Neo4j),
+            "stdout" | "-" => Ok(Self::Stdout),
+            path => {
+                let format = ExportFormat::from_extension(path).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Неизвестный формат приёмника '{}': ожидается neo4j, stdout или файл .json/.svg/.gml/.csv/.jsonl",
+                        spec
+                    )
+                })?;
+                Ok(Self::File { path: path.to_string(), format })
+            }
+        }
+    }
+}
+
+fn default_tile_size() -> f32 {
+    4096.0
+}
+
+/// Сохранение в Neo4j с карантином несохранённых батчей
+pub struct Neo4jSink {
+    client: Arc<Neo4jClient>,
+    batch_size: usize,
+}
+
+impl Neo4jSink {
+    pub fn new(client: Arc<Neo4jClient>, batch_size: usize) -> Self {
+        Self { client, batch_size }
+    }
+}
+
+#[async_trait]
+impl ResultSink for Neo4jSink {
+    fn name(&self) -> &str {
+        "neo4j"
+    }
+
+    async fn write(&self, run: &LayoutRun<'_>) -> Result<SinkReport> {
+        let save_report = self
+            .client
+            .save_layout_results_with_aggregates(run.run_id, run.positions, run.aggregates, self.batch_size)
+            .await?;
+
+        if !save_report.is_complete() {
+            warn!("⚠️ Частичное сохранение: {}/{} позиций, требуют повтора {} позиций",
+                  save_report.saved_rows, save_report.total_rows, save_report.failed_rows());
+            for failed in &save_report.failed_batches {
+                warn!("   - батч {} (строки {}-{}): {}",
+                      failed.batch + 1, failed.start, failed.end.saturating_sub(1), failed.error);
+            }
+            if let Some(ref path) = save_report.quarantine_path {
+                warn!("📁 Позиции для ручного повтора сохранены в {}", path.display());
+            }
+        }
+
+        // Маршруты пишутся прямо в связи, без промежуточных свойств атомарного сохранения
+        let mut paths_complete = true;
+        if !run.edge_paths.is_empty() {
+            let paths_report = self.client.save_edge_paths(run.edge_paths, self.batch_size).await?;
+            if !paths_report.is_complete() {
+                warn!("⚠️ Частичное сохранение маршрутов связей: {}/{}",
+                      paths_report.saved_rows, paths_report.total_rows);
+                paths_complete = false;
+            }
+        }
+
+        self.client.save_layout_meta(run.meta).await?;
+
+        Ok(SinkReport {
+            sink: self.name().to_string(),
+            written: save_report.saved_rows,
+            location: "neo4j".to_string(),
+            partial: !save_report.is_complete() || !paths_complete,
+        })
+    }
+}
+
+/// Запись результата в файл (JSON по умолчанию)
+pub struct FileSink {
+    path_template: String,
+    format: ExportFormat,
+}
+
+impl FileSink {
+    pub fn new(path_template: impl Into<String>) -> Self {
+        Self {
+            path_template: path_template.into(),
+            format: ExportFormat::Json,
+        }
+    }
+
+    /// Формат файла
+    pub fn with_format(mut self, format: ExportFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Путь к файлу для прогона
+    pub fn path_for(&self, run_id: &str) -> std::path::PathBuf {
+        std::path::PathBuf::from(self.path_template.replace("{run_id}", run_id))
+    }
+}
+
+#[async_trait]
+impl ResultSink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn write(&self, run: &LayoutRun<'_>) -> Result<SinkReport> {
+        let path = self.path_for(run.run_id);
+
+        // Сериализация и запись большого экспорта не должны занимать поток runtime
+        run_blocking(|| -> Result<()> {
+            let document = match self.format {
+                ExportFormat::Json => serde_json::to_vec(run)?,
+                ExportFormat::Svg => export::render_svg(run).into_bytes(),
+                ExportFormat::Gml => export::render_gml(run).into_bytes(),
+                ExportFormat::Csv => export::render_csv(run).into_bytes(),
+                ExportFormat::Jsonl => export::render_jsonl(run)?.into_bytes(),
+            };
+
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, document)
+                .map_err(|e| anyhow::anyhow!("Не удалось записать {}: {}", path.display(), e))
+        })?;
+
+        Ok(SinkReport {
+            sink: self.name().to_string(),
+            written: run.positions.len(),
+            location: path.display().to_string(),
+            partial: false,
+        })
+    }
+}
+
+/// Вывод результата в stdout одним JSON-документом
+pub struct StdoutSink;
+
+#[async_trait]
+impl ResultSink for StdoutSink {
+    fn name(&self) -> &str {
+        "stdout"
+    }
+
+    async fn write(&self, run: &LayoutRun<'_>) -> Result<SinkReport> {
+        run_blocking(|| -> Result<()> {
+            let stdout = std::io::stdout();
+            let mut handle = stdout.lock();
+            serde_json::to_writer(&mut handle, run)?;
+            writeln!(handle)?;
+            handle.flush()?;
+            Ok(())
+        })?;
+
+        Ok(SinkReport {
+            sink: self.name().to_string(),
+            written: run.positions.len(),
+            location: "stdout".to_string(),
+            partial: false,
+        })
+    }
+}
+
+/// Ключ тайла: (столбец, строка) сетки со стороной `tile_size`
+pub fn tile_key(position: &VertexPosition, tile_size: f32) -> (i64, i64) {
+    (
+        (position.x / tile_size).floor() as i64,
+        (position.y / tile_size).floor() as i64,
+    )
+}
+
+/// Разбиение позиций на тайлы (упорядочено по ключу)
+pub fn split_into_tiles(
+    positions: &[VertexPosition],
+    tile_size: f32,
+) -> BTreeMap<(i64, i64), Vec<&VertexPosition>> {
+    let mut tiles: BTreeMap<(i64, i64), Vec<&VertexPosition>> = BTreeMap::new();
+    for position in positions {
+        tiles.entry(tile_key(position, tile_size)).or_default().push(position);
+    }
+    tiles
+}
+
+/// Выгрузка в S3-совместимое хранилище
+///
+/// Раскладка ключей:
+/// - `{prefix}/{run_id}/layout.json` — полный результат
+/// - `{prefix}/{run_id}/meta.json` — метаданные и список тайлов
+/// - `{prefix}/{run_id}/tiles/{col}_{row}.json` — позиции тайла
+/// - `{prefix}/latest.json` — ссылка на последний прогон (пишется последней)
+///
+/// Учётные данные берутся из окружения (`AWS_ACCESS_KEY_ID` и т.д.).
+#[cfg(feature = "object-store")]
+pub struct ObjectStoreSink {
+    store: Arc<dyn object_store::ObjectStore>,
+    bucket: String,
+    prefix: String,
+    tile_size: f32,
+}
+
+#[cfg(feature = "object-store")]
+impl ObjectStoreSink {
+    pub fn new(
+        bucket: &str,
+        prefix: &str,
+        endpoint: Option<&str>,
+        region: Option<&str>,
+        tile_size: f32,
+    ) -> Result<Self> {
+        let mut builder = object_store::aws::AmazonS3Builder::from_env().with_bucket_name(bucket);
+        if let Some(endpoint) = endpoint {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+        if let Some(region) = region {
+            builder = builder.with_region(region);
+        }
+
+        Ok(Self {
+            store: Arc::new(builder.build()?),
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            tile_size,
+        })
+    }
+
+    fn key(&self, relative: &str) -> object_store::path::Path {
+        if self.prefix.is_empty() {
+            object_store::path::Path::from(relative)
+        } else {
+            object_store::path::Path::from(format!("{}/{}", self.prefix, relative))
+        }
+    }
+
+    async fn put_json<T: Serialize + ?Sized>(&self, relative: &str, value: &T) -> Result<()> {
+        let document = run_blocking(|| serde_json::to_vec(value))?;
+        self.store
+            .put(&self.key(relative), document.into())
+            .await
+            .map_err(|e| anyhow::anyhow!("Не удалось выгрузить {}: {}", relative, e))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "object-store")]
+#[async_trait]
+impl ResultSink for ObjectStoreSink {
+    fn name(&self) -> &str {
+        "object_store"
+    }
+
+    async fn write(&self, run: &LayoutRun<'_>) -> Result<SinkReport> {
+        let run_prefix = run.run_id;
+        self.put_json(&format!("{}/layout.json", run_prefix), run).await?;
+
+        let mut tile_names = Vec::new();
+        if self.tile_size > 0.0 {
+            for ((col, row), tile) in split_into_tiles(run.positions, self.tile_size) {
+                let name = format!("{}_{}", col, row);
+                self.put_json(&format!("{}/tiles/{}.json", run_prefix, name), &tile).await?;
+                tile_names.push(name);
+            }
+        }
+
+        self.put_json(
+            &format!("{}/meta.json", run_prefix),
+            &serde_json::json!({
+                "meta": run.meta,
+                "tile_size": self.tile_size,
+                "tiles": tile_names,
+            }),
+        )
+        .await?;
+
+        // Указатель пишется последним: фронтенд не увидит недогруженный прогон
+        self.put_json("latest.json", &serde_json::json!({ "run_id": run.run_id })).await?;
+
+        Ok(SinkReport {
+            sink: self.name().to_string(),
+            written: run.positions.len(),
+            location: format!("s3://{}/{}", self.bucket, self.key(run_prefix)),
+            partial: false,
+        })
+    }
+}
+
+/// Создание приёмников по конфигурации
+pub fn build_sinks(config: &Config, neo4j_client: Arc<Neo4jClient>) -> Result<Vec<Box<dyn ResultSink>>> {
+    let mut sinks: Vec<Box<dyn ResultSink>> = Vec::with_capacity(config.output.sinks.len());
+
+    for sink in &config.output.sinks {
+        match sink {
+            SinkConfig::Neo4j => sinks.push(Box::new(Neo4jSink::new(
+                Arc::clone(&neo4j_client),
+                config.neo4j.save_batch_size,
+            ))),
+            SinkConfig::File { path, format } => {
+                sinks.push(Box::new(FileSink::new(path.clone()).with_format(*format)))
+            }
+            SinkConfig::Stdout => sinks.push(Box::new(StdoutSink)),
+            #[cfg(feature = "object-store")]
+            SinkConfig::ObjectStore { bucket, prefix, endpoint, region, tile_size } => {
+                sinks.push(Box::new(ObjectStoreSink::new(
+                    bucket,
+                    prefix,
+                    endpoint.as_deref(),
+                    region.as_deref(),
+                    *tile_size,
+                )?));
+            }
+            #[cfg(not(feature = "object-store"))]
+            SinkConfig::ObjectStore { bucket, .. } => {
+                return Err(anyhow::anyhow!(
+                    "Приёмник object_store (бакет '{}') требует сборки с фичей object-store",
+                    bucket
+                ));
+            }
+        }
+    }
+
+    if sinks.is_empty() {
+        return Err(anyhow::anyhow!("Не задано ни одного приёмника результатов ([output] sinks)"));
+    }
+
+    Ok(sinks)
+}
+
+/// Запись прогона во все приёмники
+///
+/// Приёмники вызываются по очереди и независимо; ошибка возвращается, только
+/// если не удалось записать ни в один.
+pub async fn write_all(sinks: &[Box<dyn ResultSink>], run: &LayoutRun<'_>) -> Result<Vec<SinkReport>> {
+    let mut reports = Vec::with_capacity(sinks.len());
+    let mut errors = Vec::new();
+
+    for sink in sinks {
+        match sink.write(run).await {
+            Ok(report) => {
+                info!("💾 {}: записано {} позиций → {}{}",
+                      report.sink, report.written, report.location,
+                      if report.partial { " (частично)" } else { "" });
+                reports.push(report);
+            }
+            Err(e) => {
+                warn!("❌ Приёмник {} не записал прогон {}: {}", sink.name(), run.run_id, e);
+                errors.push(format!("{}: {}", sink.name(), e));
+            }
+        }
+    }
+
+    if reports.is_empty() {
+        return Err(anyhow::anyhow!("Ни один приёмник не сохранил результат: {}", errors.join("; ")));
+    }
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(vertex_count: usize) -> LayoutMeta {
+        LayoutMeta {
+            run_id: "run-1".to_string(),
+            algorithm_version: "test".to_string(),
+            block_width: 160.0,
+            block_height: 80.0,
+            horizontal_gap: 80.0,
+            vertical_gap: 50.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            level_packing: "OnePerLevel".to_string(),
+            vertex_count,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            scale_profile: "web".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_writes_run() -> Result<()> {
+        let positions = vec![VertexPosition {
+            article_id: "a".to_string(),
+            layer: 0,
+            level: 0,
+            x: 0.0,
+            y: 0.0,
+        }];
+        let meta = meta(positions.len());
+        let run = LayoutRun { run_id: "run-1", meta: &meta, positions: &positions, style: None, aggregates: None, edge_paths: &[] };
+
+        let dir = std::env::temp_dir().join(format!("layout-sink-{}", uuid::Uuid::new_v4()));
+        let sink = FileSink::new(format!("{}/{{run_id}}.json", dir.display()));
+        let report = sink.write(&run).await?;
+
+        assert_eq!(report.written, 1);
+        let written: serde_json::Value = serde_json::from_slice(&std::fs::read(dir.join("run-1.json"))?)?;
+        assert_eq!(written["run_id"], "run-1");
+        assert_eq!(written["positions"][0]["article_id"], "a");
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_into_tiles() {
+        let positions: Vec<VertexPosition> = [(10.0, 10.0), (5000.0, 10.0), (4095.0, 4096.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y))| VertexPosition {
+                article_id: format!("v{}", i),
+                layer: 0,
+                level: 0,
+                x,
+                y,
+            })
+            .collect();
+
+        let tiles = split_into_tiles(&positions, 4096.0);
+        let keys: Vec<(i64, i64)> = tiles.keys().copied().collect();
+        assert_eq!(keys, vec![(0, 0), (0, 1), (1, 0)]);
+        assert_eq!(tiles[&(1, 0)][0].article_id, "v1");
+    }
+
+    #[test]
+    fn test_sink_config_parsing() {
+        #[derive(Deserialize)]
+        struct Output {
+            sinks: Vec<SinkConfig>,
+        }
+
+        let output: Output = toml::from_str(
+            r#"
+            sinks = [
+                { type = "neo4j" },
+                { type = "file", path = "out/{run_id}.json" },
+                { type = "file", path = "out/{run_id}.svg", format = "svg" },
+                { type = "object_store", bucket = "layouts", prefix = "maps" },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(output.sinks[0], SinkConfig::Neo4j));
+        assert!(matches!(
+            output.sinks[1],
+            SinkConfig::File { ref path, format: ExportFormat::Json } if path == "out/{run_id}.json"
+        ));
+        assert!(matches!(output.sinks[2], SinkConfig::File { format: ExportFormat::Svg, .. }));
+        assert!(matches!(output.sinks[3], SinkConfig::ObjectStore { ref bucket, .. } if bucket == "layouts"));
+    }
+
+    #[test]
+    fn test_sink_config_from_cli() {
+        assert!(matches!(SinkConfig::from_cli("neo4j").unwrap(), SinkConfig::Neo4j));
+        assert!(matches!(SinkConfig::from_cli("stdout").unwrap(), SinkConfig::Stdout));
+        assert!(matches!(
+            SinkConfig::from_cli("out/{run_id}.csv").unwrap(),
+            SinkConfig::File { ref path, format: ExportFormat::Csv } if path == "out/{run_id}.csv"
+        ));
+        assert!(SinkConfig::from_cli("out/layout.xlsx").is_err());
+    }
+}
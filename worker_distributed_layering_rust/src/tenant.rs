@@ -0,0 +1,60 @@
+/*!
+# Идентификатор арендатора
+
+Один воркер может обслуживать несколько арендаторов (баз, команд). Метрики
+укладки получают метку `tenant`, а артефакты задач хранятся в
+`{dir}/{tenant}/{job_id}/` с отдельной политикой хранения: гигантский граф
+одного арендатора не вытесняет артефакты другого и не искажает его дашборды.
+
+Запросы без арендатора относятся к `default`. ID арендатора становится
+меткой Prometheus и именем каталога, поэтому допускаются только латинские
+буквы, цифры, `-`, `_` и `.` (не больше `MAX_TENANT_ID_LEN` символов).
+
+Если в `[server] tenant_tokens` заданы токены, сервер не доверяет `tenant_id`
+из запроса: арендатор определяется по токену `authorization: Bearer <токен>`.
+*/
+
+use anyhow::Result;
+
+/// Арендатор запросов без явного ID
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Максимальная длина ID арендатора
+pub const MAX_TENANT_ID_LEN: usize = 64;
+
+/// Проверенный ID арендатора; пустой ID — `DEFAULT_TENANT`
+pub fn tenant_id(raw: &str) -> Result<String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(DEFAULT_TENANT.to_string());
+    }
+
+    let valid = raw.len() <= MAX_TENANT_ID_LEN
+        && raw != "."
+        && raw != ".."
+        && raw.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(raw.to_string())
+    } else {
+        Err(anyhow::anyhow!(
+            "Недопустимый ID арендатора '{}': допустимы латинские буквы, цифры, '-', '_', '.' (до {} символов)",
+            raw,
+            MAX_TENANT_ID_LEN
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenant_id() {
+        assert_eq!(tenant_id("").unwrap(), DEFAULT_TENANT);
+        assert_eq!(tenant_id(" acme-1 ").unwrap(), "acme-1");
+        assert!(tenant_id("..").is_err());
+        assert!(tenant_id("a/b").is_err());
+        assert!(tenant_id("арендатор").is_err());
+        assert!(tenant_id(&"x".repeat(MAX_TENANT_ID_LEN + 1)).is_err());
+    }
+}
@@ -184,11 +184,11 @@ pub fn test_layout_from_gml<P: AsRef<Path>>(path: P) -> Result<()> {
     println!();
 
     // Visualize layer assignment
-    visualize_layers(layer_map);
+    visualize_layers(&layer_map);
 
     // Validate
     println!("🔍 Валидация...");
-    let invalid_edges = global_state.validate_layers();
+    let invalid_edges = global_state.validate_layers()?;
     if invalid_edges == 0 {
         println!("   ✅ Все рёбра корректны\n");
     } else {
@@ -197,7 +197,7 @@ pub fn test_layout_from_gml<P: AsRef<Path>>(path: P) -> Result<()> {
 
     // ASCII visualization
     println!("=== ASCII ВИЗУАЛИЗАЦИЯ УКЛАДКИ ===\n");
-    visualize_ascii(layer_map, &edges);
+    visualize_ascii(&layer_map, &edges);
 
     Ok(())
 }
@@ -243,11 +243,11 @@ pub fn test_layout() -> Result<()> {
     println!();
 
     // Visualize layer assignment
-    visualize_layers(layer_map);
+    visualize_layers(&layer_map);
 
     // Validate
     println!("🔍 Валидация...");
-    let invalid_edges = global_state.validate_layers();
+    let invalid_edges = global_state.validate_layers()?;
     if invalid_edges == 0 {
         println!("   ✅ Все рёбра корректны\n");
     } else {
@@ -256,7 +256,7 @@ pub fn test_layout() -> Result<()> {
 
     // ASCII visualization
     println!("=== ASCII ВИЗУАЛИЗАЦИЯ УКЛАДКИ ===\n");
-    visualize_ascii(layer_map, &edges);
+    visualize_ascii(&layer_map, &edges);
 
     Ok(())
 }
@@ -5,13 +5,12 @@ use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use graph_layout_engine::{
-    generated::{LayoutOptions, MemoryStrategy},
-    neo4j::{GraphEdge, VertexPosition},
+    generated::{LayeringStrategy, LayoutOptions, MemoryStrategy},
+    neo4j::{EdgePath, GraphEdge, VertexPosition},
     HighPerformanceLayoutEngine, LayoutAlgorithm,
 };
 use proptest::prelude::*;
 use proptest::test_runner::TestRunner;
-use serde_json;
 use tokio::runtime::Runtime;
 
 fn default_options() -> LayoutOptions {
@@ -29,77 +28,23 @@ fn default_options() -> LayoutOptions {
         enable_simd: true,
         enable_gpu: false,
         memory_strategy: MemoryStrategy::MemoryAuto as i32,
+        ..LayoutOptions::default()
     }
 }
 
 fn sample_edges() -> Vec<GraphEdge> {
     vec![
-        GraphEdge {
-            source_id: "A".into(),
-            target_id: "B".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "A".into(),
-            target_id: "C".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "B".into(),
-            target_id: "D".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "C".into(),
-            target_id: "D".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "C".into(),
-            target_id: "E".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "D".into(),
-            target_id: "F".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "E".into(),
-            target_id: "F".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "A".into(),
-            target_id: "E".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "G".into(),
-            target_id: "F".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "G".into(),
-            target_id: "H".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
-        GraphEdge {
-            source_id: "B".into(),
-            target_id: "F".into(),
-            weight: 1.0,
-            edge_type: "ref".into(),
-        },
+        GraphEdge::new("A", "B", 1.0),
+        GraphEdge::new("A", "C", 1.0),
+        GraphEdge::new("B", "D", 1.0),
+        GraphEdge::new("C", "D", 1.0),
+        GraphEdge::new("C", "E", 1.0),
+        GraphEdge::new("D", "F", 1.0),
+        GraphEdge::new("E", "F", 1.0),
+        GraphEdge::new("A", "E", 1.0),
+        GraphEdge::new("G", "F", 1.0),
+        GraphEdge::new("G", "H", 1.0),
+        GraphEdge::new("B", "F", 1.0),
     ]
 }
 
@@ -110,22 +55,10 @@ fn position_map(positions: &[VertexPosition]) -> HashMap<String, VertexPosition>
         .collect()
 }
 
-fn edge_paths_from_metadata(parameters: &HashMap<String, String>) -> HashMap<(String, String), Vec<(f32, f32)>> {
-    let Some(raw_json) = parameters.get("edge_paths") else {
-        return HashMap::new();
-    };
-
-    let raw: HashMap<String, Vec<[f32; 2]>> = serde_json::from_str(raw_json)
-        .expect("edge_paths metadata should be valid JSON");
-
-    raw.into_iter()
-        .map(|(key, coords)| {
-            let mut parts = key.split("->");
-            let src = parts.next().unwrap_or_default().to_string();
-            let dst = parts.next().unwrap_or_default().to_string();
-            let points = coords.into_iter().map(|pair| (pair[0], pair[1])).collect();
-            ((src, dst), points)
-        })
+fn edge_path_map(paths: &[EdgePath]) -> HashMap<(String, String), Vec<(f32, f32)>> {
+    paths
+        .iter()
+        .map(|path| ((path.source_id.clone(), path.target_id.clone()), path.points.clone()))
         .collect()
 }
 
@@ -168,7 +101,7 @@ async fn layout_engine_places_vertices_for_small_dag() -> Result<()> {
     let result = engine.compute_layout(edges.clone(), &options).await?;
 
     let positions = position_map(&result.positions);
-    let edge_paths = edge_paths_from_metadata(&result.metadata.parameters);
+    let edge_paths = edge_path_map(&result.edge_paths);
 
     let mut unique_real = HashSet::new();
     for pos in &result.positions {
@@ -407,12 +340,7 @@ fn dag_edges_strategy() -> impl Strategy<Value = Vec<GraphEdge>> {
             for src in 0..node_count {
                 for dst in (src + 1)..node_count {
                     if mask[bit] {
-                        edges.push(GraphEdge {
-                            source_id: format!("v{}", src),
-                            target_id: format!("v{}", dst),
-                            weight: 1.0,
-                            edge_type: "test".into(),
-                        });
+                        edges.push(GraphEdge::new(format!("v{}", src), format!("v{}", dst), 1.0));
                     }
                     bit += 1;
                 }
@@ -420,12 +348,7 @@ fn dag_edges_strategy() -> impl Strategy<Value = Vec<GraphEdge>> {
 
             if edges.is_empty() {
                 for src in 0..(node_count - 1) {
-                    edges.push(GraphEdge {
-                        source_id: format!("v{}", src),
-                        target_id: format!("v{}", src + 1),
-                        weight: 1.0,
-                        edge_type: "fallback".into(),
-                    });
+                    edges.push(GraphEdge::new(format!("v{}", src), format!("v{}", src + 1), 1.0));
                 }
             }
 
@@ -452,7 +375,7 @@ fn random_dag_layout_preserves_partial_order() {
             let positions_vec = result.positions;
             let stats = result.statistics;
             let positions = position_map(&positions_vec);
-            let edge_paths = edge_paths_from_metadata(&result.metadata.parameters);
+            let edge_paths = edge_path_map(&result.edge_paths);
             prop_assert_eq!(
                 positions.len(),
                 positions_vec.len(),
@@ -571,7 +494,7 @@ async fn layout_engine_exports_gml_snapshot() -> Result<()> {
         "layout computation should return positions"
     );
 
-    let edge_paths = edge_paths_from_metadata(&result.metadata.parameters);
+    let edge_paths = edge_path_map(&result.edge_paths);
     let output_path = artifacts_path("test_graph.gml");
     write_gml(&result.positions, &edges, &edge_paths, options.block_width, options.block_height, &output_path)?;
 
@@ -584,3 +507,117 @@ async fn layout_engine_exports_gml_snapshot() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn coffman_graham_layering_limits_layer_width() -> Result<()> {
+    let options = LayoutOptions {
+        layering_strategy: LayeringStrategy::LayeringCoffmanGraham as i32,
+        max_layer_width: 2,
+        ..default_options()
+    };
+    let mut engine = HighPerformanceLayoutEngine::new(&options)?;
+    let edges = sample_edges();
+
+    let result = engine.compute_layout(edges.clone(), &options).await?;
+    let positions = position_map(&result.positions);
+
+    let mut layer_sizes: HashMap<i32, usize> = HashMap::new();
+    for pos in result.positions.iter().filter(|pos| !is_dummy_vertex(&pos.article_id)) {
+        *layer_sizes.entry(pos.layer).or_insert(0) += 1;
+    }
+    assert!(layer_sizes.values().all(|&size| size <= 2), "layer sizes: {:?}", layer_sizes);
+
+    for edge in &edges {
+        assert!(
+            positions[&edge.source_id].layer < positions[&edge.target_id].layer,
+            "edge {} -> {} must point to a higher layer",
+            edge.source_id,
+            edge.target_id
+        );
+    }
+    assert_eq!(result.metadata.parameters.get("layering").map(String::as_str), Some("CoffmanGraham"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn clustered_vertices_stay_adjacent_within_layer() -> Result<()> {
+    let options = default_options();
+    let mut engine = HighPerformanceLayoutEngine::new(&options)?;
+    let clustered_edge = |target: &str, cluster: &str| GraphEdge {
+        target_cluster: Some(cluster.into()),
+        ..GraphEdge::new("R", target, 1.0)
+    };
+    // Vertex IDs interleave the clusters, so ordering by ID alone would mix them
+    let edges = vec![
+        clustered_edge("x1", "alpha"),
+        clustered_edge("x2", "beta"),
+        clustered_edge("x3", "alpha"),
+        clustered_edge("x4", "beta"),
+    ];
+
+    let result = engine.compute_layout(edges, &options).await?;
+    let positions = position_map(&result.positions);
+
+    assert_eq!((positions["x1"].level - positions["x3"].level).abs(), 1);
+    assert_eq!((positions["x2"].level - positions["x4"].level).abs(), 1);
+
+    let bounds: Vec<serde_json::Value> = serde_json::from_str(
+        result.metadata.parameters.get("cluster_bounds").expect("cluster_bounds in metadata"),
+    )?;
+    assert_eq!(bounds.len(), 2);
+    assert_eq!(bounds[0]["cluster_id"], "alpha");
+    assert_eq!(bounds[0]["vertex_count"], 2);
+    assert_eq!(bounds[1]["cluster_id"], "beta");
+    Ok(())
+}
+
+#[tokio::test]
+async fn layout_result_exposes_occupancy_grid() -> Result<()> {
+    let options = default_options();
+    let mut engine = HighPerformanceLayoutEngine::new(&options)?;
+
+    let result = engine.compute_layout(sample_edges(), &options).await?;
+    let occupancy = &result.occupancy;
+
+    assert!(!occupancy.is_empty());
+    assert!(occupancy.len() <= result.positions.len());
+    for pos in &result.positions {
+        assert!(occupancy.is_occupied(pos.layer, pos.level), "{} is not in the grid", pos.article_id);
+        let free = occupancy.nearest_free_level(pos.layer, pos.level);
+        assert!(!occupancy.is_occupied(pos.layer, free));
+        assert!(occupancy
+            .free_slots_near(pos.layer, pos.level, 1)
+            .iter()
+            .all(|&(layer, level)| !occupancy.is_occupied(layer, level)));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn seed_neighborhood_limits_layout_to_subgraph() -> Result<()> {
+    let options = LayoutOptions {
+        seed_ids: vec!["H".into(), "unknown".into()],
+        neighborhood_depth: 2,
+        ..default_options()
+    };
+    let mut engine = HighPerformanceLayoutEngine::new(&options)?;
+
+    let result = engine.compute_layout(sample_edges(), &options).await?;
+    let mut placed: Vec<&str> = result
+        .positions
+        .iter()
+        .map(|pos| pos.article_id.as_str())
+        .filter(|id| !is_dummy_vertex(id))
+        .collect();
+    placed.sort_unstable();
+
+    // H -> G (1 связь) -> F (2 связи); соседи F дальше радиуса
+    assert_eq!(placed, vec!["F", "G", "H"]);
+    assert_eq!(result.metadata.parameters.get("neighborhood_depth").map(String::as_str), Some("2"));
+
+    let missing = LayoutOptions {
+        seed_ids: vec!["unknown".into()],
+        ..default_options()
+    };
+    assert!(engine.compute_layout(sample_edges(), &missing).await.is_err());
+    Ok(())
+}
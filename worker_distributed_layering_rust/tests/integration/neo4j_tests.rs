@@ -0,0 +1,239 @@
+//! Сквозной тест пути Neo4j: контейнер Neo4j → db_optimizer → auto-layout → сохранённые позиции.
+//!
+//! Требует Docker и запускается только с feature `integration-neo4j`:
+//!
+//! ```bash
+//! cargo test --features integration-neo4j --test neo4j_tests
+//! ```
+//!
+//! С feature `failure-injection` дополнительно проверяются повторные попытки
+//! и карантин при сбоях сохранения батчей.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Result;
+use graph_layout_engine::{
+    neo4j::{Neo4jClient, VertexPosition},
+    sinks::SinkConfig,
+    Config, DatabaseOptimizer,
+};
+use neo4rs::{query, BoltType};
+use testcontainers::{clients::Cli, core::WaitFor, GenericImage};
+
+const NEO4J_IMAGE: &str = "neo4j";
+const NEO4J_TAG: &str = "5.15";
+const NEO4J_PASSWORD: &str = "password";
+const BOLT_PORT: u16 = 7687;
+
+/// Небольшой граф цитирований из двух компонент: (источник, цель)
+const CITATIONS: &[(&str, &str)] = &[
+    ("A", "B"),
+    ("B", "C"),
+    ("A", "C"),
+    ("D", "C"),
+    ("E", "F"),
+];
+
+/// Ожидаемые слои: источники в слое 0, цель — на слой дальше самого дальнего источника
+fn expected_layers() -> HashMap<&'static str, i32> {
+    HashMap::from([("A", 0), ("B", 1), ("C", 2), ("D", 0), ("E", 0), ("F", 1)])
+}
+
+fn neo4j_image() -> GenericImage {
+    GenericImage::new(NEO4J_IMAGE, NEO4J_TAG)
+        .with_env_var("NEO4J_AUTH", format!("neo4j/{}", NEO4J_PASSWORD))
+        .with_exposed_port(BOLT_PORT)
+        .with_wait_for(WaitFor::message_on_stdout("Started."))
+}
+
+/// Конфигурация сервиса, направленная на контейнер
+fn test_config(bolt_port: u16, work_dir: &PathBuf) -> Config {
+    let mut config = Config::default();
+    config.neo4j.uri = format!("bolt://127.0.0.1:{}", bolt_port);
+    config.neo4j.user = "neo4j".to_string();
+    config.neo4j.password = NEO4J_PASSWORD.to_string();
+    config.neo4j.database = "neo4j".to_string();
+    config.neo4j.pool_size = 4;
+    config.neo4j.retry.base_delay_ms = 50;
+    config.neo4j.save_quarantine_path = work_dir
+        .join("quarantine.json")
+        .to_string_lossy()
+        .into_owned();
+    config.algorithms.deterministic = true;
+    config.output.sinks = vec![SinkConfig::Neo4j];
+    config
+}
+
+async fn seed_citation_graph(client: &Neo4jClient) -> Result<()> {
+    let graph = client.graph();
+    for (source, target) in CITATIONS {
+        graph
+            .run(
+                query(
+                    "MERGE (a:Article {uid: $source}) \
+                     MERGE (b:Article {uid: $target}) \
+                     MERGE (a)-[:BIBLIOGRAPHIC_LINK]->(b)",
+                )
+                .param("source", *source)
+                .param("target", *target),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+async fn index_names(client: &Neo4jClient) -> Result<HashSet<String>> {
+    let mut result = client.graph().execute(query("SHOW INDEXES YIELD name RETURN name")).await?;
+    let mut names = HashSet::new();
+    while let Some(row) = result.next().await? {
+        names.insert(row.get::<String>("name")?);
+    }
+    Ok(names)
+}
+
+/// Запуск бинарника в режиме auto-layout с конфигурацией из файла
+fn run_auto_layout(config: &Config, work_dir: &PathBuf) -> Result<()> {
+    let config_path = work_dir.join("config.toml");
+    std::fs::write(&config_path, toml::to_string(config)?)?;
+
+    let status = Command::new(env!("CARGO_BIN_EXE_graph-layout-server"))
+        .args(["--mode", "auto-layout", "--log-level", "warn", "--config"])
+        .arg(&config_path)
+        .status()?;
+    anyhow::ensure!(status.success(), "auto-layout завершился с ошибкой: {}", status);
+    Ok(())
+}
+
+#[tokio::test]
+async fn auto_layout_stores_positions_in_neo4j() -> Result<()> {
+    let docker = Cli::default();
+    let container = docker.run(neo4j_image());
+    let bolt_port = container.get_host_port_ipv4(BOLT_PORT);
+
+    let work_dir = std::env::temp_dir().join(format!("neo4j-it-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir)?;
+    let config = test_config(bolt_port, &work_dir);
+
+    let client = Neo4jClient::new(&config).await?;
+    seed_citation_graph(&client).await?;
+
+    // db_optimizer: индексы создаются и повторный запуск идемпотентен
+    let optimizer = DatabaseOptimizer::new(client.graph());
+    optimizer.prepare_database().await?;
+    optimizer.prepare_database().await?;
+    assert!(index_names(&client).await?.contains("article_uid_unique"));
+
+    run_auto_layout(&config, &work_dir)?;
+
+    let positions: HashMap<String, VertexPosition> = client
+        .load_layout_positions()
+        .await?
+        .into_iter()
+        .map(|p| (p.article_id.clone(), p))
+        .collect();
+
+    let expected = expected_layers();
+    assert_eq!(positions.len(), expected.len());
+    for (article, layer) in &expected {
+        let position = positions
+            .get(*article)
+            .unwrap_or_else(|| panic!("нет сохранённой позиции для {}", article));
+        assert_eq!(position.layer, *layer, "слой статьи {}", article);
+    }
+
+    // Каждая связь направлена слева направо
+    for (source, target) in CITATIONS {
+        assert!(positions[*source].layer < positions[*target].layer, "{} -> {}", source, target);
+    }
+
+    // Позиции внутри слоя не пересекаются, координаты согласованы с layer/level
+    let mut occupied = HashSet::new();
+    let column = config.algorithms.block_width + config.algorithms.horizontal_gap;
+    let row = config.algorithms.block_height + config.algorithms.vertical_gap;
+    for position in positions.values() {
+        assert!(occupied.insert((position.layer, position.level)), "занятая ячейка {:?}", position);
+        assert!((position.x - position.layer as f32 * column).abs() < 1e-3);
+        assert!((position.y - position.level as f32 * row).abs() < 1e-3);
+    }
+
+    // execute_query: параметры и разбор строк
+    #[derive(serde::Deserialize)]
+    struct Cited {
+        uid: String,
+        citations: i64,
+    }
+    let cited: Vec<Cited> = client
+        .execute_query_as(
+            "MATCH (a:Article)-[:BIBLIOGRAPHIC_LINK]->(b:Article {uid: $uid}) \
+             RETURN b.uid AS uid, count(a) AS citations",
+            Some(HashMap::from([("uid".to_string(), BoltType::from("C"))])),
+        )
+        .await?;
+    assert_eq!(cited.len(), 1);
+    assert_eq!((cited[0].uid.as_str(), cited[0].citations), ("C", 3));
+
+    let rows = client.execute_query("MATCH (n:Article) RETURN count(n) AS total", None).await?;
+    assert_eq!(rows[0]["total"], BoltType::from(expected.len() as i64));
+
+    // execute_write: запись выполняется один раз
+    let written = client
+        .execute_write("MATCH (n:Article {uid: 'C'}) SET n.checked = true RETURN n.uid AS uid", None)
+        .await?;
+    assert_eq!(written.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+    Ok(())
+}
+
+#[cfg(feature = "failure-injection")]
+#[tokio::test]
+async fn save_retries_and_quarantines_injected_batch_failures() -> Result<()> {
+    use graph_layout_engine::failure_injection::{Fault, FaultPlan, FaultPoint};
+
+    let docker = Cli::default();
+    let container = docker.run(neo4j_image());
+    let bolt_port = container.get_host_port_ipv4(BOLT_PORT);
+
+    let work_dir = std::env::temp_dir().join(format!("neo4j-it-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir)?;
+    let config = test_config(bolt_port, &work_dir);
+
+    let client = Neo4jClient::new(&config).await?;
+    seed_citation_graph(&client).await?;
+
+    let positions: Vec<VertexPosition> = expected_layers()
+        .into_iter()
+        .map(|(article, layer)| VertexPosition {
+            article_id: article.to_string(),
+            layer,
+            level: 0,
+            x: layer as f32 * 100.0,
+            y: 0.0,
+        })
+        .collect();
+
+    // Два таймаута подряд: батч сохраняется с третьей попытки
+    let plan = FaultPlan::install();
+    plan.arm(FaultPoint::SaveBatch, Fault::Timeout, Some(2));
+    let report = client.save_layout_results_with_batch_size(&positions, 2).await?;
+    assert!(report.is_complete());
+    assert_eq!(report.saved_rows, positions.len());
+    assert_eq!(plan.hits(FaultPoint::SaveBatch), 2);
+    drop(plan);
+
+    // Батч, не проходящий ни одной попытки, уходит в карантин, остальные сохраняются
+    let plan = FaultPlan::install();
+    plan.arm_matching(FaultPoint::SaveBatch, "батча укладки 2", Fault::Error, None);
+    let report = client.save_layout_results_with_batch_size(&positions, 2).await?;
+    assert_eq!(report.failed_batches.len(), 1);
+    assert_eq!(report.saved_rows, positions.len() - 2);
+    let quarantine_path = report.quarantine_path.clone().expect("файл карантина");
+    let quarantined = graph_layout_engine::neo4j::read_quarantine_file(&quarantine_path)?;
+    assert_eq!(quarantined.positions.len(), 2);
+    drop(plan);
+
+    let _ = std::fs::remove_dir_all(&work_dir);
+    Ok(())
+}